@@ -20,21 +20,25 @@ fn criterion_benchmark(c: &mut Criterion) {
     let factorial = load_contract("programs/benches/factorial_2M.cairo");
     let fibonacci = load_contract("programs/benches/fib_2M.cairo");
     let logistic_map = load_contract("programs/benches/logistic_map.cairo");
+    let sum = load_contract("programs/benches/sum_2M.cairo");
 
     let aot_factorial = aot_cache.compile_and_insert(Felt::ZERO, &factorial, OptLevel::None);
     let aot_fibonacci = aot_cache.compile_and_insert(Felt::ONE, &fibonacci, OptLevel::None);
     let aot_logistic_map =
         aot_cache.compile_and_insert(Felt::from(2), &logistic_map, OptLevel::None);
+    let aot_sum = aot_cache.compile_and_insert(Felt::from(3), &sum, OptLevel::None);
 
     let jit_factorial = jit_cache.compile_and_insert(Felt::ZERO, &factorial, OptLevel::None);
     let jit_fibonacci = jit_cache.compile_and_insert(Felt::ONE, &fibonacci, OptLevel::None);
     let jit_logistic_map =
         jit_cache.compile_and_insert(Felt::from(2), &logistic_map, OptLevel::None);
+    let jit_sum = jit_cache.compile_and_insert(Felt::from(3), &sum, OptLevel::None);
 
     let factorial_function_id = find_function_id(&factorial, "factorial_2M::factorial_2M::main");
     let fibonacci_function_id = find_function_id(&fibonacci, "fib_2M::fib_2M::main");
     let logistic_map_function_id =
         find_function_id(&logistic_map, "logistic_map::logistic_map::main");
+    let sum_function_id = find_function_id(&sum, "sum_2M::sum_2M::main");
 
     c.bench_function("Cached JIT factorial_2M", |b| {
         b.iter(|| jit_factorial.invoke_dynamic(factorial_function_id, &[], Some(u128::MAX)));
@@ -45,6 +49,9 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("Cached JIT logistic_map", |b| {
         b.iter(|| jit_logistic_map.invoke_dynamic(logistic_map_function_id, &[], Some(u128::MAX)));
     });
+    c.bench_function("Cached JIT sum_2M", |b| {
+        b.iter(|| jit_sum.invoke_dynamic(sum_function_id, &[], Some(u128::MAX)));
+    });
 
     c.bench_function("Cached AOT factorial_2M", |b| {
         b.iter(|| aot_factorial.invoke_dynamic(factorial_function_id, &[], Some(u128::MAX)));
@@ -55,6 +62,9 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("Cached AOT logistic_map", |b| {
         b.iter(|| aot_logistic_map.invoke_dynamic(logistic_map_function_id, &[], Some(u128::MAX)));
     });
+    c.bench_function("Cached AOT sum_2M", |b| {
+        b.iter(|| aot_sum.invoke_dynamic(sum_function_id, &[], Some(u128::MAX)));
+    });
 
     #[cfg(target_arch = "x86_64")]
     {
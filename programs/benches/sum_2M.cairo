@@ -0,0 +1,15 @@
+// Summation loop, which is compiled into a native loop instead of recursive calls.
+fn main() {
+    let mut i: felt252 = 0;
+    let mut sum: felt252 = 0;
+    loop {
+        if i == 2000000 {
+            break;
+        }
+
+        sum += i;
+        i += 1;
+    };
+
+    assert(sum == 1999999000000, 'invalid result');
+}
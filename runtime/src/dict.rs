@@ -0,0 +1,144 @@
+//! # Felt252 dictionaries
+//!
+//! The backing storage of `Felt252Dict<T>`: an open-addressing hash map (with linear probing) from
+//! felts to pointers to the values, which are allocated by the generated code.
+//!
+//! Keys are never removed, since Cairo dictionaries can only be written to. Missing keys are
+//! reported as null pointers, and the generated code falls back to the default value of `T`.
+//!
+//! The map also counts the accesses to it, which are needed to compute the gas refunded when the
+//! dictionary is squashed.
+
+use std::{collections::hash_map::RandomState, ffi::c_void, hash::BuildHasher, ptr::NonNull};
+
+/// Number of slots allocated for the first insertion.
+const INITIAL_CAPACITY: usize = 16;
+
+/// A hash map from felts (as little endian bytes) to pointers to their values.
+#[derive(Debug, Default)]
+pub struct FeltDict {
+    /// The slots of the map. Their number is always a power of two, or zero before the first
+    /// insertion.
+    slots: Vec<Option<([u8; 32], NonNull<c_void>)>>,
+    len: usize,
+    /// Number of accesses to the map, used to compute the gas refund when squashing.
+    accesses: u64,
+    hasher: RandomState,
+}
+
+impl FeltDict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of keys in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of accesses recorded through [FeltDict::record_access].
+    pub fn accesses(&self) -> u64 {
+        self.accesses
+    }
+
+    pub fn record_access(&mut self) {
+        self.accesses += 1;
+    }
+
+    pub fn get(&self, key: &[u8; 32]) -> Option<NonNull<c_void>> {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        self.slots[self.find_slot(key)].map(|(_, value)| value)
+    }
+
+    /// Insert a value, returning the previous one (if any).
+    pub fn insert(&mut self, key: [u8; 32], value: NonNull<c_void>) -> Option<NonNull<c_void>> {
+        // Keep the load factor under 3/4, so that the probe sequences stay short.
+        if 4 * (self.len + 1) > 3 * self.slots.len() {
+            self.grow();
+        }
+
+        let idx = self.find_slot(&key);
+        match &mut self.slots[idx] {
+            Some((_, old_value)) => Some(std::mem::replace(old_value, value)),
+            slot @ None => {
+                *slot = Some((key, value));
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Iterate over the keys and their values, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8; 32], NonNull<c_void>)> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.as_ref().map(|(key, value)| (key, *value)))
+    }
+
+    /// Return the index of the slot which holds the key, or of the empty slot where it would be
+    /// inserted. There must be at least one slot.
+    fn find_slot(&self, key: &[u8; 32]) -> usize {
+        let mask = self.slots.len() - 1;
+
+        let mut idx = self.hasher.hash_one(key) as usize & mask;
+        loop {
+            match &self.slots[idx] {
+                Some((slot_key, _)) if slot_key != key => idx = (idx + 1) & mask,
+                _ => return idx,
+            }
+        }
+    }
+
+    fn grow(&mut self) {
+        let capacity = (2 * self.slots.len()).max(INITIAL_CAPACITY);
+        let slots = std::mem::replace(&mut self.slots, vec![None; capacity]);
+
+        for (key, value) in slots.into_iter().flatten() {
+            let idx = self.find_slot(&key);
+            self.slots[idx] = Some((key, value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(value: u64) -> [u8; 32] {
+        let mut key = [0; 32];
+        key[..8].copy_from_slice(&value.to_le_bytes());
+        key
+    }
+
+    fn value(value: usize) -> NonNull<c_void> {
+        NonNull::new(value as *mut c_void).unwrap()
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let mut dict = FeltDict::new();
+        assert_eq!(dict.get(&key(1)), None);
+
+        for i in 0..1000 {
+            assert_eq!(dict.insert(key(i), value(i as usize + 1)), None);
+        }
+        assert_eq!(dict.len(), 1000);
+
+        for i in 0..1000 {
+            assert_eq!(dict.get(&key(i)), Some(value(i as usize + 1)));
+        }
+        assert_eq!(dict.get(&key(1000)), None);
+
+        assert_eq!(dict.insert(key(7), value(1)), Some(value(8)));
+        assert_eq!(dict.get(&key(7)), Some(value(1)));
+        assert_eq!(dict.len(), 1000);
+        assert_eq!(dict.iter().count(), 1000);
+    }
+}
@@ -1,5 +1,19 @@
+//! # Cairo Native runtime
+//!
+//! Helpers called by the generated code for the libfuncs which are impractical to implement in
+//! MLIR (ex. hashes, elliptic curve operations or dictionaries). They are exported as C symbols
+//! (`cairo_native__*`), which the compiler declares through the `RuntimeBindingsMeta` metadata.
+//! The counters of the profiler are kept here too (check out [__profiler__statement]).
+//!
+//! The JIT engine resolves them from the host process, while AOT-compiled programs are linked
+//! against the static library built from this crate.
+
 #![allow(non_snake_case)]
 
+pub use self::{
+    dict::FeltDict,
+    profiler::{__profiler__statement, take_profiler_counters, ProfilerBuffer},
+};
 use cairo_lang_sierra_gas::core_libfunc_cost::{
     DICT_SQUASH_REPEATED_ACCESS_COST, DICT_SQUASH_UNIQUE_KEY_COST,
 };
@@ -7,7 +21,18 @@ use lazy_static::lazy_static;
 use starknet_crypto::FieldElement;
 use starknet_curve::AffinePoint;
 use starknet_types_core::felt::Felt;
-use std::{collections::HashMap, fs::File, io::Write, os::fd::FromRawFd, ptr::NonNull, slice};
+use std::{
+    fs::File,
+    io::Write,
+    os::fd::FromRawFd,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::NonNull,
+    slice,
+    sync::{Arc, RwLock},
+};
+
+mod dict;
+mod profiler;
 
 lazy_static! {
     pub static ref HALF_PRIME: FieldElement = FieldElement::from_dec_str(
@@ -18,10 +43,38 @@ lazy_static! {
         (DICT_SQUASH_UNIQUE_KEY_COST.cost() - DICT_SQUASH_REPEATED_ACCESS_COST.cost()) as u64;
 }
 
+/// A host callback which receives the values printed by the `print` libfunc.
+pub type DebugPrintCallback = Arc<dyn Fn(&[Felt]) + Send + Sync>;
+
+static DEBUG_PRINT_CALLBACK: RwLock<Option<DebugPrintCallback>> = RwLock::new(None);
+
+/// Register a callback which will receive the values printed by the `print` libfunc (ex. Cairo's
+/// `println!`) instead of writing them into the target file descriptor. Passing `None` restores the
+/// default behavior. Returns the previous callback.
+///
+/// The callback is global: it's shared by every program which uses this copy of the runtime, from
+/// every thread. In practice that's the programs run by the JIT executor, which resolve the runtime
+/// from the host process. AOT-compiled libraries are linked against their own copy of the runtime,
+/// so they never see it and keep writing into the file descriptor.
+///
+/// The callback is called without holding any lock, so it may replace itself. If it panics, the
+/// panic is caught and the `print` libfunc fails instead.
+pub fn set_debug_print_callback(
+    callback: Option<DebugPrintCallback>,
+) -> Option<DebugPrintCallback> {
+    let mut current = DEBUG_PRINT_CALLBACK
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *current, callback)
+}
+
 /// Based on `cairo-lang-runner`'s implementation.
 ///
 /// Source: <https://github.com/starkware-libs/cairo/blob/main/crates/cairo-lang-runner/src/casm_run/mod.rs#L1946-L1948>
 ///
+/// When a [DebugPrintCallback] is registered, the values are forwarded to it instead. A panic in the
+/// callback can't unwind into the generated code, so it's reported as a failure.
+///
 /// # Safety
 ///
 /// This function is intended to be called from MLIR, deals with pointers, and is therefore
@@ -32,6 +85,22 @@ pub unsafe extern "C" fn cairo_native__libfunc__debug__print(
     data: *const [u8; 32],
     len: u32,
 ) -> i32 {
+    // Clone the callback so that the lock isn't held while it runs.
+    let callback = DEBUG_PRINT_CALLBACK
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(callback) = callback {
+        let values = (0..len as usize)
+            .map(|i| Felt::from_bytes_le(&*data.add(i)))
+            .collect::<Vec<_>>();
+
+        return match catch_unwind(AssertUnwindSafe(|| callback(&values))) {
+            Ok(()) => 0,
+            Err(_) => 1,
+        };
+    }
+
     let mut target = File::from_raw_fd(target_fd);
 
     for i in 0..len as usize {
@@ -149,7 +218,7 @@ pub unsafe extern "C" fn cairo_native__libfunc__hades_permutation(
     op2.copy_from_slice(&state[2].to_bytes_be());
 }
 
-/// Allocates a new dictionary. Internally a rust hashmap: `HashMap<[u8; 32], NonNull<()>`
+/// Allocates a new dictionary. Internally a [FeltDict].
 ///
 /// # Safety
 ///
@@ -157,24 +226,31 @@ pub unsafe extern "C" fn cairo_native__libfunc__hades_permutation(
 /// definitely unsafe to use manually.
 #[no_mangle]
 pub unsafe extern "C" fn cairo_native__alloc_dict() -> *mut std::ffi::c_void {
-    Box::into_raw(Box::<(HashMap<[u8; 32], NonNull<std::ffi::c_void>>, u64)>::default()) as _
+    Box::into_raw(Box::<FeltDict>::default()) as _
 }
 
 /// Frees the dictionary.
 ///
+/// The entries were allocated by the generated code, so they're freed with the `free` hook of the
+/// executor when there's one, or with `libc::free` otherwise.
+///
 /// # Safety
 ///
 /// This function is intended to be called from MLIR, deals with pointers, and is therefore
 /// definitely unsafe to use manually.
 #[no_mangle]
 pub unsafe extern "C" fn cairo_native__dict_free(
-    ptr: *mut (HashMap<[u8; 32], NonNull<std::ffi::c_void>>, u64),
+    ptr: *mut FeltDict,
+    free_hook: Option<unsafe extern "C" fn(*mut std::ffi::c_void)>,
 ) {
-    let mut map = Box::from_raw(ptr);
+    let dict = Box::from_raw(ptr);
 
     // Free the entries manually.
-    for (_, entry) in map.as_mut().0.drain() {
-        libc::free(entry.as_ptr().cast());
+    for (_, entry) in dict.iter() {
+        match free_hook {
+            Some(free_hook) => free_hook(entry.as_ptr()),
+            None => libc::free(entry.as_ptr().cast()),
+        }
     }
 }
 
@@ -187,17 +263,15 @@ pub unsafe extern "C" fn cairo_native__dict_free(
 /// definitely unsafe to use manually.
 #[no_mangle]
 pub unsafe extern "C" fn cairo_native__dict_get(
-    ptr: *mut (HashMap<[u8; 32], NonNull<std::ffi::c_void>>, u64),
+    ptr: *mut FeltDict,
     key: &[u8; 32],
 ) -> *mut std::ffi::c_void {
-    let dict: &mut (HashMap<[u8; 32], NonNull<std::ffi::c_void>>, u64) = &mut *ptr;
-    let map = &dict.0;
-    dict.1 += 1;
+    let dict = &mut *ptr;
+    dict.record_access();
 
-    if let Some(v) = map.get(key) {
-        v.as_ptr()
-    } else {
-        std::ptr::null_mut()
+    match dict.get(key) {
+        Some(v) => v.as_ptr(),
+        None => std::ptr::null_mut(),
     }
 }
 
@@ -209,17 +283,15 @@ pub unsafe extern "C" fn cairo_native__dict_get(
 /// definitely unsafe to use manually.
 #[no_mangle]
 pub unsafe extern "C" fn cairo_native__dict_insert(
-    ptr: *mut (HashMap<[u8; 32], NonNull<std::ffi::c_void>>, u64),
+    ptr: *mut FeltDict,
     key: &[u8; 32],
     value: NonNull<std::ffi::c_void>,
 ) -> *mut std::ffi::c_void {
     let dict = &mut *ptr;
-    let old_ptr = dict.0.insert(*key, value);
 
-    if let Some(v) = old_ptr {
-        v.as_ptr()
-    } else {
-        std::ptr::null_mut()
+    match dict.insert(*key, value) {
+        Some(v) => v.as_ptr(),
+        None => std::ptr::null_mut(),
     }
 }
 
@@ -230,11 +302,9 @@ pub unsafe extern "C" fn cairo_native__dict_insert(
 /// This function is intended to be called from MLIR, deals with pointers, and is therefore
 /// definitely unsafe to use manually.
 #[no_mangle]
-pub unsafe extern "C" fn cairo_native__dict_gas_refund(
-    ptr: *const (HashMap<[u8; 32], NonNull<std::ffi::c_void>>, u64),
-) -> u64 {
+pub unsafe extern "C" fn cairo_native__dict_gas_refund(ptr: *const FeltDict) -> u64 {
     let dict = &*ptr;
-    (dict.1 - dict.0.len() as u64) * *DICT_GAS_REFUND_PER_ACCESS
+    (dict.accesses() - dict.len() as u64) * *DICT_GAS_REFUND_PER_ACCESS
 }
 
 /// Compute `ec_point_from_x_nz(x)` and store it.
@@ -480,3 +550,42 @@ pub unsafe extern "C" fn cairo_native__libfunc__ec__ec_state_try_finalize_nz(
         true
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pedersen() {
+        let lhs = FieldElement::from(1u8).to_bytes_be();
+        let rhs = FieldElement::from(2u8).to_bytes_be();
+        let mut dst = [0; 32];
+
+        unsafe { cairo_native__libfunc__pedersen(dst.as_mut_ptr(), lhs.as_ptr(), rhs.as_ptr()) };
+        assert_eq!(
+            dst,
+            starknet_crypto::pedersen_hash(&FieldElement::from(1u8), &FieldElement::from(2u8))
+                .to_bytes_be()
+        );
+    }
+
+    #[test]
+    fn dict_gas_refund() {
+        unsafe {
+            let dict = cairo_native__alloc_dict().cast::<FeltDict>();
+
+            let value = NonNull::new(libc::malloc(8)).unwrap();
+            assert!(cairo_native__dict_get(dict, &[1; 32]).is_null());
+            assert!(cairo_native__dict_insert(dict, &[1; 32], value).is_null());
+            assert_eq!(cairo_native__dict_get(dict, &[1; 32]), value.as_ptr());
+
+            // Two accesses to a single key.
+            assert_eq!(
+                cairo_native__dict_gas_refund(dict),
+                *DICT_GAS_REFUND_PER_ACCESS
+            );
+
+            cairo_native__dict_free(dict, None);
+        }
+    }
+}
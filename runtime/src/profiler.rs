@@ -0,0 +1,135 @@
+//! # Profiler counters
+//!
+//! Storage for the statement counters of the programs compiled with the profiler enabled. The
+//! generated code calls [__profiler__statement] when entering a function to get the
+//! [ProfilerBuffer] of the current thread, then updates it on its own before executing every
+//! statement: it increments the counter of the statement, and adds the cycles elapsed since the
+//! previous statement started to the previous statement.
+//!
+//! The host collects the counters with [take_profiler_counters].
+
+use std::{
+    cell::{RefCell, UnsafeCell},
+    ptr::{addr_of_mut, null_mut},
+};
+
+/// The counters of a thread, as seen by the generated code.
+#[repr(C)]
+pub struct ProfilerBuffer {
+    /// The cycles of the statement being executed, or `scratch` if there isn't any.
+    last: *mut u64,
+    /// The value of the cycle counter when the statement being executed started.
+    timestamp: u64,
+    /// The `(count, cycles)` pairs of the statements, indexed by statement.
+    entries: *mut u64,
+    scratch: u64,
+}
+
+struct ThreadCounters {
+    buffer: UnsafeCell<ProfilerBuffer>,
+    /// Every entries array ever handed out, the last one being the current. Once replaced by a
+    /// bigger one, they're kept alive because the generated code may still be updating them.
+    arrays: RefCell<Vec<Box<[u64]>>>,
+}
+
+thread_local! {
+    static COUNTERS: ThreadCounters = const {
+        ThreadCounters {
+            buffer: UnsafeCell::new(ProfilerBuffer {
+                last: null_mut(),
+                timestamp: 0,
+                entries: null_mut(),
+                scratch: 0,
+            }),
+            arrays: RefCell::new(Vec::new()),
+        }
+    };
+}
+
+/// Return the buffer of the current thread, with room for the counters of `statements_len`
+/// statements. The buffer stays valid until the thread exits.
+///
+/// # Safety
+///
+/// The entries of the returned buffer may be replaced by later calls, so the generated code must
+/// reload them before every update.
+#[no_mangle]
+pub unsafe extern "C" fn __profiler__statement(statements_len: u64) -> *mut ProfilerBuffer {
+    COUNTERS.with(|counters| {
+        let buffer = counters.buffer.get();
+        if (*buffer).last.is_null() {
+            (*buffer).last = addr_of_mut!((*buffer).scratch);
+        }
+
+        let mut arrays = counters.arrays.borrow_mut();
+        let len = 2 * statements_len as usize;
+        if arrays.last().map_or(true, |x| x.len() < len) {
+            arrays.push(vec![0; len].into_boxed_slice());
+            (*buffer).entries = arrays.last_mut().unwrap().as_mut_ptr();
+        }
+
+        buffer
+    })
+}
+
+/// Return the `(count, cycles)` pairs of the statements executed on the current thread since the
+/// last call, indexed by statement, and reset them.
+///
+/// The statement being executed, if any, is measured until `now`, which must be a value of the
+/// same cycle counter the generated code reads.
+pub fn take_profiler_counters(now: u64) -> Vec<(u64, u64)> {
+    COUNTERS.with(|counters| {
+        let buffer = counters.buffer.get();
+        unsafe {
+            if !(*buffer).last.is_null() {
+                *(*buffer).last =
+                    (*(*buffer).last).wrapping_add(now.wrapping_sub((*buffer).timestamp));
+                (*buffer).last = addr_of_mut!((*buffer).scratch);
+            }
+            (*buffer).timestamp = now;
+        }
+
+        let mut arrays = counters.arrays.borrow_mut();
+        let mut totals = vec![(0, 0); arrays.last().map_or(0, |x| x.len() / 2)];
+        for array in arrays.iter_mut() {
+            for (total, entry) in totals.iter_mut().zip(array.chunks_exact_mut(2)) {
+                total.0 += entry[0];
+                total.1 += entry[1];
+                entry.fill(0);
+            }
+        }
+
+        totals
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Do what the generated code does before executing a statement.
+    unsafe fn enter_statement(buffer: *mut ProfilerBuffer, statement_idx: usize, now: u64) {
+        *(*buffer).last += now - (*buffer).timestamp;
+        *(*buffer).entries.add(2 * statement_idx) += 1;
+        (*buffer).last = (*buffer).entries.add(2 * statement_idx + 1);
+        (*buffer).timestamp = now;
+    }
+
+    #[test]
+    fn count_statements() {
+        take_profiler_counters(0);
+
+        unsafe {
+            let buffer = __profiler__statement(2);
+            enter_statement(buffer, 1, 10);
+            enter_statement(buffer, 0, 15);
+            // A bigger program replaces the entries while the first one is still running.
+            let buffer = __profiler__statement(3);
+            enter_statement(buffer, 2, 20);
+            enter_statement(buffer, 1, 22);
+        }
+
+        assert_eq!(take_profiler_counters(30), [(1, 5), (2, 13), (1, 2)]);
+        assert_eq!(take_profiler_counters(40), [(0, 0); 3]);
+    }
+}
@@ -7,11 +7,32 @@ _invoke_trampoline:
     // x1 <- args_ptr: *const u64
     // x2 <- args_len: usize
     // x3 <- ret_ptr: &mut [u64; 4]
+    // x4 <- stack_ptr: *mut u8 (optional, 16-byte aligned)
+    // x5 <- jump_buffer: *mut [u64; 19] (optional)
 
     stp     x29,    x30,    [sp, #-16]!
     stp     x19,    x3,     [sp, #-16]!     // Necessary to restore the stack after the call.
     mov     x19,    sp
 
+    cbz     x5,     5f                      // Check if a jump buffer was provided.
+    stp     x19,    x20,    [x5]            // Save the callee-saved registers and the address
+    stp     x21,    x22,    [x5, #16]       //   after the call, so that `_cairo_native_longjmp`
+    stp     x23,    x24,    [x5, #32]       //   can resume there.
+    stp     x25,    x26,    [x5, #48]
+    stp     x27,    x28,    [x5, #64]
+    stp     d8,     d9,     [x5, #80]
+    stp     d10,    d11,    [x5, #96]
+    stp     d12,    d13,    [x5, #112]
+    stp     d14,    d15,    [x5, #128]
+    adr     x6,     4f
+    str     x6,     [x5, #144]
+
+  5:
+
+    cbz     x4,     0f                      // Check if a dedicated stack was provided.
+    mov     sp,     x4                      // Switch to the dedicated stack (already aligned).
+
+  0:
     mov     x9,     x0                      // We'll need x0.
     add     x10,    x1,     x2,     lsl 3   // Move the pointer to the end (past last element).
 
@@ -59,6 +80,7 @@ _invoke_trampoline:
     // Call the function.
     blr     x9
 
+  4:
     // Restore the stack and context registers.
     mov     sp,     x19
     ldp     x19,    x4,     [sp],   16
@@ -69,3 +91,21 @@ _invoke_trampoline:
     stp     x2,     x3,     [x4]
 
     ret
+
+
+.global _cairo_native_longjmp
+_cairo_native_longjmp:
+    // x0 <- jump_buffer: *const [u64; 19]
+
+    ldp     x19,    x20,    [x0]            // Restore the callee-saved registers.
+    ldp     x21,    x22,    [x0, #16]
+    ldp     x23,    x24,    [x0, #32]
+    ldp     x25,    x26,    [x0, #48]
+    ldp     x27,    x28,    [x0, #64]
+    ldp     d8,     d9,     [x0, #80]
+    ldp     d10,    d11,    [x0, #96]
+    ldp     d12,    d13,    [x0, #112]
+    ldp     d14,    d15,    [x0, #128]
+    ldr     x1,     [x0, #144]
+
+    br      x1                              // Resume after the call in `_invoke_trampoline`.
@@ -7,12 +7,34 @@ _invoke_trampoline:
     # rsi <- args_ptr: *const u64
     # rdx <- args_len: usize
     # rcx <- ret_ptr: &mut [u64; 2]
+    # r8  <- stack_ptr: *mut u8 (optional, 16-byte aligned)
+    # r9  <- jump_buffer: *mut [u64; 7] (optional)
 
     push    rbp                     # Push rbp (callee-saved).
     push    rcx                     # Push rcx (ret_ptr).
     mov     rbp,    rsp             # Store the current stack pointer.
     sub     rsp,    8               # Align the stack.
 
+    test    r9,     r9              # Check if a jump buffer was provided.
+    jz      0f                      # If not, skip saving the registers.
+    mov     [r9],           rbx     # Save the callee-saved registers and the address after the
+    mov     [r9 + 0x08],    rbp     #   call, so that `_cairo_native_longjmp` can resume there.
+    mov     [r9 + 0x10],    r12
+    mov     [r9 + 0x18],    r13
+    mov     [r9 + 0x20],    r14
+    mov     [r9 + 0x28],    r15
+    lea     rax,    [rip + 4f]
+    mov     [r9 + 0x30],    rax
+
+  0:
+
+    test    r8,     r8              # Check if a dedicated stack was provided.
+    jz      0f                      # If not, keep using the current one.
+    lea     rsp,    [r8 - 16]       # Switch to the dedicated stack, keeping the alignment and
+                                    #   leaving room for the stack arguments' padding below.
+
+  0:
+
     mov     r10,    rdi             # We'll need rdi.
     mov     r11,    rsi             # We'll need rsi.
 
@@ -61,6 +83,7 @@ _invoke_trampoline:
     # Call the function.
     call    r10
 
+  4:
     mov     rsp,    rbp
     pop     rcx
     pop     rbp
@@ -70,3 +93,18 @@ _invoke_trampoline:
     mov     [rcx + 8],  rdx
 
     ret
+
+
+.global _cairo_native_longjmp
+_cairo_native_longjmp:
+    # rdi <- jump_buffer: *const [u64; 7]
+
+    mov     rbx,    [rdi]           # Restore the callee-saved registers.
+    mov     rbp,    [rdi + 0x08]
+    mov     r12,    [rdi + 0x10]
+    mov     r13,    [rdi + 0x18]
+    mov     r14,    [rdi + 0x20]
+    mov     r15,    [rdi + 0x28]
+    mov     rsp,    rbp             # Drop the frames of the interrupted call.
+
+    jmp     qword ptr [rdi + 0x30]  # Resume after the call in `_invoke_trampoline`.
@@ -0,0 +1,215 @@
+//! # Codegen backends
+//!
+//! A backend takes a Sierra program and turns it into something that can execute it. The default
+//! (and currently only) backend is [`MlirBackend`], which lowers the program to MLIR using the
+//! libfunc builders and then relies on LLVM for the code generation, either JIT or AOT.
+//!
+//! Other backends (ex. a Cranelift-based one for fast-startup JIT scenarios) can be developed by
+//! implementing [`Backend`], without changing the interface of the existing libfunc builders. Their
+//! compiled programs are run through [`ProgramExecutor`], so that code generic over the backend can
+//! invoke them.
+
+use crate::{
+    context::NativeContext,
+    error::Error,
+    execution_result::{ContractExecutionResult, ExecutionResult},
+    executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
+    starknet::StarknetSyscallHandler,
+    values::JitValue,
+};
+use cairo_lang_sierra::{ids::FunctionId, program::Program};
+use starknet_types_core::felt::Felt;
+
+/// A code generation backend.
+///
+/// The lifetime is the one of the borrow of the backend while compiling, which allows executors to
+/// keep references to state owned by the backend (ex. the MLIR context for the JIT executor).
+pub trait Backend<'a> {
+    /// The compiled program, ready to be executed.
+    type Executor: ProgramExecutor;
+
+    /// A short, human-readable name for the backend.
+    fn name(&self) -> &'static str;
+
+    /// Compile a Sierra program.
+    fn compile(&'a self, program: &Program) -> Result<Self::Executor, Error>;
+}
+
+/// A program compiled by a [`Backend`].
+///
+/// The methods behave like the ones of [`NativeExecutor`] with the same name.
+pub trait ProgramExecutor {
+    /// Return the gas required to call the given function, or `None` if the program was compiled
+    /// without gas costs.
+    fn initial_required_gas(&self, function_id: &FunctionId) -> Option<u128>;
+
+    /// Invoke the given function by its function id, with the given arguments and gas.
+    fn invoke_dynamic(
+        &self,
+        function_id: &FunctionId,
+        args: &[JitValue],
+        gas: Option<u128>,
+    ) -> Result<ExecutionResult, Error>;
+
+    /// Invoke the given function by its function id, with the given arguments and gas, for
+    /// programs which require a syscall handler.
+    fn invoke_dynamic_with_syscall_handler(
+        &self,
+        function_id: &FunctionId,
+        args: &[JitValue],
+        gas: Option<u128>,
+        syscall_handler: impl StarknetSyscallHandler,
+    ) -> Result<ExecutionResult, Error>;
+
+    /// Invoke the given function by its function id, with the given arguments and gas, for
+    /// Starknet contracts.
+    fn invoke_contract_dynamic(
+        &self,
+        function_id: &FunctionId,
+        args: &[Felt],
+        gas: Option<u128>,
+        syscall_handler: impl StarknetSyscallHandler,
+    ) -> Result<ContractExecutionResult, Error>;
+}
+
+impl ProgramExecutor for NativeExecutor<'_> {
+    fn initial_required_gas(&self, function_id: &FunctionId) -> Option<u128> {
+        NativeExecutor::initial_required_gas(self, function_id)
+    }
+
+    fn invoke_dynamic(
+        &self,
+        function_id: &FunctionId,
+        args: &[JitValue],
+        gas: Option<u128>,
+    ) -> Result<ExecutionResult, Error> {
+        NativeExecutor::invoke_dynamic(self, function_id, args, gas)
+    }
+
+    fn invoke_dynamic_with_syscall_handler(
+        &self,
+        function_id: &FunctionId,
+        args: &[JitValue],
+        gas: Option<u128>,
+        syscall_handler: impl StarknetSyscallHandler,
+    ) -> Result<ExecutionResult, Error> {
+        NativeExecutor::invoke_dynamic_with_syscall_handler(
+            self,
+            function_id,
+            args,
+            gas,
+            syscall_handler,
+        )
+    }
+
+    fn invoke_contract_dynamic(
+        &self,
+        function_id: &FunctionId,
+        args: &[Felt],
+        gas: Option<u128>,
+        syscall_handler: impl StarknetSyscallHandler,
+    ) -> Result<ContractExecutionResult, Error> {
+        NativeExecutor::invoke_contract_dynamic(self, function_id, args, gas, syscall_handler)
+    }
+}
+
+/// How the [`MlirBackend`] runs the generated code.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MlirExecutionMode {
+    /// Use LLVM's JIT execution engine.
+    #[default]
+    Jit,
+    /// Compile into a shared library and load it.
+    Aot,
+}
+
+/// The MLIR and LLVM backend.
+///
+/// The optimization level and the target are taken from the context's
+/// [CompilerConfig](crate::context::CompilerConfig).
+#[derive(Debug, Default)]
+pub struct MlirBackend {
+    context: NativeContext,
+    mode: MlirExecutionMode,
+}
+
+impl MlirBackend {
+    pub fn new(context: NativeContext, mode: MlirExecutionMode) -> Self {
+        Self { context, mode }
+    }
+
+    pub fn context(&self) -> &NativeContext {
+        &self.context
+    }
+}
+
+impl<'a> Backend<'a> for MlirBackend {
+    type Executor = NativeExecutor<'a>;
+
+    fn name(&self) -> &'static str {
+        "mlir"
+    }
+
+    fn compile(&'a self, program: &Program) -> Result<Self::Executor, Error> {
+        let module = self.context.compile(program, None)?;
+        let opt_level = self.context.config().opt_level();
+
+        Ok(match self.mode {
+            MlirExecutionMode::Jit => {
+                JitNativeExecutor::from_native_module(module, opt_level).into()
+            }
+            MlirExecutionMode::Aot => AotNativeExecutor::from_native_module_with_target(
+                module,
+                opt_level,
+                self.context.config().target(),
+            )
+            .into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::CompilerConfig, values::JitValue, OptLevel};
+    use cairo_lang_sierra::ProgramParser;
+    use test_case::test_case;
+
+    /// Compile and run a program through any backend.
+    fn run_add<'a>(backend: &'a impl Backend<'a>, program: &Program) -> JitValue {
+        let executor = backend.compile(program).unwrap();
+        executor
+            .invoke_dynamic(
+                &program.funcs[0].id,
+                &[JitValue::Felt252(2.into()), JitValue::Felt252(3.into())],
+                None,
+            )
+            .unwrap()
+            .return_value
+    }
+
+    #[test_case(MlirExecutionMode::Jit)]
+    #[test_case(MlirExecutionMode::Aot)]
+    fn mlir_backend(mode: MlirExecutionMode) {
+        let program = ProgramParser::new()
+            .parse(
+                "type felt252 = felt252;
+
+                libfunc felt252_add = felt252_add;
+                libfunc store_temp_felt252 = store_temp<felt252>;
+
+                felt252_add([0], [1]) -> ([2]);
+                store_temp_felt252([2]) -> ([2]);
+                return([2]);
+
+                run_test@0([0]: felt252, [1]: felt252) -> (felt252);",
+            )
+            .unwrap();
+
+        let context =
+            NativeContext::new().with_config(CompilerConfig::new().with_opt_level(OptLevel::None));
+        let backend = MlirBackend::new(context, mode);
+        assert_eq!(run_add(&backend, &program), JitValue::Felt252(5.into()));
+    }
+}
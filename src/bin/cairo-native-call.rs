@@ -0,0 +1,171 @@
+//! Calls an external function of a contract class, converting its arguments and results using the
+//! class' ABI.
+//!
+//! The arguments are written like Cairo literals (ex. `0x1234`, `-5`, `[1, 2]`, `(1, true)`,
+//! `Some(3)` or `"text"`). The storage of the contract can be kept in a JSON file between calls,
+//! which makes it convenient for testing contracts by hand.
+
+mod utils;
+
+use anyhow::Context;
+use cairo_lang_starknet_classes::contract_class::ContractClass;
+use cairo_native::{
+    context::NativeContext,
+    execution_result::decode_panic_message,
+    executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
+    starknet_stub::StubSyscallHandler,
+};
+use clap::Parser;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::BufReader,
+    path::PathBuf,
+};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+use utils::{
+    abi::{format_felt, parse_felt, selector, Abi},
+    RunMode,
+};
+
+/// Calls an external function of a contract class.
+/// Exits with 1 if the call fails, otherwise 0.
+#[derive(Parser, Debug)]
+#[clap(version, verbatim_doc_comment)]
+struct Args {
+    /// The Sierra contract class (the JSON output of `starknet-compile`).
+    class: PathBuf,
+    /// The name of the external function to call.
+    function: String,
+    /// The arguments of the function, written like Cairo literals.
+    args: Vec<String>,
+    /// JSON file with the storage of the contract, as an object which maps addresses to values. It
+    /// is created if it doesn't exist, and updated after every successful call.
+    #[arg(long)]
+    state: Option<PathBuf>,
+    /// The amount of provided gas.
+    #[arg(long, default_value_t = u128::MAX)]
+    available_gas: u128,
+    /// Run with JIT or AOT (compiled).
+    #[arg(long, value_enum, default_value_t = RunMode::Jit)]
+    run_mode: RunMode,
+    /// Optimization level, Valid: 0, 1, 2, 3. Values higher than 3 are considered as 3.
+    #[arg(short = 'O', long, default_value_t = 0)]
+    opt_level: u8,
+}
+
+fn main() -> anyhow::Result<()> {
+    // Configure logging and error handling.
+    tracing::subscriber::set_global_default(
+        FmtSubscriber::builder()
+            .with_env_filter(EnvFilter::from_default_env())
+            .finish(),
+    )?;
+
+    let args = Args::parse();
+
+    let contract: ContractClass = serde_json::from_reader(BufReader::new(
+        File::open(&args.class)
+            .with_context(|| format!("Failed to open {}.", args.class.display()))?,
+    ))
+    .with_context(|| "Invalid contract class.")?;
+
+    let abi = Abi::new(
+        contract
+            .abi
+            .as_ref()
+            .context("The contract class has no ABI.")?,
+    );
+    let function = abi
+        .function(&args.function)
+        .with_context(|| format!("Function `{}` not found in the ABI.", args.function))?;
+    anyhow::ensure!(
+        args.args.len() == function.inputs.len(),
+        "`{}` takes {} arguments ({}), but {} were given.",
+        function.name,
+        function.inputs.len(),
+        function
+            .inputs
+            .iter()
+            .map(|x| format!("{}: {}", x.name, x.ty))
+            .collect::<Vec<_>>()
+            .join(", "),
+        args.args.len(),
+    );
+
+    let mut calldata = Vec::new();
+    for (input, arg) in function.inputs.iter().zip(&args.args) {
+        calldata.extend(
+            abi.encode(&input.ty, arg)
+                .with_context(|| format!("Invalid argument `{}`.", input.name))?,
+        );
+    }
+
+    let mut syscall_handler = StubSyscallHandler::default();
+    if let Some(path) = args.state.as_ref().filter(|x| x.exists()) {
+        let state: HashMap<String, String> =
+            serde_json::from_reader(BufReader::new(File::open(path)?))
+                .with_context(|| format!("Invalid state file {}.", path.display()))?;
+        for (address, value) in state {
+            syscall_handler
+                .storage
+                .insert((0, parse_felt(&address)?), parse_felt(&value)?);
+        }
+    }
+
+    let native_context = NativeContext::new();
+    let (native_module, entry_points) = native_context.compile_contract(&contract)?;
+    let function_id = entry_points
+        .find_external(&selector(&function.name))
+        .with_context(|| format!("`{}` is not an external function.", function.name))?
+        .clone();
+    let native_executor: NativeExecutor = match args.run_mode {
+        RunMode::Aot => {
+            AotNativeExecutor::from_native_module(native_module, args.opt_level.into()).into()
+        }
+        RunMode::Jit => {
+            JitNativeExecutor::from_native_module(native_module, args.opt_level.into()).into()
+        }
+    };
+
+    let result = native_executor.invoke_contract_dynamic(
+        &function_id,
+        &calldata,
+        Some(args.available_gas),
+        &mut syscall_handler,
+    )?;
+
+    for event in &syscall_handler.events {
+        match abi.decode_event(&event.keys, &event.data) {
+            Some(event) => println!("Event: {event}"),
+            None => println!("Event: keys {:?}, data {:?}", event.keys, event.data),
+        }
+    }
+
+    if result.failure_flag {
+        println!(
+            "Call failed with {}.",
+            decode_panic_message(&result.return_values)
+        );
+        println!("Remaining gas: {}", result.remaining_gas);
+        std::process::exit(1);
+    }
+
+    let mut return_values = result.return_values.into_iter();
+    for output in &function.outputs {
+        println!("Returned {}", abi.decode(&output.ty, &mut return_values)?);
+    }
+    println!("Remaining gas: {}", result.remaining_gas);
+
+    if let Some(path) = &args.state {
+        let state = syscall_handler
+            .storage
+            .iter()
+            .filter(|((address_domain, _), _)| *address_domain == 0)
+            .map(|((_, address), value)| (format_felt(address), format_felt(value)))
+            .collect::<BTreeMap<_, _>>();
+        serde_json::to_writer_pretty(File::create(path)?, &state)?;
+    }
+
+    Ok(())
+}
@@ -1,11 +1,13 @@
 use anyhow::Context;
 use cairo_lang_compiler::project::check_compiler_path;
 use cairo_native::{
-    context::NativeContext, module_to_object, object_to_shared_lib,
-    utils::cairo_to_sierra_with_debug_info,
+    context::{CompilerConfig, NativeContext},
+    module_to_object_with_target, object_to_shared_lib_with_target,
+    utils::{cairo_to_sierra_with_debug_info, load_sierra_program},
+    RelocationModel, TargetConfig, NATIVE_CPU,
 };
 use clap::{Parser, ValueEnum};
-use std::path::PathBuf;
+use std::{ffi::OsStr, path::PathBuf};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -14,12 +16,31 @@ enum RunMode {
     Jit,
 }
 
-/// Compiles a Cairo project outputting the generated MLIR and the shared library.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum RelocModel {
+    DynamicNoPic,
+    Pic,
+    Static,
+}
+
+impl From<RelocModel> for RelocationModel {
+    fn from(value: RelocModel) -> Self {
+        match value {
+            RelocModel::DynamicNoPic => RelocationModel::DynamicNoPic,
+            RelocModel::Pic => RelocationModel::Pic,
+            RelocModel::Static => RelocationModel::Static,
+        }
+    }
+}
+
+/// Compiles a Cairo project (or a compiled Sierra program) outputting the generated MLIR and the shared
+/// library.
 /// Exits with 1 if the compilation or run fails, otherwise 0.
 #[derive(Parser, Debug)]
 #[clap(version, verbatim_doc_comment)]
 struct Args {
-    /// The Cairo project path to compile and run its tests.
+    /// The Cairo project path to compile, or a compiled Sierra program (a `.sierra` text program,
+    /// or a `.json` program or contract class).
     path: PathBuf,
     /// Whether path is a single file.
     #[arg(short, long)]
@@ -37,6 +58,21 @@ struct Args {
     output_mlir: Option<PathBuf>,
     /// If a path is passed, a dynamic library will be compiled and saved at that path.
     output_library: Option<PathBuf>,
+    /// The target triple to compile for (ex. `aarch64-unknown-linux-gnu`). Defaults to the host's.
+    /// Dynamic libraries of other platforms are linked by the target's linker, which can be
+    /// overridden with `CAIRO_NATIVE_LINKER`.
+    #[arg(long)]
+    target_triple: Option<String>,
+    /// The CPU the dynamic library is compiled for (ex. `skylake` or `apple-m1`). Defaults to the
+    /// host's CPU.
+    #[arg(long, default_value = NATIVE_CPU)]
+    target_cpu: String,
+    /// Features to enable or disable on top of the CPU's, like `+avx2,-avx512f`.
+    #[arg(long, default_value = "")]
+    target_features: String,
+    /// The relocation model of the dynamic library.
+    #[arg(long, value_enum, default_value_t = RelocModel::DynamicNoPic)]
+    relocation_model: RelocModel,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -49,16 +85,33 @@ fn main() -> anyhow::Result<()> {
 
     let args = Args::parse();
 
-    // Check if args.path is a file or a directory.
-    check_compiler_path(args.single_file, &args.path)?;
+    let target = TargetConfig {
+        triple: args.target_triple.clone(),
+        cpu: args.target_cpu.clone(),
+        features: args.target_features.clone(),
+        relocation_model: args.relocation_model.into(),
+    };
+    let native_context =
+        NativeContext::new().with_config(CompilerConfig::new().with_target(target.clone()));
+    let (sierra_program, debug_locations) = if matches!(
+        args.path.extension().and_then(OsStr::to_str),
+        Some("sierra" | "json")
+    ) {
+        // Sierra programs produced by other tools don't come with the Cairo sources, so
+        // there are no debug locations.
+        (load_sierra_program(&args.path)?, None)
+    } else {
+        // Check if args.path is a file or a directory.
+        check_compiler_path(args.single_file, &args.path)?;
 
-    let native_context = NativeContext::new();
-    let (sierra_program, debug_locations) =
-        cairo_to_sierra_with_debug_info(native_context.context(), &args.path)?;
+        let (program, debug_locations) =
+            cairo_to_sierra_with_debug_info(native_context.context(), &args.path)?;
+        (program, Some(debug_locations))
+    };
 
     // Compile the sierra program into a MLIR module.
     let native_module = native_context
-        .compile(&sierra_program, Some(debug_locations))
+        .compile(&sierra_program, debug_locations)
         .unwrap();
 
     let output_mlir = args
@@ -72,9 +125,10 @@ fn main() -> anyhow::Result<()> {
     .context("Failed to write output.")?;
 
     if let Some(output_library) = &args.output_library {
-        let object_data = module_to_object(native_module.module(), args.opt_level.into())
-            .context("Failed to convert module to object.")?;
-        object_to_shared_lib(&object_data, output_library)
+        let object_data =
+            module_to_object_with_target(native_module.module(), args.opt_level.into(), &target)
+                .context("Failed to convert module to object.")?;
+        object_to_shared_lib_with_target(&object_data, output_library, &target)
             .context("Failed to write shared library.")?;
     }
 
@@ -0,0 +1,124 @@
+mod utils;
+
+use anyhow::{bail, Context};
+use cairo_lang_compiler::{
+    compile_prepared_db, db::RootDatabase, project::setup_project, CompilerConfig,
+};
+use cairo_lang_sierra::program::Program;
+use cairo_native::{context, utils::load_sierra_program};
+use clap::Parser;
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+use utils::diff::{compile_module, ModuleDiff};
+
+/// Compare the MLIR generated for two programs, or for a program against a module previously
+/// dumped by another version of the compiler (using `cairo-native-dump`).
+#[derive(Parser, Debug)]
+#[clap(version, verbatim_doc_comment)]
+struct Args {
+    /// The old side of the diff: a `.cairo`, `.sierra`, `.json` (program or contract class) or
+    /// `.mlir` file.
+    lhs: PathBuf,
+    /// The new side of the diff: a `.cairo`, `.sierra`, `.json` (program or contract class) or
+    /// `.mlir` file.
+    rhs: PathBuf,
+
+    /// Number of unchanged lines to show around every change.
+    #[arg(short = 'U', long, default_value_t = 3)]
+    context: usize,
+    /// Only print which operations changed.
+    #[arg(long)]
+    stat: bool,
+    /// Exit with an error code if the modules differ.
+    #[arg(long)]
+    check: bool,
+
+    /// Optimization level used to compile the old side.
+    #[arg(long, default_value_t = 0)]
+    lhs_opt: u8,
+    /// Optimization level used to compile the new side.
+    #[arg(long, default_value_t = 0)]
+    rhs_opt: u8,
+    /// Compile the old side with overflow checks.
+    #[arg(long)]
+    lhs_overflow_checks: bool,
+    /// Compile the new side with overflow checks.
+    #[arg(long)]
+    rhs_overflow_checks: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    // Configure logging and error handling.
+    tracing::subscriber::set_global_default(
+        FmtSubscriber::builder()
+            .with_env_filter(EnvFilter::from_default_env())
+            .finish(),
+    )?;
+
+    // Every side has its own configuration, so that the same program can be compared against
+    // itself (ex. to see what an optimization level changes).
+    let lhs = load_module(
+        &args.lhs,
+        context::CompilerConfig::new()
+            .with_opt_level(args.lhs_opt.into())
+            .with_overflow_checks(args.lhs_overflow_checks),
+    )?;
+    let rhs = load_module(
+        &args.rhs,
+        context::CompilerConfig::new()
+            .with_opt_level(args.rhs_opt.into())
+            .with_overflow_checks(args.rhs_overflow_checks),
+    )?;
+
+    let diff = ModuleDiff::new(&lhs, &rhs);
+    if args.stat {
+        print!("{}", diff.summary());
+    } else {
+        print!("{}", diff.render(args.context));
+    }
+
+    if args.check && !diff.is_empty() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Load a printed MLIR module, compiling the input with the current compiler and the given
+/// configuration if needed.
+fn load_module(path: &Path, config: context::CompilerConfig) -> anyhow::Result<String> {
+    let program = match path.extension().and_then(OsStr::to_str) {
+        Some("mlir") => {
+            return fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))
+        }
+        Some("cairo") => load_cairo(path)?,
+        Some("sierra" | "json") => load_sierra_program(path)?,
+        _ => bail!(
+            "{}: expected a `cairo`, `sierra`, `json` or `mlir` extension",
+            path.display()
+        ),
+    };
+
+    compile_module(&program, config)
+}
+
+fn load_cairo(path: &Path) -> anyhow::Result<Program> {
+    let mut db = RootDatabase::builder().detect_corelib().build()?;
+    let main_crate_ids = setup_project(&mut db, path)?;
+
+    compile_prepared_db(
+        &mut db,
+        main_crate_ids,
+        CompilerConfig {
+            replace_ids: true,
+            ..Default::default()
+        },
+    )
+}
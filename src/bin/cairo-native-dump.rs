@@ -4,7 +4,7 @@ use cairo_lang_compiler::{
 };
 use cairo_lang_defs::plugin::NamedPlugin;
 use cairo_lang_semantic::plugin::PluginSuite;
-use cairo_lang_sierra::{program::Program, ProgramParser};
+use cairo_lang_sierra::program::Program;
 use cairo_lang_starknet::{
     compile::compile_contract_in_prepared_db, inline_macros::selector::SelectorMacro,
     plugin::StarkNetPlugin,
@@ -12,6 +12,7 @@ use cairo_lang_starknet::{
 use cairo_native::{
     context::NativeContext,
     debug_info::{DebugInfo, DebugLocations},
+    utils::load_sierra_program,
 };
 use clap::Parser;
 use melior::{ir::operation::OperationPrintingFlags, Context};
@@ -130,16 +131,8 @@ fn load_program<'c>(
 
             (program, debug_locations)
         }
-        Some("sierra") => {
-            let program_src = fs::read_to_string(path)?;
-
-            let program_parser = ProgramParser::new();
-            let program = program_parser
-                .parse(&program_src)
-                .map_err(|e| e.map_token(|t| t.to_string()))?;
-
-            (program, None)
-        }
+        // Compiled programs (or contract classes) don't include debug info.
+        Some("sierra" | "json") => (load_sierra_program(path)?, None),
         _ => unreachable!(),
     })
 }
@@ -165,10 +158,10 @@ enum CompilerOutput {
 
 fn parse_input(input: &str) -> Result<PathBuf, String> {
     Ok(match Path::new(input).extension().and_then(OsStr::to_str) {
-        Some("cairo" | "sierra") => input.into(),
+        Some("cairo" | "sierra" | "json") => input.into(),
         _ => {
             return Err(
-                "Input path expected to have either `cairo` or `sierra` as its extension."
+                "Input path expected to have either `cairo`, `sierra` or `json` (for compiled programs or contract classes) as its extension."
                     .to_string(),
             )
         }
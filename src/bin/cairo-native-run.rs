@@ -7,22 +7,23 @@ use cairo_lang_compiler::{
     project::{check_compiler_path, setup_project},
 };
 use cairo_lang_diagnostics::ToOption;
-use cairo_lang_runner::short_string::as_cairo_short_string;
+use cairo_lang_runner::{casm_run::format_next_item, SierraCasmRunner};
 use cairo_lang_sierra_generator::{
     db::SierraGenGroup,
     replace_ids::{DebugReplacer, SierraIdReplacer},
 };
 use cairo_lang_starknet::contract::get_contracts_info;
 use cairo_native::{
-    context::NativeContext,
+    context::{CompilerConfig, NativeContext},
     debug_info::{DebugInfo, DebugLocations},
+    entry_points::entry_points,
     executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
-    metadata::gas::{GasMetadata, MetadataComputationConfig},
+    testing::{result_to_runresult, run_vm, RunComparison},
 };
 use clap::{Parser, ValueEnum};
 use std::path::{Path, PathBuf};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
-use utils::{find_function, result_to_runresult};
+use utils::find_function;
 
 #[derive(Clone, Debug, ValueEnum)]
 enum RunMode {
@@ -52,6 +53,13 @@ struct Args {
     /// Optimization level, Valid: 0, 1, 2, 3. Values higher than 3 are considered as 3.
     #[arg(short = 'O', long, default_value_t = 0)]
     opt_level: u8,
+    /// Also run the program with the Cairo VM and compare the outputs, the remaining gas and the
+    /// builtins used. Exits with 1 if they don't match.
+    #[arg(long)]
+    compare_vm: bool,
+    /// List the program's functions with their parameter and return types, then exit.
+    #[arg(long)]
+    list: bool,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -86,14 +94,15 @@ fn main() -> anyhow::Result<()> {
         .program
         .clone();
     let replacer = DebugReplacer { db };
-    if args.available_gas.is_none() && sierra_program.requires_gas_counter() {
+    if !args.list && args.available_gas.is_none() && sierra_program.requires_gas_counter() {
         anyhow::bail!("Program requires gas counter, please provide `--available-gas` argument.");
     }
 
-    let _contracts_info = get_contracts_info(db, main_crate_ids, &replacer)?;
+    let contracts_info = get_contracts_info(db, main_crate_ids, &replacer)?;
     let sierra_program = replacer.apply(&sierra_program);
 
-    let native_context = NativeContext::new();
+    let native_context = NativeContext::new()
+        .with_config(CompilerConfig::new().with_range_check_tracking(args.compare_vm));
 
     let debug_locations = {
         let debug_info = DebugInfo::extract(db, &sierra_program)
@@ -112,6 +121,20 @@ fn main() -> anyhow::Result<()> {
         .compile(&sierra_program, Some(debug_locations))
         .unwrap();
 
+    if args.list {
+        for entry_point in entry_points(&sierra_program, native_module.program_registry())? {
+            println!(
+                "{}({}) -> ({})",
+                entry_point
+                    .name
+                    .unwrap_or_else(|| format!("[{}]", entry_point.id)),
+                entry_point.params.join(", "),
+                entry_point.return_types.join(", "),
+            );
+        }
+        return Ok(());
+    }
+
     let native_executor: NativeExecutor = match args.run_mode {
         RunMode::Aot => {
             AotNativeExecutor::from_native_module(native_module, args.opt_level.into()).into()
@@ -121,17 +144,22 @@ fn main() -> anyhow::Result<()> {
         }
     };
 
-    let gas_metadata =
-        GasMetadata::new(&sierra_program, Some(MetadataComputationConfig::default())).unwrap();
-
     let func = find_function(&sierra_program, "::main")?;
 
-    let initial_gas = gas_metadata
-        .get_initial_available_gas(&func.id, args.available_gas.map(|x| x.try_into().unwrap()))
-        .with_context(|| "not enough gas to run")?;
+    // The executor already subtracts the function's required gas from the available gas.
+    let available_gas = args.available_gas.map(|x| x.try_into().unwrap());
+    if let Some((required_gas, available_gas)) = native_executor
+        .initial_required_gas(&func.id)
+        .zip(available_gas)
+    {
+        anyhow::ensure!(
+            required_gas <= available_gas,
+            "not enough gas to run (required: {required_gas}, available: {available_gas})",
+        );
+    }
 
     let result = native_executor
-        .invoke_dynamic(&func.id, &[], Some(initial_gas))
+        .invoke_dynamic(&func.id, &[], available_gas)
         .with_context(|| "Failed to run the function.")?;
 
     let run_result = result_to_runresult(&result)?;
@@ -141,12 +169,12 @@ fn main() -> anyhow::Result<()> {
             println!("Run completed successfully, returning {values:?}")
         }
         cairo_lang_runner::RunResultValue::Panic(values) => {
+            // Panic data may contain serialized `ByteArray`s (ie. from `panic!("...")`), which
+            // are printed as strings.
             print!("Run panicked with [");
-            for value in &values {
-                match as_cairo_short_string(value) {
-                    Some(as_string) => print!("{value} ('{as_string}'), "),
-                    None => print!("{value}, "),
-                }
+            let mut values = values.into_iter();
+            while let Some(item) = format_next_item(&mut values) {
+                print!("{}, ", item.quote_if_string());
             }
             println!("].")
         }
@@ -155,5 +183,28 @@ fn main() -> anyhow::Result<()> {
         println!("Remaining gas: {gas}");
     }
 
+    if args.compare_vm {
+        let runner = SierraCasmRunner::new(
+            sierra_program.clone(),
+            sierra_program.requires_gas_counter().then(Default::default),
+            contracts_info,
+            None,
+        )?;
+        let vm_result = run_vm(
+            &runner,
+            runner.find_function("::main")?,
+            &[],
+            args.available_gas,
+        )
+        .with_context(|| "Failed to run the function in the VM.")?;
+
+        let comparison = RunComparison::new(&vm_result, &result)?;
+        println!();
+        print!("{comparison}");
+        if !comparison.is_match() {
+            std::process::exit(1);
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,529 @@
+//! Reference compilation server.
+//!
+//! Exposes the compiler over plain HTTP so that compilation can be offloaded to a dedicated
+//! machine. It only depends on the standard library for networking, and is meant to be used as a
+//! starting point rather than as a production-ready service.
+//!
+//! Endpoints:
+//!   - `GET /health`: Returns `200 OK` when the server is up.
+//!   - `POST /compile?opt_level=N`: Takes a Sierra contract class (the JSON output of
+//!     `starknet-compile`) as the body and returns the compiled shared library. The compilation
+//!     report is returned in the `X-Native-*` headers.
+//!
+//! Connections are handled by a fixed number of workers. When all of them are busy and the queue
+//! of pending connections is full, new connections are answered with `503 Service Unavailable`.
+//! Programs which make the compiler panic are answered with `500 Internal Server Error`, and the
+//! worker keeps serving connections.
+
+use anyhow::Context;
+use cairo_lang_starknet_classes::contract_class::ContractClass;
+use cairo_native::{
+    context::{CompilationLimits, CompilerConfig, NativeContext},
+    module_to_object, object_to_shared_lib, OptLevel,
+};
+use clap::Parser;
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, TrySendError},
+        Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+/// Serves the compiler over HTTP.
+#[derive(Parser, Debug)]
+#[clap(version, verbatim_doc_comment)]
+struct Args {
+    /// Address to listen on.
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    /// Number of connections handled at the same time. Defaults to the number of CPUs.
+    #[arg(long)]
+    workers: Option<NonZeroUsize>,
+    /// Number of connections waiting for a worker before new ones are rejected.
+    #[arg(long, default_value_t = 64)]
+    queue_size: usize,
+    /// Seconds to wait for the client to send (or receive) data before closing the connection.
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+    /// Maximum accepted size of the request line and headers in bytes.
+    #[arg(long, default_value_t = 16 * 1024)]
+    max_header_size: usize,
+    /// Maximum accepted body size in bytes.
+    #[arg(long, default_value_t = 64 * 1024 * 1024)]
+    max_body_size: usize,
+    /// Maximum number of Sierra functions of the compiled programs.
+    #[arg(long)]
+    max_functions: Option<usize>,
+    /// Maximum number of Sierra statements of the compiled programs.
+    #[arg(long)]
+    max_statements: Option<usize>,
+    /// Maximum number of MLIR operations generated for the compiled programs.
+    #[arg(long)]
+    max_mlir_operations: Option<usize>,
+}
+
+/// Limits on the requests accepted by the server.
+#[derive(Clone, Copy, Debug)]
+struct RequestLimits {
+    max_header_size: usize,
+    max_body_size: usize,
+}
+
+/// The state shared by the workers.
+struct Server {
+    context: NativeContext,
+    limits: RequestLimits,
+    timeout: Duration,
+}
+
+/// An HTTP response.
+struct Response {
+    status: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type", "text/plain".to_string())],
+            body: body.into().into_bytes(),
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    // Configure logging and error handling.
+    tracing::subscriber::set_global_default(
+        FmtSubscriber::builder()
+            .with_env_filter(EnvFilter::from_default_env())
+            .finish(),
+    )?;
+
+    let args = Args::parse();
+
+    // The context is shared by every request, so the configuration applies to all of them.
+    let server = Server {
+        context: NativeContext::new().with_config(CompilerConfig::new().with_limits(
+            CompilationLimits {
+                max_functions: args.max_functions,
+                max_statements: args.max_statements,
+                max_mlir_operations: args.max_mlir_operations,
+                ..Default::default()
+            },
+        )),
+        limits: RequestLimits {
+            max_header_size: args.max_header_size,
+            max_body_size: args.max_body_size,
+        },
+        timeout: Duration::from_secs(args.timeout),
+    };
+    let workers = args
+        .workers
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get);
+
+    let listener = TcpListener::bind(args.listen)
+        .with_context(|| format!("Failed to listen on {}.", args.listen))?;
+    tracing::info!("Listening on {} with {workers} workers.", args.listen);
+
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(args.queue_size);
+    let receiver = Mutex::new(receiver);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let stream = receiver
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .recv();
+                let Ok(stream) = stream else {
+                    break;
+                };
+
+                // A panic would otherwise stop the worker for good.
+                match panic::catch_unwind(AssertUnwindSafe(|| handle_connection(stream, &server))) {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => tracing::warn!("Connection error: {e}"),
+                    Err(_) => tracing::error!("Worker panicked while handling a connection."),
+                }
+            });
+        }
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(x) => x,
+                Err(e) => {
+                    tracing::warn!("Failed to accept connection: {e}");
+                    continue;
+                }
+            };
+
+            if let Err(TrySendError::Full(stream)) = sender.try_send(stream) {
+                tracing::warn!("Rejecting connection: all the workers are busy.");
+                let response = Response::text("503 Service Unavailable", "The server is busy.");
+                if let Err(e) = write_response(stream, response) {
+                    tracing::warn!("Connection error: {e}");
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, server: &Server) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(server.timeout))?;
+    stream.set_write_timeout(Some(server.timeout))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = handle_request(&mut reader, server.limits, &server.context);
+
+    write_response(stream, response)
+}
+
+/// Read a request and return the response to it.
+fn handle_request(
+    reader: &mut impl BufRead,
+    limits: RequestLimits,
+    context: &NativeContext,
+) -> Response {
+    let (method, target, content_length) = match read_head(reader, limits.max_header_size) {
+        Ok(x) => x,
+        Err(response) => return response,
+    };
+
+    let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+    match (method.as_str(), path) {
+        ("GET", "/health") => Response::text("200 OK", "ok"),
+        ("POST", "/compile") if content_length > limits.max_body_size => {
+            Response::text("413 Payload Too Large", "Body exceeds the maximum size.")
+        }
+        ("POST", "/compile") => {
+            let mut body = vec![0; content_length];
+            if let Err(e) = reader.read_exact(&mut body) {
+                return read_error_response(e);
+            }
+
+            let opt_level = query
+                .split('&')
+                .filter_map(|x| x.split_once('='))
+                .find(|(key, _)| *key == "opt_level")
+                .and_then(|(_, value)| value.parse::<u8>().ok())
+                .unwrap_or(0);
+
+            // Some programs still make the compiler panic (ex. unimplemented types).
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                compile(context, &body, opt_level.into())
+            }))
+            .unwrap_or_else(|_| {
+                Response::text("500 Internal Server Error", "The compiler panicked.")
+            })
+        }
+        (_, "/health" | "/compile") => Response::text("405 Method Not Allowed", ""),
+        _ => Response::text("404 Not Found", ""),
+    }
+}
+
+/// Read the request line and the headers. Returns the method, the target and the content length.
+fn read_head(
+    reader: &mut impl BufRead,
+    max_size: usize,
+) -> Result<(String, String, usize), Response> {
+    let mut remaining = max_size;
+
+    let request_line = read_head_line(reader, &mut remaining)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Err(Response::text("400 Bad Request", "Malformed request line.")),
+    };
+
+    let mut content_length = 0;
+    loop {
+        let line = read_head_line(reader, &mut remaining)?;
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::text("400 Bad Request", "Invalid Content-Length."))?;
+            }
+        }
+    }
+
+    Ok((method, target, content_length))
+}
+
+/// Read a line of the request head without its line terminator. The line is counted against the
+/// `remaining` size of the head.
+fn read_head_line(reader: &mut impl BufRead, remaining: &mut usize) -> Result<String, Response> {
+    // Read one byte more than allowed to tell a line that's too long from one that just fits.
+    let mut line = Vec::new();
+    reader
+        .by_ref()
+        .take((*remaining as u64).saturating_add(1))
+        .read_until(b'\n', &mut line)
+        .map_err(read_error_response)?;
+
+    if line.len() > *remaining {
+        return Err(Response::text(
+            "431 Request Header Fields Too Large",
+            "Request head exceeds the maximum size.",
+        ));
+    }
+    *remaining -= line.len();
+
+    if line.pop() != Some(b'\n') {
+        return Err(Response::text(
+            "400 Bad Request",
+            "Unexpected end of the request.",
+        ));
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    String::from_utf8(line).map_err(|_| Response::text("400 Bad Request", "Invalid request head."))
+}
+
+/// Return the response to an error while reading a request.
+fn read_error_response(e: io::Error) -> Response {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+            Response::text("408 Request Timeout", "Timed out reading the request.")
+        }
+        ErrorKind::UnexpectedEof => {
+            Response::text("400 Bad Request", "Unexpected end of the request.")
+        }
+        _ => Response::text(
+            "400 Bad Request",
+            format!("Failed to read the request: {e}"),
+        ),
+    }
+}
+
+fn compile(native_context: &NativeContext, body: &[u8], opt_level: OptLevel) -> Response {
+    let contract: ContractClass = match serde_json::from_slice(body) {
+        Ok(x) => x,
+        Err(e) => return Response::text("400 Bad Request", format!("Invalid contract class: {e}")),
+    };
+    let program = match contract.extract_sierra_program() {
+        Ok(x) => x,
+        Err(e) => return Response::text("400 Bad Request", format!("Invalid Sierra program: {e}")),
+    };
+
+    let start = Instant::now();
+    let native_module = match native_context.compile(&program, None) {
+        Ok(x) => x,
+        Err(e) => {
+            return Response::text(
+                "422 Unprocessable Entity",
+                format!("Compilation failed: {e}"),
+            )
+        }
+    };
+    let compile_time = start.elapsed();
+
+    let start = Instant::now();
+    let shared_lib = module_to_object(native_module.module(), opt_level)
+        .map_err(anyhow::Error::from)
+        .and_then(|object| {
+            let path = tempfile::Builder::new()
+                .suffix(".so")
+                .tempfile()?
+                .into_temp_path();
+            object_to_shared_lib(&object, &path)?;
+            Ok(std::fs::read(&path)?)
+        });
+    let shared_lib = match shared_lib {
+        Ok(x) => x,
+        Err(e) => {
+            return Response::text(
+                "500 Internal Server Error",
+                format!("Code generation failed: {e}"),
+            )
+        }
+    };
+    let codegen_time = start.elapsed();
+
+    tracing::info!(
+        "Compiled {} functions in {compile_time:?} (codegen {codegen_time:?}).",
+        program.funcs.len()
+    );
+
+    Response {
+        status: "200 OK",
+        headers: vec![
+            ("Content-Type", "application/octet-stream".to_string()),
+            ("X-Native-Functions", program.funcs.len().to_string()),
+            ("X-Native-Statements", program.statements.len().to_string()),
+            ("X-Native-Compile-Ms", compile_time.as_millis().to_string()),
+            ("X-Native-Codegen-Ms", codegen_time.as_millis().to_string()),
+        ],
+        body: shared_lib,
+    }
+}
+
+fn write_response(mut stream: TcpStream, response: Response) -> anyhow::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\n", response.status)?;
+    for (name, value) in &response.headers {
+        write!(stream, "{name}: {value}\r\n")?;
+    }
+    write!(
+        stream,
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cairo_lang_sierra::{program::DeclaredTypeInfo, ProgramParser};
+    use cairo_lang_starknet_classes::{
+        compiler_version::{current_compiler_version_id, current_sierra_version_id},
+        contract_class::ContractEntryPoints,
+        felt252_serde::sierra_to_felt252s,
+    };
+
+    const LIMITS: RequestLimits = RequestLimits {
+        max_header_size: 256,
+        max_body_size: 1024,
+    };
+
+    fn status_of(request: &[u8]) -> &'static str {
+        handle_request(&mut BufReader::new(request), LIMITS, &NativeContext::new()).status
+    }
+
+    #[test]
+    fn health() {
+        assert_eq!(status_of(b"GET /health HTTP/1.1\r\n\r\n"), "200 OK");
+        assert_eq!(
+            status_of(b"POST /health HTTP/1.1\r\n\r\n"),
+            "405 Method Not Allowed"
+        );
+        assert_eq!(status_of(b"GET /missing HTTP/1.1\r\n\r\n"), "404 Not Found");
+    }
+
+    #[test]
+    fn malformed_head() {
+        assert_eq!(status_of(b"GARBAGE\r\n\r\n"), "400 Bad Request");
+        assert_eq!(status_of(b"GET /health HTTP/1.1\r\n"), "400 Bad Request");
+        assert_eq!(status_of(b""), "400 Bad Request");
+        assert_eq!(status_of(b"GET /\xff HTTP/1.1\r\n\r\n"), "400 Bad Request");
+        assert_eq!(
+            status_of(b"POST /compile HTTP/1.1\r\nContent-Length: -1\r\n\r\n"),
+            "400 Bad Request"
+        );
+    }
+
+    #[test]
+    fn oversized_head() {
+        let long_line = format!(
+            "GET /{} HTTP/1.1\r\n\r\n",
+            "a".repeat(LIMITS.max_header_size)
+        );
+        assert_eq!(
+            status_of(long_line.as_bytes()),
+            "431 Request Header Fields Too Large"
+        );
+
+        // Every header fits, but all of them together don't.
+        let many_headers = format!(
+            "GET /health HTTP/1.1\r\n{}\r\n",
+            "X-Header: value\r\n".repeat(LIMITS.max_header_size / 16)
+        );
+        assert_eq!(
+            status_of(many_headers.as_bytes()),
+            "431 Request Header Fields Too Large"
+        );
+    }
+
+    #[test]
+    fn oversized_body() {
+        let request = format!(
+            "POST /compile HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            LIMITS.max_body_size + 1
+        );
+        assert_eq!(status_of(request.as_bytes()), "413 Payload Too Large");
+    }
+
+    #[test]
+    fn malformed_body() {
+        // The body is shorter than announced.
+        assert_eq!(
+            status_of(b"POST /compile HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}"),
+            "400 Bad Request"
+        );
+        assert_eq!(
+            status_of(b"POST /compile HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"),
+            "400 Bad Request"
+        );
+    }
+    #[test]
+    fn compiler_panic() {
+        // The `Span` type isn't implemented yet, so building it panics.
+        let mut program = ProgramParser::new()
+            .parse(
+                "type felt252 = felt252;
+                type Span = Span<felt252>;
+
+                return([0]);
+
+                foo@0([0]: Span) -> (Span);",
+            )
+            .unwrap();
+        for type_declaration in &mut program.type_declarations {
+            type_declaration.declared_type_info = Some(DeclaredTypeInfo {
+                storable: true,
+                droppable: true,
+                duplicatable: true,
+                zero_sized: false,
+            });
+        }
+
+        let contract = ContractClass {
+            sierra_program: sierra_to_felt252s(
+                current_sierra_version_id(),
+                current_compiler_version_id(),
+                &program,
+            )
+            .unwrap(),
+            sierra_program_debug_info: None,
+            contract_class_version: "0.1.0".to_string(),
+            entry_points_by_type: ContractEntryPoints::default(),
+            abi: None,
+        };
+        let body = serde_json::to_string(&contract).unwrap();
+        let request = format!(
+            "POST /compile HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+
+        let context = NativeContext::new();
+        let limits = RequestLimits {
+            max_header_size: LIMITS.max_header_size,
+            max_body_size: body.len(),
+        };
+        for _ in 0..2 {
+            let response =
+                handle_request(&mut BufReader::new(request.as_bytes()), limits, &context);
+            assert_eq!(response.status, "500 Internal Server Error");
+        }
+    }
+}
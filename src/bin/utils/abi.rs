@@ -0,0 +1,561 @@
+//! Conversion between human-readable values and their serialization as felts, following the types
+//! declared in the ABI of a contract.
+//!
+//! Values are written like Cairo literals:
+//!   - Felts and integers: `123`, `-5` or `0x7b`. A `u256` is written as a single number.
+//!   - Booleans: `true` or `false`.
+//!   - Byte arrays: `"hello"`.
+//!   - Arrays and spans: `[1, 2, 3]`.
+//!   - Tuples and structs, with their members in order: `(1, 2)` or `{1, 2}`.
+//!   - Enums: `Some(5)` or `None`.
+
+use anyhow::{bail, ensure, Context};
+use cairo_lang_starknet_classes::{
+    abi::{Contract, EventFieldKind, EventKind, Function, Item},
+    keccak::starknet_keccak,
+};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use starknet_types_core::felt::Felt;
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+/// A human-readable value, before knowing its type.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Atom(String),
+    String(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    Variant(String, Vec<Value>),
+}
+
+struct Event {
+    selector: Felt,
+    name: String,
+    members: Vec<(String, String, EventFieldKind)>,
+}
+
+/// The functions and types declared in the ABI of a contract.
+#[derive(Default)]
+pub struct Abi {
+    functions: HashMap<String, Function>,
+    structs: HashMap<String, Vec<(String, String)>>,
+    enums: HashMap<String, Vec<(String, String)>>,
+    events: Vec<Event>,
+}
+
+impl Abi {
+    pub fn new(contract: &Contract) -> Self {
+        let mut abi = Self::default();
+        for item in contract.clone() {
+            abi.add_item(item);
+        }
+
+        abi
+    }
+
+    fn add_item(&mut self, item: Item) {
+        match item {
+            Item::Function(function) => {
+                self.functions.insert(function.name.clone(), function);
+            }
+            Item::Interface(interface) => {
+                for item in interface.items {
+                    self.add_item(item);
+                }
+            }
+            Item::Struct(info) => {
+                self.structs.insert(
+                    info.name,
+                    info.members.into_iter().map(|x| (x.name, x.ty)).collect(),
+                );
+            }
+            Item::Enum(info) => {
+                self.enums.insert(
+                    info.name,
+                    info.variants.into_iter().map(|x| (x.name, x.ty)).collect(),
+                );
+            }
+            // Only struct events are decoded, which covers the variants of the usual `Event` enum.
+            Item::Event(event) => {
+                if let EventKind::Struct { members } = event.kind {
+                    let short_name = event.name.rsplit("::").next().unwrap_or(&event.name);
+                    self.events.push(Event {
+                        selector: selector(short_name),
+                        members: members
+                            .into_iter()
+                            .map(|x| (x.name, x.ty, x.kind))
+                            .collect(),
+                        name: event.name,
+                    });
+                }
+            }
+            Item::Constructor(_) | Item::L1Handler(_) | Item::Impl(_) => {}
+        }
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+
+    /// Serialize the human-readable `value` of type `ty`.
+    pub fn encode(&self, ty: &str, value: &str) -> anyhow::Result<Vec<Felt>> {
+        let mut output = Vec::new();
+        self.encode_value(ty, &parse_value(value)?, &mut output)
+            .with_context(|| format!("invalid value `{value}` for type `{ty}`"))?;
+
+        Ok(output)
+    }
+
+    fn encode_value(&self, ty: &str, value: &Value, output: &mut Vec<Felt>) -> anyhow::Result<()> {
+        if let Some(item_ty) = array_item_type(ty) {
+            let Value::List(items) = value else {
+                bail!("expected an array");
+            };
+
+            output.push(Felt::from(items.len() as u64));
+            return items
+                .iter()
+                .try_for_each(|x| self.encode_value(item_ty, x, output));
+        }
+
+        let members = tuple_types(ty).or_else(|| {
+            self.structs
+                .get(ty)
+                .map(|x| x.iter().map(|(_, ty)| ty.as_str()).collect())
+        });
+        if let Some(members) = members {
+            let Value::Tuple(items) = value else {
+                bail!("expected a tuple or struct");
+            };
+            ensure!(
+                items.len() == members.len(),
+                "expected {} members, got {}",
+                members.len(),
+                items.len()
+            );
+
+            return members
+                .into_iter()
+                .zip(items)
+                .try_for_each(|(ty, x)| self.encode_value(ty, x, output));
+        }
+
+        match (ty, value) {
+            ("core::bool", Value::Atom(x)) if x == "true" || x == "false" => {
+                output.push(if x == "true" { Felt::ONE } else { Felt::ZERO });
+                return Ok(());
+            }
+            ("core::integer::u256", Value::Atom(x)) => {
+                let value = parse_biguint(x)?;
+                ensure!(value.bits() <= 256, "`{x}` doesn't fit in a u256");
+
+                output.push(biguint_to_felt(&(&value & BigUint::from(u128::MAX))));
+                output.push(biguint_to_felt(&(value >> 128u32)));
+                return Ok(());
+            }
+            ("core::byte_array::ByteArray", Value::String(x)) => {
+                let chunks = x.as_bytes().chunks_exact(31);
+                let pending = chunks.remainder();
+
+                output.push(Felt::from(chunks.len() as u64));
+                output.extend(chunks.map(Felt::from_bytes_be_slice));
+                output.push(Felt::from_bytes_be_slice(pending));
+                output.push(Felt::from(pending.len() as u64));
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if let Some(variants) = self.enums.get(ty) {
+            let (name, payload) = match value {
+                Value::Atom(name) => (name, &[] as &[Value]),
+                Value::Variant(name, payload) => (name, payload.as_slice()),
+                _ => bail!("expected an enum variant"),
+            };
+            let idx = variants
+                .iter()
+                .position(|(x, _)| x == name)
+                .with_context(|| format!("unknown variant `{name}`"))?;
+
+            output.push(Felt::from(idx as u64));
+            let variant_ty = &variants[idx].1;
+            return match payload {
+                [value] => self.encode_value(variant_ty, value, output),
+                _ => self.encode_value(variant_ty, &Value::Tuple(payload.to_vec()), output),
+            };
+        }
+
+        // Everything else (felts, integers, addresses...) is a single felt.
+        match value {
+            Value::Atom(x) => output.push(parse_felt(x)?),
+            _ => bail!("expected a number"),
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a value of type `ty` into its human-readable form.
+    pub fn decode(&self, ty: &str, data: &mut dyn Iterator<Item = Felt>) -> anyhow::Result<String> {
+        if let Some(item_ty) = array_item_type(ty) {
+            let len = felt_to_usize(next_felt(data)?)?;
+            let items = (0..len)
+                .map(|_| self.decode(item_ty, data))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            return Ok(format!("[{}]", items.join(", ")));
+        }
+
+        if let Some(members) = tuple_types(ty) {
+            let items = members
+                .into_iter()
+                .map(|ty| self.decode(ty, data))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            return Ok(format!("({})", items.join(", ")));
+        }
+
+        if let Some(members) = self.structs.get(ty) {
+            let items = members
+                .iter()
+                .map(|(name, ty)| Ok(format!("{name}: {}", self.decode(ty, data)?)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            return Ok(format!("{{ {} }}", items.join(", ")));
+        }
+
+        match ty {
+            "core::bool" => return Ok((next_felt(data)? != Felt::ZERO).to_string()),
+            "core::integer::u256" => {
+                let low = next_felt(data)?.to_biguint();
+                let high = next_felt(data)?.to_biguint();
+                return Ok(((high << 128u32) + low).to_string());
+            }
+            "core::byte_array::ByteArray" => {
+                let len = felt_to_usize(next_felt(data)?)?;
+                let mut bytes = Vec::new();
+                for _ in 0..len {
+                    bytes.extend_from_slice(&next_felt(data)?.to_bytes_be()[1..]);
+                }
+
+                let pending = next_felt(data)?.to_bytes_be();
+                let pending_len = felt_to_usize(next_felt(data)?)?;
+                ensure!(pending_len < 31, "invalid byte array");
+                bytes.extend_from_slice(&pending[32 - pending_len..]);
+
+                return Ok(format!("{:?}", String::from_utf8_lossy(&bytes)));
+            }
+            _ => {}
+        }
+
+        if let Some(variants) = self.enums.get(ty) {
+            let idx = felt_to_usize(next_felt(data)?)?;
+            let (name, variant_ty) = variants
+                .get(idx)
+                .with_context(|| format!("invalid variant {idx} of `{ty}`"))?;
+
+            return Ok(match variant_ty.as_str() {
+                "()" => name.clone(),
+                _ => format!("{name}({})", self.decode(variant_ty, data)?),
+            });
+        }
+
+        let value = next_felt(data)?;
+        let short_ty = ty.rsplit("::").next().unwrap_or(ty);
+        Ok(match short_ty {
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => value.to_string(),
+            // Negative values are stored as `PRIME - x`.
+            "i8" | "i16" | "i32" | "i64" | "i128" => {
+                if value.to_biguint() > Felt::MAX.to_biguint() >> 1u32 {
+                    format!("-{}", -value)
+                } else {
+                    value.to_string()
+                }
+            }
+            _ => format_felt(&value),
+        })
+    }
+
+    /// Decode an emitted event, or return `None` if it doesn't match any event of the ABI.
+    pub fn decode_event(&self, keys: &[Felt], data: &[Felt]) -> Option<String> {
+        let event = self
+            .events
+            .iter()
+            .find(|x| keys.first() == Some(&x.selector))?;
+
+        let mut keys = keys[1..].iter().copied();
+        let mut data = data.iter().copied();
+        let members = event
+            .members
+            .iter()
+            .map(|(name, ty, kind)| {
+                let source: &mut dyn Iterator<Item = Felt> = match kind {
+                    EventFieldKind::KeySerde => &mut keys,
+                    _ => &mut data,
+                };
+                Ok(format!("{name}: {}", self.decode(ty, source)?))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .ok()?;
+
+        Some(format!("{} {{ {} }}", event.name, members.join(", ")))
+    }
+}
+
+/// Return the selector of an entry point or event.
+pub fn selector(name: &str) -> Felt {
+    biguint_to_felt(&starknet_keccak(name.as_bytes()))
+}
+
+/// Return the item type of an array or span type.
+fn array_item_type(ty: &str) -> Option<&str> {
+    ["core::array::Array::<", "core::array::Span::<"]
+        .into_iter()
+        .find_map(|prefix| ty.strip_prefix(prefix)?.strip_suffix('>'))
+}
+
+/// Return the member types of a tuple type.
+fn tuple_types(ty: &str) -> Option<Vec<&str>> {
+    let members = ty.strip_prefix('(')?.strip_suffix(')')?;
+
+    let mut types = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, c) in members.char_indices() {
+        match c {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                types.push(members[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    if !members[start..].trim().is_empty() {
+        types.push(members[start..].trim());
+    }
+
+    Some(types)
+}
+
+pub fn format_felt(value: &Felt) -> String {
+    format!("0x{}", value.to_biguint().to_str_radix(16))
+}
+
+fn next_felt(data: &mut dyn Iterator<Item = Felt>) -> anyhow::Result<Felt> {
+    data.next().context("not enough data")
+}
+
+fn felt_to_usize(value: Felt) -> anyhow::Result<usize> {
+    value
+        .to_biguint()
+        .to_usize()
+        .with_context(|| format!("invalid length {value}"))
+}
+
+fn biguint_to_felt(value: &BigUint) -> Felt {
+    Felt::from_bytes_be_slice(&value.to_bytes_be())
+}
+
+fn parse_biguint(value: &str) -> anyhow::Result<BigUint> {
+    match value.strip_prefix("0x") {
+        Some(x) => BigUint::parse_bytes(x.as_bytes(), 16),
+        None => BigUint::parse_bytes(value.as_bytes(), 10),
+    }
+    .with_context(|| format!("invalid number `{value}`"))
+}
+
+/// Parse a felt written in decimal (optionally negative) or hexadecimal.
+pub fn parse_felt(value: &str) -> anyhow::Result<Felt> {
+    if let Some(value) = value.strip_prefix('-') {
+        return Ok(-parse_felt(value)?);
+    }
+
+    let value = parse_biguint(value)?;
+    ensure!(
+        value <= Felt::MAX.to_biguint(),
+        "`{value}` doesn't fit in a felt"
+    );
+
+    Ok(biguint_to_felt(&value))
+}
+
+fn parse_value(input: &str) -> anyhow::Result<Value> {
+    let mut chars = input.chars().peekable();
+    let value = parse_next(&mut chars)?;
+
+    skip_whitespace(&mut chars);
+    if let Some(c) = chars.next() {
+        bail!("unexpected `{c}` after the value");
+    }
+
+    Ok(value)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_next(chars: &mut Peekable<Chars>) -> anyhow::Result<Value> {
+    skip_whitespace(chars);
+    match chars.peek().copied() {
+        Some('[') => {
+            chars.next();
+            Ok(Value::List(parse_items(chars, ']')?))
+        }
+        Some('(') => {
+            chars.next();
+            Ok(Value::Tuple(parse_items(chars, ')')?))
+        }
+        Some('{') => {
+            chars.next();
+            Ok(Value::Tuple(parse_items(chars, '}')?))
+        }
+        Some('"') => {
+            chars.next();
+
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break Ok(Value::String(value)),
+                    Some('\\') => value.push(chars.next().context("unterminated string")?),
+                    Some(c) => value.push(c),
+                    None => bail!("unterminated string"),
+                }
+            }
+        }
+        Some(c) => {
+            let mut atom = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':'))
+            {
+                atom.push(c);
+            }
+            ensure!(!atom.is_empty(), "unexpected `{c}`");
+
+            skip_whitespace(chars);
+            if chars.next_if_eq(&'(').is_some() {
+                Ok(Value::Variant(atom, parse_items(chars, ')')?))
+            } else {
+                Ok(Value::Atom(atom))
+            }
+        }
+        None => bail!("expected a value"),
+    }
+}
+
+fn parse_items(chars: &mut Peekable<Chars>, end: char) -> anyhow::Result<Vec<Value>> {
+    let mut items = Vec::new();
+    loop {
+        skip_whitespace(chars);
+        if chars.next_if_eq(&end).is_some() {
+            return Ok(items);
+        }
+
+        items.push(parse_next(chars)?);
+
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => {}
+            Some(c) if c == end => return Ok(items),
+            Some(c) => bail!("expected `,` or `{end}`, found `{c}`"),
+            None => bail!("expected `{end}`"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo_lang_starknet_classes::abi::{Enum, EnumVariant, Struct, StructMember};
+
+    fn abi() -> Abi {
+        let mut abi = Abi::default();
+        abi.add_item(Item::Struct(Struct {
+            name: "example::Point".into(),
+            members: vec![
+                StructMember {
+                    name: "x".into(),
+                    ty: "core::integer::u32".into(),
+                },
+                StructMember {
+                    name: "y".into(),
+                    ty: "core::integer::i32".into(),
+                },
+            ],
+        }));
+        abi.add_item(Item::Enum(Enum {
+            name: "core::option::Option::<example::Point>".into(),
+            variants: vec![
+                EnumVariant {
+                    name: "Some".into(),
+                    ty: "example::Point".into(),
+                },
+                EnumVariant {
+                    name: "None".into(),
+                    ty: "()".into(),
+                },
+            ],
+        }));
+        abi
+    }
+
+    #[test]
+    fn round_trip() {
+        let abi = abi();
+        let cases = [
+            ("core::felt252", "0x1234", "0x1234"),
+            (
+                "core::integer::u256",
+                "0x100000000000000000000000000000001",
+                "340282366920938463463374607431768211457",
+            ),
+            (
+                "core::array::Array::<core::integer::u8>",
+                "[1, 2, 3]",
+                "[1, 2, 3]",
+            ),
+            ("(core::felt252, core::bool)", "(0, true)", "(0x0, true)"),
+            (
+                "core::option::Option::<example::Point>",
+                "Some({1, -2})",
+                "Some({ x: 1, y: -2 })",
+            ),
+            ("core::option::Option::<example::Point>", "None", "None"),
+            (
+                "core::byte_array::ByteArray",
+                "\"Hello, World! This text is longer than 31 bytes.\"",
+                "\"Hello, World! This text is longer than 31 bytes.\"",
+            ),
+        ];
+
+        for (ty, input, output) in cases {
+            let data = abi.encode(ty, input).unwrap();
+            let mut data = data.into_iter();
+            assert_eq!(abi.decode(ty, &mut data).unwrap(), output);
+            assert_eq!(data.next(), None);
+        }
+    }
+
+    #[test]
+    fn encode_u256() {
+        assert_eq!(
+            abi()
+                .encode("core::integer::u256", "0x100000000000000000000000000000002")
+                .unwrap(),
+            [Felt::from(2), Felt::ONE]
+        );
+    }
+
+    #[test]
+    fn encode_errors() {
+        let abi = abi();
+        assert!(abi.encode("core::felt252", "[1]").is_err());
+        assert!(abi.encode("core::felt252", "1 2").is_err());
+        assert!(abi.encode("example::Point", "{1}").is_err());
+        assert!(abi
+            .encode("core::option::Option::<example::Point>", "Nothing")
+            .is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Structural diffing of printed MLIR modules.
+//!
+//! Modules are split into their top-level operations (functions, globals...) keyed by their symbol
+//! name, so that reordering functions doesn't show up as a change and every changed function gets
+//! its own line diff.
+
+use cairo_lang_sierra::program::Program;
+use cairo_native::context::{CompilerConfig, NativeContext};
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Largest line product for which a full LCS table is computed. Bigger functions (after removing
+/// the common prefix and suffix) are reported as replaced as a whole.
+const MAX_LCS_CELLS: usize = 1 << 24;
+
+/// Summary of a module diff.
+#[derive(Debug, Default)]
+pub struct ModuleDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<(String, Vec<DiffLine>)>,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Compile a program with the given configuration and print the resulting module.
+pub fn compile_module(program: &Program, config: CompilerConfig) -> anyhow::Result<String> {
+    let context = NativeContext::new().with_config(config);
+    let module = context.compile(program, None)?;
+
+    Ok(module.module().as_operation().to_string())
+}
+
+impl ModuleDiff {
+    /// Compare two printed modules.
+    pub fn new(lhs: &str, rhs: &str) -> Self {
+        let lhs = split_module(lhs);
+        let rhs = split_module(rhs);
+
+        let mut diff = Self::default();
+        for (symbol, lhs_body) in &lhs {
+            match rhs.get(symbol) {
+                Some(rhs_body) if rhs_body == lhs_body => diff.unchanged += 1,
+                Some(rhs_body) => diff
+                    .changed
+                    .push((symbol.clone(), diff_lines(lhs_body, rhs_body))),
+                None => diff.removed.push(symbol.clone()),
+            }
+        }
+        diff.added.extend(
+            rhs.keys()
+                .filter(|symbol| !lhs.contains_key(*symbol))
+                .cloned(),
+        );
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Render a one-line-per-operation summary.
+    pub fn summary(&self) -> String {
+        let mut output = String::new();
+        for symbol in &self.removed {
+            writeln!(output, "- {symbol}").unwrap();
+        }
+        for symbol in &self.added {
+            writeln!(output, "+ {symbol}").unwrap();
+        }
+        for (symbol, lines) in &self.changed {
+            let (added, removed) = lines.iter().fold((0, 0), |(a, r), line| match line {
+                DiffLine::Same(_) => (a, r),
+                DiffLine::Added(_) => (a + 1, r),
+                DiffLine::Removed(_) => (a, r + 1),
+            });
+            writeln!(output, "~ {symbol} (+{added} -{removed})").unwrap();
+        }
+        writeln!(
+            output,
+            "{} removed, {} added, {} changed, {} unchanged",
+            self.removed.len(),
+            self.added.len(),
+            self.changed.len(),
+            self.unchanged,
+        )
+        .unwrap();
+
+        output
+    }
+
+    /// Render the changed operations as unified-like diffs with `context` lines around each
+    /// change.
+    pub fn render(&self, context: usize) -> String {
+        let mut output = String::new();
+        for (symbol, lines) in &self.changed {
+            writeln!(output, "@@ {symbol} @@").unwrap();
+
+            let is_change = |line: &DiffLine| !matches!(line, DiffLine::Same(_));
+            let mut last_printed = None;
+            for (idx, line) in lines.iter().enumerate() {
+                let near_change = lines
+                    [idx.saturating_sub(context)..lines.len().min(idx + context + 1)]
+                    .iter()
+                    .any(is_change);
+                if !near_change {
+                    continue;
+                }
+
+                if last_printed.is_some_and(|last| last + 1 != idx) {
+                    writeln!(output, "  ...").unwrap();
+                }
+                last_printed = Some(idx);
+
+                match line {
+                    DiffLine::Same(x) => writeln!(output, "  {x}"),
+                    DiffLine::Added(x) => writeln!(output, "+ {x}"),
+                    DiffLine::Removed(x) => writeln!(output, "- {x}"),
+                }
+                .unwrap();
+            }
+        }
+        output.push_str(&self.summary());
+
+        output
+    }
+}
+
+/// Split a printed module into its top-level operations, keyed by symbol name.
+fn split_module(module: &str) -> BTreeMap<String, Vec<String>> {
+    let mut items = BTreeMap::<String, Vec<String>>::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in module.lines() {
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            // The `module { ... }` wrapper itself.
+            continue;
+        }
+
+        if indent == 2 && !line.trim_start().starts_with('}') {
+            if let Some((key, body)) = current.take() {
+                insert_unique(&mut items, key, body);
+            }
+            current = Some((symbol_name(line), Vec::new()));
+        }
+
+        if let Some((_, body)) = &mut current {
+            body.push(line.trim_end().to_string());
+        }
+    }
+    if let Some((key, body)) = current.take() {
+        insert_unique(&mut items, key, body);
+    }
+
+    items
+}
+
+fn insert_unique(items: &mut BTreeMap<String, Vec<String>>, key: String, body: Vec<String>) {
+    let mut unique_key = key.clone();
+    let mut idx = 1;
+    while items.contains_key(&unique_key) {
+        unique_key = format!("{key}#{idx}");
+        idx += 1;
+    }
+    items.insert(unique_key, body);
+}
+
+/// Extract the symbol of a top-level operation, or use the whole line if there's none.
+fn symbol_name(line: &str) -> String {
+    let line = line.trim();
+    match line.find('@') {
+        Some(start) => {
+            let name = &line[start..];
+            let end = if let Some(quoted) = name.strip_prefix("@\"") {
+                quoted.find('"').map(|x| x + 3).unwrap_or(name.len())
+            } else {
+                name.find(|c: char| c.is_whitespace() || "(<:,".contains(c))
+                    .unwrap_or(name.len())
+            };
+            name[..end].to_string()
+        }
+        None => line.to_string(),
+    }
+}
+
+/// Line diff based on the longest common subsequence.
+fn diff_lines(lhs: &[String], rhs: &[String]) -> Vec<DiffLine> {
+    let prefix = lhs.iter().zip(rhs).take_while(|(a, b)| a == b).count();
+    let suffix = lhs[prefix..]
+        .iter()
+        .rev()
+        .zip(rhs[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let lhs_mid = &lhs[prefix..lhs.len() - suffix];
+    let rhs_mid = &rhs[prefix..rhs.len() - suffix];
+
+    let mut result = lhs[..prefix]
+        .iter()
+        .cloned()
+        .map(DiffLine::Same)
+        .collect::<Vec<_>>();
+
+    if (lhs_mid.len() + 1) * (rhs_mid.len() + 1) > MAX_LCS_CELLS {
+        result.extend(lhs_mid.iter().cloned().map(DiffLine::Removed));
+        result.extend(rhs_mid.iter().cloned().map(DiffLine::Added));
+    } else {
+        let (n, m) = (lhs_mid.len(), rhs_mid.len());
+        let mut table = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                table[i * (m + 1) + j] = if lhs_mid[i] == rhs_mid[j] {
+                    table[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    table[(i + 1) * (m + 1) + j].max(table[i * (m + 1) + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if lhs_mid[i] == rhs_mid[j] {
+                result.push(DiffLine::Same(lhs_mid[i].clone()));
+                i += 1;
+                j += 1;
+            } else if table[(i + 1) * (m + 1) + j] >= table[i * (m + 1) + j + 1] {
+                result.push(DiffLine::Removed(lhs_mid[i].clone()));
+                i += 1;
+            } else {
+                result.push(DiffLine::Added(rhs_mid[j].clone()));
+                j += 1;
+            }
+        }
+        result.extend(lhs_mid[i..].iter().cloned().map(DiffLine::Removed));
+        result.extend(rhs_mid[j..].iter().cloned().map(DiffLine::Added));
+    }
+
+    result.extend(
+        lhs[lhs.len() - suffix..]
+            .iter()
+            .cloned()
+            .map(DiffLine::Same),
+    );
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo_lang_sierra::ProgramParser;
+    use cairo_native::OptLevel;
+
+    const FOO: &str = "  func.func @foo(%arg0: i64) -> i64 {
+    return %arg0 : i64
+  }";
+    const BAR: &str = "  func.func @bar(%arg0: i64) -> i64 {
+    %c1_i64 = arith.constant 1 : i64
+    %0 = arith.addi %arg0, %c1_i64 : i64
+    return %0 : i64
+  }";
+
+    fn module(items: &[&str]) -> String {
+        format!("module {{\n{}\n}}\n", items.join("\n"))
+    }
+
+    #[test]
+    fn identical_modules() {
+        let module = module(&[FOO, BAR]);
+        let diff = ModuleDiff::new(&module, &module);
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(
+            diff.summary(),
+            "0 removed, 0 added, 0 changed, 2 unchanged\n"
+        );
+    }
+
+    #[test]
+    fn reordered_functions() {
+        let diff = ModuleDiff::new(&module(&[FOO, BAR]), &module(&[BAR, FOO]));
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 2);
+    }
+
+    #[test]
+    fn changed_function() {
+        let changed_bar = BAR.replace("arith.constant 1", "arith.constant 2");
+        let baz = FOO.replace("@foo", "@baz");
+        let diff = ModuleDiff::new(&module(&[FOO, BAR]), &module(&[&changed_bar, &baz]));
+
+        assert!(!diff.is_empty());
+        assert_eq!(diff.removed, ["@foo"]);
+        assert_eq!(diff.added, ["@baz"]);
+        assert_eq!(diff.unchanged, 0);
+
+        let [(symbol, lines)] = diff.changed.as_slice() else {
+            panic!("expected a single changed function");
+        };
+        assert_eq!(symbol, "@bar");
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| !matches!(line, DiffLine::Same(_)))
+                .collect::<Vec<_>>(),
+            [
+                &DiffLine::Removed("    %c1_i64 = arith.constant 1 : i64".to_string()),
+                &DiffLine::Added("    %c1_i64 = arith.constant 2 : i64".to_string()),
+            ]
+        );
+
+        let rendered = diff.render(0);
+        assert!(rendered.starts_with("@@ @bar @@\n- "));
+        assert!(rendered.ends_with("1 removed, 1 added, 1 changed, 0 unchanged\n"));
+    }
+
+    #[test]
+    fn compare_configurations() {
+        let program = ProgramParser::new()
+            .parse(
+                "type felt252 = felt252;
+                libfunc felt252_const<2> = felt252_const<2>;
+                libfunc felt252_add = felt252_add;
+                libfunc felt252_mul = felt252_mul;
+                libfunc store_temp<felt252> = store_temp<felt252>;
+
+                felt252_const<2>() -> ([1]);
+                felt252_add([0], [1]) -> ([2]);
+                felt252_const<2>() -> ([3]);
+                felt252_mul([2], [3]) -> ([4]);
+                store_temp<felt252>([4]) -> ([5]);
+                return([5]);
+
+                run_test@0([0]: felt252) -> (felt252);",
+            )
+            .unwrap();
+
+        let unoptimized = compile_module(&program, CompilerConfig::new()).unwrap();
+        let optimized = compile_module(
+            &program,
+            CompilerConfig::new().with_opt_level(OptLevel::Aggressive),
+        )
+        .unwrap();
+
+        assert!(ModuleDiff::new(&unoptimized, &unoptimized).is_empty());
+
+        // The optimizations change the function.
+        let diff = ModuleDiff::new(&unoptimized, &optimized);
+        assert!(!diff.is_empty());
+        assert!(diff
+            .changed
+            .iter()
+            .any(|(symbol, _)| symbol.contains("run_test")));
+    }
+}
@@ -1,4 +1,14 @@
+//! # Program caches
+//!
+//! Caches of compiled programs, indexed by a user-provided key (ex. the class hash of a contract),
+//! so that programs are only compiled once. The [AOT cache](AotProgramCache) can also be exported
+//! into a directory and imported back, which makes it persistent across process restarts.
+//!
+//! The [ProgramCache] wraps either of them, for embedders which choose the backend at runtime.
+
 pub use self::{aot::AotProgramCache, jit::JitProgramCache};
+use crate::{executor::NativeExecutor, OptLevel};
+use cairo_lang_sierra::program::Program;
 use std::hash::Hash;
 
 pub mod aot;
@@ -13,6 +23,42 @@ where
     Jit(JitProgramCache<'a, K>),
 }
 
+impl<'a, K> ProgramCache<'a, K>
+where
+    K: PartialEq + Eq + Hash,
+{
+    pub fn get(&self, key: &K) -> Option<NativeExecutor<'a>> {
+        match self {
+            ProgramCache::Aot(cache) => cache.get(key).map(NativeExecutor::Aot),
+            ProgramCache::Jit(cache) => cache.get(key).map(NativeExecutor::Jit),
+        }
+    }
+
+    pub fn compile_and_insert(
+        &mut self,
+        key: K,
+        program: &Program,
+        opt_level: OptLevel,
+    ) -> NativeExecutor<'a> {
+        match self {
+            ProgramCache::Aot(cache) => {
+                NativeExecutor::Aot(cache.compile_and_insert(key, program, opt_level))
+            }
+            ProgramCache::Jit(cache) => {
+                NativeExecutor::Jit(cache.compile_and_insert(key, program, opt_level))
+            }
+        }
+    }
+
+    /// Remove a program from the cache. Check out [AotProgramCache::remove] for more info.
+    pub fn remove(&mut self, key: &K) -> Option<NativeExecutor<'a>> {
+        match self {
+            ProgramCache::Aot(cache) => cache.remove(key).map(NativeExecutor::Aot),
+            ProgramCache::Jit(cache) => cache.remove(key).map(NativeExecutor::Jit),
+        }
+    }
+}
+
 impl<'a, K> From<AotProgramCache<'a, K>> for ProgramCache<'a, K>
 where
     K: PartialEq + Eq + Hash,
@@ -30,3 +76,39 @@ where
         Self::Jit(value)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::NativeContext, utils::test::load_cairo, values::JitValue};
+    use starknet_types_core::felt::Felt;
+
+    #[test]
+    fn test_program_cache() {
+        let native_context = NativeContext::new();
+        let (_, program) = load_cairo! {
+            fn run_test() -> felt252 {
+                42
+            }
+        };
+        let function_id = &program.funcs.first().expect("should have a function").id;
+
+        for mut cache in [
+            ProgramCache::from(AotProgramCache::new(&native_context)),
+            ProgramCache::from(JitProgramCache::new(&native_context)),
+        ] {
+            assert!(cache.get(&"program").is_none());
+            cache.compile_and_insert("program", &program, OptLevel::default());
+
+            let res = cache
+                .get(&"program")
+                .expect("should be cached")
+                .invoke_dynamic(function_id, &[], Some(u128::MAX))
+                .expect("should run");
+            assert_eq!(res.return_value, JitValue::Felt252(Felt::from(42)));
+
+            assert!(cache.remove(&"program").is_some());
+            assert!(cache.get(&"program").is_none());
+        }
+    }
+}
@@ -1,22 +1,58 @@
+//! # AOT program cache
+//!
+//! The compiled programs can be exported into a directory and imported back (ex. after restarting
+//! the node), which avoids compiling every program again. The directory contains an index with the
+//! keys of the programs and the name of their shared libraries, which are stored beside it.
+//!
+//! The index is only imported when it was exported by the same version of Cairo Native. Besides,
+//! every entry stores the Keccak-256 hashes of its program, of the configuration it was compiled
+//! with (the optimization level, the [CompilerConfig](crate::context::CompilerConfig) of the
+//! context and its plugins) and of its shared library. Entries are skipped when any of them doesn't
+//! match (ex. when the program of a key has changed, the context is configured differently or the
+//! file has been truncated). Those checks detect stale and corrupted entries, but don't protect
+//! against tampering: the directory must be trusted, since its shared libraries are loaded as is.
+
 use crate::{
-    context::NativeContext, executor::AotNativeExecutor, metadata::gas::GasMetadata,
-    module::NativeModule, utils::SHARED_LIBRARY_EXT, OptLevel,
+    context::NativeContext, executor::AotNativeExecutor, module::NativeModule,
+    utils::SHARED_LIBRARY_EXT, OptLevel,
 };
 use cairo_lang_sierra::program::Program;
 use libloading::Library;
+use sha3::{Digest, Keccak256};
 use std::{
-    collections::HashMap,
-    fmt::{self, Debug},
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display},
+    fs,
     hash::Hash,
+    io::{self, Write},
+    path::Path,
     rc::Rc,
+    str::FromStr,
 };
+use tempfile::TempPath;
+
+/// Name of the index file in an exported cache.
+const INDEX_FILE_NAME: &str = "index";
+/// First line of the index, which is followed by the crate version.
+const INDEX_HEADER: &str = "cairo-native-aot-cache v1";
+
+struct CacheEntry {
+    executor: Rc<AotNativeExecutor>,
+    /// The shared library the executor was loaded from, which is needed when exporting.
+    library_path: TempPath,
+    /// Hash of the program, to check that the program is the same one when importing.
+    program_hash: String,
+    /// Hash of the configuration the program was compiled with, to check that it's the same one
+    /// when importing.
+    config_hash: String,
+}
 
 pub struct AotProgramCache<'a, K>
 where
     K: PartialEq + Eq + Hash,
 {
     context: &'a NativeContext,
-    cache: HashMap<K, Rc<AotNativeExecutor>>,
+    cache: HashMap<K, CacheEntry>,
 }
 
 impl<'a, K> AotProgramCache<'a, K>
@@ -31,7 +67,7 @@ where
     }
 
     pub fn get(&self, key: &K) -> Option<Rc<AotNativeExecutor>> {
-        self.cache.get(key).cloned()
+        self.cache.get(key).map(|entry| entry.executor.clone())
     }
 
     pub fn compile_and_insert(
@@ -47,29 +83,258 @@ where
         } = self.context.compile(program, None).expect("should compile");
 
         // Compile module into an object.
-        let object_data = crate::ffi::module_to_object(&module, opt_level).unwrap();
+        let object_data = crate::ffi::module_to_object_with_target(
+            &module,
+            opt_level,
+            self.context.config().target(),
+        )
+        .unwrap();
 
         // Compile object into a shared library.
-        let shared_library_path = tempfile::Builder::new()
-            .prefix("lib")
-            .suffix(SHARED_LIBRARY_EXT)
-            .tempfile()
-            .unwrap()
-            .into_temp_path();
-        crate::ffi::object_to_shared_lib(&object_data, &shared_library_path).unwrap();
+        let library_path = new_library_path().unwrap();
+        crate::ffi::object_to_shared_lib(&object_data, &library_path).unwrap();
 
-        let shared_library = unsafe { Library::new(shared_library_path).unwrap() };
-        let executor = AotNativeExecutor::new(
+        let shared_library = unsafe { Library::new(&library_path).unwrap() };
+        let executor = Rc::new(AotNativeExecutor::from_library(
             shared_library,
             registry,
-            metadata.get::<GasMetadata>().cloned().unwrap(),
-        );
+            metadata,
+        ));
 
-        let executor = Rc::new(executor);
-        self.cache.insert(key, executor.clone());
+        self.cache.insert(
+            key,
+            CacheEntry {
+                executor: executor.clone(),
+                library_path,
+                program_hash: program_hash(program),
+                config_hash: config_hash(self.context, opt_level),
+            },
+        );
 
         executor
     }
+
+    /// Remove a program from the cache.
+    ///
+    /// The compiled code is released once the returned executor (and every other clone obtained
+    /// through [`Self::get`]) is dropped.
+    pub fn remove(&mut self, key: &K) -> Option<Rc<AotNativeExecutor>> {
+        self.cache.remove(key).map(|entry| entry.executor)
+    }
+
+    /// Remove every program from the cache.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Export every program of the cache into a directory, which is created if it doesn't exist.
+    ///
+    /// The directory should be dedicated to the cache, since the shared libraries of previous
+    /// exports which are no longer referenced are deleted. The keys are written using their
+    /// [Display] implementation, which can't contain tabs nor line breaks.
+    pub fn export(&self, path: &Path) -> io::Result<()>
+    where
+        K: Display,
+    {
+        fs::create_dir_all(path)?;
+
+        let mut index = format!("{INDEX_HEADER}\n{}\n", env!("CARGO_PKG_VERSION"));
+        let mut library_names = HashSet::new();
+        for (key, entry) in &self.cache {
+            let key = key.to_string();
+            if key.contains(['\t', '\n', '\r']) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the key {key:?} can't be exported"),
+                ));
+            }
+
+            // Libraries are named after their hash, so the ones which already exist from a previous
+            // export (and may be loaded) don't need to be written again.
+            let library = fs::read(&entry.library_path)?;
+            let library_hash = hash(&library);
+            let library_name = format!("{library_hash}.{SHARED_LIBRARY_EXT}");
+            if !path.join(&library_name).exists() {
+                write_atomically(&path.join(&library_name), &library)?;
+            }
+
+            index.push_str(&format!(
+                "{key}\t{library_name}\t{}\t{library_hash}\t{}\n",
+                entry.program_hash, entry.config_hash,
+            ));
+            library_names.insert(library_name);
+        }
+        write_atomically(&path.join(INDEX_FILE_NAME), index.as_bytes())?;
+
+        for dir_entry in fs::read_dir(path)? {
+            let file_name = dir_entry?.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+
+            if file_name.ends_with(&format!(".{SHARED_LIBRARY_EXT}"))
+                && !library_names.contains(file_name)
+            {
+                fs::remove_file(path.join(file_name))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Import the programs exported into a directory by [export](Self::export), replacing the
+    /// ones with the same key. Returns the number of imported programs.
+    ///
+    /// The program of every key is obtained from `get_program`, and must be the same one it was
+    /// compiled from. Entries whose key has no program, whose program or shared library don't
+    /// match the ones exported, or which weren't compiled with `opt_level` by a context configured
+    /// like this one are skipped. The whole index is skipped if it was exported by another version
+    /// of Cairo Native.
+    pub fn import(
+        &mut self,
+        path: &Path,
+        opt_level: OptLevel,
+        mut get_program: impl FnMut(&K) -> Option<Program>,
+    ) -> io::Result<usize>
+    where
+        K: FromStr,
+    {
+        let index = fs::read_to_string(path.join(INDEX_FILE_NAME))?;
+        let mut lines = index.lines();
+
+        if lines.next() != Some(INDEX_HEADER) || lines.next() != Some(env!("CARGO_PKG_VERSION")) {
+            tracing::warn!("skipping the cache index of another version");
+            return Ok(0);
+        }
+
+        let config_hash = config_hash(self.context, opt_level);
+        let mut count = 0;
+        for line in lines {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [key_str, library_name, program_hash, library_hash, entry_config_hash] = fields[..]
+            else {
+                tracing::warn!("skipping invalid cache entry {line:?}");
+                continue;
+            };
+            let Ok(key) = key_str.parse::<K>() else {
+                tracing::warn!("skipping invalid cache entry {line:?}");
+                continue;
+            };
+
+            // The name of the library is checked to avoid reading files outside of the directory.
+            if !is_hash(library_hash)
+                || library_name != format!("{library_hash}.{SHARED_LIBRARY_EXT}")
+            {
+                tracing::warn!("skipping invalid cache entry {line:?}");
+                continue;
+            }
+
+            if entry_config_hash != config_hash {
+                tracing::debug!("skipping cache entry {key_str} compiled with another config");
+                continue;
+            }
+
+            let Some(program) = get_program(&key) else {
+                tracing::debug!("skipping cache entry {key_str} without program");
+                continue;
+            };
+            if self::program_hash(&program) != program_hash {
+                tracing::warn!("skipping cache entry {key_str} whose program has changed");
+                continue;
+            }
+
+            let library = match fs::read(path.join(library_name)) {
+                Ok(library) if hash(&library) == library_hash => library,
+                _ => {
+                    tracing::warn!(
+                        "skipping cache entry {key_str} whose library is missing or corrupt"
+                    );
+                    continue;
+                }
+            };
+
+            // The library is copied so that later exports into the same directory can't modify
+            // the loaded file.
+            let library_path = new_library_path()?;
+            fs::write(&library_path, library)?;
+
+            let (registry, metadata) = match self.context.executor_metadata(&program) {
+                Ok(x) => x,
+                Err(e) => {
+                    tracing::warn!("skipping cache entry {key_str}: {e}");
+                    continue;
+                }
+            };
+            let shared_library = match unsafe { Library::new(&library_path) } {
+                Ok(x) => x,
+                Err(e) => {
+                    tracing::warn!("skipping cache entry {key_str}: {e}");
+                    continue;
+                }
+            };
+            let executor = AotNativeExecutor::from_library(shared_library, registry, metadata);
+
+            self.cache.insert(
+                key,
+                CacheEntry {
+                    executor: Rc::new(executor),
+                    library_path,
+                    program_hash: program_hash.to_string(),
+                    config_hash: config_hash.clone(),
+                },
+            );
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+fn new_library_path() -> io::Result<TempPath> {
+    Ok(tempfile::Builder::new()
+        .prefix("lib")
+        .suffix(&format!(".{SHARED_LIBRARY_EXT}"))
+        .tempfile()?
+        .into_temp_path())
+}
+
+/// Return the Keccak-256 hash of some data, as a hexadecimal string.
+fn hash(data: &[u8]) -> String {
+    Keccak256::digest(data)
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+fn is_hash(value: &str) -> bool {
+    value.len() == 64
+        && value
+            .bytes()
+            .all(|x| matches!(x, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn program_hash(program: &Program) -> String {
+    hash(program.to_string().as_bytes())
+}
+
+fn config_hash(context: &NativeContext, opt_level: OptLevel) -> String {
+    hash(format!("{opt_level:?}\n{}", context.codegen_description()).as_bytes())
+}
+
+/// Write a file through a temporary one, so that it's never left half-written.
+fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut file = tempfile::NamedTempFile::new_in(path.parent().unwrap_or(Path::new(".")))?;
+    file.write_all(data)?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
 }
 
 impl<'a, K> Debug for AotProgramCache<'a, K>
@@ -84,7 +349,11 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{utils::test::load_cairo, values::JitValue};
+    use crate::{
+        context::{CompilerConfig, PanicMode},
+        utils::test::load_cairo,
+        values::JitValue,
+    };
     use starknet_types_core::felt::Felt;
 
     #[test]
@@ -107,4 +376,84 @@ mod tests {
         // After compiling and inserting the program, we should be able to run it.
         assert_eq!(res.return_value, JitValue::Felt252(Felt::from(42)));
     }
+
+    #[test]
+    fn test_aot_export_import() {
+        let native_context = NativeContext::new();
+        let mut cache = AotProgramCache::new(&native_context);
+
+        let (_, program1) = load_cairo! {
+            fn run_test() -> felt252 {
+                42
+            }
+        };
+        let (_, program2) = load_cairo! {
+            fn run_test() -> felt252 {
+                24
+            }
+        };
+
+        cache.compile_and_insert(1u32, &program1, OptLevel::default());
+        cache.compile_and_insert(2u32, &program2, OptLevel::default());
+
+        let dir = tempfile::tempdir().unwrap();
+        cache.export(dir.path()).unwrap();
+
+        // The program of the key 2 has changed, and there isn't any program for the key 3.
+        let mut imported = AotProgramCache::<u32>::new(&native_context);
+        let count = imported
+            .import(dir.path(), OptLevel::default(), |key| match key {
+                1 => Some(program1.clone()),
+                2 => Some(program1.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(imported.get(&2).is_none());
+
+        let function_id = &program1.funcs.first().expect("should have a function").id;
+        let res = imported
+            .get(&1)
+            .unwrap()
+            .invoke_dynamic(function_id, &[], Some(u128::MAX))
+            .expect("should run");
+        assert_eq!(res.return_value, JitValue::Felt252(Felt::from(42)));
+
+        // Programs compiled with another optimization level or configuration are skipped.
+        let get_program = |key: &u32| match key {
+            1 => Some(program1.clone()),
+            _ => Some(program2.clone()),
+        };
+        let mut imported = AotProgramCache::<u32>::new(&native_context);
+        assert_eq!(
+            imported
+                .import(dir.path(), OptLevel::Aggressive, get_program)
+                .unwrap(),
+            0
+        );
+
+        let other_context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_panic_mode(PanicMode::Error));
+        let mut imported = AotProgramCache::<u32>::new(&other_context);
+        assert_eq!(
+            imported
+                .import(dir.path(), OptLevel::default(), get_program)
+                .unwrap(),
+            0
+        );
+
+        // Corrupt libraries are skipped.
+        for dir_entry in fs::read_dir(dir.path()).unwrap() {
+            let path = dir_entry.unwrap().path();
+            if path.extension().is_some_and(|x| x == SHARED_LIBRARY_EXT) {
+                fs::write(path, b"corrupt").unwrap();
+            }
+        }
+
+        let mut imported = AotProgramCache::<u32>::new(&native_context);
+        let count = imported
+            .import(dir.path(), OptLevel::default(), get_program)
+            .unwrap();
+        assert_eq!(count, 0);
+    }
 }
@@ -53,6 +53,27 @@ where
 
         executor
     }
+
+    /// Remove a program from the cache.
+    ///
+    /// The compiled code is released once the returned executor (and every other clone obtained
+    /// through [`Self::get`]) is dropped.
+    pub fn remove(&mut self, key: &K) -> Option<Rc<JitNativeExecutor<'a>>> {
+        self.cache.remove(key)
+    }
+
+    /// Remove every program from the cache.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
 }
 
 impl<'a, K> Debug for JitProgramCache<'a, K>
@@ -107,4 +128,28 @@ mod test {
 
         assert!(diff_2 < diff_1);
     }
+
+    #[test]
+    fn test_cache_remove() {
+        let (_, program) = load_cairo!(
+            fn main(lhs: felt252, rhs: felt252) -> felt252 {
+                lhs + rhs
+            }
+        );
+
+        let context = NativeContext::new();
+        let mut cache: JitProgramCache<&'static str> = JitProgramCache::new(&context);
+
+        cache.compile_and_insert("program1", &program, Default::default());
+        cache.compile_and_insert("program2", &program, Default::default());
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache.remove(&"program1").is_some());
+        assert!(cache.remove(&"program1").is_none());
+        assert!(cache.get(&"program1").is_none());
+        assert!(cache.get(&"program2").is_some());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
 }
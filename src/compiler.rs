@@ -37,6 +37,24 @@
 //! (`generate_function_name`)[generate_function_name] will generate a new symbol name based on its
 //! function id.
 //!
+//! ## Reproducible output
+//!
+//! Compiling the same program must always generate the same module, byte for byte, so that the
+//! output can be cached and compared. Functions are emitted in the order they're declared in the
+//! program, blocks are ordered by their statement index and the landing block arguments are sorted
+//! by variable id. Hash maps are only used for lookups, never iterated over when emitting code.
+//!
+//! ## Parallel compilation
+//!
+//! Functions only reference each other by their symbol, therefore they can be lowered
+//! independently. [compile_parallel] splits the functions in chunks of [PARALLEL_CHUNK_SIZE],
+//! which are compiled in parallel into their own modules, each one with its own
+//! [fork](MetadataStorage::fork) of the metadata. Once done, the modules are merged in declaration
+//! order, keeping a single copy of the symbols declared by more than one chunk (ex. the runtime
+//! bindings), whose definitions must be the same. The chunks don't depend on the number of threads,
+//! and every symbol is first declared by the same function as in [compile], so the output is the
+//! same as when compiling sequentially.
+//!
 //! ## Tail-recursive functions
 //!
 //! Part of the tail-recursion handling algorithm is implemented here, but tail-recursive functions
@@ -45,11 +63,20 @@
 //! [BFS algorithm]: https://en.wikipedia.org/wiki/Breadth-first_search
 
 use crate::{
+    block_ext::BlockExt,
     debug_info::DebugLocations,
     error::Error,
-    libfuncs::{BranchArg, LibfuncBuilder, LibfuncHelper},
+    ffi::merge_modules,
+    libfuncs::{used_libfuncs, BranchArg, LibfuncBuilder, LibfuncHelper},
     metadata::{
+        determinism::DeterminismMeta,
+        drop_overrides::DropOverridesMeta,
         gas::{GasCost, GasMetadata},
+        int_range::IntRangeMeta,
+        libfunc_plugins::LibfuncPluginsMeta,
+        libfunc_templates::LibfuncTemplatesMeta,
+        range_check_usage::RangeCheckUsageMeta,
+        snapshot_clones::SnapshotClonesMeta,
         tail_recursion::TailRecursionMeta,
         MetadataStorage,
     },
@@ -60,34 +87,41 @@ use bumpalo::Bump;
 use cairo_lang_sierra::{
     edit_state,
     extensions::{
-        core::{CoreLibfunc, CoreType},
+        core::{CoreConcreteLibfunc, CoreLibfunc, CoreType, CoreTypeConcrete},
+        mem::MemConcreteLibfunc,
         ConcreteLibfunc,
     },
-    ids::{ConcreteTypeId, VarId},
+    ids::{ConcreteLibfuncId, ConcreteTypeId, VarId},
     program::{Function, Invocation, Program, Statement, StatementIdx},
     program_registry::ProgramRegistry,
 };
 use itertools::Itertools;
 use melior::{
     dialect::{
-        arith::CmpiPredicate,
+        arith::{self, CmpiPredicate},
         cf, func, index,
         llvm::{self, LoadStoreOptions},
         memref,
     },
     ir::{
-        attribute::{IntegerAttribute, StringAttribute, TypeAttribute},
+        attribute::{FlatSymbolRefAttribute, IntegerAttribute, StringAttribute, TypeAttribute},
         r#type::{FunctionType, IntegerType, MemRefType},
         Attribute, Block, BlockRef, Identifier, Location, Module, Region, Type, Value,
     },
     Context,
 };
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::{
     cell::Cell,
     collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
     ops::Deref,
 };
 
+#[cfg(feature = "with-profiler")]
+use crate::metadata::profiler::ProfilerMeta;
+#[cfg(feature = "with-debug-utils")]
+use crate::metadata::{debug_utils::DebugUtils, execution_snapshots::ExecutionSnapshotsMeta};
+
 /// The [BlockStorage] type is used to map each statement into its own entry block (on the right),
 /// and its landing block (on the left) if required.
 ///
@@ -96,6 +130,61 @@ use std::{
 type BlockStorage<'c, 'a> =
     HashMap<StatementIdx, (Option<(BlockRef<'c, 'a>, Vec<VarId>)>, BlockRef<'c, 'a>)>;
 
+/// Number of functions compiled by each task of [compile_parallel]. It doesn't depend on the number
+/// of threads so that the output is the same on every machine.
+pub const PARALLEL_CHUNK_SIZE: usize = 16;
+
+/// The file the statements without debug info are attributed to in the generated code. Statement
+/// `n` is at line `n + 1` of it, so that the Sierra program can be used as its source.
+pub const SIERRA_STATEMENTS_FILE: &str = "<sierra>";
+
+/// The MLIR context shared by the workers of [compile_parallel].
+///
+/// MLIR contexts are thread-safe as long as multithreading is enabled on them, which is the
+/// default and is never disabled by [initialize_mlir](crate::context::initialize_mlir). Types and
+/// attributes are uniqued behind a lock, so they can be created from any thread.
+struct SharedContext<'c>(&'c Context);
+
+unsafe impl Sync for SharedContext<'_> {}
+
+impl<'c> SharedContext<'c> {
+    fn get(&self) -> &'c Context {
+        self.0
+    }
+}
+
+/// The debug locations shared by the workers of [compile_parallel].
+///
+/// Locations are attributes owned by the (thread-safe) context, and they're only read while
+/// lowering.
+struct SharedDebugLocations<'a, 'c>(Option<&'a DebugLocations<'c>>);
+
+unsafe impl Sync for SharedDebugLocations<'_, '_> {}
+
+impl<'a, 'c> SharedDebugLocations<'a, 'c> {
+    fn get(&self) -> Option<&'a DebugLocations<'c>> {
+        self.0
+    }
+}
+
+/// The metadata given to a worker of [compile_parallel].
+///
+/// The storage isn't [Send] because its entries are type-erased. A fork only holds copies of
+/// plain data (no `Rc` or cells shared with the original) and the metadata of the worker's own
+/// module, and it's only accessed by that worker.
+struct WorkerMetadata(MetadataStorage);
+
+unsafe impl Send for WorkerMetadata {}
+
+/// The module and the metadata built by a worker of [compile_parallel], or its error.
+///
+/// The module belongs to the shared context (check out [SharedContext]). Nothing else references
+/// its operations, which are only accessed again after the worker is done, to merge them. The
+/// metadata is covered by [WorkerMetadata].
+struct WorkerOutput<'c>(Result<(Module<'c>, MetadataStorage), Error>);
+
+unsafe impl Send for WorkerOutput<'_> {}
+
 /// Run the compiler on a program. The compiled program is stored in the MLIR module.
 ///
 /// The generics `TType` and `TLibfunc` contain the information required to generate the MLIR types
@@ -116,7 +205,11 @@ pub fn compile(
     metadata: &mut MetadataStorage,
     debug_info: Option<&DebugLocations>,
 ) -> Result<(), Error> {
+    compile_prologue(context, module, program, registry, metadata)?;
+
     for function in &program.funcs {
+        let _span = tracing::info_span!("compile_function", id = %function.id).entered();
+
         tracing::info!("Compiling function `{}`.", function.id);
         compile_func(
             context,
@@ -133,6 +226,113 @@ pub fn compile(
     Ok(())
 }
 
+/// Like [compile], but the functions are lowered in parallel. Check out the
+/// [module docs](self#parallel-compilation) for more info.
+///
+/// Only the metadata known to [MetadataStorage::fork] is available while lowering the functions.
+pub fn compile_parallel(
+    context: &Context,
+    module: &Module,
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    metadata: &mut MetadataStorage,
+    debug_info: Option<&DebugLocations>,
+) -> Result<(), Error> {
+    compile_parallel_with(
+        context,
+        module,
+        program,
+        registry,
+        metadata,
+        debug_info,
+        MetadataStorage::fork,
+    )
+}
+
+/// Like [compile_parallel], but the metadata of every worker is created by `fork` from the
+/// metadata of the whole program.
+pub(crate) fn compile_parallel_with(
+    context: &Context,
+    module: &Module,
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    metadata: &mut MetadataStorage,
+    debug_info: Option<&DebugLocations>,
+    fork: impl Fn(&MetadataStorage) -> MetadataStorage,
+) -> Result<(), Error> {
+    compile_prologue(context, module, program, registry, metadata)?;
+
+    let chunks = program
+        .funcs
+        .chunks(PARALLEL_CHUNK_SIZE)
+        .map(|functions| (functions, WorkerMetadata(fork(metadata))))
+        .collect::<Vec<_>>();
+    let shared_context = SharedContext(context);
+    let shared_debug_info = SharedDebugLocations(debug_info);
+    let statements = program.statements.as_slice();
+
+    tracing::info!("Compiling {} chunks of functions.", chunks.len());
+    let results = chunks
+        .into_par_iter()
+        .map(|(functions, WorkerMetadata(mut chunk_metadata))| {
+            let context = shared_context.get();
+            let debug_info = shared_debug_info.get();
+            let chunk_module = Module::new(Location::unknown(context));
+
+            let result = functions.iter().try_for_each(|function| {
+                let _span = tracing::info_span!("compile_function", id = %function.id).entered();
+
+                tracing::info!("Compiling function `{}`.", function.id);
+                compile_func(
+                    context,
+                    &chunk_module,
+                    registry,
+                    function,
+                    statements,
+                    &mut chunk_metadata,
+                    debug_info,
+                )
+            });
+
+            WorkerOutput(result.map(|()| (chunk_module, chunk_metadata)))
+        })
+        .collect::<Vec<_>>();
+
+    let (chunk_modules, chunk_metadata): (Vec<_>, Vec<_>) = results
+        .into_iter()
+        .map(|WorkerOutput(result)| result)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .unzip();
+
+    merge_modules(module, chunk_modules)?;
+    for chunk_metadata in chunk_metadata {
+        metadata.join(chunk_metadata);
+    }
+
+    tracing::info!("The program was compiled successfully.");
+    Ok(())
+}
+
+/// Prepare the metadata and generate the code shared by all the functions.
+fn compile_prologue(
+    context: &Context,
+    module: &Module,
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    metadata: &mut MetadataStorage,
+) -> Result<(), Error> {
+    if let Some(plugins) = metadata.get_mut::<LibfuncPluginsMeta>() {
+        plugins.resolve(program);
+    }
+    if metadata.get::<LibfuncTemplatesMeta>().is_some() {
+        tracing::debug!("Generating the libfunc templates.");
+        generate_libfunc_templates(context, module, program, registry, metadata)?;
+    }
+
+    Ok(())
+}
+
 /// Compile a single Sierra function.
 ///
 /// The function accepts a `Function` argument, which provides the function's entry point, signature
@@ -248,9 +448,12 @@ fn compile_func(
             values.push((
                 &param.id,
                 if type_info.is_builtin() && type_info.is_zst(registry) {
+                    let param_ty =
+                        type_info.build(context, module, registry, metadata, &param.ty)?;
                     pre_entry_block
-                        .append_operation(llvm::undef(
-                            type_info.build(context, module, registry, metadata, &param.ty)?,
+                        .append_operation(DeterminismMeta::build_undef(
+                            metadata,
+                            param_ty,
                             Location::unknown(context),
                         ))
                         .result(0)?
@@ -280,6 +483,27 @@ fn compile_func(
         Location::unknown(context),
     ));
 
+    // The location of the statement in the Cairo source, when known, or in the Sierra program.
+    let statement_location = |statement_idx: StatementIdx| {
+        debug_info
+            .and_then(|debug_info| debug_info.statements.get(&statement_idx).copied())
+            .unwrap_or_else(|| {
+                Location::new(context, SIERRA_STATEMENTS_FILE, statement_idx.0 + 1, 0)
+            })
+    };
+
+    #[cfg(feature = "with-profiler")]
+    let profiler_buffer = match metadata.get_mut::<ProfilerMeta>() {
+        Some(profiler) => Some(profiler.build_buffer(
+            context,
+            module,
+            &pre_entry_block,
+            statements.len(),
+            statement_location(function.entry_point),
+        )?),
+        None => None,
+    };
+
     let mut tailrec_storage = Vec::<(Value, BlockRef)>::new();
     foreach_statement_in_function::<_, Error>(
         statements,
@@ -291,6 +515,9 @@ fn compile_func(
                 metadata.remove::<GasCost>();
                 metadata.insert(GasCost(gas_cost));
             }
+            if let Some(int_range) = metadata.get_mut::<IntRangeMeta>() {
+                int_range.set_current_statement(statement_idx);
+            }
 
             let (landing_block, block) = &blocks[&statement_idx];
 
@@ -322,7 +549,7 @@ fn compile_func(
                     Location::name(
                         context,
                         &format!("landing_block(stmt_idx={})", statement_idx),
-                        Location::unknown(context),
+                        statement_location(statement_idx),
                     ),
                 ));
             }
@@ -336,7 +563,76 @@ fn compile_func(
                     let libfunc_name =
                         format!("{}(stmt_idx={})", invocation.libfunc_id, statement_idx);
 
-                    let (state, _) = edit_state::take_args(state, invocation.args.iter())?;
+                    let (state, args) = edit_state::take_args(state, invocation.args.iter())?;
+
+                    let concrete_libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
+
+                    #[cfg(feature = "with-debug-utils")]
+                    if metadata
+                        .get::<ExecutionSnapshotsMeta>()
+                        .is_some_and(|meta| meta.contains(statement_idx))
+                    {
+                        generate_execution_snapshot(
+                            context,
+                            module,
+                            registry,
+                            &pre_entry_block,
+                            block,
+                            metadata,
+                            statement_idx,
+                            concrete_libfunc,
+                            &invocation.args,
+                            &args,
+                        )?;
+                    }
+
+                    #[cfg(feature = "with-profiler")]
+                    if let (Some(profiler), Some(buffer)) =
+                        (metadata.get::<ProfilerMeta>(), profiler_buffer)
+                    {
+                        profiler.measure_statement(
+                            context,
+                            block,
+                            buffer,
+                            statement_idx.0,
+                            Location::name(
+                                context,
+                                &format!("profiler(stmt_idx={})", statement_idx),
+                                statement_location(statement_idx),
+                            ),
+                        )?;
+                    }
+
+                    let overrides = match metadata
+                        .get::<RangeCheckUsageMeta>()
+                        .and_then(|meta| meta.usage(concrete_libfunc))
+                    {
+                        Some(usage) => generate_range_check_overrides(
+                            context,
+                            registry,
+                            block,
+                            concrete_libfunc,
+                            &args,
+                            &usage,
+                        )?,
+                        None => Vec::new(),
+                    };
+
+                    let result_types = concrete_libfunc
+                        .branch_signatures()
+                        .iter()
+                        .map(|branch| {
+                            branch
+                                .vars
+                                .iter()
+                                .map(|var| {
+                                    registry
+                                        .get_type(&var.ty)?
+                                        .build(context, module, registry, metadata, &var.ty)
+                                })
+                                .collect::<Result<Vec<_>, _>>()
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
 
                     let helper = LibfuncHelper {
                         module,
@@ -356,9 +652,10 @@ fn compile_func(
                             .iter()
                             .map(|x| vec![Cell::new(None); x.results.len()])
                             .collect::<Vec<_>>(),
+                        result_types,
+                        overrides,
                     };
 
-                    let concrete_libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
                     if let Some(target) = concrete_libfunc.is_function_call() {
                         if target == &function.id && state.is_empty() {
                             // TODO: Defer insertions until after the recursion has been confirmed
@@ -367,7 +664,7 @@ fn compile_func(
                             let location = Location::name(
                                 context,
                                 &format!("recursion_counter({})", libfunc_name),
-                                Location::unknown(context),
+                                statement_location(statement_idx),
                             );
                             let op0 = pre_entry_block.insert_operation(
                                 0,
@@ -398,28 +695,55 @@ fn compile_func(
                                 ),
                             );
 
+                            let mut tailrec_meta =
+                                TailRecursionMeta::new(op0.result(0)?.into(), &entry_block);
+                            tailrec_meta.set_forwards_results(forwards_results_to_return(
+                                registry,
+                                statements,
+                                statement_idx,
+                                invocation,
+                            )?);
                             metadata
-                                .insert(TailRecursionMeta::new(op0.result(0)?.into(), &entry_block))
+                                .insert(tailrec_meta)
                                 .expect("should not have this metadata inserted yet");
                         }
                     }
 
-                    concrete_libfunc.build(
-                        context,
-                        registry,
-                        block,
-                        Location::name(
+                    let location =
+                        Location::name(context, &libfunc_name, statement_location(statement_idx));
+                    let plugin = metadata
+                        .get::<LibfuncPluginsMeta>()
+                        .and_then(|meta| meta.plugin(&invocation.libfunc_id));
+                    // Range check overrides are statement-specific, so they can't be applied to a
+                    // template.
+                    let template = metadata
+                        .get::<LibfuncTemplatesMeta>()
+                        .and_then(|meta| meta.template(&invocation.libfunc_id))
+                        .filter(|_| helper.overrides.is_empty())
+                        .map(str::to_string);
+                    match (plugin, template) {
+                        (Some(plugin), _) => plugin.build(
                             context,
-                            &libfunc_name,
-                            debug_info
-                                .and_then(|debug_info| {
-                                    debug_info.statements.get(&statement_idx).copied()
-                                })
-                                .unwrap_or_else(|| Location::unknown(context)),
-                        ),
-                        &helper,
-                        metadata,
-                    )?;
+                            registry,
+                            block,
+                            location,
+                            &helper,
+                            metadata,
+                            concrete_libfunc,
+                        )?,
+                        (None, Some(symbol)) => generate_libfunc_template_call(
+                            context,
+                            registry,
+                            block,
+                            location,
+                            &helper,
+                            metadata,
+                            concrete_libfunc,
+                            &symbol,
+                        )?,
+                        (None, None) => concrete_libfunc
+                            .build(context, registry, block, location, &helper, metadata)?,
+                    }
                     assert!(block.terminator().is_some());
 
                     if let Some(tailrec_meta) = metadata.remove::<TailRecursionMeta>() {
@@ -459,7 +783,7 @@ fn compile_func(
                     let location = Location::name(
                         context,
                         &format!("return(stmt_idx={})", statement_idx),
-                        Location::unknown(context),
+                        statement_location(statement_idx),
                     );
 
                     let (_, mut values) = edit_state::take_args(state, var_ids.iter())?;
@@ -469,7 +793,7 @@ fn compile_func(
                         let location = Location::name(
                             context,
                             &format!("return(stmt_idx={}, tail_recursion)", statement_idx),
-                            Location::unknown(context),
+                            statement_location(statement_idx),
                         );
                         // Perform tail recursion.
                         for counter_idx in tailrec_state.into_values() {
@@ -670,7 +994,10 @@ fn compile_func(
                 Attribute::unit(context),
             ),
         ],
-        Location::unknown(context),
+        // The debug info scope of the function is created from its location.
+        debug_info
+            .and_then(|debug_info| debug_info.funcs.get(&function.id).copied())
+            .unwrap_or_else(|| statement_location(function.entry_point)),
     ));
 
     tracing::debug!("Done generating function {}.", function.id);
@@ -885,6 +1212,57 @@ fn extract_types<'c: 'a, 'a>(
     })
 }
 
+/// Check whether the results of the function call at `statement_idx` are returned unchanged, which
+/// is the pattern generated by Cairo loops. Only moves and libfuncs that don't generate any code are
+/// allowed between the call and the return statement.
+pub(crate) fn forwards_results_to_return(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    statements: &[Statement],
+    statement_idx: StatementIdx,
+    invocation: &Invocation,
+) -> Result<bool, Error> {
+    let [branch] = invocation.branches.as_slice() else {
+        return Ok(false);
+    };
+
+    let mut vars = branch.results.clone();
+    let mut statement_idx = statement_idx.next(&branch.target);
+
+    // Bounded by the number of statements to avoid looping forever on cyclic jumps.
+    for _ in 0..statements.len() {
+        let invocation = match &statements[statement_idx.0] {
+            Statement::Invocation(invocation) => invocation,
+            Statement::Return(ret_vars) => return Ok(ret_vars == &vars),
+        };
+        let [branch] = invocation.branches.as_slice() else {
+            return Ok(false);
+        };
+
+        match registry.get_libfunc(&invocation.libfunc_id)? {
+            CoreConcreteLibfunc::Mem(
+                MemConcreteLibfunc::StoreTemp(_) | MemConcreteLibfunc::Rename(_),
+            ) => {
+                let ([arg], [result]) = (invocation.args.as_slice(), branch.results.as_slice())
+                else {
+                    return Ok(false);
+                };
+                let Some(var) = vars.iter_mut().find(|var| *var == arg) else {
+                    return Ok(false);
+                };
+                *var = result.clone();
+            }
+            CoreConcreteLibfunc::ApTracking(_)
+            | CoreConcreteLibfunc::BranchAlign(_)
+            | CoreConcreteLibfunc::UnconditionalJump(_) => {}
+            _ => return Ok(false),
+        }
+
+        statement_idx = statement_idx.next(&branch.target);
+    }
+
+    Ok(false)
+}
+
 fn foreach_statement_in_function<S, E>(
     statements: &[Statement],
     entry_point: StatementIdx,
@@ -925,6 +1303,313 @@ where
     Ok(())
 }
 
+/// Compute the range check returned by every branch of a libfunc from the usages given by
+/// [RangeCheckUsageMeta].
+///
+/// Returns the index of the range check within the branch's results along with its new value, or
+/// `None` for the branches that don't return one.
+fn generate_range_check_overrides<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    block: &'this Block<'ctx>,
+    libfunc: &CoreConcreteLibfunc,
+    args: &[Value<'ctx, 'this>],
+    usage: &[u64],
+) -> Result<Vec<Option<(usize, Value<'ctx, 'this>)>>, Error>
+where
+    'this: 'ctx,
+{
+    let is_range_check = |ty: &ConcreteTypeId| {
+        registry
+            .get_type(ty)
+            .map(|ty| matches!(ty, CoreTypeConcrete::RangeCheck(_)))
+    };
+
+    let mut range_check = None;
+    for (param, arg) in libfunc.param_signatures().iter().zip(args) {
+        if is_range_check(&param.ty)? {
+            range_check = Some(*arg);
+            break;
+        }
+    }
+    let Some(range_check) = range_check else {
+        return Ok(Vec::new());
+    };
+
+    let location = Location::unknown(context);
+    libfunc
+        .branch_signatures()
+        .iter()
+        .zip(usage)
+        .map(|(branch, usage)| {
+            for (idx, var) in branch.vars.iter().enumerate() {
+                if is_range_check(&var.ty)? {
+                    let usage = block.const_int(context, location, *usage, 64)?;
+                    let value =
+                        block.append_op_result(arith::addi(range_check, usage, location))?;
+                    return Ok(Some((idx, value)));
+                }
+            }
+
+            Ok(None)
+        })
+        .collect()
+}
+
+/// Report the arguments of a statement to the host as an
+/// [execution snapshot](crate::execution_snapshots).
+///
+/// Every value is copied into a stack slot so that the host can decode it without taking its
+/// ownership. Zero-sized values are skipped.
+#[cfg(feature = "with-debug-utils")]
+#[allow(clippy::too_many_arguments)]
+fn generate_execution_snapshot<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    init_block: &Block<'ctx>,
+    block: &Block<'ctx>,
+    metadata: &mut MetadataStorage,
+    statement_idx: StatementIdx,
+    libfunc: &CoreConcreteLibfunc,
+    var_ids: &[VarId],
+    args: &[Value<'ctx, '_>],
+) -> Result<(), Error> {
+    let location = Location::name(
+        context,
+        &format!("execution_snapshot(stmt_idx={})", statement_idx),
+        Location::unknown(context),
+    );
+
+    let mut values = Vec::with_capacity(args.len());
+    for ((var_id, param), arg) in var_ids.iter().zip(libfunc.param_signatures()).zip(args) {
+        let type_info = registry.get_type(&param.ty)?;
+        if type_info.is_zst(registry) {
+            continue;
+        }
+
+        let ptr = init_block.alloca1(
+            context,
+            location,
+            type_info.build(context, module, registry, metadata, &param.ty)?,
+            type_info.layout(registry)?.align(),
+        )?;
+        block.store(context, location, ptr, *arg)?;
+
+        values.push((var_id.id, param.ty.id, ptr));
+    }
+
+    let debug_utils = metadata
+        .get_mut::<DebugUtils>()
+        .expect("Debug utilities should be available.");
+    debug_utils.snapshot_begin(context, module, block, statement_idx.0, location)?;
+    for (var_id, type_id, ptr) in values {
+        debug_utils.snapshot_value(context, module, block, var_id, type_id, ptr, location)?;
+    }
+
+    Ok(())
+}
+
+/// Build the template functions of the registered libfuncs used by the program. Check out the
+/// [libfunc templates metadata](crate::metadata::libfunc_templates) for more info.
+///
+/// The templates are generated in declaration order so that the output stays reproducible.
+fn generate_libfunc_templates(
+    context: &Context,
+    module: &Module,
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    metadata: &mut MetadataStorage,
+) -> Result<(), Error> {
+    for decl in used_libfuncs(program) {
+        // Libfuncs built by a plugin are left to it.
+        let is_registered = metadata
+            .get::<LibfuncTemplatesMeta>()
+            .is_some_and(|meta| meta.is_registered(&decl.long_id.generic_id))
+            && !metadata
+                .get::<LibfuncPluginsMeta>()
+                .is_some_and(|meta| meta.plugin(&decl.id).is_some());
+        if is_registered {
+            generate_libfunc_template(context, module, registry, metadata, &decl.id)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the template function of a libfunc, unless it can't be templated.
+///
+/// The template's entry block is used as the libfunc's init block, and its only branch returns
+/// the results.
+fn generate_libfunc_template(
+    context: &Context,
+    module: &Module,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    metadata: &mut MetadataStorage,
+    libfunc_id: &ConcreteLibfuncId,
+) -> Result<(), Error> {
+    let libfunc = registry.get_libfunc(libfunc_id)?;
+    if libfunc.is_function_call().is_some()
+        || matches!(libfunc, CoreConcreteLibfunc::Gas(_))
+        || libfunc.branch_signatures().len() != 1
+    {
+        return Ok(());
+    }
+
+    let param_types = libfunc
+        .param_signatures()
+        .iter()
+        .map(|param| {
+            registry
+                .get_type(&param.ty)?
+                .build(context, module, registry, metadata, &param.ty)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let result_types = libfunc.branch_signatures()[0]
+        .vars
+        .iter()
+        .map(|var| {
+            registry
+                .get_type(&var.ty)?
+                .build(context, module, registry, metadata, &var.ty)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The types have been built already, so their drop and clone implementations (if any) are
+    // registered by now.
+    let is_trivial = match libfunc {
+        CoreConcreteLibfunc::Drop(info) => !metadata
+            .get::<DropOverridesMeta>()
+            .is_some_and(|meta| meta.is_overridden(&info.signature.param_signatures[0].ty)),
+        CoreConcreteLibfunc::Dup(info) => metadata
+            .get::<SnapshotClonesMeta>()
+            .and_then(|meta| meta.wrap_invoke(&info.signature.param_signatures[0].ty))
+            .is_none(),
+        _ => false,
+    };
+    if is_trivial {
+        return Ok(());
+    }
+
+    let symbol = format!("cairo_native__libfunc_template__{}", libfunc_id.id);
+    let location = Location::name(
+        context,
+        &format!("libfunc_template({libfunc_id})"),
+        // Templates are shared by many statements, so they're not attributed to any of them.
+        Location::new(context, SIERRA_STATEMENTS_FILE, 0, 0),
+    );
+
+    let region = Region::new();
+    let blocks_arena = Bump::new();
+
+    let param_args = param_types
+        .iter()
+        .map(|ty| (*ty, location))
+        .collect::<Vec<_>>();
+    let init_block = region.append_block(Block::new(&param_args));
+    let entry_block = region.append_block(Block::new(&param_args));
+    let return_block = region.append_block(Block::new(
+        &result_types
+            .iter()
+            .map(|ty| (*ty, location))
+            .collect::<Vec<_>>(),
+    ));
+
+    let helper = LibfuncHelper {
+        module,
+        init_block: &init_block,
+        region: &region,
+        blocks_arena: &blocks_arena,
+        last_block: Cell::new(&entry_block),
+        branches: vec![(
+            return_block.deref(),
+            (0..result_types.len()).map(BranchArg::Returned).collect(),
+        )],
+        results: vec![vec![Cell::new(None); result_types.len()]],
+        result_types: vec![result_types.clone()],
+        overrides: Vec::new(),
+    };
+    libfunc.build(context, registry, &entry_block, location, &helper, metadata)?;
+
+    // The libfunc may have inserted operations into the init block, so it's terminated last.
+    init_block.append_operation(cf::br(
+        &entry_block,
+        &(0..param_types.len())
+            .map(|i| init_block.argument(i).map(Into::into))
+            .collect::<Result<Vec<_>, _>>()?,
+        location,
+    ));
+    return_block.append_operation(func::r#return(
+        &(0..result_types.len())
+            .map(|i| return_block.argument(i).map(Into::into))
+            .collect::<Result<Vec<_>, _>>()?,
+        location,
+    ));
+
+    module.body().append_operation(func::func(
+        context,
+        StringAttribute::new(context, &symbol),
+        TypeAttribute::new(FunctionType::new(context, &param_types, &result_types).into()),
+        region,
+        &[
+            (
+                Identifier::new(context, "sym_visibility"),
+                StringAttribute::new(context, "private").into(),
+            ),
+            (
+                Identifier::new(context, "llvm.linkage"),
+                Attribute::parse(context, "#llvm.linkage<internal>").unwrap(),
+            ),
+        ],
+        location,
+    ));
+
+    metadata
+        .get_mut::<LibfuncTemplatesMeta>()
+        .expect("libfunc templates metadata should be present")
+        .insert_template(libfunc_id.clone(), symbol);
+    Ok(())
+}
+
+/// Invoke a libfunc through its template function instead of building it inline.
+#[allow(clippy::too_many_arguments)]
+fn generate_libfunc_template_call<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    libfunc: &CoreConcreteLibfunc,
+    symbol: &str,
+) -> Result<(), Error> {
+    let result_types = libfunc.branch_signatures()[0]
+        .vars
+        .iter()
+        .map(|var| {
+            registry
+                .get_type(&var.ty)?
+                .build(context, helper, registry, metadata, &var.ty)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let call = block.append_operation(func::call(
+        context,
+        FlatSymbolRefAttribute::new(context, symbol),
+        &(0..block.argument_count())
+            .map(|i| block.argument(i).map(Into::into))
+            .collect::<Result<Vec<_>, _>>()?,
+        &result_types,
+        location,
+    ));
+    let results = (0..result_types.len())
+        .map(|i| call.result(i).map(Into::into))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    block.append_operation(helper.br(0, &results, location));
+    Ok(())
+}
+
 fn generate_branching_targets<'ctx, 'this, 'a>(
     blocks: &'this BlockStorage<'ctx, 'this>,
     statements: &'this [Statement],
@@ -981,3 +1666,268 @@ where
         })
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{CompilationLimits, CompilerConfig, NativeContext},
+        executor::JitNativeExecutor,
+        metadata::libfunc_templates::DEFAULT_LIBFUNC_TEMPLATES,
+        utils::{find_function_id, test::load_cairo},
+        values::JitValue,
+        OptLevel,
+    };
+    use cairo_lang_sierra::ProgramParser;
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        // `countdown` returns its recursive call's results unchanged, like Cairo loops do, while
+        // `count` needs them to be unwound.
+        static ref RECURSIVE_PROGRAM: Program = ProgramParser::new()
+            .parse(
+                "type felt252 = felt252;
+                type NonZeroFelt252 = NonZero<felt252>;
+
+                libfunc branch_align = branch_align;
+                libfunc call_count = function_call<user@count>;
+                libfunc call_countdown = function_call<user@countdown>;
+                libfunc drop_nz_felt252 = drop<NonZeroFelt252>;
+                libfunc dup_felt252 = dup<felt252>;
+                libfunc felt252_add = felt252_add;
+                libfunc felt252_const_1 = felt252_const<1>;
+                libfunc felt252_is_zero = felt252_is_zero;
+                libfunc felt252_sub = felt252_sub;
+                libfunc rename_felt252 = rename<felt252>;
+                libfunc store_temp_felt252 = store_temp<felt252>;
+
+                dup_felt252([0]) -> ([0], [1]);
+                felt252_is_zero([1]) { fallthrough() 4([2]) };
+                branch_align() -> ();
+                return([0]);
+                branch_align() -> ();
+                drop_nz_felt252([2]) -> ();
+                felt252_const_1() -> ([3]);
+                felt252_sub([0], [3]) -> ([4]);
+                store_temp_felt252([4]) -> ([4]);
+                call_countdown([4]) -> ([5]);
+                rename_felt252([5]) -> ([6]);
+                store_temp_felt252([6]) -> ([6]);
+                return([6]);
+
+                dup_felt252([0]) -> ([0], [1]);
+                felt252_is_zero([1]) { fallthrough() 17([2]) };
+                branch_align() -> ();
+                return([0]);
+                branch_align() -> ();
+                drop_nz_felt252([2]) -> ();
+                felt252_const_1() -> ([3]);
+                felt252_sub([0], [3]) -> ([4]);
+                store_temp_felt252([4]) -> ([4]);
+                call_count([4]) -> ([5]);
+                felt252_const_1() -> ([6]);
+                felt252_add([5], [6]) -> ([7]);
+                store_temp_felt252([7]) -> ([7]);
+                return([7]);
+
+                countdown@0([0]: felt252) -> (felt252);
+                count@13([0]: felt252) -> (felt252);",
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn detect_forwarded_results() {
+        let program = &*RECURSIVE_PROGRAM;
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program).unwrap();
+
+        let forwards_results = |idx| {
+            let Statement::Invocation(invocation) = &program.statements[idx] else {
+                panic!("statement {idx} should be an invocation");
+            };
+            forwards_results_to_return(
+                &registry,
+                &program.statements,
+                StatementIdx(idx),
+                invocation,
+            )
+            .unwrap()
+        };
+
+        assert!(forwards_results(9));
+        assert!(!forwards_results(22));
+    }
+
+    #[test]
+    fn run_tail_recursion() {
+        let program = &*RECURSIVE_PROGRAM;
+
+        let module = NativeContext::new().compile(program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let run = |func: usize| {
+            executor
+                .invoke_dynamic(
+                    &program.funcs[func].id,
+                    &[JitValue::Felt252(100000.into())],
+                    None,
+                )
+                .unwrap()
+                .return_value
+        };
+
+        assert_eq!(run(0), JitValue::Felt252(0.into()));
+        assert_eq!(run(1), JitValue::Felt252(100000.into()));
+    }
+
+    #[test]
+    fn run_libfunc_templates() {
+        let program = load_cairo! {
+            fn run_test() -> u32 {
+                let values = array![1_u32, 2_u32, 3_u32];
+                let other = array![4_u32, 5_u32];
+                let snapshot = @values;
+                *snapshot.at(0) + *snapshot.at(2) + other.len()
+            }
+        };
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program.1).unwrap();
+        let libfuncs = used_libfuncs(&program.1);
+        assert!(libfuncs
+            .iter()
+            .all(|decl| program.1.libfunc_declarations.contains(*decl)));
+        let drop_array = libfuncs
+            .iter()
+            .find(|decl| match registry.get_libfunc(&decl.id).unwrap() {
+                CoreConcreteLibfunc::Drop(info) => matches!(
+                    registry.get_type(&info.signature.param_signatures[0].ty),
+                    Ok(CoreTypeConcrete::Array(_))
+                ),
+                _ => false,
+            })
+            .expect("the program should drop an array");
+
+        let context = NativeContext::new().with_config(
+            CompilerConfig::new().with_libfunc_templates(DEFAULT_LIBFUNC_TEMPLATES.iter().copied()),
+        );
+        let module = context.compile(&program.1, None).unwrap();
+        assert!(module
+            .get_metadata::<LibfuncTemplatesMeta>()
+            .and_then(|meta| meta.template(&drop_array.id))
+            .is_some());
+
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[],
+                None,
+            )
+            .unwrap();
+        assert_eq!(result.return_value, JitValue::Uint32(6));
+    }
+
+    #[test]
+    fn run_parallel_compilation() {
+        // Every function `f{i}` returns `f{i - 1}(x) + 1`, so that the calls cross the chunks.
+        let num_functions = 3 * PARALLEL_CHUNK_SIZE + 1;
+        let mut source = String::from(
+            "type felt252 = felt252;
+
+            libfunc felt252_add = felt252_add;
+            libfunc felt252_const_1 = felt252_const<1>;
+            libfunc store_temp_felt252 = store_temp<felt252>;
+            ",
+        );
+        for i in 1..num_functions {
+            source += &format!("libfunc call_f{0} = function_call<user@f{0}>;\n", i - 1);
+        }
+        source += "store_temp_felt252([0]) -> ([0]);\nreturn([0]);\n";
+        for i in 1..num_functions {
+            source += &format!(
+                "store_temp_felt252([0]) -> ([0]);
+                call_f{}([0]) -> ([1]);
+                felt252_const_1() -> ([2]);
+                felt252_add([1], [2]) -> ([3]);
+                store_temp_felt252([3]) -> ([3]);
+                return([3]);
+                ",
+                i - 1
+            );
+        }
+        source += "f0@0([0]: felt252) -> (felt252);\n";
+        for i in 1..num_functions {
+            source += &format!("f{i}@{}([0]: felt252) -> (felt252);\n", 2 + 6 * (i - 1));
+        }
+        let program = ProgramParser::new().parse(&source).unwrap();
+
+        let run = |parallel_compilation| {
+            let context = NativeContext::new()
+                .with_config(CompilerConfig::new().with_parallel_compilation(parallel_compilation));
+            let module = context.compile(&program, None).unwrap();
+            let mlir = module.module().as_operation().to_string();
+
+            let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+            let result = executor
+                .invoke_dynamic(
+                    &program.funcs[num_functions - 1].id,
+                    &[JitValue::Felt252(5.into())],
+                    None,
+                )
+                .unwrap();
+            (mlir, result.return_value)
+        };
+
+        let expected = JitValue::Felt252((5 + num_functions as u64 - 1).into());
+        let (mlir, result) = run(false);
+        assert_eq!(result, expected);
+
+        // The output doesn't depend on how the chunks are scheduled, and it's the same as when
+        // compiling sequentially.
+        for _ in 0..2 {
+            assert_eq!(run(true), (mlir.clone(), expected.clone()));
+        }
+    }
+
+    #[test]
+    fn run_parallel_compilation_overflow_checks() {
+        // Every struct nests 16 copies of the previous one, so `S6` takes 8 GiB and the payload of
+        // the enum doesn't fit in the `u32` length of an LLVM array.
+        let mut source = format!(
+            "type felt252 = felt252;\ntype S0 = Struct<ut@S0{}>;\n",
+            ", felt252".repeat(16)
+        );
+        for i in 1..=6 {
+            let fields = format!(", S{}", i - 1).repeat(16);
+            source.push_str(&format!("type S{i} = Struct<ut@S{i}{fields}>;\n"));
+        }
+        source.push_str(
+            "type E = Enum<ut@E, S6, felt252>;
+
+            libfunc drop_e = drop<E>;
+
+            drop_e([0]) -> ();
+            return();
+
+            f@0([0]: E) -> ();",
+        );
+        let program = ProgramParser::new().parse(&source).unwrap();
+
+        for parallel_compilation in [false, true] {
+            let context = NativeContext::new().with_config(
+                CompilerConfig::new()
+                    .with_limits(CompilationLimits {
+                        max_type_size: None,
+                        ..Default::default()
+                    })
+                    .with_overflow_checks(true)
+                    .with_parallel_compilation(parallel_compilation),
+            );
+
+            let result = context.compile(&program, None);
+            assert!(
+                matches!(result, Err(Error::IntegerOverflow(_))),
+                "parallel_compilation = {parallel_compilation}"
+            );
+        }
+    }
+}
@@ -1,22 +1,42 @@
-use std::sync::OnceLock;
+use std::{
+    alloc::Layout,
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::{Arc, OnceLock},
+};
 
 use crate::{
     debug_info::DebugLocations,
+    entry_points::ContractEntryPoints,
     error::Error,
-    ffi::{get_data_layout_rep, get_target_triple},
+    ffi::get_data_layout_rep,
+    libfuncs::LibfuncBuilder,
     metadata::{
+        determinism::DeterminismMeta,
         gas::{GasMetadata, MetadataComputationConfig},
+        int_range::IntRangeMeta,
+        libfunc_plugins::LibfuncPluginsMeta,
+        libfunc_templates::LibfuncTemplatesMeta,
+        overflow_checks::OverflowChecksMeta,
+        panic::PanicMeta,
+        range_check_usage::RangeCheckUsageMeta,
+        recursion::RecursionMeta,
         runtime_bindings::RuntimeBindingsMeta,
         MetadataStorage,
     },
     module::NativeModule,
+    plugin::LibfuncPlugin,
+    types::{build_layout, inline_members},
     utils::run_pass_manager,
+    OptLevel, TargetConfig,
 };
 use cairo_lang_sierra::{
     extensions::core::{CoreLibfunc, CoreType},
-    program::Program,
+    ids::{ConcreteTypeId, FunctionId},
+    program::{Function, Program, Statement},
     program_registry::ProgramRegistry,
 };
+use cairo_lang_starknet_classes::contract_class::ContractClass;
 use llvm_sys::target::{
     LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos, LLVM_InitializeAllTargetMCs,
     LLVM_InitializeAllTargets,
@@ -26,21 +46,169 @@ use melior::{
     ir::{
         attribute::StringAttribute,
         operation::{OperationBuilder, OperationPrintingFlags},
-        Block, Identifier, Location, Module, Region,
+        Block, BlockRef, Identifier, Location, Module, Region,
     },
     utility::{register_all_dialects, register_all_llvm_translations, register_all_passes},
     Context,
 };
+use tracing::instrument;
+
+#[cfg(feature = "with-debug-utils")]
+use crate::metadata::execution_snapshots::ExecutionSnapshotsMeta;
+#[cfg(feature = "with-profiler")]
+use crate::metadata::profiler::ProfilerMeta;
 
 /// Context of IRs, dialects and passes for Cairo programs compilation.
 #[derive(Debug, Eq, PartialEq)]
 pub struct NativeContext {
     context: Context,
+    config: CompilerConfig,
+    plugins: LibfuncPluginsMeta,
+}
+
+/// Options used when compiling programs.
+///
+/// New options are added as builder methods so that adding them doesn't break existing code.
+///
+/// ```
+/// use cairo_native::{context::{CompilerConfig, NativeContext}, OptLevel};
+///
+/// let context = NativeContext::new().with_config(
+///     CompilerConfig::new()
+///         .with_opt_level(OptLevel::Aggressive)
+///         .with_infinite_gas(true),
+/// );
+/// assert_eq!(context.config().opt_level(), OptLevel::Aggressive);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompilerConfig {
+    opt_level: OptLevel,
+    limits: CompilationLimits,
+    track_range_checks: bool,
+    overflow_checks: bool,
+    infinite_gas: bool,
+    panic_mode: PanicMode,
+    determinism_mode: DeterminismMode,
+    libfunc_templates: BTreeSet<String>,
+    target: TargetConfig,
+    parallel_compilation: bool,
+    debug_info: bool,
+    #[cfg(feature = "with-debug-utils")]
+    snapshot_statements: std::collections::BTreeSet<usize>,
+    #[cfg(feature = "with-profiler")]
+    profiler: bool,
+}
+
+/// What the compiled code does when it reaches an unrecoverable error.
+///
+/// Cairo panics (ex. `panic_with_felt252`) are not affected by this setting: they're regular return
+/// values and always end up in the [ExecutionResult](crate::execution_result::ExecutionResult) of
+/// the invocation. Unrecoverable errors are those which can't happen in a valid program, such as an
+/// invalid enum tag or a failed memory allocation, and end the whole process by default.
+///
+/// The generated code has no unwind tables, so an error can't unwind out of it. Instead, the
+/// [Error](PanicMode::Error) and [Hook](PanicMode::Hook) modes jump straight back into the
+/// executor, like `longjmp` does, discarding the native frames of the invocation. Memory owned by
+/// the interrupted invocation is reclaimed by the executor afterwards.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PanicMode {
+    /// Print a message describing the error to stderr, then abort.
+    #[default]
+    Abort,
+    /// Execute a trap instruction without printing anything. The generated code is smaller, and
+    /// debuggers stop right at the failing instruction.
+    Trap,
+    /// Stop the invocation and return an
+    /// [UnrecoverableError](crate::error::Error::UnrecoverableError) from the executor.
+    Error,
+    /// Like [Error](PanicMode::Error), but call the hook registered with
+    /// [set_panic_hook](crate::executor::set_panic_hook) first, while the native frames of the
+    /// invocation are still there (ex. to inspect them from a debugger).
+    Hook,
+}
+
+/// How strictly the generated code is checked for nondeterministic behavior. Check out the
+/// [determinism module](crate::determinism) for more info.
+///
+/// Both the audit and the strict modes harden the generated code (ex. by zeroing the unused bytes
+/// of the values), which makes it slightly slower.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeterminismMode {
+    /// Don't check anything.
+    #[default]
+    Off,
+    /// Report every nondeterministic behavior found as a warning, but carry on.
+    Audit,
+    /// Fail the compilation or the execution when a nondeterministic behavior is found.
+    Strict,
+}
+
+/// Limits enforced while compiling a program, to guard against programs that explode during
+/// lowering.
+///
+/// Every limit is checked before running the MLIR passes and the LLVM codegen, which is where most
+/// of the compilation time goes. A `None` disables the check.
+///
+/// The limits on the types are enabled by default, since a program with deeply nested or huge types
+/// can crash the compiler. The rest are disabled by default. The depth of the types can't be
+/// unlimited at all, since the compiler builds the types recursively.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompilationLimits {
+    /// Maximum number of Sierra functions in the program.
+    pub max_functions: Option<usize>,
+    /// Maximum number of Sierra statements in the program.
+    pub max_statements: Option<usize>,
+    /// Maximum number of MLIR operations generated for the program, counted right after lowering
+    /// the Sierra program and before running any pass.
+    ///
+    /// This doesn't limit the size of the compiled code, which depends on the optimizations and the
+    /// target. The operation count only tends to grow with it.
+    pub max_mlir_operations: Option<usize>,
+    /// Maximum nesting depth of the types stored inline within other types (ex. structs within
+    /// structs). Types behind a pointer (ex. boxes or arrays) don't add to the depth.
+    ///
+    /// Values above [MAX_TYPE_DEPTH] are treated as [MAX_TYPE_DEPTH].
+    pub max_type_depth: usize,
+    /// Maximum size of a type, in bytes.
+    pub max_type_size: Option<usize>,
+}
+
+/// The default [CompilationLimits::max_type_depth].
+pub const DEFAULT_MAX_TYPE_DEPTH: usize = 128;
+/// The highest [CompilationLimits::max_type_depth] allowed. Deeper types could overflow the stack
+/// of the compiler while their MLIR types and layouts are built.
+pub const MAX_TYPE_DEPTH: usize = 256;
+/// The default [CompilationLimits::max_type_size].
+pub const DEFAULT_MAX_TYPE_SIZE: usize = 1 << 20;
+
+impl Default for CompilationLimits {
+    fn default() -> Self {
+        Self {
+            max_functions: None,
+            max_statements: None,
+            max_mlir_operations: None,
+            max_type_depth: DEFAULT_MAX_TYPE_DEPTH,
+            max_type_size: Some(DEFAULT_MAX_TYPE_SIZE),
+        }
+    }
 }
 
 unsafe impl Send for NativeContext {}
 unsafe impl Sync for NativeContext {}
 
+/// The metadata of a program which doesn't depend on the functions being lowered, so that callers
+/// compiling the same program many times can compute it only once.
+#[derive(Clone, Debug)]
+pub(crate) struct ProgramMetadata {
+    pub gas: GasMetadata,
+    pub int_range: IntRangeMeta,
+    pub recursion: RecursionMeta,
+}
+
 impl Default for NativeContext {
     fn default() -> Self {
         Self::new()
@@ -50,19 +218,91 @@ impl Default for NativeContext {
 impl NativeContext {
     pub fn new() -> Self {
         let context = initialize_mlir();
-        Self { context }
+        Self {
+            context,
+            config: CompilerConfig::default(),
+            plugins: LibfuncPluginsMeta::default(),
+        }
+    }
+
+    /// Compile every program with the given configuration.
+    pub fn with_config(mut self, config: CompilerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Build the libfuncs registered by a plugin with it, on every program compiled with this
+    /// context. Check out the [plugin module](crate::plugin) for more info.
+    ///
+    /// Fails if the plugin is not compatible with the current plugin API, or if any of its libfuncs
+    /// is already built by another plugin.
+    pub fn with_libfunc_plugin(
+        mut self,
+        plugin: impl LibfuncPlugin + 'static,
+    ) -> Result<Self, Error> {
+        self.plugins.register(Arc::new(plugin))?;
+        Ok(self)
     }
 
     pub fn context(&self) -> &Context {
         &self.context
     }
 
+    pub fn config(&self) -> &CompilerConfig {
+        &self.config
+    }
+
+    /// Describe everything about this context which changes the generated code, to tell apart the
+    /// programs compiled by contexts configured differently.
+    pub(crate) fn codegen_description(&self) -> String {
+        format!(
+            "{:?}\n{:?}\n{:?}",
+            self.config,
+            self.config.target.resolve(),
+            self.plugins
+        )
+    }
+
+    pub fn limits(&self) -> &CompilationLimits {
+        &self.config.limits
+    }
+
     /// Compiles a sierra program into MLIR and then lowers to LLVM.
     /// Returns the corresponding NativeModule struct.
+    #[instrument(skip_all, fields(functions = program.funcs.len()))]
     pub fn compile(
         &self,
         program: &Program,
         debug_locations: Option<DebugLocations>,
+    ) -> Result<NativeModule, Error> {
+        self.compile_subset(program, debug_locations, None, None)
+    }
+
+    /// Like [compile](Self::compile), but only the given entry points and the functions they call
+    /// (directly or not) are lowered, which is much faster for programs with many entry points.
+    /// The other functions can't be invoked from the returned module.
+    ///
+    /// Check out the [LazyJitNativeExecutor](crate::executor::LazyJitNativeExecutor) for an
+    /// executor which compiles every entry point on its first invocation.
+    #[instrument(skip_all, fields(functions = program.funcs.len()))]
+    pub fn compile_entry_points(
+        &self,
+        program: &Program,
+        entry_points: &[FunctionId],
+    ) -> Result<NativeModule, Error> {
+        self.compile_subset(program, None, Some(entry_points), None)
+    }
+
+    /// Compile the functions reachable from `entry_points`, or all of them when it's `None`.
+    ///
+    /// The [ProgramMetadata] is computed from the program unless given (check out
+    /// [program_metadata](Self::program_metadata)).
+    pub(crate) fn compile_subset(
+        &self,
+        program: &Program,
+        debug_locations: Option<DebugLocations>,
+        entry_points: Option<&[FunctionId]>,
+        program_metadata: Option<&ProgramMetadata>,
     ) -> Result<NativeModule, Error> {
         static INITIALIZED: OnceLock<()> = OnceLock::new();
         INITIALIZED.get_or_init(|| unsafe {
@@ -72,12 +312,16 @@ impl NativeContext {
             LLVM_InitializeAllAsmPrinters();
             tracing::debug!("initialized llvm targets");
         });
-        let target_triple = get_target_triple();
+        self.config
+            .target
+            .validate()
+            .map_err(|e| Error::LLVMCompileError(e.to_string()))?;
+        let target_triple = self.config.target.target_triple();
 
         let module_region = Region::new();
         module_region.append_block(Block::new(&[]));
 
-        let data_layout_ret = &get_data_layout_rep()?;
+        let data_layout_ret = &get_data_layout_rep(&self.config.target)?;
 
         let op = OperationBuilder::new(
             "builtin.module",
@@ -99,36 +343,61 @@ impl NativeContext {
 
         let mut module = Module::from_operation(op).expect("module failed to create");
 
-        let has_gas_builtin = program
-            .type_declarations
-            .iter()
-            .any(|decl| decl.long_id.generic_id.0.as_str() == "GasBuiltin");
+        self.config.limits.check_program(program)?;
+
+        // The libfuncs unknown to the registry are replaced by the stand-ins of their plugins.
+        let mut plugins = self.plugins.clone();
+        let program: &Program = &plugins.resolve_stand_ins(program);
 
         let mut metadata = MetadataStorage::new();
-        // Make the runtime library available.
-        metadata.insert(RuntimeBindingsMeta::default());
-        // We assume that GasMetadata will be always present when the program uses the gas builtin.
-        let gas_metadata = if has_gas_builtin {
-            GasMetadata::new(program, Some(MetadataComputationConfig::default()))
-        } else {
-            GasMetadata::new(program, None)
-        }?;
-        // Unwrapping here is not necessary since the insertion will only fail if there was
-        // already some metadata of the same type.
-        metadata.insert(gas_metadata);
+        self.insert_config_metadata(&mut metadata);
 
         // Create the Sierra program registry
         let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program)?;
+        self.config.limits.check_types(program, &registry)?;
 
-        crate::compile(
-            &self.context,
+        let ProgramMetadata {
+            gas,
+            int_range,
+            recursion,
+        } = match program_metadata {
+            Some(program_metadata) => program_metadata.clone(),
+            None => self.compute_program_metadata(program, &registry, None)?,
+        };
+        // Unwrapping here is not necessary since the insertion will only fail if there was
+        // already some metadata of the same type.
+        metadata.insert(gas);
+        metadata.insert(int_range);
+        metadata.insert(recursion);
+        if !plugins.is_empty() {
+            metadata.insert(plugins);
+        }
+        if !self.config.libfunc_templates.is_empty() {
+            metadata.insert(LibfuncTemplatesMeta::new(
+                self.config.libfunc_templates.iter().map(String::as_str),
+            ));
+        }
+
+        // The metadata is still computed from the whole program, since it doesn't depend on which
+        // functions are lowered.
+        let lowered_program = match entry_points {
+            Some(entry_points) => Cow::Owned(Program {
+                funcs: callee_closure(program, &registry, entry_points)?,
+                ..program.clone()
+            }),
+            None => Cow::Borrowed(program),
+        };
+        self.compile_functions(
             &module,
-            program,
+            &lowered_program,
             &registry,
             &mut metadata,
             debug_locations.as_ref(),
         )?;
 
+        self.config.limits.check_module(&module)?;
+        crate::determinism::audit_module(&module, &mut metadata)?;
+
         if let Ok(x) = std::env::var("NATIVE_DEBUG_DUMP_PREPASS") {
             if x == "1" || x == "true" {
                 std::fs::write("dump-prepass.mlir", module.as_operation().to_string())
@@ -143,7 +412,12 @@ impl NativeContext {
             }
         }
 
-        run_pass_manager(&self.context, &mut module)?;
+        run_pass_manager(
+            &self.context,
+            &mut module,
+            self.config.opt_level,
+            self.config.debug_info,
+        )?;
 
         if let Ok(x) = std::env::var("NATIVE_DEBUG_DUMP") {
             if x == "1" || x == "true" {
@@ -172,38 +446,534 @@ impl NativeContext {
         Ok(NativeModule::new(module, registry, metadata))
     }
 
+    /// Compile the Sierra program of a Starknet contract class, and resolve its entry points to
+    /// the functions which can be invoked with the contract ABI. Check out [ContractEntryPoints]
+    /// for more info.
+    pub fn compile_contract(
+        &self,
+        contract: &ContractClass,
+    ) -> Result<(NativeModule, ContractEntryPoints), Error> {
+        let program = contract
+            .extract_sierra_program()
+            .map_err(|e| Error::InvalidContractClass(e.to_string()))?;
+
+        let module = self.compile(&program, None)?;
+        let entry_points = ContractEntryPoints::new(contract, &program, module.program_registry())?;
+
+        Ok((module, entry_points))
+    }
+
+    /// Load a module serialized by [NativeModule::to_mlir_string], which may have been compiled
+    /// in another process or machine.
+    ///
+    /// The module is already lowered into the LLVM dialect, so only the code generation is left
+    /// (ex. by an executor). The program and the configuration must be the same ones used when
+    /// compiling it with [compile](Self::compile), since the metadata required by the executors
+    /// is computed again from them.
+    #[instrument(skip_all, fields(functions = program.funcs.len()))]
+    pub fn load_module(&self, program: &Program, mlir: &str) -> Result<NativeModule, Error> {
+        let module = Module::parse(&self.context, mlir).ok_or(Error::ParseModuleError)?;
+        let (registry, metadata) = self.executor_metadata(program)?;
+
+        Ok(NativeModule::new(module, registry, metadata))
+    }
+
+    /// Lower the functions of the program into the module, in parallel if enabled.
+    fn compile_functions(
+        &self,
+        module: &Module,
+        program: &Program,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+        metadata: &mut MetadataStorage,
+        debug_locations: Option<&DebugLocations>,
+    ) -> Result<(), Error> {
+        if self.config.parallel_compilation {
+            // The metadata derived from the configuration is built again for every worker, so that
+            // it doesn't depend on which metadata is known to `MetadataStorage::fork`.
+            crate::compiler::compile_parallel_with(
+                &self.context,
+                module,
+                program,
+                registry,
+                metadata,
+                debug_locations,
+                |metadata| {
+                    let mut fork = metadata.fork();
+                    self.insert_config_metadata(&mut fork);
+                    fork
+                },
+            )
+        } else {
+            crate::compile(
+                &self.context,
+                module,
+                program,
+                registry,
+                metadata,
+                debug_locations,
+            )
+        }
+    }
+
+    /// Insert the metadata which only depends on the configuration. Metadata which is already
+    /// there is kept.
+    fn insert_config_metadata(&self, metadata: &mut MetadataStorage) {
+        // Make the runtime library available.
+        metadata.insert(RuntimeBindingsMeta::default());
+        if self.config.track_range_checks {
+            metadata.insert(RangeCheckUsageMeta);
+        }
+        if self.config.overflow_checks {
+            metadata.insert(OverflowChecksMeta);
+        }
+        metadata.insert(PanicMeta::new(self.config.panic_mode));
+        metadata.insert(DeterminismMeta::new(self.config.determinism_mode));
+        #[cfg(feature = "with-debug-utils")]
+        if !self.config.snapshot_statements.is_empty() {
+            metadata.insert(ExecutionSnapshotsMeta::new(
+                self.config.snapshot_statements.iter().copied(),
+            ));
+        }
+        #[cfg(feature = "with-profiler")]
+        if self.config.profiler {
+            metadata.insert(ProfilerMeta::new(&self.config.target.target_triple()));
+        }
+    }
+
+    /// Compute the registry and the metadata required by the executors for a program compiled by
+    /// this context, without compiling it again.
+    pub(crate) fn executor_metadata(
+        &self,
+        program: &Program,
+    ) -> Result<(ProgramRegistry<CoreType, CoreLibfunc>, MetadataStorage), Error> {
+        self.config.limits.check_program(program)?;
+        let program: &Program = &self.plugins.clone().resolve_stand_ins(program);
+
+        let mut metadata = MetadataStorage::new();
+        metadata.insert(self.gas_metadata(program)?);
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program)?;
+        metadata.insert(RecursionMeta::new(program, &registry)?);
+        metadata.insert(PanicMeta::new(self.config.panic_mode));
+        metadata.insert(DeterminismMeta::new(self.config.determinism_mode));
+
+        Ok((registry, metadata))
+    }
+
+    /// Compute the registry and the [ProgramMetadata] of a program compiled by this context, to be
+    /// given to [compile_subset](Self::compile_subset) when compiling it.
+    ///
+    /// The gas metadata is computed with `gas_config` when given, instead of the configured one.
+    pub(crate) fn program_metadata(
+        &self,
+        program: &Program,
+        gas_config: Option<MetadataComputationConfig>,
+    ) -> Result<(ProgramRegistry<CoreType, CoreLibfunc>, ProgramMetadata), Error> {
+        self.config.limits.check_program(program)?;
+        let program: &Program = &self.plugins.clone().resolve_stand_ins(program);
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program)?;
+        self.config.limits.check_types(program, &registry)?;
+        let program_metadata = self.compute_program_metadata(program, &registry, gas_config)?;
+
+        Ok((registry, program_metadata))
+    }
+
+    fn compute_program_metadata(
+        &self,
+        program: &Program,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+        gas_config: Option<MetadataComputationConfig>,
+    ) -> Result<ProgramMetadata, Error> {
+        Ok(ProgramMetadata {
+            gas: match gas_config {
+                Some(gas_config) => GasMetadata::new(program, Some(gas_config))?,
+                None => self.gas_metadata(program)?,
+            },
+            int_range: IntRangeMeta::new(program, registry)?,
+            recursion: RecursionMeta::new(program, registry)?,
+        })
+    }
+
+    fn gas_metadata(&self, program: &Program) -> Result<GasMetadata, Error> {
+        let has_gas_builtin = program
+            .type_declarations
+            .iter()
+            .any(|decl| decl.long_id.generic_id.0.as_str() == "GasBuiltin");
+
+        // We assume that GasMetadata will be always present when the program uses the gas builtin.
+        Ok(if has_gas_builtin && !self.config.infinite_gas {
+            GasMetadata::new(program, Some(MetadataComputationConfig::default()))
+        } else {
+            GasMetadata::new(program, None)
+        }?)
+    }
+
     /// Compiles a sierra program into MLIR and then lowers to LLVM. Using the given metadata.
     /// Returns the corresponding NativeModule struct.
+    ///
+    /// Like [compile](Self::compile), except that the gas metadata is computed with
+    /// `metadata_config`.
+    #[instrument(skip_all, fields(functions = program.funcs.len()))]
     pub fn compile_with_metadata(
         &self,
         program: &Program,
         metadata_config: MetadataComputationConfig,
     ) -> Result<NativeModule, Error> {
-        let mut module = Module::new(Location::unknown(&self.context));
+        let (_, program_metadata) = self.program_metadata(program, Some(metadata_config))?;
+        self.compile_subset(program, None, None, Some(&program_metadata))
+    }
+}
 
-        let mut metadata = MetadataStorage::new();
-        // Make the runtime library available.
-        metadata.insert(RuntimeBindingsMeta::default());
+impl CompilerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        let gas_metadata = GasMetadata::new(program, Some(metadata_config))?;
-        metadata.insert(gas_metadata);
+    /// The optimization level used when lowering the compiled programs, both by the MLIR passes
+    /// (check out [run_pass_manager]) and by the executors of an
+    /// [MlirBackend](crate::backend::MlirBackend).
+    pub fn with_opt_level(mut self, opt_level: OptLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
 
-        // Create the Sierra program registry
-        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program)?;
+    /// Enforce the given limits on every compiled program.
+    pub fn with_limits(mut self, limits: CompilationLimits) -> Self {
+        self.limits = limits;
+        self
+    }
 
-        crate::compile(
-            &self.context,
-            &module,
-            program,
-            &registry,
-            &mut metadata,
-            None,
-        )?;
+    /// Make every compiled program count the same number of range checks as the Cairo VM, instead
+    /// of an approximation. Check out [RangeCheckUsageMeta] for more info.
+    pub fn with_range_check_tracking(mut self, enabled: bool) -> Self {
+        self.track_range_checks = enabled;
+        self
+    }
+
+    /// Fail the compilation of pathological programs (ex. with huge types) whose sizes or offsets
+    /// don't fit in the integers used by the generated code, instead of silently truncating them.
+    /// Check out [OverflowChecksMeta] for more info.
+    pub fn with_overflow_checks(mut self, enabled: bool) -> Self {
+        self.overflow_checks = enabled;
+        self
+    }
 
-        run_pass_manager(&self.context, &mut module)?;
+    /// Compile programs without gas metering: every `withdraw_gas` succeeds without consuming any
+    /// gas, so no initial gas needs to be supplied when invoking them. Programs using the gas
+    /// builtin are otherwise always compiled with their gas costs.
+    pub fn with_infinite_gas(mut self, enabled: bool) -> Self {
+        self.infinite_gas = enabled;
+        self
+    }
 
-        Ok(NativeModule::new(module, registry, metadata))
+    /// Choose what the generated code does on unrecoverable errors. Check out [PanicMode] for
+    /// more info.
+    pub fn with_panic_mode(mut self, panic_mode: PanicMode) -> Self {
+        self.panic_mode = panic_mode;
+        self
+    }
+
+    /// Check the generated code for nondeterministic behavior. Check out [DeterminismMode] for
+    /// more info.
+    pub fn with_determinism_mode(mut self, determinism_mode: DeterminismMode) -> Self {
+        self.determinism_mode = determinism_mode;
+        self
+    }
+
+    /// Build the given generic libfuncs (ex. `dup`) only once into a
+    /// [template](crate::metadata::libfunc_templates) instead of on every occurrence.
+    ///
+    /// Check out [DEFAULT_LIBFUNC_TEMPLATES] for a sensible default.
+    ///
+    /// [DEFAULT_LIBFUNC_TEMPLATES]: crate::metadata::libfunc_templates::DEFAULT_LIBFUNC_TEMPLATES
+    pub fn with_libfunc_templates(
+        mut self,
+        generic_ids: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.libfunc_templates = generic_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The machine the programs are compiled for. The data layout of the compiled modules is the
+    /// target's, and the shared libraries built by the executors of an
+    /// [MlirBackend](crate::backend::MlirBackend) and by the
+    /// [AOT program cache](crate::cache::AotProgramCache) use its CPU and relocation model.
+    /// Defaults to the host's CPU.
+    ///
+    /// Executors can only load code compiled for the host. Check out [TargetConfig] for how to
+    /// cross-compile.
+    pub fn with_target(mut self, target: TargetConfig) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Lower the functions of every program in parallel, which speeds up the compilation of large
+    /// programs. The generated module is the same as when compiling sequentially. Check out
+    /// [compile_parallel](crate::compile_parallel) for more info.
+    pub fn with_parallel_compilation(mut self, enabled: bool) -> Self {
+        self.parallel_compilation = enabled;
+        self
+    }
+
+    /// Emit DWARF debug info (line tables only) into the compiled modules, so that the backtraces
+    /// of native crashes can be mapped back to the program. Each instruction is attributed to the
+    /// Cairo source of its Sierra statement when the debug info of the program is given, or to the
+    /// line `statement index + 1` of the [`<sierra>`](crate::SIERRA_STATEMENTS_FILE) file
+    /// otherwise.
+    pub fn with_debug_info(mut self, enabled: bool) -> Self {
+        self.debug_info = enabled;
+        self
+    }
+
+    /// Take an [execution snapshot](crate::execution_snapshots) before executing each of the
+    /// given Sierra statements.
+    #[cfg(feature = "with-debug-utils")]
+    pub fn with_snapshot_statements(mut self, statements: impl IntoIterator<Item = usize>) -> Self {
+        self.snapshot_statements = statements.into_iter().collect();
+        self
+    }
+
+    /// Measure the execution of every libfunc invocation. Check out the
+    /// [profiler](crate::profiler) for more info.
+    #[cfg(feature = "with-profiler")]
+    pub fn with_profiler(mut self, enabled: bool) -> Self {
+        self.profiler = enabled;
+        self
+    }
+
+    pub fn opt_level(&self) -> OptLevel {
+        self.opt_level
+    }
+
+    pub fn limits(&self) -> &CompilationLimits {
+        &self.limits
+    }
+
+    pub fn range_check_tracking(&self) -> bool {
+        self.track_range_checks
+    }
+
+    pub fn overflow_checks(&self) -> bool {
+        self.overflow_checks
+    }
+
+    pub fn infinite_gas(&self) -> bool {
+        self.infinite_gas
+    }
+
+    #[cfg(feature = "with-debug-utils")]
+    pub fn snapshot_statements(&self) -> &std::collections::BTreeSet<usize> {
+        &self.snapshot_statements
+    }
+
+    #[cfg(feature = "with-profiler")]
+    pub fn profiler(&self) -> bool {
+        self.profiler
+    }
+
+    pub fn panic_mode(&self) -> PanicMode {
+        self.panic_mode
+    }
+
+    pub fn determinism_mode(&self) -> DeterminismMode {
+        self.determinism_mode
     }
+
+    pub fn libfunc_templates(&self) -> &BTreeSet<String> {
+        &self.libfunc_templates
+    }
+
+    pub fn target(&self) -> &TargetConfig {
+        &self.target
+    }
+
+    pub fn parallel_compilation(&self) -> bool {
+        self.parallel_compilation
+    }
+
+    pub fn debug_info(&self) -> bool {
+        self.debug_info
+    }
+}
+
+impl CompilationLimits {
+    /// Check the limits which only depend on the Sierra program.
+    fn check_program(&self, program: &Program) -> Result<(), Error> {
+        check_limit("functions", self.max_functions, program.funcs.len())?;
+        check_limit("statements", self.max_statements, program.statements.len())
+    }
+
+    /// Check the depth and the size of every type of the program.
+    ///
+    /// The layouts are computed with an explicit stack instead of recursively, so that the nesting
+    /// is rejected before it can overflow the stack of the compiler. Types which contain themselves
+    /// inline are always rejected, since they would have an infinite size.
+    fn check_types(
+        &self,
+        program: &Program,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    ) -> Result<(), Error> {
+        let max_depth = self.max_type_depth.min(MAX_TYPE_DEPTH);
+        // The depth and the layout of every type already checked.
+        let mut checked = HashMap::<&ConcreteTypeId, (usize, Layout)>::new();
+
+        for declaration in &program.type_declarations {
+            // Every entry is a type and whether its members have already been pushed. The types
+            // whose members have been pushed are the ones being walked.
+            let mut stack = vec![(&declaration.id, false)];
+            let mut walking = Vec::<&ConcreteTypeId>::new();
+
+            while let Some((id, members_pushed)) = stack.pop() {
+                let ty = registry.get_type(id)?;
+                let members = inline_members(ty);
+
+                if !members_pushed {
+                    if checked.contains_key(id) {
+                        continue;
+                    }
+                    if walking.contains(&id) {
+                        return Err(Error::TypeTooComplex(format!("type {id} contains itself")));
+                    }
+                    if walking.len() >= max_depth {
+                        return Err(too_deep(&declaration.id, max_depth));
+                    }
+
+                    walking.push(id);
+                    stack.push((id, true));
+                    stack.extend(members.iter().map(|member| (member, false)));
+                    continue;
+                }
+
+                walking.pop();
+
+                let depth = 1 + members
+                    .iter()
+                    .map(|member| checked[member].0)
+                    .max()
+                    .unwrap_or(0);
+                if depth > max_depth {
+                    return Err(too_deep(id, max_depth));
+                }
+
+                let layout = build_layout(ty, |member| Ok(checked[member].1))?;
+                if let Some(max_size) = self.max_type_size {
+                    if layout.size() > max_size {
+                        return Err(Error::TypeTooComplex(format!(
+                            "type {id} takes {} bytes, but the limit is {max_size}",
+                            layout.size()
+                        )));
+                    }
+                }
+
+                checked.insert(id, (depth, layout));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the limits which depend on the generated MLIR module.
+    fn check_module(&self, module: &Module) -> Result<(), Error> {
+        if self.max_mlir_operations.is_some() {
+            let count = count_operations(&module.body())?;
+            check_limit("MLIR operations", self.max_mlir_operations, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn too_deep(id: &ConcreteTypeId, max_depth: usize) -> Error {
+    Error::TypeTooComplex(format!(
+        "type {id} is nested deeper than the limit of {max_depth} levels"
+    ))
+}
+
+fn check_limit(what: &str, limit: Option<usize>, actual: usize) -> Result<(), Error> {
+    match limit {
+        Some(limit) if actual > limit => Err(Error::CompilationLimitExceeded(format!(
+            "the program has {actual} {what}, but the limit is {limit}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Return the given functions and the ones they call (directly or not), in declaration order.
+fn callee_closure(
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry_points: &[FunctionId],
+) -> Result<Vec<Function>, Error> {
+    let mut functions = HashSet::new();
+    let mut visited_statements = HashSet::new();
+
+    let mut pending_functions = entry_points.to_vec();
+    while let Some(function_id) = pending_functions.pop() {
+        if !functions.insert(function_id.clone()) {
+            continue;
+        }
+
+        let mut pending_statements = vec![registry.get_function(&function_id)?.entry_point];
+        while let Some(statement_idx) = pending_statements.pop() {
+            if !visited_statements.insert(statement_idx) {
+                continue;
+            }
+
+            if let Statement::Invocation(invocation) = &program.statements[statement_idx.0] {
+                let libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
+                if let Some(callee_id) = libfunc.is_function_call() {
+                    pending_functions.push(callee_id.clone());
+                }
+
+                pending_statements.extend(
+                    invocation
+                        .branches
+                        .iter()
+                        .map(|branch| statement_idx.next(&branch.target)),
+                );
+            }
+        }
+    }
+
+    Ok(program
+        .funcs
+        .iter()
+        .filter(|function| functions.contains(&function.id))
+        .cloned()
+        .collect())
+}
+
+/// Count the operations within a block, including the ones in nested regions.
+///
+/// The nested blocks are visited from a worklist instead of recursively, so that the limit can be
+/// checked on arbitrarily nested IR without overflowing the stack.
+fn count_operations(block: &Block) -> Result<usize, Error> {
+    let mut count = 0;
+
+    // Raw handles, since the references to the nested blocks can't outlive the operations they were
+    // found through.
+    let mut pending_blocks = vec![block.to_raw()];
+    while let Some(block) = pending_blocks.pop() {
+        // SAFETY: Every block belongs to the module being checked, which outlives this function.
+        let block = unsafe { BlockRef::from_raw(block) };
+
+        let mut next_op = block.first_operation();
+        while let Some(op) = next_op {
+            count += 1;
+            for idx in 0..op.region_count() {
+                let mut next_block = op.region(idx)?.first_block();
+                while let Some(block) = next_block {
+                    pending_blocks.push(block.to_raw());
+                    next_block = block.next_in_region();
+                }
+            }
+
+            next_op = op.next_in_block();
+        }
+    }
+
+    Ok(count)
 }
 
 /// Initialize an MLIR context.
@@ -0,0 +1,411 @@
+//! # Determinism audit
+//!
+//! Consensus-critical users need every execution of a program to produce the exact same results,
+//! byte by byte. When the [DeterminismMode] is enabled, the compiled programs are checked for the
+//! following sources of nondeterminism:
+//!
+//!   - Uninitialized memory: the libfuncs build their values from zeroes instead of `undef`, and
+//!     clear the memory before storing values which have padding. The generated module is then
+//!     checked for any remaining `undef` or `poison` value.
+//!   - Pointer-value-dependent behavior: the generated module is checked for pointers converted into
+//!     integers, since their values change between executions.
+//!   - Dirty padding: the executors check that the padding bytes of the returned values (including
+//!     the unused bits of felts and the contents of arrays and boxes) are zeroed.
+//!
+//! The iteration order of the dictionaries (which are hash maps with a random seed) is not
+//! observable by the generated code, which only performs lookups on them.
+//!
+//! In [audit](DeterminismMode::Audit) mode every finding is logged as a warning (and the ones found
+//! while compiling are kept in the module's [DeterminismMeta]), while in
+//! [strict](DeterminismMode::Strict) mode the compilation or execution fails instead. When an
+//! execution fails, the memory owned by the values it returned is reclaimed by the executor.
+//!
+//! ```
+//! use cairo_native::context::{CompilerConfig, DeterminismMode, NativeContext};
+//!
+//! let context = NativeContext::new()
+//!     .with_config(CompilerConfig::new().with_determinism_mode(DeterminismMode::Strict));
+//! ```
+
+use crate::{
+    context::DeterminismMode,
+    error::{Error, Result},
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    types::TypeBuilder,
+};
+use cairo_lang_sierra::{
+    extensions::{
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+        starknet::StarkNetTypeConcrete,
+    },
+    ids::ConcreteTypeId,
+    program_registry::ProgramRegistry,
+};
+use melior::ir::{Block, Module};
+use std::{alloc::Layout, ops::Range, ptr::NonNull};
+
+/// Check the generated module for nondeterministic behavior, before running any pass on it.
+pub(crate) fn audit_module(module: &Module, metadata: &mut MetadataStorage) -> Result<()> {
+    let mode = DeterminismMeta::mode_of(metadata);
+    if mode == DeterminismMode::Off {
+        return Ok(());
+    }
+
+    let mut findings = Vec::new();
+    audit_block(&module.body(), &mut findings)?;
+
+    report(mode, findings.iter().cloned())?;
+    if let Some(meta) = metadata.get_mut::<DeterminismMeta>() {
+        findings
+            .into_iter()
+            .for_each(|finding| meta.add_finding(finding));
+    }
+
+    Ok(())
+}
+
+/// Check the operations within a block, recursively.
+fn audit_block(block: &Block, findings: &mut Vec<String>) -> Result<()> {
+    let mut next_op = block.first_operation();
+    while let Some(op) = next_op {
+        match op.name().as_string_ref().as_str().unwrap_or_default() {
+            "llvm.mlir.undef" | "llvm.mlir.poison" => {
+                findings.push(format!("uninitialized value at {}", op.location()));
+            }
+            "llvm.ptrtoint" => {
+                findings.push(format!(
+                    "pointer converted into an integer at {}",
+                    op.location()
+                ));
+            }
+            _ => {}
+        }
+
+        for idx in 0..op.region_count() {
+            let mut next_block = op.region(idx)?.first_block();
+            while let Some(block) = next_block {
+                audit_block(&block, findings)?;
+                next_block = block.next_in_region();
+            }
+        }
+
+        next_op = op.next_in_block();
+    }
+
+    Ok(())
+}
+
+/// Check that the padding of a returned value is zeroed.
+///
+/// # Safety
+///
+/// The pointer must point to a valid value of the given type.
+pub(crate) unsafe fn audit_return_value(
+    mode: DeterminismMode,
+    ptr: NonNull<()>,
+    type_id: &ConcreteTypeId,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+) -> Result<()> {
+    if mode == DeterminismMode::Off {
+        return Ok(());
+    }
+
+    let mut findings = Vec::new();
+    check_padding(ptr.cast().as_ptr(), type_id, registry, &mut findings);
+
+    report(mode, findings.into_iter())
+}
+
+fn report(mode: DeterminismMode, findings: impl Iterator<Item = String>) -> Result<()> {
+    match mode {
+        DeterminismMode::Off => Ok(()),
+        DeterminismMode::Audit => {
+            findings.for_each(|finding| tracing::warn!("nondeterministic behavior: {finding}"));
+            Ok(())
+        }
+        DeterminismMode::Strict => {
+            let findings = findings.collect::<Vec<_>>();
+            if findings.is_empty() {
+                Ok(())
+            } else {
+                Err(Error::NondeterministicBehavior(findings.join(", ")))
+            }
+        }
+    }
+}
+
+/// Find the dirty padding bytes of a value in memory.
+unsafe fn check_padding(
+    ptr: *const u8,
+    type_id: &ConcreteTypeId,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    findings: &mut Vec<String>,
+) {
+    let type_info = registry.get_type(type_id).unwrap();
+
+    match type_info {
+        CoreTypeConcrete::Felt252(_)
+        | CoreTypeConcrete::StarkNet(
+            StarkNetTypeConcrete::ClassHash(_)
+            | StarkNetTypeConcrete::ContractAddress(_)
+            | StarkNetTypeConcrete::StorageAddress(_)
+            | StarkNetTypeConcrete::StorageBaseAddress(_),
+        ) => check_felt(ptr, type_id, findings),
+        CoreTypeConcrete::EcPoint(_) | CoreTypeConcrete::EcState(_) => {
+            let felt_size = 32;
+            for offset in (0..type_info.layout(registry).unwrap().size()).step_by(felt_size) {
+                check_felt(ptr.add(offset), type_id, findings);
+            }
+        }
+        CoreTypeConcrete::Bytes31(_) => check_zeroed(ptr, 31..32, type_id, findings),
+        CoreTypeConcrete::Struct(info) => {
+            let mut layout: Option<Layout> = None;
+            for member_ty in &info.members {
+                let member_layout = registry
+                    .get_type(member_ty)
+                    .unwrap()
+                    .layout(registry)
+                    .unwrap();
+
+                let prev_end = layout.map_or(0, |x| x.size());
+                let (new_layout, offset) = match layout {
+                    Some(layout) => layout.extend(member_layout).unwrap(),
+                    None => (member_layout, 0),
+                };
+                layout = Some(new_layout);
+
+                check_zeroed(ptr, prev_end..offset, type_id, findings);
+                check_padding(ptr.add(offset), member_ty, registry, findings);
+            }
+
+            let end = layout.map_or(0, |x| x.size());
+            check_zeroed(
+                ptr,
+                end..type_info.layout(registry).unwrap().size(),
+                type_id,
+                findings,
+            );
+        }
+        CoreTypeConcrete::Enum(info) => match info.variants.len() {
+            0 => {}
+            1 => check_padding(ptr, &info.variants[0], registry, findings),
+            _ => {
+                let (layout, tag_layout, variant_layouts) =
+                    crate::types::r#enum::get_layout_for_variants(registry, &info.variants)
+                        .unwrap();
+
+                let tag = match tag_layout.size() {
+                    1 => *ptr.cast::<u8>() as usize,
+                    2 => *ptr.cast::<u16>() as usize,
+                    4 => *ptr.cast::<u32>() as usize,
+                    8 => *ptr.cast::<u64>() as usize,
+                    _ => unreachable!(),
+                };
+                let Some(payload_layout) = variant_layouts.get(tag) else {
+                    findings.push(format!("invalid tag {tag} for a value of type {type_id}"));
+                    return;
+                };
+
+                let offset = tag_layout.extend(*payload_layout).unwrap().1;
+                check_zeroed(ptr, tag_layout.size()..offset, type_id, findings);
+                check_padding(ptr.add(offset), &info.variants[tag], registry, findings);
+                check_zeroed(
+                    ptr,
+                    offset + payload_layout.size()..layout.size(),
+                    type_id,
+                    findings,
+                );
+            }
+        },
+        CoreTypeConcrete::NonZero(info) | CoreTypeConcrete::Snapshot(info) => {
+            check_padding(ptr, &info.ty, registry, findings)
+        }
+        CoreTypeConcrete::Box(info) => {
+            check_padding(*ptr.cast::<*const u8>(), &info.ty, registry, findings)
+        }
+        CoreTypeConcrete::Nullable(info) => {
+            let inner_ptr = *ptr.cast::<*const u8>();
+            if !inner_ptr.is_null() {
+                check_padding(inner_ptr, &info.ty, registry, findings);
+            }
+        }
+        CoreTypeConcrete::Array(info) => {
+            let (array_layout, start_offset) = Layout::new::<*const u8>()
+                .extend(Layout::new::<u32>())
+                .unwrap();
+            let (array_layout, end_offset) = array_layout.extend(Layout::new::<u32>()).unwrap();
+            let (array_layout, _) = array_layout.extend(Layout::new::<u32>()).unwrap();
+            check_zeroed(
+                ptr,
+                array_layout.size()..array_layout.pad_to_align().size(),
+                type_id,
+                findings,
+            );
+
+            let data_ptr = *ptr.cast::<*const u8>();
+            let start = *ptr.add(start_offset).cast::<u32>() as usize;
+            let end = *ptr.add(end_offset).cast::<u32>() as usize;
+
+            let elem_layout = registry
+                .get_type(&info.ty)
+                .unwrap()
+                .layout(registry)
+                .unwrap();
+            let elem_stride = elem_layout.pad_to_align().size();
+            for idx in start..end {
+                let elem_ptr = data_ptr.add(idx * elem_stride);
+                check_padding(elem_ptr, &info.ty, registry, findings);
+                check_zeroed(
+                    elem_ptr,
+                    elem_layout.size()..elem_stride,
+                    &info.ty,
+                    findings,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+unsafe fn check_felt(ptr: *const u8, type_id: &ConcreteTypeId, findings: &mut Vec<String>) {
+    if *ptr.add(31) & 0xF0 != 0 {
+        findings.push(format!(
+            "the unused bits of a felt in a value of type {type_id} are not zeroed"
+        ));
+    }
+}
+
+unsafe fn check_zeroed(
+    ptr: *const u8,
+    range: Range<usize>,
+    type_id: &ConcreteTypeId,
+    findings: &mut Vec<String>,
+) {
+    if let Some(offset) = range.into_iter().find(|&offset| *ptr.add(offset) != 0) {
+        findings.push(format!(
+            "the padding at byte {offset} of a value of type {type_id} is not zeroed"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{CompilerConfig, NativeContext},
+        executor::JitNativeExecutor,
+        utils::{find_function_id, test::load_cairo},
+        values::JitValue,
+        OptLevel,
+    };
+    use starknet_types_core::felt::Felt;
+
+    #[test]
+    fn strict_mode() {
+        let program = load_cairo! {
+            #[derive(Drop)]
+            enum Value {
+                A: u8,
+                B: (u8, felt252),
+            }
+
+            fn run_test() -> (Array<Value>, Box<Value>) {
+                let mut values = ArrayTrait::new();
+                values.append(Value::A(1));
+                values.append(Value::B((2, 3)));
+                (values, BoxTrait::new(Value::A(4)))
+            }
+        };
+
+        let context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_determinism_mode(DeterminismMode::Strict));
+        let module = context.compile(&program.1, None).unwrap();
+        assert!(module
+            .get_metadata::<DeterminismMeta>()
+            .unwrap()
+            .findings()
+            .is_empty());
+
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[],
+                None,
+            )
+            .unwrap();
+
+        let JitValue::Struct { fields, .. } = result.return_value else {
+            panic!("expected a struct");
+        };
+        assert_eq!(
+            fields[0],
+            JitValue::Array(vec![
+                JitValue::Enum {
+                    tag: 0,
+                    value: Box::new(JitValue::Uint8(1)),
+                    debug_name: None,
+                },
+                JitValue::Enum {
+                    tag: 1,
+                    value: Box::new(JitValue::Struct {
+                        fields: vec![JitValue::Uint8(2), JitValue::Felt252(Felt::from(3))],
+                        debug_name: None,
+                    }),
+                    debug_name: None,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn dirty_padding() {
+        let program = load_cairo! {
+            fn run_test() -> (u8, felt252) {
+                (1, 2)
+            }
+        };
+        let registry = ProgramRegistry::new(&program.1).unwrap();
+        let function_id = find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0));
+        let type_id = &registry
+            .get_function(function_id)
+            .unwrap()
+            .signature
+            .ret_types[0];
+
+        // The `u8` is followed by 15 bytes of padding.
+        let mut data = [0u128; 3];
+        data[0] = 1;
+        unsafe {
+            audit_return_value(
+                DeterminismMode::Strict,
+                NonNull::from(&mut data).cast(),
+                type_id,
+                &registry,
+            )
+            .unwrap();
+        }
+
+        data[0] |= 0xFF00;
+        let result = unsafe {
+            audit_return_value(
+                DeterminismMode::Strict,
+                NonNull::from(&mut data).cast(),
+                type_id,
+                &registry,
+            )
+        };
+        assert!(matches!(result, Err(Error::NondeterministicBehavior(_))));
+
+        // Audit mode only reports it.
+        unsafe {
+            audit_return_value(
+                DeterminismMode::Audit,
+                NonNull::from(&mut data).cast(),
+                type_id,
+                &registry,
+            )
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! # Entry point reflection
+//!
+//! Lists the functions of a program along with the types a caller has to provide and will receive
+//! back. The implicit arguments (builtins, the gas counter and the syscall handler) are handled by
+//! the executor, and are therefore not part of the user-facing signature.
+//!
+//! The entry points of Starknet contract classes can also be resolved to their functions by their
+//! selectors. Check out [ContractEntryPoints] for more info.
+
+use crate::{error::Error, types::TypeBuilder, utils::find_entry_point_by_idx};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+    ids::{ConcreteTypeId, FunctionId},
+    program::{GenFunction, Program, StatementIdx},
+    program_registry::ProgramRegistry,
+};
+use cairo_lang_starknet_classes::contract_class::{self, ContractClass};
+use starknet_types_core::felt::Felt;
+
+/// The user-facing signature of a program's entry point.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryPoint {
+    /// Numeric id of the function, as used by the executors.
+    pub id: u64,
+    /// Debug name of the function, if available.
+    pub name: Option<String>,
+    /// Names of the parameter types, without the implicits.
+    pub params: Vec<String>,
+    /// Names of the return types, without the implicits.
+    pub return_types: Vec<String>,
+}
+
+/// Return the user-facing signature of every function in the program, in declaration order.
+pub fn entry_points(
+    program: &Program,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+) -> Result<Vec<EntryPoint>, Error> {
+    let user_types = |types: &[ConcreteTypeId]| -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        for ty in types {
+            if !registry.get_type(ty)?.is_builtin() {
+                names.push(ty.to_string());
+            }
+        }
+        Ok(names)
+    };
+
+    program
+        .funcs
+        .iter()
+        .map(|func| {
+            Ok(EntryPoint {
+                id: func.id.id,
+                name: func.id.debug_name.as_ref().map(ToString::to_string),
+                params: user_types(&func.signature.param_types)?,
+                return_types: user_types(&func.signature.ret_types)?,
+            })
+        })
+        .collect()
+}
+
+/// The entry points of a Starknet contract class, resolved to the functions of its program.
+///
+/// The functions of the entry points are generated by the Starknet compiler plugin. They all take
+/// the calldata as a `Span<felt252>` and return the serialized result as a
+/// `PanicResult<(Span<felt252>,)>`, which is the ABI expected by the executors'
+/// `invoke_contract_dynamic`. Therefore, they can be invoked without knowing the signature of the
+/// contract functions they wrap.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContractEntryPoints {
+    pub external: Vec<ContractEntryPoint>,
+    pub l1_handler: Vec<ContractEntryPoint>,
+    pub constructor: Vec<ContractEntryPoint>,
+}
+
+/// A contract entry point, resolved to its function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractEntryPoint {
+    pub selector: Felt,
+    pub function_id: FunctionId,
+}
+
+impl ContractEntryPoints {
+    /// Resolve the entry points of a contract class, checking that their functions have the
+    /// contract ABI.
+    pub fn new(
+        contract: &ContractClass,
+        program: &Program,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    ) -> Result<Self, Error> {
+        let resolve = |entry_points: &[contract_class::ContractEntryPoint]| {
+            entry_points
+                .iter()
+                .map(|entry_point| {
+                    let func = find_entry_point_by_idx(program, entry_point.function_idx)
+                        .ok_or_else(|| {
+                            Error::InvalidContractClass(format!(
+                                "function {} of an entry point not found",
+                                entry_point.function_idx
+                            ))
+                        })?;
+                    check_contract_abi(func, registry)?;
+
+                    Ok(ContractEntryPoint {
+                        selector: Felt::from_bytes_be_slice(&entry_point.selector.to_bytes_be()),
+                        function_id: func.id.clone(),
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        };
+
+        Ok(Self {
+            external: resolve(&contract.entry_points_by_type.external)?,
+            l1_handler: resolve(&contract.entry_points_by_type.l1_handler)?,
+            constructor: resolve(&contract.entry_points_by_type.constructor)?,
+        })
+    }
+
+    /// Return the function of the external entry point with the given selector.
+    pub fn find_external(&self, selector: &Felt) -> Option<&FunctionId> {
+        find_by_selector(&self.external, selector)
+    }
+
+    /// Return the function of the L1 handler with the given selector.
+    pub fn find_l1_handler(&self, selector: &Felt) -> Option<&FunctionId> {
+        find_by_selector(&self.l1_handler, selector)
+    }
+}
+
+fn find_by_selector<'a>(
+    entry_points: &'a [ContractEntryPoint],
+    selector: &Felt,
+) -> Option<&'a FunctionId> {
+    entry_points
+        .iter()
+        .find(|entry_point| &entry_point.selector == selector)
+        .map(|entry_point| &entry_point.function_id)
+}
+
+/// Check that a function takes a span and returns a panic result, besides the implicits.
+fn check_contract_abi(
+    func: &GenFunction<StatementIdx>,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+) -> Result<(), Error> {
+    let user_types = |types: &[ConcreteTypeId]| -> Result<Vec<&CoreTypeConcrete>, Error> {
+        let mut user_types = Vec::new();
+        for ty in types {
+            let ty = registry.get_type(ty)?;
+            if !ty.is_builtin() {
+                user_types.push(ty);
+            }
+        }
+        Ok(user_types)
+    };
+
+    match (
+        user_types(&func.signature.param_types)?.as_slice(),
+        user_types(&func.signature.ret_types)?.as_slice(),
+    ) {
+        ([CoreTypeConcrete::Struct(_)], [CoreTypeConcrete::Enum(_)]) => Ok(()),
+        _ => Err(Error::InvalidContractClass(format!(
+            "the function of entry point {} doesn't have the contract ABI",
+            func.id
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cairo_lang_sierra::ProgramParser;
+
+    #[test]
+    fn entry_points_strip_implicits() {
+        let program = ProgramParser::new()
+            .parse(
+                "type RangeCheck = RangeCheck;
+                type GasBuiltin = GasBuiltin;
+                type felt252 = felt252;
+                type u8 = u8;
+
+                return([0], [1], [2]);
+
+                run_test@0([0]: RangeCheck, [1]: GasBuiltin, [2]: felt252, [3]: u8) -> (RangeCheck, GasBuiltin, felt252);",
+            )
+            .unwrap();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+
+        assert_eq!(
+            entry_points(&program, &registry).unwrap(),
+            [EntryPoint {
+                id: 0,
+                name: Some("run_test".to_string()),
+                params: vec!["felt252".to_string(), "u8".to_string()],
+                return_types: vec!["felt252".to_string()],
+            }],
+        );
+    }
+}
@@ -4,6 +4,7 @@ use cairo_lang_sierra::extensions::modules::utils::Range;
 use cairo_lang_sierra::{
     edit_state::EditStateError, ids::ConcreteTypeId, program_registry::ProgramRegistryError,
 };
+use starknet_types_core::felt::Felt;
 use std::{alloc::LayoutError, num::TryFromIntError};
 use thiserror::Error;
 
@@ -38,6 +39,9 @@ pub enum Error {
     #[error("error parsing attribute")]
     ParseAttributeError,
 
+    #[error("error parsing the MLIR module")]
+    ParseModuleError,
+
     #[error("missing metadata")]
     MissingMetadata,
 
@@ -58,6 +62,39 @@ pub enum Error {
 
     #[error("cairo const data mismatch")]
     ConstDataMismatch,
+
+    #[error("integer overflow while computing {0}")]
+    IntegerOverflow(String),
+
+    #[error("compilation limit exceeded: {0}")]
+    CompilationLimitExceeded(String),
+
+    #[error("type too complex: {0}")]
+    TypeTooComplex(String),
+
+    #[error("incompatible libfunc plugin: {0}")]
+    IncompatiblePlugin(String),
+
+    #[error("nondeterministic behavior: {0}")]
+    NondeterministicBehavior(String),
+
+    #[error("invalid program: {0}")]
+    InvalidProgram(String),
+
+    #[error("invalid contract class: {0}")]
+    InvalidContractClass(String),
+
+    #[error("payload limit exceeded: {0}")]
+    PayloadLimitExceeded(String),
+
+    #[error("program panicked with {message}")]
+    Panic { message: String, data: Vec<Felt> },
+
+    #[error("unrecoverable error: {0}")]
+    UnrecoverableError(String),
+
+    #[error("missing feature: {0}")]
+    MissingFeature(String),
 }
 
 impl Error {
@@ -69,6 +106,19 @@ impl Error {
                 .unwrap_or_default(),
         )
     }
+
+    /// Return whether the execution ran out of gas, either because the initial gas didn't cover
+    /// the cost of the entry point or because a gas withdrawal failed later on (which panics with
+    /// `'Out of gas'`, like in the Cairo runner).
+    pub fn is_out_of_gas(&self) -> bool {
+        match self {
+            Self::GasMetadataError(GasMetadataError::NotEnoughGas { .. }) => true,
+            Self::Panic { data, .. } => {
+                data.as_slice() == [Felt::from_bytes_be_slice(b"Out of gas")]
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
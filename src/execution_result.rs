@@ -1,8 +1,11 @@
-/// # Execution Result
-///
-/// This module contains the structures used to interpret the program execution results, either
-/// normal programs or starknet contracts.
-use crate::{error::Error, values::JitValue};
+//! # Execution Result
+//!
+//! This module contains the structures used to interpret the program execution results, either
+//! normal programs or starknet contracts.
+
+use crate::{
+    error::Error, starknet::PayloadLimits, syscall_profile::SyscallProfile, values::JitValue,
+};
 use starknet_types_core::felt::Felt;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -23,6 +26,79 @@ pub struct ExecutionResult {
     pub remaining_gas: Option<u128>,
     pub return_value: JitValue,
     pub builtin_stats: BuiltinStats,
+    /// The latency of the syscalls, when the syscall handler
+    /// [enables it](crate::starknet::StarknetSyscallHandler::profile_syscalls).
+    pub syscall_profile: Option<SyscallProfile>,
+}
+
+impl ExecutionResult {
+    /// Return the value returned by the program, unwrapping its `PanicResult` if it has one.
+    ///
+    /// Panics (ex. `panic_with_felt252`) are returned as an [Error::Panic] with the panic data and
+    /// its decoded message. The return value is only recognized as a `PanicResult` when the program
+    /// has debug names, otherwise it's returned as is.
+    pub fn into_result(self) -> Result<JitValue, Error> {
+        match self.return_value {
+            JitValue::Enum {
+                tag,
+                value,
+                debug_name: Some(debug_name),
+            } if debug_name.starts_with("core::panics::PanicResult::") => match (tag, *value) {
+                (0, JitValue::Struct { mut fields, .. }) if fields.len() == 1 => {
+                    Ok(fields.remove(0))
+                }
+                (1, JitValue::Struct { fields, .. }) => match fields.get(1) {
+                    Some(JitValue::Array(data)) => {
+                        let data = data
+                            .iter()
+                            .map(|x| match x {
+                                JitValue::Felt252(x) => Ok(*x),
+                                _ => Err(Error::UnexpectedValue("felt252".to_string())),
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        Err(Error::Panic {
+                            message: decode_panic_message(&data),
+                            data,
+                        })
+                    }
+                    _ => Err(Error::UnexpectedValue(
+                        "(Panic, Array<felt252>)".to_string(),
+                    )),
+                },
+                (tag, value) => Err(Error::UnexpectedValue(format!(
+                    "{debug_name}, got variant {tag} with value {value:?}"
+                ))),
+            },
+            return_value => Ok(return_value),
+        }
+    }
+}
+
+/// Format the data of a panic: felts which are valid short strings of at least two characters are
+/// quoted, and the rest are printed as numbers.
+///
+/// Single characters aren't decoded, since small numbers (ex. `42`, which would be `'*'`) are a lot
+/// more common than one-character messages.
+pub fn decode_panic_message(data: &[Felt]) -> String {
+    let items = data
+        .iter()
+        .map(|felt| {
+            let bytes = felt.to_bytes_be();
+            let bytes = &bytes[bytes.iter().position(|&x| x != 0).unwrap_or(bytes.len())..];
+
+            if bytes.len() > 1 && bytes.iter().all(|x| x.is_ascii_graphic() || *x == b' ') {
+                format!("'{}'", String::from_utf8_lossy(bytes))
+            } else {
+                felt.to_bigint().to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match items.as_slice() {
+        [item] => item.clone(),
+        _ => format!("({})", items.join(", ")),
+    }
 }
 
 /// Starknet contract execution result.
@@ -33,6 +109,10 @@ pub struct ContractExecutionResult {
     pub failure_flag: bool,
     pub return_values: Vec<Felt>,
     pub error_msg: Option<String>,
+    /// Whether the return data was truncated to fit the
+    /// [max_retdata_len](crate::starknet::PayloadLimits::max_retdata_len) limit.
+    #[cfg_attr(feature = "with-serde", serde(default))]
+    pub truncated: bool,
 }
 
 impl ContractExecutionResult {
@@ -103,7 +183,9 @@ impl ContractExecutionResult {
                             // remove null chars
                             .filter(|b| *b != 0)
                             .collect();
-                        let str_error = String::from_utf8(bytes_err).unwrap().to_owned();
+                        // The panic data may contain arbitrary felts (ex. addresses), not only
+                        // short strings.
+                        let str_error = String::from_utf8_lossy(&bytes_err).into_owned();
 
                         error_msg = Some(str_error);
                         felt_vec
@@ -133,6 +215,121 @@ impl ContractExecutionResult {
             return_values,
             failure_flag,
             error_msg,
+            truncated: false,
         })
     }
+
+    /// Convert a [`ExecutionResult`] whose return data has been decoded with the return data limit
+    /// of `limits`, like [from_execution_result](Self::from_execution_result).
+    ///
+    /// The return data was already truncated while decoding if it exceeded the limit, in which
+    /// case `retdata_len` is its original length and the policy of `limits` is applied.
+    pub(crate) fn from_limited_execution_result(
+        result: ExecutionResult,
+        limits: &PayloadLimits,
+        retdata_len: Option<usize>,
+    ) -> Result<Self, Error> {
+        let mut result = Self::from_execution_result(result)?;
+
+        if let Some(retdata_len) = retdata_len {
+            limits
+                .check("return data", retdata_len, limits.max_retdata_len)
+                .map_err(Error::PayloadLimitExceeded)?;
+            result.truncated = true;
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "with-serde")]
+    use cairo_lang_sierra::extensions::utils::Range;
+    #[cfg(feature = "with-serde")]
+    use std::collections::HashMap;
+
+    #[test]
+    fn contract_result_from_panic() {
+        let panic_data = [Felt::from_bytes_be_slice(b"ERC20: failed"), Felt::from(-1)];
+        let result = ExecutionResult {
+            remaining_gas: Some(10),
+            return_value: JitValue::Enum {
+                tag: 1,
+                value: Box::new(JitValue::Struct {
+                    fields: vec![
+                        JitValue::Struct {
+                            fields: vec![],
+                            debug_name: None,
+                        },
+                        JitValue::Array(panic_data.map(JitValue::Felt252).to_vec()),
+                    ],
+                    debug_name: None,
+                }),
+                debug_name: None,
+            },
+            builtin_stats: BuiltinStats::default(),
+            syscall_profile: None,
+        };
+
+        let result = ContractExecutionResult::from_execution_result(result).unwrap();
+        assert!(result.failure_flag);
+        assert_eq!(result.remaining_gas, 10);
+        assert_eq!(result.return_values, panic_data);
+        assert!(result.error_msg.unwrap().starts_with("ERC20: failed"));
+    }
+
+    #[test]
+    #[cfg(feature = "with-serde")]
+    fn serde_roundtrip() {
+        let result = ExecutionResult {
+            remaining_gas: Some(u128::MAX),
+            return_value: JitValue::Enum {
+                tag: 0,
+                value: Box::new(JitValue::Struct {
+                    fields: vec![
+                        JitValue::Felt252(Felt::from(-1)),
+                        JitValue::Bytes31([0xAB; 31]),
+                        JitValue::Array(vec![JitValue::Uint8(1), JitValue::Uint8(2)]),
+                        JitValue::Felt252Dict {
+                            value: HashMap::from([(Felt::ONE, JitValue::Sint128(i128::MIN))]),
+                            debug_name: None,
+                        },
+                        JitValue::BoundedInt {
+                            value: Felt::from(3),
+                            range: Range::closed(-5, 5),
+                        },
+                    ],
+                    debug_name: None,
+                }),
+                debug_name: None,
+            },
+            builtin_stats: BuiltinStats {
+                range_check: 3,
+                ..Default::default()
+            },
+            syscall_profile: None,
+        };
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ExecutionResult>(&json).unwrap(),
+            result
+        );
+
+        let result = ContractExecutionResult {
+            remaining_gas: 10,
+            failure_flag: true,
+            return_values: vec![Felt::from(1), Felt::from(2)],
+            error_msg: Some("error".to_string()),
+            truncated: false,
+        };
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ContractExecutionResult>(&json).unwrap(),
+            result
+        );
+    }
 }
@@ -0,0 +1,369 @@
+//! # Execution snapshots
+//!
+//! Time-travel debugging support for long executions. When the program is compiled with some
+//! [snapshot statements](crate::context::CompilerConfig::with_snapshot_statements), the generated
+//! code reports the arguments of those statements (including builtin counters and the contents of
+//! any array, box or dictionary reachable from them) every time they're about to be executed.
+//!
+//! The values are decoded and stored while the program is running, so that they can be inspected or
+//! dumped to a file afterwards. It's meant to answer questions like "what was the value of X at
+//! statement N" when chasing miscompilations without having to step through the whole execution.
+//!
+//! Only the JIT executor is supported, since the reporting functions are provided by the host.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let snapshots_context = NativeContext::new()
+//!     .with_config(CompilerConfig::new().with_snapshot_statements([12, 34]));
+//! let module = snapshots_context.compile(&program, None)?;
+//! let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+//!
+//! let (result, snapshots) =
+//!     ExecutionSnapshots::record(&program, || executor.invoke_dynamic(entry_point, &[], None))?;
+//! snapshots.dump("snapshots.txt")?;
+//! ```
+
+#![cfg(feature = "with-debug-utils")]
+
+use crate::{error::Result, types::TypeBuilder, utils::with_recorder, values::JitValue};
+use cairo_lang_sierra::{
+    extensions::{
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+        starknet::StarkNetTypeConcrete,
+    },
+    ids::{ConcreteTypeId, VarId},
+    program::{Program, StatementIdx},
+    program_registry::ProgramRegistry,
+};
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    ptr::NonNull,
+};
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+struct Recorder {
+    registry: ProgramRegistry<CoreType, CoreLibfunc>,
+    snapshots: Vec<ExecutionSnapshot>,
+}
+
+/// The state of the live values at a statement, right before it was executed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionSnapshot {
+    pub statement_idx: StatementIdx,
+    pub values: Vec<(VarId, SnapshotValue)>,
+}
+
+/// A value captured by an [ExecutionSnapshot].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotValue {
+    /// A regular value.
+    Value(JitValue),
+    /// The counter of a builtin, or the remaining gas for the gas builtin.
+    Counter(u128),
+    /// A value which can't be decoded (ex. a dictionary entry or the syscall handler).
+    Opaque,
+}
+
+/// All the snapshots taken during an execution, in execution order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExecutionSnapshots {
+    snapshots: Vec<ExecutionSnapshot>,
+}
+
+impl ExecutionSnapshots {
+    /// Record the snapshots taken by the compiled `program` while running `f`.
+    ///
+    /// Only executions on the current thread are recorded. Snapshots taken outside of a `record`
+    /// call are discarded.
+    pub fn record<T>(program: &Program, f: impl FnOnce() -> T) -> Result<(T, Self)> {
+        let recorder = Recorder {
+            registry: ProgramRegistry::new(program)?,
+            snapshots: Vec::new(),
+        };
+        let (output, recorder) = with_recorder(&RECORDER, recorder, f);
+
+        Ok((
+            output,
+            Self {
+                snapshots: recorder.snapshots,
+            },
+        ))
+    }
+
+    pub fn snapshots(&self) -> &[ExecutionSnapshot] {
+        &self.snapshots
+    }
+
+    /// Return the snapshots taken at a statement, in execution order.
+    pub fn at(&self, statement_idx: StatementIdx) -> impl Iterator<Item = &ExecutionSnapshot> {
+        self.snapshots
+            .iter()
+            .filter(move |x| x.statement_idx == statement_idx)
+    }
+
+    /// Return the statements where the snapshots were taken, in execution order.
+    ///
+    /// Since snapshots are taken before invocations, when every statement of the program is
+    /// snapshotted this is the execution trace (without the returns), which can be compared
+    /// against the one from the Cairo VM to find where both executions diverge.
+    pub fn trace(&self) -> impl Iterator<Item = StatementIdx> + '_ {
+        self.snapshots.iter().map(|x| x.statement_idx)
+    }
+
+    /// Write the snapshots in a human-readable format.
+    pub fn write_to(&self, mut target: impl Write) -> io::Result<()> {
+        for (idx, snapshot) in self.snapshots.iter().enumerate() {
+            writeln!(target, "#{idx} at statement {}:", snapshot.statement_idx)?;
+            for (var_id, value) in &snapshot.values {
+                match value {
+                    SnapshotValue::Value(value) => writeln!(target, "  {var_id} = {value:?}")?,
+                    SnapshotValue::Counter(value) => writeln!(target, "  {var_id} = <{value}>")?,
+                    SnapshotValue::Opaque => writeln!(target, "  {var_id} = <opaque>")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the snapshots into a file, replacing its contents.
+    pub fn dump(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut target = BufWriter::new(File::create(path)?);
+        self.write_to(&mut target)?;
+        target.flush()
+    }
+}
+
+/// Whether the value can be decoded without taking its ownership.
+fn is_decodable(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_id: &ConcreteTypeId,
+) -> bool {
+    let Ok(type_info) = registry.get_type(type_id) else {
+        return false;
+    };
+
+    match type_info {
+        CoreTypeConcrete::Array(info)
+        | CoreTypeConcrete::Box(info)
+        | CoreTypeConcrete::Nullable(info)
+        | CoreTypeConcrete::NonZero(info)
+        | CoreTypeConcrete::Snapshot(info)
+        | CoreTypeConcrete::Felt252Dict(info)
+        | CoreTypeConcrete::SquashedFelt252Dict(info) => is_decodable(registry, &info.ty),
+        CoreTypeConcrete::Struct(info) => info.members.iter().all(|x| is_decodable(registry, x)),
+        CoreTypeConcrete::Enum(info) => {
+            !info.variants.is_empty() && info.variants.iter().all(|x| is_decodable(registry, x))
+        }
+        CoreTypeConcrete::Uninitialized(_)
+        | CoreTypeConcrete::Felt252DictEntry(_)
+        | CoreTypeConcrete::Uint128MulGuarantee(_)
+        | CoreTypeConcrete::Span(_)
+        | CoreTypeConcrete::Const(_)
+        | CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::System(_)) => false,
+        _ => !type_info.is_builtin(),
+    }
+}
+
+/// Start a snapshot. Called by the generated code before reporting the values.
+pub(crate) extern "C" fn snapshot_begin_impl(statement_idx: u64) {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.snapshots.push(ExecutionSnapshot {
+                statement_idx: StatementIdx(statement_idx as usize),
+                values: Vec::new(),
+            });
+        }
+    });
+}
+
+/// Add a value to the current snapshot. Called by the generated code with a pointer to a copy of
+/// the value, which is still owned by the program.
+pub(crate) unsafe extern "C" fn snapshot_value_impl(var_id: u64, type_id: u64, ptr: *const ()) {
+    RECORDER.with(|recorder| {
+        let mut recorder = recorder.borrow_mut();
+        let Some(Recorder {
+            registry,
+            snapshots,
+        }) = recorder.as_mut()
+        else {
+            return;
+        };
+        let Some(snapshot) = snapshots.last_mut() else {
+            return;
+        };
+
+        let type_id = ConcreteTypeId::new(type_id);
+        let value = match (registry.get_type(&type_id), NonNull::new(ptr.cast_mut())) {
+            (Ok(CoreTypeConcrete::GasBuiltin(_)), Some(ptr)) => {
+                SnapshotValue::Counter(ptr.cast::<u128>().read())
+            }
+            (Ok(CoreTypeConcrete::BuiltinCosts(_)), _)
+            | (Ok(CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::System(_))), _) => {
+                SnapshotValue::Opaque
+            }
+            (Ok(type_info), Some(ptr)) if type_info.is_builtin() => {
+                SnapshotValue::Counter(ptr.cast::<u64>().read().into())
+            }
+            (Ok(_), Some(ptr)) if is_decodable(registry, &type_id) => {
+                JitValue::peek_jit(ptr, &type_id, registry)
+                    .map_or(SnapshotValue::Opaque, SnapshotValue::Value)
+            }
+            _ => SnapshotValue::Opaque,
+        };
+
+        snapshot.values.push((VarId::new(var_id), value));
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{CompilerConfig, NativeContext},
+        executor::JitNativeExecutor,
+        utils::{find_function_id, test::load_cairo},
+        OptLevel,
+    };
+    use cairo_lang_sierra::program::Statement;
+    use starknet_types_core::felt::Felt;
+
+    #[test]
+    fn record_array_contents() {
+        let program = load_cairo! {
+            fn run_test() -> u32 {
+                let mut values = ArrayTrait::<felt252>::new();
+                values.append(1);
+                values.append(2);
+                values.append(3);
+                values.len()
+            }
+        };
+
+        // Snapshot every `array_append` invocation.
+        let statements = program
+            .1
+            .statements
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, statement)| match statement {
+                Statement::Invocation(invocation)
+                    if invocation
+                        .libfunc_id
+                        .debug_name
+                        .as_deref()
+                        .is_some_and(|x| x.starts_with("array_append")) =>
+                {
+                    Some(idx)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!statements.is_empty());
+
+        let context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_snapshot_statements(statements.clone()));
+        let module = context.compile(&program.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+
+        let (result, snapshots) = ExecutionSnapshots::record(&program.1, || {
+            executor.invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[],
+                None,
+            )
+        })
+        .unwrap();
+        assert_eq!(result.unwrap().return_value, JitValue::Uint32(3));
+
+        // The array is still owned by the program when it's recorded, so the snapshots must have
+        // left it intact.
+        let arrays = snapshots
+            .snapshots()
+            .iter()
+            .filter_map(|snapshot| {
+                assert!(statements.contains(&snapshot.statement_idx.0));
+                snapshot.values.iter().find_map(|(_, value)| match value {
+                    SnapshotValue::Value(JitValue::Array(x)) => Some(x.len()),
+                    _ => None,
+                })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(arrays, [0, 1, 2]);
+
+        let appended = snapshots
+            .snapshots()
+            .iter()
+            .flat_map(|x| &x.values)
+            .filter_map(|(_, value)| match value {
+                SnapshotValue::Value(JitValue::Felt252(x)) => Some(*x),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(appended, [Felt::from(1), Felt::from(2), Felt::from(3)]);
+
+        let mut dump = Vec::new();
+        snapshots.write_to(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        assert!(dump.starts_with(&format!("#0 at statement {}:", statements[0])));
+    }
+
+    #[test]
+    fn record_trace() {
+        let program = load_cairo! {
+            fn run_test(x: felt252) -> felt252 {
+                if x == 0 {
+                    1
+                } else {
+                    2
+                }
+            }
+        };
+        let function_id = find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0));
+        let entry_point = program
+            .1
+            .funcs
+            .iter()
+            .find(|x| &x.id == function_id)
+            .unwrap()
+            .entry_point;
+
+        let context = NativeContext::new().with_config(
+            CompilerConfig::new().with_snapshot_statements(0..program.1.statements.len()),
+        );
+        let module = context.compile(&program.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+
+        let mut traces = Vec::new();
+        for x in [0, 5] {
+            let (_, snapshots) = ExecutionSnapshots::record(&program.1, || {
+                executor.invoke_dynamic(function_id, &[JitValue::Felt252(x.into())], None)
+            })
+            .unwrap();
+
+            // Only invocations are snapshotted.
+            let trace = snapshots.trace().collect::<Vec<_>>();
+            assert_eq!(trace.first(), Some(&entry_point));
+            assert!(trace
+                .iter()
+                .all(|x| matches!(program.1.statements[x.0], Statement::Invocation(_))));
+            traces.push(trace);
+        }
+
+        // Each call takes a different branch.
+        assert_ne!(traces[0], traces[1]);
+    }
+
+    #[test]
+    fn discard_outside_of_record() {
+        snapshot_begin_impl(0);
+        assert!(RECORDER.with(|x| x.borrow().is_none()));
+    }
+}
@@ -3,14 +3,17 @@
 //! This module provides methods to execute the programs, either via JIT or compiled ahead
 //! of time. It also provides a cache to avoid recompiling previously compiled programs.
 
-pub use self::{aot::AotNativeExecutor, jit::JitNativeExecutor};
+pub use self::{aot::AotNativeExecutor, jit::JitNativeExecutor, lazy::LazyJitNativeExecutor};
 use crate::{
+    context::DeterminismMode,
+    determinism,
     error::Error,
     execution_result::{BuiltinStats, ContractExecutionResult, ExecutionResult},
     starknet::{handler::StarknetSyscallHandlerCallbacks, StarknetSyscallHandler},
+    syscall_profile::SyscallProfile,
     types::TypeBuilder,
     utils::get_integer_layout,
-    values::JitValue,
+    values::{ArrayLenLimit, JitValue},
 };
 use bumpalo::Bump;
 use cairo_lang_sierra::{
@@ -32,7 +35,21 @@ use std::{
 };
 
 mod aot;
+mod arena;
 mod jit;
+mod lazy;
+mod panic;
+mod stack;
+
+pub use self::{
+    arena::{allocation_stats, AllocationStats},
+    panic::set_panic_hook,
+};
+pub(crate) use self::{
+    arena::{free_allocation, install_allocation_hooks},
+    panic::install_panic_handler,
+    stack::ExecutionStack,
+};
 
 #[cfg(target_arch = "aarch64")]
 global_asm!(include_str!("arch/aarch64.s"));
@@ -44,12 +61,20 @@ extern "C" {
     ///
     /// The `ret_ptr` argument is only used when the first argument (the actual return pointer) is
     /// unused. Used for u8, u16, u32, u64, u128 and felt252, but not for arrays, enums or structs.
+    ///
+    /// If `stack_ptr` is not null, the function runs on the stack whose top it points to, which
+    /// must be 16-byte aligned.
+    ///
+    /// If `jump_buffer` is not null, the registers required to resume right after the call are
+    /// saved into it. Check out the [panic module](self::panic) for more info.
     #[cfg_attr(not(target_os = "macos"), link_name = "_invoke_trampoline")]
     fn invoke_trampoline(
         fn_ptr: *const c_void,
         args_ptr: *const u64,
         args_len: usize,
         ret_ptr: *mut u64,
+        stack_ptr: *mut c_void,
+        jump_buffer: *mut panic::JumpBuffer,
     );
 }
 
@@ -61,6 +86,15 @@ pub enum NativeExecutor<'m> {
 }
 
 impl<'a> NativeExecutor<'a> {
+    /// Return the gas required to call the given function, or `None` if the program was compiled
+    /// without gas costs.
+    pub fn initial_required_gas(&self, function_id: &FunctionId) -> Option<u128> {
+        match self {
+            NativeExecutor::Aot(executor) => executor.initial_required_gas(function_id),
+            NativeExecutor::Jit(executor) => executor.initial_required_gas(function_id),
+        }
+    }
+
     /// Invoke the given function by its function id, with the given arguments and gas.
     pub fn invoke_dynamic(
         &self,
@@ -142,6 +176,20 @@ impl<'m> From<JitNativeExecutor<'m>> for NativeExecutor<'m> {
 /// constructs the function call in place.
 ///
 /// To pass the arguments, they are stored in a arena.
+///
+/// When an execution stack is provided, the function runs on it instead of the current thread's
+/// stack.
+///
+/// When the program was compiled with the [error](crate::context::PanicMode::Error) or
+/// [hook](crate::context::PanicMode::Hook) panic modes, an unrecoverable error stops the invocation
+/// and is returned as an [Error::UnrecoverableError].
+///
+/// When the [determinism audit](crate::determinism) is enabled, the padding of the returned values
+/// is checked before parsing them.
+///
+/// When the syscall handler [enables it](StarknetSyscallHandler::profile_syscalls), the latency of
+/// the syscalls is recorded into the result.
+#[allow(clippy::too_many_arguments)]
 fn invoke_dynamic(
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     function_ptr: *const c_void,
@@ -149,8 +197,26 @@ fn invoke_dynamic(
     args: &[JitValue],
     gas: u128,
     mut syscall_handler: Option<impl StarknetSyscallHandler>,
+    stack: Option<&ExecutionStack>,
+    determinism_mode: DeterminismMode,
+    array_len_limit: Option<&ArrayLenLimit>,
 ) -> Result<ExecutionResult, Error> {
     tracing::info!("Invoking function with signature: {function_signature:?}.");
+
+    // Builtins are provided by the executor, so the arguments only contain the other parameters
+    // (including the zero-sized ones, which are then skipped).
+    let num_user_params = function_signature
+        .param_types
+        .iter()
+        .filter(|id| !registry.get_type(id).unwrap().is_builtin())
+        .count();
+    if args.len() != num_user_params {
+        return Err(Error::UnexpectedValue(format!(
+            "expected {num_user_params} arguments but got {}",
+            args.len()
+        )));
+    }
+
     let arena = Bump::new();
     let mut invoke_data = ArgumentMapper::new(&arena, registry);
 
@@ -185,6 +251,10 @@ fn invoke_dynamic(
         });
 
         let return_ptr = arena.alloc_layout(layout).cast::<()>();
+        if determinism_mode != DeterminismMode::Off {
+            // The generated code doesn't write the padding of the returned values.
+            unsafe { std::ptr::write_bytes(return_ptr.cast::<u8>().as_ptr(), 0, layout.size()) };
+        }
         invoke_data.push_aligned(
             get_integer_layout(64).align(),
             &[return_ptr.as_ptr() as u64],
@@ -195,6 +265,11 @@ fn invoke_dynamic(
         None
     };
 
+    let syscall_profile = syscall_handler
+        .as_ref()
+        .is_some_and(|x| x.profile_syscalls())
+        .then(SyscallProfile::start);
+
     // The Cairo compiler doesn't specify that the cheatcode syscall needs the syscall handler,
     // so we must always allocate it in case it needs it, regardless of whether it's passed
     // as an argument to the entry point or not.
@@ -213,12 +288,17 @@ fn invoke_dynamic(
 
     // Generate argument list.
     let mut iter = args.iter();
-    for type_id in function_signature.param_types.iter().filter(|id| {
-        let info = registry.get_type(id).unwrap();
-        !info.is_zst(registry)
-    }) {
+    for type_id in &function_signature.param_types {
+        let type_info = registry.get_type(type_id).unwrap();
+        if type_info.is_zst(registry) {
+            if !type_info.is_builtin() {
+                iter.next();
+            }
+            continue;
+        }
+
         // Process gas requirements and syscall handler.
-        match registry.get_type(type_id).unwrap() {
+        match type_info {
             CoreTypeConcrete::GasBuiltin(_) => invoke_data.push_aligned(
                 get_integer_layout(128).align(),
                 &[gas as u64, (gas >> 64) as u64],
@@ -253,14 +333,27 @@ fn invoke_dynamic(
     #[cfg(target_arch = "aarch64")]
     let mut ret_registers = [0; 4];
 
-    unsafe {
-        invoke_trampoline(
-            function_ptr,
-            invoke_data.invoke_data().as_ptr(),
-            invoke_data.invoke_data().len(),
-            ret_registers.as_mut_ptr(),
-        );
-    }
+    // Everything the program allocates from now on belongs to the invocation, and whatever is
+    // left once the results have been decoded is reclaimed.
+    let execution_arena = arena::ExecutionArena::enter();
+    let invocation = panic::catch_unrecoverable_error(|jump_buffer| {
+        let invoke = |stack_ptr| unsafe {
+            invoke_trampoline(
+                function_ptr,
+                invoke_data.invoke_data().as_ptr(),
+                invoke_data.invoke_data().len(),
+                ret_registers.as_mut_ptr(),
+                stack_ptr,
+                jump_buffer,
+            );
+        };
+        match stack {
+            Some(stack) => stack.with_top(invoke),
+            None => invoke(null_mut()),
+        }
+    });
+
+    let syscall_profile = syscall_profile.map(|x| x.finish());
 
     // If the syscall handler was changed, then reset the previous one.
     // It's only necessary to restore the pointer if it's been modified i.e. if previous_syscall_handler is Some(...)
@@ -269,6 +362,9 @@ fn invoke_dynamic(
         crate::starknet::SYSCALL_HANDLER_VTABLE.set(previous_syscall_handler);
     }
 
+    // The returned values are garbage if the invocation was interrupted.
+    invocation?;
+
     // Parse final gas.
     unsafe fn read_value<T>(ptr: &mut NonNull<()>) -> &T {
         let align_offset = ptr
@@ -325,6 +421,44 @@ fn invoke_dynamic(
         }
     }
 
+    // Check the padding of the returned value before it's parsed (and possibly freed).
+    if let Some(ret_type) = function_signature
+        .ret_types
+        .last()
+        .filter(|_| determinism_mode != DeterminismMode::Off)
+    {
+        let type_info = registry.get_type(ret_type)?;
+        let value_ptr = match return_ptr {
+            Some(return_ptr) => {
+                let align_offset = return_ptr
+                    .cast::<u8>()
+                    .as_ptr()
+                    .align_offset(type_info.layout(registry)?.align());
+                Some((
+                    unsafe {
+                        NonNull::new_unchecked(return_ptr.cast::<u8>().as_ptr().add(align_offset))
+                            .cast()
+                    },
+                    ret_type,
+                ))
+            }
+            None => match type_info {
+                CoreTypeConcrete::Box(info) => {
+                    NonNull::new(ret_registers[0] as *mut ()).map(|ptr| (ptr, &info.ty))
+                }
+                _ => None,
+            },
+        };
+
+        if let Some((value_ptr, type_id)) = value_ptr {
+            if !type_info.is_builtin() {
+                unsafe {
+                    determinism::audit_return_value(determinism_mode, value_ptr, type_id, registry)?
+                };
+            }
+        }
+    }
+
     // Parse return values.
     let return_value = function_signature
         .ret_types
@@ -339,6 +473,7 @@ fn invoke_dynamic(
                     registry,
                     return_ptr,
                     ret_registers,
+                    array_len_limit,
                     // TODO: Consider returning an Option<JitValue> as return_value instead
                     // As cairo functions can not have a return value
                 ))
@@ -351,13 +486,16 @@ fn invoke_dynamic(
             })
         })?;
 
-    // FIXME: Arena deallocation.
-    std::mem::forget(arena);
+    // Everything the program may keep (ie. array data) is heap-allocated, and the return values
+    // have already been copied out of the return pointer, so the arena can be freed now.
+    drop(arena);
+    drop(execution_arena);
 
     Ok(ExecutionResult {
         remaining_gas,
         return_value,
         builtin_stats,
+        syscall_profile,
     })
 }
 
@@ -437,7 +575,7 @@ impl<'a> ArgumentMapper<'a> {
                 let ptr = if values.is_empty() {
                     null_mut()
                 } else {
-                    unsafe { libc::realloc(null_mut(), type_layout.size() * values.len()) }
+                    unsafe { crate::types::array::alloc_data(type_layout.size() * values.len()) }
                 };
 
                 for (idx, value) in values.iter().enumerate() {
@@ -532,7 +670,7 @@ impl<'a> ArgumentMapper<'a> {
             (CoreTypeConcrete::Bytes31(_), JitValue::Bytes31(value)) => {
                 self.push_aligned(
                     get_integer_layout(248).align(),
-                    &Felt::from_bytes_be_slice(value).to_le_digits(),
+                    &Felt::from_bytes_le_slice(value).to_le_digits(),
                 );
             }
             (CoreTypeConcrete::Felt252Dict(_), JitValue::Felt252Dict { .. }) => {
@@ -633,6 +771,7 @@ fn parse_result(
     mut return_ptr: Option<NonNull<()>>,
     #[cfg(target_arch = "x86_64")] mut ret_registers: [u64; 2],
     #[cfg(target_arch = "aarch64")] mut ret_registers: [u64; 4],
+    array_len_limit: Option<&ArrayLenLimit>,
 ) -> Result<JitValue, Error> {
     let type_info = registry.get_type(type_id).unwrap();
 
@@ -653,16 +792,16 @@ fn parse_result(
 
     match type_info {
         CoreTypeConcrete::Array(_) => {
-            Ok(JitValue::from_jit(return_ptr.unwrap(), type_id, registry))
+            JitValue::from_jit(return_ptr.unwrap(), type_id, registry, array_len_limit)
         }
         CoreTypeConcrete::Box(info) => unsafe {
             let ptr = return_ptr.unwrap_or(NonNull::new_unchecked(ret_registers[0] as *mut ()));
-            let value = JitValue::from_jit(ptr, &info.ty, registry);
-            libc::free(ptr.cast().as_ptr());
+            let value = JitValue::from_jit(ptr, &info.ty, registry, array_len_limit)?;
+            free_allocation(ptr.cast().as_ptr());
             Ok(value)
         },
         CoreTypeConcrete::EcPoint(_) | CoreTypeConcrete::EcState(_) => {
-            Ok(JitValue::from_jit(return_ptr.unwrap(), type_id, registry))
+            JitValue::from_jit(return_ptr.unwrap(), type_id, registry, array_len_limit)
         }
         CoreTypeConcrete::Felt252(_)
         | CoreTypeConcrete::StarkNet(
@@ -671,7 +810,7 @@ fn parse_result(
             | StarkNetTypeConcrete::StorageAddress(_)
             | StarkNetTypeConcrete::StorageBaseAddress(_),
         ) => match return_ptr {
-            Some(return_ptr) => Ok(JitValue::from_jit(return_ptr, type_id, registry)),
+            Some(return_ptr) => JitValue::from_jit(return_ptr, type_id, registry, array_len_limit),
             None => {
                 #[cfg(target_arch = "x86_64")]
                 // Since x86_64's return values hold at most two different 64bit registers,
@@ -688,7 +827,7 @@ fn parse_result(
             }
         },
         CoreTypeConcrete::Bytes31(_) => match return_ptr {
-            Some(return_ptr) => Ok(JitValue::from_jit(return_ptr, type_id, registry)),
+            Some(return_ptr) => JitValue::from_jit(return_ptr, type_id, registry, array_len_limit),
             None => {
                 #[cfg(target_arch = "x86_64")]
                 // Since x86_64's return values hold at most two different 64bit registers,
@@ -747,9 +886,13 @@ fn parse_result(
                 ((ret_registers[1] as i128) << 64) | ret_registers[0] as i128,
             )),
         },
-        CoreTypeConcrete::NonZero(info) => {
-            parse_result(&info.ty, registry, return_ptr, ret_registers)
-        }
+        CoreTypeConcrete::NonZero(info) => parse_result(
+            &info.ty,
+            registry,
+            return_ptr,
+            ret_registers,
+            array_len_limit,
+        ),
         CoreTypeConcrete::Nullable(info) => unsafe {
             let ptr = return_ptr.map_or(ret_registers[0] as *mut (), |x| {
                 *x.cast::<*mut ()>().as_ref()
@@ -758,8 +901,8 @@ fn parse_result(
                 Ok(JitValue::Null)
             } else {
                 let ptr = NonNull::new_unchecked(ptr);
-                let value = JitValue::from_jit(ptr, &info.ty, registry);
-                libc::free(ptr.as_ptr().cast());
+                let value = JitValue::from_jit(ptr, &info.ty, registry, array_len_limit)?;
+                free_allocation(ptr.as_ptr().cast());
                 Ok(value)
             }
         },
@@ -809,7 +952,12 @@ fn parse_result(
             };
 
             let value = match ptr {
-                Ok(ptr) => Box::new(JitValue::from_jit(ptr, &info.variants[tag], registry)),
+                Ok(ptr) => Box::new(JitValue::from_jit(
+                    ptr,
+                    &info.variants[tag],
+                    registry,
+                    array_len_limit,
+                )?),
                 Err(offset) => {
                     ret_registers.copy_within(offset.., 0);
                     Box::new(parse_result(
@@ -817,6 +965,7 @@ fn parse_result(
                         registry,
                         None,
                         ret_registers,
+                        array_len_limit,
                     )?)
                 }
             };
@@ -834,14 +983,14 @@ fn parse_result(
                     debug_name: type_id.debug_name.as_deref().map(ToString::to_string),
                 })
             } else {
-                Ok(JitValue::from_jit(return_ptr.unwrap(), type_id, registry))
+                JitValue::from_jit(return_ptr.unwrap(), type_id, registry, array_len_limit)
             }
         }
         CoreTypeConcrete::Felt252Dict(_) | CoreTypeConcrete::SquashedFelt252Dict(_) => unsafe {
             let ptr = return_ptr.unwrap_or(NonNull::new_unchecked(
                 addr_of_mut!(ret_registers[0]) as *mut ()
             ));
-            let value = JitValue::from_jit(ptr, type_id, registry);
+            let value = JitValue::from_jit(ptr, type_id, registry, array_len_limit)?;
             Ok(value)
         },
 
@@ -869,11 +1018,94 @@ fn parse_result(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        context::NativeContext,
+        utils::{find_function_id, test::load_cairo},
+        OptLevel,
+    };
     use cairo_lang_sierra::extensions::types::InfoOnlyConcreteType;
     use cairo_lang_sierra::extensions::types::TypeInfo;
     use cairo_lang_sierra::program::ConcreteTypeLongId;
     use cairo_lang_sierra::ProgramParser;
 
+    #[test]
+    fn test_native_executor_dispatch() {
+        let (module_name, program) = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a * b + 1
+            }
+        };
+        let function_id =
+            find_function_id(&program, &format!("{module_name}::{module_name}::run_test"));
+
+        let native_context = NativeContext::new();
+        let executors: [NativeExecutor; 2] = [
+            AotNativeExecutor::from_native_module(
+                native_context.compile(&program, None).unwrap(),
+                OptLevel::None,
+            )
+            .into(),
+            JitNativeExecutor::from_native_module(
+                native_context.compile(&program, None).unwrap(),
+                OptLevel::None,
+            )
+            .into(),
+        ];
+
+        for executor in executors {
+            let result = executor
+                .invoke_dynamic(
+                    function_id,
+                    &[JitValue::Felt252(6.into()), JitValue::Felt252(7.into())],
+                    None,
+                )
+                .unwrap();
+            assert_eq!(result.return_value, JitValue::Felt252(43.into()));
+        }
+    }
+
+    #[test]
+    fn test_invoke_dynamic_argument_count() {
+        let (module_name, program) = load_cairo! {
+            fn run_test(a: u32, b: u32) -> u32 {
+                a + b
+            }
+        };
+        let function_id =
+            find_function_id(&program, &format!("{module_name}::{module_name}::run_test"));
+
+        let native_context = NativeContext::new();
+        let executor = JitNativeExecutor::from_native_module(
+            native_context.compile(&program, None).unwrap(),
+            OptLevel::None,
+        );
+
+        // The range check builtin is not part of the arguments.
+        let result = executor
+            .invoke_dynamic(
+                function_id,
+                &[JitValue::Uint32(2), JitValue::Uint32(3)],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            result.return_value,
+            JitValue::Enum {
+                tag: 0,
+                value: Box::new(JitValue::Struct {
+                    fields: vec![JitValue::Uint32(5)],
+                    debug_name: None,
+                }),
+                debug_name: None,
+            }
+        );
+
+        assert!(matches!(
+            executor.invoke_dynamic(function_id, &[JitValue::Uint32(2)], None),
+            Err(Error::UnexpectedValue(_))
+        ));
+    }
+
     #[test]
     fn test_argument_mapper_push_sint8() {
         let program = ProgramParser::new().parse("").unwrap();
@@ -1,34 +1,42 @@
+use super::{
+    install_allocation_hooks, install_panic_handler, stack::EXECUTION_STACK_SIZE, ExecutionStack,
+};
 use crate::{
+    context::{DeterminismMode, NativeContext},
     error::Error,
     execution_result::{ContractExecutionResult, ExecutionResult},
-    metadata::gas::GasMetadata,
+    metadata::{determinism::DeterminismMeta, gas::GasMetadata, panic::PanicMeta, MetadataStorage},
     module::NativeModule,
     starknet::{DummySyscallHandler, StarknetSyscallHandler},
     utils::generate_function_name,
-    values::JitValue,
-    OptLevel,
+    values::{ArrayLenLimit, JitValue},
+    InMemoryObject, OptLevel, TargetConfig,
 };
 use cairo_lang_sierra::{
     extensions::core::{CoreLibfunc, CoreType},
     ids::FunctionId,
-    program::FunctionSignature,
+    program::{FunctionSignature, Program},
     program_registry::ProgramRegistry,
 };
 use educe::Educe;
 use libc::c_void;
 use libloading::Library;
 use starknet_types_core::felt::Felt;
+use std::{path::Path, ptr::null_mut};
 use tempfile::NamedTempFile;
+use tracing::instrument;
 
 #[derive(Educe)]
 #[educe(Debug)]
 pub struct AotNativeExecutor {
     #[educe(Debug(ignore))]
-    library: Library,
+    library: LoadedLibrary,
     #[educe(Debug(ignore))]
     registry: ProgramRegistry<CoreType, CoreLibfunc>,
 
     gas_metadata: GasMetadata,
+    stack: Option<ExecutionStack>,
+    determinism_mode: DeterminismMode,
 }
 
 impl AotNativeExecutor {
@@ -38,32 +46,137 @@ impl AotNativeExecutor {
         gas_metadata: GasMetadata,
     ) -> Self {
         Self {
-            library,
+            library: LoadedLibrary::Shared(library),
             registry,
             gas_metadata,
+            stack: None,
+            determinism_mode: DeterminismMode::Off,
         }
     }
 
+    /// Check the values returned by the entry points for nondeterministic behavior. Executors
+    /// created from a [`NativeModule`] use the mode it was compiled with.
+    pub fn with_determinism_mode(mut self, determinism_mode: DeterminismMode) -> Self {
+        self.determinism_mode = determinism_mode;
+        self
+    }
+
+    /// Run the entry points on a dedicated stack, which is required by programs with deeply
+    /// recursive functions. Executors created from a [`NativeModule`] enable it automatically when
+    /// the program has recursive functions.
+    pub fn with_execution_stack(mut self, enabled: bool) -> Self {
+        self.stack = enabled.then(|| ExecutionStack::new(EXECUTION_STACK_SIZE));
+        self
+    }
+
     /// Utility to convert a [`NativeModule`] into an [`AotNativeExecutor`].
     pub fn from_native_module(module: NativeModule, opt_level: OptLevel) -> Self {
+        Self::from_native_module_with_target(module, opt_level, &TargetConfig::default())
+    }
+
+    /// Like [from_native_module](Self::from_native_module), but compiling for the given target
+    /// instead of the host's CPU. The target has to be supported by the host, since the library is
+    /// loaded right away.
+    pub fn from_native_module_with_target(
+        module: NativeModule,
+        opt_level: OptLevel,
+        target: &TargetConfig,
+    ) -> Self {
         let NativeModule {
             module,
             registry,
-            mut metadata,
+            metadata,
         } = module;
 
         let library_path = NamedTempFile::new().unwrap().into_temp_path();
 
-        let object_data = crate::module_to_object(&module, opt_level).unwrap();
+        let object_data = crate::module_to_object_with_target(&module, opt_level, target).unwrap();
         crate::object_to_shared_lib(&object_data, &library_path).unwrap();
 
+        Self::from_library(
+            unsafe { Library::new(library_path).unwrap() },
+            registry,
+            metadata,
+        )
+    }
+
+    /// Like [from_native_module](Self::from_native_module), but the object is linked and loaded in
+    /// memory, without an external linker nor temporary files. Check out [InMemoryObject] for more
+    /// info.
+    pub fn from_native_module_in_memory(
+        module: NativeModule,
+        opt_level: OptLevel,
+    ) -> Result<Self, Error> {
+        let NativeModule {
+            module,
+            registry,
+            metadata,
+        } = module;
+
+        let object_data = crate::module_to_object(&module, opt_level)
+            .map_err(|e| Error::LLVMCompileError(e.to_string()))?;
+        let object = InMemoryObject::load(&object_data)
+            .map_err(|e| Error::LLVMCompileError(e.to_string()))?;
+
+        Ok(Self::from_loaded_library(
+            LoadedLibrary::InMemory(object),
+            registry,
+            metadata,
+        ))
+    }
+
+    /// Load a shared library compiled earlier (ex. by `cairo-native-compile`, or by another
+    /// process) from a program. The metadata required by the executor is computed again from the
+    /// program, so it doesn't have to be compiled again.
+    ///
+    /// # Safety
+    ///
+    /// The shared library is loaded and run as is. It must have been compiled from the same program
+    /// by the same version of Cairo Native, using a context with the same configuration.
+    pub unsafe fn from_shared_lib(
+        context: &NativeContext,
+        program: &Program,
+        path: &Path,
+    ) -> Result<Self, Error> {
+        let (registry, metadata) = context.executor_metadata(program)?;
+        let library = Library::new(path)
+            .map_err(|e| Error::Error(format!("failed to load {}: {e}", path.display())))?;
+
+        Ok(Self::from_library(library, registry, metadata))
+    }
+
+    /// Create the executor of an already loaded shared library, using the metadata of the program
+    /// it was compiled from.
+    pub(crate) fn from_library(
+        library: Library,
+        registry: ProgramRegistry<CoreType, CoreLibfunc>,
+        metadata: MetadataStorage,
+    ) -> Self {
+        Self::from_loaded_library(LoadedLibrary::Shared(library), registry, metadata)
+    }
+
+    fn from_loaded_library(
+        library: LoadedLibrary,
+        registry: ProgramRegistry<CoreType, CoreLibfunc>,
+        mut metadata: MetadataStorage,
+    ) -> Self {
+        unsafe {
+            install_panic_handler(PanicMeta::mode_of(&metadata), |symbol| {
+                library.get(symbol).unwrap_or(null_mut())
+            });
+            install_allocation_hooks(|symbol| library.get(symbol).unwrap_or(null_mut()));
+        }
+
         Self {
-            library: unsafe { Library::new(library_path).unwrap() },
+            library,
             registry,
             gas_metadata: metadata.remove().unwrap(),
+            stack: ExecutionStack::for_module(&metadata),
+            determinism_mode: DeterminismMeta::mode_of(&metadata),
         }
     }
 
+    #[instrument(skip_all, fields(function = %function_id))]
     pub fn invoke_dynamic(
         &self,
         function_id: &FunctionId,
@@ -82,9 +195,13 @@ impl AotNativeExecutor {
             args,
             available_gas,
             Option::<DummySyscallHandler>::None,
+            self.stack.as_ref(),
+            self.determinism_mode,
+            None,
         )
     }
 
+    #[instrument(skip_all, fields(function = %function_id))]
     pub fn invoke_dynamic_with_syscall_handler(
         &self,
         function_id: &FunctionId,
@@ -104,9 +221,13 @@ impl AotNativeExecutor {
             args,
             available_gas,
             Some(syscall_handler),
+            self.stack.as_ref(),
+            self.determinism_mode,
+            None,
         )
     }
 
+    #[instrument(skip_all, fields(function = %function_id))]
     pub fn invoke_contract_dynamic(
         &self,
         function_id: &FunctionId,
@@ -118,21 +239,38 @@ impl AotNativeExecutor {
             .gas_metadata
             .get_initial_available_gas(function_id, gas)
             .map_err(crate::error::Error::GasMetadataError)?;
+        let limits = syscall_handler.payload_limits();
+        let array_len_limit = limits.max_retdata_len.map(ArrayLenLimit::new);
+
+        ContractExecutionResult::from_limited_execution_result(
+            super::invoke_dynamic(
+                &self.registry,
+                self.find_function_ptr(function_id),
+                self.extract_signature(function_id),
+                &[JitValue::Struct {
+                    fields: vec![JitValue::Array(
+                        args.iter().cloned().map(JitValue::Felt252).collect(),
+                    )],
+                    // TODO: Populate `debug_name`.
+                    debug_name: None,
+                }],
+                available_gas,
+                Some(syscall_handler),
+                self.stack.as_ref(),
+                self.determinism_mode,
+                array_len_limit.as_ref(),
+            )?,
+            &limits,
+            array_len_limit.as_ref().and_then(ArrayLenLimit::exceeded),
+        )
+    }
 
-        ContractExecutionResult::from_execution_result(super::invoke_dynamic(
-            &self.registry,
-            self.find_function_ptr(function_id),
-            self.extract_signature(function_id),
-            &[JitValue::Struct {
-                fields: vec![JitValue::Array(
-                    args.iter().cloned().map(JitValue::Felt252).collect(),
-                )],
-                // TODO: Populate `debug_name`.
-                debug_name: None,
-            }],
-            available_gas,
-            Some(syscall_handler),
-        )?)
+    /// Return the gas required to call the given function, or `None` if the program was compiled
+    /// without gas costs.
+    ///
+    /// The gas passed when invoking the function has to be at least this amount.
+    pub fn initial_required_gas(&self, function_id: &FunctionId) -> Option<u128> {
+        self.gas_metadata.initial_required_gas(function_id)
     }
 
     pub fn find_function_ptr(&self, function_id: &FunctionId) -> *mut c_void {
@@ -140,13 +278,7 @@ impl AotNativeExecutor {
         let function_name = format!("_mlir_ciface_{function_name}");
 
         // Arguments and return values are hardcoded since they'll be handled by the trampoline.
-        unsafe {
-            self.library
-                .get::<extern "C" fn()>(function_name.as_bytes())
-                .unwrap()
-                .into_raw()
-                .into_raw()
-        }
+        self.library.get(&function_name).unwrap()
     }
 
     fn extract_signature(&self, function_id: &FunctionId) -> &FunctionSignature {
@@ -154,15 +286,34 @@ impl AotNativeExecutor {
     }
 }
 
+/// The code of a program, loaded either as a shared library or as an in-memory object.
+enum LoadedLibrary {
+    Shared(Library),
+    InMemory(InMemoryObject),
+}
+
+impl LoadedLibrary {
+    /// Return the address of a symbol, or `None` if it isn't defined.
+    fn get(&self, symbol: &str) -> Option<*mut c_void> {
+        match self {
+            Self::Shared(library) => unsafe {
+                library
+                    .get::<*mut c_void>(symbol.as_bytes())
+                    .ok()
+                    .map(|symbol| *symbol)
+            },
+            Self::InMemory(object) => object.get(symbol).map(|x| x.as_ptr()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        context::NativeContext,
         starknet_stub::StubSyscallHandler,
         utils::test::{load_cairo, load_starknet},
     };
-    use cairo_lang_sierra::program::Program;
     use rstest::*;
 
     #[fixture]
@@ -223,6 +374,53 @@ mod tests {
         assert_eq!(result.return_value, JitValue::Felt252(Felt::from(42)));
     }
 
+    #[rstest]
+    fn test_invoke_dynamic_from_shared_lib(program: Program) {
+        let native_context = NativeContext::new();
+        let library_path = NamedTempFile::new().unwrap().into_temp_path();
+        {
+            let module = native_context
+                .compile(&program, None)
+                .expect("failed to compile context");
+            let object_data = crate::module_to_object(module.module(), OptLevel::default())
+                .expect("failed to compile the module");
+            crate::object_to_shared_lib(&object_data, &library_path)
+                .expect("failed to link the shared library");
+        }
+
+        let executor =
+            unsafe { AotNativeExecutor::from_shared_lib(&native_context, &program, &library_path) }
+                .expect("failed to load the shared library");
+
+        // The first function in the program is `run_test`.
+        let entrypoint_function_id = &program.funcs.first().expect("should have a function").id;
+
+        let result = executor
+            .invoke_dynamic(entrypoint_function_id, &[], Some(u128::MAX))
+            .unwrap();
+
+        assert_eq!(result.return_value, JitValue::Felt252(Felt::from(42)));
+    }
+
+    #[rstest]
+    fn test_invoke_dynamic_in_memory(program: Program) {
+        let native_context = NativeContext::new();
+        let module = native_context
+            .compile(&program, None)
+            .expect("failed to compile context");
+        let executor =
+            AotNativeExecutor::from_native_module_in_memory(module, OptLevel::default()).unwrap();
+
+        // The first function in the program is `run_test`.
+        let entrypoint_function_id = &program.funcs.first().expect("should have a function").id;
+
+        let result = executor
+            .invoke_dynamic(entrypoint_function_id, &[], Some(u128::MAX))
+            .unwrap();
+
+        assert_eq!(result.return_value, JitValue::Felt252(Felt::from(42)));
+    }
+
     #[rstest]
     fn test_invoke_dynamic_with_syscall_handler(program: Program) {
         let native_context = NativeContext::new();
@@ -0,0 +1,277 @@
+//! # Execution arena
+//!
+//! The generated code allocates its memory through the allocation hooks installed by the executor
+//! (see [the realloc bindings](crate::metadata::realloc_bindings)). While an invocation runs, the
+//! hooks register the memory it allocates in its arena, and forget about it once it's freed.
+//!
+//! When the invocation is over and its results have been decoded, whatever is still registered is
+//! no longer reachable (ex. the memory owned by the frames discarded by an unrecoverable error), so
+//! the arena reclaims it. The memory allocated outside of an invocation (ex. the arguments) is
+//! never registered, and it's freed normally.
+
+use crate::metadata::realloc_bindings::{FREE_HOOK_SYMBOL, REALLOC_HOOK_SYMBOL};
+use libc::c_void;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+};
+
+thread_local! {
+    /// The live allocations of the innermost invocation running on this thread.
+    static ARENA: RefCell<Option<HashSet<usize>>> = const { RefCell::new(None) };
+    static STATS: Cell<AllocationStats> = const {
+        Cell::new(AllocationStats {
+            allocations: 0,
+            deallocations: 0,
+            reclaimed: 0,
+        })
+    };
+}
+
+/// Allocation statistics of the invocations run on a thread.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct AllocationStats {
+    /// The allocations made by the generated code during the invocations.
+    pub allocations: u64,
+    /// The allocations freed by the program, or by the executor when decoding the results.
+    pub deallocations: u64,
+    /// The allocations reclaimed by the arena after the invocation.
+    pub reclaimed: u64,
+}
+
+impl AllocationStats {
+    /// The allocations which haven't been released yet. It's always zero between invocations.
+    pub fn live(&self) -> u64 {
+        self.allocations - self.deallocations - self.reclaimed
+    }
+}
+
+/// Return the allocation statistics of the invocations run on the current thread so far.
+pub fn allocation_stats() -> AllocationStats {
+    STATS.get()
+}
+
+fn update_stats(f: impl FnOnce(&mut AllocationStats)) {
+    let mut stats = STATS.get();
+    f(&mut stats);
+    STATS.set(stats);
+}
+
+/// Point the allocation hook globals of a loaded module, found using `lookup`, to the hooks of the
+/// arena. Modules which never allocate don't have the globals.
+///
+/// # Safety
+///
+/// `lookup` must return the address of the symbols of the module (or null if they don't exist).
+pub(crate) unsafe fn install_allocation_hooks(lookup: impl Fn(&str) -> *mut c_void) {
+    let hooks: [(&str, *const c_void); 2] = [
+        (REALLOC_HOOK_SYMBOL, realloc_hook as *const c_void),
+        (FREE_HOOK_SYMBOL, free_hook as *const c_void),
+    ];
+
+    for (symbol, hook) in hooks {
+        let global = lookup(symbol);
+        if !global.is_null() {
+            global.cast::<*const c_void>().write(hook);
+        }
+    }
+}
+
+/// The arena of an invocation. The memory allocated by the generated code while it's alive is
+/// registered in it, and whatever hasn't been freed when it's dropped is reclaimed.
+pub(crate) struct ExecutionArena {
+    previous: Option<HashSet<usize>>,
+}
+
+impl ExecutionArena {
+    pub fn enter() -> Self {
+        Self {
+            previous: ARENA.replace(Some(HashSet::new())),
+        }
+    }
+}
+
+impl Drop for ExecutionArena {
+    fn drop(&mut self) {
+        let allocations = ARENA.replace(self.previous.take()).unwrap_or_default();
+
+        update_stats(|stats| stats.reclaimed += allocations.len() as u64);
+        for ptr in allocations {
+            unsafe { libc::free(ptr as *mut c_void) };
+        }
+    }
+}
+
+/// Free memory allocated by the generated code, forgetting about it if it's registered in the
+/// current arena.
+///
+/// # Safety
+///
+/// Same as `libc::free`.
+pub(crate) unsafe fn free_allocation(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let was_registered = ARENA.with_borrow_mut(|arena| {
+        arena
+            .as_mut()
+            .is_some_and(|arena| arena.remove(&(ptr as usize)))
+    });
+    if was_registered {
+        update_stats(|stats| stats.deallocations += 1);
+    }
+
+    libc::free(ptr);
+}
+
+unsafe extern "C" fn realloc_hook(ptr: *mut c_void, len: u64) -> *mut c_void {
+    let new_ptr = libc::realloc(ptr, len as usize);
+    if new_ptr.is_null() {
+        // The original allocation is left untouched on failure.
+        return new_ptr;
+    }
+
+    ARENA.with_borrow_mut(|arena| {
+        if let Some(arena) = arena {
+            // Growing memory from outside of the invocation (ex. an argument) makes it owned by
+            // the invocation from now on.
+            if ptr.is_null() || !arena.remove(&(ptr as usize)) {
+                update_stats(|stats| stats.allocations += 1);
+            }
+            arena.insert(new_ptr as usize);
+        }
+    });
+
+    new_ptr
+}
+
+unsafe extern "C" fn free_hook(ptr: *mut c_void) {
+    free_allocation(ptr);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{CompilerConfig, NativeContext, PanicMode},
+        error::{Error, Result},
+        executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
+        libfuncs::LibfuncHelper,
+        metadata::{panic::PanicMeta, MetadataStorage},
+        plugin::{LibfuncPlugin, PluginApiVersion, PLUGIN_API_VERSION},
+        utils::{find_function_id, test::load_cairo},
+        values::JitValue,
+        OptLevel,
+    };
+    use cairo_lang_sierra::{
+        extensions::core::{CoreConcreteLibfunc, CoreLibfunc, CoreType},
+        ids::GenericLibfuncId,
+        program_registry::ProgramRegistry,
+    };
+    use melior::{
+        ir::{Block, Location},
+        Context,
+    };
+
+    /// Makes `felt252_add` always fail with an unrecoverable error.
+    struct FailingAddPlugin;
+
+    impl LibfuncPlugin for FailingAddPlugin {
+        fn name(&self) -> &str {
+            "failing-add"
+        }
+
+        fn api_version(&self) -> PluginApiVersion {
+            PLUGIN_API_VERSION
+        }
+
+        fn generic_libfuncs(&self) -> Vec<GenericLibfuncId> {
+            vec!["felt252_add".into()]
+        }
+
+        fn build<'ctx, 'this>(
+            &self,
+            context: &'ctx Context,
+            _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+            entry: &'this Block<'ctx>,
+            location: Location<'ctx>,
+            helper: &LibfuncHelper<'ctx, 'this>,
+            metadata: &mut MetadataStorage,
+            _info: &CoreConcreteLibfunc,
+        ) -> Result<()> {
+            PanicMeta::get(metadata).build_panic(context, helper, entry, location, "failed add")
+        }
+    }
+
+    #[test]
+    fn returned_values_are_released() {
+        let program = load_cairo! {
+            fn run_test() -> (Array<Array<felt252>>, Box<felt252>) {
+                let mut values = array![1, 2, 3];
+                values.append(4);
+                (array![values, array![5]], BoxTrait::new(6))
+            }
+        };
+        let function_id = find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0));
+
+        let context = NativeContext::new();
+        let executors: [NativeExecutor; 2] = [
+            AotNativeExecutor::from_native_module(
+                context.compile(&program.1, None).unwrap(),
+                OptLevel::None,
+            )
+            .into(),
+            JitNativeExecutor::from_native_module(
+                context.compile(&program.1, None).unwrap(),
+                OptLevel::None,
+            )
+            .into(),
+        ];
+
+        for executor in executors {
+            let before = allocation_stats();
+            executor.invoke_dynamic(function_id, &[], None).unwrap();
+            let after = allocation_stats();
+
+            // Everything was released while decoding the results.
+            assert!(after.allocations > before.allocations);
+            assert_eq!(after.reclaimed, before.reclaimed);
+            assert_eq!(after.live(), 0);
+        }
+    }
+
+    #[test]
+    fn interrupted_invocation_is_reclaimed() {
+        let program = load_cairo! {
+            fn run_test() -> Array<felt252> {
+                let mut values = array![1, 2];
+                let value = *values.at(0) + *values.at(1);
+                values.append(value);
+                values
+            }
+        };
+
+        let context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_panic_mode(PanicMode::Error))
+            .with_libfunc_plugin(FailingAddPlugin)
+            .unwrap();
+        let module = context.compile(&program.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+
+        let before = allocation_stats();
+        let error = executor
+            .invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[],
+                None,
+            )
+            .unwrap_err();
+        let after = allocation_stats();
+
+        assert!(matches!(error, Error::UnrecoverableError(_)));
+        // The array was still alive when the invocation was interrupted.
+        assert!(after.reclaimed > before.reclaimed);
+        assert_eq!(after.live(), 0);
+    }
+}
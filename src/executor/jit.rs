@@ -1,11 +1,13 @@
+use super::{install_allocation_hooks, install_panic_handler, ExecutionStack};
 use crate::{
+    context::DeterminismMode,
     error::Error,
     execution_result::{ContractExecutionResult, ExecutionResult},
-    metadata::gas::GasMetadata,
+    metadata::{determinism::DeterminismMeta, gas::GasMetadata, panic::PanicMeta},
     module::NativeModule,
     starknet::{DummySyscallHandler, StarknetSyscallHandler},
     utils::{create_engine, generate_function_name},
-    values::JitValue,
+    values::{ArrayLenLimit, JitValue},
     OptLevel,
 };
 use cairo_lang_sierra::{
@@ -17,8 +19,12 @@ use cairo_lang_sierra::{
 use libc::c_void;
 use melior::{ir::Module, ExecutionEngine};
 use starknet_types_core::felt::Felt;
+use tracing::instrument;
 
 /// A MLIR JIT execution engine in the context of Cairo Native.
+///
+/// The JIT-compiled code is owned by the executor and released when it's dropped. Values returned
+/// by the program are copied out before returning, so they remain valid afterwards.
 pub struct JitNativeExecutor<'m> {
     engine: ExecutionEngine,
 
@@ -26,8 +32,15 @@ pub struct JitNativeExecutor<'m> {
     registry: ProgramRegistry<CoreType, CoreLibfunc>,
 
     gas_metadata: GasMetadata,
+    stack: Option<ExecutionStack>,
+    determinism_mode: DeterminismMode,
 }
 
+// After creation the engine is only used to look up symbols, and the module is never modified. The
+// state of an invocation is kept by the thread running it.
+unsafe impl Send for JitNativeExecutor<'_> {}
+unsafe impl Sync for JitNativeExecutor<'_> {}
+
 impl std::fmt::Debug for JitNativeExecutor<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("JitNativeExecutor")
@@ -45,11 +58,21 @@ impl<'m> JitNativeExecutor<'m> {
             metadata,
         } = native_module;
 
+        let engine = create_engine(&module, &metadata, opt_level);
+        unsafe {
+            install_panic_handler(PanicMeta::mode_of(&metadata), |symbol| {
+                engine.lookup(symbol).cast()
+            });
+            install_allocation_hooks(|symbol| engine.lookup(symbol).cast());
+        }
+
         Self {
-            engine: create_engine(&module, &metadata, opt_level),
+            engine,
             module,
             registry,
             gas_metadata: metadata.get::<GasMetadata>().cloned().unwrap(),
+            stack: ExecutionStack::for_module(&metadata),
+            determinism_mode: DeterminismMeta::mode_of(&metadata),
         }
     }
 
@@ -64,6 +87,7 @@ impl<'m> JitNativeExecutor<'m> {
     /// Execute a program with the given params.
     ///
     /// See [`cairo_native::jit_runner::execute`]
+    #[instrument(skip_all, fields(function = %function_id))]
     pub fn invoke_dynamic(
         &self,
         function_id: &FunctionId,
@@ -82,12 +106,16 @@ impl<'m> JitNativeExecutor<'m> {
             args,
             available_gas,
             Option::<DummySyscallHandler>::None,
+            self.stack.as_ref(),
+            self.determinism_mode,
+            None,
         )
     }
 
     /// Execute a program with the given params.
     ///
     /// See [`cairo_native::jit_runner::execute`]
+    #[instrument(skip_all, fields(function = %function_id))]
     pub fn invoke_dynamic_with_syscall_handler(
         &self,
         function_id: &FunctionId,
@@ -107,9 +135,13 @@ impl<'m> JitNativeExecutor<'m> {
             args,
             available_gas,
             Some(syscall_handler),
+            self.stack.as_ref(),
+            self.determinism_mode,
+            None,
         )
     }
 
+    #[instrument(skip_all, fields(function = %function_id))]
     pub fn invoke_contract_dynamic(
         &self,
         function_id: &FunctionId,
@@ -121,21 +153,38 @@ impl<'m> JitNativeExecutor<'m> {
             .gas_metadata
             .get_initial_available_gas(function_id, gas)
             .map_err(crate::error::Error::GasMetadataError)?;
+        let limits = syscall_handler.payload_limits();
+        let array_len_limit = limits.max_retdata_len.map(ArrayLenLimit::new);
         // TODO: Check signature for contract interface.
-        ContractExecutionResult::from_execution_result(super::invoke_dynamic(
-            &self.registry,
-            self.find_function_ptr(function_id),
-            self.extract_signature(function_id),
-            &[JitValue::Struct {
-                fields: vec![JitValue::Array(
-                    args.iter().cloned().map(JitValue::Felt252).collect(),
-                )],
-                // TODO: Populate `debug_name`.
-                debug_name: None,
-            }],
-            available_gas,
-            Some(syscall_handler),
-        )?)
+        ContractExecutionResult::from_limited_execution_result(
+            super::invoke_dynamic(
+                &self.registry,
+                self.find_function_ptr(function_id),
+                self.extract_signature(function_id),
+                &[JitValue::Struct {
+                    fields: vec![JitValue::Array(
+                        args.iter().cloned().map(JitValue::Felt252).collect(),
+                    )],
+                    // TODO: Populate `debug_name`.
+                    debug_name: None,
+                }],
+                available_gas,
+                Some(syscall_handler),
+                self.stack.as_ref(),
+                self.determinism_mode,
+                array_len_limit.as_ref(),
+            )?,
+            &limits,
+            array_len_limit.as_ref().and_then(ArrayLenLimit::exceeded),
+        )
+    }
+
+    /// Return the gas required to call the given function, or `None` if the program was compiled
+    /// without gas costs.
+    ///
+    /// The gas passed when invoking the function has to be at least this amount.
+    pub fn initial_required_gas(&self, function_id: &FunctionId) -> Option<u128> {
+        self.gas_metadata.initial_required_gas(function_id)
     }
 
     pub fn find_function_ptr(&self, function_id: &FunctionId) -> *mut c_void {
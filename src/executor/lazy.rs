@@ -0,0 +1,269 @@
+//! # Lazy JIT executor
+//!
+//! Compiling a whole program before running any of it is wasteful for contracts with many entry
+//! points, since most invocations only need a few of them. The lazy executor instead compiles an
+//! entry point (and the functions it calls) the first time it's invoked, and reuses the compiled
+//! code afterwards.
+//!
+//! Every entry point is compiled into its own module, therefore the functions shared by many
+//! entry points are compiled once for each of them.
+//!
+//! The executor can be shared between threads. An entry point invoked from many threads at once
+//! before being compiled may be compiled more than once, but only one of the results is kept.
+
+use super::JitNativeExecutor;
+use crate::{
+    context::{NativeContext, ProgramMetadata},
+    error::Error,
+    execution_result::{ContractExecutionResult, ExecutionResult},
+    starknet::StarknetSyscallHandler,
+    values::JitValue,
+    OptLevel,
+};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType},
+    ids::FunctionId,
+    program::Program,
+    program_registry::ProgramRegistry,
+};
+use starknet_types_core::felt::Felt;
+use std::{
+    collections::HashMap,
+    slice,
+    sync::{Arc, RwLock},
+};
+use tracing::instrument;
+
+/// A JIT executor which compiles the entry points of a program on their first invocation. Check
+/// out the [module docs](self) for more info.
+pub struct LazyJitNativeExecutor<'m> {
+    context: &'m NativeContext,
+    program: Program,
+    opt_level: OptLevel,
+
+    registry: ProgramRegistry<CoreType, CoreLibfunc>,
+    program_metadata: ProgramMetadata,
+    executors: RwLock<HashMap<FunctionId, Arc<JitNativeExecutor<'m>>>>,
+}
+
+impl std::fmt::Debug for LazyJitNativeExecutor<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyJitNativeExecutor")
+            .field("gas_metadata", &self.program_metadata.gas)
+            .field("executors", &self.executors)
+            .finish()
+    }
+}
+
+impl<'m> LazyJitNativeExecutor<'m> {
+    /// Prepare a program to be executed, without compiling any of its functions yet.
+    #[instrument(skip_all, fields(functions = program.funcs.len()))]
+    pub fn new(
+        context: &'m NativeContext,
+        program: &Program,
+        opt_level: OptLevel,
+    ) -> Result<Self, Error> {
+        let (registry, program_metadata) = context.program_metadata(program, None)?;
+
+        Ok(Self {
+            context,
+            program: program.clone(),
+            opt_level,
+            registry,
+            program_metadata,
+            executors: RwLock::default(),
+        })
+    }
+
+    pub fn program_registry(&self) -> &ProgramRegistry<CoreType, CoreLibfunc> {
+        &self.registry
+    }
+
+    /// Return the executor of an entry point, compiling it if it hasn't been invoked before.
+    pub fn entry_point_executor(
+        &self,
+        function_id: &FunctionId,
+    ) -> Result<Arc<JitNativeExecutor<'m>>, Error> {
+        if let Some(executor) = self
+            .executors
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(function_id)
+        {
+            return Ok(executor.clone());
+        }
+
+        tracing::debug!("Compiling entry point `{function_id}`.");
+        let module = self.context.compile_subset(
+            &self.program,
+            None,
+            Some(slice::from_ref(function_id)),
+            Some(&self.program_metadata),
+        )?;
+        let executor = Arc::new(JitNativeExecutor::from_native_module(
+            module,
+            self.opt_level,
+        ));
+
+        // The lock isn't held while compiling, so that the cache may be accessed from other
+        // executions in the meantime (ex. from a syscall handler or another thread).
+        Ok(self
+            .executors
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(function_id.clone())
+            .or_insert(executor)
+            .clone())
+    }
+
+    /// Return whether an entry point has already been compiled.
+    pub fn is_compiled(&self, function_id: &FunctionId) -> bool {
+        self.executors
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(function_id)
+    }
+
+    /// Execute a program with the given params, compiling the function first if needed.
+    ///
+    /// See [JitNativeExecutor::invoke_dynamic].
+    #[instrument(skip_all, fields(function = %function_id))]
+    pub fn invoke_dynamic(
+        &self,
+        function_id: &FunctionId,
+        args: &[JitValue],
+        gas: Option<u128>,
+    ) -> Result<ExecutionResult, Error> {
+        self.entry_point_executor(function_id)?
+            .invoke_dynamic(function_id, args, gas)
+    }
+
+    /// Execute a program with the given params, compiling the function first if needed.
+    ///
+    /// See [JitNativeExecutor::invoke_dynamic_with_syscall_handler].
+    #[instrument(skip_all, fields(function = %function_id))]
+    pub fn invoke_dynamic_with_syscall_handler(
+        &self,
+        function_id: &FunctionId,
+        args: &[JitValue],
+        gas: Option<u128>,
+        syscall_handler: impl StarknetSyscallHandler,
+    ) -> Result<ExecutionResult, Error> {
+        self.entry_point_executor(function_id)?
+            .invoke_dynamic_with_syscall_handler(function_id, args, gas, syscall_handler)
+    }
+
+    #[instrument(skip_all, fields(function = %function_id))]
+    pub fn invoke_contract_dynamic(
+        &self,
+        function_id: &FunctionId,
+        args: &[Felt],
+        gas: Option<u128>,
+        syscall_handler: impl StarknetSyscallHandler,
+    ) -> Result<ContractExecutionResult, Error> {
+        self.entry_point_executor(function_id)?
+            .invoke_contract_dynamic(function_id, args, gas, syscall_handler)
+    }
+
+    /// Return the gas required to call the given function, or `None` if the program was compiled
+    /// without gas costs. It doesn't need the function to be compiled.
+    ///
+    /// The gas passed when invoking the function has to be at least this amount.
+    pub fn initial_required_gas(&self, function_id: &FunctionId) -> Option<u128> {
+        self.program_metadata.gas.initial_required_gas(function_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::{find_function_id, generate_function_name, test::load_cairo};
+
+    #[test]
+    fn compile_on_first_invocation() {
+        let program = load_cairo! {
+            fn run_test(n: felt252) -> felt252 {
+                double(n) + 1
+            }
+
+            #[inline(never)]
+            fn double(n: felt252) -> felt252 {
+                n * 2
+            }
+
+            #[inline(never)]
+            fn unused(n: felt252) -> felt252 {
+                n * 3
+            }
+        };
+        let function_id = |name: &str| {
+            find_function_id(&program.1, &format!("{0}::{0}::{name}", program.0)).clone()
+        };
+        let run_test_id = function_id("run_test");
+
+        let context = NativeContext::new();
+        let executor = LazyJitNativeExecutor::new(&context, &program.1, OptLevel::None).unwrap();
+        assert!(!executor.is_compiled(&run_test_id));
+
+        let result = executor
+            .invoke_dynamic(&run_test_id, &[JitValue::Felt252(5.into())], None)
+            .unwrap();
+        assert_eq!(result.return_value, JitValue::Felt252(11.into()));
+        assert!(executor.is_compiled(&run_test_id));
+
+        // Only the invoked function and its callees were compiled.
+        let module = executor
+            .entry_point_executor(&run_test_id)
+            .unwrap()
+            .module()
+            .as_operation()
+            .to_string();
+        for (name, is_compiled) in [("run_test", true), ("double", true), ("unused", false)] {
+            let symbol = generate_function_name(&function_id(name)).into_owned();
+            assert_eq!(module.contains(&symbol), is_compiled, "{name}");
+        }
+
+        let unused_id = function_id("unused");
+        assert!(!executor.is_compiled(&unused_id));
+        let result = executor
+            .invoke_dynamic(&unused_id, &[JitValue::Felt252(5.into())], None)
+            .unwrap();
+        assert_eq!(result.return_value, JitValue::Felt252(15.into()));
+        assert!(executor.is_compiled(&unused_id));
+    }
+
+    #[test]
+    fn invoke_from_many_threads() {
+        let program = load_cairo! {
+            fn run_test(n: felt252) -> felt252 {
+                n * 2
+            }
+        };
+        let function_id = find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0));
+
+        let context = NativeContext::new();
+        let executor = LazyJitNativeExecutor::new(&context, &program.1, OptLevel::None).unwrap();
+
+        std::thread::scope(|scope| {
+            let handles = (0..4u64)
+                .map(|i| {
+                    let executor = &executor;
+                    scope.spawn(move || {
+                        executor
+                            .invoke_dynamic(function_id, &[JitValue::Felt252(i.into())], None)
+                            .unwrap()
+                            .return_value
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for (i, handle) in handles.into_iter().enumerate() {
+                assert_eq!(
+                    handle.join().unwrap(),
+                    JitValue::Felt252((2 * i as u64).into())
+                );
+            }
+        });
+        assert!(executor.is_compiled(function_id));
+    }
+}
@@ -0,0 +1,128 @@
+//! # Unrecoverable error handling
+//!
+//! Implements the executor side of the [error](PanicMode::Error) and [hook](PanicMode::Hook) panic
+//! modes. The trampoline saves the callee-saved registers of the invocation into a jump buffer
+//! before calling the entry point. When the generated code hits an unrecoverable error it calls
+//! the panic handler installed by the executor, which stores the message and restores the jump
+//! buffer (like `longjmp` does), so that the trampoline returns as if the call had finished.
+//!
+//! Nothing is unwound: the native frames of the invocation are discarded, and the memory they
+//! owned is reclaimed later by the [execution arena](super::arena). The frames of the handler
+//! itself never own anything that needs dropping when the jump happens.
+
+use crate::{context::PanicMode, error::Error, metadata::panic::PANIC_HANDLER_SYMBOL};
+use libc::c_void;
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::{addr_of_mut, null_mut},
+    slice,
+    sync::RwLock,
+};
+
+/// The registers saved by the trampoline: the callee-saved ones and the address to resume at.
+#[cfg(target_arch = "x86_64")]
+const JUMP_BUFFER_LEN: usize = 7;
+#[cfg(target_arch = "aarch64")]
+const JUMP_BUFFER_LEN: usize = 19;
+
+pub(crate) type JumpBuffer = [u64; JUMP_BUFFER_LEN];
+
+type PanicHook = Box<dyn Fn(&str) + Send + Sync>;
+
+static PANIC_HOOK: RwLock<Option<PanicHook>> = RwLock::new(None);
+
+thread_local! {
+    /// The jump buffer of the innermost invocation running on this thread.
+    static JUMP_BUFFER: Cell<*mut JumpBuffer> = const { Cell::new(null_mut()) };
+    /// The message of the unrecoverable error which interrupted the innermost invocation.
+    static UNRECOVERABLE_ERROR: Cell<Option<String>> = const { Cell::new(None) };
+}
+
+extern "C" {
+    /// Restore the registers saved into a jump buffer by the trampoline. Never returns.
+    #[cfg_attr(not(target_os = "macos"), link_name = "_cairo_native_longjmp")]
+    fn cairo_native_longjmp(jump_buffer: *const JumpBuffer) -> !;
+}
+
+/// Register the hook called by programs compiled with [PanicMode::Hook] when they hit an
+/// unrecoverable error, replacing the previous one. The hook receives a message describing the
+/// error.
+pub fn set_panic_hook(hook: impl Fn(&str) + Send + Sync + 'static) {
+    *PANIC_HOOK.write().unwrap() = Some(Box::new(hook));
+}
+
+/// Point the panic handler global of a loaded module, found using `lookup`, to the handler of the
+/// mode it was compiled with. Modules without unrecoverable errors don't have the global.
+///
+/// # Safety
+///
+/// The module must have been compiled with the given mode, and `lookup` must return the address
+/// of its symbols (or null if they don't exist).
+pub(crate) unsafe fn install_panic_handler(
+    mode: PanicMode,
+    lookup: impl FnOnce(&str) -> *mut c_void,
+) {
+    let handler: unsafe extern "C" fn(*const u8, u64) = match mode {
+        PanicMode::Abort | PanicMode::Trap => return,
+        PanicMode::Error => abandon_invocation,
+        PanicMode::Hook => call_hook_and_abandon_invocation,
+    };
+
+    let global = lookup(PANIC_HANDLER_SYMBOL);
+    if !global.is_null() {
+        global
+            .cast::<*const c_void>()
+            .write(handler as *const c_void);
+    }
+}
+
+/// Run an invocation of the trampoline with a fresh jump buffer, and turn the unrecoverable error
+/// which interrupted it, if any, into an [Error::UnrecoverableError].
+pub(crate) fn catch_unrecoverable_error<T>(
+    f: impl FnOnce(*mut JumpBuffer) -> T,
+) -> Result<T, Error> {
+    let mut jump_buffer = JumpBuffer::default();
+    let jump_buffer_ptr = addr_of_mut!(jump_buffer);
+
+    let previous = JUMP_BUFFER.replace(jump_buffer_ptr);
+    let result = f(jump_buffer_ptr);
+    JUMP_BUFFER.set(previous);
+
+    match UNRECOVERABLE_ERROR.take() {
+        Some(message) => Err(Error::UnrecoverableError(message)),
+        None => Ok(result),
+    }
+}
+
+unsafe fn read_message<'a>(msg_ptr: *const u8, msg_len: u64) -> Cow<'a, str> {
+    String::from_utf8_lossy(slice::from_raw_parts(msg_ptr, msg_len as usize))
+}
+
+/// Stop the current invocation. Returns (and therefore lets the generated code abort) if the code
+/// isn't running inside an invocation of the executor.
+unsafe extern "C" fn abandon_invocation(msg_ptr: *const u8, msg_len: u64) {
+    let jump_buffer = JUMP_BUFFER.get();
+    if jump_buffer.is_null() {
+        return;
+    }
+
+    UNRECOVERABLE_ERROR.set(Some(read_message(msg_ptr, msg_len).into_owned()));
+    cairo_native_longjmp(jump_buffer);
+}
+
+unsafe extern "C" fn call_hook_and_abandon_invocation(msg_ptr: *const u8, msg_len: u64) {
+    // Panics can't unwind into the generated code.
+    if catch_unwind(AssertUnwindSafe(|| {
+        if let Some(hook) = PANIC_HOOK.read().unwrap().as_ref() {
+            hook(&read_message(msg_ptr, msg_len));
+        }
+    }))
+    .is_err()
+    {
+        std::process::abort();
+    }
+
+    abandon_invocation(msg_ptr, msg_len);
+}
@@ -0,0 +1,231 @@
+//! # Execution stack
+//!
+//! Programs with recursive functions may need a lot more stack than what's available in the current
+//! thread. Their entry points are run on a dedicated stack instead, which is reserved on first use
+//! but only uses memory for the pages that are actually touched.
+//!
+//! Every invocation gets a stack of its own, so that concurrent invocations (ex. of an executor
+//! shared between threads) and reentrant ones (ex. a syscall handler executing another entry point
+//! of the same program) all have the full stack available. The stacks are kept in a pool and
+//! reused by later invocations.
+//!
+//! If a stack can't be reserved (ex. because the address space is limited), the invocation keeps
+//! running on the current thread's stack.
+
+use crate::metadata::{recursion::RecursionMeta, MetadataStorage};
+use libc::c_void;
+use std::{
+    io,
+    ptr::null_mut,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// Size of the reserved address space for each dedicated stack (1 GiB).
+pub const EXECUTION_STACK_SIZE: usize = 1 << 30;
+
+/// A pool of dedicated stacks, each one with a guard page at its bottom to catch overflows.
+#[derive(Debug)]
+pub struct ExecutionStack {
+    size: usize,
+    /// The reserved stacks which aren't in use.
+    pool: Mutex<Vec<Mapping>>,
+}
+
+#[derive(Debug)]
+struct Mapping {
+    base: *mut c_void,
+    size: usize,
+}
+
+// A mapping is owned either by the pool or by the single call it has been checked out for.
+unsafe impl Send for Mapping {}
+
+/// A stack checked out of the pool, which is returned to it when dropped (even if the call using
+/// it panics).
+struct CheckedOut<'a> {
+    stack: &'a ExecutionStack,
+    mapping: Option<Mapping>,
+}
+
+impl ExecutionStack {
+    /// Create a pool of stacks of the given size. The memory isn't reserved until it's first used.
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            pool: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create a stack if the program has any recursive function.
+    pub fn for_module(metadata: &MetadataStorage) -> Option<Self> {
+        metadata
+            .get::<RecursionMeta>()
+            .is_some_and(RecursionMeta::has_recursion)
+            .then(|| Self::new(EXECUTION_STACK_SIZE))
+    }
+
+    /// Call `f` with the (page-aligned) top of a stack which isn't used by any other call, or with
+    /// a null pointer if no stack could be reserved.
+    pub fn with_top<T>(&self, f: impl FnOnce(*mut c_void) -> T) -> T {
+        let Some(checked_out) = self.check_out() else {
+            return f(null_mut());
+        };
+
+        f(checked_out.top())
+    }
+
+    fn check_out(&self) -> Option<CheckedOut<'_>> {
+        let mapping = match self.lock_pool().pop() {
+            Some(mapping) => mapping,
+            None => match Mapping::new(self.size) {
+                Ok(mapping) => mapping,
+                Err(e) => {
+                    tracing::warn!(
+                        "failed to reserve an execution stack, using the current one: {e}"
+                    );
+                    return None;
+                }
+            },
+        };
+
+        Some(CheckedOut {
+            stack: self,
+            mapping: Some(mapping),
+        })
+    }
+
+    fn lock_pool(&self) -> MutexGuard<'_, Vec<Mapping>> {
+        // The pool is never left in an inconsistent state, even if a call panics.
+        self.pool.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl CheckedOut<'_> {
+    fn top(&self) -> *mut c_void {
+        let mapping = self.mapping.as_ref().unwrap();
+        unsafe { mapping.base.cast::<u8>().add(mapping.size) }.cast()
+    }
+}
+
+impl Drop for CheckedOut<'_> {
+    fn drop(&mut self) {
+        if let Some(mapping) = self.mapping.take() {
+            self.stack.lock_pool().push(mapping);
+        }
+    }
+}
+
+impl Mapping {
+    fn new(size: usize) -> io::Result<Self> {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let size = size.next_multiple_of(page_size) + page_size;
+
+        let base = unsafe {
+            libc::mmap(
+                null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The stack grows downwards, so the guard page is the lowest one.
+        if unsafe { libc::mprotect(base, page_size, libc::PROT_NONE) } != 0 {
+            let error = io::Error::last_os_error();
+            unsafe { libc::munmap(base, size) };
+            return Err(error);
+        }
+
+        Ok(Self { base, size })
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.base, self.size) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{
+        panic::{catch_unwind, AssertUnwindSafe},
+        sync::Barrier,
+        thread,
+    };
+
+    #[test]
+    fn reserve_on_first_use() {
+        let stack = ExecutionStack::new(1 << 20);
+        assert!(stack.lock_pool().is_empty());
+
+        let (outer, inner) = stack.with_top(|outer| {
+            // Nested calls get a stack of their own.
+            let inner = stack.with_top(|inner| inner as usize);
+            (outer as usize, inner)
+        });
+        assert_ne!(outer, 0);
+        assert_ne!(inner, 0);
+        assert_ne!(outer, inner);
+        assert_eq!(stack.lock_pool().len(), 2);
+
+        // The stacks are reused.
+        let top = stack.with_top(|top| top as usize);
+        assert!(top == outer || top == inner);
+        assert_eq!(stack.lock_pool().len(), 2);
+    }
+
+    #[test]
+    fn concurrent_calls() {
+        let stack = ExecutionStack::new(1 << 20);
+        let barrier = Barrier::new(2);
+
+        let tops = thread::scope(|scope| {
+            let threads = (0..2)
+                .map(|_| {
+                    scope.spawn(|| {
+                        stack.with_top(|top| {
+                            // Both calls are running at the same time.
+                            barrier.wait();
+                            top as usize
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert!(tops.iter().all(|&top| top != 0));
+        assert_ne!(tops[0], tops[1]);
+    }
+
+    #[test]
+    fn return_stack_on_panic() {
+        let stack = ExecutionStack::new(1 << 20);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            stack.with_top(|_| panic!("the call failed"));
+        }));
+        assert!(result.is_err());
+        assert_eq!(stack.lock_pool().len(), 1);
+
+        assert_ne!(stack.with_top(|top| top as usize), 0);
+    }
+
+    #[test]
+    fn fall_back_to_current_stack() {
+        // More address space than any platform has.
+        let stack = ExecutionStack::new(usize::MAX / 2);
+        assert!(stack.with_top(|top| top.is_null()));
+        assert!(stack.lock_pool().is_empty());
+    }
+}
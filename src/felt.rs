@@ -0,0 +1,121 @@
+//! # Felt utilities
+//!
+//! Host-side helpers to convert between [`Felt`], `num_bigint` integers and the `[u32; 8]`
+//! representation used by the compiled programs, and to do modular arithmetic over the `felt252`
+//! prime.
+//!
+//! Negative integers are mapped to their field representation (`PRIME - |value|`). When converting
+//! back into a signed integer, every value above [`struct@HALF_PRIME`] is considered negative, just
+//! like Cairo does for its signed integer types.
+
+use lazy_static::lazy_static;
+use num_bigint::{BigInt, BigUint, Sign};
+use starknet_types_core::felt::Felt;
+
+lazy_static! {
+    /// The `felt252` prime modulo.
+    pub static ref PRIME: BigUint =
+        "3618502788666131213697322783095070105623107215331596699973092056135872020481"
+            .parse()
+            .unwrap();
+    /// Half of the `felt252` prime modulo, rounded down.
+    pub static ref HALF_PRIME: BigInt =
+        "1809251394333065606848661391547535052811553607665798349986546028067936010240"
+            .parse()
+            .unwrap();
+}
+
+/// Reduce any integer into the `[0, PRIME)` range.
+pub fn reduce(value: &BigInt) -> BigUint {
+    let magnitude = value.magnitude() % &*PRIME;
+    match value.sign() {
+        Sign::Minus if magnitude != BigUint::default() => &*PRIME - magnitude,
+        _ => magnitude,
+    }
+}
+
+/// Convert any integer into a felt, wrapping it around the prime modulo.
+pub fn from_bigint(value: &BigInt) -> Felt {
+    Felt::from(&reduce(value))
+}
+
+/// Convert a felt into a signed integer in the `(-PRIME/2, PRIME/2]` range.
+pub fn to_signed_bigint(value: &Felt) -> BigInt {
+    let value = BigInt::from(value.to_biguint());
+    if value > *HALF_PRIME {
+        value - BigInt::from(PRIME.clone())
+    } else {
+        value
+    }
+}
+
+/// Convert any integer into the little-endian `[u32; 8]` layout of a felt, wrapping it around the
+/// prime modulo.
+pub fn to_u32_digits(value: &BigInt) -> [u32; 8] {
+    let mut digits = reduce(value).to_u32_digits();
+    digits.resize(8, 0);
+    digits.try_into().unwrap()
+}
+
+/// Convert the little-endian `[u32; 8]` layout of a felt back into a felt.
+///
+/// Values that aren't in the `[0, PRIME)` range are reduced.
+pub fn from_u32_digits(digits: &[u32; 8]) -> Felt {
+    Felt::from(&(BigUint::from_slice(digits) % &*PRIME))
+}
+
+/// Return the multiplicative inverse of a felt, or `None` if it's zero.
+pub fn inverse(value: &Felt) -> Option<Felt> {
+    let value = value.to_biguint();
+    (value != BigUint::default()).then(|| Felt::from(&value.modpow(&(&*PRIME - 2u32), &PRIME)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reduce_wraps_around() {
+        assert_eq!(reduce(&BigInt::from(0)), BigUint::from(0u32));
+        assert_eq!(reduce(&BigInt::from(-1)), &*PRIME - 1u32);
+        assert_eq!(reduce(&BigInt::from(PRIME.clone())), BigUint::from(0u32));
+        assert_eq!(reduce(&-BigInt::from(PRIME.clone())), BigUint::from(0u32));
+        assert_eq!(
+            reduce(&(BigInt::from(PRIME.clone()) + 5)),
+            BigUint::from(5u32)
+        );
+    }
+
+    #[test]
+    fn signed_round_trip() {
+        for value in [
+            BigInt::from(0),
+            BigInt::from(1),
+            BigInt::from(-1),
+            HALF_PRIME.clone(),
+            -HALF_PRIME.clone(),
+        ] {
+            assert_eq!(to_signed_bigint(&from_bigint(&value)), value);
+        }
+
+        assert_eq!(from_bigint(&BigInt::from(-1)), Felt::ZERO - Felt::ONE);
+    }
+
+    #[test]
+    fn u32_digits_round_trip() {
+        let value = BigInt::from(-7);
+        let digits = to_u32_digits(&value);
+
+        assert_eq!(digits[0], PRIME.to_u32_digits()[0] - 7);
+        assert_eq!(from_u32_digits(&digits), from_bigint(&value));
+    }
+
+    #[test]
+    fn inverse_of_felts() {
+        assert_eq!(inverse(&Felt::ZERO), None);
+        assert_eq!(inverse(&Felt::ONE), Some(Felt::ONE));
+
+        let value = Felt::from(123456789u64);
+        assert_eq!(value * inverse(&value).unwrap(), Felt::ONE);
+    }
+}
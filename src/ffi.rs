@@ -6,32 +6,44 @@
 use crate::error::Error as CompileError;
 use llvm_sys::{
     core::{
-        LLVMContextCreate, LLVMContextDispose, LLVMDisposeMemoryBuffer, LLVMDisposeMessage,
-        LLVMDisposeModule, LLVMGetBufferSize, LLVMGetBufferStart,
+        LLVMContextCreate, LLVMContextDispose, LLVMCreateMemoryBufferWithMemoryRangeCopy,
+        LLVMDisposeMemoryBuffer, LLVMDisposeMessage, LLVMDisposeModule, LLVMGetBufferSize,
+        LLVMGetBufferStart, LLVMSetTarget,
+    },
+    error::{LLVMDisposeErrorMessage, LLVMErrorRef, LLVMGetErrorMessage},
+    orc2::{
+        lljit::{
+            LLVMOrcCreateLLJIT, LLVMOrcDisposeLLJIT, LLVMOrcLLJITAddObjectFile,
+            LLVMOrcLLJITGetGlobalPrefix, LLVMOrcLLJITGetMainJITDylib, LLVMOrcLLJITLookup,
+            LLVMOrcLLJITRef,
+        },
+        LLVMOrcCreateDynamicLibrarySearchGeneratorForProcess, LLVMOrcJITDylibAddGenerator,
     },
     prelude::{LLVMContextRef, LLVMMemoryBufferRef, LLVMModuleRef},
     target::{
-        LLVM_InitializeAllAsmParsers, LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos,
-        LLVM_InitializeAllTargetMCs, LLVM_InitializeAllTargets,
+        LLVMDisposeTargetData, LLVMSetModuleDataLayout, LLVM_InitializeAllAsmParsers,
+        LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos, LLVM_InitializeAllTargetMCs,
+        LLVM_InitializeAllTargets,
     },
     target_machine::{
-        LLVMCodeGenFileType, LLVMCodeGenOptLevel, LLVMCodeModel, LLVMCreateTargetMachine,
-        LLVMDisposeTargetMachine, LLVMGetDefaultTargetTriple, LLVMGetHostCPUFeatures,
-        LLVMGetHostCPUName, LLVMGetTargetFromTriple, LLVMRelocMode,
+        LLVMCodeGenFileType, LLVMCodeGenOptLevel, LLVMCodeModel, LLVMCreateTargetDataLayout,
+        LLVMCreateTargetMachine, LLVMDisposeTargetMachine, LLVMGetDefaultTargetTriple,
+        LLVMGetHostCPUFeatures, LLVMGetHostCPUName, LLVMGetTargetFromTriple, LLVMRelocMode,
         LLVMTargetMachineEmitToMemoryBuffer, LLVMTargetRef,
     },
 };
-use melior::ir::{Module, Type, TypeLike};
-use mlir_sys::MlirOperation;
+use melior::ir::{attribute::StringAttribute, Module, OperationRef, Type, TypeLike};
+use mlir_sys::{mlirBlockAppendOwnedOperation, mlirOperationRemoveFromParent, MlirOperation};
 use std::{
     borrow::Cow,
+    collections::HashMap,
     error::Error,
-    ffi::{c_void, CStr},
+    ffi::{c_char, c_void, CStr, CString},
     fmt::Display,
     io::Write,
     mem::MaybeUninit,
     path::Path,
-    ptr::{addr_of_mut, null_mut},
+    ptr::{addr_of_mut, null_mut, NonNull},
     sync::OnceLock,
 };
 use tempfile::NamedTempFile;
@@ -55,6 +67,64 @@ pub fn get_struct_field_type_at<'c>(r#type: &Type<'c>, index: usize) -> Type<'c>
     unsafe { Type::from_raw(ty_ptr) }
 }
 
+/// Move the operations of the given modules to the end of `target`, in order.
+///
+/// Only the first definition of each symbol is kept, so the modules may declare the same external
+/// functions or globals. Fails if the definitions of the same symbol differ.
+pub fn merge_modules<'c>(
+    target: &Module<'c>,
+    sources: impl IntoIterator<Item = Module<'c>>,
+) -> Result<(), CompileError> {
+    let target_body = target.body();
+
+    let mut symbols = HashMap::new();
+    let mut next = target_body.first_operation();
+    while let Some(operation) = next {
+        next = operation.next_in_block();
+        if let Some(symbol) = symbol_name(operation) {
+            symbols.entry(symbol).or_insert(operation);
+        }
+    }
+
+    for source in sources {
+        let source_body = source.body();
+
+        let mut next = source_body.first_operation();
+        while let Some(operation) = next {
+            next = operation.next_in_block();
+            let symbol = symbol_name(operation);
+            if let Some(kept) = symbol.as_ref().and_then(|symbol| symbols.get(symbol)) {
+                // Comparing the printed operations is slow, but duplicates are only declarations
+                // and small globals.
+                if kept.to_string() != operation.to_string() {
+                    return Err(CompileError::Error(format!(
+                        "symbol `{}` has different definitions in the merged modules",
+                        symbol.unwrap()
+                    )));
+                }
+                continue;
+            }
+
+            unsafe {
+                let operation = operation.to_raw();
+                mlirOperationRemoveFromParent(operation);
+                mlirBlockAppendOwnedOperation(target_body.to_raw(), operation);
+            }
+            if let Some(symbol) = symbol {
+                // The operation now belongs to the target module.
+                symbols.insert(symbol, target_body.last_operation().unwrap());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn symbol_name(operation: OperationRef<'_, '_>) -> Option<String> {
+    let symbol = StringAttribute::try_from(operation.attribute("sym_name").ok()?).ok()?;
+    Some(symbol.value().to_string())
+}
+
 /// A error from the LLVM API.
 #[derive(Debug, Clone)]
 pub struct LLVMCompileError(String);
@@ -70,6 +140,7 @@ impl Display for LLVMCompileError {
 
 /// Optimization levels.
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OptLevel {
     None,
     Less,
@@ -111,11 +182,224 @@ impl From<u8> for OptLevel {
     }
 }
 
+/// The CPU name which stands for the host's CPU.
+pub const NATIVE_CPU: &str = "native";
+
+/// The machine the objects are compiled for.
+///
+/// The default is the host's CPU with all its features, which produces the fastest code but may
+/// not run on other machines. Objects which are distributed, or have to be reproducible, should
+/// name an explicit CPU instead.
+///
+/// ```
+/// use cairo_native::{RelocationModel, TargetConfig};
+///
+/// // Any x86-64 with AVX2, but without AVX-512.
+/// let target = TargetConfig {
+///     cpu: "x86-64-v3".to_string(),
+///     features: "-avx512f".to_string(),
+///     relocation_model: RelocationModel::Pic,
+///     ..Default::default()
+/// };
+/// ```
+///
+/// ## Cross-compilation
+///
+/// Setting a [triple](Self::triple) compiles for another platform, as long as it's an x86-64 or an
+/// AArch64 one: the type layouts computed by the compiler assume 64-bit pointers and 16-byte
+/// aligned 128-bit integers, which hold for both. The program has to be compiled with a context
+/// [configured](crate::context::CompilerConfig::with_target) for the same target, so that the
+/// MLIR module gets its data layout.
+///
+/// Cross-compiled objects can't be loaded by the executors. Instead, they have to be linked by
+/// [object_to_shared_lib_with_target], which uses the target's linker and libraries:
+///
+/// - The linker is `<arch>-linux-gnu-ld` (or `ld64.lld` for macOS targets), unless the
+///   `CAIRO_NATIVE_LINKER` environment variable names another one. It's only used for other
+///   targets: objects of the host are always linked by the system's `ld`.
+/// - The libraries of Linux targets are searched for where the cross toolchains of Debian-based
+///   distributions install them (ex. `/usr/aarch64-linux-gnu/lib`).
+/// - The runtime library built for the target is read from the
+///   `CAIRO_NATIVE_RUNTIME_LIBRARY_<TRIPLE>` environment variable, where `<TRIPLE>` is the target
+///   triple in uppercase with underscores (ex. `AARCH64_UNKNOWN_LINUX_GNU`), or defaults to the
+///   output of `cargo build --release --target <triple>` (ex.
+///   `target/aarch64-unknown-linux-gnu/release/libcairo_native_runtime.a`).
+///
+/// ## WebAssembly
+///
+/// The `wasm32-unknown-unknown` triple is an experimental target, meant to run simple programs in
+/// browsers. Its pointers are 32 bits wide, so only the programs whose values don't hold pointers
+/// (ex. felts and integers, but not arrays, boxes nor dictionaries) are compiled correctly.
+///
+/// Its objects are linked into a WebAssembly module by `wasm-ld` (or the linker named by
+/// `CAIRO_NATIVE_LINKER`), which exports every function. There's no runtime library for the target:
+/// the runtime functions used by the program are imported from the `env` module instead, so the
+/// host has to provide them (ex. as JavaScript functions given to `WebAssembly.instantiate`).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TargetConfig {
+    /// The target triple (ex. `aarch64-unknown-linux-gnu`), or `None` for the host's.
+    pub triple: Option<String>,
+    /// The CPU to generate code for (ex. `skylake` or `apple-m1`), or [NATIVE_CPU] for the host's.
+    /// When cross-compiling, [NATIVE_CPU] stands for a generic CPU of the target instead.
+    pub cpu: String,
+    /// Comma-separated features to enable (`+name`) or disable (`-name`) on top of the ones of the
+    /// CPU, like `+avx2,-avx512f`.
+    pub features: String,
+    pub relocation_model: RelocationModel,
+}
+
+impl Default for TargetConfig {
+    fn default() -> Self {
+        Self {
+            triple: None,
+            cpu: NATIVE_CPU.to_string(),
+            features: String::new(),
+            relocation_model: RelocationModel::default(),
+        }
+    }
+}
+
+impl TargetConfig {
+    /// Return the target triple, which is the host's unless one was given.
+    pub fn target_triple(&self) -> String {
+        self.triple.clone().unwrap_or_else(get_target_triple)
+    }
+
+    /// Return whether the target triple is not the host's.
+    pub fn is_cross(&self) -> bool {
+        self.triple
+            .as_ref()
+            .is_some_and(|triple| *triple != get_target_triple())
+    }
+
+    /// Return whether the target is WebAssembly. Check out the [WebAssembly](Self#webassembly)
+    /// section for more info.
+    pub fn is_wasm(&self) -> bool {
+        self.target_triple().starts_with("wasm32-")
+    }
+
+    /// Check that the code generated for the target can be compiled. Check out the
+    /// [cross-compilation](Self#cross-compilation) section for more info.
+    pub fn validate(&self) -> Result<(), LLVMCompileError> {
+        if !self.is_cross() {
+            return Ok(());
+        }
+
+        let triple = self.target_triple();
+        match triple.split('-').next() {
+            Some("x86_64" | "aarch64" | "arm64" | "wasm32") => Ok(()),
+            _ => Err(LLVMCompileError(format!(
+                "unsupported target `{triple}`, only x86-64, AArch64 and wasm32 targets are supported"
+            ))),
+        }
+    }
+
+    /// Return the CPU name and the features given to LLVM, with [NATIVE_CPU] replaced by the
+    /// host's CPU and its features.
+    pub fn resolve(&self) -> (String, String) {
+        if self.cpu != NATIVE_CPU {
+            return (self.cpu.clone(), self.features.clone());
+        }
+        if self.is_cross() {
+            return ("generic".to_string(), self.features.clone());
+        }
+
+        let (cpu, mut features) = unsafe {
+            let cpu = LLVMGetHostCPUName();
+            let features = LLVMGetHostCPUFeatures();
+            let result = (
+                CStr::from_ptr(cpu).to_string_lossy().into_owned(),
+                CStr::from_ptr(features).to_string_lossy().into_owned(),
+            );
+            LLVMDisposeMessage(cpu);
+            LLVMDisposeMessage(features);
+            result
+        };
+
+        // Later features take precedence, so the explicit ones go last.
+        if !self.features.is_empty() {
+            if !features.is_empty() {
+                features.push(',');
+            }
+            features.push_str(&self.features);
+        }
+
+        (cpu, features)
+    }
+}
+
+/// How the generated code references symbols and data.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RelocationModel {
+    /// Absolute addresses for data, position independent calls.
+    #[default]
+    DynamicNoPic,
+    /// Position independent code, which can be loaded at any address.
+    Pic,
+    /// Absolute addresses, for code which is loaded at a fixed address. Objects with this model
+    /// usually can't be linked into shared libraries.
+    Static,
+}
+
+impl From<RelocationModel> for LLVMRelocMode {
+    fn from(value: RelocationModel) -> Self {
+        match value {
+            RelocationModel::DynamicNoPic => LLVMRelocMode::LLVMRelocDynamicNoPic,
+            RelocationModel::Pic => LLVMRelocMode::LLVMRelocPIC,
+            RelocationModel::Static => LLVMRelocMode::LLVMRelocStatic,
+        }
+    }
+}
+
 /// Converts a MLIR module to a compile object, that can be linked with a linker.
 pub fn module_to_object(
     module: &Module<'_>,
     opt_level: OptLevel,
 ) -> Result<Vec<u8>, LLVMCompileError> {
+    module_to_object_with_target(module, opt_level, &TargetConfig::default())
+}
+
+/// Like [module_to_object], but compiling for the given target instead of the host. Check out
+/// [TargetConfig] for more info.
+pub fn module_to_object_with_target(
+    module: &Module<'_>,
+    opt_level: OptLevel,
+    target: &TargetConfig,
+) -> Result<Vec<u8>, LLVMCompileError> {
+    target.validate()?;
+
+    let (triple, cpu, features) = target_machine_params(target)?;
+
+    unsafe {
+        emit_object(
+            module,
+            opt_level,
+            triple.as_ptr(),
+            cpu.as_ptr(),
+            features.as_ptr(),
+            target.relocation_model.into(),
+            target.triple.is_some(),
+        )
+    }
+}
+
+/// Return the target triple, CPU name and features of the target, as given to LLVM.
+fn target_machine_params(
+    target: &TargetConfig,
+) -> Result<(CString, CString, CString), LLVMCompileError> {
+    let (cpu, features) = target.resolve();
+    let into_c_string = |x: String| CString::new(x).map_err(|e| LLVMCompileError(e.to_string()));
+
+    Ok((
+        into_c_string(target.target_triple())?,
+        into_c_string(cpu)?,
+        into_c_string(features)?,
+    ))
+}
+
+fn initialize_llvm() {
     static INITIALIZED: OnceLock<()> = OnceLock::new();
 
     INITIALIZED.get_or_init(|| unsafe {
@@ -125,157 +409,253 @@ pub fn module_to_object(
         LLVM_InitializeAllAsmPrinters();
         LLVM_InitializeAllAsmParsers();
     });
+}
 
-    unsafe {
-        let llvm_context = LLVMContextCreate();
+/// Translate the MLIR module to LLVM IR and emit an object file for the given target.
+///
+/// When `override_target` is set, the target triple and data layout of the module are replaced by
+/// the ones of the target, instead of keeping the host's.
+unsafe fn emit_object(
+    module: &Module<'_>,
+    opt_level: OptLevel,
+    target_triple: *const c_char,
+    target_cpu: *const c_char,
+    target_cpu_features: *const c_char,
+    reloc_mode: LLVMRelocMode,
+    override_target: bool,
+) -> Result<Vec<u8>, LLVMCompileError> {
+    initialize_llvm();
 
-        let op = module.as_operation().to_raw();
+    let llvm_context = LLVMContextCreate();
 
-        let llvm_module = mlirTranslateModuleToLLVMIR(op, llvm_context);
+    let op = module.as_operation().to_raw();
 
-        let mut null = null_mut();
-        let mut error_buffer = addr_of_mut!(null);
+    let llvm_module = mlirTranslateModuleToLLVMIR(op, llvm_context);
 
-        let target_triple = LLVMGetDefaultTargetTriple();
-        let target_cpu = LLVMGetHostCPUName();
-        let target_cpu_features = LLVMGetHostCPUFeatures();
+    let mut null = null_mut();
+    let mut error_buffer = addr_of_mut!(null);
 
-        let mut target: MaybeUninit<LLVMTargetRef> = MaybeUninit::uninit();
+    let mut target: MaybeUninit<LLVMTargetRef> = MaybeUninit::uninit();
 
-        if LLVMGetTargetFromTriple(target_triple, target.as_mut_ptr(), error_buffer) != 0 {
-            let error = CStr::from_ptr(*error_buffer);
-            let err = error.to_string_lossy().to_string();
-            LLVMDisposeMessage(*error_buffer);
-            Err(LLVMCompileError(err))?;
-        } else if !(*error_buffer).is_null() {
-            LLVMDisposeMessage(*error_buffer);
-            error_buffer = addr_of_mut!(null);
-        }
+    if LLVMGetTargetFromTriple(target_triple, target.as_mut_ptr(), error_buffer) != 0 {
+        let error = CStr::from_ptr(*error_buffer);
+        let err = error.to_string_lossy().to_string();
+        LLVMDisposeMessage(*error_buffer);
+        Err(LLVMCompileError(err))?;
+    } else if !(*error_buffer).is_null() {
+        LLVMDisposeMessage(*error_buffer);
+        error_buffer = addr_of_mut!(null);
+    }
 
-        let target = target.assume_init();
+    let target = target.assume_init();
 
-        let machine = LLVMCreateTargetMachine(
-            target,
-            target_triple.cast(),
-            target_cpu.cast(),
-            target_cpu_features.cast(),
-            match opt_level {
-                OptLevel::None => LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
-                OptLevel::Less => LLVMCodeGenOptLevel::LLVMCodeGenLevelLess,
-                OptLevel::Default => LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
-                OptLevel::Aggressive => LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
-            },
-            LLVMRelocMode::LLVMRelocDynamicNoPic,
-            LLVMCodeModel::LLVMCodeModelDefault,
-        );
+    let machine = LLVMCreateTargetMachine(
+        target,
+        target_triple.cast(),
+        target_cpu.cast(),
+        target_cpu_features.cast(),
+        match opt_level {
+            OptLevel::None => LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
+            OptLevel::Less => LLVMCodeGenOptLevel::LLVMCodeGenLevelLess,
+            OptLevel::Default => LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+            OptLevel::Aggressive => LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
+        },
+        reloc_mode,
+        LLVMCodeModel::LLVMCodeModelDefault,
+    );
 
-        let mut out_buf: MaybeUninit<LLVMMemoryBufferRef> = MaybeUninit::uninit();
+    if override_target {
+        LLVMSetTarget(llvm_module, target_triple);
+        let data_layout = LLVMCreateTargetDataLayout(machine);
+        LLVMSetModuleDataLayout(llvm_module, data_layout);
+        LLVMDisposeTargetData(data_layout);
+    }
 
-        let ok = LLVMTargetMachineEmitToMemoryBuffer(
-            machine,
-            llvm_module,
-            LLVMCodeGenFileType::LLVMObjectFile,
-            error_buffer,
-            out_buf.as_mut_ptr(),
-        );
+    let mut out_buf: MaybeUninit<LLVMMemoryBufferRef> = MaybeUninit::uninit();
 
-        if ok != 0 {
-            let error = CStr::from_ptr(*error_buffer);
-            let err = error.to_string_lossy().to_string();
-            LLVMDisposeMessage(*error_buffer);
-            Err(LLVMCompileError(err))?;
-        } else if !(*error_buffer).is_null() {
-            LLVMDisposeMessage(*error_buffer);
-        }
+    let ok = LLVMTargetMachineEmitToMemoryBuffer(
+        machine,
+        llvm_module,
+        LLVMCodeGenFileType::LLVMObjectFile,
+        error_buffer,
+        out_buf.as_mut_ptr(),
+    );
+
+    if ok != 0 {
+        let error = CStr::from_ptr(*error_buffer);
+        let err = error.to_string_lossy().to_string();
+        LLVMDisposeMessage(*error_buffer);
+        Err(LLVMCompileError(err))?;
+    } else if !(*error_buffer).is_null() {
+        LLVMDisposeMessage(*error_buffer);
+    }
 
-        let out_buf = out_buf.assume_init();
+    let out_buf = out_buf.assume_init();
 
-        let out_buf_start: *const u8 = LLVMGetBufferStart(out_buf).cast();
-        let out_buf_size = LLVMGetBufferSize(out_buf);
+    let out_buf_start: *const u8 = LLVMGetBufferStart(out_buf).cast();
+    let out_buf_size = LLVMGetBufferSize(out_buf);
 
-        // keep it in rust side
-        let data = std::slice::from_raw_parts(out_buf_start, out_buf_size).to_vec();
+    // keep it in rust side
+    let data = std::slice::from_raw_parts(out_buf_start, out_buf_size).to_vec();
 
-        LLVMDisposeMemoryBuffer(out_buf);
-        LLVMDisposeTargetMachine(machine);
-        LLVMDisposeModule(llvm_module);
-        LLVMContextDispose(llvm_context);
+    LLVMDisposeMemoryBuffer(out_buf);
+    LLVMDisposeTargetMachine(machine);
+    LLVMDisposeModule(llvm_module);
+    LLVMContextDispose(llvm_context);
 
-        Ok(data)
-    }
+    Ok(data)
 }
 
 /// Links the passed object into a shared library, stored on the given path.
 pub fn object_to_shared_lib(object: &[u8], output_filename: &Path) -> Result<(), std::io::Error> {
+    object_to_shared_lib_with_target(object, output_filename, &TargetConfig::default())
+}
+
+/// Like [object_to_shared_lib], but for an object compiled for the given target (ex. by
+/// [module_to_object_with_target]). Check out [TargetConfig] for how cross-compiled objects are
+/// linked.
+pub fn object_to_shared_lib_with_target(
+    object: &[u8],
+    output_filename: &Path,
+    target: &TargetConfig,
+) -> Result<(), std::io::Error> {
     // linker seems to need a file and doesn't accept stdin
     let mut file = NamedTempFile::new()?;
     file.write_all(object)?;
     let file = file.into_temp_path();
 
-    let file_path = file.display().to_string();
-    let output_path = output_filename.display().to_string();
+    link_shared_lib(&file, output_filename, target)
+}
 
-    let args: Vec<Cow<'static, str>> = {
-        #[cfg(target_os = "macos")]
-        {
-            let mut args: Vec<Cow<'static, str>> = vec![
-                "-demangle".into(),
-                "-no_deduplicate".into(),
-                "-dynamic".into(),
-                "-dylib".into(),
-                "-L/usr/local/lib".into(),
-                "-L/Library/Developer/CommandLineTools/SDKs/MacOSX.sdk/usr/lib".into(),
-            ];
+/// An object file linked and loaded in memory by LLVM's JIT linker, without running an external
+/// linker nor writing anything into the filesystem.
+///
+/// The runtime library isn't linked into the object. Its symbols are the ones of the current
+/// process (like when using the [JIT executor](crate::executor::JitNativeExecutor)), and the rest
+/// of the undefined symbols (ex. `libc`) are looked up in the libraries loaded by the process.
+pub struct InMemoryObject {
+    jit: LLVMOrcLLJITRef,
+}
 
-            args.extend([
-                Cow::from(file_path),
-                "-o".into(),
-                Cow::from(output_path),
-                "-lSystem".into(),
-            ]);
+// The JIT is thread safe, and nothing is added to it after the object has been loaded.
+unsafe impl Send for InMemoryObject {}
+unsafe impl Sync for InMemoryObject {}
 
-            if let Ok(extra_dir) = std::env::var("CAIRO_NATIVE_RUNTIME_LIBRARY") {
-                args.extend([Cow::from(extra_dir)]);
-            } else {
-                args.extend(["libcairo_native_runtime.a".into()]);
-            }
+impl InMemoryObject {
+    /// Load an object compiled for the host (ex. by [module_to_object]).
+    pub fn load(object: &[u8]) -> Result<Self, LLVMCompileError> {
+        initialize_llvm();
 
-            args
-        }
-        #[cfg(target_os = "linux")]
-        {
-            let mut args: Vec<Cow<'static, str>> = vec![
-                "--hash-style=gnu".into(),
-                "--eh-frame-hdr".into(),
-                "-shared".into(),
-                "-L/lib/../lib64".into(),
-                "-L/usr/lib/../lib64".into(),
-            ];
+        unsafe {
+            let mut jit = null_mut();
+            check_error(LLVMOrcCreateLLJIT(&mut jit, null_mut()))?;
+            // Dispose of the JIT if anything below fails.
+            let this = Self { jit };
 
-            args.extend([
-                "-o".into(),
-                Cow::from(output_path),
-                "-lc".into(),
-                //"-lcairo_native_runtime".into(),
-                Cow::from(file_path),
-            ]);
+            let dylib = LLVMOrcLLJITGetMainJITDylib(jit);
 
-            if let Ok(extra_dir) = std::env::var("CAIRO_NATIVE_RUNTIME_LIBRARY") {
-                args.extend([Cow::from(extra_dir)]);
-            } else {
-                args.extend(["libcairo_native_runtime.a".into()]);
-            }
+            let mut generator = null_mut();
+            check_error(LLVMOrcCreateDynamicLibrarySearchGeneratorForProcess(
+                &mut generator,
+                LLVMOrcLLJITGetGlobalPrefix(jit),
+                None,
+                null_mut(),
+            ))?;
+            LLVMOrcJITDylibAddGenerator(dylib, generator);
+
+            #[cfg(feature = "with-runtime")]
+            this.define_runtime_symbols(dylib)?;
 
-            args
+            // The JIT takes the ownership of the buffer.
+            let buffer = LLVMCreateMemoryBufferWithMemoryRangeCopy(
+                object.as_ptr().cast(),
+                object.len(),
+                c"cairo-native-object".as_ptr(),
+            );
+            check_error(LLVMOrcLLJITAddObjectFile(jit, dylib, buffer))?;
+
+            Ok(this)
         }
-        #[cfg(target_os = "windows")]
-        {
-            unimplemented!()
+    }
+
+    /// Return the address of a symbol of the object, or `None` if it isn't defined.
+    pub fn get(&self, symbol: &str) -> Option<NonNull<c_void>> {
+        let symbol = CString::new(symbol).ok()?;
+
+        let mut address = 0;
+        unsafe { check_error(LLVMOrcLLJITLookup(self.jit, &mut address, symbol.as_ptr())) }.ok()?;
+
+        NonNull::new(address as *mut c_void)
+    }
+
+    /// Define the symbols of the runtime library linked into the current process, which aren't
+    /// necessarily exported by the executable.
+    #[cfg(feature = "with-runtime")]
+    unsafe fn define_runtime_symbols(
+        &self,
+        dylib: llvm_sys::orc2::LLVMOrcJITDylibRef,
+    ) -> Result<(), LLVMCompileError> {
+        use llvm_sys::orc2::{
+            lljit::LLVMOrcLLJITMangleAndIntern, LLVMJITEvaluatedSymbol, LLVMJITSymbolFlags,
+            LLVMJITSymbolGenericFlags, LLVMOrcAbsoluteSymbols, LLVMOrcCSymbolMapPair,
+            LLVMOrcDisposeMaterializationUnit, LLVMOrcJITDylibDefine,
+        };
+
+        let mut symbols = crate::utils::runtime_symbols()
+            .into_iter()
+            .map(|(name, ptr)| {
+                let name = CString::new(name).unwrap();
+                LLVMOrcCSymbolMapPair {
+                    Name: LLVMOrcLLJITMangleAndIntern(self.jit, name.as_ptr()),
+                    Sym: LLVMJITEvaluatedSymbol {
+                        Address: ptr as u64,
+                        Flags: LLVMJITSymbolFlags {
+                            GenericFlags:
+                                LLVMJITSymbolGenericFlags::LLVMJITSymbolGenericFlagsExported as u8
+                                    | LLVMJITSymbolGenericFlags::LLVMJITSymbolGenericFlagsCallable
+                                        as u8,
+                            TargetFlags: 0,
+                        },
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // The unit is owned by the dylib only if it's defined successfully.
+        let unit = LLVMOrcAbsoluteSymbols(symbols.as_mut_ptr(), symbols.len());
+        check_error(LLVMOrcJITDylibDefine(dylib, unit)).inspect_err(|_| {
+            LLVMOrcDisposeMaterializationUnit(unit);
+        })
+    }
+}
+
+impl Drop for InMemoryObject {
+    fn drop(&mut self) {
+        if let Err(e) = unsafe { check_error(LLVMOrcDisposeLLJIT(self.jit)) } {
+            tracing::warn!("Failed to dispose of the JIT: {e}");
         }
-    };
+    }
+}
+
+/// Consume an LLVM error, turning it into a [LLVMCompileError].
+unsafe fn check_error(error: LLVMErrorRef) -> Result<(), LLVMCompileError> {
+    if error.is_null() {
+        return Ok(());
+    }
+
+    let message = LLVMGetErrorMessage(error);
+    let result = LLVMCompileError(CStr::from_ptr(message).to_string_lossy().into_owned());
+    LLVMDisposeErrorMessage(message);
 
-    let mut linker = std::process::Command::new("ld");
-    let proc = linker.args(args.iter().map(|x| x.as_ref())).output()?;
+    Err(result)
+}
+
+fn link_shared_lib(
+    object_filename: &Path,
+    output_filename: &Path,
+    target: &TargetConfig,
+) -> Result<(), std::io::Error> {
+    let proc = linker_command(object_filename, output_filename, target).output()?;
     if proc.status.success() {
         Ok(())
     } else {
@@ -284,6 +664,122 @@ pub fn object_to_shared_lib(object: &[u8], output_filename: &Path) -> Result<(),
     }
 }
 
+/// Build the invocation of the linker which links an object of the target into a shared library.
+///
+/// Objects of the host are linked by the system's `ld`, against the libraries of the host and the
+/// runtime library from `CAIRO_NATIVE_RUNTIME_LIBRARY`. Cross-compiled objects are linked with the
+/// target's toolchain instead, and WebAssembly objects into a WebAssembly module (check out
+/// [TargetConfig]).
+fn linker_command(
+    object_filename: &Path,
+    output_filename: &Path,
+    target: &TargetConfig,
+) -> std::process::Command {
+    let file_path = object_filename.display().to_string();
+    let output_path = output_filename.display().to_string();
+
+    let triple = target.target_triple();
+    let is_cross = target.is_cross();
+    let arch = match triple.split('-').next() {
+        Some("arm64") => "aarch64",
+        Some(arch) => arch,
+        None => "",
+    };
+    let is_macos = triple.contains("apple") || triple.contains("darwin");
+
+    let runtime_library = if is_cross {
+        let triple_var = triple.to_uppercase().replace(['-', '.'], "_");
+        std::env::var(format!("CAIRO_NATIVE_RUNTIME_LIBRARY_{triple_var}"))
+            .unwrap_or_else(|_| format!("target/{triple}/release/libcairo_native_runtime.a"))
+    } else {
+        std::env::var("CAIRO_NATIVE_RUNTIME_LIBRARY")
+            .unwrap_or_else(|_| "libcairo_native_runtime.a".to_string())
+    };
+
+    let args: Vec<Cow<'static, str>> = if target.is_wasm() {
+        // The runtime functions are left undefined, so that they're imported from the host.
+        vec![
+            "--no-entry".into(),
+            "--export-dynamic".into(),
+            "--allow-undefined".into(),
+            Cow::from(file_path),
+            "-o".into(),
+            Cow::from(output_path),
+        ]
+    } else if triple.contains("windows") {
+        unimplemented!()
+    } else if is_macos {
+        let mut args: Vec<Cow<'static, str>> = vec![
+            "-demangle".into(),
+            "-no_deduplicate".into(),
+            "-dynamic".into(),
+            "-dylib".into(),
+        ];
+        if is_cross {
+            args.extend(["-arch".into(), Cow::from(arch.replace("aarch64", "arm64"))]);
+        } else {
+            args.extend([
+                "-L/usr/local/lib".into(),
+                "-L/Library/Developer/CommandLineTools/SDKs/MacOSX.sdk/usr/lib".into(),
+            ]);
+        }
+
+        args.extend([
+            Cow::from(file_path),
+            "-o".into(),
+            Cow::from(output_path),
+            "-lSystem".into(),
+            Cow::from(runtime_library),
+        ]);
+
+        args
+    } else {
+        let mut args: Vec<Cow<'static, str>> = vec![
+            "--hash-style=gnu".into(),
+            "--eh-frame-hdr".into(),
+            "-shared".into(),
+        ];
+        if is_cross {
+            // Where the cross toolchains of Debian-based distributions install the target's libc.
+            args.extend([
+                format!("-L/usr/{arch}-linux-gnu/lib").into(),
+                format!("-L/usr/lib/{arch}-linux-gnu").into(),
+            ]);
+        } else {
+            args.extend(["-L/lib/../lib64".into(), "-L/usr/lib/../lib64".into()]);
+        }
+
+        args.extend([
+            "-o".into(),
+            Cow::from(output_path),
+            "-lc".into(),
+            //"-lcairo_native_runtime".into(),
+            Cow::from(file_path),
+            Cow::from(runtime_library),
+        ]);
+
+        args
+    };
+
+    let linker_path = if is_cross {
+        std::env::var("CAIRO_NATIVE_LINKER").unwrap_or_else(|_| {
+            if target.is_wasm() {
+                "wasm-ld".to_string()
+            } else if is_macos {
+                "ld64.lld".to_string()
+            } else {
+                format!("{arch}-linux-gnu-ld")
+            }
+        })
+    } else {
+        "ld".to_string()
+    };
+
+    let mut linker = std::process::Command::new(linker_path);
+    linker.args(args.iter().map(|x| x.as_ref()));
+    linker
+}
+
 /// Gets the target triple, which identifies the platform and ABI.
 pub fn get_target_triple() -> String {
     let target_triple = unsafe {
@@ -295,21 +791,18 @@ pub fn get_target_triple() -> String {
 
 /// Gets the data layout reprrsentation as a string, to be given to the MLIR module.
 /// LLVM uses this to know the proper alignments for the given sizes, etc.
-/// This function gets the data layout of the host target triple.
-pub fn get_data_layout_rep() -> Result<String, CompileError> {
+/// This function gets the data layout of the given target.
+pub fn get_data_layout_rep(target_config: &TargetConfig) -> Result<String, CompileError> {
+    let (target_triple, target_cpu, target_cpu_features) = target_machine_params(target_config)
+        .map_err(|e| CompileError::LLVMCompileError(e.to_string()))?;
+
     unsafe {
         let mut null = null_mut();
         let error_buffer = addr_of_mut!(null);
 
-        let target_triple = LLVMGetDefaultTargetTriple();
-
-        let target_cpu = LLVMGetHostCPUName();
-
-        let target_cpu_features = LLVMGetHostCPUFeatures();
-
         let mut target: MaybeUninit<LLVMTargetRef> = MaybeUninit::uninit();
 
-        if LLVMGetTargetFromTriple(target_triple, target.as_mut_ptr(), error_buffer) != 0 {
+        if LLVMGetTargetFromTriple(target_triple.as_ptr(), target.as_mut_ptr(), error_buffer) != 0 {
             let error = CStr::from_ptr(*error_buffer);
             let err = error.to_string_lossy().to_string();
             tracing::error!("error getting target triple: {}", err);
@@ -324,11 +817,11 @@ pub fn get_data_layout_rep() -> Result<String, CompileError> {
 
         let machine = LLVMCreateTargetMachine(
             target,
-            target_triple.cast(),
-            target_cpu.cast(),
-            target_cpu_features.cast(),
+            target_triple.as_ptr(),
+            target_cpu.as_ptr(),
+            target_cpu_features.as_ptr(),
             LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
-            LLVMRelocMode::LLVMRelocDynamicNoPic,
+            target_config.relocation_model.into(),
             LLVMCodeModel::LLVMCodeModelDefault,
         );
 
@@ -342,6 +835,11 @@ pub fn get_data_layout_rep() -> Result<String, CompileError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        context::{CompilerConfig, NativeContext},
+        utils::test::load_cairo,
+    };
+    use melior::ir::attribute::StringAttribute;
 
     #[test]
     fn test_opt_level_default() {
@@ -377,4 +875,256 @@ mod tests {
         assert_eq!(OptLevel::from(3u8), OptLevel::Aggressive);
         assert_eq!(OptLevel::from(30u8), OptLevel::Aggressive);
     }
+    #[test]
+    fn test_target_config_resolve() {
+        let target = TargetConfig {
+            cpu: "generic".to_string(),
+            features: "-avx512f".to_string(),
+            relocation_model: RelocationModel::Static,
+            ..Default::default()
+        };
+        assert_eq!(
+            target.resolve(),
+            ("generic".to_string(), "-avx512f".to_string())
+        );
+
+        // The host's features are extended by the explicit ones.
+        let (cpu, features) = TargetConfig {
+            features: "-avx512f".to_string(),
+            ..Default::default()
+        }
+        .resolve();
+        assert_ne!(cpu, NATIVE_CPU);
+        assert!(features.ends_with("-avx512f"));
+    }
+
+    #[test]
+    fn test_module_to_object_with_target() {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a + b
+            }
+        };
+
+        let context = NativeContext::new();
+        let module = context.compile(&program.1, None).unwrap();
+
+        for relocation_model in [
+            RelocationModel::DynamicNoPic,
+            RelocationModel::Pic,
+            RelocationModel::Static,
+        ] {
+            let target = TargetConfig {
+                cpu: "generic".to_string(),
+                relocation_model,
+                ..Default::default()
+            };
+            let object =
+                module_to_object_with_target(module.module(), OptLevel::None, &target).unwrap();
+            assert!(!object.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_module_to_object_debug_info() {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a * b
+            }
+        };
+
+        let contains = |data: &[u8], needle: &[u8]| data.windows(needle.len()).any(|x| x == needle);
+        for debug_info in [false, true] {
+            let context =
+                NativeContext::new().with_config(CompilerConfig::new().with_debug_info(debug_info));
+            let module = context.compile(&program.1, None).unwrap();
+
+            let object = module_to_object(module.module(), OptLevel::None).unwrap();
+            assert_eq!(contains(&object, b"debug_info"), debug_info);
+            assert_eq!(contains(&object, b"debug_line"), debug_info);
+            // The statements are attributed to the Sierra program, since there's no debug info.
+            assert_eq!(
+                contains(&object, crate::SIERRA_STATEMENTS_FILE.as_bytes()),
+                debug_info
+            );
+        }
+    }
+
+    #[test]
+    fn test_cross_compile() {
+        let program = load_cairo! {
+            fn run_test(a: u128, b: u128) -> u128 {
+                a & b
+            }
+        };
+
+        // Compile for the other supported architecture, whichever the host is.
+        let (triple, machine) = if get_target_triple().starts_with("x86_64") {
+            ("aarch64-unknown-linux-gnu", 0xB7)
+        } else {
+            ("x86_64-unknown-linux-gnu", 0x3E)
+        };
+        let target = TargetConfig {
+            triple: Some(triple.to_string()),
+            ..Default::default()
+        };
+        assert!(target.is_cross());
+        assert_eq!(target.resolve().0, "generic");
+
+        let context =
+            NativeContext::new().with_config(CompilerConfig::new().with_target(target.clone()));
+        let module = context.compile(&program.1, None).unwrap();
+        let data_layout = StringAttribute::try_from(
+            module
+                .module()
+                .as_operation()
+                .attribute("llvm.data_layout")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(data_layout.value(), get_data_layout_rep(&target).unwrap());
+
+        // Check the machine of the ELF header.
+        let object =
+            module_to_object_with_target(module.module(), OptLevel::None, &target).unwrap();
+        assert_eq!(&object[..4], b"\x7fELF");
+        assert_eq!(u16::from_le_bytes([object[18], object[19]]), machine);
+    }
+
+    #[test]
+    fn test_cross_link() {
+        let (object_path, output_path) = (Path::new("program.o"), Path::new("program.so"));
+        let args = |command: &std::process::Command| {
+            command
+                .get_args()
+                .map(|x| x.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // Objects of the host are always linked by the system's linker.
+        let command = linker_command(object_path, output_path, &TargetConfig::default());
+        assert_eq!(command.get_program(), "ld");
+
+        let arch = if get_target_triple().starts_with("x86_64") {
+            "aarch64"
+        } else {
+            "x86_64"
+        };
+        let target = TargetConfig {
+            triple: Some(format!("{arch}-unknown-linux-gnu")),
+            ..Default::default()
+        };
+        let command = linker_command(object_path, output_path, &target);
+        let cross_args = args(&command);
+        if std::env::var_os("CAIRO_NATIVE_LINKER").is_none() {
+            assert_eq!(
+                command.get_program(),
+                format!("{arch}-linux-gnu-ld").as_str()
+            );
+        }
+        assert!(cross_args.contains(&format!("-L/usr/{arch}-linux-gnu/lib")));
+        assert!(!cross_args.iter().any(|x| x.contains("lib64")));
+
+        let runtime_library = cross_args.last().unwrap().clone();
+        assert_ne!(runtime_library, "libcairo_native_runtime.a");
+
+        // Link for real when the toolchain and the runtime library of the target are available.
+        let has_linker = std::process::Command::new(command.get_program())
+            .arg("--version")
+            .output()
+            .is_ok_and(|x| x.status.success());
+        if !has_linker || !Path::new(&runtime_library).exists() {
+            return;
+        }
+
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a + b
+            }
+        };
+        let context =
+            NativeContext::new().with_config(CompilerConfig::new().with_target(target.clone()));
+        let module = context.compile(&program.1, None).unwrap();
+        let object =
+            module_to_object_with_target(module.module(), OptLevel::None, &target).unwrap();
+
+        let library_path = NamedTempFile::new().unwrap().into_temp_path();
+        object_to_shared_lib_with_target(&object, &library_path, &target).unwrap();
+
+        // Check the type (shared object) and the machine of the ELF header.
+        let library = std::fs::read(&library_path).unwrap();
+        assert_eq!(u16::from_le_bytes([library[16], library[17]]), 3);
+        assert_eq!(
+            u16::from_le_bytes([library[18], library[19]]),
+            u16::from_le_bytes([object[18], object[19]])
+        );
+    }
+
+    #[test]
+    fn test_compile_wasm() {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a + b
+            }
+        };
+
+        let target = TargetConfig {
+            triple: Some("wasm32-unknown-unknown".to_string()),
+            ..Default::default()
+        };
+        assert!(target.is_wasm());
+        assert!(target.validate().is_ok());
+
+        let context =
+            NativeContext::new().with_config(CompilerConfig::new().with_target(target.clone()));
+        let module = context.compile(&program.1, None).unwrap();
+
+        // Check the magic number and the version of the WebAssembly binary.
+        let object =
+            module_to_object_with_target(module.module(), OptLevel::None, &target).unwrap();
+        assert_eq!(&object[..8], b"\0asm\x01\0\0\0");
+
+        let command = linker_command(Path::new("program.o"), Path::new("program.wasm"), &target);
+        if std::env::var_os("CAIRO_NATIVE_LINKER").is_none() {
+            assert_eq!(command.get_program(), "wasm-ld");
+        }
+        assert!(command.get_args().any(|x| x == "--allow-undefined"));
+    }
+
+    #[test]
+    fn test_cross_compile_unsupported() {
+        let target = TargetConfig {
+            triple: Some("riscv32-unknown-linux-gnu".to_string()),
+            ..Default::default()
+        };
+        assert!(target.validate().is_err());
+
+        let context = NativeContext::new().with_config(CompilerConfig::new().with_target(target));
+        let program = load_cairo! {
+            fn run_test() {}
+        };
+        assert!(context.compile(&program.1, None).is_err());
+    }
+
+    #[test]
+    fn test_merge_modules() {
+        let context = NativeContext::new();
+        let parse = |source| Module::parse(context.context(), source).unwrap();
+
+        let target = parse("llvm.func @f(i64)");
+        merge_modules(
+            &target,
+            [
+                parse("llvm.func @f(i64)\nllvm.func @g()"),
+                parse("llvm.func @g()"),
+            ],
+        )
+        .unwrap();
+        let mlir = target.as_operation().to_string();
+        assert_eq!(mlir.matches("llvm.func @f").count(), 1);
+        assert_eq!(mlir.matches("llvm.func @g").count(), 1);
+
+        // Symbols can't be defined differently.
+        assert!(merge_modules(&target, [parse("llvm.func @f(i32)")]).is_err());
+    }
 }
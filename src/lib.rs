@@ -77,11 +77,20 @@
 //!  ├─ libfuncs - Cairo Sierra libfunc implementations
 //!  ├─ libfuncs.rs - Cairo Sierra libfunc glue code
 //!  ├─ starknet.rs - Starknet syscall handler glue code.
+//!  ├─ starknet_cache.rs - Read-through storage cache for syscall handlers.
 //!  ├─ ffi.rs - Missing FFI C wrappers, rust side.
 //!  ├─ block_ext.rs - A melior (MLIR) block trait extension to write less code.
+//!  ├─ backend.rs - Codegen backend abstraction.
 //!  ├─ lib.rs - The main lib file.
+//!  ├─ plugin.rs - Libfunc builders supplied by downstream crates.
+//!  ├─ prelude.rs - Re-exports of the commonly used types.
 //!  ├─ execution_result.rs - Program result parsing.
+//!  ├─ execution_snapshots.rs - Value snapshots for time-travel debugging (with-debug-utils).
+//!  ├─ profiler.rs - Per-statement and per-libfunc profiling (with-profiler).
+//!  ├─ entry_points.rs - Entry point signature reflection.
+//!  ├─ determinism.rs - Strict-determinism audit of the generated code.
 //!  ├─ values.rs - JIT serialization.
+//!  ├─ felt.rs - Host-side felt conversions and modular arithmetic.
 //!  ├─ metadata.rs - Metadata injector to use within the compilation process.
 //!  ├─ compiler.rs - The glue code of the compiler, has the codegen for the function signatures
 //!  and calls the libfunc codegen implementations.
@@ -94,24 +103,41 @@
 #![allow(clippy::missing_safety_doc)]
 
 pub use self::{
-    compiler::compile,
-    ffi::{module_to_object, object_to_shared_lib, LLVMCompileError, OptLevel},
+    compiler::{compile, compile_parallel, PARALLEL_CHUNK_SIZE, SIERRA_STATEMENTS_FILE},
+    ffi::{
+        module_to_object, module_to_object_with_target, object_to_shared_lib,
+        object_to_shared_lib_with_target, InMemoryObject, LLVMCompileError, OptLevel,
+        RelocationModel, TargetConfig, NATIVE_CPU,
+    },
 };
 
+pub mod backend;
 pub(crate) mod block_ext;
 pub mod cache;
 mod compiler;
 pub mod context;
 pub mod debug_info;
+pub mod determinism;
+pub mod entry_points;
 pub mod error;
 pub mod execution_result;
+pub mod execution_snapshots;
 pub mod executor;
+pub mod felt;
 mod ffi;
 pub mod libfuncs;
 pub mod metadata;
 pub mod module;
+pub mod plugin;
+pub mod prelude;
+pub mod profiler;
 pub mod starknet;
+pub mod starknet_cache;
+pub mod starknet_fuzz;
 pub mod starknet_stub;
+pub mod syscall_profile;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod types;
 pub mod utils;
 pub mod values;
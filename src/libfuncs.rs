@@ -8,14 +8,15 @@ use bumpalo::Bump;
 use cairo_lang_sierra::{
     extensions::core::{CoreConcreteLibfunc, CoreLibfunc, CoreType},
     ids::FunctionId,
+    program::{LibfuncDeclaration, Program, Statement},
     program_registry::ProgramRegistry,
 };
 use melior::{
     dialect::{arith, cf},
-    ir::{Block, BlockRef, Location, Module, Operation, Region, Value, ValueLike},
+    ir::{Block, BlockRef, Location, Module, Operation, Region, Type, Value, ValueLike},
     Context,
 };
-use std::{borrow::Cow, cell::Cell, error::Error, ops::Deref};
+use std::{borrow::Cow, cell::Cell, collections::HashSet, error::Error, ops::Deref};
 
 pub mod ap_tracking;
 pub mod array;
@@ -59,6 +60,27 @@ pub mod uint8;
 pub mod unconditional_jump;
 pub mod unwrap_non_zero;
 
+/// Return the declarations of the libfuncs invoked by a program, in declaration order.
+///
+/// Libfuncs which are declared but never invoked are skipped. Check out the
+/// [libfunc templates](crate::metadata::libfunc_templates) to build the common ones only once.
+pub fn used_libfuncs(program: &Program) -> Vec<&LibfuncDeclaration> {
+    let used = program
+        .statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Invocation(invocation) => Some(&invocation.libfunc_id),
+            Statement::Return(_) => None,
+        })
+        .collect::<HashSet<_>>();
+
+    program
+        .libfunc_declarations
+        .iter()
+        .filter(|decl| used.contains(&decl.id))
+        .collect()
+}
+
 /// Generation of MLIR operations from their Sierra counterparts.
 ///
 /// All possible Sierra libfuncs must implement it. It is already implemented for all the core
@@ -255,6 +277,14 @@ where
 
     pub(crate) branches: Vec<(&'this Block<'ctx>, Vec<BranchArg<'ctx, 'this>>)>,
     pub(crate) results: Vec<Vec<Cell<Option<Value<'ctx, 'this>>>>>,
+    /// The types of the values returned by each branch, as declared by the libfunc's Sierra
+    /// signature. Debug builds check every branch payload against them.
+    pub(crate) result_types: Vec<Vec<Type<'ctx>>>,
+
+    /// Per-branch replacement for one of the returned values, used to return the range check
+    /// counted by [RangeCheckUsageMeta](crate::metadata::range_check_usage::RangeCheckUsageMeta)
+    /// instead of the one computed by the libfunc.
+    pub(crate) overrides: Vec<Option<(usize, Value<'ctx, 'this>)>>,
 }
 
 impl<'ctx, 'this> LibfuncHelper<'ctx, 'this>
@@ -305,21 +335,7 @@ where
         results: &[Value<'ctx, 'this>],
         location: Location<'ctx>,
     ) -> Operation<'ctx> {
-        let (successor, operands) = &self.branches[branch];
-
-        for (dst, src) in self.results[branch].iter().zip(results) {
-            dst.replace(Some(*src));
-        }
-
-        let destination_operands = operands
-            .iter()
-            .copied()
-            .map(|op| match op {
-                BranchArg::External(x) => x,
-                BranchArg::Returned(i) => results[i],
-            })
-            .collect::<Vec<_>>();
-
+        let (successor, destination_operands) = self.branch_target(branch, results);
         cf::br(successor, &destination_operands, location)
     }
 
@@ -340,43 +356,8 @@ where
         results: [&[Value<'ctx, 'this>]; 2],
         location: Location<'ctx>,
     ) -> Operation<'ctx> {
-        let (block_true, args_true) = {
-            let (successor, operands) = &self.branches[branches[0]];
-
-            for (dst, src) in self.results[branches[0]].iter().zip(results[0]) {
-                dst.replace(Some(*src));
-            }
-
-            let destination_operands = operands
-                .iter()
-                .copied()
-                .map(|op| match op {
-                    BranchArg::External(x) => x,
-                    BranchArg::Returned(i) => results[0][i],
-                })
-                .collect::<Vec<_>>();
-
-            (*successor, destination_operands)
-        };
-
-        let (block_false, args_false) = {
-            let (successor, operands) = &self.branches[branches[1]];
-
-            for (dst, src) in self.results[branches[1]].iter().zip(results[1]) {
-                dst.replace(Some(*src));
-            }
-
-            let destination_operands = operands
-                .iter()
-                .copied()
-                .map(|op| match op {
-                    BranchArg::External(x) => x,
-                    BranchArg::Returned(i) => results[1][i],
-                })
-                .collect::<Vec<_>>();
-
-            (*successor, destination_operands)
-        };
+        let (block_true, args_true) = self.branch_target(branches[0], results[0]);
+        let (block_false, args_false) = self.branch_target(branches[1], results[1]);
 
         cf::cond_br(
             context,
@@ -408,22 +389,8 @@ where
         let default_destination = match default.0 {
             BranchTarget::Jump(x) => (x, Cow::Borrowed(default.1)),
             BranchTarget::Return(i) => {
-                let (successor, operands) = &self.branches[i];
-
-                for (dst, src) in self.results[i].iter().zip(default.1) {
-                    dst.replace(Some(*src));
-                }
-
-                let destination_operands = operands
-                    .iter()
-                    .copied()
-                    .map(|op| match op {
-                        BranchArg::External(x) => x,
-                        BranchArg::Returned(i) => default.1[i],
-                    })
-                    .collect();
-
-                (*successor, Cow::Owned(destination_operands))
+                let (successor, destination_operands) = self.branch_target(i, default.1);
+                (successor, Cow::Owned(destination_operands))
             }
         };
 
@@ -435,22 +402,8 @@ where
             case_destinations.push(match *successor {
                 BranchTarget::Jump(x) => (x, Cow::Borrowed(*operands)),
                 BranchTarget::Return(i) => {
-                    let (successor, operands) = &self.branches[i];
-
-                    for (dst, src) in self.results[i].iter().zip(default.1) {
-                        dst.replace(Some(*src));
-                    }
-
-                    let destination_operands = operands
-                        .iter()
-                        .copied()
-                        .map(|op| match op {
-                            BranchArg::External(x) => x,
-                            BranchArg::Returned(i) => default.1[i],
-                        })
-                        .collect();
-
-                    (*successor, Cow::Owned(destination_operands))
+                    let (successor, destination_operands) = self.branch_target(i, operands);
+                    (successor, Cow::Owned(destination_operands))
                 }
             });
         }
@@ -468,6 +421,46 @@ where
             location,
         )?)
     }
+
+    /// Store the returned values of a branch and return its successor block along with its
+    /// operands.
+    fn branch_target(
+        &self,
+        branch: usize,
+        results: &[Value<'ctx, 'this>],
+    ) -> (&'this Block<'ctx>, Vec<Value<'ctx, 'this>>) {
+        let mut results = results.to_vec();
+        if let Some((idx, value)) = self.overrides.get(branch).copied().flatten() {
+            results[idx] = value;
+        }
+
+        // Every branch must return exactly the values declared by its Sierra signature.
+        #[cfg(debug_assertions)]
+        if let Some(result_types) = self.result_types.get(branch) {
+            let value_types = results.iter().map(|x| x.r#type()).collect::<Vec<_>>();
+            assert_eq!(
+                &value_types, result_types,
+                "Branch {branch} returned values which don't match its signature."
+            );
+        }
+
+        let (successor, operands) = &self.branches[branch];
+
+        for (dst, src) in self.results[branch].iter().zip(&results) {
+            dst.replace(Some(*src));
+        }
+
+        let destination_operands = operands
+            .iter()
+            .copied()
+            .map(|op| match op {
+                BranchArg::External(x) => x,
+                BranchArg::Returned(i) => results[i],
+            })
+            .collect();
+
+        (successor, destination_operands)
+    }
 }
 
 impl<'ctx, 'this> Deref for LibfuncHelper<'ctx, 'this> {
@@ -514,7 +507,6 @@ mod tests {
     use super::*;
     use crate::context::NativeContext;
     use melior::ir::r#type::IntegerType;
-    use melior::ir::Type;
 
     #[test]
     fn switch_branch_arg_external_test() {
@@ -540,6 +532,8 @@ mod tests {
             last_block: Cell::new(&last_block),
             branches: Vec::new(),
             results: Vec::new(),
+            result_types: Vec::new(),
+            overrides: Vec::new(),
         };
 
         // Create an integer type with 32 bits
@@ -610,6 +604,8 @@ mod tests {
             last_block: Cell::new(&last_block),
             branches: Vec::new(),
             results: Vec::new(),
+            result_types: Vec::new(),
+            overrides: Vec::new(),
         };
 
         // Create an integer type with 32 bits
@@ -689,6 +685,8 @@ mod tests {
             last_block: Cell::new(&last_block),
             branches: Vec::new(),
             results: Vec::new(),
+            result_types: Vec::new(),
+            overrides: Vec::new(),
         };
 
         // Create an integer type with 32 bits
@@ -1,13 +1,17 @@
 //! # Array libfuncs
-
-// TODO: A future possible improvement would be to put the array behind a double pointer and a
-//   reference counter, to avoid unnecessary clones.
+//!
+//! Arrays share their buffer with their snapshots. Check out the [array type](crate::types::array)
+//! for more info.
 
 use super::LibfuncHelper;
 use crate::{
     block_ext::BlockExt,
     error::Result,
-    metadata::{realloc_bindings::ReallocBindingsMeta, MetadataStorage},
+    metadata::{
+        determinism::DeterminismMeta, overflow_checks::OverflowChecksMeta, panic::PanicMeta,
+        realloc_bindings::ReallocBindingsMeta, snapshot_clones::SnapshotClonesMeta,
+        MetadataStorage,
+    },
     types::TypeBuilder,
     utils::ProgramRegistryExt,
 };
@@ -18,6 +22,7 @@ use cairo_lang_sierra::{
         lib_func::{SignatureAndTypeConcreteLibfunc, SignatureOnlyConcreteLibfunc},
         ConcreteLibfunc,
     },
+    ids::ConcreteTypeId,
     program_registry::ProgramRegistry,
 };
 use melior::{
@@ -101,7 +106,8 @@ pub fn build_new<'ctx, 'this>(
         .append_op_result(ods::llvm::mlir_zero(context, pointer(context, 0), location).into())?;
 
     let k0 = entry.const_int(context, location, 0, 32)?;
-    let value = entry.append_op_result(llvm::undef(array_ty, location))?;
+    let value =
+        entry.append_op_result(DeterminismMeta::build_undef(metadata, array_ty, location))?;
     let value = entry.insert_value(context, location, value, ptr, 0)?;
     let value = entry.insert_value(context, location, value, k0, 1)?;
     let value = entry.insert_value(context, location, value, k0, 2)?;
@@ -122,13 +128,14 @@ pub fn build_append<'ctx, 'this>(
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
     // Algorithm:
+    //   - If the buffer is shared with a snapshot: copy it, then append.
     //   - If array_end < capacity, then append.
     //   - If array_end == capacity:
     //     - If array_start == 0: realloc, then append.
     //     - If array_start != 0: memmove, then append.
 
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let array_ty = registry.build_type(
@@ -154,28 +161,60 @@ pub fn build_append<'ctx, 'this>(
     let array_capacity =
         entry.extract_value(context, location, entry.argument(0)?.into(), len_ty, 3)?;
 
-    let has_tail_space = entry.append_op_result(arith::cmpi(
-        context,
-        CmpiPredicate::Ult,
-        array_end,
-        array_capacity,
-        location,
-    ))?;
-
+    let unique_block = helper.append_block(Block::new(&[]));
     let handle_block = helper.append_block(Block::new(&[]));
     let memmove_block = helper.append_block(Block::new(&[]));
     let realloc_block = helper.append_block(Block::new(&[]));
     let append_block = helper.append_block(Block::new(&[(array_ty, location)]));
 
-    entry.append_operation(cf::cond_br(
-        context,
-        has_tail_space,
-        append_block,
-        handle_block,
-        &[entry.argument(0)?.into()],
-        &[],
-        location,
-    ));
+    {
+        let ptr = entry.extract_value(context, location, entry.argument(0)?.into(), ptr_ty, 0)?;
+        let (block, is_shared) =
+            crate::types::array::build_is_shared(context, helper, entry, location, ptr)?;
+
+        let copy_block = helper.append_block(Block::new(&[]));
+        block.append_operation(cf::cond_br(
+            context,
+            is_shared,
+            copy_block,
+            unique_block,
+            &[],
+            &[],
+            location,
+        ));
+
+        let (copy_block, value) = build_copy_on_write(
+            context,
+            registry,
+            copy_block,
+            location,
+            helper,
+            metadata,
+            info,
+            entry.argument(0)?.into(),
+        )?;
+        copy_block.append_operation(cf::br(append_block, &[value], location));
+    }
+
+    {
+        let has_tail_space = unique_block.append_op_result(arith::cmpi(
+            context,
+            CmpiPredicate::Ult,
+            array_end,
+            array_capacity,
+            location,
+        ))?;
+
+        unique_block.append_operation(cf::cond_br(
+            context,
+            has_tail_space,
+            append_block,
+            handle_block,
+            &[entry.argument(0)?.into()],
+            &[],
+            location,
+        ));
+    }
 
     {
         let k0 = handle_block.const_int(context, location, 0, 32)?;
@@ -254,18 +293,7 @@ pub fn build_append<'ctx, 'this>(
     }
 
     {
-        let k8 = realloc_block.const_int(context, location, 8, 32)?;
-        let k1024 = realloc_block.const_int(context, location, 1024, 32)?;
-
-        // Array allocation growth formula:
-        //   new_len = max(8, old_len + min(1024, 2 * old_len));
-        let new_capacity = realloc_block.append_op_result(arith::shli(array_end, k1, location))?;
-        let new_capacity =
-            realloc_block.append_op_result(arith::minui(new_capacity, k1024, location))?;
-        let new_capacity =
-            realloc_block.append_op_result(arith::addi(new_capacity, array_end, location))?;
-        let new_capacity =
-            realloc_block.append_op_result(arith::maxui(new_capacity, k8, location))?;
+        let new_capacity = build_grown_capacity(context, realloc_block, location, array_end)?;
 
         let realloc_size = {
             let new_capacity = realloc_block.append_op_result(arith::extui(
@@ -278,12 +306,15 @@ pub fn build_append<'ctx, 'this>(
 
         let ptr =
             realloc_block.extract_value(context, location, entry.argument(0)?.into(), ptr_ty, 0)?;
-        let ptr = realloc_block.append_op_result(ReallocBindingsMeta::realloc(
+        let ptr = crate::types::array::build_realloc_data(
             context,
+            helper,
+            realloc_block,
+            location,
+            metadata,
             ptr,
             realloc_size,
-            location,
-        ))?;
+        )?;
 
         // No need to memmove, guaranteed by the fact that if we needed to memmove we'd have gone
         // through the memmove block instead of reallocating.
@@ -326,6 +357,14 @@ pub fn build_append<'ctx, 'this>(
             location,
         ))?;
 
+        DeterminismMeta::build_clear(
+            metadata,
+            context,
+            append_block,
+            location,
+            ptr,
+            elem_layout.pad_to_align().size(),
+        )?;
         append_block.store(context, location, ptr, entry.argument(1)?.into())?;
 
         let array_len = append_block.append_op_result(arith::addi(array_end, k1, location))?;
@@ -369,6 +408,13 @@ pub fn build_len<'ctx, 'this>(
 
     let array_len = entry.append_op_result(arith::subi(array_end, array_start, location))?;
 
+    // The snapshot is consumed, so its reference to the buffer has to be released.
+    let ptr_ty = crate::ffi::get_struct_field_type_at(&array_ty, 0);
+    let array_ptr = entry.extract_value(context, location, array_value, ptr_ty, 0)?;
+    let entry = crate::types::array::build_release_data(
+        context, helper, entry, location, metadata, array_ptr,
+    )?;
+
     entry.append_operation(helper.br(0, &[array_len], location));
     Ok(())
 }
@@ -384,7 +430,7 @@ pub fn build_get<'ctx, 'this>(
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let range_check =
@@ -470,20 +516,52 @@ pub fn build_get<'ctx, 'this>(
         let target_ptr = valid_block.append_op_result(ReallocBindingsMeta::realloc(
             context, target_ptr, elem_size, location,
         ))?;
-        assert_nonnull(
+        let valid_block = assert_nonnull(
             context,
+            helper,
+            metadata,
             valid_block,
             location,
             target_ptr,
             "realloc returned nullptr",
         )?;
 
-        // TODO: Support clone-only types (those that are not copy).
-        valid_block.memcpy(context, location, elem_ptr, target_ptr, elem_size);
+        let valid_block = build_copy_elem(
+            context,
+            registry,
+            valid_block,
+            location,
+            helper,
+            metadata,
+            &info.ty,
+            elem_ptr,
+            target_ptr,
+        )?;
+
+        // The snapshot is consumed, so its reference to the buffer has to be released (after the
+        // element has been copied out of it).
+        let valid_block = crate::types::array::build_release_data(
+            context,
+            helper,
+            valid_block,
+            location,
+            metadata,
+            ptr,
+        )?;
 
         valid_block.append_operation(helper.br(0, &[range_check, target_ptr], location));
     }
 
+    let ptr = error_block.extract_value(context, location, value, ptr_ty, 0)?;
+    let error_block = crate::types::array::build_release_data(
+        context,
+        helper,
+        error_block,
+        location,
+        metadata,
+        ptr,
+    )?;
+
     error_block.append_operation(helper.br(1, &[range_check], location));
     Ok(())
 }
@@ -497,9 +575,29 @@ pub fn build_pop_front<'ctx, 'this>(
     helper: &LibfuncHelper<'ctx, 'this>,
     metadata: &mut MetadataStorage,
     info: &SignatureAndTypeConcreteLibfunc,
+) -> Result<()> {
+    build_pop_front_impl(
+        context, registry, entry, location, helper, metadata, info, false,
+    )
+}
+
+/// Pop the first element of an array or a snapshot of an array.
+///
+/// When popping from an array whose buffer is shared with a snapshot, the element is cloned instead
+/// of moved since the snapshot may still use it.
+#[allow(clippy::too_many_arguments)]
+fn build_pop_front_impl<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    info: &SignatureAndTypeConcreteLibfunc,
+    is_snapshot: bool,
 ) -> Result<()> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let array_ty = registry.build_type(
@@ -545,13 +643,15 @@ pub fn build_pop_front<'ctx, 'this>(
         let ptr = valid_block.extract_value(context, location, value, ptr_ty, 0)?;
 
         let elem_size = valid_block.const_int(context, location, elem_layout.size(), 64)?;
+        let elem_stride =
+            valid_block.const_int(context, location, elem_layout.pad_to_align().size(), 64)?;
         let elem_offset = valid_block.append_op_result(arith::extui(
             array_start,
             IntegerType::new(context, 64).into(),
             location,
         ))?;
         let elem_offset =
-            valid_block.append_op_result(arith::muli(elem_offset, elem_size, location))?;
+            valid_block.append_op_result(arith::muli(elem_offset, elem_stride, location))?;
         let ptr = valid_block.append_op_result(llvm::get_element_ptr_dynamic(
             context,
             ptr,
@@ -567,15 +667,81 @@ pub fn build_pop_front<'ctx, 'this>(
         let target_ptr = valid_block.append_op_result(ReallocBindingsMeta::realloc(
             context, target_ptr, elem_size, location,
         ))?;
-        assert_nonnull(
+        let valid_block = assert_nonnull(
             context,
+            helper,
+            metadata,
             valid_block,
             location,
             target_ptr,
             "realloc returned nullptr",
         )?;
 
-        valid_block.memcpy(context, location, ptr, target_ptr, elem_size);
+        // Snapshots share their elements with the array they come from, therefore they always
+        // need cloning. Arrays only need it when their buffer is shared with a snapshot.
+        registry.build_type(context, helper, registry, metadata, &info.ty)?;
+        let has_clone = metadata
+            .get::<SnapshotClonesMeta>()
+            .is_some_and(|meta| meta.wrap_invoke(&info.ty).is_some());
+
+        let valid_block = match (is_snapshot, has_clone) {
+            (true, _) => build_copy_elem(
+                context,
+                registry,
+                valid_block,
+                location,
+                helper,
+                metadata,
+                &info.ty,
+                ptr,
+                target_ptr,
+            )?,
+            (false, true) => {
+                let array_ptr = valid_block.extract_value(context, location, value, ptr_ty, 0)?;
+                let (block, is_shared) = crate::types::array::build_is_shared(
+                    context,
+                    helper,
+                    valid_block,
+                    location,
+                    array_ptr,
+                )?;
+
+                let clone_block = helper.append_block(Block::new(&[]));
+                let move_block = helper.append_block(Block::new(&[]));
+                let finish_block = helper.append_block(Block::new(&[]));
+                block.append_operation(cf::cond_br(
+                    context,
+                    is_shared,
+                    clone_block,
+                    move_block,
+                    &[],
+                    &[],
+                    location,
+                ));
+
+                let clone_block = build_copy_elem(
+                    context,
+                    registry,
+                    clone_block,
+                    location,
+                    helper,
+                    metadata,
+                    &info.ty,
+                    ptr,
+                    target_ptr,
+                )?;
+                clone_block.append_operation(cf::br(finish_block, &[], location));
+
+                move_block.memcpy(context, location, ptr, target_ptr, elem_size);
+                move_block.append_operation(cf::br(finish_block, &[], location));
+
+                finish_block
+            }
+            (false, false) => {
+                valid_block.memcpy(context, location, ptr, target_ptr, elem_size);
+                valid_block
+            }
+        };
 
         let k1 = valid_block.const_int(context, location, 1, 32)?;
         let new_start = valid_block.append_op_result(arith::addi(array_start, k1, location))?;
@@ -612,7 +778,9 @@ pub fn build_snapshot_pop_front<'ctx, 'this>(
     metadata: &mut MetadataStorage,
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
-    build_pop_front(context, registry, entry, location, helper, metadata, info)
+    build_pop_front_impl(
+        context, registry, entry, location, helper, metadata, info, true,
+    )
 }
 
 /// Generate MLIR operations for the `array_snapshot_pop_back` libfunc.
@@ -626,7 +794,7 @@ pub fn build_snapshot_pop_back<'ctx, 'this>(
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let array_ty = registry.build_type(
@@ -674,13 +842,15 @@ pub fn build_snapshot_pop_back<'ctx, 'this>(
         let ptr = valid_block.extract_value(context, location, value, ptr_ty, 0)?;
 
         let elem_size = valid_block.const_int(context, location, elem_layout.size(), 64)?;
+        let elem_stride =
+            valid_block.const_int(context, location, elem_layout.pad_to_align().size(), 64)?;
         let elem_offset = valid_block.append_op_result(arith::extui(
             new_end,
             IntegerType::new(context, 64).into(),
             location,
         ))?;
         let elem_offset =
-            valid_block.append_op_result(arith::muli(elem_offset, elem_size, location))?;
+            valid_block.append_op_result(arith::muli(elem_offset, elem_stride, location))?;
         let ptr = valid_block.append_op_result(llvm::get_element_ptr_dynamic(
             context,
             ptr,
@@ -696,15 +866,28 @@ pub fn build_snapshot_pop_back<'ctx, 'this>(
         let target_ptr = valid_block.append_op_result(ReallocBindingsMeta::realloc(
             context, target_ptr, elem_size, location,
         ))?;
-        assert_nonnull(
+        let valid_block = assert_nonnull(
             context,
+            helper,
+            metadata,
             valid_block,
             location,
             target_ptr,
             "realloc returned nullptr",
         )?;
 
-        valid_block.memcpy(context, location, ptr, target_ptr, elem_size);
+        // Snapshots share their elements with the array they come from.
+        let valid_block = build_copy_elem(
+            context,
+            registry,
+            valid_block,
+            location,
+            helper,
+            metadata,
+            &info.ty,
+            ptr,
+            target_ptr,
+        )?;
 
         let value = valid_block.insert_value(context, location, value, new_end, 2)?;
 
@@ -725,10 +908,6 @@ pub fn build_slice<'ctx, 'this>(
     metadata: &mut MetadataStorage,
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
-    if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
-    }
-
     let range_check =
         super::increment_builtin_counter(context, entry, location, entry.argument(0)?.into())?;
 
@@ -742,9 +921,6 @@ pub fn build_slice<'ctx, 'this>(
 
     let len_ty = crate::ffi::get_struct_field_type_at(&array_ty, 1);
 
-    let elem_ty = registry.get_type(&info.ty)?;
-    let elem_layout = elem_ty.layout(registry)?;
-
     let slice_since = entry.argument(2)?.into();
     let slice_length = entry.argument(3)?.into();
 
@@ -787,64 +963,31 @@ pub fn build_slice<'ctx, 'this>(
     ));
 
     {
-        let elem_size =
-            slice_block.const_int(context, location, elem_layout.pad_to_align().size(), 64)?;
-        let dst_size = slice_block.append_op_result(arith::extui(
-            slice_length,
-            IntegerType::new(context, 64).into(),
-            location,
-        ))?;
-        let dst_size = slice_block.append_op_result(arith::muli(dst_size, elem_size, location))?;
-
-        let dst_ptr = slice_block.append_op_result(
-            ods::llvm::mlir_zero(context, pointer(context, 0), location).into(),
-        )?;
-        let dst_ptr = slice_block.append_op_result(ReallocBindingsMeta::realloc(
-            context, dst_ptr, dst_size, location,
-        ))?;
-
-        // TODO: Find out if we need to clone stuff using the snapshot clone meta.
-        let src_offset = {
-            let slice_since = slice_block.append_op_result(arith::extui(
-                slice_since,
-                IntegerType::new(context, 64).into(),
-                location,
-            ))?;
-
-            slice_block.append_op_result(arith::muli(slice_since, elem_size, location))?
-        };
-
-        let src_ptr = slice_block.extract_value(
+        // Snapshots never modify their buffer, therefore the slice can share it.
+        let value = slice_block.insert_value(
             context,
             location,
             entry.argument(1)?.into(),
-            pointer(context, 0),
-            0,
-        )?;
-        let src_ptr = slice_block.append_op_result(llvm::get_element_ptr_dynamic(
-            context,
-            src_ptr,
-            &[src_offset],
-            IntegerType::new(context, 8).into(),
-            llvm::r#type::pointer(context, 0),
-            location,
-        ))?;
-
-        slice_block.memcpy(context, location, src_ptr, dst_ptr, dst_size);
-
-        let k0 = slice_block.const_int_from_type(context, location, 0, len_ty)?;
-
-        let value = slice_block.append_op_result(llvm::undef(array_ty, location))?;
-        let value = slice_block.insert_values(
-            context,
-            location,
-            value,
-            &[dst_ptr, k0, slice_length, slice_length],
+            slice_since,
+            1,
         )?;
+        let value = slice_block.insert_value(context, location, value, slice_until, 2)?;
 
         slice_block.append_operation(helper.br(0, &[range_check, value], location));
     }
 
+    // The snapshot is consumed, so its reference to the buffer has to be released.
+    let ptr_ty = crate::ffi::get_struct_field_type_at(&array_ty, 0);
+    let ptr = error_block.extract_value(context, location, entry.argument(1)?.into(), ptr_ty, 0)?;
+    let error_block = crate::types::array::build_release_data(
+        context,
+        helper,
+        error_block,
+        location,
+        metadata,
+        ptr,
+    )?;
+
     error_block.append_operation(helper.br(1, &[range_check], location));
     Ok(())
 }
@@ -862,7 +1005,7 @@ pub fn build_span_from_tuple<'ctx, 'this>(
     // tuple to array span (t,t,t) -> &[t,t,t]
 
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let struct_type_info = registry.get_type(&info.ty)?;
@@ -890,7 +1033,8 @@ pub fn build_span_from_tuple<'ctx, 'this>(
 
     let array_len_value = entry.const_int_from_type(context, location, fields.len(), len_ty)?;
 
-    let array_container = entry.append_op_result(llvm::undef(array_ty, location))?;
+    let array_container =
+        entry.append_op_result(DeterminismMeta::build_undef(metadata, array_ty, location))?;
 
     let k0 = entry.const_int_from_type(context, location, 0, len_ty)?;
 
@@ -900,18 +1044,15 @@ pub fn build_span_from_tuple<'ctx, 'this>(
     let array_container =
         entry.insert_value(context, location, array_container, array_len_value, 3)?;
 
-    let ptr = entry
-        .append_op_result(ods::llvm::mlir_zero(context, pointer(context, 0), location).into())?;
-
     let field_size: Value = entry.const_int(context, location, field_stride, 64)?;
     let array_len_value_i64 =
         entry.append_op_result(arith::extui(array_len_value, field_size.r#type(), location))?;
     let total_size =
         entry.append_op_result(arith::muli(field_size, array_len_value_i64, location))?;
 
-    let ptr = entry.append_op_result(ReallocBindingsMeta::realloc(
-        context, ptr, total_size, location,
-    ))?;
+    let ptr = crate::types::array::build_alloc_data(
+        context, helper, entry, location, metadata, total_size,
+    )?;
 
     for (i, _) in fields.iter().enumerate() {
         let value: Value = entry.extract_value(context, location, container, field_ty, i)?;
@@ -919,7 +1060,10 @@ pub fn build_span_from_tuple<'ctx, 'this>(
         let target_ptr = entry.append_op_result(llvm::get_element_ptr(
             context,
             ptr,
-            DenseI32ArrayAttribute::new(context, &[i as i32]),
+            DenseI32ArrayAttribute::new(
+                context,
+                &[OverflowChecksMeta::narrow(metadata, i, "a field index")?],
+            ),
             field_ty,
             pointer(context, 0),
             location,
@@ -935,17 +1079,239 @@ pub fn build_span_from_tuple<'ctx, 'this>(
     Ok(())
 }
 
+/// Compute the capacity of a buffer that has to grow past `len` elements.
+fn build_grown_capacity<'ctx, 'this>(
+    context: &'ctx Context,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    len: Value<'ctx, 'this>,
+) -> Result<Value<'ctx, 'this>> {
+    let k1 = block.const_int(context, location, 1, 32)?;
+    let k8 = block.const_int(context, location, 8, 32)?;
+    let k1024 = block.const_int(context, location, 1024, 32)?;
+
+    // Array allocation growth formula:
+    //   new_len = max(8, old_len + min(1024, 2 * old_len));
+    let new_capacity = block.append_op_result(arith::shli(len, k1, location))?;
+    let new_capacity = block.append_op_result(arith::minui(new_capacity, k1024, location))?;
+    let new_capacity = block.append_op_result(arith::addi(new_capacity, len, location))?;
+    block.append_op_result(arith::maxui(new_capacity, k8, location))
+}
+
+/// Move the array's elements into a new buffer, so that it can be modified without affecting the
+/// snapshots it was sharing its buffer with.
+///
+/// The elements are cloned since the snapshots keep using them. The new buffer has room for at
+/// least one more element.
+#[allow(clippy::too_many_arguments)]
+fn build_copy_on_write<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    info: &SignatureAndTypeConcreteLibfunc,
+    value: Value<'ctx, 'this>,
+) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
+    let array_ty = registry.build_type(
+        context,
+        helper,
+        registry,
+        metadata,
+        &info.param_signatures()[0].ty,
+    )?;
+
+    let ptr_ty = crate::ffi::get_struct_field_type_at(&array_ty, 0);
+    let len_ty = crate::ffi::get_struct_field_type_at(&array_ty, 1);
+
+    // Building the element type registers its clone implementation, if any.
+    let (elem_ty, elem_layout) =
+        registry.build_type_with_layout(context, helper, registry, metadata, &info.ty)?;
+    let elem_clone = metadata
+        .get::<SnapshotClonesMeta>()
+        .and_then(|meta| meta.wrap_invoke(&info.ty));
+
+    let elem_stride = block.const_int(context, location, elem_layout.pad_to_align().size(), 64)?;
+
+    let src_ptr = block.extract_value(context, location, value, ptr_ty, 0)?;
+    let array_start = block.extract_value(context, location, value, len_ty, 1)?;
+    let array_end = block.extract_value(context, location, value, len_ty, 2)?;
+    let array_len = block.append_op_result(arith::subi(array_end, array_start, location))?;
+
+    let new_capacity = build_grown_capacity(context, block, location, array_len)?;
+    let dst_size = block.append_op_result(arith::extui(
+        new_capacity,
+        IntegerType::new(context, 64).into(),
+        location,
+    ))?;
+    let dst_size = block.append_op_result(arith::muli(dst_size, elem_stride, location))?;
+    let dst_ptr = crate::types::array::build_alloc_data(
+        context, helper, block, location, metadata, dst_size,
+    )?;
+
+    let src_offset = block.append_op_result(arith::extui(
+        array_start,
+        IntegerType::new(context, 64).into(),
+        location,
+    ))?;
+    let src_offset = block.append_op_result(arith::muli(src_offset, elem_stride, location))?;
+    let elems_ptr = block.append_op_result(llvm::get_element_ptr_dynamic(
+        context,
+        src_ptr,
+        &[src_offset],
+        IntegerType::new(context, 8).into(),
+        pointer(context, 0),
+        location,
+    ))?;
+
+    let block = match elem_clone {
+        Some(elem_clone) => {
+            let loop_block = helper.append_block(Block::new(&[(len_ty, location)]));
+            let body_block = helper.append_block(Block::new(&[]));
+            let finish_block = helper.append_block(Block::new(&[]));
+
+            let k0 = block.const_int_from_type(context, location, 0, len_ty)?;
+            block.append_operation(cf::br(loop_block, &[k0], location));
+
+            let idx = loop_block.argument(0)?.into();
+            let has_next = loop_block.append_op_result(arith::cmpi(
+                context,
+                CmpiPredicate::Ult,
+                idx,
+                array_len,
+                location,
+            ))?;
+            loop_block.append_operation(cf::cond_br(
+                context,
+                has_next,
+                body_block,
+                finish_block,
+                &[],
+                &[],
+                location,
+            ));
+
+            let offset = body_block.append_op_result(arith::extui(
+                idx,
+                IntegerType::new(context, 64).into(),
+                location,
+            ))?;
+            let offset = body_block.append_op_result(arith::muli(offset, elem_stride, location))?;
+            let src_elem_ptr = body_block.append_op_result(llvm::get_element_ptr_dynamic(
+                context,
+                elems_ptr,
+                &[offset],
+                IntegerType::new(context, 8).into(),
+                pointer(context, 0),
+                location,
+            ))?;
+            let dst_elem_ptr = body_block.append_op_result(llvm::get_element_ptr_dynamic(
+                context,
+                dst_ptr,
+                &[offset],
+                IntegerType::new(context, 8).into(),
+                pointer(context, 0),
+                location,
+            ))?;
+
+            let elem = body_block.load(context, location, src_elem_ptr, elem_ty)?;
+            let (body_block, elem) = elem_clone(
+                context, registry, body_block, location, helper, metadata, elem,
+            )?;
+            body_block.store(context, location, dst_elem_ptr, elem)?;
+
+            let k1 = body_block.const_int_from_type(context, location, 1, len_ty)?;
+            let idx = body_block.append_op_result(arith::addi(idx, k1, location))?;
+            body_block.append_operation(cf::br(loop_block, &[idx], location));
+
+            finish_block
+        }
+        None => {
+            let copy_size = block.append_op_result(arith::extui(
+                array_len,
+                IntegerType::new(context, 64).into(),
+                location,
+            ))?;
+            let copy_size =
+                block.append_op_result(arith::muli(copy_size, elem_stride, location))?;
+            block.memcpy(context, location, elems_ptr, dst_ptr, copy_size);
+
+            block
+        }
+    };
+
+    // The old buffer is still referenced by the snapshots, therefore this won't free it.
+    let block = crate::types::array::build_release_data(
+        context, helper, block, location, metadata, src_ptr,
+    )?;
+
+    let k0 = block.const_int_from_type(context, location, 0, len_ty)?;
+    let value =
+        block.append_op_result(DeterminismMeta::build_undef(metadata, array_ty, location))?;
+    let value = block.insert_values(
+        context,
+        location,
+        value,
+        &[dst_ptr, k0, array_len, new_capacity],
+    )?;
+
+    Ok((block, value))
+}
+
+/// Copy an element out of a buffer that is still used elsewhere, cloning it if its type has a
+/// clone implementation.
+///
+/// Returns the block where the code generation should continue.
+#[allow(clippy::too_many_arguments)]
+fn build_copy_elem<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    elem_id: &ConcreteTypeId,
+    src_ptr: Value<'ctx, 'this>,
+    dst_ptr: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    let elem_ty = registry.build_type(context, helper, registry, metadata, elem_id)?;
+    let elem_clone = metadata
+        .get::<SnapshotClonesMeta>()
+        .and_then(|meta| meta.wrap_invoke(elem_id));
+
+    match elem_clone {
+        Some(elem_clone) => {
+            let elem = block.load(context, location, src_ptr, elem_ty)?;
+            let (block, elem) =
+                elem_clone(context, registry, block, location, helper, metadata, elem)?;
+            block.store(context, location, dst_ptr, elem)?;
+
+            Ok(block)
+        }
+        None => {
+            let elem_size = registry.get_type(elem_id)?.layout(registry)?.size();
+            let elem_size = block.const_int(context, location, elem_size, 64)?;
+            block.memcpy(context, location, src_ptr, dst_ptr, elem_size);
+
+            Ok(block)
+        }
+    }
+}
+
 fn assert_nonnull<'ctx, 'this>(
     context: &'ctx Context,
-    entry: &'this Block<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    block: &'this Block<'ctx>,
     location: Location<'ctx>,
     ptr: Value<'ctx, 'this>,
     msg: &str,
-) -> Result<()> {
+) -> Result<&'this Block<'ctx>> {
     let null_ptr =
-        entry.append_op_result(ods::llvm::mlir_zero(context, ptr.r#type(), location).into())?;
+        block.append_op_result(ods::llvm::mlir_zero(context, ptr.r#type(), location).into())?;
 
-    let ptr_is_not_null = entry.append_op_result(
+    let ptr_is_not_null = block.append_op_result(
         ods::llvm::icmp(
             context,
             IntegerType::new(context, 1).into(),
@@ -957,13 +1323,13 @@ fn assert_nonnull<'ctx, 'this>(
         .into(),
     )?;
 
-    entry.append_operation(cf::assert(context, ptr_is_not_null, msg, location));
-    Ok(())
+    PanicMeta::get(metadata).build_assert(context, helper, block, location, ptr_is_not_null, msg)
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
+        executor::allocation_stats,
         utils::test::{jit_enum, jit_panic, jit_struct, load_cairo, run_program},
         values::JitValue,
     };
@@ -1214,6 +1580,36 @@ mod test {
         );
     }
 
+    #[test]
+    fn run_pop_padded() {
+        // The size of `(u128, u8)` isn't a multiple of its alignment, so the elements are padded.
+        let program = load_cairo!(
+            use array::ArrayTrait;
+
+            fn run_test() -> (Option<(u128, u8)>, Option<@(u128, u8)>, Option<(u128, u8)>) {
+                let mut numbers = ArrayTrait::new();
+                numbers.append((1_u128, 2_u8));
+                numbers.append((3_u128, 4_u8));
+                numbers.append((5_u128, 6_u8));
+                numbers.append((7_u128, 8_u8));
+                let first = numbers.pop_front();
+                let mut span = numbers.span();
+                let last = span.pop_back();
+                (first, last, numbers.pop_front())
+            }
+        );
+        let result = run_program(&program, "run_test", &[]).return_value;
+
+        assert_eq!(
+            result,
+            jit_struct!(
+                jit_enum!(0, jit_struct!(1u128.into(), 2u8.into())),
+                jit_enum!(0, jit_struct!(7u128.into(), 8u8.into())),
+                jit_enum!(0, jit_struct!(3u128.into(), 4u8.into())),
+            ),
+        );
+    }
+
     #[test]
     fn run_slice() {
         let program = load_cairo!(
@@ -1278,6 +1674,109 @@ mod test {
         );
     }
 
+    #[test]
+    fn run_slice_loop() {
+        // Slicing doesn't copy the buffer, so shrinking a big span one element at a time from both
+        // ends is linear.
+        #[rustfmt::skip]
+        let program = load_cairo!(
+            use array::ArrayTrait;
+            use array::SpanTrait;
+
+            fn run_test() -> (u32, u32, u32) {
+                let mut data: Array<u32> = ArrayTrait::new();
+                let mut i = 0_u32;
+                loop {
+                    if i == 10000 {
+                        break;
+                    }
+                    data.append(i);
+                    i += 1;
+                };
+
+                let mut span = data.span();
+                let mut sum = 0_u32;
+                loop {
+                    if span.len() < 2 {
+                        break;
+                    }
+                    sum += *span[0] + *span[span.len() - 1];
+                    span = span.slice(1, span.len() - 2);
+                };
+
+                data.append(10000);
+                (sum, span.len(), data.len())
+            }
+        );
+        let result = run_program(&program, "run_test", &[]).return_value;
+
+        assert_eq!(
+            result,
+            jit_enum!(
+                0,
+                jit_struct!(jit_struct!(
+                    49995000u32.into(),
+                    0u32.into(),
+                    10001u32.into()
+                ))
+            ),
+        );
+    }
+
+    #[test]
+    fn run_slice_nested_loop() {
+        // Every slice of the outer loop is sliced again by the inner one, and all of them share
+        // the buffer of the original array.
+        #[rustfmt::skip]
+        let program = load_cairo!(
+            use array::ArrayTrait;
+            use array::SpanTrait;
+
+            fn run_test() -> felt252 {
+                let mut data: Array<felt252> = ArrayTrait::new();
+                let mut i = 0;
+                loop {
+                    if i == 1000 {
+                        break;
+                    }
+                    data.append(i);
+                    i += 1;
+                };
+
+                let span = data.span();
+                let mut total = 0;
+                let mut start = 0_u32;
+                loop {
+                    if start == 100 {
+                        break;
+                    }
+                    let window = span.slice(start, 900);
+                    let mut offset = 0_u32;
+                    loop {
+                        if offset == 900 {
+                            break;
+                        }
+                        total += *window.slice(offset, 100)[0];
+                        offset += 100;
+                    };
+                    start += 1;
+                };
+
+                total
+            }
+        );
+        let result = run_program(&program, "run_test", &[]).return_value;
+
+        // Every window sums `start * 9 + 3600`, for `start` from 0 to 99.
+        assert_eq!(
+            result,
+            jit_enum!(
+                0,
+                jit_struct!(JitValue::Felt252((9 * 4950 + 360000).into()))
+            )
+        );
+    }
+
     #[test]
     fn run_span_from_tuple() {
         let program = load_cairo!(
@@ -1663,4 +2162,84 @@ mod test {
             JitValue::Array(vec![1u64.into(), 2u64.into()]),
         );
     }
+
+    #[test]
+    fn array_snapshot_copy_on_write() {
+        let program = load_cairo!(
+            fn run_test() -> (Span<u32>, Array<u32>) {
+                let mut data = array![1, 2];
+                let span = data.span();
+                data.append(3);
+                (span, data)
+            }
+        );
+
+        assert_eq!(
+            run_program(&program, "run_test", &[]).return_value,
+            jit_struct!(jit_struct!([1u32, 2u32].into()), [1u32, 2u32, 3u32].into()),
+        );
+    }
+
+    #[test]
+    fn array_snapshot_copy_on_write_nested() {
+        // Elements popped from (or copied out of) a shared buffer must be cloned, otherwise
+        // modifying them would also modify the snapshot.
+        let program = load_cairo!(
+            fn run_test() -> (Span<Array<u32>>, Array<Array<u32>>) {
+                let mut data = array![array![1], array![2]];
+                let span = data.span();
+                let mut first = data.pop_front().unwrap();
+                first.append(10);
+                data.append(first);
+                (span, data)
+            }
+        );
+
+        assert_eq!(
+            run_program(&program, "run_test", &[]).return_value,
+            jit_struct!(
+                jit_struct!(JitValue::Array(vec![[1u32].into(), [2u32].into()])),
+                JitValue::Array(vec![[2u32].into(), [1u32, 10u32].into()]),
+            ),
+        );
+    }
+
+    #[test]
+    fn array_snapshot_append_loop() {
+        // Consuming a snapshot releases its reference to the buffer, so appending after it's gone
+        // neither copies the buffer (which would make the loop quadratic) nor leaks it.
+        #[rustfmt::skip]
+        let program = load_cairo!(
+            use array::ArrayTrait;
+
+            fn count(data: @Array<u32>) -> usize {
+                data.len()
+            }
+
+            fn run_test() -> usize {
+                let mut data: Array<u32> = ArrayTrait::new();
+                let mut total = 0_usize;
+                let mut i = 0_u32;
+                loop {
+                    if i == 1000 {
+                        break;
+                    }
+                    data.append(i);
+                    total += count(@data);
+                    i += 1;
+                };
+                total
+            }
+        );
+
+        let before = allocation_stats();
+        let result = run_program(&program, "run_test", &[]).return_value;
+        let after = allocation_stats();
+
+        assert_eq!(result, jit_enum!(0, jit_struct!(500500u32.into())));
+        // Growing the buffer reallocates it, which doesn't count as a new allocation.
+        assert!(after.allocations - before.allocations < 10);
+        assert_eq!(after.reclaimed, before.reclaimed);
+        assert_eq!(after.live(), 0);
+    }
 }
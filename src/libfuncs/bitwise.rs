@@ -1,39 +1,78 @@
 //! # Bitwise libfuncs
 
 use super::LibfuncHelper;
-use crate::{block_ext::BlockExt, error::Result, metadata::MetadataStorage};
+use crate::{
+    block_ext::BlockExt,
+    error::Result,
+    metadata::{int_range::IntRangeMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
+};
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
         lib_func::SignatureOnlyConcreteLibfunc,
+        utils::Range,
+        ConcreteLibfunc,
     },
     program_registry::ProgramRegistry,
 };
 use melior::{
     dialect::arith,
-    ir::{Block, Location},
+    ir::{r#type::IntegerType, Block, Location},
     Context,
 };
 
 /// Generate MLIR operations for the `bitwise` libfunc.
+///
+/// Programs which don't use the `uN_bitwise` libfuncs implement the bitwise operators of `u8` and
+/// `u16` by upcasting their operands into `u128`. When the [integer range analysis](IntRangeMeta)
+/// proves that both operands fit in 8 or 16 bits, the operations are performed on that width
+/// instead. The builtin is still counted, since the Cairo VM would use it anyway.
 pub fn build<'ctx, 'this>(
     context: &'ctx Context,
-    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
-    _metadata: &mut MetadataStorage,
-    _info: &SignatureOnlyConcreteLibfunc,
+    metadata: &mut MetadataStorage,
+    info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
     let bitwise =
         super::increment_builtin_counter(context, entry, location, entry.argument(0)?.into())?;
 
-    let lhs = entry.argument(1)?.into();
-    let rhs = entry.argument(2)?.into();
+    let mut lhs = entry.argument(1)?.into();
+    let mut rhs = entry.argument(2)?.into();
+
+    let value_ty = registry.build_type(
+        context,
+        helper,
+        registry,
+        metadata,
+        &info.param_signatures()[1].ty,
+    )?;
+    let value_width = IntegerType::try_from(value_ty)?.width();
+    let narrow_width = [8, 16].into_iter().find(|&width| {
+        let range = Range::half_open(0, 1 << width);
+        width < value_width
+            && metadata
+                .get::<IntRangeMeta>()
+                .is_some_and(|x| x.is_arg_in_range(1, &range) && x.is_arg_in_range(2, &range))
+    });
+    if let Some(width) = narrow_width {
+        let narrow_ty = IntegerType::new(context, width).into();
+        lhs = entry.append_op_result(arith::trunci(lhs, narrow_ty, location))?;
+        rhs = entry.append_op_result(arith::trunci(rhs, narrow_ty, location))?;
+    }
 
-    let logical_and = entry.append_op_result(arith::andi(lhs, rhs, location))?;
-    let logical_xor = entry.append_op_result(arith::xori(lhs, rhs, location))?;
-    let logical_or = entry.append_op_result(arith::ori(lhs, rhs, location))?;
+    let mut logical_and = entry.append_op_result(arith::andi(lhs, rhs, location))?;
+    let mut logical_xor = entry.append_op_result(arith::xori(lhs, rhs, location))?;
+    let mut logical_or = entry.append_op_result(arith::ori(lhs, rhs, location))?;
+
+    if narrow_width.is_some() {
+        logical_and = entry.append_op_result(arith::extui(logical_and, value_ty, location))?;
+        logical_xor = entry.append_op_result(arith::extui(logical_xor, value_ty, location))?;
+        logical_or = entry.append_op_result(arith::extui(logical_or, value_ty, location))?;
+    }
 
     entry.append_operation(helper.br(
         0,
@@ -57,6 +96,11 @@ mod test {
                 bitwise(lhs, rhs)
             }
         };
+        static ref BITWISE_OPERATORS: (String, Program) = load_cairo! {
+            fn run_test(a: u8, b: u16, c: u32, d: u64) -> (u8, u16, u32, u64, u8, u16, u32, u64) {
+                (a & 0x0F, b | 0x0F0F, c ^ 0xFFFF0000, d & 0x00FF00FF00FF00FF, a | a, b ^ b, c & c, d | 1)
+            }
+        };
     }
 
     #[test]
@@ -108,4 +152,40 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn bitwise_builtin_usage() {
+        let result = run_program(&BITWISE, "run_test", &[1u128.into(), 2u128.into()]);
+        assert_eq!(result.builtin_stats.bitwise, 1);
+    }
+
+    #[test]
+    fn bitwise_operators() {
+        let result = run_program(
+            &BITWISE_OPERATORS,
+            "run_test",
+            &[
+                0xA5u8.into(),
+                0x1234u16.into(),
+                0x12345678u32.into(),
+                0x0123456789ABCDEFu64.into(),
+            ],
+        );
+
+        assert_eq!(
+            result.return_value,
+            jit_struct!(
+                0x05u8.into(),
+                0x1F3Fu16.into(),
+                0xEDCB5678u32.into(),
+                0x0023006700AB00EFu64.into(),
+                0xA5u8.into(),
+                0u16.into(),
+                0x12345678u32.into(),
+                0x0123456789ABCDEFu64.into()
+            )
+        );
+        // Narrower integers use their own `uN_bitwise` libfuncs, which share the same builtin.
+        assert_eq!(result.builtin_stats.bitwise, 8);
+    }
 }
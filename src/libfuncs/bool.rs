@@ -2,7 +2,10 @@
 
 use super::LibfuncHelper;
 use crate::{
-    block_ext::BlockExt, error::Result, metadata::MetadataStorage, types::TypeBuilder,
+    block_ext::BlockExt,
+    error::Result,
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    types::TypeBuilder,
     utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
@@ -15,7 +18,7 @@ use cairo_lang_sierra::{
     program_registry::ProgramRegistry,
 };
 use melior::{
-    dialect::{arith, llvm},
+    dialect::arith,
     ir::{r#type::IntegerType, Block, Location},
     Context,
 };
@@ -111,16 +114,14 @@ fn build_bool_binary<'ctx, 'this>(
         BoolOp::Or => entry.append_op_result(arith::ori(lhs_tag, rhs_tag, location))?,
     };
 
-    let res = entry.append_op_result(llvm::undef(
-        enum_ty.build(
-            context,
-            helper,
-            registry,
-            metadata,
-            &info.param_signatures()[0].ty,
-        )?,
-        location,
-    ))?;
+    let res_ty = enum_ty.build(
+        context,
+        helper,
+        registry,
+        metadata,
+        &info.param_signatures()[0].ty,
+    )?;
+    let res = entry.append_op_result(DeterminismMeta::build_undef(metadata, res_ty, location))?;
 
     let res = entry.insert_value(context, location, res, new_tag_value, 0)?;
 
@@ -154,16 +155,14 @@ pub fn build_bool_not<'ctx, 'this>(
 
     let new_tag_value = entry.append_op_result(arith::xori(tag_value, const_1, location))?;
 
-    let res = entry.append_op_result(llvm::undef(
-        enum_ty.build(
-            context,
-            helper,
-            registry,
-            metadata,
-            &info.param_signatures()[0].ty,
-        )?,
-        location,
-    ))?;
+    let res_ty = enum_ty.build(
+        context,
+        helper,
+        registry,
+        metadata,
+        &info.param_signatures()[0].ty,
+    )?;
+    let res = entry.append_op_result(DeterminismMeta::build_undef(metadata, res_ty, location))?;
     let res = entry.insert_value(context, location, res, new_tag_value, 0)?;
 
     entry.append_operation(helper.br(0, &[res], location));
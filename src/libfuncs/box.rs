@@ -5,17 +5,16 @@
 use super::LibfuncHelper;
 use crate::{
     error::Result,
-    metadata::{realloc_bindings::ReallocBindingsMeta, MetadataStorage},
+    metadata::{
+        determinism::DeterminismMeta, realloc_bindings::ReallocBindingsMeta, MetadataStorage,
+    },
     types::TypeBuilder,
 };
 use cairo_lang_sierra::{
     extensions::{
         boxing::BoxConcreteLibfunc,
         core::{CoreLibfunc, CoreType},
-        lib_func::{
-            BranchSignature, LibfuncSignature, SignatureAndTypeConcreteLibfunc,
-            SignatureOnlyConcreteLibfunc,
-        },
+        lib_func::SignatureAndTypeConcreteLibfunc,
     },
     program_registry::ProgramRegistry,
 };
@@ -63,7 +62,7 @@ pub fn build_into_box<'ctx, 'this>(
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let inner_type = registry.get_type(&info.ty)?;
@@ -93,6 +92,14 @@ pub fn build_into_box<'ctx, 'this>(
         .result(0)?
         .into();
 
+    DeterminismMeta::build_clear(
+        metadata,
+        context,
+        entry,
+        location,
+        ptr,
+        inner_layout.pad_to_align().size(),
+    )?;
     entry.append_operation(llvm::store(
         context,
         entry.argument(0)?.into(),
@@ -147,44 +154,27 @@ pub fn build_unbox<'ctx, 'this>(
     Ok(())
 }
 
+/// Generate MLIR operations for the `box_forward_snapshot` libfunc.
+///
+/// A snapshot of a box has the same layout as a box of a snapshot, therefore the value is just
+/// moved.
 fn build_forward_snapshot<'ctx, 'this>(
-    context: &'ctx Context,
-    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    _context: &'ctx Context,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
-    metadata: &mut MetadataStorage,
-    info: &SignatureAndTypeConcreteLibfunc,
+    _metadata: &mut MetadataStorage,
+    _info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
-    super::snapshot_take::build(
-        context,
-        registry,
-        entry,
-        location,
-        helper,
-        metadata,
-        &SignatureOnlyConcreteLibfunc {
-            signature: LibfuncSignature {
-                param_signatures: info.signature.param_signatures.clone(),
-                branch_signatures: info
-                    .signature
-                    .branch_signatures
-                    .iter()
-                    .map(|x| BranchSignature {
-                        vars: x.vars.clone(),
-                        ap_change: x.ap_change.clone(),
-                    })
-                    .collect(),
-                fallthrough: info.signature.fallthrough,
-            },
-        },
-    )
+    entry.append_operation(helper.br(0, &[entry.argument(0)?.into()], location));
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        utils::test::{load_cairo, run_program_assert_output},
+        utils::test::{jit_enum, jit_struct, load_cairo, run_program_assert_output},
         values::JitValue,
     };
 
@@ -220,6 +210,39 @@ mod test {
         run_program_assert_output(&program, "run_test", &[], JitValue::Uint32(2));
     }
 
+    #[test]
+    fn run_box_option_nested() {
+        let program = load_cairo! {
+            use core::box::BoxTrait;
+
+            fn sum(value: Option<Box<(u32, Option<Box<u32>>)>>) -> u32 {
+                match value {
+                    Option::Some(x) => {
+                        let (head, tail) = x.unbox();
+                        match tail {
+                            Option::Some(tail) => head + tail.unbox(),
+                            Option::None => head,
+                        }
+                    },
+                    Option::None => 0,
+                }
+            }
+
+            fn run_test() -> u32 {
+                let value = Option::Some(BoxTrait::new((1, Option::Some(BoxTrait::new(2)))));
+                let _dropped = Option::Some(BoxTrait::new(array![3_u32, 4_u32]));
+                sum(value) + sum(Option::None)
+            }
+        };
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            jit_enum!(0, jit_struct!(3u32.into())),
+        );
+    }
+
     #[test]
     fn box_unbox_stack_allocated_enum_single() {
         let program = load_cairo! {
@@ -103,7 +103,7 @@ pub fn build_to_felt252<'ctx, 'this>(
     Ok(())
 }
 
-/// Generate MLIR operations for the `u8_from_felt252` libfunc.
+/// Generate MLIR operations for the `bytes31_try_from_felt252` libfunc.
 pub fn build_from_felt252<'ctx, 'this>(
     context: &'ctx Context,
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
@@ -172,8 +172,9 @@ pub fn build_from_felt252<'ctx, 'this>(
 
 #[cfg(test)]
 mod test {
-    use crate::utils::test::{
-        jit_enum, jit_panic, jit_struct, load_cairo, run_program_assert_output,
+    use crate::{
+        utils::test::{jit_enum, jit_panic, jit_struct, load_cairo, run_program_assert_output},
+        values::JitValue,
     };
     use cairo_lang_sierra::program::Program;
     use lazy_static::lazy_static;
@@ -207,4 +208,45 @@ mod test {
             jit_panic!(Felt::from_bytes_be_slice(b"Option::unwrap failed.")),
         );
     }
+
+    #[test]
+    fn bytes31_try_from_felt252_bounds() {
+        let max = Felt::from_bytes_be_slice(&[0xFF; 31]);
+
+        run_program_assert_output(
+            &BYTES31_ROUNDTRIP,
+            "run_test",
+            &[max.into()],
+            jit_enum!(0, jit_struct!(max.into())),
+        );
+
+        run_program_assert_output(
+            &BYTES31_ROUNDTRIP,
+            "run_test",
+            &[(max + Felt::ONE).into()],
+            jit_panic!(Felt::from_bytes_be_slice(b"Option::unwrap failed.")),
+        );
+    }
+
+    #[test]
+    fn bytes31_argument() {
+        let program = load_cairo! {
+            use core::bytes_31::bytes31_to_felt252;
+
+            fn run_test(value: bytes31) -> felt252 {
+                bytes31_to_felt252(value)
+            }
+        };
+
+        let mut value = [0; 31];
+        value[0] = 0x34;
+        value[1] = 0x12;
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[JitValue::Bytes31(value)],
+            Felt::from(0x1234).into(),
+        );
+    }
 }
@@ -1,12 +1,12 @@
 //! # Casting libfuncs
 
-use std::ops::Shr;
+use std::{cmp::Ordering, ops::Shr};
 
 use super::LibfuncHelper;
 use crate::{
     block_ext::BlockExt,
     error::{Error, Result, SierraAssertError},
-    metadata::{prime_modulo::PrimeModuloMeta, MetadataStorage},
+    metadata::{int_range::IntRangeMeta, prime_modulo::PrimeModuloMeta, MetadataStorage},
     types::TypeBuilder,
 };
 use cairo_lang_sierra::{
@@ -88,22 +88,35 @@ pub fn build_downcast<'ctx, 'this>(
         .is_integer_signed()
         .ok_or_else(|| Error::SierraAssert(SierraAssertError::Cast))?;
     let any_is_signed = src_is_signed | dst_is_signed;
-    let src_is_felt = matches!(
-        src_type,
-        CoreTypeConcrete::Felt252(_) | CoreTypeConcrete::BoundedInt(_)
-    );
-    let dst_is_felt = matches!(
-        dst_type,
-        CoreTypeConcrete::Felt252(_) | CoreTypeConcrete::BoundedInt(_)
-    );
+    let src_is_felt = matches!(src_type, CoreTypeConcrete::Felt252(_));
+    let dst_is_felt = matches!(dst_type, CoreTypeConcrete::Felt252(_));
     let src_value: melior::ir::Value = entry.argument(1)?.into();
 
     let mut block = entry;
 
     let (is_in_range, result) = if info.from_ty == info.to_ty {
-        // can't cast to the same type
-        let k0 = block.const_int(context, location, 0, 1)?;
-        (k0, src_value)
+        // Casting to the same type always fits.
+        let k1 = block.const_int(context, location, 1, 1)?;
+        (k1, src_value)
+    } else if !any_is_signed
+        && !src_is_felt
+        && !dst_is_felt
+        && metadata
+            .get::<IntRangeMeta>()
+            .is_some_and(|x| x.is_arg_in_range(1, &info.to_range))
+    {
+        // The value is known to fit (ex. the result of a bitwise operation between upcasted
+        // values), so only the conversion is needed. The range check is still counted, since the
+        // Cairo VM would use it anyway.
+        let k1 = block.const_int(context, location, 1, 1)?;
+        let result = match src_width.cmp(&dst_width) {
+            Ordering::Greater => {
+                block.append_op_result(arith::trunci(src_value, dst_ty, location))?
+            }
+            Ordering::Less => block.append_op_result(arith::extui(src_value, dst_ty, location))?,
+            Ordering::Equal => src_value,
+        };
+        (k1, result)
     } else {
         // make unsigned felt into signed felt
         // felt > half prime = negative
@@ -308,7 +321,7 @@ pub fn build_upcast<'ctx, 'this>(
 
     let result = if src_width == dst_width {
         block.argument(0)?.into()
-    } else if is_signed || is_felt {
+    } else if is_signed {
         if is_felt {
             let result = block.append_op_result(arith::extsi(
                 block.argument(0)?.into(),
@@ -398,8 +411,9 @@ mod test {
         utils::test::{jit_enum, jit_struct, load_cairo, run_program_assert_output},
         values::JitValue,
     };
-    use cairo_lang_sierra::program::Program;
+    use cairo_lang_sierra::{program::Program, ProgramParser};
     use lazy_static::lazy_static;
+    use starknet_types_core::felt::Felt;
 
     lazy_static! {
         static ref DOWNCAST: (String, Program) = load_cairo! {
@@ -446,6 +460,97 @@ mod test {
                 )
             }
         };
+        static ref DOWNCAST_SIGNED: (String, Program) = load_cairo! {
+            use core::integer::downcast;
+
+            fn run_test(
+                v16: i16, v128: i128, felt: felt252
+            ) -> (Option<i8>, Option<i8>, Option<u8>, Option<i8>, Option<u128>) {
+                (downcast(v16), downcast(v128), downcast(v16), downcast(felt), downcast(v128))
+            }
+        };
+        static ref DOWNCAST_SAME: (String, Program) = load_cairo! {
+            use core::integer::downcast;
+
+            fn run_test(value: u64) -> Option<u64> {
+                downcast(value)
+            }
+        };
+    }
+
+    #[test]
+    fn downcast_in_range() {
+        run_program_assert_output(
+            &DOWNCAST,
+            "run_test",
+            &[
+                1u8.into(),
+                2u16.into(),
+                3u32.into(),
+                4u64.into(),
+                5u128.into(),
+            ],
+            jit_struct!(
+                jit_struct!(
+                    jit_enum!(0, 5u8.into()),
+                    jit_enum!(0, 4u8.into()),
+                    jit_enum!(0, 3u8.into()),
+                    jit_enum!(0, 2u8.into()),
+                    jit_enum!(0, 1u8.into()),
+                ),
+                jit_struct!(
+                    jit_enum!(0, 5u16.into()),
+                    jit_enum!(0, 4u16.into()),
+                    jit_enum!(0, 3u16.into()),
+                    jit_enum!(0, 2u16.into()),
+                ),
+                jit_struct!(
+                    jit_enum!(0, 5u32.into()),
+                    jit_enum!(0, 4u32.into()),
+                    jit_enum!(0, 3u32.into()),
+                ),
+                jit_struct!(jit_enum!(0, 5u64.into()), jit_enum!(0, 4u64.into())),
+                jit_struct!(jit_enum!(0, 5u128.into())),
+            ),
+        );
+    }
+
+    #[test]
+    fn downcast_signed() {
+        run_program_assert_output(
+            &DOWNCAST_SIGNED,
+            "run_test",
+            &[(-128i16).into(), (-129i128).into(), Felt::from(-1).into()],
+            jit_struct!(
+                jit_enum!(0, (-128i8).into()),
+                jit_enum!(1, jit_struct!()),
+                jit_enum!(1, jit_struct!()),
+                jit_enum!(0, (-1i8).into()),
+                jit_enum!(1, jit_struct!()),
+            ),
+        );
+        run_program_assert_output(
+            &DOWNCAST_SIGNED,
+            "run_test",
+            &[127i16.into(), 127i128.into(), Felt::from(128).into()],
+            jit_struct!(
+                jit_enum!(0, 127i8.into()),
+                jit_enum!(0, 127i8.into()),
+                jit_enum!(0, 127u8.into()),
+                jit_enum!(1, jit_struct!()),
+                jit_enum!(0, 127u128.into()),
+            ),
+        );
+    }
+
+    #[test]
+    fn downcast_same_type() {
+        run_program_assert_output(
+            &DOWNCAST_SAME,
+            "run_test",
+            &[u64::MAX.into()],
+            jit_enum!(0, u64::MAX.into()),
+        );
     }
 
     #[test]
@@ -690,4 +795,47 @@ mod test {
             ),
         );
     }
+
+    #[test]
+    fn upcast_unsigned_bounded_int() {
+        // The values above 127 have their highest bit set, which must not be sign-extended.
+        let program = ProgramParser::new()
+            .parse(
+                "type u8 = u8;
+                type BoundedInt = BoundedInt<0, 255>;
+                type felt252 = felt252;
+                type u16 = u16;
+
+                libfunc upcast_bounded_int = upcast<u8, BoundedInt>;
+                libfunc upcast_felt252 = upcast<BoundedInt, felt252>;
+                libfunc upcast_u16 = upcast<BoundedInt, u16>;
+
+                upcast_bounded_int([0]) -> ([1]);
+                upcast_felt252([1]) -> ([2]);
+                return([2]);
+                upcast_bounded_int([0]) -> ([1]);
+                upcast_u16([1]) -> ([2]);
+                return([2]);
+
+                program::program::to_felt252@0([0]: u8) -> (felt252);
+                program::program::to_u16@3([0]: u8) -> (u16);",
+            )
+            .unwrap();
+        let program = ("program".to_string(), program);
+
+        for value in [0, 127, 128, 200, 255] {
+            run_program_assert_output(
+                &program,
+                "to_felt252",
+                &[JitValue::Uint8(value)],
+                JitValue::Felt252(value.into()),
+            );
+            run_program_assert_output(
+                &program,
+                "to_u16",
+                &[JitValue::Uint8(value)],
+                JitValue::Uint16(value.into()),
+            );
+        }
+    }
 }
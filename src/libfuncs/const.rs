@@ -1,4 +1,7 @@
 //! # Const libfuncs
+//!
+//! Small constants are rebuilt every time they're used, while big aggregates are stored in the
+//! [constant segment](crate::metadata::const_segment) and loaded from there.
 
 use super::LibfuncHelper;
 use crate::block_ext::BlockExt;
@@ -6,9 +9,10 @@ use crate::{
     error::{Error, Result},
     libfuncs::{r#enum::build_enum_value, r#struct::build_struct_value},
     metadata::{
-        prime_modulo::PrimeModuloMeta, realloc_bindings::ReallocBindingsMeta, MetadataStorage,
+        const_segment::ConstSegmentMeta, prime_modulo::PrimeModuloMeta,
+        realloc_bindings::ReallocBindingsMeta, MetadataStorage,
     },
-    types::TypeBuilder,
+    types::{r#enum::get_layout_for_variants, TypeBuilder},
     utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
@@ -19,6 +23,7 @@ use cairo_lang_sierra::{
         },
         core::{CoreLibfunc, CoreType, CoreTypeConcrete},
     },
+    ids::ConcreteTypeId,
     program::GenericArg,
     program_registry::ProgramRegistry,
 };
@@ -27,11 +32,15 @@ use melior::{
         arith,
         llvm::{self, r#type::pointer},
     },
-    ir::{Attribute, Block, Location, Value},
+    ir::{operation::OperationBuilder, Attribute, Block, Identifier, Location, Value},
     Context,
 };
-use num_bigint::ToBigInt;
+use num_bigint::{BigUint, Sign, ToBigInt};
 use starknet_types_core::felt::Felt;
+use std::alloc::Layout;
+
+/// Size (in bytes) above which aggregate constants are stored in the constant segment.
+const CONST_SEGMENT_THRESHOLD: usize = 32;
 
 /// Select and call the correct libfunc builder function from the selector.
 pub fn build<'ctx, 'this>(
@@ -64,7 +73,7 @@ pub fn build_const_as_box<'ctx, 'this>(
     info: &ConstAsBoxConcreteLibfunc,
 ) -> Result<()> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let const_type_outer = registry.get_type(&info.const_type)?;
@@ -75,10 +84,6 @@ pub fn build_const_as_box<'ctx, 'this>(
         _ => unreachable!(),
     };
 
-    let value = build_const_type_value(
-        context, registry, entry, location, helper, metadata, const_type,
-    )?;
-
     let const_ty = registry.get_type(&const_type.inner_ty)?;
     let inner_layout = const_ty.layout(registry)?;
 
@@ -91,7 +96,24 @@ pub fn build_const_as_box<'ctx, 'this>(
     ))?;
 
     // Store constant in box
-    entry.store(context, location, ptr, value)?;
+    if is_const_segment_value(registry, const_type)? {
+        let global_ptr = build_const_segment_ptr(
+            context,
+            registry,
+            entry,
+            location,
+            helper,
+            metadata,
+            &info.const_type,
+            const_type,
+        )?;
+        entry.memcpy(context, location, global_ptr, ptr, value_len);
+    } else {
+        let value = build_const_type_value(
+            context, registry, entry, location, helper, metadata, const_type,
+        )?;
+        entry.store(context, location, ptr, value)?;
+    }
 
     entry.append_operation(helper.br(0, &[ptr], location));
     Ok(())
@@ -114,14 +136,194 @@ pub fn build_const_as_immediate<'ctx, 'this>(
         _ => unreachable!(),
     };
 
-    let value = build_const_type_value(
-        context, registry, entry, location, helper, metadata, const_type,
-    )?;
+    let value = if is_const_segment_value(registry, const_type)? {
+        let global_ptr = build_const_segment_ptr(
+            context,
+            registry,
+            entry,
+            location,
+            helper,
+            metadata,
+            &info.const_type,
+            const_type,
+        )?;
+        let inner_ty =
+            registry.build_type(context, helper, registry, metadata, &const_type.inner_ty)?;
+
+        entry.load(context, location, global_ptr, inner_ty)?
+    } else {
+        build_const_type_value(
+            context, registry, entry, location, helper, metadata, const_type,
+        )?
+    };
 
     entry.append_operation(helper.br(0, &[value], location));
     Ok(())
 }
 
+/// Whether a constant should be loaded from the constant segment instead of being rebuilt every
+/// time it's used. Only aggregates bigger than a felt are worth it.
+fn is_const_segment_value(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    info: &ConstConcreteType,
+) -> Result<bool> {
+    let inner_type = registry.get_type(&info.inner_ty)?;
+
+    Ok(match inner_type {
+        CoreTypeConcrete::Struct(_) | CoreTypeConcrete::Enum(_) => {
+            inner_type.layout(registry)?.size() > CONST_SEGMENT_THRESHOLD
+        }
+        _ => false,
+    })
+}
+
+/// Return a pointer to the global which holds the constant, declaring it if necessary.
+#[allow(clippy::too_many_arguments)]
+fn build_const_segment_ptr<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    const_type_id: &ConcreteTypeId,
+    info: &ConstConcreteType,
+) -> Result<Value<'ctx, 'this>> {
+    let prime = metadata
+        .get::<PrimeModuloMeta<Felt>>()
+        .ok_or(Error::MissingMetadata)?
+        .prime()
+        .clone();
+
+    if metadata.get::<ConstSegmentMeta>().is_none() {
+        metadata.insert(ConstSegmentMeta::default());
+    }
+    let global = metadata
+        .get_mut::<ConstSegmentMeta>()
+        .unwrap()
+        .get_or_declare(context, helper, const_type_id, || {
+            let layout = registry
+                .get_type(&info.inner_ty)?
+                .layout(registry)?
+                .pad_to_align();
+
+            let mut data = vec![0; layout.size()];
+            write_const_data(registry, &prime, info, &mut data)?;
+
+            Ok((data, layout.align()))
+        })?;
+
+    entry.append_op_result(
+        OperationBuilder::new("llvm.mlir.addressof", location)
+            .add_attributes(&[(Identifier::new(context, "global_name"), global.into())])
+            .add_results(&[pointer(context, 0)])
+            .build()?,
+    )
+}
+
+/// Write the memory representation of a constant into `target`, which must be zero-initialized
+/// and have the size of the constant's type.
+fn write_const_data(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    prime: &BigUint,
+    info: &ConstConcreteType,
+    target: &mut [u8],
+) -> Result<()> {
+    match registry.get_type(&info.inner_ty)? {
+        CoreTypeConcrete::Struct(struct_info) => {
+            if info.inner_data.len() != struct_info.members.len() {
+                return Err(Error::ConstDataMismatch);
+            }
+
+            let mut layout: Option<Layout> = None;
+            for (field, member_ty) in info.inner_data.iter().zip(&struct_info.members) {
+                let member_layout = registry.get_type(member_ty)?.layout(registry)?;
+                let (new_layout, offset) = match layout {
+                    Some(layout) => layout.extend(member_layout)?,
+                    None => (member_layout, 0),
+                };
+                layout = Some(new_layout);
+
+                let GenericArg::Type(field_ty) = field else {
+                    return Err(Error::ConstDataMismatch);
+                };
+                write_const_data(
+                    registry,
+                    prime,
+                    get_const_type(registry, field_ty)?,
+                    &mut target[offset..offset + member_layout.size()],
+                )?;
+            }
+        }
+        CoreTypeConcrete::Enum(enum_info) => match &info.inner_data[..] {
+            [GenericArg::Value(variant_index), GenericArg::Type(payload_ty)] => {
+                let variant_index: usize = variant_index
+                    .try_into()
+                    .map_err(|_| Error::ConstDataMismatch)?;
+                let (_, tag_layout, variant_layouts) =
+                    get_layout_for_variants(registry, &enum_info.variants)?;
+                let payload_layout = variant_layouts
+                    .get(variant_index)
+                    .ok_or(Error::ConstDataMismatch)?;
+
+                target[..tag_layout.size()]
+                    .copy_from_slice(&variant_index.to_le_bytes()[..tag_layout.size()]);
+
+                let offset = tag_layout.extend(*payload_layout)?.1;
+                write_const_data(
+                    registry,
+                    prime,
+                    get_const_type(registry, payload_ty)?,
+                    &mut target[offset..offset + payload_layout.size()],
+                )?;
+            }
+            _ => return Err(Error::ConstDataMismatch),
+        },
+        CoreTypeConcrete::NonZero(_) => match &info.inner_data[..] {
+            [GenericArg::Type(inner)] => {
+                write_const_data(registry, prime, get_const_type(registry, inner)?, target)?
+            }
+            _ => return Err(Error::ConstDataMismatch),
+        },
+        inner_type => match &info.inner_data[..] {
+            [GenericArg::Value(value)] => {
+                let value = match inner_type {
+                    CoreTypeConcrete::Felt252(_) if value.sign() == Sign::Minus => {
+                        value + prime.to_bigint().expect("Prime to BigInt shouldn't fail")
+                    }
+                    _ => value.clone(),
+                };
+
+                // Negative values are stored in two's complement.
+                let bytes = if value.sign() == Sign::Minus {
+                    target.fill(0xFF);
+                    value.to_signed_bytes_le()
+                } else {
+                    value.magnitude().to_bytes_le()
+                };
+                if bytes.len() > target.len() {
+                    return Err(Error::ConstDataMismatch);
+                }
+
+                target[..bytes.len()].copy_from_slice(&bytes);
+            }
+            _ => return Err(Error::ConstDataMismatch),
+        },
+    }
+
+    Ok(())
+}
+
+fn get_const_type<'a>(
+    registry: &'a ProgramRegistry<CoreType, CoreLibfunc>,
+    type_id: &ConcreteTypeId,
+) -> Result<&'a ConstConcreteType> {
+    match registry.get_type(type_id)? {
+        CoreTypeConcrete::Const(inner) => Ok(inner),
+        _ => Err(Error::ConstDataMismatch),
+    }
+}
+
 pub fn build_const_type_value<'ctx, 'this>(
     context: &'ctx Context,
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
@@ -241,7 +443,7 @@ pub fn build_const_type_value<'ctx, 'this>(
             [GenericArg::Value(value)] => {
                 let mlir_value: Value = match inner_type {
                     CoreTypeConcrete::Felt252(_) => {
-                        let value = if value.sign() == num_bigint::Sign::Minus {
+                        let value = if value.sign() == Sign::Minus {
                             let prime = metadata
                                 .get::<PrimeModuloMeta<Felt>>()
                                 .ok_or(Error::MissingMetadata)?
@@ -277,9 +479,11 @@ pub fn build_const_type_value<'ctx, 'this>(
 #[cfg(test)]
 pub mod test {
     use crate::{
-        utils::test::{jit_struct, load_cairo, run_program},
+        context::NativeContext,
+        utils::test::{jit_enum, jit_struct, load_cairo, run_program},
         values::JitValue,
     };
+    use starknet_types_core::felt::Felt;
 
     #[test]
     fn run_const_as_box() {
@@ -301,4 +505,85 @@ pub mod test {
         let result = run_program(&program, "run_test", &[]).return_value;
         assert_eq!(result, jit_struct!(JitValue::Sint32(-2)));
     }
+
+    #[test]
+    fn run_const_as_box_const_segment() {
+        let program = load_cairo!(
+            use core::box::BoxTrait;
+
+            enum Kind {
+                A: felt252,
+                B: i64,
+            }
+
+            struct Hello {
+                a: felt252,
+                b: i16,
+                c: Kind,
+                d: felt252,
+            }
+
+            fn run_test() -> (Hello, Hello) {
+                let x = BoxTrait::new(Hello {
+                    a: -1,
+                    b: -300,
+                    c: Kind::B(-5),
+                    d: 1234,
+                });
+                let y = BoxTrait::new(Hello {
+                    a: 1,
+                    b: 2,
+                    c: Kind::A(3),
+                    d: 4,
+                });
+                (x.unbox(), y.unbox())
+            }
+        );
+
+        // Both constants are big enough to be stored in the constant segment.
+        let module = NativeContext::new().compile(&program.1, None).unwrap();
+        let ir = module.module().as_operation().to_string();
+        assert_eq!(ir.matches("llvm.mlir.global internal constant").count(), 2);
+
+        let result = run_program(&program, "run_test", &[]).return_value;
+        assert_eq!(
+            result,
+            jit_struct!(
+                jit_struct!(
+                    JitValue::Felt252(Felt::from(-1)),
+                    JitValue::Sint16(-300),
+                    jit_enum!(1, JitValue::Sint64(-5)),
+                    JitValue::Felt252(Felt::from(1234)),
+                ),
+                jit_struct!(
+                    JitValue::Felt252(Felt::from(1)),
+                    JitValue::Sint16(2),
+                    jit_enum!(0, JitValue::Felt252(Felt::from(3))),
+                    JitValue::Felt252(Felt::from(4)),
+                ),
+            )
+        );
+    }
+
+    #[test]
+    fn run_const_as_immediate_const_segment() {
+        let program = load_cairo!(
+            const VALUES: (felt252, felt252, felt252, u8) = (1, -2, 3, 4);
+
+            fn run_test() -> (felt252, felt252, felt252, u8) {
+                VALUES
+            }
+        );
+
+        let result = run_program(&program, "run_test", &[]).return_value;
+        assert_eq!(
+            result,
+            jit_struct!(
+                JitValue::Felt252(Felt::from(1)),
+                JitValue::Felt252(Felt::from(-2)),
+                JitValue::Felt252(Felt::from(3)),
+                JitValue::Uint8(4),
+            )
+        );
+    }
 }
@@ -1,11 +1,17 @@
-//! # Branch alignment libfunc
+//! # Coupon libfuncs
 //!
-//! Natively compiled code doesn't need branch alignment because it has no notion of segments.
-//! Because of this, this libfunc is a no-op.
+//! Coupons pay in advance for running a function, which can be invoked later using `coupon_call`
+//! (implemented alongside [function calls](super::function_call)). They only matter for the gas
+//! accounting, which is already resolved by the [gas metadata](crate::metadata::gas) at compile
+//! time, so coupons are zero-sized and these libfuncs don't generate any operation other than the
+//! branch.
 
 use super::LibfuncHelper;
 use crate::{
-    block_ext::BlockExt, error::Result, metadata::MetadataStorage, utils::ProgramRegistryExt,
+    block_ext::BlockExt,
+    error::Result,
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -17,13 +23,11 @@ use cairo_lang_sierra::{
     program_registry::ProgramRegistry,
 };
 use melior::{
-    dialect::llvm,
     ir::{Block, Location},
     Context,
 };
 
-/// Generate MLIR operations for the `coupon` libfuncs.
-/// In native it's mostly a no-op operation.
+/// Select and call the correct libfunc builder function from the selector.
 pub fn build<'ctx, 'this>(
     context: &'ctx Context,
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
@@ -35,21 +39,18 @@ pub fn build<'ctx, 'this>(
 ) -> Result<()> {
     match selector {
         CouponConcreteLibfunc::Buy(info) => {
-            // Libfunc for buying a coupon for a function. The cost of the coupon is the cost of running the
-            // function (not including the `call` and `ret` instructions).
-            // The coupon can be used to pay in advance for running the function, and run it later for
-            // free (paying only for the `call` and `ret` instructions) using `coupon_call`.
             build_buy(context, registry, entry, location, helper, metadata, info)
         }
         CouponConcreteLibfunc::Refund(info) => {
-            // Libfunc for getting a refund for an unused coupon. The refund is the cost of the function
-            // and it is added back to the gas wallet.
             build_refund(context, registry, entry, location, helper, metadata, info)
         }
     }
 }
 
-/// Generate MLIR operations for the `coupon` libfunc.
+/// Generate MLIR operations for the `coupon_buy` libfunc.
+///
+/// The cost of the coupon is the cost of running the function (without the call itself), which is
+/// already accounted for by the gas metadata.
 pub fn build_buy<'ctx, 'this>(
     context: &'ctx Context,
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
@@ -69,14 +70,16 @@ pub fn build_buy<'ctx, 'this>(
         metadata,
         &info.branch_signatures()[0].vars[0].ty,
     )?;
-    let coupon = entry.append_op_result(llvm::undef(ty, location))?;
+    let coupon = entry.append_op_result(DeterminismMeta::build_undef(metadata, ty, location))?;
 
     entry.append_operation(helper.br(0, &[coupon], location));
 
     Ok(())
 }
 
-/// Generate MLIR operations for the `coupon` libfunc.
+/// Generate MLIR operations for the `coupon_refund` libfunc.
+///
+/// The refund (the cost of the function) is already accounted for by the gas metadata.
 pub fn build_refund<'ctx, 'this>(
     _context: &'ctx Context,
     _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
@@ -94,3 +97,66 @@ pub fn build_refund<'ctx, 'this>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{context::NativeContext, executor::JitNativeExecutor, values::JitValue, OptLevel};
+    use cairo_lang_sierra::{program::Program, ProgramParser};
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        // Cairo can't generate coupons yet, so the program is written in Sierra.
+        static ref COUPON_PROGRAM: Program = ProgramParser::new()
+            .parse(
+                "type u32 = u32;
+                type CouponFoo = Coupon<user@foo>;
+
+                libfunc coupon_buy_foo = coupon_buy<CouponFoo>;
+                libfunc coupon_call_foo = coupon_call<user@foo>;
+                libfunc coupon_refund_foo = coupon_refund<CouponFoo>;
+                libfunc u32_const_7 = u32_const<7>;
+                libfunc store_temp_u32 = store_temp<u32>;
+
+                coupon_buy_foo() -> ([0]);
+                u32_const_7() -> ([1]);
+                store_temp_u32([1]) -> ([1]);
+                coupon_call_foo([1], [0]) -> ([2]);
+                return([2]);
+
+                coupon_buy_foo() -> ([0]);
+                coupon_refund_foo([0]) -> ();
+                u32_const_7() -> ([1]);
+                store_temp_u32([1]) -> ([1]);
+                return([1]);
+
+                store_temp_u32([0]) -> ([0]);
+                return([0]);
+
+                run_call@0() -> (u32);
+                run_refund@5() -> (u32);
+                foo@10([0]: u32) -> (u32);",
+            )
+            .unwrap();
+    }
+
+    fn run(func: usize) -> JitValue {
+        let program = &*COUPON_PROGRAM;
+
+        let module = NativeContext::new().compile(program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        executor
+            .invoke_dynamic(&program.funcs[func].id, &[], None)
+            .unwrap()
+            .return_value
+    }
+
+    #[test]
+    fn run_coupon_call() {
+        assert_eq!(run(0), JitValue::Uint32(7));
+    }
+
+    #[test]
+    fn run_coupon_refund() {
+        assert_eq!(run(1), JitValue::Uint32(7));
+    }
+}
@@ -1,4 +1,14 @@
 //! # Debug libfuncs
+//!
+//! The `print` libfunc (used by Cairo's `print!` and `println!` macros) writes its values into
+//! stdout through the runtime. Test frameworks which need to capture the output may register a host
+//! callback with [set_debug_print_callback] instead. Since the callback lives in the runtime, only
+//! the programs run by the JIT executor (which share the host's runtime) will use it. AOT-compiled
+//! libraries carry their own copy of the runtime and always print.
+//!
+//! The `cheatcode` libfunc isn't handled here: when the `with-cheatcode` feature is enabled, it's
+//! forwarded to the `cheatcode` method of the
+//! [syscall handler](crate::starknet::StarknetSyscallHandler).
 
 // Printable: 9-13, 27, 32, 33-126
 //     is_ascii_graphic() -> 33-126
@@ -13,7 +23,7 @@ use super::LibfuncHelper;
 use crate::block_ext::BlockExt;
 use crate::{
     error::Result,
-    metadata::{runtime_bindings::RuntimeBindingsMeta, MetadataStorage},
+    metadata::{panic::PanicMeta, runtime_bindings::RuntimeBindingsMeta, MetadataStorage},
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -24,17 +34,20 @@ use cairo_lang_sierra::{
     program_registry::ProgramRegistry,
 };
 use melior::{
-    dialect::{arith, cf, llvm},
+    dialect::{arith, llvm},
     ir::{r#type::IntegerType, Block, Location},
     Context,
 };
 
-pub fn build<'ctx>(
+#[cfg(feature = "with-runtime")]
+pub use cairo_native_runtime::{set_debug_print_callback, DebugPrintCallback};
+
+pub fn build<'ctx, 'this>(
     context: &'ctx Context,
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-    entry: &Block<'ctx>,
+    entry: &'this Block<'ctx>,
     location: Location<'ctx>,
-    helper: &LibfuncHelper<'ctx, '_>,
+    helper: &LibfuncHelper<'ctx, 'this>,
     metadata: &mut MetadataStorage,
     selector: &DebugConcreteLibfunc,
 ) -> Result<()> {
@@ -45,12 +58,12 @@ pub fn build<'ctx>(
     }
 }
 
-pub fn build_print<'ctx>(
+pub fn build_print<'ctx, 'this>(
     context: &'ctx Context,
     _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-    entry: &Block<'ctx>,
+    entry: &'this Block<'ctx>,
     location: Location<'ctx>,
-    helper: &LibfuncHelper<'ctx, '_>,
+    helper: &LibfuncHelper<'ctx, 'this>,
     metadata: &mut MetadataStorage,
     _info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
@@ -113,14 +126,49 @@ pub fn build_print<'ctx>(
         k0,
         location,
     ))?;
-    cf::assert(
+    let entry = PanicMeta::get(metadata).build_assert(
         context,
+        helper,
+        entry,
+        location,
         return_code_is_ok,
         "Print libfunc invocation failed.",
-        location,
-    );
+    )?;
 
     entry.append_operation(helper.br(0, &[], location));
 
     Ok(())
 }
+
+#[cfg(all(test, feature = "with-runtime"))]
+mod test {
+    use super::*;
+    use crate::utils::test::{load_cairo, run_program};
+    use starknet_types_core::felt::Felt;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn print_callback() {
+        let program = load_cairo! {
+            use core::debug::print;
+
+            fn run_test() {
+                print(array![0x1234, 0x5678]);
+            }
+        };
+
+        let printed = Arc::new(Mutex::new(Vec::new()));
+        let prev_callback = set_debug_print_callback(Some(Arc::new({
+            let printed = printed.clone();
+            move |values| printed.lock().unwrap().push(values.to_vec())
+        })));
+        run_program(&program, "run_test", &[]);
+        set_debug_print_callback(prev_callback);
+
+        // Other tests may be printing at the same time.
+        assert!(printed
+            .lock()
+            .unwrap()
+            .contains(&vec![Felt::from(0x1234), Felt::from(0x5678)]));
+    }
+}
@@ -1,12 +1,16 @@
-//! # `AP` tracking libfuncs
+//! # Value dropping libfunc
 //!
 //! Most types are trivial and don't need dropping (or rather, they will be dropped automatically
 //! by MLIR). For those types, this libfunc is a no-op.
 //!
-//! However, types like an array need manual dropping.
+//! However, types like an array need manual dropping. Their type builders register a drop
+//! implementation in the [drop overrides metadata](crate::metadata::drop_overrides).
 
 use super::LibfuncHelper;
-use crate::{error::Result, metadata::MetadataStorage, types::TypeBuilder};
+use crate::{
+    error::Result,
+    metadata::{drop_overrides::DropOverridesMeta, MetadataStorage},
+};
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
@@ -29,20 +33,90 @@ pub fn build<'ctx, 'this>(
     metadata: &mut MetadataStorage,
     info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
-    // Note: Complex types implement drop within the type itself (in `build_drop`).
-
-    let ty = registry.get_type(&info.signature.param_signatures[0].ty)?;
-    ty.build_drop(
-        context,
-        registry,
-        entry,
-        location,
-        helper,
-        metadata,
-        &info.signature.param_signatures[0].ty,
-    )?;
+    let entry = match metadata
+        .get::<DropOverridesMeta>()
+        .and_then(|meta| meta.wrap_invoke(&info.signature.param_signatures[0].ty))
+    {
+        Some(drop_fn) => drop_fn(
+            context,
+            registry,
+            entry,
+            location,
+            helper,
+            metadata,
+            entry.argument(0)?.into(),
+        )?,
+        None => entry,
+    };
 
     entry.append_operation(helper.br(0, &[], location));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        metadata::{drop_overrides::DropOverridesMeta, MetadataStorage},
+        types::TypeBuilder,
+        utils::test::{load_cairo, run_program_assert_output},
+    };
+    use cairo_lang_sierra::{
+        extensions::core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+        program_registry::ProgramRegistry,
+    };
+    use melior::{
+        ir::{Location, Module},
+        Context,
+    };
+    use starknet_types_core::felt::Felt;
+
+    #[test]
+    fn drop_heap_values() {
+        let program = load_cairo! {
+            use core::dict::Felt252DictTrait;
+
+            fn run_test() -> felt252 {
+                let array = array![1, 2, 3];
+                let boxed = BoxTrait::new(4);
+                let _boxed_array = BoxTrait::new(array![8, 9]);
+                let _nullable: Nullable<felt252> = NullableTrait::new(5);
+                let mut dict: Felt252Dict<felt252> = Default::default();
+                dict.insert(6, 7);
+
+                array.len().into() + boxed.unbox()
+            }
+        };
+
+        run_program_assert_output(&program, "run_test", &[], Felt::from(7).into());
+    }
+
+    #[test]
+    fn drop_overrides_registration() {
+        let (_, program) = load_cairo! {
+            fn run_test(_a: Array<felt252>, _b: Box<felt252>, _c: felt252) {}
+        };
+
+        let context = Context::new();
+        let module = Module::new(Location::unknown(&context));
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let mut metadata = MetadataStorage::new();
+
+        for decl in &program.type_declarations {
+            registry
+                .get_type(&decl.id)
+                .unwrap()
+                .build(&context, &module, &registry, &mut metadata, &decl.id)
+                .unwrap();
+        }
+
+        let meta = metadata.get::<DropOverridesMeta>().unwrap();
+        for decl in &program.type_declarations {
+            let expected = matches!(
+                registry.get_type(&decl.id).unwrap(),
+                CoreTypeConcrete::Array(_) | CoreTypeConcrete::Box(_)
+            );
+            assert_eq!(meta.is_overridden(&decl.id), expected);
+        }
+    }
+}
@@ -41,6 +41,7 @@ pub fn build<'ctx, 'this>(
         .and_then(|meta| meta.wrap_invoke(&info.signature.param_signatures[0].ty))
     {
         Some(clone_fn) => {
+            let original_value = entry.argument(0)?.into();
             let (entry, cloned_value) = clone_fn(
                 context,
                 registry,
@@ -48,14 +49,12 @@ pub fn build<'ctx, 'this>(
                 location,
                 helper,
                 metadata,
-                entry.argument(0)?.into(),
+                original_value,
             )?;
 
-            entry.append_operation(helper.br(
-                0,
-                &[entry.argument(0)?.into(), cloned_value],
-                location,
-            ));
+            // The clone implementation may have continued in a different block, which doesn't
+            // have the libfunc's arguments.
+            entry.append_operation(helper.br(0, &[original_value, cloned_value], location));
         }
         None => {
             entry.append_operation(helper.br(
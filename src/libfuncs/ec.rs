@@ -5,7 +5,8 @@ use crate::{
     block_ext::BlockExt,
     error::{Error, Result},
     metadata::{
-        prime_modulo::PrimeModuloMeta, runtime_bindings::RuntimeBindingsMeta, MetadataStorage,
+        determinism::DeterminismMeta, prime_modulo::PrimeModuloMeta,
+        runtime_bindings::RuntimeBindingsMeta, MetadataStorage,
     },
     types::felt252::register_prime_modulo_meta,
     utils::{get_integer_layout, ProgramRegistryExt},
@@ -196,7 +197,11 @@ pub fn build_point_from_x<'ctx, 'this>(
         get_integer_layout(252).align(),
     )?;
 
-    let point = entry.append_op_result(llvm::undef(ec_point_ty, location))?;
+    let point = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        ec_point_ty,
+        location,
+    ))?;
     let point = entry.insert_value(context, location, point, entry.argument(1)?.into(), 0)?;
 
     entry.store(context, location, point_ptr, point)?;
@@ -377,7 +382,7 @@ pub fn build_state_init<'ctx, 'this>(
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
-    _metadata: &mut MetadataStorage,
+    metadata: &mut MetadataStorage,
     _info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
     let ec_state_ty = llvm::r#type::r#struct(
@@ -391,7 +396,11 @@ pub fn build_state_init<'ctx, 'this>(
         false,
     );
 
-    let point = entry.append_op_result(llvm::undef(ec_state_ty, location))?;
+    let point = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        ec_state_ty,
+        location,
+    ))?;
 
     let value = BigInt::parse_bytes(
         b"3151312365169595090315724863753927489909436624354740709748557281394568342450",
@@ -445,7 +454,11 @@ pub fn build_try_new<'ctx, 'this>(
         get_integer_layout(252).align(),
     )?;
 
-    let point = entry.append_op_result(llvm::undef(ec_point_ty, location))?;
+    let point = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        ec_point_ty,
+        location,
+    ))?;
     let point = entry.insert_value(context, location, point, entry.argument(0)?.into(), 0)?;
     let point = entry.insert_value(context, location, point, entry.argument(1)?.into(), 1)?;
 
@@ -522,7 +535,11 @@ pub fn build_zero<'ctx, 'this>(
         &info.branch_signatures()[0].vars[0].ty,
     )?;
 
-    let point = entry.append_op_result(llvm::undef(ec_point_ty, location))?;
+    let point = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        ec_point_ty,
+        location,
+    ))?;
 
     let k0 = entry.const_int(context, location, 0, 252)?;
 
@@ -616,6 +633,16 @@ mod test {
                 ec_point_unwrap(point)
             }
         };
+        static ref EC_MUL: (String, Program) = load_cairo! {
+            use core::ec::{ec_state_add_mul, ec_state_init, ec_state_try_finalize_nz, EcPoint};
+            use core::zeroable::NonZero;
+
+            fn run_test(scalar: felt252, point: NonZero<EcPoint>) -> Option<NonZero<EcPoint>> {
+                let mut state = ec_state_init();
+                ec_state_add_mul(ref state, scalar, point);
+                ec_state_try_finalize_nz(state)
+            }
+        };
         static ref EC_POINT_ZERO: (String, Program) = load_cairo! {
             use core::ec::{ec_point_zero, EcPoint};
 
@@ -890,4 +917,47 @@ mod test {
             ),
         );
     }
+
+    #[test]
+    fn ec_mul_generator() {
+        // The STARK curve generator.
+        let generator = JitValue::EcPoint(
+            Felt::from_hex("0x1ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca")
+                .unwrap(),
+            Felt::from_hex("0x5668060aa49730b7be4801df46ec62de53ecd11abe43a32873000c36e8dc1f")
+                .unwrap(),
+        );
+        let r = |scalar: &str| {
+            run_program(
+                &EC_MUL,
+                "run_test",
+                &[Felt::from_hex(scalar).unwrap().into(), generator.clone()],
+            )
+            .return_value
+        };
+
+        // Public key of the private key `0x12`, from the `starknet-crypto` test vectors.
+        assert_eq!(
+            r("0x12"),
+            jit_enum!(
+                0,
+                JitValue::EcPoint(
+                    Felt::from_hex(
+                        "0x19661066e96a8b9f06a1d136881ee924dfb6a885239caa5fd3f87a54c6b25c4"
+                    )
+                    .unwrap(),
+                    Felt::from_dec_str(
+                        "2615536950657094826354052923949077173993749485486288480655484184943655143049"
+                    )
+                    .unwrap(),
+                )
+            )
+        );
+
+        // Multiplying by the curve order yields the point at infinity.
+        assert_eq!(
+            r("0x800000000000010ffffffffffffffffb781126dcae7b2321e66a241adc64d2f"),
+            jit_enum!(1, jit_struct!())
+        );
+    }
 }
@@ -5,27 +5,28 @@
 use super::LibfuncHelper;
 use crate::{
     block_ext::BlockExt,
+    context::DeterminismMode,
     error::{Error, Result},
-    metadata::{enum_snapshot_variants::EnumSnapshotVariantsMeta, MetadataStorage},
+    metadata::{
+        determinism::DeterminismMeta, enum_snapshot_variants::EnumSnapshotVariantsMeta,
+        panic::PanicMeta, snapshot_clones::SnapshotClonesMeta, MetadataStorage,
+    },
     types::TypeBuilder,
+    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
         enm::{EnumConcreteLibfunc, EnumFromBoundedIntConcreteLibfunc, EnumInitConcreteLibfunc},
         lib_func::SignatureOnlyConcreteLibfunc,
-        ConcreteLibfunc,
+        ConcreteLibfunc, ConcreteType,
     },
     ids::ConcreteTypeId,
     program_registry::ProgramRegistry,
 };
 use melior::{
-    dialect::{arith, cf, llvm, ods},
-    ir::{
-        attribute::{DenseI64ArrayAttribute, IntegerAttribute},
-        r#type::IntegerType,
-        Block, Location, Value,
-    },
+    dialect::{cf, llvm, ods},
+    ir::{r#type::IntegerType, Block, Location, Value},
     Context,
 };
 use std::num::TryFromIntError;
@@ -124,22 +125,10 @@ pub fn build_enum_value<'ctx, 'this>(
                 false,
             );
 
-            let tag_val = entry
-                .append_operation(arith::constant(
-                    context,
-                    IntegerAttribute::new(
-                        tag_ty,
-                        variant_index
-                            .try_into()
-                            .expect("couldnt convert index to i64"),
-                    )
-                    .into(),
-                    location,
-                ))
-                .result(0)?
-                .into();
+            let tag_val = entry.const_int_from_type(context, location, variant_index, tag_ty)?;
 
-            let val = entry.append_op_result(llvm::undef(enum_ty, location))?;
+            let val = entry
+                .append_op_result(DeterminismMeta::build_undef(metadata, enum_ty, location))?;
             let val = entry.insert_value(context, location, val, tag_val, 0)?;
 
             let mut val = if payload_type_info.is_zst(registry) {
@@ -156,6 +145,14 @@ pub fn build_enum_value<'ctx, 'this>(
                     layout.align(),
                 )?;
 
+                // The bytes after the payload would be whatever was on the stack.
+                if DeterminismMeta::mode_of(metadata) != DeterminismMode::Off {
+                    let enum_ty =
+                        type_info.build(context, helper, registry, metadata, enum_type)?;
+                    let zero = entry.append_op_result(llvm::zero(enum_ty, location))?;
+                    entry.store(context, location, stack_ptr, zero)?;
+                }
+
                 // Convert the enum from the concrete variant to the internal representation.
                 entry.store(context, location, stack_ptr, val)?;
                 val = entry.load(
@@ -222,7 +219,8 @@ pub fn build_from_bounded_int<'ctx, 'this>(
         }
     };
 
-    let value = entry.append_op_result(llvm::undef(enum_ty, location))?;
+    let value =
+        entry.append_op_result(DeterminismMeta::build_undef(metadata, enum_ty, location))?;
     let value = entry.insert_value(context, location, value, tag_value, 0)?;
 
     entry.append_operation(helper.br(0, &[value], location));
@@ -241,145 +239,20 @@ pub fn build_match<'ctx, 'this>(
     info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
     let type_info = registry.get_type(&info.param_signatures()[0].ty)?;
+    let variant_ids = type_info.variants().unwrap().to_vec();
 
-    let variant_ids = type_info.variants().unwrap();
-    match variant_ids.len() {
-        0 => {
-            // The Cairo compiler will generate an enum match for enums without variants, so this
-            // case cannot be a compile-time error. We're assuming that even though it's been
-            // generated, it's just dead code and can be made into an assertion that always fails.
-
-            let k0 = entry.const_int(context, location, 0, 1)?;
-            entry.append_operation(cf::assert(
-                context,
-                k0,
-                "attempt to match a zero-variant enum",
-                location,
-            ));
-
-            entry.append_operation(llvm::unreachable(location));
-        }
-        1 => {
-            entry.append_operation(helper.br(0, &[entry.argument(0)?.into()], location));
-        }
-        _ => {
-            let (layout, (tag_ty, _), variant_tys) = crate::types::r#enum::get_type_for_variants(
-                context,
-                helper,
-                registry,
-                metadata,
-                variant_ids,
-            )?;
-
-            let (stack_ptr, tag_val) = if type_info.is_memory_allocated(registry) {
-                let stack_ptr = helper.init_block().alloca1(
-                    context,
-                    location,
-                    type_info.build(
-                        context,
-                        helper,
-                        registry,
-                        metadata,
-                        &info.param_signatures()[0].ty,
-                    )?,
-                    layout.align(),
-                )?;
-                entry.store(context, location, stack_ptr, entry.argument(0)?.into())?;
-                let tag_val = entry.load(context, location, stack_ptr, tag_ty)?;
-
-                (Some(stack_ptr), tag_val)
-            } else {
-                let tag_val = entry
-                    .append_operation(llvm::extract_value(
-                        context,
-                        entry.argument(0)?.into(),
-                        DenseI64ArrayAttribute::new(context, &[0]),
-                        tag_ty,
-                        location,
-                    ))
-                    .result(0)?
-                    .into();
-
-                (None, tag_val)
-            };
-
-            let default_block = helper.append_block(Block::new(&[]));
-            let variant_blocks = variant_tys
-                .iter()
-                .map(|_| helper.append_block(Block::new(&[])))
-                .collect::<Vec<_>>();
-
-            let case_values = (0..variant_tys.len())
-                .map(i64::try_from)
-                .collect::<std::result::Result<Vec<_>, TryFromIntError>>()?;
-
-            entry.append_operation(cf::switch(
-                context,
-                &case_values,
-                tag_val,
-                tag_ty,
-                (default_block, &[]),
-                &variant_blocks
-                    .iter()
-                    .copied()
-                    .map(|block| (block, [].as_slice()))
-                    .collect::<Vec<_>>(),
-                location,
-            )?);
-
-            // Default block.
-            {
-                let val = default_block
-                    .append_operation(arith::constant(
-                        context,
-                        IntegerAttribute::new(IntegerType::new(context, 1).into(), 0).into(),
-                        location,
-                    ))
-                    .result(0)?
-                    .into();
-
-                default_block.append_operation(cf::assert(
-                    context,
-                    val,
-                    "Invalid enum tag.",
-                    location,
-                ));
-                default_block.append_operation(llvm::unreachable(location));
-            }
-
-            // Enum variants.
-            for (i, (block, (payload_ty, _))) in
-                variant_blocks.into_iter().zip(variant_tys).enumerate()
-            {
-                let enum_ty = llvm::r#type::r#struct(context, &[tag_ty, payload_ty], false);
-
-                let payload_val = match stack_ptr {
-                    Some(stack_ptr) => {
-                        let val = block.load(context, location, stack_ptr, enum_ty)?;
-                        block.extract_value(context, location, val, payload_ty, 1)?
-                    }
-                    None => {
-                        // If the enum is not memory-allocated it means that:
-                        //   - Either it's a C-style enum and all payloads have the same type.
-                        //   - Or the enum only has a single non-memory-allocated variant.
-                        if variant_ids.len() == 1 {
-                            entry.argument(0)?.into()
-                        } else {
-                            assert!(registry.get_type(&variant_ids[i])?.is_zst(registry));
-                            block
-                                .append_operation(llvm::undef(payload_ty, location))
-                                .result(0)?
-                                .into()
-                        }
-                    }
-                };
-
-                block.append_operation(helper.br(i, &[payload_val], location));
-            }
-        }
-    }
-
-    Ok(())
+    // Duplicated enums are bitwise copies which share their payload, so it can't be moved out.
+    build_match_variants(
+        context,
+        registry,
+        entry,
+        location,
+        helper,
+        metadata,
+        &info.param_signatures()[0].ty,
+        &variant_ids,
+        type_info.info().duplicatable,
+    )
 }
 
 /// Generate MLIR operations for the `enum_snapshot_match` libfunc.
@@ -392,33 +265,79 @@ pub fn build_snapshot_match<'ctx, 'this>(
     metadata: &mut MetadataStorage,
     info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
-    let type_info = registry.get_type(&info.param_signatures()[0].ty)?;
-
-    // This libfunc's implementation is identical to `enum_match` aside from fetching the snapshotted enum's variants from the metadata:
+    // The snapshot type doesn't have the enum's variants, so they're fetched from the metadata.
     let variant_ids = metadata
         .get::<EnumSnapshotVariantsMeta>()
         .ok_or(Error::MissingMetadata)?
         .get_variants(&info.param_signatures()[0].ty)
         .expect("enum should always have variants")
         .clone();
+
+    // Snapshots of enums are bitwise copies which share their payload with the original enum.
+    build_match_variants(
+        context,
+        registry,
+        entry,
+        location,
+        helper,
+        metadata,
+        &info.param_signatures()[0].ty,
+        &variant_ids,
+        true,
+    )
+}
+
+/// Branch to the target of the enum's current variant, passing its payload.
+///
+/// The tag is read either directly from the value (C-style enums) or from a stack copy of the enum
+/// (memory-allocated enums), then a switch jumps to one block per variant, each of which extracts
+/// its payload and branches to the libfunc target with the same index.
+///
+/// When `clone_payload` is set, payloads with a clone implementation are cloned instead of moved.
+#[allow(clippy::too_many_arguments)]
+fn build_match_variants<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    enum_type: &ConcreteTypeId,
+    variant_ids: &[ConcreteTypeId],
+    clone_payload: bool,
+) -> Result<()> {
+    let type_info = registry.get_type(enum_type)?;
+
     match variant_ids.len() {
         0 => {
             // The Cairo compiler will generate an enum match for enums without variants, so this
             // case cannot be a compile-time error. We're assuming that even though it's been
             // generated, it's just dead code and can be made into an assertion that always fails.
 
-            let k0 = entry.const_int(context, location, 0, 1)?;
-            entry.append_operation(cf::assert(
+            PanicMeta::get(metadata).build_panic(
                 context,
-                k0,
-                "attempt to match a zero-variant enum",
+                helper,
+                entry,
                 location,
-            ));
-
-            entry.append_operation(llvm::unreachable(location));
+                "attempt to match a zero-variant enum",
+            )?;
         }
         1 => {
-            entry.append_operation(helper.br(0, &[entry.argument(0)?.into()], location));
+            // Single-variant enums have the same layout as their payload.
+            registry.build_type(context, helper, registry, metadata, &variant_ids[0])?;
+            let (entry, payload_val) = build_take_payload(
+                context,
+                registry,
+                entry,
+                location,
+                helper,
+                metadata,
+                &variant_ids[0],
+                entry.argument(0)?.into(),
+                clone_payload,
+            )?;
+
+            entry.append_operation(helper.br(0, &[payload_val], location));
         }
         _ => {
             let (layout, (tag_ty, _), variant_tys) = crate::types::r#enum::get_type_for_variants(
@@ -426,20 +345,14 @@ pub fn build_snapshot_match<'ctx, 'this>(
                 helper,
                 registry,
                 metadata,
-                &variant_ids,
+                variant_ids,
             )?;
 
             let (stack_ptr, tag_val) = if type_info.is_memory_allocated(registry) {
                 let stack_ptr = helper.init_block().alloca1(
                     context,
                     location,
-                    type_info.build(
-                        context,
-                        helper,
-                        registry,
-                        metadata,
-                        &info.param_signatures()[0].ty,
-                    )?,
+                    type_info.build(context, helper, registry, metadata, enum_type)?,
                     layout.align(),
                 )?;
                 entry.store(context, location, stack_ptr, entry.argument(0)?.into())?;
@@ -478,41 +391,44 @@ pub fn build_snapshot_match<'ctx, 'this>(
             )?);
 
             // Default block.
-            {
-                let val = default_block.const_int(context, location, 0, 1)?;
-
-                default_block.append_operation(cf::assert(
-                    context,
-                    val,
-                    "Invalid enum tag.",
-                    location,
-                ));
-                default_block.append_operation(llvm::unreachable(location));
-            }
+            PanicMeta::get(metadata).build_panic(
+                context,
+                helper,
+                default_block,
+                location,
+                "Invalid enum tag.",
+            )?;
 
             // Enum variants.
             for (i, (block, (payload_ty, _))) in
                 variant_blocks.into_iter().zip(variant_tys).enumerate()
             {
-                let enum_ty = llvm::r#type::r#struct(context, &[tag_ty, payload_ty], false);
-
                 let payload_val = match stack_ptr {
                     Some(stack_ptr) => {
+                        let enum_ty = llvm::r#type::r#struct(context, &[tag_ty, payload_ty], false);
                         let val = block.load(context, location, stack_ptr, enum_ty)?;
                         block.extract_value(context, location, val, payload_ty, 1)?
                     }
                     None => {
-                        // If the enum is not memory-allocated it means that:
-                        //   - Either it's a C-style enum and all payloads have the same type.
-                        //   - Or the enum only has a single non-memory-allocated variant.
-                        if variant_ids.len() == 1 {
-                            entry.argument(0)?.into()
-                        } else {
-                            assert!(registry.get_type(&variant_ids[i])?.is_zst(registry));
-                            block.append_op_result(llvm::undef(payload_ty, location))?
-                        }
+                        // Enums with more than one variant are only kept in registers when all
+                        // their payloads are zero-sized (C-style enums).
+                        debug_assert!(registry.get_type(&variant_ids[i])?.is_zst(registry));
+                        block.append_op_result(DeterminismMeta::build_undef(
+                            metadata, payload_ty, location,
+                        ))?
                     }
                 };
+                let (block, payload_val) = build_take_payload(
+                    context,
+                    registry,
+                    block,
+                    location,
+                    helper,
+                    metadata,
+                    &variant_ids[i],
+                    payload_val,
+                    clone_payload,
+                )?;
 
                 block.append_operation(helper.br(i, &[payload_val], location));
             }
@@ -522,6 +438,42 @@ pub fn build_snapshot_match<'ctx, 'this>(
     Ok(())
 }
 
+/// Return the payload of the matched variant, cloning it if required.
+///
+/// Returns the block where the code generation should continue along with the payload.
+#[allow(clippy::too_many_arguments)]
+fn build_take_payload<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    payload_type: &ConcreteTypeId,
+    payload_val: Value<'ctx, 'this>,
+    clone_payload: bool,
+) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
+    let payload_clone = match clone_payload {
+        true => metadata
+            .get::<SnapshotClonesMeta>()
+            .and_then(|meta| meta.wrap_invoke(payload_type)),
+        false => None,
+    };
+
+    match payload_clone {
+        Some(payload_clone) => payload_clone(
+            context,
+            registry,
+            block,
+            location,
+            helper,
+            metadata,
+            payload_val,
+        ),
+        None => Ok((block, payload_val)),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -587,6 +539,40 @@ mod test {
                     MyEnum::E(_) => 3_u8,
                 }
             }
+
+            fn match_snapshot(x: @MyEnum) -> u64 {
+                match x {
+                    MyEnum::A(_) => 0,
+                    MyEnum::B(x) => (*x).into(),
+                    MyEnum::C(x) => (*x).into(),
+                    MyEnum::D(x) => (*x).into(),
+                    MyEnum::E(x) => *x,
+                }
+            }
+
+            fn match_e_snapshot() -> u64 {
+                match_snapshot(@MyEnum::E(1234))
+            }
+        };
+        static ref ENUM_MATCH_C_STYLE: (String, Program) = load_cairo! {
+            #[derive(Drop)]
+            enum Color {
+                Red,
+                Green,
+                Blue,
+            }
+
+            fn next(color: Color) -> Color {
+                match color {
+                    Color::Red => Color::Green,
+                    Color::Green => Color::Blue,
+                    Color::Blue => Color::Red,
+                }
+            }
+
+            fn run_test() -> (Color, Color, Color, bool) {
+                (next(Color::Red), next(Color::Green), next(Color::Blue), !false)
+            }
         };
     }
 
@@ -613,6 +599,26 @@ mod test {
         run_program_assert_output(&ENUM_MATCH, "match_b", &[], 5u8.into());
     }
 
+    #[test]
+    fn enum_snapshot_match() {
+        run_program_assert_output(&ENUM_MATCH, "match_e_snapshot", &[], 1234u64.into());
+    }
+
+    #[test]
+    fn enum_match_c_style() {
+        run_program_assert_output(
+            &ENUM_MATCH_C_STYLE,
+            "run_test",
+            &[],
+            jit_struct!(
+                jit_enum!(1, jit_struct!()),
+                jit_enum!(2, jit_struct!()),
+                jit_enum!(0, jit_struct!()),
+                jit_enum!(1, jit_struct!()),
+            ),
+        );
+    }
+
     #[test]
     fn compile_enum_match_without_variants() {
         let (_, program) = load_cairo! {
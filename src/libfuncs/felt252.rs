@@ -102,10 +102,19 @@ pub fn build_binary_operation<'ctx, 'this>(
                 _ => operation.c.to_biguint().expect("sign already checked"),
             };
 
-            // TODO: Ensure that the constant is on the correct side of the operation.
+            // The constant is always the right hand side operand. Since it's known at compile time,
+            // dividing by it is the same as multiplying by its (precomputed) inverse.
+            let (op, value) = match operation.operator {
+                Felt252BinaryOperator::Div => (
+                    Felt252BinaryOperator::Mul,
+                    value.modpow(&(prime - 2u32), prime),
+                ),
+                op => (op, value),
+            };
+
             let rhs = entry.const_int_from_type(context, location, value, felt252_ty)?;
 
-            (operation.operator, entry.argument(0)?.into(), rhs)
+            (op, entry.argument(0)?.into(), rhs)
         }
     };
 
@@ -387,10 +396,13 @@ pub fn build_is_zero<'ctx, 'this>(
 #[cfg(test)]
 pub mod test {
     use crate::{
-        utils::test::{load_cairo, run_program, run_program_assert_output},
+        context::NativeContext,
+        executor::JitNativeExecutor,
+        utils::test::{jit_enum, jit_struct, load_cairo, run_program, run_program_assert_output},
         values::JitValue,
+        OptLevel,
     };
-    use cairo_lang_sierra::program::Program;
+    use cairo_lang_sierra::{program::Program, ProgramParser};
     use lazy_static::lazy_static;
 
     lazy_static! {
@@ -425,11 +437,6 @@ pub mod test {
             }
         };
 
-        // TODO: Add test program for `felt252_add_const`.
-        // TODO: Add test program for `felt252_sub_const`.
-        // TODO: Add test program for `felt252_mul_const`.
-        // TODO: Add test program for `felt252_div_const`.
-
         static ref FELT252_CONST: (String, Program) = load_cairo! {
             fn run_test() -> (felt252, felt252, felt252, felt252) {
                 (0, 1, -2, -1)
@@ -444,6 +451,17 @@ pub mod test {
                 }
             }
         };
+
+        static ref FELT252_IS_ZERO_UNWRAP: (String, Program) = load_cairo! {
+            use core::zeroable::{IsZeroResult, NonZeroIntoImpl};
+
+            fn run_test(x: felt252) -> Option<felt252> {
+                match felt252_is_zero(x) {
+                    IsZeroResult::Zero => Option::None,
+                    IsZeroResult::NonZero(x) => Option::Some(NonZeroIntoImpl::into(x)),
+                }
+            }
+        };
     }
 
     #[test]
@@ -772,6 +790,122 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn felt252_div_inverses() {
+        let run_test =
+            |lhs, rhs| match run_program(&FELT252_DIV, "run_test", &[lhs, rhs]).return_value {
+                JitValue::Enum { value, .. } => match *value {
+                    JitValue::Struct { fields, .. } => fields[0].clone(),
+                    _ => panic!("Expected Struct"),
+                },
+                _ => panic!("Expected Enum"),
+            };
+
+        // Dividing one returns the modular inverse of the divisor.
+        assert_eq!(
+            run_test(JitValue::felt_str("1"), JitValue::felt_str("2")),
+            JitValue::felt_str(
+                "1809251394333065606848661391547535052811553607665798349986546028067936010241"
+            )
+        );
+        assert_eq!(
+            run_test(JitValue::felt_str("1"), JitValue::felt_str("3")),
+            JitValue::felt_str(
+                "1206167596222043737899107594365023368541035738443865566657697352045290673494"
+            )
+        );
+        assert_eq!(
+            run_test(JitValue::felt_str("1"), JitValue::felt_str("7")),
+            JitValue::felt_str(
+                "3101573818856683897454848099795774376248377613141368599976936048116461731841"
+            )
+        );
+
+        // Inexact division of values that use the whole felt range.
+        assert_eq!(
+            run_test(
+                JitValue::felt_str(
+                    "514631507721405306298073637848375664226723355710112857507800679889911926255"
+                ),
+                JitValue::felt_str(
+                    "14126923864605837373081509497553327746001177276144628897982068960318223956"
+                ),
+            ),
+            JitValue::felt_str(
+                "1592723131134725727047422586345923947583698280964614869261197425168252573233"
+            )
+        );
+    }
+
+    /// Run a Sierra program which applies the `felt252_*_const` libfunc to its argument. The Cairo
+    /// compiler doesn't generate them, so the program is written in Sierra directly.
+    fn run_const_operation(libfunc: &str, value: JitValue) -> JitValue {
+        let program = ProgramParser::new()
+            .parse(&format!(
+                "type felt252 = felt252;
+
+                libfunc op = {libfunc};
+
+                op([0]) -> ([1]);
+                return([1]);
+
+                run_test@0([0]: felt252) -> (felt252);"
+            ))
+            .unwrap();
+
+        let module = NativeContext::new().compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        executor
+            .invoke_dynamic(&program.funcs[0].id, &[value], None)
+            .unwrap()
+            .return_value
+    }
+
+    #[test]
+    fn felt252_add_const() {
+        let r = |c: &str, x| run_const_operation(&format!("felt252_add_const<{c}>"), x);
+
+        assert_eq!(r("0", JitValue::felt_str("0")), JitValue::felt_str("0"));
+        assert_eq!(r("2", JitValue::felt_str("1")), JitValue::felt_str("3"));
+        assert_eq!(r("-1", JitValue::felt_str("1")), JitValue::felt_str("0"));
+        assert_eq!(r("1", JitValue::felt_str("-1")), JitValue::felt_str("0"));
+    }
+
+    #[test]
+    fn felt252_sub_const() {
+        let r = |c: &str, x| run_const_operation(&format!("felt252_sub_const<{c}>"), x);
+
+        assert_eq!(r("0", JitValue::felt_str("0")), JitValue::felt_str("0"));
+        assert_eq!(r("1", JitValue::felt_str("3")), JitValue::felt_str("2"));
+        assert_eq!(r("3", JitValue::felt_str("1")), JitValue::felt_str("-2"));
+        assert_eq!(r("-1", JitValue::felt_str("-1")), JitValue::felt_str("0"));
+    }
+
+    #[test]
+    fn felt252_mul_const() {
+        let r = |c: &str, x| run_const_operation(&format!("felt252_mul_const<{c}>"), x);
+
+        assert_eq!(r("0", JitValue::felt_str("5")), JitValue::felt_str("0"));
+        assert_eq!(r("3", JitValue::felt_str("2")), JitValue::felt_str("6"));
+        assert_eq!(r("-1", JitValue::felt_str("2")), JitValue::felt_str("-2"));
+        assert_eq!(r("-2", JitValue::felt_str("-1")), JitValue::felt_str("2"));
+    }
+
+    #[test]
+    fn felt252_div_const() {
+        let r = |c: &str, x| run_const_operation(&format!("felt252_div_const<{c}>"), x);
+
+        assert_eq!(r("1", JitValue::felt_str("5")), JitValue::felt_str("5"));
+        assert_eq!(r("2", JitValue::felt_str("6")), JitValue::felt_str("3"));
+        assert_eq!(r("-1", JitValue::felt_str("2")), JitValue::felt_str("-2"));
+        assert_eq!(
+            r("2", JitValue::felt_str("1")),
+            JitValue::felt_str(
+                "1809251394333065606848661391547535052811553607665798349986546028067936010241"
+            )
+        );
+    }
+
     #[test]
     fn felt252_const() {
         assert_eq!(
@@ -797,4 +931,19 @@ pub mod test {
         assert_eq!(r(JitValue::felt_str("-2")), JitValue::felt_str("0"));
         assert_eq!(r(JitValue::felt_str("-1")), JitValue::felt_str("0"));
     }
+
+    #[test]
+    fn felt252_is_zero_unwrap() {
+        let r = |x| run_program(&FELT252_IS_ZERO_UNWRAP, "run_test", &[x]).return_value;
+
+        assert_eq!(r(JitValue::felt_str("0")), jit_enum!(1, jit_struct!()));
+        assert_eq!(
+            r(JitValue::felt_str("1")),
+            jit_enum!(0, JitValue::felt_str("1"))
+        );
+        assert_eq!(
+            r(JitValue::felt_str("-1")),
+            jit_enum!(0, JitValue::felt_str("-1"))
+        );
+    }
 }
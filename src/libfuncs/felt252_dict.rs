@@ -112,7 +112,9 @@ pub fn build_squash<'ctx, 'this>(
 #[cfg(test)]
 mod test {
     use crate::{
-        utils::test::{jit_dict, jit_enum, jit_struct, load_cairo, run_program_assert_output},
+        utils::test::{
+            jit_dict, jit_enum, jit_struct, load_cairo, run_program, run_program_assert_output,
+        },
         values::JitValue,
     };
 
@@ -145,6 +147,101 @@ mod test {
         run_program_assert_output(&program, "run_test", &[], 1u32.into());
     }
 
+    #[test]
+    fn run_dict_many_keys() {
+        #[rustfmt::skip]
+        let program = load_cairo!(
+            use traits::Default;
+            use dict::Felt252DictTrait;
+
+            fn run_test() -> u32 {
+                let mut dict: Felt252Dict<u32> = Default::default();
+                let mut i = 0_u32;
+                loop {
+                    if i == 1000 {
+                        break;
+                    }
+                    dict.insert(i.into(), i);
+                    i += 1;
+                };
+
+                let mut sum = 0_u32;
+                let mut i = 0_u32;
+                loop {
+                    if i == 1001 {
+                        break;
+                    }
+                    sum += dict.get(i.into());
+                    i += 1;
+                };
+                sum
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            jit_enum!(0, jit_struct!(499500u32.into())),
+        );
+    }
+
+    #[test]
+    fn run_dict_default_and_squash() {
+        let program = load_cairo!(
+            use traits::Default;
+            use dict::Felt252DictTrait;
+
+            fn run_test(mut dict: Felt252Dict<u32>) -> (u32, u32, u32) {
+                let present = dict.get(1);
+                let missing = dict.get(7);
+                dict.insert(7, 8);
+                let inserted = dict.get(7);
+                let _squashed = dict.squash();
+                (present, missing, inserted)
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[jit_dict!(
+                1 => 2u32,
+                2 => 3u32,
+            )],
+            jit_struct!(2u32.into(), 0u32.into(), 8u32.into()),
+        );
+    }
+
+    #[test]
+    fn run_dict_across_functions() {
+        // The segment arena is threaded through the functions which create and squash the dict.
+        let program = load_cairo!(
+            use traits::Default;
+            use dict::{Felt252DictTrait, SquashedFelt252Dict};
+
+            #[inline(never)]
+            fn build_dict() -> Felt252Dict<u32> {
+                let mut dict: Felt252Dict<u32> = Default::default();
+                dict.insert(1, 2_u32);
+                dict
+            }
+
+            #[inline(never)]
+            fn squash_dict(dict: Felt252Dict<u32>) -> SquashedFelt252Dict<u32> {
+                dict.squash()
+            }
+
+            fn run_test() {
+                let _squashed = squash_dict(build_dict());
+            }
+        );
+
+        let result = run_program(&program, "run_test", &[]);
+        assert_eq!(result.return_value, jit_struct!());
+        assert_eq!(result.builtin_stats.segment_arena, 2);
+    }
+
     #[test]
     fn run_dict_insert_ret_dict() {
         let program = load_cairo!(
@@ -5,8 +5,8 @@ use crate::{
     block_ext::BlockExt,
     error::Result,
     metadata::{
-        realloc_bindings::ReallocBindingsMeta, runtime_bindings::RuntimeBindingsMeta,
-        MetadataStorage,
+        determinism::DeterminismMeta, realloc_bindings::ReallocBindingsMeta,
+        runtime_bindings::RuntimeBindingsMeta, MetadataStorage,
     },
     types::TypeBuilder,
     utils::{get_integer_layout, ProgramRegistryExt},
@@ -59,7 +59,7 @@ pub fn build_get<'ctx, 'this>(
     info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let (key_ty, key_layout) = registry.build_type_with_layout(
@@ -172,7 +172,8 @@ pub fn build_get<'ctx, 'this>(
 
     // construct the struct
 
-    let entry_value = block_final.append_op_result(llvm::undef(entry_ty, location))?;
+    let entry_value =
+        block_final.append_op_result(DeterminismMeta::build_undef(metadata, entry_ty, location))?;
 
     let value_ptr = block_final.argument(0)?.into();
     let value = block_final.argument(1)?.into();
@@ -190,12 +191,12 @@ pub fn build_get<'ctx, 'this>(
 
 pub fn build_finalize<'ctx, 'this>(
     context: &'ctx Context,
-    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
     metadata: &mut MetadataStorage,
-    _info: &SignatureAndTypeConcreteLibfunc,
+    info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
     let key_ty = IntegerType::new(context, 252).into();
     let key_layout = get_integer_layout(252);
@@ -221,6 +222,15 @@ pub fn build_finalize<'ctx, 'this>(
         2,
     )?;
 
+    let value_layout = registry.get_type(&info.ty)?.layout(registry)?;
+    DeterminismMeta::build_clear(
+        metadata,
+        context,
+        entry,
+        location,
+        value_ptr,
+        value_layout.pad_to_align().size(),
+    )?;
     entry.store(context, location, value_ptr, new_value)?;
 
     let key_ptr = helper
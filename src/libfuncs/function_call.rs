@@ -1,5 +1,15 @@
 //! # Function call libfuncs
 //!
+//! Calls follow the same convention as the function definitions generated by the compiler:
+//!   - Zero-sized builtins are neither passed nor returned. Their values are recreated after the
+//!     call, since they carry no data.
+//!   - Memory-allocated arguments are passed by pointer.
+//!   - Multiple return values are returned as a struct, except when there's a single
+//!     memory-allocated value which is written into a caller-provided return pointer instead.
+//!
+//! The `coupon_call` libfunc is lowered the same way. Its extra argument, the coupon, is a zero-sized
+//! builtin and therefore it's not passed either.
+//!
 //! Includes logic for handling direct tail recursive function calls. More information on this topic
 //! at the [tail recursive metadata](crate::metadata::tail_recursion).
 
@@ -7,7 +17,10 @@ use super::LibfuncHelper;
 use crate::{
     block_ext::BlockExt,
     error::Result,
-    metadata::{tail_recursion::TailRecursionMeta, MetadataStorage},
+    metadata::{
+        determinism::DeterminismMeta, overflow_checks::OverflowChecksMeta,
+        tail_recursion::TailRecursionMeta, MetadataStorage,
+    },
     types::TypeBuilder,
     utils::generate_function_name,
 };
@@ -67,24 +80,31 @@ pub fn build<'ctx, 'this>(
     }
 
     if let Some(tailrec_meta) = &mut tailrec_meta {
-        let depth_counter =
-            entry.append_op_result(memref::load(tailrec_meta.depth_counter(), &[], location))?;
+        // Calls whose results are returned unchanged (ex. Cairo loops) never have to be unwound,
+        // therefore they don't need to be counted.
+        if !tailrec_meta.forwards_results() {
+            let depth_counter = entry.append_op_result(memref::load(
+                tailrec_meta.depth_counter(),
+                &[],
+                location,
+            ))?;
 
-        let index1 = entry.append_op_result(index::constant(
-            context,
-            IntegerAttribute::new(Type::index(context), 1),
-            location,
-        ))?;
+            let index1 = entry.append_op_result(index::constant(
+                context,
+                IntegerAttribute::new(Type::index(context), 1),
+                location,
+            ))?;
 
-        let depth_counter_plus_1 =
-            entry.append_op_result(index::add(depth_counter, index1, location))?;
+            let depth_counter_plus_1 =
+                entry.append_op_result(index::add(depth_counter, index1, location))?;
 
-        entry.append_operation(memref::store(
-            depth_counter_plus_1,
-            tailrec_meta.depth_counter(),
-            &[],
-            location,
-        ));
+            entry.append_operation(memref::store(
+                depth_counter_plus_1,
+                tailrec_meta.depth_counter(),
+                &[],
+                location,
+            ));
+        }
 
         entry.append_operation(cf::br(
             &tailrec_meta.recursion_target(),
@@ -110,7 +130,11 @@ pub fn build<'ctx, 'this>(
                 })
                 .collect::<Vec<_>>(),
         ));
-        tailrec_meta.set_return_target(cont_block);
+        // The continuation block is unreachable when the results are forwarded, but it's still
+        // required to provide the branch's results.
+        if !tailrec_meta.forwards_results() {
+            tailrec_meta.set_return_target(cont_block);
+        }
 
         let mut results = Vec::<Value>::new();
         let mut count = 0;
@@ -118,10 +142,12 @@ pub fn build<'ctx, 'this>(
             let type_info = registry.get_type(&var_info.ty)?;
 
             if type_info.is_builtin() && type_info.is_zst(registry) {
-                results.push(cont_block.append_op_result(llvm::undef(
-                    type_info.build(context, helper, registry, metadata, &var_info.ty)?,
-                    location,
-                ))?);
+                let var_ty = type_info.build(context, helper, registry, metadata, &var_info.ty)?;
+                results.push(
+                    cont_block.append_op_result(DeterminismMeta::build_undef(
+                        metadata, var_ty, location,
+                    ))?,
+                );
             } else {
                 let val = cont_block.argument(count)?.into();
                 count += 1;
@@ -201,11 +227,15 @@ pub fn build<'ctx, 'this>(
                 // Manual return type.
 
                 let mut layout = Layout::new::<()>();
-                for (idx, type_id) in info.function.signature.ret_types.iter().enumerate() {
+                for type_id in &info.function.signature.ret_types {
                     let type_info = registry.get_type(type_id)?;
 
                     if type_info.is_builtin() && type_info.is_zst(registry) {
-                        results.push(entry.argument(idx)?.into());
+                        let ret_ty =
+                            type_info.build(context, helper, registry, metadata, type_id)?;
+                        results.push(entry.append_op_result(DeterminismMeta::build_undef(
+                            metadata, ret_ty, location,
+                        ))?);
                     } else {
                         let val = arguments[0];
 
@@ -216,7 +246,14 @@ pub fn build<'ctx, 'this>(
                         let pointer_val = entry.append_op_result(llvm::get_element_ptr(
                             context,
                             val,
-                            DenseI32ArrayAttribute::new(context, &[offset as i32]),
+                            DenseI32ArrayAttribute::new(
+                                context,
+                                &[OverflowChecksMeta::narrow(
+                                    metadata,
+                                    offset,
+                                    "a return value offset",
+                                )?],
+                            ),
                             IntegerType::new(context, 8).into(),
                             llvm::r#type::pointer(context, 0),
                             location,
@@ -236,11 +273,15 @@ pub fn build<'ctx, 'this>(
                 // handle the rest.
 
                 let mut count = 0;
-                for (idx, type_id) in info.function.signature.ret_types.iter().enumerate() {
+                for type_id in &info.function.signature.ret_types {
                     let type_info = registry.get_type(type_id)?;
 
                     if type_info.is_builtin() && type_info.is_zst(registry) {
-                        results.push(entry.argument(idx)?.into());
+                        let ret_ty =
+                            type_info.build(context, helper, registry, metadata, type_id)?;
+                        results.push(entry.append_op_result(DeterminismMeta::build_undef(
+                            metadata, ret_ty, location,
+                        ))?);
                     } else {
                         let val = function_call_result.result(count)?.into();
                         count += 1;
@@ -253,12 +294,16 @@ pub fn build<'ctx, 'this>(
                 // Returned data is simple.
 
                 let mut count = 0;
-                for (idx, type_id) in info.function.signature.ret_types.iter().enumerate() {
+                for type_id in &info.function.signature.ret_types {
                     let type_info = registry.get_type(type_id)?;
                     assert!(!type_info.is_memory_allocated(registry));
 
                     if type_info.is_builtin() && type_info.is_zst(registry) {
-                        results.push(entry.argument(idx)?.into());
+                        let ret_ty =
+                            type_info.build(context, helper, registry, metadata, type_id)?;
+                        results.push(entry.append_op_result(DeterminismMeta::build_undef(
+                            metadata, ret_ty, location,
+                        ))?);
                     } else {
                         let value = function_call_result.result(count)?.into();
                         count += 1;
@@ -278,3 +323,46 @@ pub fn build<'ctx, 'this>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        utils::test::{jit_enum, jit_struct, load_cairo, run_program_assert_output},
+        values::JitValue,
+    };
+
+    #[test]
+    fn function_call_returns() {
+        let program = load_cairo! {
+            #[inline(never)]
+            fn swap(a: felt252, b: u256) -> (u256, felt252) {
+                (b, a)
+            }
+
+            #[inline(never)]
+            fn wrap(value: u16) -> Option<u16> {
+                Option::Some(value)
+            }
+
+            fn run_test(a: felt252, b: u256, c: u16) -> (u256, felt252, Option<u16>) {
+                let (b, a) = swap(a, b);
+                (b, a, wrap(c))
+            }
+        };
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[
+                JitValue::Felt252(7.into()),
+                jit_struct!(JitValue::Uint128(1), JitValue::Uint128(2)),
+                JitValue::Uint16(3),
+            ],
+            jit_struct!(
+                jit_struct!(JitValue::Uint128(1), JitValue::Uint128(2)),
+                JitValue::Felt252(7.into()),
+                jit_enum!(0, JitValue::Uint16(3)),
+            ),
+        );
+    }
+}
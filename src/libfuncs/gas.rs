@@ -4,7 +4,7 @@ use super::LibfuncHelper;
 use crate::{
     block_ext::BlockExt,
     error::Result,
-    metadata::{gas::GasCost, MetadataStorage},
+    metadata::{determinism::DeterminismMeta, gas::GasCost, MetadataStorage},
     utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
@@ -39,7 +39,9 @@ pub fn build<'ctx, 'this>(
         GasConcreteLibfunc::WithdrawGas(info) => {
             build_withdraw_gas(context, registry, entry, location, helper, metadata, info)
         }
-        GasConcreteLibfunc::RedepositGas(_) => todo!("implement redeposit gas libfunc"),
+        GasConcreteLibfunc::RedepositGas(info) => {
+            build_redeposit_gas(context, registry, entry, location, helper, metadata, info)
+        }
         GasConcreteLibfunc::GetAvailableGas(info) => {
             build_get_available_gas(context, registry, entry, location, helper, metadata, info)
         }
@@ -52,7 +54,7 @@ pub fn build<'ctx, 'this>(
     }
 }
 
-/// Generate MLIR operations for the `get_builtin_costs` libfunc.
+/// Generate MLIR operations for the `get_available_gas` libfunc.
 pub fn build_get_available_gas<'ctx, 'this>(
     _context: &'ctx Context,
     _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
@@ -84,11 +86,14 @@ pub fn build_withdraw_gas<'ctx, 'this>(
         super::increment_builtin_counter(context, entry, location, entry.argument(0)?.into())?;
     let current_gas = entry.argument(1)?.into();
 
-    let cost = metadata.get::<GasCost>().and_then(|x| x.0);
+    // Programs compiled without gas costs (infinite gas mode) never run out of gas.
+    let Some(cost) = metadata.get::<GasCost>().and_then(|x| x.0) else {
+        entry.append_operation(helper.br(0, &[range_check, current_gas], location));
+        return Ok(());
+    };
 
     let u128_type: melior::ir::Type = IntegerType::new(context, 128).into();
-    let gas_cost_val =
-        entry.const_int_from_type(context, location, cost.unwrap_or(0), u128_type)?;
+    let gas_cost_val = entry.const_int_from_type(context, location, cost, u128_type)?;
 
     let is_enough = entry.append_op_result(arith::cmpi(
         context,
@@ -113,6 +118,34 @@ pub fn build_withdraw_gas<'ctx, 'this>(
     Ok(())
 }
 
+/// Generate MLIR operations for the `redeposit_gas` libfunc.
+pub fn build_redeposit_gas<'ctx, 'this>(
+    context: &'ctx Context,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &MetadataStorage,
+    _info: &SignatureOnlyConcreteLibfunc,
+) -> Result<()> {
+    let current_gas = entry.argument(0)?.into();
+
+    // Programs compiled without gas costs (infinite gas mode) have nothing to give back.
+    let Some(cost) = metadata.get::<GasCost>().and_then(|x| x.0) else {
+        entry.append_operation(helper.br(0, &[current_gas], location));
+        return Ok(());
+    };
+
+    // The statement's cost is the amount that was withdrawn in excess and can be returned. It never
+    // overflows since it's bounded by the gas that was available before withdrawing it.
+    let u128_type: melior::ir::Type = IntegerType::new(context, 128).into();
+    let gas_cost_val = entry.const_int_from_type(context, location, cost, u128_type)?;
+    let resulting_gas = entry.append_op_result(arith::addi(current_gas, gas_cost_val, location))?;
+
+    entry.append_operation(helper.br(0, &[resulting_gas], location));
+    Ok(())
+}
+
 /// Generate MLIR operations for the `withdraw_gas_all` libfunc.
 pub fn build_builtin_withdraw_gas<'ctx, 'this>(
     context: &'ctx Context,
@@ -127,11 +160,14 @@ pub fn build_builtin_withdraw_gas<'ctx, 'this>(
         super::increment_builtin_counter(context, entry, location, entry.argument(0)?.into())?;
     let current_gas = entry.argument(1)?.into();
 
-    let cost = metadata.get::<GasCost>().and_then(|x| x.0);
+    // Programs compiled without gas costs (infinite gas mode) never run out of gas.
+    let Some(cost) = metadata.get::<GasCost>().and_then(|x| x.0) else {
+        entry.append_operation(helper.br(0, &[range_check, current_gas], location));
+        return Ok(());
+    };
 
     let u128_type: melior::ir::Type = IntegerType::new(context, 128).into();
-    let gas_cost_val =
-        entry.const_int_from_type(context, location, cost.unwrap_or(0), u128_type)?;
+    let gas_cost_val = entry.const_int_from_type(context, location, cost, u128_type)?;
 
     let is_enough = entry.append_op_result(arith::cmpi(
         context,
@@ -174,8 +210,13 @@ pub fn build_get_builtin_costs<'ctx, 'this>(
         &info.branch_signatures()[0].vars[0].ty,
     )?;
 
-    // TODO: Implement libfunc.
-    let op0 = entry.append_op_result(llvm::undef(builtin_costs_ty, location))?;
+    // Every builtin's cost is already known at compile time and included in each statement's
+    // `GasCost`, therefore the cost table is a zero-sized value that carries no data.
+    let op0 = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        builtin_costs_ty,
+        location,
+    ))?;
 
     entry.append_operation(helper.br(0, &[op0], location));
 
@@ -184,7 +225,16 @@ pub fn build_get_builtin_costs<'ctx, 'this>(
 
 #[cfg(test)]
 mod test {
-    use crate::utils::test::{load_cairo, run_program};
+    use crate::{
+        context::{CompilerConfig, NativeContext},
+        error::Error,
+        executor::JitNativeExecutor,
+        metadata::gas::GasMetadataError,
+        starknet_stub::StubSyscallHandler,
+        utils::test::{load_cairo, run_program},
+        values::JitValue,
+        OptLevel,
+    };
 
     #[test]
     fn run_withdraw_gas() {
@@ -219,4 +269,148 @@ mod test {
             Some(340282366920938463463374607431768204835),
         );
     }
+
+    #[test]
+    fn run_withdraw_gas_infinite() {
+        #[rustfmt::skip]
+        let program = load_cairo!(
+            use gas::withdraw_gas;
+
+            fn run_test() -> felt252 {
+                let mut i = 0;
+
+                loop {
+                    if i == 1000 {
+                        break;
+                    }
+
+                    match withdraw_gas() {
+                        Option::Some(()) => {
+                            i = i + 1;
+                        },
+                        Option::None(()) => {
+                            break;
+                        }
+                    };
+                }
+
+                i
+            }
+        );
+
+        let entry_point = format!("{0}::{0}::run_test", program.0);
+        let entry_point_id = &program
+            .1
+            .funcs
+            .iter()
+            .find(|x| x.id.debug_name.as_deref() == Some(&entry_point))
+            .unwrap()
+            .id;
+
+        let module = NativeContext::new()
+            .with_config(CompilerConfig::new().with_infinite_gas(true))
+            .compile(&program.1, None)
+            .unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+
+        // No initial gas is supplied, yet every withdrawal succeeds.
+        let result = executor
+            .invoke_dynamic_with_syscall_handler(
+                entry_point_id,
+                &[],
+                None,
+                &mut StubSyscallHandler::default(),
+            )
+            .unwrap();
+        assert_eq!(result.return_value, JitValue::Felt252(1000.into()));
+        assert_eq!(result.remaining_gas, Some(0));
+    }
+
+    #[test]
+    fn run_withdraw_gas_initial_required() {
+        #[rustfmt::skip]
+        let program = load_cairo!(
+            use gas::withdraw_gas;
+
+            fn run_test() {
+                withdraw_gas().unwrap();
+            }
+        );
+
+        let entry_point = format!("{0}::{0}::run_test", program.0);
+        let entry_point_id = &program
+            .1
+            .funcs
+            .iter()
+            .find(|x| x.id.debug_name.as_deref() == Some(&entry_point))
+            .unwrap()
+            .id;
+
+        let module = NativeContext::new().compile(&program.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+
+        let required_gas = executor.initial_required_gas(entry_point_id).unwrap();
+        assert!(required_gas > 0);
+
+        let result = executor
+            .invoke_dynamic(entry_point_id, &[], Some(required_gas + 1000))
+            .unwrap();
+        assert!(result.remaining_gas.unwrap() <= 1000);
+
+        let error = executor
+            .invoke_dynamic(entry_point_id, &[], Some(required_gas - 1))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::GasMetadataError(GasMetadataError::NotEnoughGas { .. }),
+        ));
+        assert!(error.is_out_of_gas());
+    }
+
+    #[test]
+    fn run_out_of_gas() {
+        let program = load_cairo!(
+            fn run_test(n: felt252) -> felt252 {
+                if n == 0 {
+                    0
+                } else {
+                    run_test(n - 1) + 1
+                }
+            }
+        );
+
+        let entry_point = format!("{0}::{0}::run_test", program.0);
+        let entry_point_id = &program
+            .1
+            .funcs
+            .iter()
+            .find(|x| x.id.debug_name.as_deref() == Some(&entry_point))
+            .unwrap()
+            .id;
+
+        let module = NativeContext::new().compile(&program.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let required_gas = executor.initial_required_gas(entry_point_id).unwrap();
+
+        let result = executor
+            .invoke_dynamic(
+                entry_point_id,
+                &[JitValue::Felt252(10.into())],
+                Some(u128::MAX),
+            )
+            .unwrap();
+        assert_eq!(result.into_result().unwrap(), JitValue::Felt252(10.into()));
+
+        // Enough gas to call the function, but not to recurse 10 times.
+        let error = executor
+            .invoke_dynamic(
+                entry_point_id,
+                &[JitValue::Felt252(10.into())],
+                Some(required_gas),
+            )
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+        assert!(error.is_out_of_gas(), "{error}");
+    }
 }
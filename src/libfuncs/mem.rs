@@ -5,7 +5,10 @@
 
 use super::LibfuncHelper;
 use crate::{
-    block_ext::BlockExt, error::Result, metadata::MetadataStorage, utils::ProgramRegistryExt,
+    block_ext::BlockExt,
+    error::Result,
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -17,7 +20,6 @@ use cairo_lang_sierra::{
     program_registry::ProgramRegistry,
 };
 use melior::{
-    dialect::llvm,
     ir::{Block, Location},
     Context,
 };
@@ -84,7 +86,11 @@ pub fn build_alloc_local<'ctx, 'this>(
         &info.branch_signatures()[0].vars[0].ty,
     )?;
 
-    let value_undef = entry.append_op_result(llvm::undef(target_type, location))?;
+    let value_undef = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        target_type,
+        location,
+    ))?;
 
     entry.append_operation(helper.br(0, &[value_undef], location));
 
@@ -8,10 +8,7 @@ use crate::{error::Result, metadata::MetadataStorage};
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
-        lib_func::{
-            BranchSignature, LibfuncSignature, SignatureAndTypeConcreteLibfunc,
-            SignatureOnlyConcreteLibfunc,
-        },
+        lib_func::{SignatureAndTypeConcreteLibfunc, SignatureOnlyConcreteLibfunc},
         nullable::NullableConcreteLibfunc,
     },
     program_registry::ProgramRegistry,
@@ -133,38 +130,21 @@ fn build_match_nullable<'ctx, 'this>(
     Ok(())
 }
 
+/// Generate MLIR operations for the `nullable_forward_snapshot` libfunc.
+///
+/// A snapshot of a nullable has the same layout as a nullable of a snapshot, therefore the value
+/// is just moved.
 fn build_forward_snapshot<'ctx, 'this>(
-    context: &'ctx Context,
-    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    _context: &'ctx Context,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
-    metadata: &mut MetadataStorage,
-    info: &SignatureAndTypeConcreteLibfunc,
+    _metadata: &mut MetadataStorage,
+    _info: &SignatureAndTypeConcreteLibfunc,
 ) -> Result<()> {
-    super::snapshot_take::build(
-        context,
-        registry,
-        entry,
-        location,
-        helper,
-        metadata,
-        &SignatureOnlyConcreteLibfunc {
-            signature: LibfuncSignature {
-                param_signatures: info.signature.param_signatures.clone(),
-                branch_signatures: info
-                    .signature
-                    .branch_signatures
-                    .iter()
-                    .map(|x| BranchSignature {
-                        vars: x.vars.clone(),
-                        ap_change: x.ap_change.clone(),
-                    })
-                    .collect(),
-                fallthrough: info.signature.fallthrough,
-            },
-        },
-    )
+    entry.append_operation(helper.br(0, &[entry.argument(0)?.into()], location));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -237,6 +217,37 @@ mod test {
         run_program_assert_output(&program, "run_test", &[0u8.into()], 99u8.into());
     }
 
+    #[test]
+    fn run_dict_of_nullable() {
+        let program = load_cairo! {
+            use core::dict::Felt252DictTrait;
+            use core::nullable::{match_nullable, FromNullableResult, NullableTrait};
+
+            fn run_test() -> (u32, felt252) {
+                let mut dict: Felt252Dict<Nullable<Span<felt252>>> = Default::default();
+                dict.insert(0, NullableTrait::new(array![1, 2, 3].span()));
+                dict.insert(1, NullableTrait::new(array![4].span()));
+
+                let first = dict.get(0).deref();
+                let missing = match match_nullable(dict.get(2)) {
+                    FromNullableResult::Null(_) => 0,
+                    FromNullableResult::NotNull(_) => 1,
+                };
+                (first.len(), *dict.get(1).deref()[0] + missing)
+            }
+        };
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            jit_enum!(
+                0,
+                jit_struct!(jit_struct!(3u32.into(), JitValue::Felt252(4.into())))
+            ),
+        );
+    }
+
     #[test]
     fn match_snapshot_nullable_clone_bug() {
         let program = load_cairo! {
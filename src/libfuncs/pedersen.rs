@@ -40,6 +40,9 @@ pub fn build<'ctx, 'this>(
     }
 }
 
+/// Generate MLIR operations for the `pedersen` libfunc.
+///
+/// The hash is computed by the runtime library, which expects its operands in big endian.
 pub fn build_pedersen<'ctx>(
     context: &'ctx Context,
     registry: &ProgramRegistry<CoreType, CoreLibfunc>,
@@ -49,10 +52,6 @@ pub fn build_pedersen<'ctx>(
     metadata: &mut MetadataStorage,
     info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
-    metadata
-        .get_mut::<RuntimeBindingsMeta>()
-        .expect("Runtime library not available.");
-
     let pedersen_builtin =
         super::increment_builtin_counter(context, entry, location, entry.argument(0)?.into())?;
 
@@ -110,7 +109,10 @@ pub fn build_pedersen<'ctx>(
 
 #[cfg(test)]
 mod test {
-    use crate::utils::test::{load_cairo, run_program_assert_output};
+    use crate::{
+        utils::test::{load_cairo, run_program, run_program_assert_output},
+        values::JitValue,
+    };
 
     use starknet_types_core::felt::Felt;
 
@@ -135,4 +137,44 @@ mod test {
             .into(),
         );
     }
+
+    #[test]
+    fn run_pedersen_full_width() {
+        let program = load_cairo!(
+            use core::pedersen::pedersen;
+
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                pedersen(a, b)
+            }
+        );
+
+        // Test vectors from `starknet-crypto`, which use the whole 252 bits of the operands.
+        let test_data = [
+            (
+                "0x03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb",
+                "0x0208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a",
+                "0x030e480bed5fe53fa909cc0f8c4d99b8f9f2c016be4c41e13a4848797979c662",
+            ),
+            (
+                "0x058f580910a6ca59b28927c08fe6c43e2e303ca384badc365795fc645d479d45",
+                "0x078734f65a067be9bdb39de18434d71e79f7b6466a4b66bbd979ab9e7515fe0b",
+                "0x068cc0b76cddd1dd4ed2301ada9b7c872b23875d5ff837b3a87993e0d9996b87",
+            ),
+        ];
+
+        for (lhs, rhs, expected) in test_data {
+            let result = run_program(
+                &program,
+                "run_test",
+                &[
+                    Felt::from_hex(lhs).unwrap().into(),
+                    Felt::from_hex(rhs).unwrap().into(),
+                ],
+            );
+            assert_eq!(
+                result.return_value,
+                JitValue::Felt252(Felt::from_hex(expected).unwrap()),
+            );
+        }
+    }
 }
@@ -249,7 +249,30 @@ pub fn build_to_felt252<'ctx, 'this>(
     )?;
     let value: Value = entry.argument(0)?.into();
 
-    let result = entry.append_op_result(arith::extui(value, felt252_ty, location))?;
+    // Negative values are sign-extended, which yields `2^252 - |value|`. Adding the prime (with
+    // wrapping arithmetic) then results in `PRIME - |value|`, which is the felt representation.
+    let is_negative = entry.append_op_result(arith::cmpi(
+        context,
+        CmpiPredicate::Slt,
+        value,
+        entry.const_int_from_type(context, location, 0, value.r#type())?,
+        location,
+    ))?;
+
+    let extended = entry.append_op_result(arith::extsi(value, felt252_ty, location))?;
+    let prime = entry.const_int_from_type(
+        context,
+        location,
+        metadata
+            .get::<PrimeModuloMeta<Felt>>()
+            .ok_or(Error::MissingMetadata)?
+            .prime()
+            .clone(),
+        felt252_ty,
+    )?;
+    let wrapped = entry.append_op_result(arith::addi(extended, prime, location))?;
+
+    let result = entry.append_op_result(arith::select(is_negative, wrapped, extended, location))?;
 
     entry.append_operation(helper.br(0, &[result], location));
 
@@ -453,6 +476,13 @@ mod test {
                 lhs == rhs
             }
         };
+        static ref I128_DIFF: (String, Program) = load_cairo! {
+            extern fn i128_diff(lhs: i128, rhs: i128) -> Result<u128, u128> implicits(RangeCheck) nopanic;
+
+            fn run_test(lhs: i128, rhs: i128) -> Result<u128, u128> {
+                i128_diff(lhs, rhs)
+            }
+        };
         static ref I128_IS_ZERO: (String, Program) = load_cairo! {
             use zeroable::IsZeroResult;
 
@@ -502,6 +532,70 @@ mod test {
         run_program_assert_output(&program, "run_test", &[], Felt::from(2).into());
     }
 
+    #[test]
+    fn i128_to_felt252_negative() {
+        let program = load_cairo!(
+            use traits::Into;
+
+            fn run_test() -> (felt252, felt252) {
+                (
+                    (-2_i128).into(),
+                    (-170141183460469231731687303715884105728_i128).into(),
+                )
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            jit_struct!(Felt::from(-2i128).into(), Felt::from(i128::MIN).into()),
+        );
+    }
+
+    #[test]
+    fn i128_from_felt252_negative() {
+        let program = load_cairo!(
+            use traits::TryInto;
+
+            fn run_test() -> (Option<i128>, Option<i128>) {
+                (
+                    (-170141183460469231731687303715884105728).try_into(),
+                    (-170141183460469231731687303715884105729).try_into(),
+                )
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            jit_struct!(jit_enum!(0, i128::MIN.into()), jit_enum!(1, jit_struct!())),
+        );
+    }
+
+    #[test]
+    fn i128_diff() {
+        #[track_caller]
+        fn run(lhs: i128, rhs: i128) {
+            let result = lhs.wrapping_sub(rhs) as u128;
+            run_program_assert_output(
+                &I128_DIFF,
+                "run_test",
+                &[lhs.into(), rhs.into()],
+                jit_enum!(if lhs >= rhs { 0 } else { 1 }, result.into()),
+            );
+        }
+
+        run(0, 0);
+        run(1, 0);
+        run(0, 1);
+        run(-1, 1);
+        run(1, -1);
+        run(i128::MAX, i128::MIN);
+        run(i128::MIN, i128::MAX);
+    }
+
     #[test]
     fn i128_from_felt252() {
         let program = load_cairo!(
@@ -578,6 +672,50 @@ mod test {
         run(MAX, MAX);
     }
 
+    #[test]
+    fn i128_overflowing_branch_values() {
+        let program = load_cairo!(
+            enum SignedIntegerResult<T> {
+                InRange: T,
+                Underflow: T,
+                Overflow: T,
+            }
+
+            extern fn i128_overflowing_add_impl(lhs: i128, rhs: i128) -> SignedIntegerResult<i128> implicits(RangeCheck) nopanic;
+            extern fn i128_overflowing_sub_impl(lhs: i128, rhs: i128) -> SignedIntegerResult<i128> implicits(RangeCheck) nopanic;
+
+            fn run_test(lhs: i128, rhs: i128) -> (SignedIntegerResult<i128>, SignedIntegerResult<i128>) {
+                (i128_overflowing_add_impl(lhs, rhs), i128_overflowing_sub_impl(lhs, rhs))
+            }
+        );
+
+        // Every branch returns the result, wrapped around when out of range.
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[1i128.into(), 2i128.into()],
+            jit_struct!(jit_enum!(0, 3i128.into()), jit_enum!(0, (-1i128).into())),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i128::MAX.into(), (-1i128).into()],
+            jit_struct!(
+                jit_enum!(0, (i128::MAX - 1).into()),
+                jit_enum!(2, i128::MIN.into())
+            ),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i128::MIN.into(), 1i128.into()],
+            jit_struct!(
+                jit_enum!(0, (i128::MIN + 1).into()),
+                jit_enum!(1, i128::MAX.into())
+            ),
+        );
+    }
+
     #[test]
     fn i128_overflowing_sub() {
         #[track_caller]
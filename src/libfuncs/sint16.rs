@@ -608,6 +608,50 @@ mod test {
         run(MAX, MAX);
     }
 
+    #[test]
+    fn i16_overflowing_branch_values() {
+        let program = load_cairo!(
+            enum SignedIntegerResult<T> {
+                InRange: T,
+                Underflow: T,
+                Overflow: T,
+            }
+
+            extern fn i16_overflowing_add_impl(lhs: i16, rhs: i16) -> SignedIntegerResult<i16> implicits(RangeCheck) nopanic;
+            extern fn i16_overflowing_sub_impl(lhs: i16, rhs: i16) -> SignedIntegerResult<i16> implicits(RangeCheck) nopanic;
+
+            fn run_test(lhs: i16, rhs: i16) -> (SignedIntegerResult<i16>, SignedIntegerResult<i16>) {
+                (i16_overflowing_add_impl(lhs, rhs), i16_overflowing_sub_impl(lhs, rhs))
+            }
+        );
+
+        // Every branch returns the result, wrapped around when out of range.
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[1i16.into(), 2i16.into()],
+            jit_struct!(jit_enum!(0, 3i16.into()), jit_enum!(0, (-1i16).into())),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i16::MAX.into(), (-1i16).into()],
+            jit_struct!(
+                jit_enum!(0, (i16::MAX - 1).into()),
+                jit_enum!(2, i16::MIN.into())
+            ),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i16::MIN.into(), 1i16.into()],
+            jit_struct!(
+                jit_enum!(0, (i16::MIN + 1).into()),
+                jit_enum!(1, i16::MAX.into())
+            ),
+        );
+    }
+
     #[test]
     fn i16_overflowing_sub() {
         #[track_caller]
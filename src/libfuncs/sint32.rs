@@ -611,6 +611,50 @@ mod test {
         run(MAX, MAX);
     }
 
+    #[test]
+    fn i32_overflowing_branch_values() {
+        let program = load_cairo!(
+            enum SignedIntegerResult<T> {
+                InRange: T,
+                Underflow: T,
+                Overflow: T,
+            }
+
+            extern fn i32_overflowing_add_impl(lhs: i32, rhs: i32) -> SignedIntegerResult<i32> implicits(RangeCheck) nopanic;
+            extern fn i32_overflowing_sub_impl(lhs: i32, rhs: i32) -> SignedIntegerResult<i32> implicits(RangeCheck) nopanic;
+
+            fn run_test(lhs: i32, rhs: i32) -> (SignedIntegerResult<i32>, SignedIntegerResult<i32>) {
+                (i32_overflowing_add_impl(lhs, rhs), i32_overflowing_sub_impl(lhs, rhs))
+            }
+        );
+
+        // Every branch returns the result, wrapped around when out of range.
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[1i32.into(), 2i32.into()],
+            jit_struct!(jit_enum!(0, 3i32.into()), jit_enum!(0, (-1i32).into())),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i32::MAX.into(), (-1i32).into()],
+            jit_struct!(
+                jit_enum!(0, (i32::MAX - 1).into()),
+                jit_enum!(2, i32::MIN.into())
+            ),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i32::MIN.into(), 1i32.into()],
+            jit_struct!(
+                jit_enum!(0, (i32::MIN + 1).into()),
+                jit_enum!(1, i32::MAX.into())
+            ),
+        );
+    }
+
     #[test]
     fn i32_overflowing_sub() {
         #[track_caller]
@@ -614,6 +614,50 @@ mod test {
         run(MAX, MAX);
     }
 
+    #[test]
+    fn i64_overflowing_branch_values() {
+        let program = load_cairo!(
+            enum SignedIntegerResult<T> {
+                InRange: T,
+                Underflow: T,
+                Overflow: T,
+            }
+
+            extern fn i64_overflowing_add_impl(lhs: i64, rhs: i64) -> SignedIntegerResult<i64> implicits(RangeCheck) nopanic;
+            extern fn i64_overflowing_sub_impl(lhs: i64, rhs: i64) -> SignedIntegerResult<i64> implicits(RangeCheck) nopanic;
+
+            fn run_test(lhs: i64, rhs: i64) -> (SignedIntegerResult<i64>, SignedIntegerResult<i64>) {
+                (i64_overflowing_add_impl(lhs, rhs), i64_overflowing_sub_impl(lhs, rhs))
+            }
+        );
+
+        // Every branch returns the result, wrapped around when out of range.
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[1i64.into(), 2i64.into()],
+            jit_struct!(jit_enum!(0, 3i64.into()), jit_enum!(0, (-1i64).into())),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i64::MAX.into(), (-1i64).into()],
+            jit_struct!(
+                jit_enum!(0, (i64::MAX - 1).into()),
+                jit_enum!(2, i64::MIN.into())
+            ),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i64::MIN.into(), 1i64.into()],
+            jit_struct!(
+                jit_enum!(0, (i64::MIN + 1).into()),
+                jit_enum!(1, i64::MAX.into())
+            ),
+        );
+    }
+
     #[test]
     fn i64_overflowing_sub() {
         #[track_caller]
@@ -610,6 +610,50 @@ mod test {
         run(MAX, MAX);
     }
 
+    #[test]
+    fn i8_overflowing_branch_values() {
+        let program = load_cairo!(
+            enum SignedIntegerResult<T> {
+                InRange: T,
+                Underflow: T,
+                Overflow: T,
+            }
+
+            extern fn i8_overflowing_add_impl(lhs: i8, rhs: i8) -> SignedIntegerResult<i8> implicits(RangeCheck) nopanic;
+            extern fn i8_overflowing_sub_impl(lhs: i8, rhs: i8) -> SignedIntegerResult<i8> implicits(RangeCheck) nopanic;
+
+            fn run_test(lhs: i8, rhs: i8) -> (SignedIntegerResult<i8>, SignedIntegerResult<i8>) {
+                (i8_overflowing_add_impl(lhs, rhs), i8_overflowing_sub_impl(lhs, rhs))
+            }
+        );
+
+        // Every branch returns the result, wrapped around when out of range.
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[1i8.into(), 2i8.into()],
+            jit_struct!(jit_enum!(0, 3i8.into()), jit_enum!(0, (-1i8).into())),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i8::MAX.into(), (-1i8).into()],
+            jit_struct!(
+                jit_enum!(0, (i8::MAX - 1).into()),
+                jit_enum!(2, i8::MIN.into())
+            ),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[i8::MIN.into(), 1i8.into()],
+            jit_struct!(
+                jit_enum!(0, (i8::MIN + 1).into()),
+                jit_enum!(1, i8::MAX.into())
+            ),
+        );
+    }
+
     #[test]
     fn i8_overflowing_sub() {
         #[track_caller]
@@ -3989,6 +3989,20 @@ mod test {
                 storage_address_try_from_felt252(value)
             }
         };
+        static ref CONTRACT_ADDRESS_TRY_FROM_FELT252: (String, Program) = load_cairo! {
+            use starknet::contract_address::{ContractAddress, contract_address_try_from_felt252};
+
+            fn run_program(value: felt252) -> Option<ContractAddress> {
+                contract_address_try_from_felt252(value)
+            }
+        };
+        static ref CLASS_HASH_TRY_FROM_FELT252: (String, Program) = load_cairo! {
+            use starknet::class_hash::{ClassHash, class_hash_try_from_felt252};
+
+            fn run_program(value: felt252) -> Option<ClassHash> {
+                class_hash_try_from_felt252(value)
+            }
+        };
         static ref CLASS_HASH_CONST: (String, Program) = load_cairo! {
             use starknet::class_hash::{class_hash_const, ClassHash};
 
@@ -4003,6 +4017,37 @@ mod test {
         run_program_assert_output(&CLASS_HASH_CONST, "run_program", &[], Felt::ZERO.into())
     }
 
+    #[test]
+    fn address_try_from_felt252() {
+        // Contract addresses and class hashes must be smaller than 2**251.
+        let limit = Felt::from_dec_str(
+            "3618502788666131106986593281521497120414687020801267626233049500247285301248",
+        )
+        .unwrap();
+
+        for program in [
+            &*CONTRACT_ADDRESS_TRY_FROM_FELT252,
+            &*CLASS_HASH_TRY_FROM_FELT252,
+        ] {
+            for value in [Felt::ZERO, Felt::ONE, limit - Felt::ONE] {
+                run_program_assert_output(
+                    program,
+                    "run_program",
+                    &[value.into()],
+                    jit_enum!(0, value.into()),
+                );
+            }
+            for value in [limit, Felt::from(-1)] {
+                run_program_assert_output(
+                    program,
+                    "run_program",
+                    &[value.into()],
+                    jit_enum!(1, jit_struct!()),
+                );
+            }
+        }
+    }
+
     #[test]
     fn storage_base_address_from_felt252() {
         run_program_assert_output(
@@ -1,9 +1,16 @@
 //! # Struct-related libfuncs
+//!
+//! Structs are lowered into LLVM structs with the fields' types (check out
+//! [the struct type](crate::types::struct) for more information). Zero-sized fields are never
+//! inserted nor extracted since they don't hold any data: their values are just `llvm.undef`
+//! (zeroes when the [determinism audit](crate::determinism) is enabled).
 
 use super::LibfuncHelper;
 use crate::block_ext::BlockExt;
 use crate::{
-    error::Result, metadata::MetadataStorage, types::TypeBuilder, utils::ProgramRegistryExt,
+    error::Result,
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    types::TypeBuilder,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -16,7 +23,6 @@ use cairo_lang_sierra::{
     program_registry::ProgramRegistry,
 };
 use melior::{
-    dialect::llvm,
     ir::{Block, Location, Value},
     Context,
 };
@@ -52,11 +58,9 @@ pub fn build_construct<'ctx, 'this>(
     metadata: &mut MetadataStorage,
     info: &SignatureOnlyConcreteLibfunc,
 ) -> Result<()> {
-    let mut fields = Vec::new();
-
-    for (i, _) in info.param_signatures().iter().enumerate() {
-        fields.push(entry.argument(i).unwrap().into());
-    }
+    let fields = (0..info.param_signatures().len())
+        .map(|i| Ok(entry.argument(i)?.into()))
+        .collect::<Result<Vec<_>>>()?;
 
     let value = build_struct_value(
         context,
@@ -74,7 +78,7 @@ pub fn build_construct<'ctx, 'this>(
     Ok(())
 }
 
-/// Generate MLIR operations for the `struct_construct` libfunc.
+/// Build a struct value from its fields, skipping the zero-sized ones.
 #[allow(clippy::too_many_arguments)]
 pub fn build_struct_value<'ctx, 'this>(
     context: &'ctx Context,
@@ -86,11 +90,22 @@ pub fn build_struct_value<'ctx, 'this>(
     struct_type: &ConcreteTypeId,
     fields: &[Value<'ctx, 'this>],
 ) -> Result<Value<'ctx, 'this>> {
-    let struct_ty = registry.build_type(context, helper, registry, metadata, struct_type)?;
+    let type_info = registry.get_type(struct_type)?;
+    let struct_ty = type_info.build(context, helper, registry, metadata, struct_type)?;
+
+    let mut value =
+        entry.append_op_result(DeterminismMeta::build_undef(metadata, struct_ty, location))?;
+    if type_info.is_zst(registry) {
+        return Ok(value);
+    }
 
-    let acc = entry.append_operation(llvm::undef(struct_ty, location));
+    for (i, (field, field_type)) in fields.iter().zip(type_info.fields().unwrap()).enumerate() {
+        if !registry.get_type(field_type)?.is_zst(registry) {
+            value = entry.insert_value(context, location, value, *field, i)?;
+        }
+    }
 
-    entry.insert_values(context, location, acc.result(0)?.into(), fields)
+    Ok(value)
 }
 
 /// Generate MLIR operations for the `struct_deconstruct` libfunc.
@@ -110,7 +125,11 @@ pub fn build_deconstruct<'ctx, 'this>(
         let type_info = registry.get_type(&var_info.ty)?;
         let field_ty = type_info.build(context, helper, registry, metadata, &var_info.ty)?;
 
-        let value = entry.extract_value(context, location, container, field_ty, i)?;
+        let value = if type_info.is_zst(registry) {
+            entry.append_op_result(DeterminismMeta::build_undef(metadata, field_ty, location))?
+        } else {
+            entry.extract_value(context, location, container, field_ty, i)?
+        };
 
         fields.push(value);
     }
@@ -119,3 +138,89 @@ pub fn build_deconstruct<'ctx, 'this>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        utils::test::{jit_struct, load_cairo, run_program_assert_output},
+        values::JitValue,
+    };
+    use cairo_lang_sierra::program::Program;
+    use lazy_static::lazy_static;
+    use starknet_types_core::felt::Felt;
+
+    lazy_static! {
+        static ref STRUCTS: (String, Program) = load_cairo! {
+            #[derive(Copy, Drop)]
+            struct Empty {}
+
+            #[derive(Copy, Drop)]
+            struct Point {
+                x: felt252,
+                marker: Empty,
+                y: u8,
+            }
+
+            fn construct() -> Point {
+                Point { x: 1, marker: Empty {}, y: 2 }
+            }
+
+            fn deconstruct(p: Point) -> (u8, felt252) {
+                let Point { x, marker: _, y } = p;
+                (y, x)
+            }
+
+            fn snapshot_deconstruct(p: @Point) -> felt252 {
+                *p.x + (*p.y).into()
+            }
+
+            fn run_deconstruct() -> (u8, felt252, felt252) {
+                let p = construct();
+                let (y, x) = deconstruct(p);
+                (y, x, snapshot_deconstruct(@p))
+            }
+
+            fn empty() -> ((), Empty) {
+                ((), Empty {})
+            }
+        };
+    }
+
+    #[test]
+    fn struct_construct() {
+        run_program_assert_output(
+            &STRUCTS,
+            "construct",
+            &[],
+            jit_struct!(
+                JitValue::Felt252(Felt::ONE),
+                jit_struct!(),
+                JitValue::Uint8(2),
+            ),
+        );
+    }
+
+    #[test]
+    fn struct_deconstruct() {
+        run_program_assert_output(
+            &STRUCTS,
+            "run_deconstruct",
+            &[],
+            jit_struct!(
+                JitValue::Uint8(2),
+                JitValue::Felt252(Felt::ONE),
+                JitValue::Felt252(Felt::from(3)),
+            ),
+        );
+    }
+
+    #[test]
+    fn struct_zero_sized() {
+        run_program_assert_output(
+            &STRUCTS,
+            "empty",
+            &[],
+            jit_struct!(jit_struct!(), jit_struct!()),
+        );
+    }
+}
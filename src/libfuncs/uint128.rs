@@ -2,7 +2,10 @@
 
 use super::LibfuncHelper;
 use crate::{
-    block_ext::BlockExt, error::Result, metadata::MetadataStorage, utils::ProgramRegistryExt,
+    block_ext::BlockExt,
+    error::Result,
+    metadata::{determinism::DeterminismMeta, int_range::IntRangeMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -12,6 +15,7 @@ use cairo_lang_sierra::{
             IntConstConcreteLibfunc, IntOperationConcreteLibfunc, IntOperator,
         },
         lib_func::SignatureOnlyConcreteLibfunc,
+        utils::Range,
         ConcreteLibfunc,
     },
     program_registry::ProgramRegistry,
@@ -264,7 +268,7 @@ pub fn build_operation<'ctx, 'this>(
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
-    _metadata: &mut MetadataStorage,
+    metadata: &mut MetadataStorage,
     info: &IntOperationConcreteLibfunc,
 ) -> Result<()> {
     let range_check: Value =
@@ -273,6 +277,19 @@ pub fn build_operation<'ctx, 'this>(
     let lhs: Value = entry.argument(1)?.into();
     let rhs: Value = entry.argument(2)?.into();
 
+    // Skip the overflow check when the range analysis proves it can't fail.
+    if metadata.get::<IntRangeMeta>().is_some_and(|x| {
+        x.is_operation_in_range(info.operator, &Range::closed(u128::MIN, u128::MAX))
+    }) {
+        let result = entry.append_op_result(match info.operator {
+            IntOperator::OverflowingAdd => arith::addi(lhs, rhs, location),
+            IntOperator::OverflowingSub => arith::subi(lhs, rhs, location),
+        })?;
+
+        entry.append_operation(helper.br(0, &[range_check, result], location));
+        return Ok(());
+    }
+
     let op_name = match info.operator {
         IntOperator::OverflowingAdd => "llvm.intr.uadd.with.overflow",
         IntOperator::OverflowingSub => "llvm.intr.usub.with.overflow",
@@ -536,7 +553,11 @@ pub fn build_guarantee_mul<'ctx, 'this>(
     let result_hi = entry.append_op_result(arith::shrui(result, const_128, location))?;
     let result_hi = entry.append_op_result(arith::trunci(result_hi, origin_type, location))?;
 
-    let guarantee = entry.append_op_result(llvm::undef(guarantee_type, location))?;
+    let guarantee = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        guarantee_type,
+        location,
+    ))?;
 
     entry.append_operation(helper.br(0, &[result_hi, result_lo, guarantee], location));
     Ok(())
@@ -877,6 +898,36 @@ mod test {
         run(MAX, MAX);
     }
 
+    #[test]
+    fn u128_overflowing_branch_values() {
+        let program = load_cairo!(
+            use core::integer::{u128_overflowing_add, u128_overflowing_sub};
+
+            fn run_test(lhs: u128, rhs: u128) -> (Result<u128, u128>, Result<u128, u128>) {
+                (
+                    u128_overflowing_add(lhs, rhs),
+                    u128_overflowing_sub(lhs, rhs),
+                )
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[1u128.into(), 2u128.into()],
+            jit_struct!(jit_enum!(0, 3u128.into()), jit_enum!(1, u128::MAX.into())),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[u128::MAX.into(), 1u128.into()],
+            jit_struct!(
+                jit_enum!(1, 0u128.into()),
+                jit_enum!(0, (u128::MAX - 1).into())
+            ),
+        );
+    }
+
     #[test]
     fn u128_sub() {
         #[track_caller]
@@ -1,7 +1,11 @@
 //! # `u256`-related libfuncs
 
 use super::LibfuncHelper;
-use crate::{error::Result, metadata::MetadataStorage, utils::ProgramRegistryExt};
+use crate::{
+    error::Result,
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
+};
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
@@ -198,7 +202,8 @@ pub fn build_divmod<'ctx, 'this>(
         .into();
 
     let result_div = entry
-        .append_operation(llvm::undef(
+        .append_operation(DeterminismMeta::build_undef(
+            metadata,
             llvm::r#type::r#struct(context, &[i128_ty, i128_ty], false),
             location,
         ))
@@ -226,7 +231,8 @@ pub fn build_divmod<'ctx, 'this>(
         .into();
 
     let result_rem = entry
-        .append_operation(llvm::undef(
+        .append_operation(DeterminismMeta::build_undef(
+            metadata,
             llvm::r#type::r#struct(context, &[i128_ty, i128_ty], false),
             location,
         ))
@@ -253,7 +259,11 @@ pub fn build_divmod<'ctx, 'this>(
         .result(0)?
         .into();
 
-    let op = entry.append_operation(llvm::undef(guarantee_type, location));
+    let op = entry.append_operation(DeterminismMeta::build_undef(
+        metadata,
+        guarantee_type,
+        location,
+    ));
     let guarantee = op.result(0)?.into();
 
     entry.append_operation(helper.br(
@@ -908,7 +918,7 @@ pub fn build_u256_guarantee_inv_mod_n<'ctx, 'this>(
         &info.output_types()[0][1],
     )?;
     let result_inv = entry
-        .append_operation(llvm::undef(return_ty, location))
+        .append_operation(DeterminismMeta::build_undef(metadata, return_ty, location))
         .result(0)?
         .into();
     let result_inv = entry
@@ -958,7 +968,11 @@ pub fn build_u256_guarantee_inv_mod_n<'ctx, 'this>(
         metadata,
         &info.output_types()[0][2],
     )?;
-    let op = entry.append_operation(llvm::undef(guarantee_type, location));
+    let op = entry.append_operation(DeterminismMeta::build_undef(
+        metadata,
+        guarantee_type,
+        location,
+    ));
     let guarantee = op.result(0)?.into();
 
     entry.append_operation(helper.cond_br(
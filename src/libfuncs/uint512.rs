@@ -2,7 +2,10 @@
 
 use super::LibfuncHelper;
 use crate::{
-    block_ext::BlockExt, error::Result, metadata::MetadataStorage, utils::ProgramRegistryExt,
+    block_ext::BlockExt,
+    error::Result,
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -139,7 +142,8 @@ pub fn build_divmod_u256<'ctx, 'this>(
         ))?,
     );
 
-    let result_div_val = entry.append_op_result(llvm::undef(
+    let result_div_val = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
         llvm::r#type::r#struct(context, &[i128_ty, i128_ty, i128_ty, i128_ty], false),
         location,
     ))?;
@@ -150,7 +154,8 @@ pub fn build_divmod_u256<'ctx, 'this>(
         &[result_div.0, result_div.1, result_div.2, result_div.3],
     )?;
 
-    let result_rem_val = entry.append_op_result(llvm::undef(
+    let result_rem_val = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
         llvm::r#type::r#struct(context, &[i128_ty, i128_ty], false),
         location,
     ))?;
@@ -161,7 +166,11 @@ pub fn build_divmod_u256<'ctx, 'this>(
         &[result_rem.0, result_rem.1],
     )?;
 
-    let guarantee = entry.append_op_result(llvm::undef(guarantee_type, location))?;
+    let guarantee = entry.append_op_result(DeterminismMeta::build_undef(
+        metadata,
+        guarantee_type,
+        location,
+    ))?;
 
     entry.append_operation(helper.br(
         0,
@@ -2,7 +2,10 @@
 
 use super::LibfuncHelper;
 use crate::{
-    block_ext::BlockExt, error::Result, metadata::MetadataStorage, utils::ProgramRegistryExt,
+    block_ext::BlockExt,
+    error::Result,
+    metadata::{int_range::IntRangeMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -12,6 +15,7 @@ use cairo_lang_sierra::{
             IntConstConcreteLibfunc, IntOperationConcreteLibfunc, IntOperator,
         },
         lib_func::SignatureOnlyConcreteLibfunc,
+        utils::Range,
         ConcreteLibfunc,
     },
     program_registry::ProgramRegistry,
@@ -43,7 +47,7 @@ pub fn build<'ctx, 'this>(
             build_const(context, registry, entry, location, helper, metadata, info)
         }
         UintConcrete::Operation(info) => {
-            build_operation(context, registry, entry, location, helper, info)
+            build_operation(context, registry, entry, location, helper, metadata, info)
         }
         UintConcrete::SquareRoot(info) => {
             build_square_root(context, registry, entry, location, helper, metadata, info)
@@ -103,6 +107,7 @@ pub fn build_operation<'ctx, 'this>(
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
     info: &IntOperationConcreteLibfunc,
 ) -> Result<()> {
     let range_check: Value =
@@ -111,6 +116,20 @@ pub fn build_operation<'ctx, 'this>(
     let lhs: Value = entry.argument(1)?.into();
     let rhs: Value = entry.argument(2)?.into();
 
+    // Skip the overflow check when the range analysis proves it can't fail.
+    if metadata
+        .get::<IntRangeMeta>()
+        .is_some_and(|x| x.is_operation_in_range(info.operator, &Range::closed(u64::MIN, u64::MAX)))
+    {
+        let result = entry.append_op_result(match info.operator {
+            IntOperator::OverflowingAdd => arith::addi(lhs, rhs, location),
+            IntOperator::OverflowingSub => arith::subi(lhs, rhs, location),
+        })?;
+
+        entry.append_operation(helper.br(0, &[range_check, result], location));
+        return Ok(());
+    }
+
     let op_name = match info.operator {
         IntOperator::OverflowingAdd => "llvm.intr.uadd.with.overflow",
         IntOperator::OverflowingSub => "llvm.intr.usub.with.overflow",
@@ -690,6 +709,33 @@ mod test {
         run(MAX, MAX);
     }
 
+    #[test]
+    fn u64_overflowing_branch_values() {
+        let program = load_cairo!(
+            use core::integer::{u64_overflowing_add, u64_overflowing_sub};
+
+            fn run_test(lhs: u64, rhs: u64) -> (Result<u64, u64>, Result<u64, u64>) {
+                (u64_overflowing_add(lhs, rhs), u64_overflowing_sub(lhs, rhs))
+            }
+        );
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[1u64.into(), 2u64.into()],
+            jit_struct!(jit_enum!(0, 3u64.into()), jit_enum!(1, u64::MAX.into())),
+        );
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[u64::MAX.into(), 1u64.into()],
+            jit_struct!(
+                jit_enum!(1, 0u64.into()),
+                jit_enum!(0, (u64::MAX - 1).into())
+            ),
+        );
+    }
+
     #[test]
     fn u64_overflowing_sub() {
         #[track_caller]
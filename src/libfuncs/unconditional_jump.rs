@@ -1,4 +1,7 @@
 //! # Unconditional jump libfunc
+//!
+//! Jumps are already handled by the branching logic of the helper, so this libfunc only needs to
+//! branch into its single target.
 
 use super::LibfuncHelper;
 use crate::{error::Result, metadata::MetadataStorage};
@@ -28,3 +31,64 @@ pub fn build<'ctx>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{context::NativeContext, executor::JitNativeExecutor, values::JitValue, OptLevel};
+    use cairo_lang_sierra::ProgramParser;
+
+    #[test]
+    fn jump_and_no_op_libfuncs() {
+        // The Cairo compiler only generates the `AP` tracking libfuncs in some cases, therefore
+        // the program is written in Sierra directly.
+        let program = ProgramParser::new()
+            .parse(
+                "type felt252 = felt252;
+                type NonZeroFelt252 = NonZero<felt252>;
+
+                libfunc branch_align = branch_align;
+                libfunc disable_ap_tracking = disable_ap_tracking;
+                libfunc drop_nz_felt252 = drop<NonZeroFelt252>;
+                libfunc enable_ap_tracking = enable_ap_tracking;
+                libfunc felt252_const_1 = felt252_const<1>;
+                libfunc felt252_const_2 = felt252_const<2>;
+                libfunc felt252_is_zero = felt252_is_zero;
+                libfunc jump = jump;
+                libfunc revoke_ap_tracking = revoke_ap_tracking;
+                libfunc store_temp_felt252 = store_temp<felt252>;
+
+                disable_ap_tracking() -> ();
+                felt252_is_zero([0]) { fallthrough() 5([1]) };
+                branch_align() -> ();
+                felt252_const_1() -> ([2]);
+                jump() { 9() };
+                branch_align() -> ();
+                drop_nz_felt252([1]) -> ();
+                felt252_const_2() -> ([2]);
+                jump() { 9() };
+                revoke_ap_tracking() -> ();
+                enable_ap_tracking() -> ();
+                store_temp_felt252([2]) -> ([2]);
+                return([2]);
+
+                run_test@0([0]: felt252) -> (felt252);",
+            )
+            .unwrap();
+
+        let module = NativeContext::new().compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let run = |value: u8| {
+            executor
+                .invoke_dynamic(
+                    &program.funcs[0].id,
+                    &[JitValue::Felt252(value.into())],
+                    None,
+                )
+                .unwrap()
+                .return_value
+        };
+
+        assert_eq!(run(0), JitValue::Felt252(1.into()));
+        assert_eq!(run(5), JitValue::Felt252(2.into()));
+    }
+}
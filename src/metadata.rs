@@ -14,11 +14,23 @@ use std::{
     collections::{hash_map::Entry, HashMap},
 };
 
+pub mod const_segment;
 pub mod debug_utils;
+pub mod determinism;
+pub mod drop_overrides;
 pub mod enum_snapshot_variants;
+pub mod execution_snapshots;
 pub mod gas;
+pub mod int_range;
+pub mod libfunc_plugins;
+pub mod libfunc_templates;
+pub mod overflow_checks;
+pub mod panic;
 pub mod prime_modulo;
+pub mod profiler;
+pub mod range_check_usage;
 pub mod realloc_bindings;
+pub mod recursion;
 pub mod runtime_bindings;
 pub mod snapshot_clones;
 pub mod tail_recursion;
@@ -101,6 +113,63 @@ impl MetadataStorage {
             .downcast_mut::<T>()
             .unwrap()
     }
+
+    /// Create the metadata of a worker of a [parallel compilation](crate::compile_parallel).
+    ///
+    /// The configuration and the analyses of the whole program (ex. the gas metadata) are copied,
+    /// while the metadata which keeps track of the generated code (ex. the declared runtime
+    /// bindings) starts empty. Any other metadata is left out.
+    pub fn fork(&self) -> Self {
+        let mut fork = Self::new();
+
+        fork.fork_entry::<gas::GasMetadata>(self);
+        fork.fork_entry::<int_range::IntRangeMeta>(self);
+        fork.fork_entry::<recursion::RecursionMeta>(self);
+        fork.fork_entry::<range_check_usage::RangeCheckUsageMeta>(self);
+        fork.fork_entry::<overflow_checks::OverflowChecksMeta>(self);
+        fork.fork_entry::<determinism::DeterminismMeta>(self);
+        fork.fork_entry::<libfunc_plugins::LibfuncPluginsMeta>(self);
+        fork.fork_entry::<libfunc_templates::LibfuncTemplatesMeta>(self);
+        #[cfg(feature = "with-debug-utils")]
+        fork.fork_entry::<execution_snapshots::ExecutionSnapshotsMeta>(self);
+
+        if self
+            .get::<runtime_bindings::RuntimeBindingsMeta>()
+            .is_some()
+        {
+            fork.insert(runtime_bindings::RuntimeBindingsMeta::default());
+        }
+        if let Some(meta) = self.get::<panic::PanicMeta>() {
+            fork.insert(panic::PanicMeta::new(meta.mode()));
+        }
+        #[cfg(feature = "with-profiler")]
+        if let Some(meta) = self.get::<profiler::ProfilerMeta>() {
+            fork.insert(meta.fork());
+        }
+
+        fork
+    }
+
+    /// Merge back the metadata of a worker of a parallel compilation once it's done. Only the
+    /// metadata required by the executors is kept.
+    pub fn join(&mut self, _fork: Self) {
+        #[cfg(feature = "with-debug-utils")]
+        if let (Some(meta), Some(other)) = (
+            self.get_mut::<debug_utils::DebugUtils>(),
+            _fork.get::<debug_utils::DebugUtils>(),
+        ) {
+            meta.join(other);
+        }
+    }
+
+    fn fork_entry<T>(&mut self, source: &Self)
+    where
+        T: Any + Clone,
+    {
+        if let Some(meta) = source.get::<T>() {
+            self.insert(meta.clone());
+        }
+    }
 }
 
 #[cfg(feature = "with-debug-utils")]
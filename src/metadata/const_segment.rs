@@ -0,0 +1,98 @@
+//! # Constant segment
+//!
+//! Large constants (ex. structs with many felts) are expensive to rebuild every time they're used,
+//! so their contents are computed once at compile time and stored in an LLVM global constant. This
+//! metadata keeps track of the globals that have been declared so that every constant is only
+//! emitted once per module.
+
+use crate::error::Result;
+use cairo_lang_sierra::ids::ConcreteTypeId;
+use melior::{
+    dialect::llvm,
+    ir::{
+        attribute::{FlatSymbolRefAttribute, IntegerAttribute, StringAttribute, TypeAttribute},
+        operation::OperationBuilder,
+        r#type::IntegerType,
+        Attribute, Identifier, Location, Module, Region,
+    },
+    Context,
+};
+use std::{collections::HashMap, fmt::Write};
+
+/// Global constants declared for the constant segment.
+#[derive(Debug, Default)]
+pub struct ConstSegmentMeta {
+    globals: HashMap<ConcreteTypeId, String>,
+}
+
+impl ConstSegmentMeta {
+    /// Return the symbol of the global which holds the constant `id`, declaring it if it doesn't
+    /// exist yet.
+    ///
+    /// The `data` callback is only invoked when declaring the global, and should return the memory
+    /// representation of the constant and its alignment.
+    pub fn get_or_declare<'ctx>(
+        &mut self,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        id: &ConcreteTypeId,
+        data: impl FnOnce() -> Result<(Vec<u8>, usize)>,
+    ) -> Result<FlatSymbolRefAttribute<'ctx>> {
+        if let Some(name) = self.globals.get(id) {
+            return Ok(FlatSymbolRefAttribute::new(context, name));
+        }
+
+        let (data, align) = data()?;
+        let name = format!("const_segment_{}", id.id);
+
+        let mut value = String::from("dense<[");
+        for (i, byte) in data.iter().enumerate() {
+            if i != 0 {
+                value.push_str(", ");
+            }
+            write!(value, "{byte}").unwrap();
+        }
+        write!(value, "]> : tensor<{}xi8>", data.len()).unwrap();
+
+        let location = Location::unknown(context);
+        module.body().append_operation(
+            OperationBuilder::new("llvm.mlir.global", location)
+                .add_attributes(&[
+                    (
+                        Identifier::new(context, "sym_name"),
+                        StringAttribute::new(context, &name).into(),
+                    ),
+                    (
+                        Identifier::new(context, "global_type"),
+                        TypeAttribute::new(llvm::r#type::array(
+                            IntegerType::new(context, 8).into(),
+                            data.len().try_into()?,
+                        ))
+                        .into(),
+                    ),
+                    (
+                        Identifier::new(context, "linkage"),
+                        Attribute::parse(context, "#llvm.linkage<internal>").unwrap(),
+                    ),
+                    (
+                        Identifier::new(context, "constant"),
+                        Attribute::unit(context),
+                    ),
+                    (
+                        Identifier::new(context, "alignment"),
+                        IntegerAttribute::new(IntegerType::new(context, 64).into(), align as i64)
+                            .into(),
+                    ),
+                    (
+                        Identifier::new(context, "value"),
+                        Attribute::parse(context, &value).unwrap(),
+                    ),
+                ])
+                .add_regions([Region::new()])
+                .build()?,
+        );
+
+        self.globals.insert(id.clone(), name.clone());
+        Ok(FlatSymbolRefAttribute::new(context, &name))
+    }
+}
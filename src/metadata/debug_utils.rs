@@ -83,7 +83,7 @@
 
 #![cfg(feature = "with-debug-utils")]
 
-use crate::{block_ext::BlockExt, error::Result, utils::get_integer_layout};
+use crate::{block_ext::BlockExt, error::Result, execution_snapshots, utils::get_integer_layout};
 use melior::{
     dialect::{
         arith, func,
@@ -113,6 +113,7 @@ enum DebugBinding {
     PrintPointer,
     PrintFelt252,
     DumpMemRegion,
+    ExecutionSnapshot,
 }
 
 #[derive(Debug, Default)]
@@ -121,6 +122,11 @@ pub struct DebugUtils {
 }
 
 impl DebugUtils {
+    /// Merge the bindings declared by a worker of a parallel compilation.
+    pub(crate) fn join(&mut self, other: &Self) {
+        self.active_map.extend(&other.active_map);
+    }
+
     pub fn breakpoint_marker<'c, 'a>(
         &mut self,
         context: &'c Context,
@@ -658,6 +664,94 @@ impl DebugUtils {
         Ok(())
     }
 
+    /// Start an [execution snapshot](crate::execution_snapshots) of the given statement.
+    ///
+    /// The values are reported afterwards, one by one, using
+    /// [snapshot_value](Self::snapshot_value).
+    pub fn snapshot_begin<'c, 'a>(
+        &mut self,
+        context: &'c Context,
+        module: &Module,
+        block: &'a Block<'c>,
+        statement_idx: usize,
+        location: Location<'c>,
+    ) -> Result<()>
+    where
+        'c: 'a,
+    {
+        self.declare_snapshot_bindings(context, module);
+
+        let statement_idx = block.const_int(context, location, statement_idx, 64)?;
+        block.append_operation(func::call(
+            context,
+            FlatSymbolRefAttribute::new(context, "__debug__snapshot_begin"),
+            &[statement_idx],
+            &[],
+            location,
+        ));
+
+        Ok(())
+    }
+
+    /// Add a value to the current execution snapshot.
+    ///
+    /// Requires a pointer to the value (at runtime) and its Sierra variable and type ids (at
+    /// compile-time).
+    #[allow(clippy::too_many_arguments)]
+    pub fn snapshot_value<'c, 'a>(
+        &mut self,
+        context: &'c Context,
+        module: &Module,
+        block: &'a Block<'c>,
+        var_id: u64,
+        type_id: u64,
+        ptr: Value<'c, '_>,
+        location: Location<'c>,
+    ) -> Result<()>
+    where
+        'c: 'a,
+    {
+        self.declare_snapshot_bindings(context, module);
+
+        let var_id = block.const_int(context, location, var_id, 64)?;
+        let type_id = block.const_int(context, location, type_id, 64)?;
+        block.append_operation(func::call(
+            context,
+            FlatSymbolRefAttribute::new(context, "__debug__snapshot_value"),
+            &[var_id, type_id, ptr],
+            &[],
+            location,
+        ));
+
+        Ok(())
+    }
+
+    fn declare_snapshot_bindings(&mut self, context: &Context, module: &Module) {
+        if self.active_map.insert(DebugBinding::ExecutionSnapshot) {
+            let i64_ty = IntegerType::new(context, 64).into();
+
+            for (name, arg_types) in [
+                ("__debug__snapshot_begin", &[i64_ty][..]),
+                (
+                    "__debug__snapshot_value",
+                    &[i64_ty, i64_ty, llvm::r#type::pointer(context, 0)],
+                ),
+            ] {
+                module.body().append_operation(func::func(
+                    context,
+                    StringAttribute::new(context, name),
+                    TypeAttribute::new(FunctionType::new(context, arg_types, &[]).into()),
+                    Region::new(),
+                    &[(
+                        Identifier::new(context, "sym_visibility"),
+                        StringAttribute::new(context, "private").into(),
+                    )],
+                    Location::unknown(context),
+                ));
+            }
+        }
+    }
+
     pub fn register_impls(&self, engine: &ExecutionEngine) {
         if self.active_map.contains(&DebugBinding::BreakpointMarker) {
             unsafe {
@@ -748,6 +842,20 @@ impl DebugUtils {
                 );
             }
         }
+
+        if self.active_map.contains(&DebugBinding::ExecutionSnapshot) {
+            unsafe {
+                engine.register_symbol(
+                    "__debug__snapshot_begin",
+                    execution_snapshots::snapshot_begin_impl as *const fn(u64) -> () as *mut (),
+                );
+                engine.register_symbol(
+                    "__debug__snapshot_value",
+                    execution_snapshots::snapshot_value_impl as *const fn(u64, u64, *const ()) -> ()
+                        as *mut (),
+                );
+            }
+        }
     }
 }
 
@@ -0,0 +1,87 @@
+//! # Determinism audit
+//!
+//! Keeps the [DeterminismMode] of the current compilation, which the libfuncs check to decide
+//! whether they have to harden the code they generate, and the findings of the
+//! [audit](crate::determinism) of the generated module.
+//!
+//! When the metadata is missing (ex. when calling [compile](crate::compile) directly), the audit is
+//! disabled.
+
+use super::MetadataStorage;
+use crate::{block_ext::BlockExt, context::DeterminismMode, error::Result};
+use melior::{
+    dialect::llvm,
+    ir::{r#type::IntegerType, Block, Location, Operation, Type, Value},
+    Context,
+};
+
+/// Determinism audit metadata.
+#[derive(Clone, Debug, Default)]
+pub struct DeterminismMeta {
+    mode: DeterminismMode,
+    findings: Vec<String>,
+}
+
+impl DeterminismMeta {
+    pub fn new(mode: DeterminismMode) -> Self {
+        Self {
+            mode,
+            findings: Vec::new(),
+        }
+    }
+
+    /// Return the mode of the current compilation, or [DeterminismMode::Off] if the metadata is
+    /// missing.
+    pub fn mode_of(metadata: &MetadataStorage) -> DeterminismMode {
+        metadata.get::<Self>().map(Self::mode).unwrap_or_default()
+    }
+
+    pub fn mode(&self) -> DeterminismMode {
+        self.mode
+    }
+
+    /// The nondeterministic behaviors found in the generated module.
+    pub fn findings(&self) -> &[String] {
+        &self.findings
+    }
+
+    pub(crate) fn add_finding(&mut self, finding: String) {
+        self.findings.push(finding);
+    }
+
+    /// Create the initial value of an aggregate whose fields will be inserted afterwards.
+    ///
+    /// The value is `undef` unless the audit is enabled, in which case it's zeroed so that the
+    /// bytes which are never inserted (ex. the unused payload of an enum) are deterministic.
+    pub fn build_undef<'ctx>(
+        metadata: &MetadataStorage,
+        ty: Type<'ctx>,
+        location: Location<'ctx>,
+    ) -> Operation<'ctx> {
+        match Self::mode_of(metadata) {
+            DeterminismMode::Off => llvm::undef(ty, location),
+            DeterminismMode::Audit | DeterminismMode::Strict => llvm::zero(ty, location),
+        }
+    }
+
+    /// Zero `len` bytes of memory before a value is stored there, since storing a value doesn't
+    /// write its padding. Does nothing unless the audit is enabled.
+    pub fn build_clear<'ctx>(
+        metadata: &MetadataStorage,
+        context: &'ctx Context,
+        block: &Block<'ctx>,
+        location: Location<'ctx>,
+        ptr: Value<'ctx, '_>,
+        len: usize,
+    ) -> Result<()> {
+        if Self::mode_of(metadata) == DeterminismMode::Off || len == 0 {
+            return Ok(());
+        }
+
+        let zero = block.append_op_result(llvm::zero(
+            llvm::r#type::array(IntegerType::new(context, 8).into(), len.try_into()?),
+            location,
+        ))?;
+        block.store(context, location, ptr, zero)
+    }
+}
@@ -0,0 +1,89 @@
+//! # Drop implementations
+//!
+//! Most types are trivial and don't need any special handling when dropped, but types which own
+//! heap-allocated memory (ex. arrays, boxes or dictionaries) have to release it. Their type builders
+//! register a drop implementation here, which the `drop` libfunc will invoke.
+//!
+//! Duplication works the same way through the [snapshot clones metadata](super::snapshot_clones).
+
+use super::MetadataStorage;
+use crate::{error::Result, libfuncs::LibfuncHelper, types::WithSelf};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType},
+    ids::ConcreteTypeId,
+    program_registry::ProgramRegistry,
+};
+use melior::{
+    ir::{Block, Location, Value},
+    Context,
+};
+use std::{collections::HashMap, sync::Arc};
+
+pub type DropFn<P> = for<'ctx, 'this> fn(
+    &'ctx Context,
+    &ProgramRegistry<CoreType, CoreLibfunc>,
+    &'this Block<'ctx>,
+    Location<'ctx>,
+    &LibfuncHelper<'ctx, 'this>,
+    &mut MetadataStorage,
+    WithSelf<P>,
+    Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>>;
+
+type DropFnWrapper = Arc<
+    dyn for<'ctx, 'this> Fn(
+        &'ctx Context,
+        &ProgramRegistry<CoreType, CoreLibfunc>,
+        &'this Block<'ctx>,
+        Location<'ctx>,
+        &LibfuncHelper<'ctx, 'this>,
+        &mut MetadataStorage,
+        Value<'ctx, 'this>,
+    ) -> Result<&'this Block<'ctx>>,
+>;
+
+/// Drop implementations of the types which need one.
+#[derive(Default)]
+pub struct DropOverridesMeta {
+    mappings: HashMap<ConcreteTypeId, DropFnWrapper>,
+}
+
+impl DropOverridesMeta {
+    /// Register the drop implementation of a type.
+    pub fn register<P>(&mut self, id: ConcreteTypeId, handler: DropFn<P>, params: P)
+    where
+        P: 'static,
+    {
+        let self_ty = id.clone();
+        self.mappings.insert(
+            id,
+            Arc::new(
+                move |context, registry, entry, location, helper, metadata, value| {
+                    handler(
+                        context,
+                        registry,
+                        entry,
+                        location,
+                        helper,
+                        metadata,
+                        WithSelf::new(&self_ty, &params),
+                        value,
+                    )
+                },
+            ),
+        );
+    }
+
+    /// Return whether the type has a drop implementation.
+    pub fn is_overridden(&self, id: &ConcreteTypeId) -> bool {
+        self.mappings.contains_key(id)
+    }
+
+    /// Return the drop implementation of a type, if any.
+    ///
+    /// The returned function releases the value passed to it and returns the block where the code
+    /// generation should continue.
+    pub fn wrap_invoke(&self, id: &ConcreteTypeId) -> Option<DropFnWrapper> {
+        self.mappings.get(id).cloned()
+    }
+}
@@ -0,0 +1,30 @@
+//! # Execution snapshot statements
+//!
+//! When this metadata is present the compiler reports the arguments of the selected statements to
+//! the host every time they're about to be executed, using the
+//! [debug utilities](super::debug_utils). Check out the
+//! [execution snapshots](crate::execution_snapshots) module for how they're recorded.
+
+#![cfg(feature = "with-debug-utils")]
+
+use cairo_lang_sierra::program::StatementIdx;
+use std::collections::BTreeSet;
+
+/// The statements where an execution snapshot has to be taken.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionSnapshotsMeta {
+    statements: BTreeSet<usize>,
+}
+
+impl ExecutionSnapshotsMeta {
+    pub fn new(statements: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            statements: statements.into_iter().collect(),
+        }
+    }
+
+    /// Return whether a snapshot has to be taken before executing the statement.
+    pub fn contains(&self, statement_idx: StatementIdx) -> bool {
+        self.statements.contains(&statement_idx.0)
+    }
+}
@@ -21,6 +21,7 @@ pub struct GasMetadata {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GasCost(pub Option<u128>);
 
 /// Configuration for metadata computation.
@@ -65,8 +66,8 @@ impl GasMetadata {
         }
     }
 
-    /// Returns the initial value for the gas counter.
-    /// If `available_gas` is None returns 0.
+    /// Returns the initial value for the gas counter, which is the available gas minus the cost of
+    /// calling the function. If `available_gas` is None returns 0.
     pub fn get_initial_available_gas(
         &self,
         func: &FunctionId,
@@ -77,10 +78,10 @@ impl GasMetadata {
         };
 
         // In case we don't have any costs - it means no gas equations were solved (and we are in
-        // the case of no gas checking enabled) - so the gas builtin is irrelevant, and we
-        // can return any value.
+        // the case of no gas checking enabled) - so the gas builtin is irrelevant. The available
+        // gas is returned untouched so that it's reported back as the remaining gas.
         let Some(required_gas) = self.initial_required_gas(func) else {
-            return Ok(0);
+            return Ok(available_gas);
         };
 
         available_gas
@@ -90,6 +91,8 @@ impl GasMetadata {
             })
     }
 
+    /// Returns the gas that has to be available when calling the function, or `None` if the
+    /// program was compiled without gas costs.
     pub fn initial_required_gas(&self, func: &FunctionId) -> Option<u128> {
         if self.gas_info.function_costs.is_empty() {
             return None;
@@ -102,6 +105,8 @@ impl GasMetadata {
         )
     }
 
+    /// Returns the gas cost of a statement, or `None` if it has no associated cost (ex. libfuncs
+    /// that don't interact with the gas counter).
     pub fn get_gas_cost_for_statement(&self, idx: StatementIdx) -> Option<u128> {
         let mut cost = None;
         for cost_type in CostTokenType::iter_casm_tokens() {
@@ -0,0 +1,798 @@
+//! # Integer range analysis
+//!
+//! Many integer operations in Sierra are checked for overflows even when their operands are known
+//! to be small enough for the check to never fail (ex. adding a constant to a value that has just
+//! been proven to be below some bound). This metadata runs a forward dataflow analysis over every
+//! function's statements and records, for every invocation, which of its arguments have a known
+//! range. Libfunc builders may then use that information to skip redundant comparisons.
+//!
+//! Ranges are inferred from:
+//!   - Integer constants.
+//!   - The success branch of the unsigned integer overflowing operations.
+//!   - Both branches of the comparisons. The corelib implements `<`, `<=`, `>` and `>=` with
+//!     `uN_overflowing_sub` for unsigned integers and with `iN_diff` for signed ones, so the branch
+//!     taken tells which operand is the largest.
+//!   - Both branches of the `is_zero` libfuncs, for unsigned and signed integers.
+//!   - The bounds of `BoundedInt` types.
+//!   - The source type of `upcast`, so that the small integers keep their range after being
+//!     converted into a wider type (ex. to use the `bitwise` libfunc on them).
+//!   - The results of `bitwise`, which never have bits set above the highest one of their operands.
+//!
+//! They are propagated through the libfuncs that only move values around (ex. `store_temp`, `dup`
+//! or `rename`). Since integers are usually `dup`ed before being compared, the analysis also keeps
+//! track of which variables hold copies of the same value, so that the ranges implied by a branch
+//! apply to every copy of its operands.
+//!
+//! When a statement is reachable through multiple paths, a range is only kept if the one found
+//! first covers the ones from every other path. Otherwise it's dropped, which guarantees that the
+//! analysis terminates.
+//!
+//! The compiler updates the current statement before invoking every libfunc builder.
+
+use cairo_lang_sierra::{
+    extensions::{
+        casts::CastConcreteLibfunc,
+        core::{CoreConcreteLibfunc, CoreLibfunc, CoreType, CoreTypeConcrete},
+        int::{
+            signed::SintConcrete, signed128::Sint128Concrete, unsigned::UintConcrete,
+            unsigned128::Uint128Concrete, IntOperator,
+        },
+        mem::MemConcreteLibfunc,
+        utils::Range,
+        ConcreteLibfunc,
+    },
+    ids::{ConcreteTypeId, VarId},
+    program::{Program, Statement, StatementIdx},
+    program_registry::{ProgramRegistry, ProgramRegistryError},
+};
+use num_bigint::BigInt;
+use num_traits::{One, Signed, Zero};
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+/// A value, identified by the statement and argument from which it was first copied.
+type ValueId = (StatementIdx, usize);
+
+#[derive(Clone, Debug, Default)]
+struct RangeState {
+    ranges: HashMap<VarId, Range>,
+    /// The value held by the variables which are copies of others.
+    values: HashMap<VarId, ValueId>,
+}
+
+impl RangeState {
+    /// Narrow the range of every variable which holds the given value.
+    fn narrow(&mut self, value: ValueId, range: &Range) {
+        for (var_id, _) in self.values.iter().filter(|(_, x)| **x == value) {
+            match self.ranges.entry(var_id.clone()) {
+                Entry::Occupied(mut entry) => {
+                    if let Some(narrowed) = entry.get().intersection(range) {
+                        entry.insert(narrowed);
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(range.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Integer range analysis metadata.
+#[derive(Clone, Debug, Default)]
+pub struct IntRangeMeta {
+    arg_ranges: HashMap<StatementIdx, Vec<Option<Range>>>,
+    current_statement: Option<StatementIdx>,
+}
+
+impl IntRangeMeta {
+    /// Run the analysis on every function of the program.
+    pub fn new(
+        program: &Program,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    ) -> Result<Self, Box<ProgramRegistryError>> {
+        let mut states = HashMap::<StatementIdx, RangeState>::new();
+        for function in &program.funcs {
+            let mut initial_state = RangeState::default();
+            for param in &function.params {
+                if let Some(range) = type_range(registry, &param.ty)? {
+                    initial_state.ranges.insert(param.id.clone(), range);
+                }
+            }
+
+            let mut queue = VecDeque::new();
+            if merge_state(&mut states, function.entry_point, initial_state) {
+                queue.push_back(function.entry_point);
+            }
+
+            while let Some(statement_idx) = queue.pop_front() {
+                let Statement::Invocation(invocation) = &program.statements[statement_idx.0] else {
+                    continue;
+                };
+
+                let mut state = states[&statement_idx].clone();
+                // Copies made by a previous visit of this statement (through a cycle) are not
+                // copies of the values it's about to make.
+                state.values.retain(|_, value| value.0 != statement_idx);
+
+                let args = invocation
+                    .args
+                    .iter()
+                    .map(|var_id| state.ranges.remove(var_id))
+                    .collect::<Vec<_>>();
+                let arg_values = invocation
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(arg_idx, var_id)| {
+                        state
+                            .values
+                            .remove(var_id)
+                            .unwrap_or((statement_idx, arg_idx))
+                    })
+                    .collect::<Vec<_>>();
+
+                let libfunc = registry.get_libfunc(&invocation.libfunc_id)?;
+                let branch_ranges = propagate(registry, libfunc, &args)?;
+                let branch_arg_ranges = refine(registry, libfunc, &args)?;
+                let branch_copies = copies(libfunc);
+
+                for (branch_idx, (branch, branch_signature)) in invocation
+                    .branches
+                    .iter()
+                    .zip(libfunc.branch_signatures())
+                    .enumerate()
+                {
+                    let mut branch_state = state.clone();
+                    for (result_idx, (var_id, var_info)) in branch
+                        .results
+                        .iter()
+                        .zip(&branch_signature.vars)
+                        .enumerate()
+                    {
+                        let range = match branch_ranges
+                            .get(branch_idx)
+                            .and_then(|x| x.get(result_idx))
+                            .cloned()
+                            .flatten()
+                        {
+                            Some(range) => Some(range),
+                            None => type_range(registry, &var_info.ty)?,
+                        };
+
+                        if let Some(range) = range {
+                            branch_state.ranges.insert(var_id.clone(), range);
+                        }
+
+                        if let Some(arg_idx) = branch_copies
+                            .get(branch_idx)
+                            .and_then(|x| x.get(result_idx))
+                            .copied()
+                            .flatten()
+                        {
+                            branch_state
+                                .values
+                                .insert(var_id.clone(), arg_values[arg_idx]);
+                        }
+                    }
+
+                    if let Some(arg_ranges) = branch_arg_ranges.get(branch_idx) {
+                        for (value, range) in arg_values.iter().zip(arg_ranges) {
+                            if let Some(range) = range {
+                                branch_state.narrow(*value, range);
+                            }
+                        }
+                    }
+
+                    let target = statement_idx.next(&branch.target);
+                    if merge_state(&mut states, target, branch_state) {
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+
+        let arg_ranges = states
+            .into_iter()
+            .filter_map(
+                |(statement_idx, state)| match &program.statements[statement_idx.0] {
+                    Statement::Invocation(invocation) => Some((
+                        statement_idx,
+                        invocation
+                            .args
+                            .iter()
+                            .map(|var_id| state.ranges.get(var_id).cloned())
+                            .collect(),
+                    )),
+                    Statement::Return(_) => None,
+                },
+            )
+            .collect();
+
+        Ok(Self {
+            arg_ranges,
+            current_statement: None,
+        })
+    }
+
+    /// Set the statement whose libfunc is about to be built.
+    pub fn set_current_statement(&mut self, statement_idx: StatementIdx) {
+        self.current_statement = Some(statement_idx);
+    }
+
+    /// Return the known range of an argument of the current statement, if any.
+    pub fn arg_range(&self, arg_idx: usize) -> Option<&Range> {
+        self.arg_ranges
+            .get(&self.current_statement?)?
+            .get(arg_idx)?
+            .as_ref()
+    }
+
+    /// Return whether an argument of the current statement is known to be within a range.
+    pub fn is_arg_in_range(&self, arg_idx: usize, range: &Range) -> bool {
+        self.arg_range(arg_idx)
+            .is_some_and(|x| x.lower >= range.lower && x.upper <= range.upper)
+    }
+
+    /// Return whether the unsigned integer operation of the current statement can never overflow,
+    /// given the range of its type. The operands are expected to be the second and third
+    /// arguments, like in the `uN_overflowing_add` and `uN_overflowing_sub` libfuncs.
+    pub fn is_operation_in_range(&self, operator: IntOperator, type_range: &Range) -> bool {
+        let lhs = self.arg_range(1).unwrap_or(type_range);
+        let rhs = self.arg_range(2).unwrap_or(type_range);
+
+        let range = operation_range(operator, lhs, rhs);
+        range.lower >= type_range.lower && range.upper <= type_range.upper
+    }
+}
+
+/// Merge an incoming state into the state of a statement. Return whether it changed, in which case
+/// the statement has to be (re)visited.
+fn merge_state(
+    states: &mut HashMap<StatementIdx, RangeState>,
+    statement_idx: StatementIdx,
+    incoming: RangeState,
+) -> bool {
+    match states.entry(statement_idx) {
+        Entry::Occupied(mut entry) => {
+            let state = entry.get_mut();
+            let prev_len = (state.ranges.len(), state.values.len());
+
+            state.ranges.retain(|var_id, range| {
+                incoming
+                    .ranges
+                    .get(var_id)
+                    .is_some_and(|other| other.lower >= range.lower && other.upper <= range.upper)
+            });
+            state
+                .values
+                .retain(|var_id, value| incoming.values.get(var_id) == Some(value));
+
+            (state.ranges.len(), state.values.len()) != prev_len
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(incoming);
+            true
+        }
+    }
+}
+
+/// Return the ranges of the results of every branch of a libfunc, given the ranges of its
+/// arguments.
+fn propagate(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    libfunc: &CoreConcreteLibfunc,
+    args: &[Option<Range>],
+) -> Result<Vec<Vec<Option<Range>>>, Box<ProgramRegistryError>> {
+    let arg = |idx: usize| args.get(idx).cloned().flatten();
+
+    Ok(match libfunc {
+        CoreConcreteLibfunc::Uint8(UintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Uint16(UintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Uint32(UintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Uint64(UintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Uint128(Uint128Concrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Sint8(SintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Sint16(SintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Sint32(SintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Sint64(SintConcrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Sint128(Sint128Concrete::Const(info)) => {
+            vec![vec![Some(Range::closed(info.c, info.c))]]
+        }
+        CoreConcreteLibfunc::Uint8(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint16(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint32(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint64(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint128(Uint128Concrete::Operation(info)) => {
+            // The result is only known in the non-overflowing branch, where it's the part of the
+            // operation's range that fits in an unsigned integer.
+            let result = match (arg(1), arg(2)) {
+                (Some(lhs), Some(rhs)) => operation_range(info.operator, &lhs, &rhs)
+                    .intersection(&Range::closed(0, u128::MAX)),
+                _ => None,
+            };
+            vec![vec![None, result], vec![None, None]]
+        }
+        CoreConcreteLibfunc::Sint8(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint16(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint32(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint64(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint128(Sint128Concrete::Diff(_)) => {
+            // Like the unsigned subtraction, the difference is only known in the branch where it's
+            // not negative.
+            let result = match (arg(1), arg(2)) {
+                (Some(lhs), Some(rhs)) => operation_range(IntOperator::OverflowingSub, &lhs, &rhs)
+                    .intersection(&Range::closed(0, u128::MAX)),
+                _ => None,
+            };
+            vec![vec![None, result], vec![None, None]]
+        }
+        CoreConcreteLibfunc::Uint8(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint16(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint32(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint64(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint128(Uint128Concrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint8(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint16(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint32(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint64(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint128(Sint128Concrete::IsZero(_)) => {
+            vec![vec![], vec![arg(0).and_then(|range| non_zero(&range))]]
+        }
+        CoreConcreteLibfunc::Uint128(Uint128Concrete::Bitwise(_)) => {
+            let result = match (arg(1), arg(2)) {
+                (Some(lhs), Some(rhs)) if !lhs.lower.is_negative() && !rhs.lower.is_negative() => {
+                    let bits = (lhs.upper.max(rhs.upper) - 1).bits();
+                    Some(Range::half_open(0, BigInt::one() << bits))
+                }
+                _ => None,
+            };
+            vec![vec![None, result.clone(), result.clone(), result]]
+        }
+        CoreConcreteLibfunc::Cast(CastConcreteLibfunc::Upcast(info)) => {
+            let range = match arg(0) {
+                Some(range) => Some(range),
+                None => int_range(registry, &info.param_signatures()[0].ty)?,
+            };
+            vec![vec![range]]
+        }
+        CoreConcreteLibfunc::Mem(
+            MemConcreteLibfunc::StoreTemp(_) | MemConcreteLibfunc::Rename(_),
+        )
+        | CoreConcreteLibfunc::UnwrapNonZero(_) => vec![vec![arg(0)]],
+        CoreConcreteLibfunc::Mem(MemConcreteLibfunc::StoreLocal(_)) => vec![vec![arg(1)]],
+        CoreConcreteLibfunc::Dup(_) | CoreConcreteLibfunc::SnapshotTake(_) => {
+            vec![vec![arg(0), arg(0)]]
+        }
+        _ => Vec::new(),
+    })
+}
+
+/// Return the ranges of the arguments of a libfunc in every branch, for the libfuncs whose branch
+/// taken narrows them (ex. `u8_overflowing_sub` only succeeds when its lhs isn't smaller than its
+/// rhs).
+fn refine(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    libfunc: &CoreConcreteLibfunc,
+    args: &[Option<Range>],
+) -> Result<Vec<Vec<Option<Range>>>, Box<ProgramRegistryError>> {
+    let arg = |idx: usize| match args.get(idx).cloned().flatten() {
+        Some(range) => Ok(Some(range)),
+        None => int_range(registry, &libfunc.param_signatures()[idx].ty),
+    };
+
+    Ok(match libfunc {
+        CoreConcreteLibfunc::Uint8(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint16(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint32(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint64(UintConcrete::Operation(info))
+        | CoreConcreteLibfunc::Uint128(Uint128Concrete::Operation(info))
+            if matches!(info.operator, IntOperator::OverflowingSub) =>
+        {
+            compare(arg(1)?, arg(2)?)
+        }
+        CoreConcreteLibfunc::Sint8(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint16(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint32(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint64(SintConcrete::Diff(_))
+        | CoreConcreteLibfunc::Sint128(Sint128Concrete::Diff(_)) => compare(arg(1)?, arg(2)?),
+        CoreConcreteLibfunc::Uint8(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint16(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint32(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint64(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint128(Uint128Concrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint8(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint16(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint32(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint64(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint128(Sint128Concrete::IsZero(_)) => match arg(0)? {
+            Some(range) => vec![
+                vec![range.intersection(&Range::closed(0, 0))],
+                vec![non_zero(&range)],
+            ],
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    })
+}
+
+/// Return which argument every result of every branch of a libfunc is a copy of, if any.
+fn copies(libfunc: &CoreConcreteLibfunc) -> Vec<Vec<Option<usize>>> {
+    match libfunc {
+        CoreConcreteLibfunc::Mem(
+            MemConcreteLibfunc::StoreTemp(_) | MemConcreteLibfunc::Rename(_),
+        )
+        | CoreConcreteLibfunc::UnwrapNonZero(_)
+        | CoreConcreteLibfunc::Cast(CastConcreteLibfunc::Upcast(_)) => vec![vec![Some(0)]],
+        CoreConcreteLibfunc::Mem(MemConcreteLibfunc::StoreLocal(_)) => vec![vec![Some(1)]],
+        CoreConcreteLibfunc::Dup(_) | CoreConcreteLibfunc::SnapshotTake(_) => {
+            vec![vec![Some(0), Some(0)]]
+        }
+        CoreConcreteLibfunc::Uint8(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint16(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint32(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint64(UintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Uint128(Uint128Concrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint8(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint16(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint32(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint64(SintConcrete::IsZero(_))
+        | CoreConcreteLibfunc::Sint128(Sint128Concrete::IsZero(_)) => vec![vec![], vec![Some(0)]],
+        _ => Vec::new(),
+    }
+}
+
+/// Return the ranges of the operands of a comparison in the branch where the lhs is greater than or
+/// equal to the rhs, and in the branch where it's smaller.
+fn compare(lhs: Option<Range>, rhs: Option<Range>) -> Vec<Vec<Option<Range>>> {
+    let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+        return Vec::new();
+    };
+    let narrow = |range: &Range, lower: BigInt, upper: BigInt| {
+        (lower < upper)
+            .then(|| Range::half_open(lower, upper))
+            .and_then(|bounds| range.intersection(&bounds))
+    };
+
+    vec![
+        vec![
+            None,
+            narrow(&lhs, rhs.lower.clone(), lhs.upper.clone()),
+            narrow(&rhs, rhs.lower.clone(), lhs.upper.clone()),
+        ],
+        vec![
+            None,
+            narrow(&lhs, lhs.lower.clone(), rhs.upper.clone() - 1),
+            narrow(&rhs, lhs.lower.clone() + 1, rhs.upper.clone()),
+        ],
+    ]
+}
+
+/// Return the range of a value known not to be zero. Zero can only be removed from the range when
+/// it's one of its bounds.
+fn non_zero(range: &Range) -> Option<Range> {
+    if range.lower.is_zero() {
+        (range.upper > BigInt::one()).then(|| Range::half_open(1, range.upper.clone()))
+    } else if range.upper.is_one() {
+        range
+            .lower
+            .is_negative()
+            .then(|| Range::half_open(range.lower.clone(), 0))
+    } else {
+        Some(range.clone())
+    }
+}
+
+/// Return the range of the result of an operation between values of the given ranges, without
+/// accounting for overflows.
+fn operation_range(operator: IntOperator, lhs: &Range, rhs: &Range) -> Range {
+    match operator {
+        IntOperator::OverflowingAdd => {
+            Range::half_open(&lhs.lower + &rhs.lower, &lhs.upper + &rhs.upper - 1)
+        }
+        IntOperator::OverflowingSub => {
+            Range::half_open(&lhs.lower - &rhs.upper + 1, &lhs.upper - &rhs.lower)
+        }
+    }
+}
+
+/// Return the range of a type, if it's an integer type or a bounded type.
+fn int_range(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    ty: &ConcreteTypeId,
+) -> Result<Option<Range>, Box<ProgramRegistryError>> {
+    Ok(match registry.get_type(ty)? {
+        CoreTypeConcrete::Uint8(_) => Some(Range::closed(u8::MIN, u8::MAX)),
+        CoreTypeConcrete::Uint16(_) => Some(Range::closed(u16::MIN, u16::MAX)),
+        CoreTypeConcrete::Uint32(_) => Some(Range::closed(u32::MIN, u32::MAX)),
+        CoreTypeConcrete::Uint64(_) => Some(Range::closed(u64::MIN, u64::MAX)),
+        CoreTypeConcrete::Uint128(_) => Some(Range::closed(u128::MIN, u128::MAX)),
+        CoreTypeConcrete::Sint8(_) => Some(Range::closed(i8::MIN, i8::MAX)),
+        CoreTypeConcrete::Sint16(_) => Some(Range::closed(i16::MIN, i16::MAX)),
+        CoreTypeConcrete::Sint32(_) => Some(Range::closed(i32::MIN, i32::MAX)),
+        CoreTypeConcrete::Sint64(_) => Some(Range::closed(i64::MIN, i64::MAX)),
+        CoreTypeConcrete::Sint128(_) => Some(Range::closed(i128::MIN, i128::MAX)),
+        _ => type_range(registry, ty)?,
+    })
+}
+
+/// Return the range of a type, if it's a bounded type.
+fn type_range(
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    ty: &ConcreteTypeId,
+) -> Result<Option<Range>, Box<ProgramRegistryError>> {
+    Ok(match registry.get_type(ty)? {
+        CoreTypeConcrete::BoundedInt(info) => Some(info.range.clone()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::NativeContext, executor::JitNativeExecutor, values::JitValue, OptLevel};
+    use cairo_lang_sierra::ProgramParser;
+
+    fn program() -> Program {
+        ProgramParser::new()
+            .parse(
+                "type RangeCheck = RangeCheck;
+                type u8 = u8;
+
+                libfunc c10 = u8_const<10>;
+                libfunc c20 = u8_const<20>;
+                libfunc add = u8_overflowing_add;
+                libfunc store_temp_rc = store_temp<RangeCheck>;
+                libfunc store_temp_u8 = store_temp<u8>;
+
+                c10() -> ([1]);
+                c20() -> ([2]);
+                add([0], [1], [2]) { fallthrough([3], [4]) 6([5], [6]) };
+                store_temp_rc([3]) -> ([3]);
+                store_temp_u8([4]) -> ([4]);
+                return([3], [4]);
+                store_temp_rc([5]) -> ([5]);
+                store_temp_u8([6]) -> ([6]);
+                return([5], [6]);
+
+                run_test@0([0]: RangeCheck) -> (RangeCheck, u8);",
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn int_range_analysis() {
+        let program = program();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let mut meta = IntRangeMeta::new(&program, &registry).unwrap();
+
+        meta.set_current_statement(StatementIdx(2));
+        assert_eq!(meta.arg_range(0), None);
+        assert_eq!(meta.arg_range(1), Some(&Range::closed(10, 10)));
+        assert_eq!(meta.arg_range(2), Some(&Range::closed(20, 20)));
+        assert!(meta.is_operation_in_range(IntOperator::OverflowingAdd, &Range::closed(0, 255)));
+        assert!(!meta.is_operation_in_range(IntOperator::OverflowingSub, &Range::closed(0, 255)));
+
+        // Only the non-overflowing branch has a known result.
+        meta.set_current_statement(StatementIdx(4));
+        assert_eq!(meta.arg_range(0), Some(&Range::closed(30, 30)));
+        meta.set_current_statement(StatementIdx(7));
+        assert_eq!(meta.arg_range(0), None);
+    }
+
+    #[test]
+    fn int_range_elided_check() {
+        let program = program();
+
+        let module = NativeContext::new().compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(&program.funcs[0].id, &[], None)
+            .unwrap();
+
+        assert_eq!(result.return_value, JitValue::Uint8(30));
+        assert_eq!(result.builtin_stats.range_check, 1);
+    }
+
+    #[test]
+    fn int_range_comparison() {
+        // Adds 50 to its argument when it's smaller than 200, which can't overflow.
+        let program = ProgramParser::new()
+            .parse(
+                "type RangeCheck = RangeCheck;
+                type u8 = u8;
+
+                libfunc c0 = u8_const<0>;
+                libfunc c50 = u8_const<50>;
+                libfunc c200 = u8_const<200>;
+                libfunc dup_u8 = dup<u8>;
+                libfunc drop_u8 = drop<u8>;
+                libfunc add = u8_overflowing_add;
+                libfunc sub = u8_overflowing_sub;
+                libfunc store_temp_rc = store_temp<RangeCheck>;
+                libfunc store_temp_u8 = store_temp<u8>;
+
+                c200() -> ([2]);
+                dup_u8([1]) -> ([1], [3]);
+                sub([0], [3], [2]) { fallthrough([4], [5]) 7([6], [7]) };
+                drop_u8([5]) -> ();
+                store_temp_rc([4]) -> ([4]);
+                store_temp_u8([1]) -> ([1]);
+                return([4], [1]);
+                drop_u8([7]) -> ();
+                c50() -> ([8]);
+                add([6], [1], [8]) { fallthrough([9], [10]) 13([11], [12]) };
+                store_temp_rc([9]) -> ([9]);
+                store_temp_u8([10]) -> ([10]);
+                return([9], [10]);
+                drop_u8([12]) -> ();
+                store_temp_rc([11]) -> ([11]);
+                c0() -> ([13]);
+                store_temp_u8([13]) -> ([13]);
+                return([11], [13]);
+
+                run_test@0([0]: RangeCheck, [1]: u8) -> (RangeCheck, u8);",
+            )
+            .unwrap();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let mut meta = IntRangeMeta::new(&program, &registry).unwrap();
+
+        // The copy of the argument is narrowed by both branches of the comparison.
+        meta.set_current_statement(StatementIdx(5));
+        assert_eq!(meta.arg_range(0), Some(&Range::closed(200, 255)));
+        meta.set_current_statement(StatementIdx(9));
+        assert_eq!(meta.arg_range(1), Some(&Range::closed(0, 199)));
+        assert!(meta.is_operation_in_range(IntOperator::OverflowingAdd, &Range::closed(0, 255)));
+
+        let module = NativeContext::new().compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        for (value, expected) in [(150, 200), (199, 249), (200, 200), (255, 255)] {
+            let result = executor
+                .invoke_dynamic(&program.funcs[0].id, &[JitValue::Uint8(value)], None)
+                .unwrap();
+            assert_eq!(result.return_value, JitValue::Uint8(expected));
+        }
+    }
+
+    #[test]
+    fn int_range_signed_comparison() {
+        let program = ProgramParser::new()
+            .parse(
+                "type RangeCheck = RangeCheck;
+                type i8 = i8;
+                type u8 = u8;
+                type NonZeroI8 = NonZero<i8>;
+
+                libfunc c0 = i8_const<0>;
+                libfunc dup_i8 = dup<i8>;
+                libfunc drop_u8 = drop<u8>;
+                libfunc drop_nz = drop<NonZeroI8>;
+                libfunc diff = i8_diff;
+                libfunc is_zero = i8_is_zero;
+                libfunc store_temp_rc = store_temp<RangeCheck>;
+                libfunc store_temp_i8 = store_temp<i8>;
+
+                c0() -> ([2]);
+                dup_i8([1]) -> ([1], [3]);
+                diff([0], [3], [2]) { fallthrough([4], [5]) 13([6], [7]) };
+                drop_u8([5]) -> ();
+                dup_i8([1]) -> ([1], [8]);
+                is_zero([8]) { fallthrough() 9([9]) };
+                store_temp_rc([4]) -> ([4]);
+                store_temp_i8([1]) -> ([1]);
+                return([4], [1]);
+                drop_nz([9]) -> ();
+                store_temp_rc([4]) -> ([4]);
+                store_temp_i8([1]) -> ([1]);
+                return([4], [1]);
+                drop_u8([7]) -> ();
+                store_temp_rc([6]) -> ([6]);
+                store_temp_i8([1]) -> ([1]);
+                return([6], [1]);
+
+                run_test@0([0]: RangeCheck, [1]: i8) -> (RangeCheck, i8);",
+            )
+            .unwrap();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let mut meta = IntRangeMeta::new(&program, &registry).unwrap();
+
+        // Not negative.
+        meta.set_current_statement(StatementIdx(4));
+        assert_eq!(meta.arg_range(0), Some(&Range::closed(0, 127)));
+        // Zero.
+        meta.set_current_statement(StatementIdx(7));
+        assert_eq!(meta.arg_range(0), Some(&Range::closed(0, 0)));
+        // Positive, both the `NonZero` and the copy.
+        meta.set_current_statement(StatementIdx(9));
+        assert_eq!(meta.arg_range(0), Some(&Range::closed(1, 127)));
+        meta.set_current_statement(StatementIdx(11));
+        assert_eq!(meta.arg_range(0), Some(&Range::closed(1, 127)));
+        // Negative.
+        meta.set_current_statement(StatementIdx(15));
+        assert_eq!(meta.arg_range(0), Some(&Range::closed(-128, -1)));
+    }
+
+    fn bitwise_program() -> Program {
+        ProgramParser::new()
+            .parse(
+                "type RangeCheck = RangeCheck;
+                type Bitwise = Bitwise;
+                type u8 = u8;
+                type u128 = u128;
+
+                libfunc upcast = upcast<u8, u128>;
+                libfunc bitwise = bitwise;
+                libfunc downcast = downcast<u128, u8>;
+                libfunc drop_u128 = drop<u128>;
+                libfunc c0 = u8_const<0>;
+                libfunc store_temp_rc = store_temp<RangeCheck>;
+                libfunc store_temp_bw = store_temp<Bitwise>;
+                libfunc store_temp_u8 = store_temp<u8>;
+
+                upcast([2]) -> ([4]);
+                upcast([3]) -> ([5]);
+                bitwise([1], [4], [5]) -> ([6], [7], [8], [9]);
+                drop_u128([8]) -> ();
+                drop_u128([9]) -> ();
+                downcast([0], [7]) { fallthrough([10], [11]) 10([12]) };
+                store_temp_rc([10]) -> ([10]);
+                store_temp_bw([6]) -> ([6]);
+                store_temp_u8([11]) -> ([11]);
+                return([10], [6], [11]);
+                store_temp_rc([12]) -> ([12]);
+                store_temp_bw([6]) -> ([6]);
+                c0() -> ([13]);
+                store_temp_u8([13]) -> ([13]);
+                return([12], [6], [13]);
+
+                run_test@0([0]: RangeCheck, [1]: Bitwise, [2]: u8, [3]: u8) -> (RangeCheck, Bitwise, u8);",
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn int_range_bitwise() {
+        let program = bitwise_program();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let mut meta = IntRangeMeta::new(&program, &registry).unwrap();
+
+        // The upcasted values keep the range of `u8`, and so does the result of `bitwise`.
+        meta.set_current_statement(StatementIdx(2));
+        assert_eq!(meta.arg_range(1), Some(&Range::closed(0, 255)));
+        assert_eq!(meta.arg_range(2), Some(&Range::closed(0, 255)));
+        meta.set_current_statement(StatementIdx(5));
+        assert!(meta.is_arg_in_range(1, &Range::closed(0, 255)));
+        assert!(!meta.is_arg_in_range(1, &Range::closed(0, 127)));
+
+        let module = NativeContext::new().compile(&program, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(
+                &program.funcs[0].id,
+                &[JitValue::Uint8(0x3C), JitValue::Uint8(0x0F)],
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(result.return_value, JitValue::Uint8(0x0C));
+        assert_eq!(result.builtin_stats.bitwise, 1);
+        assert_eq!(result.builtin_stats.range_check, 1);
+    }
+}
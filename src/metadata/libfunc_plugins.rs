@@ -0,0 +1,142 @@
+//! # Libfunc plugins
+//!
+//! Keeps the [plugins](crate::plugin) registered in a context, and which libfuncs of the program
+//! being compiled are built by each of them.
+
+use crate::{
+    error::{Error, Result},
+    plugin::{LibfuncPlugin, PLUGIN_API_VERSION},
+};
+use cairo_lang_sierra::{
+    extensions::{core::CoreLibfunc, GenericLibfunc},
+    ids::{ConcreteLibfuncId, GenericLibfuncId},
+    program::Program,
+};
+use std::{borrow::Cow, collections::HashMap, fmt, sync::Arc};
+
+/// Libfunc plugins metadata.
+#[derive(Clone, Default)]
+pub struct LibfuncPluginsMeta {
+    plugins: Vec<Arc<dyn LibfuncPlugin>>,
+    generic_ids: HashMap<GenericLibfuncId, Arc<dyn LibfuncPlugin>>,
+    concrete_ids: HashMap<ConcreteLibfuncId, Arc<dyn LibfuncPlugin>>,
+    /// The libfuncs replaced by a stand-in, which are no longer found by their generic id.
+    stand_ins: HashMap<ConcreteLibfuncId, Arc<dyn LibfuncPlugin>>,
+}
+
+impl LibfuncPluginsMeta {
+    /// Register a plugin, checking that it's compatible with the current plugin API and that its
+    /// libfuncs aren't already built by another plugin.
+    pub fn register(&mut self, plugin: Arc<dyn LibfuncPlugin>) -> Result<()> {
+        if !PLUGIN_API_VERSION.is_compatible_with(plugin.api_version()) {
+            return Err(Error::IncompatiblePlugin(format!(
+                "plugin `{}` requires the plugin API {}, but the current one is {}",
+                plugin.name(),
+                plugin.api_version(),
+                PLUGIN_API_VERSION
+            )));
+        }
+
+        let generic_ids = plugin.generic_libfuncs();
+        for generic_id in &generic_ids {
+            if generic_id.0 == "function_call" {
+                return Err(Error::IncompatiblePlugin(format!(
+                    "plugin `{}` can't build `function_call`",
+                    plugin.name()
+                )));
+            }
+            if let Some(other) = self.generic_ids.get(generic_id) {
+                return Err(Error::IncompatiblePlugin(format!(
+                    "plugins `{}` and `{}` both build `{}`",
+                    other.name(),
+                    plugin.name(),
+                    generic_id.0
+                )));
+            }
+        }
+
+        self.generic_ids.extend(
+            generic_ids
+                .into_iter()
+                .map(|generic_id| (generic_id, plugin.clone())),
+        );
+        self.plugins.push(plugin);
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Replace the libfuncs of a program which are unknown to the Sierra program registry by the
+    /// [stand-ins](LibfuncPlugin::stand_in) of the plugins which build them, so that the program
+    /// can be validated. Their concrete ids are kept, so they're still built by the plugins.
+    ///
+    /// Has to be called before creating the registry, and the program returned is the one to
+    /// compile.
+    pub(crate) fn resolve_stand_ins<'a>(&mut self, program: &'a Program) -> Cow<'a, Program> {
+        let mut program = Cow::Borrowed(program);
+
+        for idx in 0..program.libfunc_declarations.len() {
+            let long_id = &program.libfunc_declarations[idx].long_id;
+            if CoreLibfunc::by_id(&long_id.generic_id).is_some() {
+                continue;
+            }
+
+            let Some(plugin) = self.generic_ids.get(&long_id.generic_id) else {
+                continue;
+            };
+            if let Some(stand_in) = plugin.stand_in(long_id) {
+                let plugin = plugin.clone();
+                let declaration = &mut program.to_mut().libfunc_declarations[idx];
+                declaration.long_id = stand_in;
+                self.stand_ins.insert(declaration.id.clone(), plugin);
+            }
+        }
+
+        program
+    }
+
+    /// Find the concrete libfuncs of a program which are built by a plugin.
+    pub(crate) fn resolve(&mut self, program: &Program) {
+        self.concrete_ids = program
+            .libfunc_declarations
+            .iter()
+            .filter_map(|decl| {
+                self.generic_ids
+                    .get(&decl.long_id.generic_id)
+                    .map(|plugin| (decl.id.clone(), plugin.clone()))
+            })
+            .collect();
+    }
+
+    /// Return the plugin which builds a concrete libfunc, if any.
+    pub fn plugin(&self, id: &ConcreteLibfuncId) -> Option<Arc<dyn LibfuncPlugin>> {
+        self.stand_ins
+            .get(id)
+            .or_else(|| self.concrete_ids.get(id))
+            .cloned()
+    }
+}
+
+impl fmt::Debug for LibfuncPluginsMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.plugins.iter().map(|plugin| plugin.name()))
+            .finish()
+    }
+}
+
+impl PartialEq for LibfuncPluginsMeta {
+    fn eq(&self, other: &Self) -> bool {
+        self.plugins.len() == other.plugins.len()
+            && self
+                .plugins
+                .iter()
+                .zip(&other.plugins)
+                .all(|(lhs, rhs)| Arc::ptr_eq(lhs, rhs))
+    }
+}
+
+impl Eq for LibfuncPluginsMeta {}
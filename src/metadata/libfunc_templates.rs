@@ -0,0 +1,52 @@
+//! # Libfunc templates
+//!
+//! Some libfuncs generate the same operations every time they're invoked (ex. constants, or `dup`
+//! and `drop` of types which own heap memory). Big programs may invoke them thousands of times, and
+//! rebuilding them on every occurrence makes up a good part of the lowering time.
+//!
+//! The libfuncs registered here are built only once, before compiling the program's functions, into
+//! an internal template function. Every occurrence then becomes a call to its template, which LLVM
+//! will inline back when optimizing.
+//!
+//! Only libfuncs with a single branch whose operations don't depend on the statement they're
+//! invoked from can be templated. The rest (ex. the gas libfuncs, or function calls) are built
+//! inline as usual even when registered. The same happens with `dup` and `drop` of trivial types,
+//! since they don't generate any operation.
+
+use cairo_lang_sierra::ids::{ConcreteLibfuncId, GenericLibfuncId};
+use std::collections::{HashMap, HashSet};
+
+/// The generic libfuncs which are worth templating in most programs.
+pub const DEFAULT_LIBFUNC_TEMPLATES: &[&str] =
+    &["const_as_box", "const_as_immediate", "drop", "dup"];
+
+/// Libfunc templates metadata.
+#[derive(Clone, Debug, Default)]
+pub struct LibfuncTemplatesMeta {
+    generic_ids: HashSet<GenericLibfuncId>,
+    templates: HashMap<ConcreteLibfuncId, String>,
+}
+
+impl LibfuncTemplatesMeta {
+    /// Create the metadata, registering the given generic libfuncs.
+    pub fn new(generic_ids: impl IntoIterator<Item = impl Into<GenericLibfuncId>>) -> Self {
+        Self {
+            generic_ids: generic_ids.into_iter().map(Into::into).collect(),
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Return whether a generic libfunc has been registered.
+    pub fn is_registered(&self, generic_id: &GenericLibfuncId) -> bool {
+        self.generic_ids.contains(generic_id)
+    }
+
+    /// Return the symbol of the template function of a concrete libfunc, if it has one.
+    pub fn template(&self, id: &ConcreteLibfuncId) -> Option<&str> {
+        self.templates.get(id).map(String::as_str)
+    }
+
+    pub(crate) fn insert_template(&mut self, id: ConcreteLibfuncId, symbol: String) {
+        self.templates.insert(id, symbol);
+    }
+}
@@ -0,0 +1,61 @@
+//! # Overflow checks
+//!
+//! The builders compute some values on the host (ex. type sizes and field offsets) from the types
+//! and constants of the program, then narrow them into the integer types used by MLIR. Pathological
+//! programs (ex. with huge structs) may produce values which don't fit.
+//!
+//! When this metadata is present, those values fail the compilation with an
+//! [IntegerOverflow](crate::error::Error::IntegerOverflow) error. Otherwise they are truncated, like
+//! an `as` cast would do.
+
+use super::MetadataStorage;
+use crate::error::{Error, Result};
+use num_traits::AsPrimitive;
+
+/// Overflow checks metadata.
+///
+/// Its presence enables the checks; it doesn't hold any state.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OverflowChecksMeta;
+
+impl OverflowChecksMeta {
+    /// Narrow a value computed on the host. The `what` describes the value in the error message.
+    pub fn narrow<T>(metadata: &MetadataStorage, value: usize, what: &str) -> Result<T>
+    where
+        T: Copy + TryFrom<usize> + 'static,
+        usize: AsPrimitive<T>,
+    {
+        match metadata.get::<Self>() {
+            Some(_) => T::try_from(value).map_err(|_| Error::IntegerOverflow(what.to_string())),
+            None => Ok(value.as_()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn narrow() {
+        let mut metadata = MetadataStorage::new();
+        assert_eq!(
+            OverflowChecksMeta::narrow::<u32>(&metadata, 1 << 32 | 5, "a value").unwrap(),
+            5
+        );
+        assert_eq!(
+            OverflowChecksMeta::narrow::<i32>(&metadata, 7, "a value").unwrap(),
+            7
+        );
+
+        metadata.insert(OverflowChecksMeta);
+        assert!(matches!(
+            OverflowChecksMeta::narrow::<u32>(&metadata, 1 << 32 | 5, "a value"),
+            Err(Error::IntegerOverflow(_))
+        ));
+        assert_eq!(
+            OverflowChecksMeta::narrow::<i32>(&metadata, 7, "a value").unwrap(),
+            7
+        );
+    }
+}
@@ -0,0 +1,451 @@
+//! # Unrecoverable errors
+//!
+//! Every libfunc that may hit an unrecoverable error (ex. an invalid enum tag or a failed memory
+//! allocation) generates its failure path through this metadata, so that all of them behave as
+//! configured by the [PanicMode] of the compiler.
+//!
+//! When the metadata is missing (ex. when calling [compile](crate::compile) directly), the default
+//! mode is used.
+//!
+//! The [error](PanicMode::Error) and [hook](PanicMode::Hook) modes call the panic handler installed
+//! by the executor through the `cairo_native__panic_handler` global, which holds a pointer to an
+//! `extern "C" fn(msg_ptr: *const u8, msg_len: u64)`. When the handler is missing or returns (ex.
+//! when the code isn't run by one of our executors), the error aborts like in the default mode.
+
+use super::MetadataStorage;
+use crate::{block_ext::BlockExt, context::PanicMode, error::Result, libfuncs::LibfuncHelper};
+use melior::{
+    dialect::{cf, llvm, ods},
+    ir::{
+        attribute::{FlatSymbolRefAttribute, IntegerAttribute, StringAttribute, TypeAttribute},
+        operation::OperationBuilder,
+        r#type::IntegerType,
+        Attribute, Block, Identifier, Location, Module, Region, Value,
+    },
+    Context,
+};
+use std::collections::HashSet;
+
+/// The global which holds the panic handler installed by the executor.
+pub(crate) const PANIC_HANDLER_SYMBOL: &str = "cairo_native__panic_handler";
+
+/// Unrecoverable error codegen metadata.
+#[derive(Clone, Debug, Default)]
+pub struct PanicMeta {
+    mode: PanicMode,
+    /// The globals declared so far in the current module.
+    globals: HashSet<String>,
+}
+
+impl PanicMeta {
+    pub fn new(mode: PanicMode) -> Self {
+        Self {
+            mode,
+            globals: HashSet::new(),
+        }
+    }
+
+    /// Return the metadata of the current compilation, inserting the default one if it's missing.
+    pub fn get(metadata: &mut MetadataStorage) -> &mut Self {
+        metadata.get_or_insert_with(Self::default)
+    }
+
+    /// Return the mode of the metadata, or the default one if it's missing.
+    pub fn mode_of(metadata: &MetadataStorage) -> PanicMode {
+        metadata.get::<Self>().map(Self::mode).unwrap_or_default()
+    }
+
+    pub fn mode(&self) -> PanicMode {
+        self.mode
+    }
+
+    /// Terminate the block with an unrecoverable error.
+    pub fn build_panic<'ctx, 'this>(
+        &mut self,
+        context: &'ctx Context,
+        helper: &LibfuncHelper<'ctx, 'this>,
+        block: &'this Block<'ctx>,
+        location: Location<'ctx>,
+        msg: &str,
+    ) -> Result<()> {
+        let block = match self.mode {
+            PanicMode::Abort => block,
+            PanicMode::Trap => {
+                block.append_operation(OperationBuilder::new("llvm.intr.trap", location).build()?);
+                block.append_operation(llvm::unreachable(location));
+                return Ok(());
+            }
+            PanicMode::Error | PanicMode::Hook => {
+                self.build_handler_call(context, helper, block, location, msg)?
+            }
+        };
+
+        let k0 = block.const_int(context, location, 0, 1)?;
+        block.append_operation(cf::assert(context, k0, msg, location));
+        block.append_operation(llvm::unreachable(location));
+        Ok(())
+    }
+
+    /// Fail with an unrecoverable error unless `condition` is true.
+    ///
+    /// Returns the block where the code generation should continue, which may not be the one that
+    /// was passed in.
+    pub fn build_assert<'ctx, 'this>(
+        &mut self,
+        context: &'ctx Context,
+        helper: &LibfuncHelper<'ctx, 'this>,
+        block: &'this Block<'ctx>,
+        location: Location<'ctx>,
+        condition: Value<'ctx, 'this>,
+        msg: &str,
+    ) -> Result<&'this Block<'ctx>> {
+        match self.mode {
+            PanicMode::Abort => {
+                block.append_operation(cf::assert(context, condition, msg, location));
+                Ok(block)
+            }
+            PanicMode::Trap | PanicMode::Error | PanicMode::Hook => {
+                let continue_block = helper.append_block(Block::new(&[]));
+                let panic_block = helper.append_block(Block::new(&[]));
+
+                block.append_operation(cf::cond_br(
+                    context,
+                    condition,
+                    continue_block,
+                    panic_block,
+                    &[],
+                    &[],
+                    location,
+                ));
+                self.build_panic(context, helper, panic_block, location, msg)?;
+
+                Ok(continue_block)
+            }
+        }
+    }
+
+    /// Call the panic handler with `msg` if it has been installed. Returns the block where the
+    /// code generation should continue if it returns.
+    fn build_handler_call<'ctx, 'this>(
+        &mut self,
+        context: &'ctx Context,
+        helper: &LibfuncHelper<'ctx, 'this>,
+        block: &'this Block<'ctx>,
+        location: Location<'ctx>,
+        msg: &str,
+    ) -> Result<&'this Block<'ctx>> {
+        let ptr_ty = llvm::r#type::pointer(context, 0);
+
+        if self.globals.insert(PANIC_HANDLER_SYMBOL.to_string()) {
+            let region = Region::new();
+            let init_block = region.append_block(Block::new(&[]));
+            let null_ptr = init_block.append_op_result(
+                ods::llvm::mlir_zero(context, ptr_ty, Location::unknown(context)).into(),
+            )?;
+            init_block.append_operation(llvm::r#return(Some(null_ptr), Location::unknown(context)));
+
+            declare_global(
+                context,
+                helper.module,
+                PANIC_HANDLER_SYMBOL,
+                TypeAttribute::new(ptr_ty),
+                &[(
+                    Identifier::new(context, "linkage"),
+                    Attribute::parse(context, "#llvm.linkage<external>").unwrap(),
+                )],
+                region,
+            )?;
+        }
+
+        // Messages are deduplicated by their contents, so that the globals of the modules built by
+        // a parallel compilation can be merged. The symbol is the message itself (hex-encoded), so
+        // that different messages never share a symbol.
+        let msg_symbol = msg
+            .bytes()
+            .fold(String::from("panic_msg_"), |mut symbol, byte| {
+                symbol.push_str(&format!("{byte:02x}"));
+                symbol
+            });
+        if self.globals.insert(msg_symbol.clone()) {
+            declare_global(
+                context,
+                helper.module,
+                &msg_symbol,
+                TypeAttribute::new(llvm::r#type::array(
+                    IntegerType::new(context, 8).into(),
+                    msg.len().try_into()?,
+                )),
+                &[
+                    (
+                        Identifier::new(context, "linkage"),
+                        Attribute::parse(context, "#llvm.linkage<internal>").unwrap(),
+                    ),
+                    (
+                        Identifier::new(context, "constant"),
+                        Attribute::unit(context),
+                    ),
+                    (
+                        Identifier::new(context, "value"),
+                        StringAttribute::new(context, msg).into(),
+                    ),
+                ],
+                Region::new(),
+            )?;
+        }
+
+        let handler_ptr = block.append_op_result(
+            OperationBuilder::new("llvm.mlir.addressof", location)
+                .add_attributes(&[(
+                    Identifier::new(context, "global_name"),
+                    FlatSymbolRefAttribute::new(context, PANIC_HANDLER_SYMBOL).into(),
+                )])
+                .add_results(&[ptr_ty])
+                .build()?,
+        )?;
+        let handler = block.load(context, location, handler_ptr, ptr_ty)?;
+
+        let null_ptr =
+            block.append_op_result(ods::llvm::mlir_zero(context, ptr_ty, location).into())?;
+        let is_installed = block.append_op_result(
+            ods::llvm::icmp(
+                context,
+                IntegerType::new(context, 1).into(),
+                handler,
+                null_ptr,
+                IntegerAttribute::new(IntegerType::new(context, 64).into(), 1).into(),
+                location,
+            )
+            .into(),
+        )?;
+
+        let call_block = helper.append_block(Block::new(&[]));
+        let abort_block = helper.append_block(Block::new(&[]));
+        block.append_operation(cf::cond_br(
+            context,
+            is_installed,
+            call_block,
+            abort_block,
+            &[],
+            &[],
+            location,
+        ));
+
+        let msg_ptr = call_block.append_op_result(
+            OperationBuilder::new("llvm.mlir.addressof", location)
+                .add_attributes(&[(
+                    Identifier::new(context, "global_name"),
+                    FlatSymbolRefAttribute::new(context, &msg_symbol).into(),
+                )])
+                .add_results(&[ptr_ty])
+                .build()?,
+        )?;
+        let msg_len = call_block.const_int(context, location, msg.len(), 64)?;
+        call_block.append_operation(
+            OperationBuilder::new("llvm.call", location)
+                .add_operands(&[handler, msg_ptr, msg_len])
+                .build()?,
+        );
+        call_block.append_operation(cf::br(abort_block, &[], location));
+
+        Ok(abort_block)
+    }
+}
+
+pub(crate) fn declare_global<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    name: &str,
+    global_type: TypeAttribute<'ctx>,
+    attributes: &[(Identifier<'ctx>, Attribute<'ctx>)],
+    initializer: Region<'ctx>,
+) -> Result<()> {
+    module.body().append_operation(
+        OperationBuilder::new("llvm.mlir.global", Location::unknown(context))
+            .add_attributes(&[
+                (
+                    Identifier::new(context, "sym_name"),
+                    StringAttribute::new(context, name).into(),
+                ),
+                (Identifier::new(context, "global_type"), global_type.into()),
+            ])
+            .add_attributes(attributes)
+            .add_regions([initializer])
+            .build()?,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{CompilerConfig, NativeContext},
+        error::Error,
+        executor::{set_panic_hook, JitNativeExecutor},
+        libfuncs::LibfuncBuilder,
+        plugin::{LibfuncPlugin, PluginApiVersion, PLUGIN_API_VERSION},
+        utils::{find_function_id, test::load_cairo},
+        values::JitValue,
+        OptLevel,
+    };
+    use cairo_lang_sierra::{
+        extensions::core::{CoreConcreteLibfunc, CoreLibfunc, CoreType},
+        ids::GenericLibfuncId,
+        program_registry::ProgramRegistry,
+    };
+    use melior::dialect::arith::{self, CmpiPredicate};
+    use starknet_types_core::felt::Felt;
+    use std::sync::Mutex;
+
+    /// Makes `felt252_add` fail with an unrecoverable error when its left operand is zero.
+    struct FailingAddPlugin;
+
+    impl LibfuncPlugin for FailingAddPlugin {
+        fn name(&self) -> &str {
+            "failing-add"
+        }
+
+        fn api_version(&self) -> PluginApiVersion {
+            PLUGIN_API_VERSION
+        }
+
+        fn generic_libfuncs(&self) -> Vec<GenericLibfuncId> {
+            vec!["felt252_add".into()]
+        }
+
+        fn build<'ctx, 'this>(
+            &self,
+            context: &'ctx Context,
+            registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+            entry: &'this Block<'ctx>,
+            location: Location<'ctx>,
+            helper: &LibfuncHelper<'ctx, 'this>,
+            metadata: &mut MetadataStorage,
+            info: &CoreConcreteLibfunc,
+        ) -> Result<()> {
+            let k0 = entry.const_int(context, location, 0, 252)?;
+            let lhs_is_not_zero = entry.append_op_result(arith::cmpi(
+                context,
+                CmpiPredicate::Ne,
+                entry.argument(0)?.into(),
+                k0,
+                location,
+            ))?;
+            let entry = PanicMeta::get(metadata).build_assert(
+                context,
+                helper,
+                entry,
+                location,
+                lhs_is_not_zero,
+                "zero lhs",
+            )?;
+
+            info.build(context, registry, entry, location, helper, metadata)
+        }
+    }
+
+    fn run_failing_add(panic_mode: PanicMode, lhs: u8) -> std::result::Result<JitValue, Error> {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a + b
+            }
+        };
+
+        let context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_panic_mode(panic_mode))
+            .with_libfunc_plugin(FailingAddPlugin)
+            .unwrap();
+        let module = context.compile(&program.1, None).unwrap();
+
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        executor
+            .invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[
+                    JitValue::Felt252(Felt::from(lhs)),
+                    JitValue::Felt252(Felt::from(2)),
+                ],
+                None,
+            )
+            .map(|result| result.return_value)
+    }
+
+    #[test]
+    fn trap_mode() {
+        let program = load_cairo! {
+            enum MyEnum {
+                A: felt252,
+                B: u8,
+            }
+
+            fn value(x: Option<MyEnum>) -> felt252 {
+                match x {
+                    Option::Some(MyEnum::A(x)) => x,
+                    Option::Some(MyEnum::B(x)) => x.into(),
+                    Option::None => 0,
+                }
+            }
+
+            fn run_test() -> felt252 {
+                let mut values = array![MyEnum::B(3), MyEnum::A(4)];
+                let a = value(values.pop_front());
+                let b = value(values.pop_front());
+                a + b
+            }
+        };
+
+        let context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_panic_mode(PanicMode::Trap));
+        let module = context.compile(&program.1, None).unwrap();
+        assert!(module
+            .module()
+            .as_operation()
+            .to_string()
+            .contains("llvm.intr.trap"));
+
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[],
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(result.return_value, JitValue::Felt252(Felt::from(7)));
+    }
+
+    #[test]
+    fn error_mode() {
+        assert_eq!(
+            run_failing_add(PanicMode::Error, 1).unwrap(),
+            JitValue::Felt252(Felt::from(3))
+        );
+
+        let error = run_failing_add(PanicMode::Error, 0).unwrap_err();
+        assert!(matches!(error, Error::UnrecoverableError(ref message) if message == "zero lhs"));
+
+        // The state of the interrupted invocation doesn't leak into the next ones.
+        assert_eq!(
+            run_failing_add(PanicMode::Error, 1).unwrap(),
+            JitValue::Felt252(Felt::from(3))
+        );
+    }
+
+    #[test]
+    fn hook_mode() {
+        static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        set_panic_hook(|message| MESSAGES.lock().unwrap().push(message.to_string()));
+
+        assert_eq!(
+            run_failing_add(PanicMode::Hook, 1).unwrap(),
+            JitValue::Felt252(Felt::from(3))
+        );
+        assert!(MESSAGES.lock().unwrap().is_empty());
+
+        let error = run_failing_add(PanicMode::Hook, 0).unwrap_err();
+        assert!(matches!(error, Error::UnrecoverableError(ref message) if message == "zero lhs"));
+        assert_eq!(MESSAGES.lock().unwrap().as_slice(), ["zero lhs"]);
+    }
+}
@@ -0,0 +1,197 @@
+//! # Profiler
+//!
+//! When this metadata is present the compiler instruments every libfunc invocation to count them
+//! and measure the cycles spent on each one. Every function gets the counters of the current thread
+//! from the runtime library when it's entered, and the generated code updates them on its own
+//! before executing each statement. Check out the [profiler](crate::profiler) module for how the
+//! measurements are collected.
+
+#![cfg(feature = "with-profiler")]
+
+use crate::{block_ext::BlockExt, error::Result};
+use melior::{
+    dialect::{arith, func, llvm},
+    ir::{
+        attribute::{
+            DenseI32ArrayAttribute, FlatSymbolRefAttribute, StringAttribute, TypeAttribute,
+        },
+        operation::OperationBuilder,
+        r#type::{FunctionType, IntegerType},
+        Attribute, Block, Identifier, Location, Module, Region, Value,
+    },
+    Context,
+};
+
+const STATEMENT_SYMBOL: &str = "__profiler__statement";
+
+/// Profiling metadata. Its presence enables the instrumentation.
+#[derive(Debug)]
+pub struct ProfilerMeta {
+    is_declared: bool,
+    is_aarch64: bool,
+}
+
+impl ProfilerMeta {
+    /// Create the metadata for programs compiled for the given target triple, which determines
+    /// the cycle counter the generated code reads.
+    pub fn new(target_triple: &str) -> Self {
+        Self {
+            is_declared: false,
+            is_aarch64: target_triple.starts_with("aarch64"),
+        }
+    }
+
+    /// Get the counters of the current thread, with room for every statement of the program. Must
+    /// be called when entering each function.
+    pub fn build_buffer<'c, 'a>(
+        &mut self,
+        context: &'c Context,
+        module: &Module,
+        block: &'a Block<'c>,
+        statements_len: usize,
+        location: Location<'c>,
+    ) -> Result<Value<'c, 'a>>
+    where
+        'c: 'a,
+    {
+        let ptr_ty = llvm::r#type::pointer(context, 0);
+
+        if !self.is_declared {
+            module.body().append_operation(func::func(
+                context,
+                StringAttribute::new(context, STATEMENT_SYMBOL),
+                TypeAttribute::new(
+                    FunctionType::new(context, &[IntegerType::new(context, 64).into()], &[ptr_ty])
+                        .into(),
+                ),
+                Region::new(),
+                &[(
+                    Identifier::new(context, "sym_visibility"),
+                    StringAttribute::new(context, "private").into(),
+                )],
+                Location::unknown(context),
+            ));
+            self.is_declared = true;
+        }
+
+        let statements_len = block.const_int(context, location, statements_len, 64)?;
+        block.append_op_result(func::call(
+            context,
+            FlatSymbolRefAttribute::new(context, STATEMENT_SYMBOL),
+            &[statements_len],
+            &[ptr_ty],
+            location,
+        ))
+    }
+
+    /// Count the execution of the given statement, and attribute the cycles elapsed since the
+    /// previous one started to it. The layout of the buffer is the one of
+    /// [ProfilerBuffer](cairo_native_runtime::ProfilerBuffer).
+    pub fn measure_statement<'c, 'a>(
+        &self,
+        context: &'c Context,
+        block: &'a Block<'c>,
+        buffer: Value<'c, 'a>,
+        statement_idx: usize,
+        location: Location<'c>,
+    ) -> Result<()>
+    where
+        'c: 'a,
+    {
+        let ptr_ty = llvm::r#type::pointer(context, 0);
+        let i64_ty = IntegerType::new(context, 64).into();
+        let field_ptr = |idx: i32| {
+            block.append_op_result(llvm::get_element_ptr(
+                context,
+                buffer,
+                DenseI32ArrayAttribute::new(context, &[idx]),
+                i64_ty,
+                ptr_ty,
+                location,
+            ))
+        };
+
+        let now = self.build_cycle_counter(context, block, location)?;
+
+        // Finish the previous statement.
+        let last_ptr = block.load(context, location, buffer, ptr_ty)?;
+        let timestamp_ptr = field_ptr(1)?;
+        let timestamp = block.load(context, location, timestamp_ptr, i64_ty)?;
+        let elapsed = block.append_op_result(arith::subi(now, timestamp, location))?;
+        let last = block.load(context, location, last_ptr, i64_ty)?;
+        let last = block.append_op_result(arith::addi(last, elapsed, location))?;
+        block.store(context, location, last_ptr, last)?;
+
+        // Start the current one.
+        let entries_ptr = field_ptr(2)?;
+        let entries = block.load(context, location, entries_ptr, ptr_ty)?;
+        let entry_ptr = |idx: usize| {
+            block.append_op_result(llvm::get_element_ptr(
+                context,
+                entries,
+                DenseI32ArrayAttribute::new(context, &[idx.try_into()?]),
+                i64_ty,
+                ptr_ty,
+                location,
+            ))
+        };
+        let count_ptr = entry_ptr(2 * statement_idx)?;
+        let count = block.load(context, location, count_ptr, i64_ty)?;
+        let k1 = block.const_int(context, location, 1, 64)?;
+        let count = block.append_op_result(arith::addi(count, k1, location))?;
+        block.store(context, location, count_ptr, count)?;
+
+        let cycles_ptr = entry_ptr(2 * statement_idx + 1)?;
+        block.store(context, location, buffer, cycles_ptr)?;
+        block.store(context, location, timestamp_ptr, now)?;
+
+        Ok(())
+    }
+
+    /// Read the cycle counter: the time-stamp counter on x86-64, and the virtual counter on
+    /// AArch64, which (unlike the cycle counter) is always readable from user space.
+    fn build_cycle_counter<'c, 'a>(
+        &self,
+        context: &'c Context,
+        block: &'a Block<'c>,
+        location: Location<'c>,
+    ) -> Result<Value<'c, 'a>> {
+        let (asm, constraints) = if self.is_aarch64 {
+            ("mrs $0, cntvct_el0", "=r")
+        } else {
+            (
+                "rdtsc\n\tshlq $$32, %rdx\n\torq %rdx, %rax",
+                "={rax},~{rdx},~{flags}",
+            )
+        };
+
+        block.append_op_result(
+            OperationBuilder::new("llvm.inline_asm", location)
+                .add_attributes(&[
+                    (
+                        Identifier::new(context, "asm_string"),
+                        StringAttribute::new(context, asm).into(),
+                    ),
+                    (
+                        Identifier::new(context, "constraints"),
+                        StringAttribute::new(context, constraints).into(),
+                    ),
+                    (
+                        Identifier::new(context, "has_side_effects"),
+                        Attribute::unit(context),
+                    ),
+                ])
+                .add_results(&[IntegerType::new(context, 64).into()])
+                .build()?,
+        )
+    }
+
+    /// Create the metadata of a worker of a parallel compilation, whose module doesn't have the
+    /// declaration yet.
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            is_declared: false,
+            is_aarch64: self.is_aarch64,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! # Range check usage tracking
+//!
+//! Most libfuncs that receive a range check just increment it once, which is enough to keep the
+//! builtin's pointer opaque but doesn't match the number of range checks used by the Cairo VM. When
+//! this metadata is present the compiler replaces the range check returned by every libfunc in the
+//! table below with its input plus the exact number of range checks the CASM implementation uses on
+//! the branch taken, so that the reported builtin usage is identical to the VM's.
+//!
+//! The per-branch usages are the same ones the gas computation uses to price every libfunc. The
+//! only libfuncs not tracked are the ones whose usage depends on runtime values (ex. dictionary
+//! squashing), which keep the usage computed by their builder.
+//!
+//! Every entry is checked against the VM, on all of its branches, by the `range_check` integration
+//! tests. Changes to the table must be reflected there.
+
+use cairo_lang_sierra::extensions::{
+    array::ArrayConcreteLibfunc,
+    bytes31::Bytes31ConcreteLibfunc,
+    casts::{CastConcreteLibfunc, CastType},
+    core::CoreConcreteLibfunc,
+    ec::EcConcreteLibfunc,
+    gas::GasConcreteLibfunc,
+    int::{
+        signed::SintConcrete, signed128::Sint128Concrete, unsigned::UintConcrete,
+        unsigned128::Uint128Concrete, unsigned256::Uint256Concrete, unsigned512::Uint512Concrete,
+    },
+    starknet::StarkNetConcreteLibfunc,
+};
+
+/// Range check usage tracking metadata.
+///
+/// Its presence enables the tracking; it doesn't hold any state.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RangeCheckUsageMeta;
+
+impl RangeCheckUsageMeta {
+    /// Return the number of range checks used by the VM on every branch of the libfunc, or `None`
+    /// if it isn't known at compile time.
+    pub fn usage(&self, libfunc: &CoreConcreteLibfunc) -> Option<Vec<u64>> {
+        Some(match libfunc {
+            CoreConcreteLibfunc::Uint8(UintConcrete::Operation(_))
+            | CoreConcreteLibfunc::Uint16(UintConcrete::Operation(_))
+            | CoreConcreteLibfunc::Uint32(UintConcrete::Operation(_))
+            | CoreConcreteLibfunc::Uint64(UintConcrete::Operation(_))
+            | CoreConcreteLibfunc::Uint128(Uint128Concrete::Operation(_)) => vec![1, 1],
+            CoreConcreteLibfunc::Uint8(UintConcrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Uint16(UintConcrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Uint32(UintConcrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Uint64(UintConcrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Sint8(SintConcrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Sint16(SintConcrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Sint32(SintConcrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Sint64(SintConcrete::FromFelt252(_)) => vec![2, 3],
+            CoreConcreteLibfunc::Uint128(Uint128Concrete::FromFelt252(_))
+            | CoreConcreteLibfunc::Sint128(Sint128Concrete::FromFelt252(_)) => vec![1, 3],
+            CoreConcreteLibfunc::Uint8(UintConcrete::Divmod(_))
+            | CoreConcreteLibfunc::Uint16(UintConcrete::Divmod(_))
+            | CoreConcreteLibfunc::Uint32(UintConcrete::Divmod(_))
+            | CoreConcreteLibfunc::Uint64(UintConcrete::Divmod(_)) => vec![3],
+            CoreConcreteLibfunc::Uint8(UintConcrete::SquareRoot(_))
+            | CoreConcreteLibfunc::Uint16(UintConcrete::SquareRoot(_))
+            | CoreConcreteLibfunc::Uint32(UintConcrete::SquareRoot(_))
+            | CoreConcreteLibfunc::Uint64(UintConcrete::SquareRoot(_))
+            | CoreConcreteLibfunc::Uint128(Uint128Concrete::SquareRoot(_))
+            | CoreConcreteLibfunc::Uint128(Uint128Concrete::Divmod(_)) => vec![4],
+            CoreConcreteLibfunc::Uint128(Uint128Concrete::MulGuaranteeVerify(_)) => vec![9],
+            CoreConcreteLibfunc::Uint256(Uint256Concrete::Divmod(_)) => vec![6],
+            CoreConcreteLibfunc::Uint256(Uint256Concrete::SquareRoot(_)) => vec![7],
+            CoreConcreteLibfunc::Uint256(Uint256Concrete::InvModN(_)) => vec![9, 7],
+            CoreConcreteLibfunc::Uint512(Uint512Concrete::DivModU256(_)) => vec![12],
+            CoreConcreteLibfunc::Sint8(SintConcrete::Operation(_))
+            | CoreConcreteLibfunc::Sint16(SintConcrete::Operation(_))
+            | CoreConcreteLibfunc::Sint32(SintConcrete::Operation(_))
+            | CoreConcreteLibfunc::Sint64(SintConcrete::Operation(_)) => vec![2, 1, 1],
+            CoreConcreteLibfunc::Sint128(Sint128Concrete::Operation(_)) => vec![1, 1, 1],
+            CoreConcreteLibfunc::Sint8(SintConcrete::Diff(_))
+            | CoreConcreteLibfunc::Sint16(SintConcrete::Diff(_))
+            | CoreConcreteLibfunc::Sint32(SintConcrete::Diff(_))
+            | CoreConcreteLibfunc::Sint64(SintConcrete::Diff(_))
+            | CoreConcreteLibfunc::Sint128(Sint128Concrete::Diff(_)) => vec![1, 1],
+            CoreConcreteLibfunc::Cast(CastConcreteLibfunc::Downcast(info)) => {
+                if info.from_range.is_full_felt252_range() {
+                    vec![2, 3]
+                } else {
+                    match info.cast_type() {
+                        CastType {
+                            overflow_above: false,
+                            overflow_below: false,
+                        } => vec![0, 0],
+                        CastType {
+                            overflow_above: true,
+                            overflow_below: true,
+                        } => vec![2, 1],
+                        CastType { .. } => vec![1, 1],
+                    }
+                }
+            }
+            CoreConcreteLibfunc::Array(
+                ArrayConcreteLibfunc::Get(_) | ArrayConcreteLibfunc::Slice(_),
+            ) => vec![1, 1],
+            CoreConcreteLibfunc::Ec(EcConcreteLibfunc::PointFromX(_)) => vec![3, 0],
+            CoreConcreteLibfunc::Gas(
+                GasConcreteLibfunc::WithdrawGas(_) | GasConcreteLibfunc::BuiltinWithdrawGas(_),
+            ) => vec![1, 1],
+            CoreConcreteLibfunc::Bytes31(Bytes31ConcreteLibfunc::TryFromFelt252(_)) => vec![3, 3],
+            CoreConcreteLibfunc::StarkNet(
+                StarkNetConcreteLibfunc::ClassHashTryFromFelt252(_)
+                | StarkNetConcreteLibfunc::ContractAddressTryFromFelt252(_)
+                | StarkNetConcreteLibfunc::StorageAddressTryFromFelt252(_),
+            ) => vec![3, 3],
+            CoreConcreteLibfunc::StarkNet(
+                StarkNetConcreteLibfunc::StorageBaseAddressFromFelt252(_),
+            ) => vec![3],
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        context::{CompilerConfig, NativeContext},
+        executor::JitNativeExecutor,
+        starknet_stub::StubSyscallHandler,
+        utils::test::load_cairo,
+        values::JitValue,
+    };
+    use cairo_lang_sierra::program::Program;
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        static ref U128_DIV: (String, Program) = load_cairo! {
+            fn run_test(lhs: u128, rhs: u128) -> u128 {
+                lhs / rhs
+            }
+        };
+    }
+
+    fn range_check_usage(track: bool) -> usize {
+        let entry_point = format!("{0}::{0}::run_test", U128_DIV.0);
+        let entry_point_id = &U128_DIV
+            .1
+            .funcs
+            .iter()
+            .find(|x| x.id.debug_name.as_deref() == Some(&entry_point))
+            .expect("Test program entry point not found.")
+            .id;
+
+        let context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_range_check_tracking(track));
+        let module = context
+            .compile(&U128_DIV.1, None)
+            .expect("Could not compile test program to MLIR.");
+
+        let executor = JitNativeExecutor::from_native_module(module, Default::default());
+        executor
+            .invoke_dynamic_with_syscall_handler(
+                entry_point_id,
+                &[JitValue::Uint128(10), JitValue::Uint128(3)],
+                Some(u128::MAX),
+                &mut StubSyscallHandler::default(),
+            )
+            .unwrap()
+            .builtin_stats
+            .range_check
+    }
+
+    #[test]
+    fn range_check_usage_tracked() {
+        // The VM uses 4 range checks in `u128_safe_divmod`.
+        assert_eq!(range_check_usage(true), 4);
+    }
+
+    #[test]
+    fn range_check_usage_untracked() {
+        assert_eq!(range_check_usage(false), 1);
+    }
+}
@@ -1,19 +1,33 @@
 //! # Memory allocation external bindings
 //!
-//! This metadata ensures that the bindings to the C function `realloc` exist in the current
-//! compilation context.
+//! This metadata ensures that the bindings to the C functions `realloc` and `free` exist in the
+//! current compilation context.
+//!
+//! The generated code doesn't call them directly, but through the `cairo_native__realloc` and
+//! `cairo_native__free` wrappers. Those call the allocation hooks installed by the executor in the
+//! `cairo_native__realloc_hook` and `cairo_native__free_hook` globals instead, if any, so that the
+//! executor can keep track of the memory owned by each invocation (see
+//! [the execution arena](crate::executor)).
 
+use super::panic::declare_global;
+use crate::{block_ext::BlockExt, error::Result};
 use melior::{
-    dialect::{func, llvm},
+    dialect::{cf, func, llvm, ods},
     ir::{
-        attribute::{FlatSymbolRefAttribute, StringAttribute, TypeAttribute},
+        attribute::{FlatSymbolRefAttribute, IntegerAttribute, StringAttribute, TypeAttribute},
+        operation::OperationBuilder,
         r#type::{FunctionType, IntegerType},
-        Identifier, Location, Module, Operation, Region, Value,
+        Attribute, Block, Identifier, Location, Module, Operation, Region, Type, Value,
     },
     Context,
 };
 use std::marker::PhantomData;
 
+/// The global which holds the `realloc` hook installed by the executor.
+pub(crate) const REALLOC_HOOK_SYMBOL: &str = "cairo_native__realloc_hook";
+/// The global which holds the `free` hook installed by the executor.
+pub(crate) const FREE_HOOK_SYMBOL: &str = "cairo_native__free_hook";
+
 /// Memory allocation `realloc` metadata.
 #[derive(Debug)]
 pub struct ReallocBindingsMeta {
@@ -22,21 +36,14 @@ pub struct ReallocBindingsMeta {
 
 impl ReallocBindingsMeta {
     /// Register the bindings to the `realloc` C function and return the metadata.
-    pub fn new(context: &Context, module: &Module) -> Self {
+    pub fn new(context: &Context, module: &Module) -> Result<Self> {
+        let ptr_ty = llvm::r#type::pointer(context, 0);
+        let len_ty = IntegerType::new(context, 64).into();
+
         module.body().append_operation(func::func(
             context,
             StringAttribute::new(context, "realloc"),
-            TypeAttribute::new(
-                FunctionType::new(
-                    context,
-                    &[
-                        llvm::r#type::pointer(context, 0),
-                        IntegerType::new(context, 64).into(),
-                    ],
-                    &[llvm::r#type::pointer(context, 0)],
-                )
-                .into(),
-            ),
+            TypeAttribute::new(FunctionType::new(context, &[ptr_ty, len_ty], &[ptr_ty]).into()),
             Region::new(),
             &[(
                 Identifier::new(context, "sym_visibility"),
@@ -47,9 +54,7 @@ impl ReallocBindingsMeta {
         module.body().append_operation(func::func(
             context,
             StringAttribute::new(context, "free"),
-            TypeAttribute::new(
-                FunctionType::new(context, &[llvm::r#type::pointer(context, 0)], &[]).into(),
-            ),
+            TypeAttribute::new(FunctionType::new(context, &[ptr_ty], &[]).into()),
             Region::new(),
             &[(
                 Identifier::new(context, "sym_visibility"),
@@ -58,9 +63,28 @@ impl ReallocBindingsMeta {
             Location::unknown(context),
         ));
 
-        Self {
+        define_wrapper(
+            context,
+            module,
+            "cairo_native__realloc",
+            REALLOC_HOOK_SYMBOL,
+            "realloc",
+            &[ptr_ty, len_ty],
+            &[ptr_ty],
+        )?;
+        define_wrapper(
+            context,
+            module,
+            "cairo_native__free",
+            FREE_HOOK_SYMBOL,
+            "free",
+            &[ptr_ty],
+            &[],
+        )?;
+
+        Ok(Self {
             phantom: PhantomData,
-        }
+        })
     }
 
     /// Calls the `realloc` function, returns a op with 1 result: an opaque pointer.
@@ -72,7 +96,7 @@ impl ReallocBindingsMeta {
     ) -> Operation<'c> {
         func::call(
             context,
-            FlatSymbolRefAttribute::new(context, "realloc"),
+            FlatSymbolRefAttribute::new(context, "cairo_native__realloc"),
             &[ptr, len],
             &[llvm::r#type::pointer(context, 0)],
             location,
@@ -87,10 +111,150 @@ impl ReallocBindingsMeta {
     ) -> Operation<'c> {
         func::call(
             context,
-            FlatSymbolRefAttribute::new(context, "free"),
+            FlatSymbolRefAttribute::new(context, "cairo_native__free"),
             &[ptr],
             &[],
             location,
         )
     }
+
+    /// Load the `free` hook, which is null unless the executor has installed one. Used to let the
+    /// runtime library free memory allocated by the generated code.
+    pub fn free_hook<'c, 'a>(
+        context: &'c Context,
+        block: &'a Block<'c>,
+        location: Location<'c>,
+    ) -> Result<Value<'c, 'a>> {
+        let ptr_ty = llvm::r#type::pointer(context, 0);
+        let hook_ptr = block.append_op_result(
+            OperationBuilder::new("llvm.mlir.addressof", location)
+                .add_attributes(&[(
+                    Identifier::new(context, "global_name"),
+                    FlatSymbolRefAttribute::new(context, FREE_HOOK_SYMBOL).into(),
+                )])
+                .add_results(&[ptr_ty])
+                .build()?,
+        )?;
+
+        block.load(context, location, hook_ptr, ptr_ty)
+    }
+}
+
+/// Define a function which calls the hook in the global `hook_symbol` if it has been installed, or
+/// `fallback` otherwise.
+fn define_wrapper<'c>(
+    context: &'c Context,
+    module: &Module<'c>,
+    name: &str,
+    hook_symbol: &str,
+    fallback: &str,
+    arg_types: &[Type<'c>],
+    ret_types: &[Type<'c>],
+) -> Result<()> {
+    let location = Location::unknown(context);
+    let ptr_ty = llvm::r#type::pointer(context, 0);
+
+    let init_region = Region::new();
+    let init_block = init_region.append_block(Block::new(&[]));
+    let null_ptr =
+        init_block.append_op_result(ods::llvm::mlir_zero(context, ptr_ty, location).into())?;
+    init_block.append_operation(llvm::r#return(Some(null_ptr), location));
+    declare_global(
+        context,
+        module,
+        hook_symbol,
+        TypeAttribute::new(ptr_ty),
+        &[(
+            Identifier::new(context, "linkage"),
+            Attribute::parse(context, "#llvm.linkage<external>").unwrap(),
+        )],
+        init_region,
+    )?;
+
+    let region = Region::new();
+    let entry = region.append_block(Block::new(
+        &arg_types
+            .iter()
+            .map(|&ty| (ty, location))
+            .collect::<Vec<_>>(),
+    ));
+    let hook_block = region.append_block(Block::new(&[]));
+    let fallback_block = region.append_block(Block::new(&[]));
+
+    let args = (0..arg_types.len())
+        .map(|i| Ok(entry.argument(i)?.into()))
+        .collect::<Result<Vec<Value>>>()?;
+
+    let hook_ptr = entry.append_op_result(
+        OperationBuilder::new("llvm.mlir.addressof", location)
+            .add_attributes(&[(
+                Identifier::new(context, "global_name"),
+                FlatSymbolRefAttribute::new(context, hook_symbol).into(),
+            )])
+            .add_results(&[ptr_ty])
+            .build()?,
+    )?;
+    let hook = entry.load(context, location, hook_ptr, ptr_ty)?;
+    let null_ptr =
+        entry.append_op_result(ods::llvm::mlir_zero(context, ptr_ty, location).into())?;
+    let is_installed = entry.append_op_result(
+        ods::llvm::icmp(
+            context,
+            IntegerType::new(context, 1).into(),
+            hook,
+            null_ptr,
+            IntegerAttribute::new(IntegerType::new(context, 64).into(), 1).into(),
+            location,
+        )
+        .into(),
+    )?;
+    entry.append_operation(cf::cond_br(
+        context,
+        is_installed,
+        &hook_block,
+        &fallback_block,
+        &[],
+        &[],
+        location,
+    ));
+
+    let hook_call = hook_block.append_operation(
+        OperationBuilder::new("llvm.call", location)
+            .add_operands(&[&[hook], args.as_slice()].concat())
+            .add_results(ret_types)
+            .build()?,
+    );
+    hook_block.append_operation(func::r#return(
+        &hook_call.results().map(Into::into).collect::<Vec<Value>>(),
+        location,
+    ));
+
+    let fallback_call = fallback_block.append_operation(func::call(
+        context,
+        FlatSymbolRefAttribute::new(context, fallback),
+        &args,
+        ret_types,
+        location,
+    ));
+    fallback_block.append_operation(func::r#return(
+        &fallback_call
+            .results()
+            .map(Into::into)
+            .collect::<Vec<Value>>(),
+        location,
+    ));
+
+    module.body().append_operation(func::func(
+        context,
+        StringAttribute::new(context, name),
+        TypeAttribute::new(FunctionType::new(context, arg_types, ret_types).into()),
+        region,
+        &[(
+            Identifier::new(context, "sym_visibility"),
+            StringAttribute::new(context, "private").into(),
+        )],
+        location,
+    ));
+
+    Ok(())
 }
@@ -0,0 +1,215 @@
+//! # Recursion information
+//!
+//! Finds the functions which may call themselves, either directly or through other functions.
+//!
+//! Direct tail recursion is already compiled into loops (check out the
+//! [tail recursion metadata](super::tail_recursion)), so the self-calls generated by Cairo loops
+//! aren't part of the call graph. Any other kind of recursion uses a native stack frame for every
+//! call. Unlike the Cairo VM, which is only limited by the available gas, deep
+//! recursion would overflow the native stack. The executors use this metadata to run programs with
+//! recursive functions on a [dedicated stack](crate::executor) which is large enough for them.
+
+use crate::{compiler::forwards_results_to_return, error::Error, libfuncs::LibfuncBuilder};
+use cairo_lang_sierra::{
+    extensions::core::{CoreLibfunc, CoreType},
+    ids::FunctionId,
+    program::{Program, Statement},
+    program_registry::ProgramRegistry,
+};
+use std::collections::{HashMap, HashSet};
+
+/// The set of recursive functions of a program.
+#[derive(Clone, Debug, Default)]
+pub struct RecursionMeta {
+    recursive_functions: HashSet<FunctionId>,
+}
+
+impl RecursionMeta {
+    /// Build the call graph of the program and find the functions which are part of a cycle.
+    pub fn new(
+        program: &Program,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    ) -> Result<Self, Error> {
+        let mut call_graph = HashMap::<&FunctionId, HashSet<&FunctionId>>::new();
+        for function in &program.funcs {
+            let callees = call_graph.entry(&function.id).or_default();
+
+            let mut visited = HashSet::new();
+            let mut queue = vec![function.entry_point];
+            while let Some(statement_idx) = queue.pop() {
+                if !visited.insert(statement_idx) {
+                    continue;
+                }
+
+                if let Statement::Invocation(invocation) = &program.statements[statement_idx.0] {
+                    if let Some(callee) = registry
+                        .get_libfunc(&invocation.libfunc_id)?
+                        .is_function_call()
+                    {
+                        // Loops are self-calls whose results are returned unchanged, which are
+                        // compiled into native loops.
+                        let is_loop = callee == &function.id
+                            && forwards_results_to_return(
+                                registry,
+                                &program.statements,
+                                statement_idx,
+                                invocation,
+                            )?;
+                        if !is_loop {
+                            callees.insert(callee);
+                        }
+                    }
+
+                    queue.extend(
+                        invocation
+                            .branches
+                            .iter()
+                            .map(|branch| statement_idx.next(&branch.target)),
+                    );
+                }
+            }
+        }
+
+        // A function is recursive if it can be reached from any of its callees.
+        let recursive_functions = program
+            .funcs
+            .iter()
+            .filter(|function| {
+                let mut visited = HashSet::new();
+                let mut queue = call_graph[&function.id].iter().copied().collect::<Vec<_>>();
+                while let Some(id) = queue.pop() {
+                    if id == &function.id {
+                        return true;
+                    }
+                    if visited.insert(id) {
+                        queue.extend(call_graph.get(id).into_iter().flatten().copied());
+                    }
+                }
+
+                false
+            })
+            .map(|function| function.id.clone())
+            .collect();
+
+        Ok(Self {
+            recursive_functions,
+        })
+    }
+
+    /// Return whether the function may call itself, either directly or through other functions.
+    pub fn is_recursive(&self, function_id: &FunctionId) -> bool {
+        self.recursive_functions.contains(function_id)
+    }
+
+    /// Return whether the program has any recursive function.
+    pub fn has_recursion(&self) -> bool {
+        !self.recursive_functions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        utils::test::{load_cairo, run_program_assert_output},
+        values::JitValue,
+    };
+
+    #[test]
+    fn recursion_analysis() {
+        let program = load_cairo! {
+            #[inline(never)]
+            fn is_even(n: felt252) -> bool {
+                if n == 0 {
+                    true
+                } else {
+                    is_odd(n - 1)
+                }
+            }
+
+            #[inline(never)]
+            fn is_odd(n: felt252) -> bool {
+                if n == 0 {
+                    false
+                } else {
+                    is_even(n - 1)
+                }
+            }
+
+            #[inline(never)]
+            fn not_recursive(n: felt252) -> felt252 {
+                n + 1
+            }
+
+            fn run_test(n: felt252) -> (bool, felt252) {
+                (is_even(n), not_recursive(n))
+            }
+        };
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program.1).unwrap();
+        let meta = RecursionMeta::new(&program.1, &registry).unwrap();
+
+        let is_recursive = |name: &str| {
+            let name = format!("{0}::{0}::{name}", program.0);
+            let function = program
+                .1
+                .funcs
+                .iter()
+                .find(|x| x.id.debug_name.as_deref() == Some(&name))
+                .unwrap();
+            meta.is_recursive(&function.id)
+        };
+
+        assert!(meta.has_recursion());
+        assert!(is_recursive("is_even"));
+        assert!(is_recursive("is_odd"));
+        assert!(!is_recursive("not_recursive"));
+        assert!(!is_recursive("run_test"));
+    }
+
+    #[test]
+    fn loops_are_not_recursive() {
+        let program = load_cairo! {
+            fn run_test(n: felt252) -> felt252 {
+                let mut acc = 0;
+                let mut i = 0;
+                loop {
+                    if i == n {
+                        break acc;
+                    }
+                    acc += i;
+                    i += 1;
+                }
+            }
+        };
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program.1).unwrap();
+        let meta = RecursionMeta::new(&program.1, &registry).unwrap();
+
+        assert!(!meta.has_recursion());
+    }
+
+    #[test]
+    fn deep_recursion() {
+        // Not tail recursive, so every call uses a stack frame. The depth is too much for the
+        // default stack of the test threads.
+        let program = load_cairo! {
+            fn sum(n: felt252) -> felt252 {
+                if n == 0 {
+                    0
+                } else {
+                    n + sum(n - 1)
+                }
+            }
+
+            fn run_test() -> felt252 {
+                sum(100000)
+            }
+        };
+
+        run_program_assert_output(
+            &program,
+            "run_test",
+            &[],
+            JitValue::Felt252(5000050000_u64.into()),
+        );
+    }
+}
@@ -462,15 +462,16 @@ impl RuntimeBindingsMeta {
         )))
     }
 
-    /// Register if necessary, then invoke the `dict_alloc_new()` function.
+    /// Register if necessary, then invoke the `dict_free()` function.
     ///
-    /// Returns a opaque pointer as the result.
+    /// The entries are freed with `free_hook`, or with `free` when it's null.
     #[allow(clippy::too_many_arguments)]
     pub fn dict_alloc_free<'c, 'a>(
         &mut self,
         context: &'c Context,
         module: &Module,
         ptr: Value<'c, 'a>,
+        free_hook: Value<'c, 'a>,
         block: &'a Block<'c>,
         location: Location<'c>,
     ) -> Result<OperationRef<'c, 'a>>
@@ -482,7 +483,15 @@ impl RuntimeBindingsMeta {
                 context,
                 StringAttribute::new(context, "cairo_native__dict_free"),
                 TypeAttribute::new(
-                    FunctionType::new(context, &[llvm::r#type::pointer(context, 0)], &[]).into(),
+                    FunctionType::new(
+                        context,
+                        &[
+                            llvm::r#type::pointer(context, 0),
+                            llvm::r#type::pointer(context, 0),
+                        ],
+                        &[],
+                    )
+                    .into(),
                 ),
                 Region::new(),
                 &[(
@@ -496,7 +505,7 @@ impl RuntimeBindingsMeta {
         Ok(block.append_operation(func::call(
             context,
             FlatSymbolRefAttribute::new(context, "cairo_native__dict_free"),
-            &[ptr],
+            &[ptr, free_hook],
             &[],
             location,
         )))
@@ -36,6 +36,13 @@
 //! The same algorithm can be applied multiple times if there are multiple tail-recursive calls
 //! within a function. The compiler should create a different depth counter for each recursive call
 //! in the function.
+//!
+//! Cairo loops are a special case: they're generated as tail-recursive functions whose recursive
+//! call results are returned unchanged. Since unwinding those calls would only forward the same
+//! values up, the compiler marks them as
+//! [forwarding their results](TailRecursionMeta::forwards_results) and the libfunc builder should
+//! emit a plain jump into the recursion target without touching the depth counter nor setting a
+//! return target. The result is a native loop.
 
 use melior::ir::{Block, BlockRef, Value, ValueLike};
 use mlir_sys::{MlirBlock, MlirValue};
@@ -50,6 +57,8 @@ pub struct TailRecursionMeta {
 
     recursion_target: MlirBlock,
     return_target: Option<MlirBlock>,
+
+    forwards_results: bool,
 }
 
 impl TailRecursionMeta {
@@ -59,6 +68,7 @@ impl TailRecursionMeta {
             depth_counter: depth_counter.to_raw(),
             recursion_target: recursion_target.to_raw(),
             return_target: None,
+            forwards_results: false,
         }
     }
 
@@ -82,4 +92,15 @@ impl TailRecursionMeta {
     pub fn set_return_target(&mut self, block: &Block) {
         self.return_target = Some(block.to_raw());
     }
+
+    /// Whether the results of the recursive call are returned unchanged, which means that it
+    /// doesn't need to be unwound.
+    pub fn forwards_results(&self) -> bool {
+        self.forwards_results
+    }
+
+    /// Mark the recursive call as returning its results unchanged.
+    pub fn set_forwards_results(&mut self, forwards_results: bool) {
+        self.forwards_results = forwards_results;
+    }
 }
@@ -1,9 +1,17 @@
-use crate::metadata::MetadataStorage;
+//! # Compiled modules
+//!
+//! A [NativeModule] is the output of [NativeContext::compile](crate::context::NativeContext::compile):
+//! the lowered MLIR module along with everything the executors need to call into it (the program
+//! registry and the compilation metadata). It's not executable by itself; it has to be turned into
+//! either a [JitNativeExecutor](crate::executor::JitNativeExecutor) or an
+//! [AotNativeExecutor](crate::executor::AotNativeExecutor) first.
+
+use crate::{error::Result, metadata::MetadataStorage};
 use cairo_lang_sierra::{
     extensions::core::{CoreLibfunc, CoreType},
     program_registry::ProgramRegistry,
 };
-use melior::ir::Module;
+use melior::ir::{operation::OperationPrintingFlags, Module};
 use std::{any::Any, fmt::Debug};
 
 /// A MLIR module in the context of Cairo Native.
@@ -69,6 +77,16 @@ impl<'m> NativeModule<'m> {
     pub fn program_registry(&self) -> &ProgramRegistry<CoreType, CoreLibfunc> {
         &self.registry
     }
+
+    /// Serialize the (already lowered) MLIR module, including its locations, so that the code
+    /// generation can be finished elsewhere. Check out
+    /// [load_module](crate::context::NativeContext::load_module) to load it back.
+    pub fn to_mlir_string(&self) -> Result<String> {
+        Ok(self
+            .module
+            .as_operation()
+            .to_string_with_flags(OperationPrintingFlags::new().enable_debug_info(true, false))?)
+    }
 }
 
 impl Debug for NativeModule<'_> {
@@ -80,7 +98,13 @@ impl Debug for NativeModule<'_> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::context::NativeContext;
+    use crate::{
+        context::NativeContext,
+        executor::JitNativeExecutor,
+        utils::{find_function_id, test::load_cairo},
+        values::JitValue,
+        OptLevel,
+    };
     use cairo_lang_sierra::ProgramParser;
     use melior::ir::Location;
     use starknet_types_core::felt::Felt;
@@ -167,4 +191,34 @@ mod test {
         // Assert that the re-inserted metadata of type u32 is retrieved correctly
         assert_eq!(module.get_metadata::<u32>(), Some(&44u32));
     }
+
+    #[test]
+    fn mlir_round_trip() {
+        let program = load_cairo! {
+            fn run_test(x: felt252) -> felt252 {
+                x * 2 + 1
+            }
+        };
+
+        let mlir = NativeContext::new()
+            .compile(&program.1, None)
+            .unwrap()
+            .to_mlir_string()
+            .unwrap();
+
+        // Load it into a different context, like another process would.
+        let context = NativeContext::new();
+        let module = context.load_module(&program.1, &mlir).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[JitValue::Felt252(10.into())],
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(result.return_value, JitValue::Felt252(21.into()));
+        assert!(context.load_module(&program.1, "not mlir").is_err());
+    }
 }
@@ -0,0 +1,309 @@
+//! # Libfunc plugins
+//!
+//! Plugins let downstream crates supply their own builders for libfuncs, matched by their generic
+//! id, without forking the compiler. This is meant for prototyping corelib extensions or
+//! experimenting with alternative lowerings of existing libfuncs.
+//!
+//! A plugin takes over every occurrence of the generic libfuncs it registers, and receives the
+//! same arguments as the builtin builders. It may still delegate to the builtin implementation
+//! through [LibfuncBuilder](crate::libfuncs::LibfuncBuilder) (ex. to instrument it).
+//!
+//! Programs are validated by the Sierra program registry before the lowering, which rejects the
+//! libfuncs it doesn't know. A plugin can still build an unknown libfunc by giving a builtin
+//! [stand-in](LibfuncPlugin::stand_in) for it, which is only used for the validation. Function
+//! calls can't be overridden, since the compiler handles them specially.
+//!
+//! ## Versioning
+//!
+//! The plugin API follows semantic versioning, independently from the crate version. Every plugin
+//! reports the [version of the API](PLUGIN_API_VERSION) it was written against, and is only
+//! accepted when it has the same major version and a minor version not newer than the current one.
+//!
+//! ```
+//! # use cairo_native::plugin::{PluginApiVersion, PLUGIN_API_VERSION};
+//! assert!(PLUGIN_API_VERSION.is_compatible_with(PluginApiVersion::new(1, 0)));
+//! assert!(!PLUGIN_API_VERSION.is_compatible_with(PluginApiVersion::new(2, 0)));
+//! ```
+
+use crate::{error::Result, libfuncs::LibfuncHelper, metadata::MetadataStorage};
+use cairo_lang_sierra::{
+    extensions::core::{CoreConcreteLibfunc, CoreLibfunc, CoreType},
+    ids::GenericLibfuncId,
+    program::ConcreteLibfuncLongId,
+    program_registry::ProgramRegistry,
+};
+use melior::{
+    ir::{Block, Location},
+    Context,
+};
+use std::fmt;
+
+/// The current version of the plugin API.
+pub const PLUGIN_API_VERSION: PluginApiVersion = PluginApiVersion::new(1, 1);
+
+/// A version of the plugin API.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PluginApiVersion {
+    /// Incremented on every breaking change to the API.
+    pub major: u32,
+    /// Incremented on every backwards compatible addition to the API.
+    pub minor: u32,
+}
+
+impl PluginApiVersion {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Return whether a plugin written against `required` can be used with this version.
+    pub fn is_compatible_with(&self, required: PluginApiVersion) -> bool {
+        self.major == required.major && self.minor >= required.minor
+    }
+}
+
+impl fmt::Display for PluginApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A plugin which builds some generic libfuncs.
+pub trait LibfuncPlugin: Send + Sync {
+    /// The name of the plugin, used in error messages and logs.
+    fn name(&self) -> &str;
+
+    /// The version of the plugin API this plugin was written against. Usually
+    /// [PLUGIN_API_VERSION] at the time it was written.
+    fn api_version(&self) -> PluginApiVersion;
+
+    /// The generic libfuncs built by this plugin.
+    fn generic_libfuncs(&self) -> Vec<GenericLibfuncId>;
+
+    /// Return the builtin libfunc which stands in for one of the libfuncs of this plugin that the
+    /// Sierra program registry doesn't know, or `None` if there's no stand-in for it.
+    ///
+    /// The stand-in must have the same signature as the libfunc. It's only used to validate the
+    /// program: the libfunc is still built by the plugin, which receives the concrete info of the
+    /// stand-in.
+    ///
+    /// Available since the version 1.1 of the API.
+    fn stand_in(&self, _long_id: &ConcreteLibfuncLongId) -> Option<ConcreteLibfuncLongId> {
+        None
+    }
+
+    /// Generate the MLIR operations of a libfunc, like
+    /// [LibfuncBuilder::build](crate::libfuncs::LibfuncBuilder::build) does for the builtin ones.
+    #[allow(clippy::too_many_arguments)]
+    fn build<'ctx, 'this>(
+        &self,
+        context: &'ctx Context,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+        entry: &'this Block<'ctx>,
+        location: Location<'ctx>,
+        helper: &LibfuncHelper<'ctx, 'this>,
+        metadata: &mut MetadataStorage,
+        info: &CoreConcreteLibfunc,
+    ) -> Result<()>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::NativeContext, error::Error, executor::JitNativeExecutor,
+        libfuncs::LibfuncBuilder, utils::test::load_cairo, values::JitValue, OptLevel,
+    };
+    use cairo_lang_sierra::{program::GenericArg, ProgramParser};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Counts the occurrences of `felt252_add`, delegating to the builtin implementation.
+    #[derive(Default)]
+    struct CountingPlugin {
+        count: Arc<AtomicUsize>,
+        api_version: Option<PluginApiVersion>,
+    }
+
+    impl LibfuncPlugin for CountingPlugin {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn api_version(&self) -> PluginApiVersion {
+            self.api_version.unwrap_or(PLUGIN_API_VERSION)
+        }
+
+        fn generic_libfuncs(&self) -> Vec<GenericLibfuncId> {
+            vec!["felt252_add".into()]
+        }
+
+        fn build<'ctx, 'this>(
+            &self,
+            context: &'ctx Context,
+            registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+            entry: &'this Block<'ctx>,
+            location: Location<'ctx>,
+            helper: &LibfuncHelper<'ctx, 'this>,
+            metadata: &mut MetadataStorage,
+            info: &CoreConcreteLibfunc,
+        ) -> Result<()> {
+            self.count.fetch_add(1, Ordering::Relaxed);
+            info.build(context, registry, entry, location, helper, metadata)
+        }
+    }
+
+    #[test]
+    fn plugin_builds_libfunc() {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252, c: felt252) -> felt252 {
+                a + b + c
+            }
+        };
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let context = NativeContext::new()
+            .with_libfunc_plugin(CountingPlugin {
+                count: count.clone(),
+                api_version: None,
+            })
+            .unwrap();
+        let module = context.compile(&program.1, None).unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+
+        let entry_point = program
+            .1
+            .funcs
+            .iter()
+            .find(|x| x.id.debug_name.as_deref().unwrap().ends_with("::run_test"))
+            .unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(
+                &entry_point.id,
+                &[
+                    JitValue::Felt252(1.into()),
+                    JitValue::Felt252(2.into()),
+                    JitValue::Felt252(3.into()),
+                ],
+                None,
+            )
+            .unwrap();
+        assert_eq!(result.return_value, JitValue::Felt252(6.into()));
+    }
+
+    /// Builds the unknown `felt252_double` libfunc, standing in for `felt252_mul_const<2>`.
+    #[derive(Default)]
+    struct StandInPlugin {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl LibfuncPlugin for StandInPlugin {
+        fn name(&self) -> &str {
+            "stand-in"
+        }
+
+        fn api_version(&self) -> PluginApiVersion {
+            PLUGIN_API_VERSION
+        }
+
+        fn generic_libfuncs(&self) -> Vec<GenericLibfuncId> {
+            vec!["felt252_double".into()]
+        }
+
+        fn stand_in(&self, long_id: &ConcreteLibfuncLongId) -> Option<ConcreteLibfuncLongId> {
+            assert_eq!(long_id.generic_id.0, "felt252_double");
+            Some(ConcreteLibfuncLongId {
+                generic_id: "felt252_mul_const".into(),
+                generic_args: vec![GenericArg::Value(2.into())],
+            })
+        }
+
+        fn build<'ctx, 'this>(
+            &self,
+            context: &'ctx Context,
+            registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+            entry: &'this Block<'ctx>,
+            location: Location<'ctx>,
+            helper: &LibfuncHelper<'ctx, 'this>,
+            metadata: &mut MetadataStorage,
+            info: &CoreConcreteLibfunc,
+        ) -> Result<()> {
+            self.count.fetch_add(1, Ordering::Relaxed);
+            info.build(context, registry, entry, location, helper, metadata)
+        }
+    }
+
+    #[test]
+    fn plugin_builds_unknown_libfunc() {
+        let program = ProgramParser::new()
+            .parse(
+                r#"
+                type felt252 = felt252;
+
+                libfunc double = felt252_double;
+                libfunc store_temp_felt252 = store_temp<felt252>;
+
+                double([0]) -> ([1]);
+                store_temp_felt252([1]) -> ([1]);
+                return([1]);
+
+                run_test@0([0]: felt252) -> (felt252);
+            "#,
+            )
+            .unwrap();
+
+        // Without the plugin the registry rejects the libfunc.
+        assert!(NativeContext::new().compile(&program, None).is_err());
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let context = NativeContext::new()
+            .with_libfunc_plugin(StandInPlugin {
+                count: count.clone(),
+            })
+            .unwrap();
+        let module = context.compile(&program, None).unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(&program.funcs[0].id, &[JitValue::Felt252(21.into())], None)
+            .unwrap();
+        assert_eq!(result.return_value, JitValue::Felt252(42.into()));
+    }
+
+    #[test]
+    fn plugin_version_check() {
+        for (api_version, is_compatible) in [
+            (PluginApiVersion::new(PLUGIN_API_VERSION.major, 0), true),
+            (
+                PluginApiVersion::new(PLUGIN_API_VERSION.major, PLUGIN_API_VERSION.minor + 1),
+                false,
+            ),
+            (
+                PluginApiVersion::new(PLUGIN_API_VERSION.major + 1, 0),
+                false,
+            ),
+        ] {
+            let result = NativeContext::new().with_libfunc_plugin(CountingPlugin {
+                api_version: Some(api_version),
+                ..Default::default()
+            });
+
+            match is_compatible {
+                true => assert!(result.is_ok()),
+                false => assert!(matches!(result, Err(Error::IncompatiblePlugin(_)))),
+            }
+        }
+    }
+
+    #[test]
+    fn plugin_conflicts() {
+        let result = NativeContext::new()
+            .with_libfunc_plugin(CountingPlugin::default())
+            .unwrap()
+            .with_libfunc_plugin(CountingPlugin::default());
+        assert!(matches!(result, Err(Error::IncompatiblePlugin(_))));
+    }
+}
@@ -0,0 +1,16 @@
+//! # Prelude
+//!
+//! Re-exports the types needed to compile and run programs, so that most users only need a single
+//! `use cairo_native::prelude::*;`.
+
+pub use crate::{
+    backend::{Backend, MlirBackend, MlirExecutionMode, ProgramExecutor},
+    context::{CompilationLimits, CompilerConfig, NativeContext, PanicMode},
+    error::Error,
+    execution_result::{ContractExecutionResult, ExecutionResult},
+    executor::{AotNativeExecutor, JitNativeExecutor, NativeExecutor},
+    module::NativeModule,
+    starknet::StarknetSyscallHandler,
+    values::JitValue,
+    OptLevel,
+};
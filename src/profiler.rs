@@ -0,0 +1,286 @@
+//! # Profiler
+//!
+//! Per-statement and per-libfunc profiling of the generated code. When the program is compiled
+//! with the [profiler enabled](crate::context::CompilerConfig::with_profiler), the generated code
+//! reads the cycle counter (ex. `rdtsc`) before executing every libfunc invocation, and updates the
+//! counters kept by the runtime library for the current thread. The cycles between two consecutive
+//! statements are attributed to the former, therefore calls to user functions only account for the
+//! call itself, while the statements of the callee are measured separately.
+//!
+//! AOT-compiled programs are linked against their own copy of the runtime library, so only the
+//! executions of the JIT executor can be collected by [Profile::record].
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let context = NativeContext::new().with_config(CompilerConfig::new().with_profiler(true));
+//! let module = context.compile(&program, None)?;
+//! let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+//!
+//! let (result, profile) = Profile::record(|| executor.invoke_dynamic(entry_point, &[], None));
+//! profile.write_to(&program, std::io::stdout())?;
+//! ```
+
+#![cfg(feature = "with-profiler")]
+
+use crate::utils::with_recorder;
+use cairo_lang_sierra::{
+    ids::ConcreteLibfuncId,
+    program::{Program, Statement, StatementIdx},
+};
+use cairo_native_runtime::take_profiler_counters;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct Recorder {
+    statements: BTreeMap<StatementIdx, StatementProfile>,
+}
+
+/// Move the counters of the current thread into the innermost recording, or discard them if there
+/// isn't any.
+fn flush_counters() {
+    let counters = take_profiler_counters(read_cycle_counter());
+    RECORDER.with_borrow_mut(|recorder| {
+        let Some(recorder) = recorder else {
+            return;
+        };
+
+        for (statement_idx, (count, cycles)) in counters.into_iter().enumerate() {
+            if count != 0 {
+                let profile = recorder
+                    .statements
+                    .entry(StatementIdx(statement_idx))
+                    .or_default();
+                profile.count += count;
+                profile.cycles += cycles;
+            }
+        }
+    });
+}
+
+/// Read the cycle counter the generated code reads.
+fn read_cycle_counter() -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::x86_64::_rdtsc()
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        let value: u64;
+        unsafe { std::arch::asm!("mrs {}, cntvct_el0", out(reg) value) };
+        value
+    }
+}
+
+/// The measurements of a statement, or of all the invocations of a libfunc.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StatementProfile {
+    /// Number of times it was executed.
+    pub count: u64,
+    /// Total cycles spent executing it, as measured by the cycle counter of the CPU (ex. the
+    /// time-stamp counter on x86-64).
+    pub cycles: u64,
+}
+
+/// The measurements taken during an execution.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Profile {
+    statements: BTreeMap<StatementIdx, StatementProfile>,
+}
+
+impl Profile {
+    /// Profile the programs executed while running `f`.
+    ///
+    /// Only executions on the current thread are measured. Notifications received outside of a
+    /// `record` call are discarded.
+    pub fn record<T>(f: impl FnOnce() -> T) -> (T, Self) {
+        // The counters so far belong to the enclosing recording, if any.
+        flush_counters();
+        let (output, recorder) = with_recorder(&RECORDER, Recorder::default(), || {
+            let output = f();
+            flush_counters();
+            output
+        });
+
+        (
+            output,
+            Self {
+                statements: recorder.statements,
+            },
+        )
+    }
+
+    /// The measurements of every executed statement.
+    pub fn statements(&self) -> &BTreeMap<StatementIdx, StatementProfile> {
+        &self.statements
+    }
+
+    /// Aggregate the measurements by libfunc, sorted by decreasing cycles.
+    ///
+    /// The program must be the one which was profiled.
+    pub fn by_libfunc<'a>(
+        &self,
+        program: &'a Program,
+    ) -> Vec<(&'a ConcreteLibfuncId, StatementProfile)> {
+        let mut libfuncs = HashMap::<_, StatementProfile>::new();
+        for (statement_idx, profile) in &self.statements {
+            if let Some(Statement::Invocation(invocation)) = program.statements.get(statement_idx.0)
+            {
+                let entry = libfuncs.entry(&invocation.libfunc_id).or_default();
+                entry.count += profile.count;
+                entry.cycles += profile.cycles;
+            }
+        }
+
+        let mut libfuncs = libfuncs.into_iter().collect::<Vec<_>>();
+        libfuncs.sort_by(|a, b| {
+            b.1.cycles
+                .cmp(&a.1.cycles)
+                .then_with(|| a.0.id.cmp(&b.0.id))
+        });
+        libfuncs
+    }
+
+    /// Write the measurements by libfunc in a human-readable format.
+    pub fn write_to(&self, program: &Program, mut target: impl Write) -> io::Result<()> {
+        for (libfunc_id, profile) in self.by_libfunc(program) {
+            writeln!(
+                target,
+                "{libfunc_id}: {} calls, {} cycles total, {} cycles average",
+                profile.count,
+                profile.cycles,
+                profile.cycles / profile.count.max(1),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{CompilerConfig, NativeContext},
+        executor::{AotNativeExecutor, JitNativeExecutor},
+        utils::{find_function_id, test::load_cairo},
+        values::JitValue,
+        OptLevel,
+    };
+
+    #[test]
+    fn profile_libfuncs() {
+        let program = load_cairo! {
+            fn run_test(n: felt252) -> felt252 {
+                if n == 0 {
+                    0
+                } else {
+                    run_test(n - 1) + 2
+                }
+            }
+        };
+
+        let context = NativeContext::new().with_config(CompilerConfig::new().with_profiler(true));
+        let module = context.compile(&program.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let function_id = find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0));
+        let run = |n: u32| {
+            executor
+                .invoke_dynamic(function_id, &[JitValue::Felt252(n.into())], Some(u128::MAX))
+                .unwrap()
+                .into_result()
+                .unwrap()
+        };
+
+        // Executions outside of a recording are discarded.
+        run(3);
+        let (result, profile) = Profile::record(|| run(10));
+        assert_eq!(result, JitValue::Felt252(20.into()));
+
+        // The function calls itself once for every non-zero value of `n`.
+        let libfuncs = profile.by_libfunc(&program.1);
+        let function_calls = libfuncs
+            .iter()
+            .find(|(id, _)| {
+                id.debug_name
+                    .as_deref()
+                    .is_some_and(|x| x.starts_with("function_call"))
+            })
+            .unwrap();
+        assert_eq!(function_calls.1.count, 10);
+        assert!(function_calls.1.cycles > 0);
+
+        let mut dump = Vec::new();
+        profile.write_to(&program.1, &mut dump).unwrap();
+        assert_eq!(
+            String::from_utf8(dump).unwrap().lines().count(),
+            libfuncs.len()
+        );
+    }
+
+    #[test]
+    fn profile_aot() {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a * b
+            }
+        };
+
+        // The AOT executor resolves the counters from the runtime library linked into the program.
+        let context = NativeContext::new().with_config(CompilerConfig::new().with_profiler(true));
+        let module = context.compile(&program.1, None).unwrap();
+        let executor = AotNativeExecutor::from_native_module(module, OptLevel::None);
+        let result = executor
+            .invoke_dynamic(
+                find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0)),
+                &[JitValue::Felt252(2.into()), JitValue::Felt252(3.into())],
+                None,
+            )
+            .unwrap();
+        assert_eq!(result.into_result().unwrap(), JitValue::Felt252(6.into()));
+    }
+
+    #[test]
+    fn nested_records() {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                a * b
+            }
+        };
+
+        let context = NativeContext::new().with_config(CompilerConfig::new().with_profiler(true));
+        let module = context.compile(&program.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        let function_id = find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0));
+        let run = || {
+            executor
+                .invoke_dynamic(
+                    function_id,
+                    &[JitValue::Felt252(2.into()), JitValue::Felt252(3.into())],
+                    None,
+                )
+                .unwrap()
+        };
+
+        let ((_, inner), outer) = Profile::record(|| {
+            run();
+            Profile::record(run)
+        });
+
+        // Each recording only gets its own execution.
+        assert!(!inner.statements().is_empty());
+        assert_eq!(
+            inner.statements().values().map(|x| x.count).sum::<u64>(),
+            outer.statements().values().map(|x| x.count).sum::<u64>(),
+        );
+        assert!(RECORDER.with(|x| x.borrow().is_none()));
+    }
+}
@@ -1,5 +1,9 @@
 //! Starknet related code for `cairo_native`
 
+use k256::elliptic_curve::{
+    ops::Reduce,
+    sec1::{Coordinates, FromEncodedPoint, ToEncodedPoint},
+};
 use starknet_types_core::felt::Felt;
 
 pub type SyscallResult<T> = std::result::Result<T, Vec<Felt>>;
@@ -94,17 +98,320 @@ pub struct TxInfo {
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Secp256k1Point {
     pub x: U256,
     pub y: U256,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Secp256r1Point {
     pub x: U256,
     pub y: U256,
 }
 
+/// Limits on the size of the payloads marshalled back to the host, which protect it from contracts
+/// returning (or emitting) absurdly big spans.
+///
+/// Everything is unlimited by default.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PayloadLimits {
+    /// Maximum number of felts returned by a contract entry point, including the panic data.
+    ///
+    /// It applies to the returned span and to the panic data array, and not to any array nested in
+    /// them (which contract entry points don't return anyway).
+    pub max_retdata_len: Option<usize>,
+    /// Maximum number of keys, and of data felts, of an emitted event.
+    pub max_event_len: Option<usize>,
+    /// What to do with the payloads which exceed the limits.
+    pub policy: PayloadLimitPolicy,
+}
+
+/// What to do with the payloads which exceed the [PayloadLimits].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PayloadLimitPolicy {
+    /// Fail the execution (or the syscall, for events).
+    #[default]
+    Fail,
+    /// Keep only the first felts of the payload, up to the limit.
+    Truncate,
+}
+
+impl PayloadLimits {
+    /// Return how many felts of a payload can be marshalled, or an error message if the payload is
+    /// too big and the policy is to fail.
+    pub(crate) fn check(
+        &self,
+        what: &str,
+        len: usize,
+        limit: Option<usize>,
+    ) -> Result<usize, String> {
+        match limit {
+            Some(limit) if len > limit => match self.policy {
+                PayloadLimitPolicy::Fail => Err(format!(
+                    "the {what} has {len} felts, but the limit is {limit}"
+                )),
+                PayloadLimitPolicy::Truncate => {
+                    tracing::warn!("Truncating the {what} from {len} to {limit} felts.");
+                    Ok(limit)
+                }
+            },
+            _ => Ok(len),
+        }
+    }
+}
+
+/// Gas charged by the `keccak` syscall for every block it absorbs.
+pub const KECCAK_ROUND_COST: u128 = 180000;
+
+/// Compute the `keccak` syscall like the Cairo VM does.
+///
+/// The input must be already padded (which the corelib's `keccak` module does) into blocks of 17
+/// words. Every block is absorbed into the state using keccak-f[1600], charging
+/// [KECCAK_ROUND_COST] for each of them, and the first 256 bits of the final state are returned.
+pub fn keccak_syscall(input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+    if input.len() % 17 != 0 {
+        return Err(vec![Felt::from_bytes_be_slice(
+            b"Invalid keccak input size",
+        )]);
+    }
+
+    let mut state = [0u64; 25];
+    for chunk in input.chunks_exact(17) {
+        if *remaining_gas < KECCAK_ROUND_COST {
+            return Err(vec![Felt::from_bytes_be_slice(b"Syscall out of gas")]);
+        }
+        *remaining_gas -= KECCAK_ROUND_COST;
+
+        for (state, value) in state.iter_mut().zip(chunk) {
+            *state ^= value;
+        }
+        keccak::f1600(&mut state);
+    }
+
+    // The low half of the hash (`state[0]` and `state[1]`) goes first in memory.
+    Ok(U256 {
+        hi: state[0] as u128 | ((state[1] as u128) << 64),
+        lo: state[2] as u128 | ((state[3] as u128) << 64),
+    })
+}
+
+/// Modulus of the secp256k1 base field, in big endian.
+const SECP256K1_P: [u8; 32] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0xFF, 0xFC, 0x2F,
+];
+
+fn invalid_argument() -> Vec<Felt> {
+    vec![Felt::from_bytes_be_slice(b"Invalid argument")]
+}
+
+/// Return the big endian bytes of a `u256`, whose high half is stored in `lo`.
+fn u256_to_be_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes[..16].copy_from_slice(&value.lo.to_be_bytes());
+    bytes[16..].copy_from_slice(&value.hi.to_be_bytes());
+    bytes
+}
+
+fn u256_from_be_bytes(bytes: &[u8]) -> U256 {
+    U256 {
+        hi: u128::from_be_bytes(bytes[16..32].try_into().unwrap()),
+        lo: u128::from_be_bytes(bytes[..16].try_into().unwrap()),
+    }
+}
+
+/// Return the secp256k1 point with the given coordinates, or `None` if it's not on the curve. The
+/// point at infinity is represented as `(0, 0)`.
+fn secp256k1_to_projective(x: U256, y: U256) -> Option<k256::ProjectivePoint> {
+    if x == U256::default() && y == U256::default() {
+        return Some(k256::ProjectivePoint::IDENTITY);
+    }
+
+    k256::ProjectivePoint::from_encoded_point(&k256::EncodedPoint::from_affine_coordinates(
+        &k256::FieldBytes::from(u256_to_be_bytes(x)),
+        &k256::FieldBytes::from(u256_to_be_bytes(y)),
+        false,
+    ))
+    .into()
+}
+
+fn secp256k1_from_projective(p: k256::ProjectivePoint) -> Secp256k1Point {
+    match p.to_encoded_point(false).coordinates() {
+        Coordinates::Uncompressed { x, y } => Secp256k1Point {
+            x: u256_from_be_bytes(x),
+            y: u256_from_be_bytes(y),
+        },
+        // The point at infinity.
+        _ => Secp256k1Point {
+            x: U256::default(),
+            y: U256::default(),
+        },
+    }
+}
+
+/// Compute the `secp256k1_new` syscall like the Cairo VM does.
+///
+/// Coordinates which aren't reduced modulo the field prime are an error, and points which aren't on
+/// the curve return `None`. The point at infinity is represented as `(0, 0)`.
+pub fn secp256k1_new_syscall(x: U256, y: U256) -> SyscallResult<Option<Secp256k1Point>> {
+    if u256_to_be_bytes(x) >= SECP256K1_P || u256_to_be_bytes(y) >= SECP256K1_P {
+        return Err(invalid_argument());
+    }
+
+    Ok(secp256k1_to_projective(x, y).map(|_| Secp256k1Point { x, y }))
+}
+
+/// Compute the `secp256k1_add` syscall like the Cairo VM does.
+pub fn secp256k1_add_syscall(
+    p0: Secp256k1Point,
+    p1: Secp256k1Point,
+) -> SyscallResult<Secp256k1Point> {
+    let p0 = secp256k1_to_projective(p0.x, p0.y).ok_or_else(invalid_argument)?;
+    let p1 = secp256k1_to_projective(p1.x, p1.y).ok_or_else(invalid_argument)?;
+
+    Ok(secp256k1_from_projective(p0 + p1))
+}
+
+/// Compute the `secp256k1_mul` syscall like the Cairo VM does. The scalar is reduced modulo the
+/// order of the curve.
+pub fn secp256k1_mul_syscall(p: Secp256k1Point, m: U256) -> SyscallResult<Secp256k1Point> {
+    let p = secp256k1_to_projective(p.x, p.y).ok_or_else(invalid_argument)?;
+    let m = <k256::Scalar as Reduce<k256::U256>>::reduce_bytes(&k256::FieldBytes::from(
+        u256_to_be_bytes(m),
+    ));
+
+    Ok(secp256k1_from_projective(p * m))
+}
+
+/// Compute the `secp256k1_get_point_from_x` syscall like the Cairo VM does. Returns `None` if there
+/// is no point with the given `x` coordinate.
+pub fn secp256k1_get_point_from_x_syscall(
+    x: U256,
+    y_parity: bool,
+) -> SyscallResult<Option<Secp256k1Point>> {
+    let x = u256_to_be_bytes(x);
+    if x >= SECP256K1_P {
+        return Err(invalid_argument());
+    }
+
+    let mut encoded_point = [0; 33];
+    encoded_point[0] = 0x02 | y_parity as u8;
+    encoded_point[1..].copy_from_slice(&x);
+
+    // The unwrap is unreachable since the encoding is always a valid compressed point.
+    let point: Option<_> = k256::ProjectivePoint::from_encoded_point(
+        &k256::EncodedPoint::from_bytes(encoded_point).unwrap(),
+    )
+    .into();
+    Ok(point.map(secp256k1_from_projective))
+}
+
+/// Modulus of the secp256r1 base field, in big endian.
+const SECP256R1_P: [u8; 32] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+/// Return the secp256r1 point with the given coordinates, or `None` if it's not on the curve. The
+/// point at infinity is represented as `(0, 0)`.
+fn secp256r1_to_projective(x: U256, y: U256) -> Option<p256::ProjectivePoint> {
+    if x == U256::default() && y == U256::default() {
+        return Some(p256::ProjectivePoint::IDENTITY);
+    }
+
+    p256::ProjectivePoint::from_encoded_point(&p256::EncodedPoint::from_affine_coordinates(
+        &p256::FieldBytes::from(u256_to_be_bytes(x)),
+        &p256::FieldBytes::from(u256_to_be_bytes(y)),
+        false,
+    ))
+    .into()
+}
+
+fn secp256r1_from_projective(p: p256::ProjectivePoint) -> Secp256r1Point {
+    match p.to_encoded_point(false).coordinates() {
+        Coordinates::Uncompressed { x, y } => Secp256r1Point {
+            x: u256_from_be_bytes(x),
+            y: u256_from_be_bytes(y),
+        },
+        // The point at infinity.
+        _ => Secp256r1Point {
+            x: U256::default(),
+            y: U256::default(),
+        },
+    }
+}
+
+/// Compute the `secp256r1_new` syscall like the Cairo VM does.
+///
+/// Coordinates which aren't reduced modulo the field prime are an error, and points which aren't on
+/// the curve return `None`. The point at infinity is represented as `(0, 0)`.
+pub fn secp256r1_new_syscall(x: U256, y: U256) -> SyscallResult<Option<Secp256r1Point>> {
+    if u256_to_be_bytes(x) >= SECP256R1_P || u256_to_be_bytes(y) >= SECP256R1_P {
+        return Err(invalid_argument());
+    }
+
+    Ok(secp256r1_to_projective(x, y).map(|_| Secp256r1Point { x, y }))
+}
+
+/// Compute the `secp256r1_add` syscall like the Cairo VM does.
+pub fn secp256r1_add_syscall(
+    p0: Secp256r1Point,
+    p1: Secp256r1Point,
+) -> SyscallResult<Secp256r1Point> {
+    let p0 = secp256r1_to_projective(p0.x, p0.y).ok_or_else(invalid_argument)?;
+    let p1 = secp256r1_to_projective(p1.x, p1.y).ok_or_else(invalid_argument)?;
+
+    Ok(secp256r1_from_projective(p0 + p1))
+}
+
+/// Compute the `secp256r1_mul` syscall like the Cairo VM does. The scalar is reduced modulo the
+/// order of the curve.
+pub fn secp256r1_mul_syscall(p: Secp256r1Point, m: U256) -> SyscallResult<Secp256r1Point> {
+    let p = secp256r1_to_projective(p.x, p.y).ok_or_else(invalid_argument)?;
+    let m = <p256::Scalar as Reduce<p256::U256>>::reduce_bytes(&p256::FieldBytes::from(
+        u256_to_be_bytes(m),
+    ));
+
+    Ok(secp256r1_from_projective(p * m))
+}
+
+/// Compute the `secp256r1_get_point_from_x` syscall like the Cairo VM does. Returns `None` if there
+/// is no point with the given `x` coordinate.
+pub fn secp256r1_get_point_from_x_syscall(
+    x: U256,
+    y_parity: bool,
+) -> SyscallResult<Option<Secp256r1Point>> {
+    let x = u256_to_be_bytes(x);
+    if x >= SECP256R1_P {
+        return Err(invalid_argument());
+    }
+
+    let mut encoded_point = [0; 33];
+    encoded_point[0] = 0x02 | y_parity as u8;
+    encoded_point[1..].copy_from_slice(&x);
+
+    // The unwrap is unreachable since the encoding is always a valid compressed point.
+    let point: Option<_> = p256::ProjectivePoint::from_encoded_point(
+        &p256::EncodedPoint::from_bytes(encoded_point).unwrap(),
+    )
+    .into();
+    Ok(point.map(secp256r1_from_projective))
+}
+
+/// The implementation of the Starknet syscalls, provided by the host (ex. a sequencer) when
+/// executing contracts.
+///
+/// Every syscall receives the gas available to the contract, from which it should deduct what it
+/// spends (the base cost of the syscall has already been charged by the contract). Failures are
+/// returned as the revert reason felts, which the contract receives in the `Err` variant of its
+/// `SyscallResult`.
+///
+/// The handler is passed to the generated code through a table of `extern "C"` callbacks, which
+/// marshal the arguments and results between the native representation and the Rust types.
 pub trait StarknetSyscallHandler {
     fn get_block_hash(
         &mut self,
@@ -172,75 +479,109 @@ pub trait StarknetSyscallHandler {
         remaining_gas: &mut u128,
     ) -> SyscallResult<()>;
 
-    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256>;
+    /// Compute the keccak hash of the input. The default implementation uses [keccak_syscall].
+    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+        keccak_syscall(input, remaining_gas)
+    }
 
     fn secp256k1_new(
         &mut self,
         x: U256,
         y: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256k1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        secp256k1_new_syscall(x, y)
+    }
 
     fn secp256k1_add(
         &mut self,
         p0: Secp256k1Point,
         p1: Secp256k1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256k1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        secp256k1_add_syscall(p0, p1)
+    }
 
     fn secp256k1_mul(
         &mut self,
         p: Secp256k1Point,
         m: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256k1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        secp256k1_mul_syscall(p, m)
+    }
 
     fn secp256k1_get_point_from_x(
         &mut self,
         x: U256,
         y_parity: bool,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256k1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        secp256k1_get_point_from_x_syscall(x, y_parity)
+    }
 
     fn secp256k1_get_xy(
         &mut self,
         p: Secp256k1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<(U256, U256)>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        Ok((p.x, p.y))
+    }
 
     fn secp256r1_new(
         &mut self,
         x: U256,
         y: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256r1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        secp256r1_new_syscall(x, y)
+    }
 
     fn secp256r1_add(
         &mut self,
         p0: Secp256r1Point,
         p1: Secp256r1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256r1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        secp256r1_add_syscall(p0, p1)
+    }
 
     fn secp256r1_mul(
         &mut self,
         p: Secp256r1Point,
         m: U256,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Secp256r1Point>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        secp256r1_mul_syscall(p, m)
+    }
 
     fn secp256r1_get_point_from_x(
         &mut self,
         x: U256,
         y_parity: bool,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<Option<Secp256r1Point>>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        secp256r1_get_point_from_x_syscall(x, y_parity)
+    }
 
     fn secp256r1_get_xy(
         &mut self,
         p: Secp256r1Point,
-        remaining_gas: &mut u128,
-    ) -> SyscallResult<(U256, U256)>;
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        Ok((p.x, p.y))
+    }
+
+    /// Limits on the size of the payloads marshalled back to the host. Unlimited by default.
+    fn payload_limits(&self) -> PayloadLimits {
+        PayloadLimits::default()
+    }
+
+    /// Whether to record the latency of the syscalls into the execution result. Check out the
+    /// [syscall_profile](crate::syscall_profile) module for more info. Disabled by default.
+    fn profile_syscalls(&self) -> bool {
+        false
+    }
 
     #[cfg(feature = "with-cheatcode")]
     fn cheatcode(&mut self, _selector: Felt, _input: &[Felt]) -> Vec<Felt> {
@@ -438,6 +779,7 @@ impl StarknetSyscallHandler for DummySyscallHandler {
 // TODO: Move to the correct place or remove if unused.
 pub(crate) mod handler {
     use super::*;
+    use crate::syscall_profile::SyscallTimer;
     use std::{
         alloc::Layout,
         fmt::Debug,
@@ -786,8 +1128,9 @@ pub(crate) mod handler {
                     capacity: 0,
                 },
                 _ => {
-                    let ptr =
-                        libc::malloc(Layout::array::<E>(data.len()).unwrap().size()) as *mut E;
+                    let ptr = crate::types::array::alloc_data(
+                        Layout::array::<E>(data.len()).unwrap().size(),
+                    ) as *mut E;
 
                     let len: u32 = data.len().try_into().unwrap();
                     for (i, val) in data.iter().enumerate() {
@@ -822,7 +1165,10 @@ pub(crate) mod handler {
             gas: &mut u128,
             block_number: u64,
         ) {
-            let result = ptr.get_block_hash(block_number, gas);
+            let _span = tracing::debug_span!("syscall", name = "get_block_hash").entered();
+            let mut timer = SyscallTimer::start("get_block_hash");
+
+            let result = timer.handler(|| ptr.get_block_hash(block_number, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -842,6 +1188,9 @@ pub(crate) mod handler {
             selector: &Felt252Abi,
             input: &ArrayAbi<Felt252Abi>,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "cheatcode").entered();
+            let mut timer = SyscallTimer::start("cheatcode");
+
             let input: Vec<_> = unsafe {
                 let since_offset = input.since as usize;
                 let until_offset = input.until as usize;
@@ -854,8 +1203,8 @@ pub(crate) mod handler {
             .collect();
             let selector = Felt::from_bytes_le(&selector.0);
 
-            let result = ptr
-                .cheatcode(selector, &input)
+            let result = timer
+                .handler(|| ptr.cheatcode(selector, &input))
                 .into_iter()
                 .map(|x| Felt252Abi(x.to_bytes_le()))
                 .collect::<Vec<_>>();
@@ -868,7 +1217,10 @@ pub(crate) mod handler {
             ptr: &mut T,
             gas: &mut u128,
         ) {
-            let result = ptr.get_execution_info(gas);
+            let _span = tracing::debug_span!("syscall", name = "get_execution_info").entered();
+            let mut timer = SyscallTimer::start("get_execution_info");
+
+            let result = timer.handler(|| ptr.get_execution_info(gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -933,7 +1285,10 @@ pub(crate) mod handler {
             ptr: &mut T,
             gas: &mut u128,
         ) {
-            let result = ptr.get_execution_info_v2(gas);
+            let _span = tracing::debug_span!("syscall", name = "get_execution_info_v2").entered();
+            let mut timer = SyscallTimer::start("get_execution_info_v2");
+
+            let result = timer.handler(|| ptr.get_execution_info_v2(gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1035,6 +1390,9 @@ pub(crate) mod handler {
             calldata: &ArrayAbi<Felt252Abi>,
             deploy_from_zero: bool,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "deploy").entered();
+            let mut timer = SyscallTimer::start("deploy");
+
             let class_hash = Felt::from_bytes_be(&{
                 let mut data = class_hash.0;
                 data.reverse();
@@ -1066,13 +1424,15 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.deploy(
-                class_hash,
-                contract_address_salt,
-                &calldata,
-                deploy_from_zero,
-                gas,
-            );
+            let result = timer.handler(|| {
+                ptr.deploy(
+                    class_hash,
+                    contract_address_salt,
+                    &calldata,
+                    deploy_from_zero,
+                    gas,
+                )
+            });
 
             *result_ptr = match result {
                 Ok(x) => {
@@ -1095,12 +1455,15 @@ pub(crate) mod handler {
             gas: &mut u128,
             class_hash: &Felt252Abi,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "replace_class").entered();
+            let mut timer = SyscallTimer::start("replace_class");
+
             let class_hash = Felt::from_bytes_be(&{
                 let mut data = class_hash.0;
                 data.reverse();
                 data
             });
-            let result = ptr.replace_class(class_hash, gas);
+            let result = timer.handler(|| ptr.replace_class(class_hash, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1121,6 +1484,9 @@ pub(crate) mod handler {
             function_selector: &Felt252Abi,
             calldata: &ArrayAbi<Felt252Abi>,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "library_call").entered();
+            let mut timer = SyscallTimer::start("library_call");
+
             let class_hash = Felt::from_bytes_be(&{
                 let mut data = class_hash.0;
                 data.reverse();
@@ -1152,7 +1518,8 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.library_call(class_hash, function_selector, &calldata, gas);
+            let result =
+                timer.handler(|| ptr.library_call(class_hash, function_selector, &calldata, gas));
 
             *result_ptr = match result {
                 Ok(x) => {
@@ -1177,6 +1544,9 @@ pub(crate) mod handler {
             entry_point_selector: &Felt252Abi,
             calldata: &ArrayAbi<Felt252Abi>,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "call_contract").entered();
+            let mut timer = SyscallTimer::start("call_contract");
+
             let address = Felt::from_bytes_be(&{
                 let mut data = address.0;
                 data.reverse();
@@ -1208,7 +1578,8 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.call_contract(address, entry_point_selector, &calldata, gas);
+            let result =
+                timer.handler(|| ptr.call_contract(address, entry_point_selector, &calldata, gas));
 
             *result_ptr = match result {
                 Ok(x) => {
@@ -1232,12 +1603,15 @@ pub(crate) mod handler {
             address_domain: u32,
             address: &Felt252Abi,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "storage_read").entered();
+            let mut timer = SyscallTimer::start("storage_read");
+
             let address = Felt::from_bytes_be(&{
                 let mut data = address.0;
                 data.reverse();
                 data
             });
-            let result = ptr.storage_read(address_domain, address, gas);
+            let result = timer.handler(|| ptr.storage_read(address_domain, address, gas));
 
             *result_ptr = match result {
                 Ok(res) => SyscallResultAbi {
@@ -1258,6 +1632,9 @@ pub(crate) mod handler {
             address: &Felt252Abi,
             value: &Felt252Abi,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "storage_write").entered();
+            let mut timer = SyscallTimer::start("storage_write");
+
             let address = Felt::from_bytes_be(&{
                 let mut data = address.0;
                 data.reverse();
@@ -1268,7 +1645,7 @@ pub(crate) mod handler {
                 data.reverse();
                 data
             });
-            let result = ptr.storage_write(address_domain, address, value, gas);
+            let result = timer.handler(|| ptr.storage_write(address_domain, address, value, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1288,7 +1665,10 @@ pub(crate) mod handler {
             keys: &ArrayAbi<Felt252Abi>,
             data: &ArrayAbi<Felt252Abi>,
         ) {
-            let keys: Vec<_> = unsafe {
+            let _span = tracing::debug_span!("syscall", name = "emit_event").entered();
+            let mut timer = SyscallTimer::start("emit_event");
+
+            let keys: &[Felt252Abi] = unsafe {
                 let since_offset = keys.since as usize;
                 let until_offset = keys.until as usize;
                 debug_assert!(since_offset <= until_offset);
@@ -1297,18 +1677,8 @@ pub(crate) mod handler {
                     0 => &[],
                     _ => std::slice::from_raw_parts(keys.ptr.add(since_offset), len),
                 }
-            }
-            .iter()
-            .map(|x| {
-                Felt::from_bytes_be(&{
-                    let mut data = x.0;
-                    data.reverse();
-                    data
-                })
-            })
-            .collect();
-
-            let data: Vec<_> = unsafe {
+            };
+            let data: &[Felt252Abi] = unsafe {
                 let since_offset = data.since as usize;
                 let until_offset = data.until as usize;
                 debug_assert!(since_offset <= until_offset);
@@ -1317,18 +1687,47 @@ pub(crate) mod handler {
                     0 => &[],
                     _ => std::slice::from_raw_parts(data.ptr.add(since_offset), len),
                 }
-            }
-            .iter()
-            .map(|x| {
-                Felt::from_bytes_be(&{
-                    let mut data = x.0;
-                    data.reverse();
-                    data
+            };
+
+            // Check the limits before copying anything.
+            let limits = ptr.payload_limits();
+            let (keys, data) = match limits
+                .check("event keys", keys.len(), limits.max_event_len)
+                .and_then(|keys_len| {
+                    let data_len = limits.check("event data", data.len(), limits.max_event_len)?;
+                    Ok((&keys[..keys_len], &data[..data_len]))
+                }) {
+                Ok(x) => x,
+                Err(e) => {
+                    tracing::warn!("Rejecting the event: {e}.");
+                    *result_ptr =
+                        Self::wrap_error(&[Felt::from_bytes_be_slice(b"Event too large")]);
+                    return;
+                }
+            };
+
+            let keys: Vec<_> = keys
+                .iter()
+                .map(|x| {
+                    Felt::from_bytes_be(&{
+                        let mut data = x.0;
+                        data.reverse();
+                        data
+                    })
                 })
-            })
-            .collect();
+                .collect();
+            let data: Vec<_> = data
+                .iter()
+                .map(|x| {
+                    Felt::from_bytes_be(&{
+                        let mut data = x.0;
+                        data.reverse();
+                        data
+                    })
+                })
+                .collect();
 
-            let result = ptr.emit_event(&keys, &data, gas);
+            let result = timer.handler(|| ptr.emit_event(&keys, &data, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1348,6 +1747,9 @@ pub(crate) mod handler {
             to_address: &Felt252Abi,
             payload: &ArrayAbi<Felt252Abi>,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "send_message_to_l1").entered();
+            let mut timer = SyscallTimer::start("send_message_to_l1");
+
             let to_address = Felt::from_bytes_be(&{
                 let mut data = to_address.0;
                 data.reverse();
@@ -1373,7 +1775,7 @@ pub(crate) mod handler {
             })
             .collect();
 
-            let result = ptr.send_message_to_l1(to_address, &payload, gas);
+            let result = timer.handler(|| ptr.send_message_to_l1(to_address, &payload, gas));
 
             *result_ptr = match result {
                 Ok(_) => SyscallResultAbi {
@@ -1392,6 +1794,9 @@ pub(crate) mod handler {
             gas: &mut u128,
             input: &ArrayAbi<u64>,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "keccak").entered();
+            let mut timer = SyscallTimer::start("keccak");
+
             let input = unsafe {
                 let since_offset = input.since as usize;
                 let until_offset = input.until as usize;
@@ -1403,7 +1808,7 @@ pub(crate) mod handler {
                 }
             };
 
-            let result = ptr.keccak(input, gas);
+            let result = timer.handler(|| ptr.keccak(input, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1423,9 +1828,12 @@ pub(crate) mod handler {
             x: &U256,
             y: &U256,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256k1_new").entered();
+            let mut timer = SyscallTimer::start("secp256k1_new");
+
             let x = *x;
             let y = *y;
-            let result = ptr.secp256k1_new(x, y, gas);
+            let result = timer.handler(|| ptr.secp256k1_new(x, y, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1448,9 +1856,12 @@ pub(crate) mod handler {
             p0: &Secp256k1Point,
             p1: &Secp256k1Point,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256k1_add").entered();
+            let mut timer = SyscallTimer::start("secp256k1_add");
+
             let p0 = *p0;
             let p1 = *p1;
-            let result = ptr.secp256k1_add(p0, p1, gas);
+            let result = timer.handler(|| ptr.secp256k1_add(p0, p1, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1470,10 +1881,13 @@ pub(crate) mod handler {
             p: &Secp256k1Point,
             scalar: &U256,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256k1_mul").entered();
+            let mut timer = SyscallTimer::start("secp256k1_mul");
+
             // Seems like it's important to dereference and create a local instead of at call site directly.
             let scalar = *scalar;
             let p = *p;
-            let result = ptr.secp256k1_mul(p, scalar, gas);
+            let result = timer.handler(|| ptr.secp256k1_mul(p, scalar, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1493,9 +1907,13 @@ pub(crate) mod handler {
             x: &U256,
             y_parity: &bool,
         ) {
+            let _span =
+                tracing::debug_span!("syscall", name = "secp256k1_get_point_from_x").entered();
+            let mut timer = SyscallTimer::start("secp256k1_get_point_from_x");
+
             let x = *x;
             let y_parity = *y_parity;
-            let result = ptr.secp256k1_get_point_from_x(x, y_parity, gas);
+            let result = timer.handler(|| ptr.secp256k1_get_point_from_x(x, y_parity, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1517,8 +1935,11 @@ pub(crate) mod handler {
             gas: &mut u128,
             p: &Secp256k1Point,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256k1_get_xy").entered();
+            let mut timer = SyscallTimer::start("secp256k1_get_xy");
+
             let p = *p;
-            let result = ptr.secp256k1_get_xy(p, gas);
+            let result = timer.handler(|| ptr.secp256k1_get_xy(p, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1538,9 +1959,12 @@ pub(crate) mod handler {
             x: &U256,
             y: &U256,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256r1_new").entered();
+            let mut timer = SyscallTimer::start("secp256r1_new");
+
             let x = *x;
             let y = *y;
-            let result = ptr.secp256r1_new(x, y, gas);
+            let result = timer.handler(|| ptr.secp256r1_new(x, y, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1563,9 +1987,12 @@ pub(crate) mod handler {
             p0: &Secp256r1Point,
             p1: &Secp256r1Point,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256r1_add").entered();
+            let mut timer = SyscallTimer::start("secp256r1_add");
+
             let p0 = *p0;
             let p1 = *p1;
-            let result = ptr.secp256r1_add(p0, p1, gas);
+            let result = timer.handler(|| ptr.secp256r1_add(p0, p1, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1585,9 +2012,12 @@ pub(crate) mod handler {
             p: &Secp256r1Point,
             scalar: &U256,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256r1_mul").entered();
+            let mut timer = SyscallTimer::start("secp256r1_mul");
+
             let scalar = *scalar;
             let p = *p;
-            let result = ptr.secp256r1_mul(p, scalar, gas);
+            let result = timer.handler(|| ptr.secp256r1_mul(p, scalar, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1607,9 +2037,13 @@ pub(crate) mod handler {
             x: &U256,
             y_parity: &bool,
         ) {
+            let _span =
+                tracing::debug_span!("syscall", name = "secp256r1_get_point_from_x").entered();
+            let mut timer = SyscallTimer::start("secp256r1_get_point_from_x");
+
             let x = *x;
             let y_parity = *y_parity;
-            let result = ptr.secp256r1_get_point_from_x(x, y_parity, gas);
+            let result = timer.handler(|| ptr.secp256r1_get_point_from_x(x, y_parity, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1631,8 +2065,11 @@ pub(crate) mod handler {
             gas: &mut u128,
             p: &Secp256r1Point,
         ) {
+            let _span = tracing::debug_span!("syscall", name = "secp256r1_get_xy").entered();
+            let mut timer = SyscallTimer::start("secp256r1_get_xy");
+
             let p = *p;
-            let result = ptr.secp256r1_get_xy(p, gas);
+            let result = timer.handler(|| ptr.secp256r1_get_xy(p, gas));
 
             *result_ptr = match result {
                 Ok(x) => SyscallResultAbi {
@@ -1674,3 +2111,41 @@ pub extern "C" fn cairo_native__vtable_cheatcode(
 
     (callbacks.cheatcode)(result_ptr, handler, selector, input);
 }
+
+#[cfg(test)]
+mod test {
+    use super::{handler::StarknetSyscallHandlerCallbacks, DummySyscallHandler};
+
+    #[test]
+    fn callback_indices() {
+        type Callbacks<'a> = StarknetSyscallHandlerCallbacks<'a, DummySyscallHandler>;
+
+        // The handler pointer comes first, followed by the callbacks without gaps.
+        let mut indices = [
+            Callbacks::GET_BLOCK_HASH,
+            Callbacks::GET_EXECUTION_INFO,
+            Callbacks::GET_EXECUTION_INFOV2,
+            Callbacks::DEPLOY,
+            Callbacks::REPLACE_CLASS,
+            Callbacks::LIBRARY_CALL,
+            Callbacks::CALL_CONTRACT,
+            Callbacks::STORAGE_READ,
+            Callbacks::STORAGE_WRITE,
+            Callbacks::EMIT_EVENT,
+            Callbacks::SEND_MESSAGE_TO_L1,
+            Callbacks::KECCAK,
+            Callbacks::SECP256K1_NEW,
+            Callbacks::SECP256K1_ADD,
+            Callbacks::SECP256K1_MUL,
+            Callbacks::SECP256K1_GET_POINT_FROM_X,
+            Callbacks::SECP256K1_GET_XY,
+            Callbacks::SECP256R1_NEW,
+            Callbacks::SECP256R1_ADD,
+            Callbacks::SECP256R1_MUL,
+            Callbacks::SECP256R1_GET_POINT_FROM_X,
+            Callbacks::SECP256R1_GET_XY,
+        ];
+        indices.sort_unstable();
+        assert_eq!(indices.to_vec(), (1..=indices.len()).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,575 @@
+//! A read-through storage cache for syscall handlers.
+//!
+//! Every `storage_read` syscall crosses the FFI boundary into the embedder's handler, which usually
+//! has to look the value up in some state backend. Workloads which read the same slots over and over
+//! (ex. many invocations of the same contract within a block) may wrap their handler with a
+//! [`CachingSyscallHandler`] so that repeated reads are served from a [`StorageCache`] instead.
+//!
+//! Serving a read from the cache must not change the gas used by the contract, otherwise the same
+//! program would consume a different amount of gas depending on the state of the cache. Every entry
+//! therefore remembers the gas the wrapped handler charged when the slot was first read, and hits
+//! charge exactly that amount.
+//!
+//! The cache is owned by the embedder and outlives the handlers, so that it can be shared between
+//! invocations. It's kept consistent as follows:
+//!
+//!   - Writes through the caching handler invalidate the written slot.
+//!   - Calls which may execute other contracts (`call_contract`, `library_call` and `deploy`)
+//!     invalidate the whole cache when they return, since they may have written to any storage,
+//!     including the caller's own.
+//!   - Anything else that changes the storage behind the cache's back (ex. reverting a transaction
+//!     or moving to the next block) must be followed by an explicit [`StorageCache::clear`] or
+//!     [`StorageCache::invalidate_contract`].
+
+use crate::starknet::{
+    ExecutionInfo, ExecutionInfoV2, PayloadLimits, Secp256k1Point, Secp256r1Point,
+    StarknetSyscallHandler, SyscallResult, U256,
+};
+use starknet_types_core::felt::Felt;
+use std::collections::HashMap;
+
+/// Storage values cached across invocations, indexed by contract address, address domain and
+/// storage address.
+#[derive(Clone, Debug, Default)]
+pub struct StorageCache {
+    entries: HashMap<(Felt, u32, Felt), CacheEntry>,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct CacheEntry {
+    value: Felt,
+    /// Gas the wrapped handler charges for reading the slot.
+    read_cost: u128,
+}
+
+impl StorageCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the cached value of a storage slot, if any.
+    pub fn get(&self, contract_address: Felt, address_domain: u32, address: Felt) -> Option<Felt> {
+        self.entries
+            .get(&(contract_address, address_domain, address))
+            .map(|entry| entry.value)
+    }
+
+    /// Insert (or replace) the value of a storage slot.
+    ///
+    /// The `read_cost` is charged when the slot is read from the cache, and must be the gas the
+    /// wrapped handler would have charged for the read to keep gas parity with uncached execution.
+    pub fn insert(
+        &mut self,
+        contract_address: Felt,
+        address_domain: u32,
+        address: Felt,
+        value: Felt,
+        read_cost: u128,
+    ) {
+        self.entries.insert(
+            (contract_address, address_domain, address),
+            CacheEntry { value, read_cost },
+        );
+    }
+
+    /// Remove a single storage slot from the cache.
+    pub fn invalidate(&mut self, contract_address: Felt, address_domain: u32, address: Felt) {
+        self.entries
+            .remove(&(contract_address, address_domain, address));
+    }
+
+    /// Remove every storage slot of a contract from the cache.
+    pub fn invalidate_contract(&mut self, contract_address: Felt) {
+        self.entries
+            .retain(|(entry_address, ..), _| *entry_address != contract_address);
+    }
+
+    /// Remove every storage slot from the cache. Statistics are kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of reads served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of reads forwarded to the wrapped handlers.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+/// A syscall handler which serves repeated storage reads of a contract from a [`StorageCache`],
+/// forwarding everything else to the wrapped handler.
+#[derive(Debug)]
+pub struct CachingSyscallHandler<'a, T> {
+    inner: T,
+    cache: &'a mut StorageCache,
+    contract_address: Felt,
+}
+
+impl<'a, T> CachingSyscallHandler<'a, T>
+where
+    T: StarknetSyscallHandler,
+{
+    /// Wrap the handler of an invocation of the contract at `contract_address`.
+    pub fn new(inner: T, cache: &'a mut StorageCache, contract_address: Felt) -> Self {
+        Self {
+            inner,
+            cache,
+            contract_address,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> StarknetSyscallHandler for CachingSyscallHandler<'_, T>
+where
+    T: StarknetSyscallHandler,
+{
+    fn get_block_hash(
+        &mut self,
+        block_number: u64,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        self.inner.get_block_hash(block_number, remaining_gas)
+    }
+
+    fn get_execution_info(&mut self, remaining_gas: &mut u128) -> SyscallResult<ExecutionInfo> {
+        self.inner.get_execution_info(remaining_gas)
+    }
+
+    fn get_execution_info_v2(
+        &mut self,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<ExecutionInfoV2> {
+        self.inner.get_execution_info_v2(remaining_gas)
+    }
+
+    fn deploy(
+        &mut self,
+        class_hash: Felt,
+        contract_address_salt: Felt,
+        calldata: &[Felt],
+        deploy_from_zero: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(Felt, Vec<Felt>)> {
+        let result = self.inner.deploy(
+            class_hash,
+            contract_address_salt,
+            calldata,
+            deploy_from_zero,
+            remaining_gas,
+        );
+        self.cache.clear();
+        result
+    }
+
+    fn replace_class(&mut self, class_hash: Felt, remaining_gas: &mut u128) -> SyscallResult<()> {
+        self.inner.replace_class(class_hash, remaining_gas)
+    }
+
+    fn library_call(
+        &mut self,
+        class_hash: Felt,
+        function_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        let result =
+            self.inner
+                .library_call(class_hash, function_selector, calldata, remaining_gas);
+        self.cache.clear();
+        result
+    }
+
+    fn call_contract(
+        &mut self,
+        address: Felt,
+        entry_point_selector: Felt,
+        calldata: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        let result =
+            self.inner
+                .call_contract(address, entry_point_selector, calldata, remaining_gas);
+        self.cache.clear();
+        result
+    }
+
+    fn storage_read(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        if let Some(entry) = self
+            .cache
+            .entries
+            .get(&(self.contract_address, address_domain, address))
+            .copied()
+        {
+            if *remaining_gas < entry.read_cost {
+                return Err(vec![Felt::from_bytes_be_slice(b"Syscall out of gas")]);
+            }
+
+            *remaining_gas -= entry.read_cost;
+            self.cache.hits += 1;
+            return Ok(entry.value);
+        }
+
+        self.cache.misses += 1;
+        let initial_gas = *remaining_gas;
+        let value = self
+            .inner
+            .storage_read(address_domain, address, remaining_gas)?;
+        self.cache.insert(
+            self.contract_address,
+            address_domain,
+            address,
+            value,
+            initial_gas.saturating_sub(*remaining_gas),
+        );
+
+        Ok(value)
+    }
+
+    fn storage_write(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        value: Felt,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.cache
+            .invalidate(self.contract_address, address_domain, address);
+        self.inner
+            .storage_write(address_domain, address, value, remaining_gas)
+    }
+
+    fn emit_event(
+        &mut self,
+        keys: &[Felt],
+        data: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.inner.emit_event(keys, data, remaining_gas)
+    }
+
+    fn send_message_to_l1(
+        &mut self,
+        to_address: Felt,
+        payload: &[Felt],
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        self.inner
+            .send_message_to_l1(to_address, payload, remaining_gas)
+    }
+
+    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+        self.inner.keccak(input, remaining_gas)
+    }
+
+    fn secp256k1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.inner.secp256k1_new(x, y, remaining_gas)
+    }
+
+    fn secp256k1_add(
+        &mut self,
+        p0: Secp256k1Point,
+        p1: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        self.inner.secp256k1_add(p0, p1, remaining_gas)
+    }
+
+    fn secp256k1_mul(
+        &mut self,
+        p: Secp256k1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        self.inner.secp256k1_mul(p, m, remaining_gas)
+    }
+
+    fn secp256k1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        self.inner
+            .secp256k1_get_point_from_x(x, y_parity, remaining_gas)
+    }
+
+    fn secp256k1_get_xy(
+        &mut self,
+        p: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        self.inner.secp256k1_get_xy(p, remaining_gas)
+    }
+
+    fn secp256r1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        self.inner.secp256r1_new(x, y, remaining_gas)
+    }
+
+    fn secp256r1_add(
+        &mut self,
+        p0: Secp256r1Point,
+        p1: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        self.inner.secp256r1_add(p0, p1, remaining_gas)
+    }
+
+    fn secp256r1_mul(
+        &mut self,
+        p: Secp256r1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        self.inner.secp256r1_mul(p, m, remaining_gas)
+    }
+
+    fn secp256r1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        self.inner
+            .secp256r1_get_point_from_x(x, y_parity, remaining_gas)
+    }
+
+    fn secp256r1_get_xy(
+        &mut self,
+        p: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        self.inner.secp256r1_get_xy(p, remaining_gas)
+    }
+
+    fn payload_limits(&self) -> PayloadLimits {
+        self.inner.payload_limits()
+    }
+
+    fn profile_syscalls(&self) -> bool {
+        self.inner.profile_syscalls()
+    }
+
+    #[cfg(feature = "with-cheatcode")]
+    fn cheatcode(&mut self, selector: Felt, input: &[Felt]) -> Vec<Felt> {
+        self.inner.cheatcode(selector, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::starknet_stub::StubSyscallHandler;
+
+    #[test]
+    fn repeated_reads_hit_the_cache() {
+        let mut stub = StubSyscallHandler::default();
+        stub.storage.insert((0, 1.into()), 42.into());
+
+        let mut cache = StorageCache::new();
+        let mut handler = CachingSyscallHandler::new(&mut stub, &mut cache, 3.into());
+        assert_eq!(handler.storage_read(0, 1.into(), &mut 0), Ok(42.into()));
+        assert_eq!(handler.storage_read(0, 1.into(), &mut 0), Ok(42.into()));
+        drop(handler);
+
+        // The cache outlives the handler.
+        stub.storage.insert((0, 1.into()), 43.into());
+        let mut handler = CachingSyscallHandler::new(&mut stub, &mut cache, 3.into());
+        assert_eq!(handler.storage_read(0, 1.into(), &mut 0), Ok(42.into()));
+        drop(handler);
+
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+    }
+
+    #[test]
+    fn writes_invalidate_the_slot() {
+        let mut stub = StubSyscallHandler::default();
+        stub.storage.insert((0, 1.into()), Felt::ZERO);
+
+        let mut cache = StorageCache::new();
+        let mut handler = CachingSyscallHandler::new(&mut stub, &mut cache, 3.into());
+
+        assert_eq!(handler.storage_read(0, 1.into(), &mut 0), Ok(Felt::ZERO));
+        handler
+            .storage_write(0, 1.into(), 5.into(), &mut 0)
+            .unwrap();
+        assert_eq!(handler.storage_read(0, 1.into(), &mut 0), Ok(5.into()));
+        drop(handler);
+
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    }
+
+    #[test]
+    fn contracts_are_cached_separately() {
+        let mut stub = StubSyscallHandler::default();
+        let mut cache = StorageCache::new();
+        cache.insert(3.into(), 0, 1.into(), 7.into(), 0);
+        cache.insert(4.into(), 0, 1.into(), 8.into(), 0);
+
+        let mut handler = CachingSyscallHandler::new(&mut stub, &mut cache, 4.into());
+        assert_eq!(handler.storage_read(0, 1.into(), &mut 0), Ok(8.into()));
+        drop(handler);
+
+        cache.invalidate_contract(4.into());
+        assert_eq!(cache.get(3.into(), 0, 1.into()), Some(7.into()));
+        assert_eq!(cache.get(4.into(), 0, 1.into()), None);
+    }
+
+    #[test]
+    fn cached_reads_are_charged() {
+        let mut stub = StubSyscallHandler::default();
+        let mut cache = StorageCache::new();
+        cache.insert(3.into(), 0, 1.into(), 7.into(), 100);
+
+        let mut handler = CachingSyscallHandler::new(&mut stub, &mut cache, 3.into());
+        let mut gas = 150;
+        assert_eq!(handler.storage_read(0, 1.into(), &mut gas), Ok(7.into()));
+        assert_eq!(gas, 50);
+        assert!(handler.storage_read(0, 1.into(), &mut gas).is_err());
+    }
+
+    #[test]
+    fn hits_and_misses_use_the_same_gas() {
+        let mut inner = MeteredSyscallHandler {
+            storage: HashMap::from([((0, 1.into()), 42.into())]),
+            read_cost: 100,
+        };
+        let mut cache = StorageCache::new();
+
+        let mut uncached_gas = 1000;
+        assert_eq!(
+            (&mut inner).storage_read(0, 1.into(), &mut uncached_gas),
+            Ok(42.into())
+        );
+
+        let mut handler = CachingSyscallHandler::new(&mut inner, &mut cache, 3.into());
+        let mut miss_gas = 1000;
+        assert_eq!(
+            handler.storage_read(0, 1.into(), &mut miss_gas),
+            Ok(42.into())
+        );
+        let mut hit_gas = 1000;
+        assert_eq!(
+            handler.storage_read(0, 1.into(), &mut hit_gas),
+            Ok(42.into())
+        );
+        drop(handler);
+
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert_eq!(miss_gas, uncached_gas);
+        assert_eq!(hit_gas, uncached_gas);
+    }
+
+    /// A handler which charges a fixed amount of gas for storage reads.
+    struct MeteredSyscallHandler {
+        storage: HashMap<(u32, Felt), Felt>,
+        read_cost: u128,
+    }
+
+    impl StarknetSyscallHandler for &mut MeteredSyscallHandler {
+        fn get_block_hash(&mut self, _: u64, _: &mut u128) -> SyscallResult<Felt> {
+            unimplemented!()
+        }
+
+        fn get_execution_info(&mut self, _: &mut u128) -> SyscallResult<ExecutionInfo> {
+            unimplemented!()
+        }
+
+        fn get_execution_info_v2(&mut self, _: &mut u128) -> SyscallResult<ExecutionInfoV2> {
+            unimplemented!()
+        }
+
+        fn deploy(
+            &mut self,
+            _: Felt,
+            _: Felt,
+            _: &[Felt],
+            _: bool,
+            _: &mut u128,
+        ) -> SyscallResult<(Felt, Vec<Felt>)> {
+            unimplemented!()
+        }
+
+        fn replace_class(&mut self, _: Felt, _: &mut u128) -> SyscallResult<()> {
+            unimplemented!()
+        }
+
+        fn library_call(
+            &mut self,
+            _: Felt,
+            _: Felt,
+            _: &[Felt],
+            _: &mut u128,
+        ) -> SyscallResult<Vec<Felt>> {
+            unimplemented!()
+        }
+
+        fn call_contract(
+            &mut self,
+            _: Felt,
+            _: Felt,
+            _: &[Felt],
+            _: &mut u128,
+        ) -> SyscallResult<Vec<Felt>> {
+            unimplemented!()
+        }
+
+        fn storage_read(
+            &mut self,
+            address_domain: u32,
+            address: Felt,
+            remaining_gas: &mut u128,
+        ) -> SyscallResult<Felt> {
+            *remaining_gas -= self.read_cost;
+            Ok(self
+                .storage
+                .get(&(address_domain, address))
+                .copied()
+                .unwrap_or_default())
+        }
+
+        fn storage_write(&mut self, _: u32, _: Felt, _: Felt, _: &mut u128) -> SyscallResult<()> {
+            unimplemented!()
+        }
+
+        fn emit_event(&mut self, _: &[Felt], _: &[Felt], _: &mut u128) -> SyscallResult<()> {
+            unimplemented!()
+        }
+
+        fn send_message_to_l1(&mut self, _: Felt, _: &[Felt], _: &mut u128) -> SyscallResult<()> {
+            unimplemented!()
+        }
+    }
+}
@@ -0,0 +1,570 @@
+//! A deterministic, seedable implementation of the starknet syscall handler trait, meant for
+//! fuzzing contract logic under native execution without a real network.
+//!
+//! Every response that would normally depend on chain state is derived from the seed:
+//!
+//!   - Storage slots that have not been written return a value derived from `(seed, domain,
+//!     address)`, so reading the same slot twice always yields the same value regardless of the
+//!     order of the calls.
+//!   - Contract calls, library calls and deployments return pseudo-random results drawn from a
+//!     sequential generator, so the same seed and the same sequence of syscalls always produces
+//!     the same responses.
+//!   - Cryptographic syscalls (keccak, secp256k1, secp256r1) are computed for real, since random
+//!     results there would just make the contract fail on its own invariants.
+
+use crate::{
+    starknet::{
+        BlockInfo, ExecutionInfo, ExecutionInfoV2, Secp256k1Point, Secp256r1Point,
+        StarknetSyscallHandler, SyscallResult, TxV2Info, U256,
+    },
+    starknet_stub::{StubEvent, StubSyscallHandler},
+};
+use starknet_types_core::felt::Felt;
+use std::collections::HashMap;
+use tracing::instrument;
+
+/// Configuration of the value ranges generated by [`FuzzSyscallHandler`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzConfig {
+    /// Maximum number of felts returned by `call_contract` and `library_call`.
+    pub max_retdata_len: usize,
+    /// Probability (from 0.0 to 1.0) of a contract call, library call or deployment failing.
+    pub call_failure_rate: f64,
+    /// Probability (from 0.0 to 1.0) of a storage read returning zero instead of a random value.
+    pub storage_zero_rate: f64,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            max_retdata_len: 4,
+            call_failure_rate: 0.0,
+            storage_zero_rate: 0.5,
+        }
+    }
+}
+
+/// A seedable syscall handler whose responses are derived from a seed.
+///
+/// Gas is not deducted, except by the syscalls that are delegated to [`StubSyscallHandler`].
+#[derive(Debug, Clone)]
+pub struct FuzzSyscallHandler {
+    seed: u64,
+    state: u64,
+    pub config: FuzzConfig,
+    /// Values written during the execution, which take precedence over the generated ones.
+    pub storage: HashMap<(u32, Felt), Felt>,
+    pub events: Vec<StubEvent>,
+    pub execution_info: ExecutionInfoV2,
+    crypto: StubSyscallHandler,
+}
+
+impl FuzzSyscallHandler {
+    /// Create a handler with the default configuration.
+    pub fn new(seed: u64) -> Self {
+        Self::with_config(seed, FuzzConfig::default())
+    }
+
+    /// Create a handler with a custom configuration.
+    pub fn with_config(seed: u64, config: FuzzConfig) -> Self {
+        let mut handler = Self {
+            seed,
+            state: seed,
+            config,
+            storage: HashMap::new(),
+            events: Vec::new(),
+            execution_info: StubSyscallHandler::default().execution_info,
+            crypto: StubSyscallHandler::default(),
+        };
+
+        handler.execution_info = ExecutionInfoV2 {
+            block_info: BlockInfo {
+                block_number: handler.next_u64() >> 1,
+                block_timestamp: handler.next_u64() >> 1,
+                sequencer_address: handler.next_felt(),
+            },
+            tx_info: TxV2Info {
+                version: 1.into(),
+                account_contract_address: handler.next_felt(),
+                max_fee: handler.next_u64().into(),
+                signature: vec![handler.next_felt(), handler.next_felt()],
+                transaction_hash: handler.next_felt(),
+                chain_id: handler.next_felt(),
+                nonce: (handler.next_u64() >> 32).into(),
+                resource_bounds: vec![],
+                tip: 0,
+                paymaster_data: vec![],
+                nonce_data_availability_mode: 0,
+                fee_data_availability_mode: 0,
+                account_deployment_data: vec![],
+            },
+            caller_address: handler.next_felt(),
+            contract_address: handler.next_felt(),
+            entry_point_selector: handler.next_felt(),
+        };
+
+        handler
+    }
+
+    /// The seed this handler was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        splitmix64(self.state)
+    }
+
+    fn next_felt(&mut self) -> Felt {
+        let mut state = self.next_u64();
+        felt_from_stream(|| {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            splitmix64(state)
+        })
+    }
+
+    fn next_retdata(&mut self) -> Vec<Felt> {
+        let len = retdata_len(self.next_u64(), self.config.max_retdata_len);
+        (0..len).map(|_| self.next_felt()).collect()
+    }
+
+    fn next_call_failed(&mut self) -> bool {
+        let sample = self.next_u64();
+        chance(sample, self.config.call_failure_rate)
+    }
+
+    /// Derive the value of a storage slot which has not been written yet. It only depends on the
+    /// seed and the slot, not on the generator state.
+    fn storage_value(&self, address_domain: u32, address: Felt) -> Felt {
+        let mut state = self.seed ^ u64::from(address_domain).rotate_left(32);
+        for chunk in address.to_bytes_be().chunks_exact(8) {
+            state = splitmix64(state ^ u64::from_be_bytes(chunk.try_into().unwrap()));
+        }
+
+        if chance(state, self.config.storage_zero_rate) {
+            return Felt::ZERO;
+        }
+
+        felt_from_stream(|| {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            splitmix64(state)
+        })
+    }
+}
+
+/// Return whether an event with the given probability happens, given a uniformly distributed
+/// sample. A probability of 0.0 never happens, while 1.0 always does.
+fn chance(sample: u64, probability: f64) -> bool {
+    // The top 53 bits, which a `f64` represents exactly, as a value in `[0, 1)`.
+    ((sample >> 11) as f64 / (1u64 << 53) as f64) < probability
+}
+
+/// Return a length from 0 to `max` (both included), given a uniformly distributed sample.
+fn retdata_len(sample: u64, max: usize) -> usize {
+    // When every `u64` is a valid length, the sample is used as is.
+    let len = match u64::try_from(max).ok().and_then(|max| max.checked_add(1)) {
+        Some(bound) => sample % bound,
+        None => sample,
+    };
+    usize::try_from(len).unwrap_or(max)
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Build a felt from 248 random bits, which is always below the prime.
+fn felt_from_stream(mut next: impl FnMut() -> u64) -> Felt {
+    let mut bytes = [0u8; 32];
+    for chunk in bytes.chunks_exact_mut(8) {
+        chunk.copy_from_slice(&next().to_be_bytes());
+    }
+    bytes[0] = 0;
+
+    Felt::from_bytes_be(&bytes)
+}
+
+impl StarknetSyscallHandler for &mut FuzzSyscallHandler {
+    #[instrument(skip(self))]
+    fn get_block_hash(
+        &mut self,
+        block_number: u64,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        tracing::debug!("called");
+        Ok(self.storage_value(u32::MAX, block_number.into()))
+    }
+
+    #[instrument(skip(self))]
+    fn get_execution_info(&mut self, remaining_gas: &mut u128) -> SyscallResult<ExecutionInfo> {
+        tracing::debug!("called");
+        self.crypto.execution_info = self.execution_info.clone();
+        (&mut self.crypto).get_execution_info(remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn get_execution_info_v2(
+        &mut self,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<ExecutionInfoV2> {
+        tracing::debug!("called");
+        Ok(self.execution_info.clone())
+    }
+
+    #[instrument(skip(self))]
+    fn deploy(
+        &mut self,
+        _class_hash: Felt,
+        _contract_address_salt: Felt,
+        _calldata: &[Felt],
+        _deploy_from_zero: bool,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<(Felt, Vec<Felt>)> {
+        tracing::debug!("called");
+        if self.next_call_failed() {
+            return Err(vec![Felt::from_bytes_be_slice(b"fuzz: deploy failed")]);
+        }
+
+        let address = self.next_felt();
+        Ok((address, self.next_retdata()))
+    }
+
+    #[instrument(skip(self))]
+    fn replace_class(&mut self, _class_hash: Felt, _remaining_gas: &mut u128) -> SyscallResult<()> {
+        tracing::debug!("called");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    fn library_call(
+        &mut self,
+        _class_hash: Felt,
+        _function_selector: Felt,
+        _calldata: &[Felt],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        tracing::debug!("called");
+        if self.next_call_failed() {
+            return Err(vec![Felt::from_bytes_be_slice(
+                b"fuzz: library call failed",
+            )]);
+        }
+
+        Ok(self.next_retdata())
+    }
+
+    #[instrument(skip(self))]
+    fn call_contract(
+        &mut self,
+        _address: Felt,
+        _entry_point_selector: Felt,
+        _calldata: &[Felt],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Vec<Felt>> {
+        tracing::debug!("called");
+        if self.next_call_failed() {
+            return Err(vec![Felt::from_bytes_be_slice(b"fuzz: call failed")]);
+        }
+
+        Ok(self.next_retdata())
+    }
+
+    #[instrument(skip(self))]
+    fn storage_read(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<Felt> {
+        tracing::debug!("called");
+        Ok(match self.storage.get(&(address_domain, address)) {
+            Some(value) => *value,
+            None => self.storage_value(address_domain, address),
+        })
+    }
+
+    #[instrument(skip(self))]
+    fn storage_write(
+        &mut self,
+        address_domain: u32,
+        address: Felt,
+        value: Felt,
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        tracing::debug!("called");
+        self.storage.insert((address_domain, address), value);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    fn emit_event(
+        &mut self,
+        keys: &[Felt],
+        data: &[Felt],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        tracing::debug!("called");
+        self.events.push(StubEvent {
+            keys: keys.to_vec(),
+            data: data.to_vec(),
+        });
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    fn send_message_to_l1(
+        &mut self,
+        _to_address: Felt,
+        _payload: &[Felt],
+        _remaining_gas: &mut u128,
+    ) -> SyscallResult<()> {
+        tracing::debug!("called");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    fn keccak(&mut self, input: &[u64], remaining_gas: &mut u128) -> SyscallResult<U256> {
+        tracing::debug!("called");
+        (&mut self.crypto).keccak(input, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256k1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256k1_new(x, y, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256k1_add(
+        &mut self,
+        p0: Secp256k1Point,
+        p1: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256k1_add(p0, p1, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256k1_mul(
+        &mut self,
+        p: Secp256k1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256k1Point> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256k1_mul(p, m, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256k1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256k1Point>> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256k1_get_point_from_x(x, y_parity, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256k1_get_xy(
+        &mut self,
+        p: Secp256k1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256k1_get_xy(p, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256r1_new(
+        &mut self,
+        x: U256,
+        y: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256r1_new(x, y, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256r1_add(
+        &mut self,
+        p0: Secp256r1Point,
+        p1: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256r1_add(p0, p1, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256r1_mul(
+        &mut self,
+        p: Secp256r1Point,
+        m: U256,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Secp256r1Point> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256r1_mul(p, m, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256r1_get_point_from_x(
+        &mut self,
+        x: U256,
+        y_parity: bool,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<Option<Secp256r1Point>> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256r1_get_point_from_x(x, y_parity, remaining_gas)
+    }
+
+    #[instrument(skip(self))]
+    fn secp256r1_get_xy(
+        &mut self,
+        p: Secp256r1Point,
+        remaining_gas: &mut u128,
+    ) -> SyscallResult<(U256, U256)> {
+        tracing::debug!("called");
+        (&mut self.crypto).secp256r1_get_xy(p, remaining_gas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_responses() {
+        let mut a = FuzzSyscallHandler::new(42);
+        let mut b = FuzzSyscallHandler::new(42);
+        let (mut a, mut b) = (&mut a, &mut b);
+
+        assert_eq!(a.execution_info, b.execution_info);
+        for i in 0..16u64 {
+            assert_eq!(
+                a.call_contract(i.into(), 0.into(), &[], &mut 0),
+                b.call_contract(i.into(), 0.into(), &[], &mut 0),
+            );
+            assert_eq!(
+                a.storage_read(0, i.into(), &mut 0),
+                b.storage_read(0, i.into(), &mut 0),
+            );
+        }
+    }
+
+    #[test]
+    fn storage_read_is_order_independent() {
+        let mut a = FuzzSyscallHandler::new(7);
+        let mut b = FuzzSyscallHandler::new(7);
+        let (mut a, mut b) = (&mut a, &mut b);
+
+        let x = a.storage_read(0, 1.into(), &mut 0).unwrap();
+        let y = a.storage_read(0, 2.into(), &mut 0).unwrap();
+        a.call_contract(0.into(), 0.into(), &[], &mut 0).unwrap();
+
+        assert_eq!(b.storage_read(0, 2.into(), &mut 0).unwrap(), y);
+        assert_eq!(b.storage_read(0, 1.into(), &mut 0).unwrap(), x);
+    }
+
+    #[test]
+    fn storage_write_overrides_generated_value() {
+        let mut handler = FuzzSyscallHandler::new(1);
+        let mut handler = &mut handler;
+
+        handler
+            .storage_write(0, 5.into(), 1234.into(), &mut 0)
+            .unwrap();
+        assert_eq!(
+            handler.storage_read(0, 5.into(), &mut 0).unwrap(),
+            1234.into()
+        );
+    }
+
+    #[test]
+    fn retdata_respects_config() {
+        let mut handler = FuzzSyscallHandler::with_config(
+            3,
+            FuzzConfig {
+                max_retdata_len: 2,
+                call_failure_rate: 0.0,
+                storage_zero_rate: 0.0,
+            },
+        );
+        let mut handler = &mut handler;
+
+        for _ in 0..64 {
+            let retdata = handler
+                .library_call(0.into(), 0.into(), &[], &mut 0)
+                .unwrap();
+            assert!(retdata.len() <= 2);
+        }
+    }
+
+    #[test]
+    fn call_failure_rate_bounds() {
+        for (rate, fails) in [(0.0, false), (1.0, true)] {
+            let mut handler = FuzzSyscallHandler::with_config(
+                5,
+                FuzzConfig {
+                    call_failure_rate: rate,
+                    ..FuzzConfig::default()
+                },
+            );
+            let mut handler = &mut handler;
+
+            for i in 0..256u64 {
+                assert_eq!(
+                    handler
+                        .call_contract(i.into(), 0.into(), &[], &mut 0)
+                        .is_err(),
+                    fails
+                );
+                assert_eq!(
+                    handler
+                        .library_call(i.into(), 0.into(), &[], &mut 0)
+                        .is_err(),
+                    fails
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn storage_zero_rate_bounds() {
+        for (rate, zero) in [(0.0, false), (1.0, true)] {
+            let mut handler = FuzzSyscallHandler::with_config(
+                5,
+                FuzzConfig {
+                    storage_zero_rate: rate,
+                    ..FuzzConfig::default()
+                },
+            );
+            let mut handler = &mut handler;
+
+            for i in 0..256u64 {
+                let value = handler.storage_read(0, i.into(), &mut 0).unwrap();
+                assert_eq!(value == Felt::ZERO, zero);
+            }
+        }
+    }
+    #[test]
+    fn retdata_len_bounds() {
+        assert_eq!(retdata_len(7, 0), 0);
+        assert_eq!(retdata_len(7, 3), 3);
+        assert_eq!(retdata_len(8, 3), 0);
+        assert_eq!(retdata_len(u64::MAX, usize::MAX), usize::MAX);
+        assert_eq!(retdata_len(5, usize::MAX), 5);
+    }
+}
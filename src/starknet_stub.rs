@@ -1,19 +1,14 @@
 //! A (somewhat) usable implementation of the starknet syscall handler trait.
 
-use std::{
-    collections::{HashMap, VecDeque},
-    iter::once,
-};
+use std::collections::{HashMap, VecDeque};
 
 use crate::starknet::{
-    BlockInfo, ExecutionInfo, ExecutionInfoV2, Secp256k1Point, Secp256r1Point,
-    StarknetSyscallHandler, SyscallResult, TxInfo, TxV2Info, U256,
-};
-use k256::elliptic_curve::{
-    generic_array::GenericArray,
-    sec1::{FromEncodedPoint, ToEncodedPoint},
+    keccak_syscall, secp256k1_add_syscall, secp256k1_get_point_from_x_syscall,
+    secp256k1_mul_syscall, secp256k1_new_syscall, secp256r1_add_syscall,
+    secp256r1_get_point_from_x_syscall, secp256r1_mul_syscall, secp256r1_new_syscall, BlockInfo,
+    ExecutionInfo, ExecutionInfoV2, Secp256k1Point, Secp256r1Point, StarknetSyscallHandler,
+    SyscallResult, TxInfo, TxV2Info, U256,
 };
-use sec1::point::Coordinates;
 use starknet_types_core::felt::Felt;
 use tracing::instrument;
 
@@ -65,12 +60,14 @@ impl Default for StubSyscallHandler {
 
 /// Event emitted by the emit_event syscall.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StubEvent {
     pub keys: Vec<Felt>,
     pub data: Vec<Felt>,
 }
 
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContractLogs {
     pub events: VecDeque<StubEvent>,
     pub l2_to_l1_messages: VecDeque<L2ToL1Message>,
@@ -178,11 +175,12 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> crate::starknet::SyscallResult<Felt> {
         tracing::debug!("called");
-        if let Some(value) = self.storage.get(&(address_domain, address)) {
-            Ok(*value)
-        } else {
-            Err(vec![Felt::from_bytes_be_slice(b"address not found")])
-        }
+        // Storage which has never been written reads as zero, like in Starknet.
+        Ok(self
+            .storage
+            .get(&(address_domain, address))
+            .copied()
+            .unwrap_or_default())
     }
 
     #[instrument(skip(self))]
@@ -229,38 +227,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
     #[instrument(skip(self))]
     fn keccak(&mut self, input: &[u64], gas: &mut u128) -> SyscallResult<U256> {
         tracing::debug!("called");
-        let length = input.len();
-
-        if length % 17 != 0 {
-            let error_msg = b"Invalid keccak input size";
-            let felt_error = Felt::from_bytes_be_slice(error_msg);
-            return Err(vec![felt_error]);
-        }
-
-        let n_chunks = length / 17;
-        let mut state = [0u64; 25];
-
-        for i in 0..n_chunks {
-            if *gas < KECCAK_ROUND_COST {
-                let error_msg = b"Syscall out of gas";
-                let felt_error = Felt::from_bytes_be_slice(error_msg);
-                return Err(vec![felt_error]);
-            }
-            const KECCAK_ROUND_COST: u128 = 180000;
-            *gas -= KECCAK_ROUND_COST;
-            let chunk = &input[i * 17..(i + 1) * 17]; //(request.input_start + i * 17)?;
-            for (i, val) in chunk.iter().enumerate() {
-                state[i] ^= val;
-            }
-            keccak::f1600(&mut state)
-        }
-
-        // state[0] and state[1] conform the hash_high (u128)
-        // state[2] and state[3] conform the hash_low (u128)
-        SyscallResult::Ok(U256 {
-            lo: state[2] as u128 | ((state[3] as u128) << 64),
-            hi: state[0] as u128 | ((state[1] as u128) << 64),
-        })
+        keccak_syscall(input, gas)
     }
 
     #[instrument(skip(self))]
@@ -271,27 +238,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Option<Secp256k1Point>> {
         tracing::debug!("called");
-        // The following unwraps should be unreachable because the iterator we provide has the
-        // expected number of bytes.
-        let point = k256::ProjectivePoint::from_encoded_point(
-            &k256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    x.lo.to_be_bytes().into_iter().chain(x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    y.lo.to_be_bytes().into_iter().chain(y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        );
-
-        if bool::from(point.is_some()) {
-            Ok(Some(Secp256k1Point { x, y }))
-        } else {
-            Ok(None)
-        }
+        secp256k1_new_syscall(x, y)
     }
 
     #[instrument(skip(self))]
@@ -302,76 +249,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Secp256k1Point> {
         tracing::debug!("called");
-        // The inner unwraps should be unreachable because the iterator we provide has the expected
-        // number of bytes. The outer unwraps depend on the felt values, which should be valid since
-        // they'll be provided by secp256 syscalls.
-        let p0 = k256::ProjectivePoint::from_encoded_point(
-            &k256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    p0.x.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p0.x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    p0.y.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p0.y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        )
-        .unwrap();
-        let p1 = k256::ProjectivePoint::from_encoded_point(
-            &k256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    p1.x.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p1.x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    p1.y.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p1.y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        )
-        .unwrap();
-
-        let p = p0 + p1;
-
-        let p = p.to_encoded_point(false);
-        let (x, y) = match p.coordinates() {
-            Coordinates::Uncompressed { x, y } => (x, y),
-            _ => {
-                // This should be unreachable because we explicitly asked for the uncompressed
-                // encoding.
-                unreachable!()
-            }
-        };
-
-        // The following two unwraps should be safe because the array always has 32 bytes. The other
-        // four are definitely safe because the slicing guarantees its length to be the right one.
-        let x: [u8; 32] = x.as_slice().try_into().unwrap();
-        let y: [u8; 32] = y.as_slice().try_into().unwrap();
-        Ok(Secp256k1Point {
-            x: U256 {
-                lo: u128::from_be_bytes(x[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(x[16..32].try_into().unwrap()),
-            },
-            y: U256 {
-                lo: u128::from_be_bytes(y[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(y[16..32].try_into().unwrap()),
-            },
-        })
+        secp256k1_add_syscall(p0, p1)
     }
 
     #[instrument(skip(self))]
@@ -382,62 +260,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Secp256k1Point> {
         tracing::debug!("called");
-        // The inner unwrap should be unreachable because the iterator we provide has the expected
-        // number of bytes. The outer unwrap depends on the felt values, which should be valid since
-        // they'll be provided by secp256 syscalls.
-        let p = k256::ProjectivePoint::from_encoded_point(
-            &k256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    p.x.lo.to_be_bytes().into_iter().chain(p.x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    p.y.lo.to_be_bytes().into_iter().chain(p.y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        )
-        .unwrap();
-        let m: k256::Scalar = k256::elliptic_curve::ScalarPrimitive::from_slice(&{
-            let mut buf = [0u8; 32];
-            buf[0..16].copy_from_slice(&m.lo.to_be_bytes());
-            buf[16..32].copy_from_slice(&m.hi.to_be_bytes());
-            buf
-        })
-        .map_err(|_| {
-            vec![Felt::from_bytes_be(
-                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0invalid scalar",
-            )]
-        })?
-        .into();
-
-        let p = p * m;
-
-        let p = p.to_encoded_point(false);
-        let (x, y) = match p.coordinates() {
-            Coordinates::Uncompressed { x, y } => (x, y),
-            _ => {
-                // This should be unreachable because we explicitly asked for the uncompressed
-                // encoding.
-                unreachable!()
-            }
-        };
-
-        // The following two unwraps should be safe because the array always has 32 bytes. The other
-        // four are definitely safe because the slicing guarantees its length to be the right one.
-        let x: [u8; 32] = x.as_slice().try_into().unwrap();
-        let y: [u8; 32] = y.as_slice().try_into().unwrap();
-        Ok(Secp256k1Point {
-            x: U256 {
-                lo: u128::from_be_bytes(x[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(x[16..32].try_into().unwrap()),
-            },
-            y: U256 {
-                lo: u128::from_be_bytes(y[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(y[16..32].try_into().unwrap()),
-            },
-        })
+        secp256k1_mul_syscall(p, m)
     }
 
     #[instrument(skip(self))]
@@ -448,49 +271,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Option<Secp256k1Point>> {
         tracing::debug!("called");
-        // The inner unwrap should be unreachable because the iterator we provide has the expected
-        // number of bytes. The outer unwrap depends on the encoding format, which should be valid
-        // since it's hardcoded..
-        let point = k256::ProjectivePoint::from_encoded_point(
-            &k256::EncodedPoint::from_bytes(
-                k256::CompressedPoint::from_exact_iter(
-                    once(0x02 | y_parity as u8)
-                        .chain(x.lo.to_be_bytes())
-                        .chain(x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-            )
-            .unwrap(),
-        );
-
-        if bool::from(point.is_some()) {
-            // This unwrap has already been checked in the `if` expression's condition.
-            let p = point.unwrap();
-
-            let p = p.to_encoded_point(false);
-            let y = match p.coordinates() {
-                Coordinates::Uncompressed { y, .. } => y,
-                _ => {
-                    // This should be unreachable because we explicitly asked for the uncompressed
-                    // encoding.
-                    unreachable!()
-                }
-            };
-
-            // The following unwrap should be safe because the array always has 32 bytes. The other
-            // two are definitely safe because the slicing guarantees its length to be the right
-            // one.
-            let y: [u8; 32] = y.as_slice().try_into().unwrap();
-            Ok(Some(Secp256k1Point {
-                x,
-                y: U256 {
-                    lo: u128::from_be_bytes(y[0..16].try_into().unwrap()),
-                    hi: u128::from_be_bytes(y[16..32].try_into().unwrap()),
-                },
-            }))
-        } else {
-            Ok(None)
-        }
+        secp256k1_get_point_from_x_syscall(x, y_parity)
     }
 
     #[instrument(skip(self))]
@@ -511,27 +292,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Option<Secp256r1Point>> {
         tracing::debug!("called");
-        // The following unwraps should be unreachable because the iterator we provide has the
-        // expected number of bytes.
-        let point = p256::ProjectivePoint::from_encoded_point(
-            &k256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    x.lo.to_be_bytes().into_iter().chain(x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    y.lo.to_be_bytes().into_iter().chain(y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        );
-
-        if bool::from(point.is_some()) {
-            Ok(Some(Secp256r1Point { x, y }))
-        } else {
-            Ok(None)
-        }
+        secp256r1_new_syscall(x, y)
     }
 
     #[instrument(skip(self))]
@@ -542,76 +303,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Secp256r1Point> {
         tracing::debug!("called");
-        // The inner unwraps should be unreachable because the iterator we provide has the expected
-        // number of bytes. The outer unwraps depend on the felt values, which should be valid since
-        // they'll be provided by secp256 syscalls.
-        let p0 = p256::ProjectivePoint::from_encoded_point(
-            &p256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    p0.x.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p0.x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    p0.y.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p0.y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        )
-        .unwrap();
-        let p1 = p256::ProjectivePoint::from_encoded_point(
-            &p256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    p1.x.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p1.x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    p1.y.lo
-                        .to_be_bytes()
-                        .into_iter()
-                        .chain(p1.y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        )
-        .unwrap();
-
-        let p = p0 + p1;
-
-        let p = p.to_encoded_point(false);
-        let (x, y) = match p.coordinates() {
-            Coordinates::Uncompressed { x, y } => (x, y),
-            _ => {
-                // This should be unreachable because we explicitly asked for the uncompressed
-                // encoding.
-                unreachable!()
-            }
-        };
-
-        // The following two unwraps should be safe because the array always has 32 bytes. The other
-        // four are definitely safe because the slicing guarantees its length to be the right one.
-        let x: [u8; 32] = x.as_slice().try_into().unwrap();
-        let y: [u8; 32] = y.as_slice().try_into().unwrap();
-        Ok(Secp256r1Point {
-            x: U256 {
-                lo: u128::from_be_bytes(x[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(x[16..32].try_into().unwrap()),
-            },
-            y: U256 {
-                lo: u128::from_be_bytes(y[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(y[16..32].try_into().unwrap()),
-            },
-        })
+        secp256r1_add_syscall(p0, p1)
     }
 
     #[instrument(skip(self))]
@@ -622,61 +314,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Secp256r1Point> {
         tracing::debug!("called");
-        // The inner unwrap should be unreachable because the iterator we provide has the expected
-        // number of bytes. The outer unwrap depends on the felt values, which should be valid since
-        // they'll be provided by secp256 syscalls.
-        let p = p256::ProjectivePoint::from_encoded_point(
-            &p256::EncodedPoint::from_affine_coordinates(
-                &GenericArray::from_exact_iter(
-                    p.x.lo.to_be_bytes().into_iter().chain(p.x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                &GenericArray::from_exact_iter(
-                    p.y.lo.to_be_bytes().into_iter().chain(p.y.hi.to_be_bytes()),
-                )
-                .unwrap(),
-                false,
-            ),
-        )
-        .unwrap();
-        let m: p256::Scalar = p256::elliptic_curve::ScalarPrimitive::from_slice(&{
-            let mut buf = [0u8; 32];
-            buf[0..16].copy_from_slice(&m.lo.to_be_bytes());
-            buf[16..32].copy_from_slice(&m.hi.to_be_bytes());
-            buf
-        })
-        .map_err(|_| {
-            vec![Felt::from_bytes_be(
-                b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0invalid scalar",
-            )]
-        })?
-        .into();
-
-        let p = p * m;
-        let p = p.to_encoded_point(false);
-        let (x, y) = match p.coordinates() {
-            Coordinates::Uncompressed { x, y } => (x, y),
-            _ => {
-                // This should be unreachable because we explicitly asked for the uncompressed
-                // encoding.
-                unreachable!()
-            }
-        };
-
-        // The following two unwraps should be safe because the array always has 32 bytes. The other
-        // four are definitely safe because the slicing guarantees its length to be the right one.
-        let x: [u8; 32] = x.as_slice().try_into().unwrap();
-        let y: [u8; 32] = y.as_slice().try_into().unwrap();
-        Ok(Secp256r1Point {
-            x: U256 {
-                lo: u128::from_be_bytes(x[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(x[16..32].try_into().unwrap()),
-            },
-            y: U256 {
-                lo: u128::from_be_bytes(y[0..16].try_into().unwrap()),
-                hi: u128::from_be_bytes(y[16..32].try_into().unwrap()),
-            },
-        })
+        secp256r1_mul_syscall(p, m)
     }
 
     #[instrument(skip(self))]
@@ -687,38 +325,7 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Option<Secp256r1Point>> {
         tracing::debug!("called");
-        let point = p256::ProjectivePoint::from_encoded_point(
-            &p256::EncodedPoint::from_bytes(
-                p256::CompressedPoint::from_exact_iter(
-                    once(0x02 | y_parity as u8)
-                        .chain(x.lo.to_be_bytes())
-                        .chain(x.hi.to_be_bytes()),
-                )
-                .unwrap(),
-            )
-            .unwrap(),
-        );
-
-        if bool::from(point.is_some()) {
-            let p = point.unwrap();
-
-            let p = p.to_encoded_point(false);
-            let y = match p.coordinates() {
-                Coordinates::Uncompressed { y, .. } => y,
-                _ => unreachable!(),
-            };
-
-            let y: [u8; 32] = y.as_slice().try_into().unwrap();
-            Ok(Some(Secp256r1Point {
-                x,
-                y: U256 {
-                    lo: u128::from_be_bytes(y[0..16].try_into().unwrap()),
-                    hi: u128::from_be_bytes(y[16..32].try_into().unwrap()),
-                },
-            }))
-        } else {
-            Ok(None)
-        }
+        secp256r1_get_point_from_x_syscall(x, y_parity)
     }
 
     #[instrument(skip(self))]
@@ -828,6 +435,49 @@ impl StarknetSyscallHandler for &mut StubSyscallHandler {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_keccak() {
+        let mut test_syscall_handler = StubSyscallHandler::default();
+        let mut test_syscall_handler = &mut test_syscall_handler;
+
+        // keccak256("") = c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470, which
+        // is read as a little endian `u256`.
+        let mut input = [0u64; 17];
+        input[0] = 1;
+        input[16] = 0x8000000000000000;
+        let mut gas = 200000;
+        assert_eq!(
+            test_syscall_handler.keccak(&input, &mut gas).unwrap(),
+            U256 {
+                hi: 0xc003c7dcb27d7e923c23f7860146d2c5,
+                lo: 0x70a4855d04d8fa7b3b2782ca53b600e5,
+            }
+        );
+        assert_eq!(gas, 20000);
+
+        // keccak256("abc") = 4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45.
+        input[0] = 0x01636261;
+        let mut gas = 200000;
+        assert_eq!(
+            test_syscall_handler.keccak(&input, &mut gas).unwrap(),
+            U256 {
+                hi: 0x67d6c826a87bd4c74fa945ea7a65034e,
+                lo: 0x456c2da18ff544ec36a0643ae3e6d1c0,
+            }
+        );
+
+        assert_eq!(
+            test_syscall_handler.keccak(&input[..16], &mut 200000),
+            Err(vec![Felt::from_bytes_be_slice(
+                b"Invalid keccak input size"
+            )])
+        );
+        assert_eq!(
+            test_syscall_handler.keccak(&input, &mut 100000),
+            Err(vec![Felt::from_bytes_be_slice(b"Syscall out of gas")])
+        );
+    }
+
     #[test]
     fn test_secp256k1_get_xy() {
         let p = Secp256k1Point {
@@ -1029,6 +679,89 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn test_secp256k1_infinity() {
+        let mut test_syscall_handler = StubSyscallHandler::default();
+        let mut test_syscall_handler = &mut test_syscall_handler;
+
+        let g = Secp256k1Point {
+            x: U256 {
+                hi: 0x029bfcdb2dce28d959f2815b16f81798,
+                lo: 0x79be667ef9dcbbac55a06295ce870b07,
+            },
+            y: U256 {
+                hi: 0xfd17b448a68554199c47d08ffb10d4b8,
+                lo: 0x483ada7726a3c4655da4fbfc0e1108a8,
+            },
+        };
+        let neg_g = Secp256k1Point {
+            x: g.x,
+            y: U256 {
+                hi: 0x02e84bb7597aabe663b82f6f04ef2777,
+                lo: 0xb7c52588d95c3b9aa25b0403f1eef757,
+            },
+        };
+        let infinity = Secp256k1Point {
+            x: U256 { hi: 0, lo: 0 },
+            y: U256 { hi: 0, lo: 0 },
+        };
+        let order = U256 {
+            hi: 0xbaaedce6af48a03bbfd25e8cd0364141,
+            lo: 0xfffffffffffffffffffffffffffffffe,
+        };
+
+        assert_eq!(
+            test_syscall_handler.secp256k1_new(infinity.x, infinity.y, &mut 10),
+            Ok(Some(infinity))
+        );
+        assert_eq!(
+            test_syscall_handler.secp256k1_add(g, neg_g, &mut 10),
+            Ok(infinity)
+        );
+        assert_eq!(
+            test_syscall_handler.secp256k1_add(infinity, g, &mut 10),
+            Ok(g)
+        );
+        assert_eq!(
+            test_syscall_handler.secp256k1_mul(g, order, &mut 10),
+            Ok(infinity)
+        );
+
+        // The scalar is reduced modulo the order of the curve.
+        assert_eq!(
+            test_syscall_handler.secp256k1_mul(
+                g,
+                U256 {
+                    hi: order.hi + 1,
+                    lo: order.lo,
+                },
+                &mut 10
+            ),
+            Ok(g)
+        );
+    }
+
+    #[test]
+    fn test_secp256k1_invalid_argument() {
+        let mut test_syscall_handler = StubSyscallHandler::default();
+        let mut test_syscall_handler = &mut test_syscall_handler;
+
+        let prime = U256 {
+            hi: 0xfffffffffffffffffffffffefffffc2f,
+            lo: 0xffffffffffffffffffffffffffffffff,
+        };
+        let invalid_argument = Err(vec![Felt::from_bytes_be_slice(b"Invalid argument")]);
+
+        assert_eq!(
+            test_syscall_handler.secp256k1_new(prime, U256 { hi: 0, lo: 0 }, &mut 10),
+            invalid_argument
+        );
+        assert_eq!(
+            test_syscall_handler.secp256k1_get_point_from_x(prime, false, &mut 10),
+            invalid_argument
+        );
+    }
+
     #[test]
     fn test_secp256r1_new() {
         let mut test_syscall_handler = StubSyscallHandler::default();
@@ -1058,7 +791,7 @@ mod tests {
         let mut test_syscall_handler = &mut test_syscall_handler;
 
         let x = U256 { hi: 0, lo: 0 };
-        let y = U256 { hi: 0, lo: 0 };
+        let y = U256 { hi: 1, lo: 0 };
 
         assert!(test_syscall_handler
             .secp256r1_new(x, y, &mut 10)
@@ -1189,6 +922,89 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn test_secp256r1_infinity() {
+        let mut test_syscall_handler = StubSyscallHandler::default();
+        let mut test_syscall_handler = &mut test_syscall_handler;
+
+        let g = Secp256r1Point {
+            x: U256 {
+                hi: 0x77037d812deb33a0f4a13945d898c296,
+                lo: 0x6b17d1f2e12c4247f8bce6e563a440f2,
+            },
+            y: U256 {
+                hi: 0x2bce33576b315ececbb6406837bf51f5,
+                lo: 0x4fe342e2fe1a7f9b8ee7eb4a7c0f9e16,
+            },
+        };
+        let neg_g = Secp256r1Point {
+            x: g.x,
+            y: U256 {
+                hi: 0xd431cca994cea1313449bf97c840ae0a,
+                lo: 0xb01cbd1c01e58065711814b583f061e9,
+            },
+        };
+        let infinity = Secp256r1Point {
+            x: U256 { hi: 0, lo: 0 },
+            y: U256 { hi: 0, lo: 0 },
+        };
+        let order = U256 {
+            hi: 0xbce6faada7179e84f3b9cac2fc632551,
+            lo: 0xffffffff00000000ffffffffffffffff,
+        };
+
+        assert_eq!(
+            test_syscall_handler.secp256r1_new(infinity.x, infinity.y, &mut 10),
+            Ok(Some(infinity))
+        );
+        assert_eq!(
+            test_syscall_handler.secp256r1_add(g, neg_g, &mut 10),
+            Ok(infinity)
+        );
+        assert_eq!(
+            test_syscall_handler.secp256r1_add(infinity, g, &mut 10),
+            Ok(g)
+        );
+        assert_eq!(
+            test_syscall_handler.secp256r1_mul(g, order, &mut 10),
+            Ok(infinity)
+        );
+
+        // The scalar is reduced modulo the order of the curve.
+        assert_eq!(
+            test_syscall_handler.secp256r1_mul(
+                g,
+                U256 {
+                    hi: order.hi + 1,
+                    lo: order.lo,
+                },
+                &mut 10
+            ),
+            Ok(g)
+        );
+    }
+
+    #[test]
+    fn test_secp256r1_invalid_argument() {
+        let mut test_syscall_handler = StubSyscallHandler::default();
+        let mut test_syscall_handler = &mut test_syscall_handler;
+
+        let prime = U256 {
+            hi: 0x00000000ffffffffffffffffffffffff,
+            lo: 0xffffffff000000010000000000000000,
+        };
+        let invalid_argument = Err(vec![Felt::from_bytes_be_slice(b"Invalid argument")]);
+
+        assert_eq!(
+            test_syscall_handler.secp256r1_new(prime, U256 { hi: 0, lo: 0 }, &mut 10),
+            invalid_argument
+        );
+        assert_eq!(
+            test_syscall_handler.secp256r1_get_point_from_x(prime, false, &mut 10),
+            invalid_argument
+        );
+    }
+
     #[test]
     fn test_secp256r1_get_xy() {
         let p = Secp256r1Point {
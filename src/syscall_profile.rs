@@ -0,0 +1,282 @@
+//! # Syscall profiling
+//!
+//! When the syscall handler [enables it](crate::starknet::StarknetSyscallHandler::profile_syscalls),
+//! the latency of every syscall is recorded into the
+//! [ExecutionResult](crate::execution_result::ExecutionResult) of the execution.
+//!
+//! The time of each syscall is split into the time spent inside the handler, and the time spent
+//! marshalling its arguments and results between the native code and the handler. This helps
+//! telling apart slow state backends from marshalling overhead.
+//!
+//! Only the syscalls invoked from the current thread while the execution is running are recorded.
+//! Nested executions (ex. a `call_contract` handler which runs another contract natively) record
+//! their syscalls into their own result, and their time is accounted into the handler time of the
+//! outer syscall.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static PROFILE: RefCell<Option<SyscallProfile>> = const { RefCell::new(None) };
+}
+
+/// Number of buckets of a [LatencyHistogram].
+const NUM_BUCKETS: usize = 32;
+
+/// A histogram of latencies with power-of-two buckets, in nanoseconds.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyHistogram {
+    buckets: [u64; NUM_BUCKETS],
+    total: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let nanos = latency.as_nanos();
+        let idx = (u128::BITS - nanos.leading_zeros()) as usize;
+
+        self.buckets[idx.min(NUM_BUCKETS - 1)] += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    /// The number of recorded latencies.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    pub fn mean(&self) -> Duration {
+        match self.count() {
+            0 => Duration::ZERO,
+            count => Duration::from_nanos((self.total.as_nanos() / u128::from(count)) as u64),
+        }
+    }
+
+    /// Return the buckets which have at least one latency, as their upper bound (exclusive) and
+    /// the number of latencies in them. The last bucket has no upper bound.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count != 0)
+            .map(|(idx, count)| {
+                let upper_bound = (idx != NUM_BUCKETS - 1).then(|| Duration::from_nanos(1 << idx));
+                (upper_bound, *count)
+            })
+    }
+
+    /// Return an upper bound of the given percentile (between 0 and 1), or `None` if it falls in
+    /// the last bucket or there are no latencies.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let target = ((count as f64 * percentile).ceil() as u64).clamp(1, count);
+        let mut accumulated = 0;
+        self.buckets().find_map(|(upper_bound, count)| {
+            accumulated += count;
+            (accumulated >= target).then_some(upper_bound).flatten()
+        })
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (lhs, rhs) in self.buckets.iter_mut().zip(other.buckets) {
+            *lhs += rhs;
+        }
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+}
+
+/// The latencies of a syscall.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyscallLatencies {
+    /// Time spent inside the syscall handler.
+    pub handler: LatencyHistogram,
+    /// Time spent converting the arguments and results between the native code and the handler.
+    pub marshalling: LatencyHistogram,
+}
+
+/// The latencies of every syscall invoked during an execution, by syscall name.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyscallProfile {
+    syscalls: BTreeMap<String, SyscallLatencies>,
+}
+
+impl SyscallProfile {
+    /// Return the latencies of a syscall (ex. `storage_read`), if it has been invoked.
+    pub fn get(&self, name: &str) -> Option<&SyscallLatencies> {
+        self.syscalls.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SyscallLatencies)> {
+        self.syscalls.iter().map(|(name, x)| (name.as_str(), x))
+    }
+
+    /// Add the latencies of another profile (ex. of another execution) to this one.
+    pub fn merge(&mut self, other: &Self) {
+        for (name, latencies) in &other.syscalls {
+            let entry = self.syscalls.entry(name.clone()).or_default();
+            entry.handler.merge(&latencies.handler);
+            entry.marshalling.merge(&latencies.marshalling);
+        }
+    }
+
+    /// Start recording the syscalls of the current thread, until the returned guard is finished or
+    /// dropped.
+    pub(crate) fn start() -> SyscallProfileGuard {
+        SyscallProfileGuard(Some(PROFILE.with(|x| x.replace(Some(Self::default())))))
+    }
+
+    fn record(&mut self, name: &str, handler: Duration, marshalling: Duration) {
+        if !self.syscalls.contains_key(name) {
+            self.syscalls
+                .insert(name.to_string(), SyscallLatencies::default());
+        }
+
+        let latencies = self.syscalls.get_mut(name).unwrap();
+        latencies.handler.record(handler);
+        latencies.marshalling.record(marshalling);
+    }
+}
+
+/// Restores the profile of the outer execution (if any) when dropped.
+pub(crate) struct SyscallProfileGuard(Option<Option<SyscallProfile>>);
+
+impl SyscallProfileGuard {
+    pub fn finish(mut self) -> SyscallProfile {
+        let previous = self.0.take().unwrap();
+        PROFILE.with(|x| x.replace(previous)).unwrap_or_default()
+    }
+}
+
+impl Drop for SyscallProfileGuard {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            PROFILE.with(|x| x.replace(previous));
+        }
+    }
+}
+
+/// Measures a syscall, from its creation until it's dropped. Does nothing when profiling is
+/// disabled.
+pub(crate) struct SyscallTimer {
+    name: &'static str,
+    start: Option<Instant>,
+    handler: Duration,
+}
+
+impl SyscallTimer {
+    pub fn start(name: &'static str) -> Self {
+        let enabled = PROFILE.with(|x| x.borrow().is_some());
+
+        Self {
+            name,
+            start: enabled.then(Instant::now),
+            handler: Duration::ZERO,
+        }
+    }
+
+    /// Invoke the syscall handler, accounting its time as handler time.
+    pub fn handler<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        if self.start.is_none() {
+            return f();
+        }
+
+        let start = Instant::now();
+        let result = f();
+        self.handler += start.elapsed();
+
+        result
+    }
+}
+
+impl Drop for SyscallTimer {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let marshalling = start.elapsed().saturating_sub(self.handler);
+            PROFILE.with(|x| {
+                if let Some(profile) = x.borrow_mut().as_mut() {
+                    profile.record(self.name, self.handler, marshalling);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets() {
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(Duration::from_nanos(0));
+        histogram.record(Duration::from_nanos(3));
+        histogram.record(Duration::from_nanos(3));
+        histogram.record(Duration::from_nanos(1000));
+        histogram.record(Duration::from_secs(10));
+
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.max(), Duration::from_secs(10));
+        assert_eq!(
+            histogram.buckets().collect::<Vec<_>>(),
+            [
+                (Some(Duration::from_nanos(1)), 1),
+                (Some(Duration::from_nanos(4)), 2),
+                (Some(Duration::from_nanos(1024)), 1),
+                (None, 1),
+            ]
+        );
+        assert_eq!(histogram.percentile(0.5), Some(Duration::from_nanos(4)));
+        assert_eq!(histogram.percentile(0.8), Some(Duration::from_nanos(1024)));
+        assert_eq!(histogram.percentile(1.0), None);
+    }
+
+    #[test]
+    fn nested_profiles() {
+        let outer = SyscallProfile::start();
+        drop(SyscallTimer::start("storage_read"));
+
+        let inner = SyscallProfile::start();
+        let mut timer = SyscallTimer::start("storage_write");
+        timer.handler(|| std::thread::sleep(Duration::from_millis(1)));
+        drop(timer);
+        let inner = inner.finish();
+
+        drop(SyscallTimer::start("storage_read"));
+        let outer = outer.finish();
+
+        assert_eq!(
+            inner.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["storage_write"]
+        );
+        assert!(inner.get("storage_write").unwrap().handler.total() >= Duration::from_millis(1));
+        assert_eq!(
+            outer.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["storage_read"]
+        );
+        assert_eq!(outer.get("storage_read").unwrap().handler.count(), 2);
+
+        // Nothing is recorded outside of a profile.
+        drop(SyscallTimer::start("storage_read"));
+        assert!(PROFILE.with(|x| x.borrow().is_none()));
+    }
+}
@@ -0,0 +1,746 @@
+//! # Test support
+//!
+//! Helpers to check that a program behaves the same when compiled with Cairo Native as when run in
+//! the Cairo VM (through `cairo-lang-runner`): same outputs, same remaining gas and same builtin
+//! usage. They're what `cairo-native-run --compare-vm` uses, and crates embedding Cairo Native can
+//! use them to check their own programs in their tests.
+//!
+//! Requires the `testing` feature, which is enabled by `build-cli`.
+
+use crate::{
+    execution_result::{BuiltinStats, ExecutionResult},
+    values::JitValue,
+};
+use anyhow::bail;
+use cairo_felt::Felt252;
+use cairo_lang_runner::{
+    build_hints_dict, Arg, CairoHintProcessor, RunResult, RunResultStarknet, RunResultValue,
+    RunnerError, SierraCasmRunner, StarknetState,
+};
+use cairo_lang_sierra::program::Function;
+use cairo_vm_runner::vm::{
+    runners::{
+        builtin_runner::{
+            BITWISE_BUILTIN_NAME, EC_OP_BUILTIN_NAME, HASH_BUILTIN_NAME, POSEIDON_BUILTIN_NAME,
+            RANGE_CHECK_BUILTIN_NAME, SEGMENT_ARENA_BUILTIN_NAME,
+        },
+        cairo_runner::RunResources,
+    },
+    vm_core::VirtualMachine,
+};
+use itertools::Itertools;
+use starknet_types_core::felt::Felt;
+use std::fmt;
+
+/// The result of running a function in the VM, along with the builtin instances it used.
+#[derive(Debug)]
+pub struct VmRunResult {
+    pub result: RunResultStarknet,
+    pub builtins: BuiltinStats,
+}
+
+/// Run a function in the VM, like [SierraCasmRunner::run_function_with_starknet_context] does, but
+/// also count the builtin instances used.
+pub fn run_vm(
+    runner: &SierraCasmRunner,
+    function: &Function,
+    args: &[Arg],
+    available_gas: Option<usize>,
+) -> Result<VmRunResult, RunnerError> {
+    let initial_gas = runner.get_initial_available_gas(function, available_gas)?;
+    let (entry_code, builtins) = runner.create_entry_code(function, args, initial_gas)?;
+    let footer = SierraCasmRunner::create_code_footer();
+
+    let casm_program = runner.get_casm_program();
+    let (hints_dict, string_to_hint) =
+        build_hints_dict(entry_code.iter().chain(&casm_program.instructions));
+    let assembled_program = casm_program.clone().assemble_ex(&entry_code, &footer);
+
+    let mut hint_processor = CairoHintProcessor {
+        runner: Some(runner),
+        starknet_state: StarknetState::default(),
+        string_to_hint,
+        run_resources: RunResources::default(),
+    };
+    let mut vm = VirtualMachine::new(true);
+    let RunResult {
+        gas_counter,
+        memory,
+        value,
+        profiling_info,
+    } = runner.run_function_with_vm(
+        function,
+        &mut vm,
+        &mut hint_processor,
+        hints_dict,
+        assembled_program.bytecode.iter(),
+        builtins,
+    )?;
+
+    Ok(VmRunResult {
+        result: RunResultStarknet {
+            gas_counter,
+            memory,
+            value,
+            starknet_state: hint_processor.starknet_state,
+            profiling_info,
+        },
+        builtins: vm_builtin_stats(&vm),
+    })
+}
+
+/// Count the builtin instances used by a finished run, from the size of their segments.
+fn vm_builtin_stats(vm: &VirtualMachine) -> BuiltinStats {
+    let mut stats = BuiltinStats::default();
+    for builtin in vm.get_builtin_runners() {
+        let used_cells = vm.get_segment_used_size(builtin.base()).unwrap_or(0);
+        match builtin.name() {
+            BITWISE_BUILTIN_NAME => stats.bitwise = used_cells.div_ceil(5),
+            EC_OP_BUILTIN_NAME => stats.ec_op = used_cells.div_ceil(7),
+            HASH_BUILTIN_NAME => stats.pedersen = used_cells.div_ceil(3),
+            POSEIDON_BUILTIN_NAME => stats.poseidon = used_cells.div_ceil(6),
+            RANGE_CHECK_BUILTIN_NAME => stats.range_check = used_cells,
+            // The segment arena starts with an info entry which isn't an instance.
+            SEGMENT_ARENA_BUILTIN_NAME => stats.segment_arena = used_cells.saturating_sub(3) / 3,
+            _ => {}
+        }
+    }
+
+    stats
+}
+
+/// The results of running the same function with the same inputs on both the VM and Native.
+///
+/// The range checks only match when the program is compiled with
+/// [range check tracking](crate::context::CompilerConfig::with_range_check_tracking).
+#[derive(Debug)]
+pub struct RunComparison {
+    pub vm_value: RunResultValue,
+    pub native_value: RunResultValue,
+    pub vm_gas: Option<Felt252>,
+    pub native_gas: Option<u128>,
+    pub vm_builtins: BuiltinStats,
+    pub native_builtins: BuiltinStats,
+}
+
+impl RunComparison {
+    pub fn new(vm_result: &VmRunResult, native_result: &ExecutionResult) -> anyhow::Result<Self> {
+        Ok(Self {
+            vm_value: vm_result.result.value.clone(),
+            native_value: result_to_runresult(native_result)?,
+            vm_gas: vm_result.result.gas_counter.clone(),
+            native_gas: native_result.remaining_gas,
+            vm_builtins: vm_result.builtins,
+            native_builtins: native_result.builtin_stats,
+        })
+    }
+
+    pub fn values_match(&self) -> bool {
+        self.vm_value == self.native_value
+    }
+
+    pub fn gas_matches(&self) -> bool {
+        self.vm_gas.clone().unwrap_or_else(|| Felt252::from(0))
+            == Felt252::from(self.native_gas.unwrap_or(0))
+    }
+
+    /// Whether both runs used the same builtin instances. The segment arena isn't compared, since
+    /// Native counts the dictionary operations in it instead (check out
+    /// [the segment arena type](crate::types::segment_arena)).
+    pub fn builtins_match(&self) -> bool {
+        let without_segment_arena = |stats: BuiltinStats| BuiltinStats {
+            segment_arena: 0,
+            ..stats
+        };
+
+        without_segment_arena(self.vm_builtins) == without_segment_arena(self.native_builtins)
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.values_match() && self.gas_matches() && self.builtins_match()
+    }
+
+    /// Panic with the whole comparison if the runs don't match.
+    #[track_caller]
+    pub fn assert_match(&self) {
+        assert!(
+            self.is_match(),
+            "the VM and Native runs don't match:\n{self}"
+        );
+    }
+}
+
+impl fmt::Display for RunComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = |is_match| if is_match { "match" } else { "MISMATCH" };
+
+        writeln!(f, "outputs: {}", status(self.values_match()))?;
+        writeln!(f, "  vm:     {:?}", self.vm_value)?;
+        writeln!(f, "  native: {:?}", self.native_value)?;
+
+        writeln!(f, "remaining gas: {}", status(self.gas_matches()))?;
+        match &self.vm_gas {
+            Some(gas) => writeln!(f, "  vm:     {gas}")?,
+            None => writeln!(f, "  vm:     -")?,
+        }
+        match self.native_gas {
+            Some(gas) => writeln!(f, "  native: {gas}")?,
+            None => writeln!(f, "  native: -")?,
+        }
+
+        writeln!(f, "builtins: {}", status(self.builtins_match()))?;
+        writeln!(f, "                 vm      native")?;
+        let (vm, native) = (&self.vm_builtins, &self.native_builtins);
+        let rows = [
+            ("range_check", vm.range_check, native.range_check),
+            ("pedersen", vm.pedersen, native.pedersen),
+            ("bitwise", vm.bitwise, native.bitwise),
+            ("ec_op", vm.ec_op, native.ec_op),
+            ("poseidon", vm.poseidon, native.poseidon),
+            ("segment_arena", vm.segment_arena, native.segment_arena),
+        ];
+        for (name, vm, native) in rows {
+            writeln!(f, "  {:<14} {vm:<7} {native}", format!("{name}:"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Convert the execution result to a run result.
+pub fn result_to_runresult(result: &ExecutionResult) -> anyhow::Result<RunResultValue> {
+    let is_success;
+    let mut felts: Vec<Felt> = Vec::new();
+
+    match &result.return_value {
+        outer_value @ JitValue::Enum {
+            tag,
+            value,
+            debug_name,
+        } => {
+            if debug_name
+                .as_ref()
+                .expect("missing debug name")
+                .starts_with("core::panics::PanicResult::")
+            {
+                is_success = *tag == 0;
+
+                if !is_success {
+                    match &**value {
+                        JitValue::Struct { fields, .. } => {
+                            for field in fields {
+                                let felt = jitvalue_to_felt(field);
+                                felts.extend(felt);
+                            }
+                        }
+                        _ => bail!("unsuported return value in cairo-native"),
+                    }
+                } else {
+                    felts.extend(jitvalue_to_felt(value));
+                }
+            } else {
+                is_success = true;
+                felts.extend(jitvalue_to_felt(outer_value));
+            }
+        }
+        x => {
+            is_success = true;
+            felts.extend(jitvalue_to_felt(x));
+        }
+    }
+
+    let return_values = felts
+        .into_iter()
+        .map(|x| x.to_bigint().into())
+        .collect_vec();
+
+    Ok(match is_success {
+        true => RunResultValue::Success(return_values),
+        false => RunResultValue::Panic(return_values),
+    })
+}
+
+/// Convert a JIT value to a felt.
+fn jitvalue_to_felt(value: &JitValue) -> Vec<Felt> {
+    let mut felts = Vec::new();
+    match value {
+        JitValue::Felt252(felt) => vec![*felt],
+        JitValue::BoundedInt { value, .. } => vec![*value],
+        JitValue::Array(fields) | JitValue::Struct { fields, .. } => {
+            fields.iter().flat_map(jitvalue_to_felt).collect()
+        }
+        JitValue::Enum {
+            value,
+            tag,
+            debug_name,
+        } => {
+            if let Some(debug_name) = debug_name {
+                if debug_name == "core::bool" {
+                    vec![(*tag == 1).into()]
+                } else {
+                    let mut felts = vec![(*tag).into()];
+                    felts.extend(jitvalue_to_felt(value));
+                    felts
+                }
+            } else {
+                todo!()
+            }
+        }
+        JitValue::Felt252Dict { value, .. } => {
+            for (key, value) in value {
+                felts.push(*key);
+                let felt = jitvalue_to_felt(value);
+                felts.extend(felt);
+            }
+
+            felts
+        }
+        JitValue::Uint8(x) => vec![(*x).into()],
+        JitValue::Uint16(x) => vec![(*x).into()],
+        JitValue::Uint32(x) => vec![(*x).into()],
+        JitValue::Uint64(x) => vec![(*x).into()],
+        JitValue::Uint128(x) => vec![(*x).into()],
+        JitValue::Sint8(x) => vec![(*x).into()],
+        JitValue::Sint16(x) => vec![(*x).into()],
+        JitValue::Sint32(x) => vec![(*x).into()],
+        JitValue::Sint64(x) => vec![(*x).into()],
+        JitValue::Sint128(x) => vec![(*x).into()],
+        JitValue::Bytes31(bytes) => vec![Felt::from_bytes_le_slice(bytes)],
+        JitValue::EcPoint(x, y) => {
+            vec![*x, *y]
+        }
+        JitValue::EcState(a, b, c, d) => {
+            vec![*a, *b, *c, *d]
+        }
+        JitValue::Secp256K1Point { x, y } => {
+            vec![x.0.into(), x.1.into(), y.0.into(), y.1.into()]
+        }
+        JitValue::Secp256R1Point { x, y } => {
+            vec![x.0.into(), x.1.into(), y.0.into(), y.1.into()]
+        }
+        JitValue::Null => vec![0.into()],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn builtins_match_ignores_the_segment_arena() {
+        let mut comparison = RunComparison {
+            vm_value: RunResultValue::Success(Vec::new()),
+            native_value: RunResultValue::Success(Vec::new()),
+            vm_gas: None,
+            native_gas: None,
+            vm_builtins: BuiltinStats {
+                range_check: 2,
+                segment_arena: 1,
+                ..Default::default()
+            },
+            native_builtins: BuiltinStats {
+                range_check: 2,
+                segment_arena: 4,
+                ..Default::default()
+            },
+        };
+        assert!(comparison.is_match());
+
+        comparison.native_builtins.range_check = 3;
+        assert!(!comparison.builtins_match());
+        assert!(!comparison.is_match());
+    }
+
+    /// Check if subsequence is present in sequence
+    fn is_subsequence<T: PartialEq>(subsequence: &[T], mut sequence: &[T]) -> bool {
+        for search in subsequence {
+            if let Some(index) = sequence.iter().position(|element| search == element) {
+                sequence = &sequence[index + 1..];
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn test_result_to_runresult_enum_nonpanic() {
+        // Tests the conversion of a non-panic enum result to a `RunResultValue::Success`.
+        assert_eq!(
+            result_to_runresult(&ExecutionResult {
+                remaining_gas: None,
+                return_value: JitValue::Enum {
+                    tag: 34,
+                    value: JitValue::Array(vec![
+                        JitValue::Felt252(42.into()),
+                        JitValue::Uint8(100),
+                        JitValue::Uint128(1000),
+                    ])
+                    .into(),
+                    debug_name: Some("debug_name".into()),
+                },
+                builtin_stats: Default::default(),
+                syscall_profile: None,
+            })
+            .unwrap(),
+            RunResultValue::Success(vec![
+                Felt252::from(34),
+                Felt252::from(42),
+                Felt252::from(100),
+                Felt252::from(1000)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_result_to_runresult_success() {
+        // Tests the conversion of a success enum result to a `RunResultValue::Success`.
+        assert_eq!(
+            result_to_runresult(&ExecutionResult {
+                remaining_gas: None,
+                return_value: JitValue::Enum {
+                    tag: 0,
+                    value: JitValue::Uint64(24).into(),
+                    debug_name: Some("core::panics::PanicResult::Test".into()),
+                },
+                builtin_stats: Default::default(),
+                syscall_profile: None,
+            })
+            .unwrap(),
+            RunResultValue::Success(vec![Felt252::from(24)])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unsuported return value in cairo-native")]
+    fn test_result_to_runresult_panic() {
+        // Tests the conversion with unsuported return value.
+        let _ = result_to_runresult(&ExecutionResult {
+            remaining_gas: None,
+            return_value: JitValue::Enum {
+                tag: 10,
+                value: JitValue::Uint64(24).into(),
+                debug_name: Some("core::panics::PanicResult::Test".into()),
+            },
+            builtin_stats: Default::default(),
+            syscall_profile: None,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "missing debug name")]
+    fn test_result_to_runresult_missing_debug_name() {
+        // Tests the conversion with no debug name.
+        let _ = result_to_runresult(&ExecutionResult {
+            remaining_gas: None,
+            return_value: JitValue::Enum {
+                tag: 10,
+                value: JitValue::Uint64(24).into(),
+                debug_name: None,
+            },
+            builtin_stats: Default::default(),
+            syscall_profile: None,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_result_to_runresult_return() {
+        // Tests the conversion of a panic enum result with non-zero tag to a `RunResultValue::Panic`.
+        assert_eq!(
+            result_to_runresult(&ExecutionResult {
+                remaining_gas: None,
+                return_value: JitValue::Enum {
+                    tag: 10,
+                    value: JitValue::Struct {
+                        fields: vec![
+                            JitValue::Felt252(42.into()),
+                            JitValue::Uint8(100),
+                            JitValue::Uint128(1000),
+                        ],
+                        debug_name: Some("debug_name".into()),
+                    }
+                    .into(),
+                    debug_name: Some("core::panics::PanicResult::Test".into()),
+                },
+                builtin_stats: Default::default(),
+                syscall_profile: None,
+            })
+            .unwrap(),
+            RunResultValue::Panic(vec![
+                Felt252::from(42),
+                Felt252::from(100),
+                Felt252::from(1000)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_result_to_runresult_non_enum() {
+        // Tests the conversion of a non-enum result to a `RunResultValue::Success`.
+        assert_eq!(
+            result_to_runresult(&ExecutionResult {
+                remaining_gas: None,
+                return_value: JitValue::Uint8(10),
+                builtin_stats: Default::default(),
+                syscall_profile: None,
+            })
+            .unwrap(),
+            RunResultValue::Success(vec![Felt252::from(10)])
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_felt252() {
+        let felt_value: Felt = 42.into();
+
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Felt252(felt_value)),
+            vec![felt_value]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_array() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Array(vec![
+                JitValue::Felt252(42.into()),
+                JitValue::Uint8(100),
+                JitValue::Uint128(1000),
+            ])),
+            vec![Felt::from(42), Felt::from(100), Felt::from(1000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_struct() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Struct {
+                fields: vec![
+                    JitValue::Felt252(42.into()),
+                    JitValue::Uint8(100),
+                    JitValue::Uint128(1000)
+                ],
+                debug_name: Some("debug_name".into())
+            }),
+            vec![Felt::from(42), Felt::from(100), Felt::from(1000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_enum() {
+        // With debug name
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Enum {
+                tag: 34,
+                value: JitValue::Array(vec![
+                    JitValue::Felt252(42.into()),
+                    JitValue::Uint8(100),
+                    JitValue::Uint128(1000),
+                ])
+                .into(),
+                debug_name: Some("debug_name".into())
+            }),
+            vec![
+                Felt::from(34),
+                Felt::from(42),
+                Felt::from(100),
+                Felt::from(1000)
+            ]
+        );
+
+        // With core::bool debug name and tag 1
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Enum {
+                tag: 1,
+                value: JitValue::Uint128(1000).into(),
+                debug_name: Some("core::bool".into())
+            }),
+            vec![Felt::ONE]
+        );
+
+        // With core::bool debug name and tag not 1
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Enum {
+                tag: 10,
+                value: JitValue::Uint128(1000).into(),
+                debug_name: Some("core::bool".into())
+            }),
+            vec![Felt::ZERO]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_u8() {
+        assert_eq!(jitvalue_to_felt(&JitValue::Uint8(10)), vec![Felt::from(10)]);
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_u16() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Uint16(100)),
+            vec![Felt::from(100)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_u32() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Uint32(1000)),
+            vec![Felt::from(1000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_u64() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Uint64(10000)),
+            vec![Felt::from(10000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_u128() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Uint128(100000)),
+            vec![Felt::from(100000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_sint8() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Sint8(-10)),
+            vec![Felt::from(-10)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_sint16() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Sint16(-100)),
+            vec![Felt::from(-100)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_sint32() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Sint32(-1000)),
+            vec![Felt::from(-1000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_sint64() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Sint64(-10000)),
+            vec![Felt::from(-10000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_sint128() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Sint128(-100000)),
+            vec![Felt::from(-100000)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_null() {
+        assert_eq!(jitvalue_to_felt(&JitValue::Null), vec![Felt::ZERO]);
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_felt252_dict() {
+        let result = jitvalue_to_felt(&JitValue::Felt252Dict {
+            value: HashMap::from([
+                (Felt::ONE, JitValue::Felt252(Felt::from(101))),
+                (Felt::TWO, JitValue::Felt252(Felt::from(102))),
+            ]),
+            debug_name: None,
+        });
+
+        let first_dict_entry = vec![Felt::from(1), Felt::from(101)];
+        let second_dict_entry = vec![Felt::from(2), Felt::from(102)];
+
+        // Check that the two Key, value pairs are in the result
+        assert!(is_subsequence(&first_dict_entry, &result));
+        assert!(is_subsequence(&second_dict_entry, &result));
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_felt252_dict_with_array() {
+        let result = jitvalue_to_felt(&JitValue::Felt252Dict {
+            value: HashMap::from([
+                (
+                    Felt::ONE,
+                    JitValue::Array(Vec::from([
+                        JitValue::Felt252(Felt::from(101)),
+                        JitValue::Felt252(Felt::from(102)),
+                    ])),
+                ),
+                (
+                    Felt::TWO,
+                    JitValue::Array(Vec::from([
+                        JitValue::Felt252(Felt::from(201)),
+                        JitValue::Felt252(Felt::from(202)),
+                    ])),
+                ),
+            ]),
+            debug_name: None,
+        });
+
+        let first_dict_entry = vec![Felt::from(1), Felt::from(101), Felt::from(102)];
+        let second_dict_entry = vec![Felt::from(2), Felt::from(201), Felt::from(202)];
+
+        // Check that the two Key, value pairs are in the result
+        assert!(is_subsequence(&first_dict_entry, &result));
+        assert!(is_subsequence(&second_dict_entry, &result));
+    }
+    #[test]
+    fn test_jitvalue_to_felt_ec_point() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::EcPoint(Felt::ONE, Felt::TWO,)),
+            vec![Felt::ONE, Felt::TWO,]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_ec_state() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::EcState(
+                Felt::ONE,
+                Felt::TWO,
+                Felt::THREE,
+                Felt::from(4)
+            )),
+            vec![Felt::ONE, Felt::TWO, Felt::THREE, Felt::from(4)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_secp256_k1_point() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Secp256K1Point {
+                x: (1, 2),
+                y: (3, 4)
+            }),
+            vec![Felt::ONE, Felt::TWO, Felt::THREE, Felt::from(4)]
+        );
+    }
+
+    #[test]
+    fn test_jitvalue_to_felt_secp256_r1_point() {
+        assert_eq!(
+            jitvalue_to_felt(&JitValue::Secp256R1Point {
+                x: (1, 2),
+                y: (3, 4)
+            }),
+            vec![Felt::ONE, Felt::TWO, Felt::THREE, Felt::from(4)]
+        );
+    }
+}
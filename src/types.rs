@@ -6,11 +6,8 @@ use crate::block_ext::BlockExt;
 use crate::{
     error::Error as CoreTypeBuilderError,
     libfuncs::LibfuncHelper,
-    metadata::{
-        realloc_bindings::ReallocBindingsMeta, runtime_bindings::RuntimeBindingsMeta,
-        MetadataStorage,
-    },
-    utils::{get_integer_layout, layout_repeat, ProgramRegistryExt},
+    metadata::{determinism::DeterminismMeta, MetadataStorage},
+    utils::{get_integer_layout, layout_repeat},
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -129,18 +126,6 @@ pub trait TypeBuilder {
         metadata: &mut MetadataStorage,
         self_ty: &ConcreteTypeId,
     ) -> Result<Value<'ctx, 'this>, Self::Error>;
-
-    #[allow(clippy::too_many_arguments)]
-    fn build_drop<'ctx, 'this>(
-        &self,
-        context: &'ctx Context,
-        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-        entry: &'this Block<'ctx>,
-        location: Location<'ctx>,
-        helper: &LibfuncHelper<'ctx, 'this>,
-        metadata: &mut MetadataStorage,
-        self_ty: &ConcreteTypeId,
-    ) -> Result<(), Self::Error>;
 }
 
 impl TypeBuilder for CoreTypeConcrete {
@@ -491,7 +476,7 @@ impl TypeBuilder for CoreTypeConcrete {
             },
             CoreTypeConcrete::Struct(_) => true,
 
-            CoreTypeConcrete::BoundedInt(_) => todo!(),
+            CoreTypeConcrete::BoundedInt(_) => false,
             CoreTypeConcrete::Const(_) => todo!(),
             CoreTypeConcrete::Span(_) => todo!(),
             CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::Secp256Point(_)) => todo!(),
@@ -572,107 +557,7 @@ impl TypeBuilder for CoreTypeConcrete {
         &self,
         registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     ) -> Result<Layout, Self::Error> {
-        Ok(match self {
-            CoreTypeConcrete::Array(_) => {
-                Layout::new::<*mut ()>()
-                    .extend(get_integer_layout(32))?
-                    .0
-                    .extend(get_integer_layout(32))?
-                    .0
-                    .extend(get_integer_layout(32))?
-                    .0
-            }
-            CoreTypeConcrete::Bitwise(_) => Layout::new::<u64>(),
-            CoreTypeConcrete::Box(_) => Layout::new::<*mut ()>(),
-            CoreTypeConcrete::EcOp(_) => Layout::new::<u64>(),
-            CoreTypeConcrete::EcPoint(_) => layout_repeat(&get_integer_layout(252), 2)?.0,
-            CoreTypeConcrete::EcState(_) => layout_repeat(&get_integer_layout(252), 4)?.0,
-            CoreTypeConcrete::Felt252(_) => get_integer_layout(252),
-            CoreTypeConcrete::GasBuiltin(_) => get_integer_layout(128),
-            CoreTypeConcrete::BuiltinCosts(_) => Layout::new::<()>(),
-            CoreTypeConcrete::Uint8(_) => get_integer_layout(8),
-            CoreTypeConcrete::Uint16(_) => get_integer_layout(16),
-            CoreTypeConcrete::Uint32(_) => get_integer_layout(32),
-            CoreTypeConcrete::Uint64(_) => get_integer_layout(64),
-            CoreTypeConcrete::Uint128(_) => get_integer_layout(128),
-            CoreTypeConcrete::Uint128MulGuarantee(_) => Layout::new::<()>(),
-            CoreTypeConcrete::NonZero(info) => registry.get_type(&info.ty)?.layout(registry)?,
-            CoreTypeConcrete::Nullable(_) => Layout::new::<*mut ()>(),
-            CoreTypeConcrete::RangeCheck(_) => Layout::new::<u64>(),
-            CoreTypeConcrete::Uninitialized(info) => {
-                registry.get_type(&info.ty)?.layout(registry)?
-            }
-            CoreTypeConcrete::Enum(info) => {
-                let tag_layout =
-                    get_integer_layout(info.variants.len().next_power_of_two().trailing_zeros());
-
-                info.variants.iter().try_fold(tag_layout, |acc, id| {
-                    let layout = tag_layout
-                        .extend(registry.get_type(id)?.layout(registry)?)?
-                        .0;
-
-                    Result::<_, Self::Error>::Ok(Layout::from_size_align(
-                        acc.size().max(layout.size()),
-                        acc.align().max(layout.align()),
-                    )?)
-                })?
-            }
-            CoreTypeConcrete::Struct(info) => info
-                .members
-                .iter()
-                .try_fold(Option::<Layout>::None, |acc, id| {
-                    Result::<_, Self::Error>::Ok(Some(match acc {
-                        Some(layout) => layout.extend(registry.get_type(id)?.layout(registry)?)?.0,
-                        None => registry.get_type(id)?.layout(registry)?,
-                    }))
-                })?
-                .unwrap_or(Layout::from_size_align(0, 1)?),
-            CoreTypeConcrete::Felt252Dict(_) => Layout::new::<*mut std::ffi::c_void>(), // ptr
-            CoreTypeConcrete::Felt252DictEntry(_) => {
-                get_integer_layout(252)
-                    .extend(Layout::new::<*mut std::ffi::c_void>())
-                    .unwrap()
-                    .0
-                    .extend(Layout::new::<*mut std::ffi::c_void>())
-                    .unwrap()
-                    .0
-            }
-            CoreTypeConcrete::SquashedFelt252Dict(_) => Layout::new::<*mut std::ffi::c_void>(), // ptr
-            CoreTypeConcrete::Pedersen(_) => Layout::new::<u64>(),
-            CoreTypeConcrete::Poseidon(_) => Layout::new::<u64>(),
-            CoreTypeConcrete::Span(_) => todo!(),
-            CoreTypeConcrete::StarkNet(info) => match info {
-                StarkNetTypeConcrete::ClassHash(_) => get_integer_layout(252),
-                StarkNetTypeConcrete::ContractAddress(_) => get_integer_layout(252),
-                StarkNetTypeConcrete::StorageBaseAddress(_) => get_integer_layout(252),
-                StarkNetTypeConcrete::StorageAddress(_) => get_integer_layout(252),
-                StarkNetTypeConcrete::System(_) => Layout::new::<*mut ()>(),
-                StarkNetTypeConcrete::Secp256Point(_) => {
-                    get_integer_layout(256)
-                        .extend(get_integer_layout(256))
-                        .unwrap()
-                        .0
-                }
-            },
-            CoreTypeConcrete::SegmentArena(_) => Layout::new::<u64>(),
-            CoreTypeConcrete::Snapshot(info) => registry.get_type(&info.ty)?.layout(registry)?,
-            CoreTypeConcrete::Sint8(_) => get_integer_layout(8),
-            CoreTypeConcrete::Sint16(_) => get_integer_layout(16),
-            CoreTypeConcrete::Sint32(_) => get_integer_layout(32),
-            CoreTypeConcrete::Sint64(_) => get_integer_layout(64),
-            CoreTypeConcrete::Sint128(_) => get_integer_layout(128),
-            CoreTypeConcrete::Bytes31(_) => get_integer_layout(248),
-            CoreTypeConcrete::BoundedInt(info) => get_integer_layout(
-                (info.range.lower.bits().max(info.range.upper.bits()) + 1)
-                    .try_into()
-                    .expect("should always fit u32"),
-            ),
-            CoreTypeConcrete::Const(const_type) => {
-                registry.get_type(&const_type.inner_ty)?.layout(registry)?
-            }
-            CoreTypeConcrete::Coupon(_) => Layout::new::<()>(),
-        }
-        .pad_to_align())
+        build_layout(self, |id| registry.get_type(id)?.layout(registry))
     }
 
     fn is_memory_allocated(&self, registry: &ProgramRegistry<CoreType, CoreLibfunc>) -> bool {
@@ -763,7 +648,9 @@ impl TypeBuilder for CoreTypeConcrete {
             Self::Sint64(_) => Some(64),
             Self::Sint128(_) => Some(128),
 
-            CoreTypeConcrete::BoundedInt(_) => Some(252),
+            CoreTypeConcrete::BoundedInt(info) => {
+                Some(self::bounded_int::width(&info.range) as usize)
+            }
             CoreTypeConcrete::Bytes31(_) => Some(248),
             CoreTypeConcrete::Const(_) => todo!(),
 
@@ -785,9 +672,7 @@ impl TypeBuilder for CoreTypeConcrete {
             Self::Sint64(_) => Some(true),
             Self::Sint128(_) => Some(true),
 
-            CoreTypeConcrete::BoundedInt(info) => {
-                Some(info.range.lower.is_negative() || info.range.upper.is_negative())
-            }
+            CoreTypeConcrete::BoundedInt(info) => Some(info.range.lower.is_negative()),
             CoreTypeConcrete::Bytes31(_) => Some(false),
             CoreTypeConcrete::Const(_) => todo!(),
 
@@ -816,7 +701,7 @@ impl TypeBuilder for CoreTypeConcrete {
         entry: &'this Block<'ctx>,
         location: Location<'ctx>,
         _helper: &LibfuncHelper<'ctx, 'this>,
-        _metadata: &mut MetadataStorage,
+        metadata: &mut MetadataStorage,
         _self_ty: &ConcreteTypeId,
     ) -> Result<Value<'ctx, 'this>, Self::Error> {
         static BOOL_USER_TYPE_ID: OnceLock<UserTypeId> = OnceLock::new();
@@ -828,7 +713,8 @@ impl TypeBuilder for CoreTypeConcrete {
                 GenericArg::UserType(id) if id == bool_user_type_id => {
                     let tag = entry.const_int(context, location, 0, 1)?;
 
-                    let value = entry.append_op_result(llvm::undef(
+                    let value = entry.append_op_result(DeterminismMeta::build_undef(
+                        metadata,
                         llvm::r#type::r#struct(
                             context,
                             &[
@@ -856,50 +742,119 @@ impl TypeBuilder for CoreTypeConcrete {
             _ => unimplemented!("unsupported dict value type"),
         })
     }
+}
 
-    fn build_drop<'ctx, 'this>(
-        &self,
-        context: &'ctx Context,
-        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-        entry: &'this Block<'ctx>,
-        location: Location<'ctx>,
-        helper: &LibfuncHelper<'ctx, 'this>,
-        metadata: &mut MetadataStorage,
-        self_ty: &ConcreteTypeId,
-    ) -> Result<(), Self::Error> {
-        match self {
-            CoreTypeConcrete::Array(_info) => {
-                if metadata.get::<ReallocBindingsMeta>().is_none() {
-                    metadata.insert(ReallocBindingsMeta::new(context, helper));
-                }
-
-                let array_ty = registry.build_type(context, helper, registry, metadata, self_ty)?;
-
-                let ptr_ty = crate::ffi::get_struct_field_type_at(&array_ty, 0);
-
-                let array_val = entry.argument(0)?.into();
-
-                let ptr = entry.extract_value(context, location, array_val, ptr_ty, 0)?;
-
-                entry.append_operation(ReallocBindingsMeta::free(context, ptr, location));
-            }
-            CoreTypeConcrete::Felt252Dict(_) | CoreTypeConcrete::SquashedFelt252Dict(_) => {
-                let runtime: &mut RuntimeBindingsMeta = metadata.get_mut().unwrap();
-                let ptr = entry.argument(0)?.into();
-
-                runtime.dict_alloc_free(context, helper, ptr, entry, location)?;
+/// Compute the layout of a type from the layouts of the types it contains, which are obtained
+/// through `member_layout`.
+pub(crate) fn build_layout(
+    ty: &CoreTypeConcrete,
+    mut member_layout: impl FnMut(&ConcreteTypeId) -> Result<Layout, CoreTypeBuilderError>,
+) -> Result<Layout, CoreTypeBuilderError> {
+    Ok(match ty {
+        CoreTypeConcrete::Array(_) => {
+            Layout::new::<*mut ()>()
+                .extend(get_integer_layout(32))?
+                .0
+                .extend(get_integer_layout(32))?
+                .0
+                .extend(get_integer_layout(32))?
+                .0
+        }
+        CoreTypeConcrete::Bitwise(_) => Layout::new::<u64>(),
+        CoreTypeConcrete::Box(_) => Layout::new::<*mut ()>(),
+        CoreTypeConcrete::EcOp(_) => Layout::new::<u64>(),
+        CoreTypeConcrete::EcPoint(_) => layout_repeat(&get_integer_layout(252), 2)?.0,
+        CoreTypeConcrete::EcState(_) => layout_repeat(&get_integer_layout(252), 4)?.0,
+        CoreTypeConcrete::Felt252(_) => get_integer_layout(252),
+        CoreTypeConcrete::GasBuiltin(_) => get_integer_layout(128),
+        CoreTypeConcrete::BuiltinCosts(_) => Layout::new::<()>(),
+        CoreTypeConcrete::Uint8(_) => get_integer_layout(8),
+        CoreTypeConcrete::Uint16(_) => get_integer_layout(16),
+        CoreTypeConcrete::Uint32(_) => get_integer_layout(32),
+        CoreTypeConcrete::Uint64(_) => get_integer_layout(64),
+        CoreTypeConcrete::Uint128(_) => get_integer_layout(128),
+        CoreTypeConcrete::Uint128MulGuarantee(_) => Layout::new::<()>(),
+        CoreTypeConcrete::NonZero(info) => member_layout(&info.ty)?,
+        CoreTypeConcrete::Nullable(_) => Layout::new::<*mut ()>(),
+        CoreTypeConcrete::RangeCheck(_) => Layout::new::<u64>(),
+        CoreTypeConcrete::Uninitialized(info) => member_layout(&info.ty)?,
+        CoreTypeConcrete::Enum(info) => {
+            let tag_layout =
+                get_integer_layout(info.variants.len().next_power_of_two().trailing_zeros());
+
+            info.variants.iter().try_fold(tag_layout, |acc, id| {
+                let layout = tag_layout.extend(member_layout(id)?)?.0;
+
+                Result::<_, CoreTypeBuilderError>::Ok(Layout::from_size_align(
+                    acc.size().max(layout.size()),
+                    acc.align().max(layout.align()),
+                )?)
+            })?
+        }
+        CoreTypeConcrete::Struct(info) => info
+            .members
+            .iter()
+            .try_fold(Option::<Layout>::None, |acc, id| {
+                Result::<_, CoreTypeBuilderError>::Ok(Some(match acc {
+                    Some(layout) => layout.extend(member_layout(id)?)?.0,
+                    None => member_layout(id)?,
+                }))
+            })?
+            .unwrap_or(Layout::from_size_align(0, 1)?),
+        CoreTypeConcrete::Felt252Dict(_) => Layout::new::<*mut std::ffi::c_void>(), // ptr
+        CoreTypeConcrete::Felt252DictEntry(_) => {
+            get_integer_layout(252)
+                .extend(Layout::new::<*mut std::ffi::c_void>())
+                .unwrap()
+                .0
+                .extend(Layout::new::<*mut std::ffi::c_void>())
+                .unwrap()
+                .0
+        }
+        CoreTypeConcrete::SquashedFelt252Dict(_) => Layout::new::<*mut std::ffi::c_void>(), // ptr
+        CoreTypeConcrete::Pedersen(_) => Layout::new::<u64>(),
+        CoreTypeConcrete::Poseidon(_) => Layout::new::<u64>(),
+        CoreTypeConcrete::Span(_) => todo!(),
+        CoreTypeConcrete::StarkNet(info) => match info {
+            StarkNetTypeConcrete::ClassHash(_) => get_integer_layout(252),
+            StarkNetTypeConcrete::ContractAddress(_) => get_integer_layout(252),
+            StarkNetTypeConcrete::StorageBaseAddress(_) => get_integer_layout(252),
+            StarkNetTypeConcrete::StorageAddress(_) => get_integer_layout(252),
+            StarkNetTypeConcrete::System(_) => Layout::new::<*mut ()>(),
+            StarkNetTypeConcrete::Secp256Point(_) => {
+                get_integer_layout(256)
+                    .extend(get_integer_layout(256))
+                    .unwrap()
+                    .0
             }
-            CoreTypeConcrete::Box(_) | CoreTypeConcrete::Nullable(_) => {
-                if metadata.get::<ReallocBindingsMeta>().is_none() {
-                    metadata.insert(ReallocBindingsMeta::new(context, helper));
-                }
+        },
+        CoreTypeConcrete::SegmentArena(_) => Layout::new::<u64>(),
+        CoreTypeConcrete::Snapshot(info) => member_layout(&info.ty)?,
+        CoreTypeConcrete::Sint8(_) => get_integer_layout(8),
+        CoreTypeConcrete::Sint16(_) => get_integer_layout(16),
+        CoreTypeConcrete::Sint32(_) => get_integer_layout(32),
+        CoreTypeConcrete::Sint64(_) => get_integer_layout(64),
+        CoreTypeConcrete::Sint128(_) => get_integer_layout(128),
+        CoreTypeConcrete::Bytes31(_) => get_integer_layout(248),
+        CoreTypeConcrete::BoundedInt(info) => {
+            get_integer_layout(self::bounded_int::width(&info.range))
+        }
+        CoreTypeConcrete::Const(const_type) => member_layout(&const_type.inner_ty)?,
+        CoreTypeConcrete::Coupon(_) => Layout::new::<()>(),
+    }
+    .pad_to_align())
+}
 
-                let ptr = entry.argument(0)?.into();
-                entry.append_operation(ReallocBindingsMeta::free(context, ptr, location));
-            }
-            _ => {}
-        };
-        Ok(())
+/// Return the types stored inline within a type, whose layouts make up its own layout.
+pub(crate) fn inline_members(ty: &CoreTypeConcrete) -> &[ConcreteTypeId] {
+    match ty {
+        CoreTypeConcrete::NonZero(info)
+        | CoreTypeConcrete::Uninitialized(info)
+        | CoreTypeConcrete::Snapshot(info) => std::slice::from_ref(&info.ty),
+        CoreTypeConcrete::Enum(info) => &info.variants,
+        CoreTypeConcrete::Struct(info) => &info.members,
+        CoreTypeConcrete::Const(const_type) => std::slice::from_ref(&const_type.inner_ty),
+        _ => &[],
     }
 }
 
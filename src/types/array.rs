@@ -11,21 +11,41 @@
 //! | ----- | -------------- | ------------------------ |
 //! |   0   | `!llvm.ptr<T>` | Pointer to the data[^1]. |
 //! |   1   | `i32`          | Array start offset[^2].  |
-//! |   1   | `i32`          | Array end offset[^2].    |
-//! |   2   | `i32`          | Allocated capacity[^2].  |
+//! |   2   | `i32`          | Array end offset[^2].    |
+//! |   3   | `i32`          | Allocated capacity[^2].  |
 //!
 //! [^1]: When capacity is zero, this field is not guaranteed to be valid.
 //! [^2]: Those numbers are number of items, **not bytes**.
+//!
+//! ## Buffer sharing
+//!
+//! The data is preceded by a [header](HEADER_SIZE) with a reference counter, which allows taking
+//! snapshots of an array without copying it: `snapshot_take` just increments the counter, and the
+//! buffer is copied later only if the original array is appended to while it's still shared
+//! (copy-on-write). Elements popped from a shared buffer are cloned instead of moved, since the
+//! snapshots may still be using them.
+//!
+//! Every snapshot owns one of those references: copying a snapshot increments the counter, and
+//! dropping (or consuming, ex. `array_len`) a snapshot decrements it. The buffer is freed when the
+//! last reference is released, no matter whether it belonged to the array or to a snapshot. Once
+//! all the snapshots are gone the array owns its buffer again and can append in place.
+//!
+//! The same applies to slices (`array_slice`): they share the buffer of the snapshot they come from
+//! and only change the start and end offsets, so slicing is `O(1)` no matter how many elements the
+//! array has.
+//!
+//! Elements copied out of a snapshot are cloned, since the buffer still holds them. The elements
+//! which are still in a buffer when it's freed are not dropped.
 
-use super::{TypeBuilder, WithSelf};
+use super::WithSelf;
 use crate::block_ext::BlockExt;
 use crate::{
     error::Result,
     libfuncs::LibfuncHelper,
     metadata::{
-        realloc_bindings::ReallocBindingsMeta, snapshot_clones::SnapshotClonesMeta, MetadataStorage,
+        drop_overrides::DropOverridesMeta, realloc_bindings::ReallocBindingsMeta,
+        snapshot_clones::SnapshotClonesMeta, MetadataStorage,
     },
-    utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
@@ -36,17 +56,25 @@ use cairo_lang_sierra::{
 };
 use melior::{
     dialect::{
-        arith, cf,
+        arith::{self, CmpiPredicate},
+        cf,
         llvm::{self, r#type::pointer},
         ods,
     },
     ir::{
-        attribute::IntegerAttribute, r#type::IntegerType, Block, Location, Module, Type, Value,
-        ValueLike,
+        attribute::{DenseI32ArrayAttribute, IntegerAttribute},
+        r#type::IntegerType,
+        Block, Location, Module, Type, Value,
     },
     Context,
 };
 
+/// Size in bytes of the header that precedes the elements of every array buffer.
+///
+/// It only holds the reference counter (an `i32`), but it's padded so that the elements keep the
+/// alignment guaranteed by the allocator.
+pub const HEADER_SIZE: usize = 16;
+
 /// Build the MLIR type.
 ///
 /// Check out [the module](self) for more info.
@@ -68,6 +96,17 @@ pub fn build<'ctx>(
             },
         );
 
+    metadata
+        .get_or_insert_with::<DropOverridesMeta>(DropOverridesMeta::default)
+        .register(
+            info.self_ty().clone(),
+            build_drop,
+            InfoAndTypeConcreteType {
+                info: info.info.clone(),
+                ty: info.ty.clone(),
+            },
+        );
+
     let ptr_ty = llvm::r#type::pointer(context, 0);
     let len_ty = IntegerType::new(context, 32).into();
 
@@ -78,167 +117,302 @@ pub fn build<'ctx>(
     ))
 }
 
+/// Release the array's reference to its buffer.
+///
+/// The elements are not dropped, since the buffer keeps stale copies of the elements that have been
+/// moved out of it.
 #[allow(clippy::too_many_arguments)]
-fn snapshot_take<'ctx, 'this>(
+fn build_drop<'ctx, 'this>(
     context: &'ctx Context,
-    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     entry: &'this Block<'ctx>,
     location: Location<'ctx>,
     helper: &LibfuncHelper<'ctx, 'this>,
     metadata: &mut MetadataStorage,
-    info: WithSelf<InfoAndTypeConcreteType>,
+    _info: WithSelf<InfoAndTypeConcreteType>,
+    value: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    let ptr = entry.extract_value(context, location, value, pointer(context, 0), 0)?;
+    build_release_data(context, helper, entry, location, metadata, ptr)
+}
+
+/// Take a snapshot by sharing the array's buffer.
+#[allow(clippy::too_many_arguments)]
+fn snapshot_take<'ctx, 'this>(
+    context: &'ctx Context,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    _metadata: &mut MetadataStorage,
+    _info: WithSelf<InfoAndTypeConcreteType>,
     src_value: Value<'ctx, 'this>,
 ) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
-    if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+    let ptr = entry.extract_value(context, location, src_value, pointer(context, 0), 0)?;
+    let is_null = build_is_null(context, entry, location, ptr)?;
+
+    let retain_block = helper.append_block(Block::new(&[]));
+    let finish_block = helper.append_block(Block::new(&[]));
+    entry.append_operation(cf::cond_br(
+        context,
+        is_null,
+        finish_block,
+        retain_block,
+        &[],
+        &[],
+        location,
+    ));
+
+    {
+        let refcount_ptr = build_refcount_ptr(context, retain_block, location, ptr)?;
+        let refcount = retain_block.load(
+            context,
+            location,
+            refcount_ptr,
+            IntegerType::new(context, 32).into(),
+        )?;
+        let k1 = retain_block.const_int(context, location, 1, 32)?;
+        let refcount = retain_block.append_op_result(arith::addi(refcount, k1, location))?;
+        retain_block.store(context, location, refcount_ptr, refcount)?;
+
+        retain_block.append_operation(cf::br(finish_block, &[], location));
     }
 
-    let elem_snapshot_take = metadata
-        .get::<SnapshotClonesMeta>()
-        .and_then(|meta| meta.wrap_invoke(&info.ty));
+    Ok((finish_block, src_value))
+}
 
-    let elem_ty = registry.get_type(&info.ty)?;
-    let elem_layout = elem_ty.layout(registry)?;
-    let elem_stride = elem_layout.pad_to_align().size();
-    let elem_ty = elem_ty.build(context, helper, registry, metadata, &info.ty)?;
+/// Return whether an array buffer pointer is null, which happens when nothing has been allocated.
+pub(crate) fn build_is_null<'ctx, 'this>(
+    context: &'ctx Context,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    ptr: Value<'ctx, 'this>,
+) -> Result<Value<'ctx, 'this>> {
+    let null_ptr = block
+        .append_op_result(ods::llvm::mlir_zero(context, pointer(context, 0), location).into())?;
 
-    let src_ptr = entry.extract_value(
+    block.append_op_result(
+        ods::llvm::icmp(
+            context,
+            IntegerType::new(context, 1).into(),
+            ptr,
+            null_ptr,
+            IntegerAttribute::new(IntegerType::new(context, 64).into(), 0).into(),
+            location,
+        )
+        .into(),
+    )
+}
+
+/// Return the pointer to the reference counter of a non-null array buffer.
+fn build_refcount_ptr<'ctx, 'this>(
+    context: &'ctx Context,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    ptr: Value<'ctx, 'this>,
+) -> Result<Value<'ctx, 'this>> {
+    block.append_op_result(llvm::get_element_ptr(
         context,
+        ptr,
+        DenseI32ArrayAttribute::new(context, &[-(HEADER_SIZE as i32)]),
+        IntegerType::new(context, 8).into(),
+        pointer(context, 0),
         location,
-        src_value,
-        llvm::r#type::pointer(context, 0),
-        0,
-    )?;
-    let array_start = entry.extract_value(
-        context,
+    ))
+}
+
+/// Return whether an array buffer is shared with a snapshot.
+///
+/// Returns the block where the code generation should continue along with the result.
+pub(crate) fn build_is_shared<'ctx, 'this>(
+    context: &'ctx Context,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    ptr: Value<'ctx, 'this>,
+) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
+    let is_null = build_is_null(context, block, location, ptr)?;
+
+    let load_block = helper.append_block(Block::new(&[]));
+    let finish_block = helper.append_block(Block::new(&[(
+        IntegerType::new(context, 1).into(),
         location,
-        src_value,
-        IntegerType::new(context, 32).into(),
-        1,
-    )?;
-    let array_end = entry.extract_value(
+    )]));
+
+    let k0 = block.const_int(context, location, 0, 1)?;
+    block.append_operation(cf::cond_br(
         context,
+        is_null,
+        finish_block,
+        load_block,
+        &[k0],
+        &[],
         location,
-        src_value,
-        IntegerType::new(context, 32).into(),
-        2,
-    )?;
+    ));
 
-    let elem_stride = entry.const_int(context, location, elem_stride, 64)?;
+    {
+        let refcount_ptr = build_refcount_ptr(context, load_block, location, ptr)?;
+        let refcount = load_block.load(
+            context,
+            location,
+            refcount_ptr,
+            IntegerType::new(context, 32).into(),
+        )?;
+        let k1 = load_block.const_int(context, location, 1, 32)?;
+        let is_shared = load_block.append_op_result(arith::cmpi(
+            context,
+            CmpiPredicate::Ugt,
+            refcount,
+            k1,
+            location,
+        ))?;
 
-    let array_ty = registry.build_type(context, helper, registry, metadata, info.self_ty())?;
+        load_block.append_operation(cf::br(finish_block, &[is_shared], location));
+    }
 
-    let array_len: Value = entry.append_op_result(arith::subi(array_end, array_start, location))?;
+    Ok((finish_block, finish_block.argument(0)?.into()))
+}
 
-    let k0 = entry.const_int_from_type(context, location, 0, array_len.r#type())?;
-    let is_len_zero = entry.append_op_result(arith::cmpi(
+/// Resize an unshared array buffer so that it has room for `size` bytes of elements, or allocate
+/// a new one if `ptr` is null. Returns the new pointer to the elements.
+pub(crate) fn build_realloc_data<'ctx, 'this>(
+    context: &'ctx Context,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    metadata: &mut MetadataStorage,
+    ptr: Value<'ctx, 'this>,
+    size: Value<'ctx, 'this>,
+) -> Result<Value<'ctx, 'this>> {
+    if metadata.get::<ReallocBindingsMeta>().is_none() {
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
+    }
+
+    let is_null = build_is_null(context, block, location, ptr)?;
+    let header_ptr = build_refcount_ptr(context, block, location, ptr)?;
+    let header_ptr = block.append_op_result(arith::select(is_null, ptr, header_ptr, location))?;
+
+    let header_size = block.const_int(context, location, HEADER_SIZE, 64)?;
+    let size = block.append_op_result(arith::addi(size, header_size, location))?;
+    let header_ptr = block.append_op_result(ReallocBindingsMeta::realloc(
+        context, header_ptr, size, location,
+    ))?;
+
+    // Unshared buffers always have a single reference.
+    let k1 = block.const_int(context, location, 1, 32)?;
+    block.store(context, location, header_ptr, k1)?;
+
+    block.append_op_result(llvm::get_element_ptr(
         context,
-        arith::CmpiPredicate::Eq,
-        array_len,
-        k0,
+        header_ptr,
+        DenseI32ArrayAttribute::new(context, &[HEADER_SIZE as i32]),
+        IntegerType::new(context, 8).into(),
+        pointer(context, 0),
         location,
-    ))?;
+    ))
+}
 
-    let null_ptr = entry
+/// Allocate a new array buffer with room for `size` bytes of elements. Returns the pointer to the
+/// elements.
+pub(crate) fn build_alloc_data<'ctx, 'this>(
+    context: &'ctx Context,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    metadata: &mut MetadataStorage,
+    size: Value<'ctx, 'this>,
+) -> Result<Value<'ctx, 'this>> {
+    let null_ptr = block
         .append_op_result(ods::llvm::mlir_zero(context, pointer(context, 0), location).into())?;
+    build_realloc_data(context, helper, block, location, metadata, null_ptr, size)
+}
+
+/// Release a reference to an array buffer, freeing it if it was the last one.
+///
+/// Returns the block where the code generation should continue.
+pub(crate) fn build_release_data<'ctx, 'this>(
+    context: &'ctx Context,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    block: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    metadata: &mut MetadataStorage,
+    ptr: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    if metadata.get::<ReallocBindingsMeta>().is_none() {
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
+    }
 
-    let block_realloc = helper.append_block(Block::new(&[]));
-    let block_finish =
-        helper.append_block(Block::new(&[(llvm::r#type::pointer(context, 0), location)]));
+    let is_null = build_is_null(context, block, location, ptr)?;
 
-    entry.append_operation(cf::cond_br(
+    let release_block = helper.append_block(Block::new(&[]));
+    let free_block = helper.append_block(Block::new(&[]));
+    let finish_block = helper.append_block(Block::new(&[]));
+    block.append_operation(cf::cond_br(
         context,
-        is_len_zero,
-        block_finish,
-        block_realloc,
-        &[null_ptr],
+        is_null,
+        finish_block,
+        release_block,
+        &[],
         &[],
         location,
     ));
 
+    let refcount_ptr = build_refcount_ptr(context, release_block, location, ptr)?;
     {
-        // realloc
-        let dst_len_bytes: Value = {
-            let array_len = block_realloc.append_op_result(arith::extui(
-                array_len,
-                IntegerType::new(context, 64).into(),
-                location,
-            ))?;
-
-            block_realloc.append_op_result(arith::muli(array_len, elem_stride, location))?
-        };
-
-        let dst_ptr = {
-            let dst_ptr = null_ptr;
-
-            block_realloc.append_op_result(ReallocBindingsMeta::realloc(
-                context,
-                dst_ptr,
-                dst_len_bytes,
-                location,
-            ))?
-        };
-
-        let src_ptr_offset = {
-            let array_start = block_realloc.append_op_result(arith::extui(
-                array_start,
-                IntegerType::new(context, 64).into(),
-                location,
-            ))?;
-
-            block_realloc.append_op_result(arith::muli(array_start, elem_stride, location))?
-        };
-        let src_ptr = block_realloc.append_op_result(llvm::get_element_ptr_dynamic(
+        let refcount = release_block.load(
             context,
-            src_ptr,
-            &[src_ptr_offset],
-            IntegerType::new(context, 8).into(),
-            llvm::r#type::pointer(context, 0),
             location,
-        ))?;
+            refcount_ptr,
+            IntegerType::new(context, 32).into(),
+        )?;
+        let k1 = release_block.const_int(context, location, 1, 32)?;
+        let refcount = release_block.append_op_result(arith::subi(refcount, k1, location))?;
+        release_block.store(context, location, refcount_ptr, refcount)?;
 
-        match elem_snapshot_take {
-            Some(elem_snapshot_take) => {
-                let value = block_realloc.load(context, location, src_ptr, elem_ty)?;
-
-                let (block_relloc, value) = elem_snapshot_take(
-                    context,
-                    registry,
-                    block_realloc,
-                    location,
-                    helper,
-                    metadata,
-                    value,
-                )?;
-
-                block_relloc.store(context, location, dst_ptr, value)?;
-                block_relloc.append_operation(cf::br(block_finish, &[dst_ptr], location));
-            }
-            None => {
-                block_realloc.append_operation(
-                    ods::llvm::intr_memcpy(
-                        context,
-                        dst_ptr,
-                        src_ptr,
-                        dst_len_bytes,
-                        IntegerAttribute::new(IntegerType::new(context, 1).into(), 0),
-                        location,
-                    )
-                    .into(),
-                );
-                block_realloc.append_operation(cf::br(block_finish, &[dst_ptr], location));
-            }
-        }
+        let k0 = release_block.const_int(context, location, 0, 32)?;
+        let is_unused = release_block.append_op_result(arith::cmpi(
+            context,
+            CmpiPredicate::Eq,
+            refcount,
+            k0,
+            location,
+        ))?;
+        release_block.append_operation(cf::cond_br(
+            context,
+            is_unused,
+            free_block,
+            finish_block,
+            &[],
+            &[],
+            location,
+        ));
     }
 
-    let dst_value = block_finish.append_op_result(llvm::undef(array_ty, location))?;
-    let dst_ptr = block_finish.argument(0)?.into();
+    free_block.append_operation(ReallocBindingsMeta::free(context, refcount_ptr, location));
+    free_block.append_operation(cf::br(finish_block, &[], location));
 
-    let k0 = block_finish.const_int(context, location, 0, 32)?;
-    let dst_value = block_finish.insert_value(context, location, dst_value, dst_ptr, 0)?;
-    let dst_value = block_finish.insert_value(context, location, dst_value, k0, 1)?;
-    let dst_value = block_finish.insert_value(context, location, dst_value, array_len, 2)?;
-    let dst_value = block_finish.insert_value(context, location, dst_value, array_len, 3)?;
+    Ok(finish_block)
+}
+
+/// Allocate an array buffer from the host with room for `size` bytes of elements. Returns the
+/// pointer to the elements.
+pub(crate) unsafe fn alloc_data(size: usize) -> *mut () {
+    let header_ptr = libc::malloc(HEADER_SIZE + size);
+    assert!(!header_ptr.is_null(), "out of memory");
+
+    header_ptr.cast::<u32>().write(1);
+    header_ptr.byte_add(HEADER_SIZE).cast()
+}
 
-    Ok((block_finish, dst_value))
+/// Release a reference to an array buffer from the host, freeing it if it was the last one.
+pub(crate) unsafe fn release_data(ptr: *mut ()) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let refcount_ptr = ptr.byte_sub(HEADER_SIZE).cast::<u32>();
+    *refcount_ptr -= 1;
+    if *refcount_ptr == 0 {
+        crate::executor::free_allocation(refcount_ptr.cast());
+    }
 }
@@ -1,20 +1,33 @@
 //! # `BoundedInt` type
 //!
 //! A `BoundedInt` is a int with a lower and high bound.
+//!
+//! It's represented as a plain MLIR integer with the smallest width able to hold every value
+//! within its bounds. Ranges with a negative lower bound use a signed (two's complement)
+//! representation, the rest are unsigned.
+//!
+//! ## Libfuncs
+//!
+//! Only the casts (`upcast` and `downcast`) operate on bounded ints for now. The rest of the
+//! `bounded_int_*` family (ex. `bounded_int_add` or `bounded_int_div_rem`) was introduced in newer
+//! versions of Sierra than the one supported by this crate (cairo-lang 2.6), whose program
+//! registry rejects them, so they'll be implemented along with the compiler upgrade.
 
 use crate::{error::Result, metadata::MetadataStorage};
 use cairo_lang_sierra::{
     extensions::{
         bounded_int::BoundedIntConcreteType,
         core::{CoreLibfunc, CoreType},
-        types::InfoOnlyConcreteType,
+        utils::Range,
     },
     program_registry::ProgramRegistry,
 };
 use melior::{
-    ir::{Module, Type},
+    ir::{r#type::IntegerType, Module, Type},
     Context,
 };
+use num_bigint::BigInt;
+use num_traits::{One, Signed};
 
 use super::WithSelf;
 
@@ -23,19 +36,48 @@ use super::WithSelf;
 /// Check out [the module](self) for more info.
 pub fn build<'ctx>(
     context: &'ctx Context,
-    module: &Module<'ctx>,
-    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-    metadata: &mut MetadataStorage,
+    _module: &Module<'ctx>,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    _metadata: &mut MetadataStorage,
     info: WithSelf<BoundedIntConcreteType>,
 ) -> Result<Type<'ctx>> {
-    // todo: possible optimization, we may be able to use less bits depending on the possible values within the range.
+    Ok(IntegerType::new(context, width(&info.range)).into())
+}
+
+/// Return the number of bits required to represent every value within the range.
+pub fn width(range: &Range) -> u32 {
+    // Sierra ranges are half-open.
+    let max = &range.upper - BigInt::one();
 
-    let info = WithSelf {
-        self_ty: info.self_ty,
-        inner: &InfoOnlyConcreteType {
-            info: info.info.clone(),
-        },
+    let bits = if range.lower.is_negative() {
+        let min_bits = (-&range.lower - BigInt::one()).bits();
+        let max_bits = if max.is_negative() { 0 } else { max.bits() };
+
+        min_bits.max(max_bits) + 1
+    } else {
+        max.bits()
     };
 
-    super::felt252::build(context, module, registry, metadata, info)
+    // The bounds are always within the felt252 range, so it'll always fit.
+    bits.max(1).try_into().expect("should always fit u32")
+}
+
+#[cfg(test)]
+mod test {
+    use super::width;
+    use cairo_lang_sierra::extensions::utils::Range;
+
+    #[test]
+    fn bounded_int_width() {
+        assert_eq!(width(&Range::closed(0, 0)), 1);
+        assert_eq!(width(&Range::closed(0, 1)), 1);
+        assert_eq!(width(&Range::closed(0, 255)), 8);
+        assert_eq!(width(&Range::closed(0, 256)), 9);
+        assert_eq!(width(&Range::closed(-1, 0)), 1);
+        assert_eq!(width(&Range::closed(-128, 127)), 8);
+        assert_eq!(width(&Range::closed(-129, 127)), 9);
+        assert_eq!(width(&Range::closed(-128, 128)), 9);
+        assert_eq!(width(&Range::closed(-10, -5)), 5);
+        assert_eq!(width(&Range::closed(0, u128::MAX)), 128);
+    }
 }
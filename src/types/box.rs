@@ -4,13 +4,16 @@
 //!
 //! ## Layout
 //!
-//! Its layout is that of whatever it wraps. In other words, if it was Rust it would be equivalent
-//! to the following:
+//! A box is a non-null pointer to a heap allocation (made through the
+//! [realloc bindings](crate::metadata::realloc_bindings)) which holds the value. In other words,
+//! if it was Rust it would be equivalent to the following:
 //!
 //! ```
-//! #[repr(transparent)]
-//! pub struct Box<T>(pub T);
+//! pub struct Box<T>(pub std::ptr::NonNull<T>);
 //! ```
+//!
+//! The allocation is owned by the box: `unbox` moves the value out of it before freeing it, and
+//! dropping the box drops the value (if it needs to) before freeing it.
 
 use super::WithSelf;
 use crate::{
@@ -18,7 +21,8 @@ use crate::{
     error::Result,
     libfuncs::LibfuncHelper,
     metadata::{
-        realloc_bindings::ReallocBindingsMeta, snapshot_clones::SnapshotClonesMeta, MetadataStorage,
+        drop_overrides::DropOverridesMeta, realloc_bindings::ReallocBindingsMeta,
+        snapshot_clones::SnapshotClonesMeta, MetadataStorage,
     },
     types::TypeBuilder,
 };
@@ -48,6 +52,17 @@ pub fn build<'ctx>(
     metadata: &mut MetadataStorage,
     info: WithSelf<InfoAndTypeConcreteType>,
 ) -> Result<Type<'ctx>> {
+    metadata
+        .get_or_insert_with::<DropOverridesMeta>(DropOverridesMeta::default)
+        .register(
+            info.self_ty().clone(),
+            build_drop,
+            InfoAndTypeConcreteType {
+                info: info.info.clone(),
+                ty: info.ty.clone(),
+            },
+        );
+
     metadata
         .get_or_insert_with::<SnapshotClonesMeta>(SnapshotClonesMeta::default)
         .register(
@@ -62,6 +77,51 @@ pub fn build<'ctx>(
     Ok(llvm::r#type::pointer(context, 0))
 }
 
+/// Drop the boxed value, then free the heap allocation of the box.
+#[allow(clippy::too_many_arguments)]
+fn build_drop<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    info: WithSelf<InfoAndTypeConcreteType>,
+    value: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    if metadata.get::<ReallocBindingsMeta>().is_none() {
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
+    }
+
+    // Building the inner type registers its drop implementation, if it has one.
+    let inner_ty = registry
+        .get_type(&info.ty)?
+        .build(context, helper, registry, metadata, &info.ty)?;
+    let inner_drop = metadata
+        .get::<DropOverridesMeta>()
+        .and_then(|meta| meta.wrap_invoke(&info.ty));
+
+    let entry = match inner_drop {
+        Some(inner_drop) => {
+            let inner_value = entry.load(context, location, value, inner_ty)?;
+            inner_drop(
+                context,
+                registry,
+                entry,
+                location,
+                helper,
+                metadata,
+                inner_value,
+            )?
+        }
+        None => entry,
+    };
+
+    entry.append_operation(ReallocBindingsMeta::free(context, value, location));
+
+    Ok(entry)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn snapshot_take<'ctx, 'this>(
     context: &'ctx Context,
@@ -74,7 +134,7 @@ fn snapshot_take<'ctx, 'this>(
     src_value: Value<'ctx, 'this>,
 ) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let inner_snapshot_take = metadata
@@ -93,7 +153,7 @@ fn snapshot_take<'ctx, 'this>(
         context, ptr, value_len, location,
     ))?;
 
-    match inner_snapshot_take {
+    let entry = match inner_snapshot_take {
         Some(inner_snapshot_take) => {
             let value = entry.load(context, location, src_value, inner_ty)?;
 
@@ -101,6 +161,7 @@ fn snapshot_take<'ctx, 'this>(
                 inner_snapshot_take(context, registry, entry, location, helper, metadata, value)?;
 
             entry.store(context, location, dst_ptr, value)?;
+            entry
         }
         None => {
             entry.append_operation(
@@ -114,8 +175,9 @@ fn snapshot_take<'ctx, 'this>(
                 )
                 .into(),
             );
+            entry
         }
-    }
+    };
 
     Ok((entry, dst_ptr))
 }
@@ -1,4 +1,7 @@
 //! # Coupon type.
+//!
+//! Coupons are zero-sized builtins: they only exist for the gas accounting, which is resolved at
+//! compile time. Check out the [coupon libfuncs](crate::libfuncs::coupon) for more info.
 
 use super::WithSelf;
 use crate::{error::Result, metadata::MetadataStorage};
@@ -4,6 +4,16 @@
 //! the same time. They are similar to Rust enums since they can contain data along with the
 //! discriminator.
 //!
+//! ## Optimized layouts
+//!
+//! Two kinds of enums skip the general representation described below:
+//!
+//!   - Enums with a single variant are represented as their payload, since there's nothing to
+//!     discriminate.
+//!   - Enums whose variants are all zero-sized (C-style enums, like `bool`) only need their
+//!     discriminant. They're not memory-allocated, so they're passed around in registers, and the
+//!     payload is a zero-sized array. For example, `bool` is an `i1`.
+//!
 //! ## Layout
 //!
 //! | Index | Type                 | Description              |
@@ -404,7 +414,7 @@
 use super::{TypeBuilder, WithSelf};
 use crate::{
     error::Result,
-    metadata::MetadataStorage,
+    metadata::{overflow_checks::OverflowChecksMeta, MetadataStorage},
     utils::{get_integer_layout, ProgramRegistryExt},
 };
 use cairo_lang_sierra::{
@@ -436,20 +446,7 @@ pub fn build<'ctx>(
     info: WithSelf<EnumConcreteType>,
 ) -> Result<Type<'ctx>> {
     let tag_bits = info.variants.len().next_power_of_two().trailing_zeros();
-
-    let tag_layout = get_integer_layout(tag_bits);
-    let layout = info.variants.iter().fold(tag_layout, |acc, id| {
-        let layout = tag_layout
-            .extend(registry.get_type(id).unwrap().layout(registry).unwrap())
-            .unwrap()
-            .0;
-
-        Layout::from_size_align(
-            acc.size().max(layout.size()),
-            acc.align().max(layout.align()),
-        )
-        .unwrap()
-    });
+    let (layout, ..) = get_layout_for_variants(registry, &info.variants)?;
 
     let i8_ty = IntegerType::new(context, 8).into();
     Ok(match info.variants.len() {
@@ -472,8 +469,19 @@ pub fn build<'ctx>(
         _ => llvm::r#type::r#struct(
             context,
             &[
-                IntegerType::new(context, (8 * layout.align()) as u32).into(),
-                llvm::r#type::array(i8_ty, (layout.size() - layout.align()) as u32),
+                IntegerType::new(
+                    context,
+                    OverflowChecksMeta::narrow(metadata, 8 * layout.align(), "an enum tag width")?,
+                )
+                .into(),
+                llvm::r#type::array(
+                    i8_ty,
+                    OverflowChecksMeta::narrow(
+                        metadata,
+                        layout.size() - layout.align(),
+                        "an enum payload size",
+                    )?,
+                ),
             ],
             false,
         ),
@@ -541,10 +549,17 @@ pub fn get_type_for_variants<'ctx>(
 
 #[cfg(test)]
 mod test {
-    use crate::{metadata::MetadataStorage, types::TypeBuilder, utils::test::load_cairo};
+    use crate::{
+        context::NativeContext,
+        error::Error,
+        metadata::{overflow_checks::OverflowChecksMeta, MetadataStorage},
+        types::TypeBuilder,
+        utils::test::load_cairo,
+    };
     use cairo_lang_sierra::{
         extensions::core::{CoreLibfunc, CoreType},
         program_registry::ProgramRegistry,
+        ProgramParser,
     };
     use melior::{
         ir::{r#type::IntegerType, Location, Module},
@@ -580,4 +595,46 @@ mod test {
             })
             .any(|width| width == i0_ty);
     }
+
+    #[test]
+    fn enum_type_overflow_checks() {
+        // Every struct nests 16 copies of the previous one, so `S6` takes 8 GiB and the payload of
+        // the enum doesn't fit in the `u32` length of an LLVM array.
+        let mut source = format!(
+            "type felt252 = felt252;\ntype S0 = Struct<ut@S0{}>;\n",
+            ", felt252".repeat(16)
+        );
+        for i in 1..=6 {
+            let fields = format!(", S{}", i - 1).repeat(16);
+            source.push_str(&format!("type S{i} = Struct<ut@S{i}{fields}>;\n"));
+        }
+        source.push_str("type E = Enum<ut@E, S6, felt252>;");
+
+        let program = ProgramParser::new().parse(&source).unwrap();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let enum_id = &program.type_declarations.last().unwrap().id;
+
+        let native_context = NativeContext::new();
+        let context = native_context.context();
+        let module = Module::new(Location::unknown(context));
+
+        for overflow_checks in [false, true] {
+            let mut metadata = MetadataStorage::new();
+            if overflow_checks {
+                metadata.insert(OverflowChecksMeta);
+            }
+
+            let result = registry.get_type(enum_id).unwrap().build(
+                context,
+                &module,
+                &registry,
+                &mut metadata,
+                enum_id,
+            );
+            match overflow_checks {
+                false => assert!(result.is_ok()),
+                true => assert!(matches!(result, Err(Error::IntegerOverflow(_)))),
+            }
+        }
+    }
 }
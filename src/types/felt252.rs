@@ -3,6 +3,8 @@
 //! A `felt252` is a 252-bit number within a
 //! [finite field](https://en.wikipedia.org/wiki/Finite_field) modulo
 //! [a prime number](struct@self::PRIME).
+//!
+//! Host-side helpers to work with felts are in the [felt](crate::felt) module.
 
 use super::WithSelf;
 use crate::{
@@ -16,25 +18,13 @@ use cairo_lang_sierra::{
     },
     program_registry::ProgramRegistry,
 };
-use lazy_static::lazy_static;
 use melior::{
     ir::{r#type::IntegerType, Module, Type},
     Context,
 };
-use num_bigint::{BigInt, BigUint};
 use starknet_types_core::felt::Felt;
 
-lazy_static! {
-    /// The `felt252` prime modulo.
-    pub static ref PRIME: BigUint =
-        "3618502788666131213697322783095070105623107215331596699973092056135872020481"
-            .parse()
-            .unwrap();
-    pub static ref HALF_PRIME: BigInt =
-        "1809251394333065606848661391547535052811553607665798349986546028067936010240"
-            .parse()
-            .unwrap();
-}
+pub use crate::felt::{HALF_PRIME, PRIME};
 
 /// Build the MLIR type.
 ///
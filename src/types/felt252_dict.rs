@@ -2,12 +2,20 @@
 //!
 //! A key value storage for values whose type implement Copy. The key is always a felt.
 //!
-//! This type is represented as a pointer to a tuple of a heap allocated Rust hashmap along with a u64
-//! used to count accesses to the dictionary. The type is interacted through the runtime functions to
-//! insert, get elements and increment the access counter.
+//! This type is represented as a pointer to a heap allocated
+//! [FeltDict](cairo_native_runtime::FeltDict), an open-addressing hash map which also counts the
+//! accesses to the dictionary. The type is interacted through the runtime functions to insert, get
+//! elements and increment the access counter.
 
 use super::WithSelf;
-use crate::{error::Result, metadata::MetadataStorage};
+use crate::{
+    error::Result,
+    libfuncs::LibfuncHelper,
+    metadata::{
+        drop_overrides::DropOverridesMeta, realloc_bindings::ReallocBindingsMeta,
+        runtime_bindings::RuntimeBindingsMeta, MetadataStorage,
+    },
+};
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
@@ -17,7 +25,7 @@ use cairo_lang_sierra::{
 };
 use melior::{
     dialect::llvm,
-    ir::{Module, Type},
+    ir::{Block, Location, Module, Type, Value},
     Context,
 };
 
@@ -28,12 +36,48 @@ pub fn build<'ctx>(
     context: &'ctx Context,
     _module: &Module<'ctx>,
     _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-    _metadata: &mut MetadataStorage,
-    _info: WithSelf<InfoAndTypeConcreteType>,
+    metadata: &mut MetadataStorage,
+    info: WithSelf<InfoAndTypeConcreteType>,
 ) -> Result<Type<'ctx>> {
+    metadata
+        .get_or_insert_with::<DropOverridesMeta>(DropOverridesMeta::default)
+        .register(
+            info.self_ty().clone(),
+            build_drop,
+            InfoAndTypeConcreteType {
+                info: info.info.clone(),
+                ty: info.ty.clone(),
+            },
+        );
+
     Ok(llvm::r#type::pointer(context, 0))
 }
 
+/// Free the dictionary through the runtime.
+#[allow(clippy::too_many_arguments)]
+fn build_drop<'ctx, 'this>(
+    context: &'ctx Context,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    _info: WithSelf<InfoAndTypeConcreteType>,
+    value: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    if metadata.get::<ReallocBindingsMeta>().is_none() {
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
+    }
+    let free_hook = ReallocBindingsMeta::free_hook(context, entry, location)?;
+
+    let runtime_bindings = metadata
+        .get_mut::<RuntimeBindingsMeta>()
+        .expect("Runtime library not available.");
+    runtime_bindings.dict_alloc_free(context, helper, value, free_hook, entry, location)?;
+
+    Ok(entry)
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
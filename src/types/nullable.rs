@@ -1,6 +1,7 @@
 //! # Nullable type
 //!
-//! Nullable is represented as a pointer, usually the null value will point to a alloca in the stack.
+//! Nullable is represented as a pointer: the null value is a null pointer, and any other value is
+//! the heap pointer of the box it was built from.
 //!
 //! A nullable is functionally equivalent to Rust's `Option<Box<T>>`. Since it's always paired with
 //! `Box<T>` we can reuse its pointer, just leaving it null when there's no value.
@@ -11,7 +12,8 @@ use crate::{
     error::Result,
     libfuncs::LibfuncHelper,
     metadata::{
-        realloc_bindings::ReallocBindingsMeta, snapshot_clones::SnapshotClonesMeta, MetadataStorage,
+        drop_overrides::DropOverridesMeta, realloc_bindings::ReallocBindingsMeta,
+        snapshot_clones::SnapshotClonesMeta, MetadataStorage,
     },
 };
 use cairo_lang_sierra::{
@@ -43,6 +45,17 @@ pub fn build<'ctx>(
     metadata: &mut MetadataStorage,
     info: WithSelf<InfoAndTypeConcreteType>,
 ) -> Result<Type<'ctx>> {
+    metadata
+        .get_or_insert_with::<DropOverridesMeta>(DropOverridesMeta::default)
+        .register(
+            info.self_ty().clone(),
+            build_drop,
+            InfoAndTypeConcreteType {
+                info: info.info.clone(),
+                ty: info.ty.clone(),
+            },
+        );
+
     metadata
         .get_or_insert_with::<SnapshotClonesMeta>(SnapshotClonesMeta::default)
         .register(
@@ -58,6 +71,29 @@ pub fn build<'ctx>(
     Ok(llvm::r#type::pointer(context, 0))
 }
 
+/// Free the heap allocation of the nullable.
+///
+/// Freeing a null pointer is a no-op, so there's no need to check for it.
+#[allow(clippy::too_many_arguments)]
+fn build_drop<'ctx, 'this>(
+    context: &'ctx Context,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    _info: WithSelf<InfoAndTypeConcreteType>,
+    value: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    if metadata.get::<ReallocBindingsMeta>().is_none() {
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
+    }
+
+    entry.append_operation(ReallocBindingsMeta::free(context, value, location));
+
+    Ok(entry)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn snapshot_take<'ctx, 'this>(
     context: &'ctx Context,
@@ -70,7 +106,7 @@ fn snapshot_take<'ctx, 'this>(
     src_value: Value<'ctx, 'this>,
 ) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
     if metadata.get::<ReallocBindingsMeta>().is_none() {
-        metadata.insert(ReallocBindingsMeta::new(context, helper));
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
     }
 
     let elem_layout = registry.get_type(&info.ty)?.layout(registry)?;
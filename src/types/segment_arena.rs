@@ -1,4 +1,12 @@
 //! # Segment arena type
+//!
+//! The segment arena builtin is used in the VM to allocate the segments of the dictionaries. Since
+//! the dictionaries are allocated by the runtime instead, this type is just a counter of the
+//! dictionary operations (creation and squashing) performed so far, which is reported in the
+//! [builtin stats](crate::execution_result::BuiltinStats) of the execution.
+//!
+//! Like the rest of the builtins, it's threaded through every function which uses dictionaries as
+//! an implicit argument and return value.
 
 use super::WithSelf;
 use crate::{error::Result, metadata::MetadataStorage};
@@ -11,22 +11,32 @@
 //! #[repr(transparent)]
 //! pub struct Snapshot<T>(pub T);
 //! ```
+//!
+//! ## Ownership
+//!
+//! A snapshot owns a copy of its value made with the clone implementation of `T`, if there's any.
+//! For example, a snapshot of an array holds a reference to the array's buffer, which is released
+//! when the snapshot is dropped.
 
 use super::{TypeBuilder, WithSelf};
 use crate::{
     error::Result,
-    metadata::{enum_snapshot_variants::EnumSnapshotVariantsMeta, MetadataStorage},
+    libfuncs::LibfuncHelper,
+    metadata::{
+        drop_overrides::DropOverridesMeta, enum_snapshot_variants::EnumSnapshotVariantsMeta,
+        snapshot_clones::SnapshotClonesMeta, MetadataStorage,
+    },
     utils::ProgramRegistryExt,
 };
 use cairo_lang_sierra::{
     extensions::{
-        core::{CoreLibfunc, CoreType},
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
         types::InfoAndTypeConcreteType,
     },
     program_registry::ProgramRegistry,
 };
 use melior::{
-    ir::{Module, Type},
+    ir::{Block, Location, Module, Type, Value},
     Context,
 };
 
@@ -51,5 +61,83 @@ pub fn build<'ctx>(
     }
     .set_mapping(info.self_ty, registry.get_type(&info.ty)?.variants());
 
-    registry.build_type(context, module, registry, metadata, &info.ty)
+    // Building the inner type registers its clone and drop implementations, if it has any.
+    let inner_ty = registry.build_type(context, module, registry, metadata, &info.ty)?;
+
+    let has_clone = metadata
+        .get::<SnapshotClonesMeta>()
+        .is_some_and(|meta| meta.wrap_invoke(&info.ty).is_some());
+    if has_clone {
+        metadata
+            .get_or_insert_with::<SnapshotClonesMeta>(SnapshotClonesMeta::default)
+            .register(
+                info.self_ty().clone(),
+                snapshot_take,
+                InfoAndTypeConcreteType {
+                    info: info.info.clone(),
+                    ty: info.ty.clone(),
+                },
+            );
+
+        // Only arrays are released, since the clone implementations of other types may share
+        // memory with the original value (ex. a box of a dictionary).
+        if let CoreTypeConcrete::Array(_) = registry.get_type(&info.ty)? {
+            metadata
+                .get_or_insert_with::<DropOverridesMeta>(DropOverridesMeta::default)
+                .register(
+                    info.self_ty().clone(),
+                    build_drop,
+                    InfoAndTypeConcreteType {
+                        info: info.info.clone(),
+                        ty: info.ty.clone(),
+                    },
+                );
+        }
+    }
+
+    Ok(inner_ty)
+}
+
+/// Release the snapshot's reference by dropping it as the inner type.
+#[allow(clippy::too_many_arguments)]
+fn build_drop<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    info: WithSelf<InfoAndTypeConcreteType>,
+    value: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    match metadata
+        .get::<DropOverridesMeta>()
+        .and_then(|meta| meta.wrap_invoke(&info.ty))
+    {
+        Some(inner_drop) => inner_drop(context, registry, entry, location, helper, metadata, value),
+        None => Ok(entry),
+    }
+}
+
+/// Copy the snapshot by cloning it as the inner type.
+#[allow(clippy::too_many_arguments)]
+fn snapshot_take<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    info: WithSelf<InfoAndTypeConcreteType>,
+    src_value: Value<'ctx, 'this>,
+) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
+    match metadata
+        .get::<SnapshotClonesMeta>()
+        .and_then(|meta| meta.wrap_invoke(&info.ty))
+    {
+        Some(inner_clone) => inner_clone(
+            context, registry, entry, location, helper, metadata, src_value,
+        ),
+        None => Ok((entry, src_value)),
+    }
 }
@@ -1,7 +1,18 @@
 //! # Squashed `Felt` dictionary type
+//!
+//! Squashing doesn't modify the dictionary, so the squashed dictionary is the same pointer to the
+//! runtime's map as the [dictionary](super::felt252_dict) it comes from. It's only kept around to
+//! be read (ex. when returned by the program) and released once dropped.
 
 use super::WithSelf;
-use crate::{error::Result, metadata::MetadataStorage};
+use crate::{
+    error::Result,
+    libfuncs::LibfuncHelper,
+    metadata::{
+        drop_overrides::DropOverridesMeta, realloc_bindings::ReallocBindingsMeta,
+        runtime_bindings::RuntimeBindingsMeta, MetadataStorage,
+    },
+};
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
@@ -11,7 +22,7 @@ use cairo_lang_sierra::{
 };
 use melior::{
     dialect::llvm,
-    ir::{Module, Type},
+    ir::{Block, Location, Module, Type, Value},
     Context,
 };
 
@@ -22,8 +33,44 @@ pub fn build<'ctx>(
     context: &'ctx Context,
     _module: &Module<'ctx>,
     _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-    _metadata: &mut MetadataStorage,
-    _info: WithSelf<InfoAndTypeConcreteType>,
+    metadata: &mut MetadataStorage,
+    info: WithSelf<InfoAndTypeConcreteType>,
 ) -> Result<Type<'ctx>> {
+    metadata
+        .get_or_insert_with::<DropOverridesMeta>(DropOverridesMeta::default)
+        .register(
+            info.self_ty().clone(),
+            build_drop,
+            InfoAndTypeConcreteType {
+                info: info.info.clone(),
+                ty: info.ty.clone(),
+            },
+        );
+
     Ok(llvm::r#type::pointer(context, 0))
 }
+
+/// Free the squashed dictionary through the runtime.
+#[allow(clippy::too_many_arguments)]
+fn build_drop<'ctx, 'this>(
+    context: &'ctx Context,
+    _registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    _info: WithSelf<InfoAndTypeConcreteType>,
+    value: Value<'ctx, 'this>,
+) -> Result<&'this Block<'ctx>> {
+    if metadata.get::<ReallocBindingsMeta>().is_none() {
+        metadata.insert(ReallocBindingsMeta::new(context, helper)?);
+    }
+    let free_hook = ReallocBindingsMeta::free_hook(context, entry, location)?;
+
+    let runtime_bindings = metadata
+        .get_mut::<RuntimeBindingsMeta>()
+        .expect("Runtime library not available.");
+    runtime_bindings.dict_alloc_free(context, helper, value, free_hook, entry, location)?;
+
+    Ok(entry)
+}
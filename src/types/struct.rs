@@ -31,9 +31,18 @@
 //! effects apply. For example, if we invert the order of the fields the ABI will change but we
 //! won't waste a single byte in padding; unless we're creating an array, in which case we'd waste
 //! only a single byte per element.
+//!
+//! Structs with members that need to be cloned (ex. arrays) are cloned member by member, so that a
+//! snapshot of the struct holds its own copy of each of them.
 
 use super::WithSelf;
-use crate::{error::Result, metadata::MetadataStorage, utils::ProgramRegistryExt};
+use crate::{
+    block_ext::BlockExt,
+    error::Result,
+    libfuncs::LibfuncHelper,
+    metadata::{snapshot_clones::SnapshotClonesMeta, MetadataStorage},
+    utils::ProgramRegistryExt,
+};
 use cairo_lang_sierra::{
     extensions::{
         core::{CoreLibfunc, CoreType},
@@ -43,7 +52,7 @@ use cairo_lang_sierra::{
 };
 use melior::{
     dialect::llvm,
-    ir::{Module, Type},
+    ir::{Block, Location, Module, Type, Value},
     Context,
 };
 
@@ -64,5 +73,65 @@ pub fn build<'ctx>(
         .collect::<Result<_>>()?;
     let struct_ty = llvm::r#type::r#struct(context, &fields, false);
 
+    // Building the members registers their clone implementations, if they have any.
+    let has_clones = metadata.get::<SnapshotClonesMeta>().is_some_and(|meta| {
+        info.members
+            .iter()
+            .any(|member| meta.wrap_invoke(member).is_some())
+    });
+    if has_clones {
+        metadata
+            .get_or_insert_with::<SnapshotClonesMeta>(SnapshotClonesMeta::default)
+            .register(
+                info.self_ty().clone(),
+                snapshot_take,
+                StructConcreteType {
+                    info: info.info.clone(),
+                    members: info.members.clone(),
+                },
+            );
+    }
+
     Ok(struct_ty)
 }
+
+/// Clone the members which have a clone implementation and copy the rest.
+#[allow(clippy::too_many_arguments)]
+fn snapshot_take<'ctx, 'this>(
+    context: &'ctx Context,
+    registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    entry: &'this Block<'ctx>,
+    location: Location<'ctx>,
+    helper: &LibfuncHelper<'ctx, 'this>,
+    metadata: &mut MetadataStorage,
+    info: WithSelf<StructConcreteType>,
+    src_value: Value<'ctx, 'this>,
+) -> Result<(&'this Block<'ctx>, Value<'ctx, 'this>)> {
+    let mut block = entry;
+    let mut value = src_value;
+    for (idx, member) in info.members.iter().enumerate() {
+        let Some(member_clone) = metadata
+            .get::<SnapshotClonesMeta>()
+            .and_then(|meta| meta.wrap_invoke(member))
+        else {
+            continue;
+        };
+
+        let member_ty = registry.build_type(context, helper, registry, metadata, member)?;
+        let member_value = block.extract_value(context, location, value, member_ty, idx)?;
+        let (next_block, member_value) = member_clone(
+            context,
+            registry,
+            block,
+            location,
+            helper,
+            metadata,
+            member_value,
+        )?;
+
+        block = next_block;
+        value = block.insert_value(context, location, value, member_value, idx)?;
+    }
+
+    Ok((block, value))
+}
@@ -3,7 +3,7 @@
 use crate::{
     debug_info::{DebugInfo, DebugLocations},
     metadata::MetadataStorage,
-    types::{felt252::PRIME, TypeBuilder},
+    types::TypeBuilder,
     OptLevel,
 };
 use cairo_lang_compiler::{
@@ -13,20 +13,22 @@ use cairo_lang_compiler::{
 use cairo_lang_sierra::{
     extensions::core::{CoreLibfunc, CoreType},
     ids::{ConcreteTypeId, FunctionId},
-    program::{GenFunction, Program, StatementIdx},
+    program::{GenFunction, Program, StatementIdx, VersionedProgram},
     program_registry::ProgramRegistry,
+    ProgramParser,
 };
+use cairo_lang_starknet_classes::contract_class::ContractClass;
 use melior::{
     ir::{Module, Type},
-    pass::{self, PassManager},
+    pass::{self, Pass, PassManager},
     Context, Error, ExecutionEngine,
 };
-use num_bigint::{BigInt, BigUint, Sign};
+use num_bigint::{BigInt, BigUint};
 use std::{
     alloc::Layout,
     borrow::Cow,
+    ffi::OsStr,
     fmt::{self, Display},
-    ops::Neg,
     path::Path,
     ptr::NonNull,
     sync::Arc,
@@ -55,7 +57,9 @@ pub fn generate_function_name(function_id: &FunctionId) -> Cow<str> {
 /// Return the layout for an integer of arbitrary width.
 ///
 /// This assumes the platform's maximum (effective) alignment is 16 bytes, and that every integer
-/// with a size in bytes of a power of two has the same alignment as its size.
+/// with a size in bytes of a power of two has the same alignment as its size. Both hold for the
+/// data layouts of x86-64 and AArch64, so the layouts don't depend on which of them is the host
+/// when cross-compiling.
 pub fn get_integer_layout(width: u32) -> Layout {
     if width == 0 {
         Layout::new::<()>()
@@ -68,14 +72,7 @@ pub fn get_integer_layout(width: u32) -> Layout {
     } else if width <= 64 {
         Layout::new::<u64>()
     } else if width <= 128 {
-        #[cfg(not(target_arch = "x86_64"))]
-        {
-            Layout::new::<u128>()
-        }
-        #[cfg(target_arch = "x86_64")]
-        {
-            Layout::new::<u128>().align_to(16).unwrap()
-        }
+        Layout::from_size_align(16, 16).unwrap()
     } else {
         let width = (width as usize).next_multiple_of(8).next_power_of_two();
         Layout::from_size_align(width >> 3, (width >> 3).min(16)).unwrap()
@@ -111,6 +108,46 @@ pub fn cairo_to_sierra(program: &Path) -> Arc<Program> {
     }
 }
 
+/// Load an already compiled Sierra program, which may be either:
+///   - A textual Sierra program (ex. `.sierra`).
+///   - A JSON program (`.json`), as emitted by `cairo-compile` or as part of a contract class.
+///     Check out [parse_sierra_json] for more info.
+pub fn load_sierra_program(path: &Path) -> Result<Program, crate::error::Error> {
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        crate::error::Error::InvalidProgram(format!("failed to read {}: {e}", path.display()))
+    })?;
+
+    match path.extension().and_then(OsStr::to_str) {
+        Some("json") => parse_sierra_json(&source),
+        _ => ProgramParser::new().parse(&source).map_err(|e| {
+            crate::error::Error::InvalidProgram(e.map_token(|t| t.to_string()).to_string())
+        }),
+    }
+}
+
+/// Parse a JSON Sierra program: either a versioned program (as emitted by `cairo-compile`), whose
+/// debug info is discarded, or a Starknet contract class (ex. `.contract_class.json`), whose
+/// program is extracted.
+pub fn parse_sierra_json(source: &str) -> Result<Program, crate::error::Error> {
+    let value: serde_json::Value = serde_json::from_str(source)
+        .map_err(|e| crate::error::Error::InvalidProgram(e.to_string()))?;
+
+    if value.get("sierra_program").is_some() {
+        let contract: ContractClass = serde_json::from_value(value)
+            .map_err(|e| crate::error::Error::InvalidContractClass(e.to_string()))?;
+        contract
+            .extract_sierra_program()
+            .map_err(|e| crate::error::Error::InvalidContractClass(e.to_string()))
+    } else {
+        let program: VersionedProgram = serde_json::from_value(value)
+            .map_err(|e| crate::error::Error::InvalidProgram(e.to_string()))?;
+        Ok(program
+            .into_v1()
+            .map_err(|e| crate::error::Error::InvalidProgram(e.to_string()))?
+            .program)
+    }
+}
+
 pub fn cairo_to_sierra_with_debug_info<'ctx>(
     context: &'ctx Context,
     program: &Path,
@@ -171,7 +208,17 @@ pub fn find_function_id<'a>(program: &'a Program, function_name: &str) -> &'a Fu
         .funcs
         .iter()
         .find(|x| x.id.debug_name.as_deref() == Some(function_name))
-        .unwrap()
+        .unwrap_or_else(|| {
+            panic!(
+                "function `{function_name}` not found, available functions: {}",
+                program
+                    .funcs
+                    .iter()
+                    .filter_map(|x| x.id.debug_name.as_deref())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
         .id
 }
 
@@ -180,27 +227,13 @@ pub fn felt252_str(value: &str) -> [u32; 8] {
     let value = value
         .parse::<BigInt>()
         .expect("value must be a digit number");
-    let value = match value.sign() {
-        Sign::Minus => &*PRIME - value.neg().to_biguint().unwrap(),
-        _ => value.to_biguint().unwrap(),
-    };
 
-    let mut u32_digits = value.to_u32_digits();
-    u32_digits.resize(8, 0);
-    u32_digits.try_into().unwrap()
+    crate::felt::to_u32_digits(&value)
 }
 
 /// Parse any type that can be a bigint to a felt that can be used in the cairo-native input.
 pub fn felt252_bigint(value: impl Into<BigInt>) -> [u32; 8] {
-    let value: BigInt = value.into();
-    let value = match value.sign() {
-        Sign::Minus => &*PRIME - value.neg().to_biguint().unwrap(),
-        _ => value.to_biguint().unwrap(),
-    };
-
-    let mut u32_digits = value.to_u32_digits();
-    u32_digits.resize(8, 0);
-    u32_digits.try_into().unwrap()
+    crate::felt::to_u32_digits(&value.into())
 }
 
 /// Parse a short string into a felt that can be used in the cairo-native input.
@@ -237,94 +270,140 @@ pub fn create_engine(
     engine
 }
 
-pub fn run_pass_manager(context: &Context, module: &mut Module) -> Result<(), Error> {
-    let pass_manager = PassManager::new(context);
-    pass_manager.enable_verifier(true);
-    pass_manager.add_pass(pass::transform::create_canonicalizer());
-    pass_manager.add_pass(pass::conversion::create_scf_to_control_flow());
-    pass_manager.add_pass(pass::conversion::create_arith_to_llvm());
-    pass_manager.add_pass(pass::conversion::create_control_flow_to_llvm());
-    pass_manager.add_pass(pass::conversion::create_index_to_llvm());
-    pass_manager.add_pass(pass::conversion::create_finalize_mem_ref_to_llvm());
-    pass_manager.add_pass(pass::conversion::create_func_to_llvm());
-    pass_manager.add_pass(pass::conversion::create_reconcile_unrealized_casts());
-    pass_manager.run(module)
+/// Lower the module into the LLVM dialect.
+///
+/// The MLIR-level optimizations depend on the optimization level: none are applied with
+/// [OptLevel::None], to keep the compilation as fast as possible, while [OptLevel::Aggressive]
+/// also eliminates common subexpressions. The LLVM optimizations are applied later on, when the
+/// module is translated into machine code.
+///
+/// With `debug_info`, every function also gets a debug info scope built from its location, which
+/// makes the locations of its operations end up in the DWARF line tables.
+pub fn run_pass_manager(
+    context: &Context,
+    module: &mut Module,
+    opt_level: OptLevel,
+    debug_info: bool,
+) -> Result<(), Error> {
+    let mut passes = Vec::<(&str, fn() -> Pass)>::new();
+    if opt_level != OptLevel::None {
+        passes.push(("canonicalize", pass::transform::create_canonicalizer));
+    }
+    if opt_level == OptLevel::Aggressive {
+        passes.push(("cse", pass::transform::create_cse));
+    }
+
+    passes.extend::<[(&str, fn() -> Pass); 7]>([
+        ("scf-to-cf", pass::conversion::create_scf_to_control_flow),
+        ("arith-to-llvm", pass::conversion::create_arith_to_llvm),
+        ("cf-to-llvm", pass::conversion::create_control_flow_to_llvm),
+        ("index-to-llvm", pass::conversion::create_index_to_llvm),
+        (
+            "finalize-memref-to-llvm",
+            pass::conversion::create_finalize_mem_ref_to_llvm,
+        ),
+        ("func-to-llvm", pass::conversion::create_func_to_llvm),
+        (
+            "reconcile-unrealized-casts",
+            pass::conversion::create_reconcile_unrealized_casts,
+        ),
+    ]);
+    if debug_info {
+        // Creates the compile unit, and a subprogram for every function from its location.
+        passes.push((
+            "ensure-debug-info-scope-on-llvm-func",
+            pass::llvm::create_di_scope_for_llvm_func_op,
+        ));
+    }
+
+    // Every pass is run separately so that each one gets its own span.
+    for (name, create_pass) in passes {
+        let _span = tracing::debug_span!("run_pass", name).entered();
+
+        let pass_manager = PassManager::new(context);
+        pass_manager.enable_verifier(true);
+        pass_manager.add_pass(create_pass());
+        pass_manager.run(module)?;
+    }
+
+    Ok(())
 }
 
 #[cfg(feature = "with-runtime")]
 pub fn register_runtime_symbols(engine: &ExecutionEngine) {
-    unsafe {
-        engine.register_symbol(
+    for (name, ptr) in runtime_symbols() {
+        unsafe {
+            engine.register_symbol(name, ptr);
+        }
+    }
+}
+
+/// Return the symbols of the runtime library linked into the current process.
+#[cfg(feature = "with-runtime")]
+pub(crate) fn runtime_symbols() -> Vec<(&'static str, *mut ())> {
+    #[allow(unused_mut)]
+    let mut symbols = vec![
+        (
             "cairo_native__libfunc__debug__print",
             cairo_native_runtime::cairo_native__libfunc__debug__print
                 as *const fn(i32, *const [u8; 32], usize) -> i32 as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__libfunc__pedersen",
             cairo_native_runtime::cairo_native__libfunc__pedersen
                 as *const fn(*mut u8, *mut u8, *mut u8) -> () as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__libfunc__hades_permutation",
             cairo_native_runtime::cairo_native__libfunc__hades_permutation
                 as *const fn(*mut u8, *mut u8, *mut u8) -> () as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__libfunc__ec__ec_point_from_x_nz",
             cairo_native_runtime::cairo_native__libfunc__ec__ec_point_from_x_nz
                 as *const fn(*mut [[u8; 32]; 2]) -> bool as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__libfunc__ec__ec_state_add",
             cairo_native_runtime::cairo_native__libfunc__ec__ec_state_add
                 as *const fn(*mut [[u8; 32]; 4], *const [[u8; 32]; 2]) -> bool
                 as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__libfunc__ec__ec_state_add_mul",
             cairo_native_runtime::cairo_native__libfunc__ec__ec_state_add_mul
                 as *const fn(*mut [[u8; 32]; 4], *const [u8; 32], *const [[u8; 32]; 2]) -> bool
                 as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__libfunc__ec__ec_state_try_finalize_nz",
             cairo_native_runtime::cairo_native__libfunc__ec__ec_state_try_finalize_nz
                 as *const fn(*const [[u8; 32]; 2], *mut [[u8; 32]; 4]) -> bool
                 as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__libfunc__ec__ec_point_try_new_nz",
             cairo_native_runtime::cairo_native__libfunc__ec__ec_point_try_new_nz
                 as *const fn(*const [[u8; 32]; 2]) -> bool as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__alloc_dict",
             cairo_native_runtime::cairo_native__alloc_dict as *const fn() -> *mut std::ffi::c_void
                 as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__dict_free",
-            cairo_native_runtime::cairo_native__dict_free as *const fn(*mut std::ffi::c_void) -> ()
+            cairo_native_runtime::cairo_native__dict_free
+                as *const fn(*mut std::ffi::c_void, *mut std::ffi::c_void) -> ()
                 as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__dict_get",
             cairo_native_runtime::cairo_native__dict_get
                 as *const fn(*mut std::ffi::c_void, &[u8; 32]) -> *mut std::ffi::c_void
                 as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__dict_insert",
             cairo_native_runtime::cairo_native__dict_insert
                 as *const fn(
@@ -332,23 +411,63 @@ pub fn register_runtime_symbols(engine: &ExecutionEngine) {
                     &[u8; 32],
                     NonNull<std::ffi::c_void>,
                 ) -> *mut std::ffi::c_void as *mut (),
-        );
-
-        engine.register_symbol(
+        ),
+        (
             "cairo_native__dict_gas_refund",
             cairo_native_runtime::cairo_native__dict_gas_refund
                 as *const fn(*const std::ffi::c_void, NonNull<std::ffi::c_void>) -> u64
                 as *mut (),
-        );
+        ),
+    ];
+
+    #[cfg(feature = "with-profiler")]
+    symbols.push((
+        "__profiler__statement",
+        cairo_native_runtime::__profiler__statement as *const fn(u64) -> *mut std::ffi::c_void
+            as *mut (),
+    ));
+
+    #[cfg(feature = "with-cheatcode")]
+    symbols.push((
+        "cairo_native__vtable_cheatcode",
+        crate::starknet::cairo_native__vtable_cheatcode as *mut (),
+    ));
+
+    symbols
+}
+
+/// Install `recorder` into the thread-local slot `key` while running `f`, then return it along with
+/// the output of `f`. The recorder which was installed before, if any, is restored afterwards (even
+/// if `f` panics), so that recordings can be nested.
+#[cfg(any(feature = "with-debug-utils", feature = "with-profiler"))]
+pub(crate) fn with_recorder<R, T>(
+    key: &'static std::thread::LocalKey<std::cell::RefCell<Option<R>>>,
+    recorder: R,
+    f: impl FnOnce() -> T,
+) -> (T, R) {
+    struct Guard<R: 'static> {
+        key: &'static std::thread::LocalKey<std::cell::RefCell<Option<R>>>,
+        previous: Option<R>,
+    }
 
-        #[cfg(feature = "with-cheatcode")]
-        {
-            engine.register_symbol(
-                "cairo_native__vtable_cheatcode",
-                crate::starknet::cairo_native__vtable_cheatcode as *mut (),
-            );
+    impl<R> Drop for Guard<R> {
+        fn drop(&mut self) {
+            self.key.with(|x| x.replace(self.previous.take()));
         }
     }
+
+    let guard = Guard {
+        key,
+        previous: key.with(|x| x.replace(Some(recorder))),
+    };
+
+    let output = f();
+    let recorder = key
+        .with(|x| x.borrow_mut().take())
+        .expect("the recorder should still be installed");
+    drop(guard);
+
+    (output, recorder)
 }
 
 /// Return a type that calls a closure when formatted using [Debug](std::fmt::Debug).
@@ -1011,6 +1130,70 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn test_load_sierra_program_json() {
+        let program = cairo_lang_sierra::ProgramParser::new()
+            .parse(
+                "type felt252 = felt252;
+
+                return([0]);
+
+                run_test@0([0]: felt252) -> (felt252);",
+            )
+            .unwrap();
+        let json = serde_json::to_string(&cairo_lang_sierra::program::VersionedProgram::v1(
+            cairo_lang_sierra::program::ProgramArtifact::stripped(program.clone()),
+        ))
+        .unwrap();
+
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        file.write_all(json.as_bytes()).unwrap();
+        assert_eq!(load_sierra_program(file.path()).unwrap(), program);
+
+        let mut file = tempfile::Builder::new()
+            .suffix(".sierra")
+            .tempfile()
+            .unwrap();
+        file.write_all(program.to_string().as_bytes()).unwrap();
+        assert_eq!(load_sierra_program(file.path()).unwrap(), program);
+
+        assert!(matches!(
+            parse_sierra_json("{}"),
+            Err(crate::error::Error::InvalidProgram(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_pass_manager_opt_levels() {
+        let program = load_cairo! {
+            fn run_test(a: felt252, b: felt252) -> felt252 {
+                (a + b) * (a + b)
+            }
+        };
+        let function_id = find_function_id(&program.1, &format!("{0}::{0}::run_test", program.0));
+
+        for opt_level in [
+            OptLevel::None,
+            OptLevel::Less,
+            OptLevel::Default,
+            OptLevel::Aggressive,
+        ] {
+            let context = NativeContext::new()
+                .with_config(crate::context::CompilerConfig::new().with_opt_level(opt_level));
+            let module = context.compile(&program.1, None).unwrap();
+            let executor = JitNativeExecutor::from_native_module(module, opt_level);
+
+            let result = executor
+                .invoke_dynamic(
+                    function_id,
+                    &[JitValue::Felt252(2.into()), JitValue::Felt252(3.into())],
+                    None,
+                )
+                .unwrap();
+            assert_eq!(result.return_value, JitValue::Felt252(25.into()));
+        }
+    }
+
     #[test]
     fn test_cairo_to_sierra_source() {
         // Define the content of the cairo program as a string.
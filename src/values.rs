@@ -4,7 +4,8 @@
 
 use crate::{
     error::Error,
-    types::{felt252::PRIME, TypeBuilder},
+    felt,
+    types::{bounded_int, TypeBuilder},
     utils::{felt252_bigint, get_integer_layout, layout_repeat, next_multiple_of_usize},
 };
 use bumpalo::Bump;
@@ -18,10 +19,45 @@ use cairo_lang_sierra::{
     program_registry::ProgramRegistry,
 };
 use educe::Educe;
-use num_bigint::{BigInt, Sign, ToBigInt};
-use num_traits::Euclid;
+use num_bigint::{BigInt, Sign};
+use num_traits::Signed;
 use starknet_types_core::felt::Felt;
-use std::{alloc::Layout, collections::HashMap, ops::Neg, ptr::NonNull};
+use std::{alloc::Layout, cell::Cell, collections::HashMap, ptr::NonNull};
+
+/// Limit on the number of elements decoded from each of the outermost arrays of a value (the ones
+/// not nested in other arrays). Check out [JitValue::from_jit].
+#[derive(Debug)]
+pub(crate) struct ArrayLenLimit {
+    max_len: usize,
+    /// The length of the longest array which exceeded the limit, if any.
+    exceeded: Cell<Option<usize>>,
+}
+
+impl ArrayLenLimit {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            exceeded: Cell::new(None),
+        }
+    }
+
+    /// Return the length of the longest array which exceeded the limit, or `None` if every array
+    /// was decoded in full.
+    pub fn exceeded(&self) -> Option<usize> {
+        self.exceeded.get()
+    }
+
+    /// Return how many elements of an array of `len` elements can be decoded.
+    fn apply(&self, len: usize) -> usize {
+        if len > self.max_len {
+            self.exceeded
+                .set(Some(self.exceeded.get().map_or(len, |prev| prev.max(len))));
+            self.max_len
+        } else {
+            len
+        }
+    }
+}
 
 /// A JitValue is a value that can be passed to the JIT engine as an argument or received as a result.
 ///
@@ -151,6 +187,20 @@ impl From<i128> for JitValue {
     }
 }
 
+/// Booleans are enums whose variants (`False` and `True`) have no data.
+impl From<bool> for JitValue {
+    fn from(value: bool) -> Self {
+        Self::Enum {
+            tag: value as usize,
+            value: Box::new(Self::Struct {
+                fields: Vec::new(),
+                debug_name: None,
+            }),
+            debug_name: None,
+        }
+    }
+}
+
 impl<T: Into<JitValue> + Clone> From<&[T]> for JitValue {
     fn from(value: &[T]) -> Self {
         Self::Array(value.iter().map(|x| x.clone().into()).collect())
@@ -202,47 +252,76 @@ impl JitValue {
                     value,
                     range: Range { lower, upper },
                 } => {
-                    let value = value.to_bigint();
+                    let width = match Self::resolve_type(ty, registry) {
+                        CoreTypeConcrete::BoundedInt(info) => bounded_int::width(&info.range),
+                        _ => Err(Error::UnexpectedValue(format!(
+                            "expected value of type {:?} but got a BoundedInt",
+                            type_id.debug_name
+                        )))?,
+                    };
 
-                    if lower < upper {
+                    if lower >= upper {
                         return Err(Error::Error("BoundedInt range is invalid".to_string()));
                     }
 
-                    let prime = &PRIME.to_bigint().unwrap();
-                    let lower = lower.rem_euclid(prime);
-                    let upper = upper.rem_euclid(prime);
+                    // Felts above half the prime represent negative values, unless the range
+                    // doesn't have any.
+                    let value = if lower.is_negative() {
+                        felt::to_signed_bigint(value)
+                    } else {
+                        value.to_bigint()
+                    };
 
-                    if lower <= upper {
-                        if !(lower <= value && value < upper) {
-                            return Err(Error::Error(
-                                "BoundedInt value is out of range".to_string(),
-                            ));
-                        }
-                    } else if !(upper > value && value >= lower) {
+                    if !(lower <= &value && &value < upper) {
                         return Err(Error::Error("BoundedInt value is out of range".to_string()));
                     }
 
-                    let ptr = arena.alloc_layout(get_integer_layout(252)).cast();
-                    let data = felt252_bigint(value);
-                    ptr.cast::<[u32; 8]>().as_mut().copy_from_slice(&data);
-                    ptr
+                    let layout = get_integer_layout(width);
+                    let ptr = arena.alloc_layout(layout).cast::<u8>();
+                    let data = std::slice::from_raw_parts_mut(ptr.as_ptr(), layout.size());
+
+                    // Ranges without negative values don't reserve a sign bit, so their
+                    // maximum may fill the whole layout.
+                    let bytes = if lower.is_negative() {
+                        value.to_signed_bytes_le()
+                    } else {
+                        value.to_bytes_le().1
+                    };
+                    data.fill(if value.is_negative() { 0xFF } else { 0x00 });
+                    let len = bytes.len().min(data.len());
+                    data[..len].copy_from_slice(&bytes[..len]);
+
+                    ptr.cast()
                 }
 
-                Self::Bytes31(_) => todo!(),
+                Self::Bytes31(value) => {
+                    let ptr = arena
+                        .alloc_layout(get_integer_layout(248))
+                        .cast::<[u8; 32]>();
+
+                    let data = ptr.as_ptr().as_mut().unwrap();
+                    data[..31].copy_from_slice(value);
+                    data[31] = 0;
+
+                    ptr.cast()
+                }
                 Self::Array(data) => {
                     if let CoreTypeConcrete::Array(info) = Self::resolve_type(ty, registry) {
                         let elem_ty = registry.get_type(&info.ty)?;
                         let elem_layout = elem_ty.layout(registry)?.pad_to_align();
 
-                        let ptr: *mut () = libc::malloc(elem_layout.size() * data.len()).cast();
-                        let len: u32 = data.len().try_into().unwrap();
+                        let len: u32 = data.len().try_into()?;
+                        let alloc_size = elem_layout.size().checked_mul(data.len()).ok_or(
+                            Error::IntegerOverflow("the array allocation size".to_string()),
+                        )?;
+                        let ptr = crate::types::array::alloc_data(alloc_size);
 
-                        for elem in data {
+                        for (idx, elem) in data.iter().enumerate() {
                             let elem = elem.to_jit(arena, registry, &info.ty)?;
 
                             std::ptr::copy_nonoverlapping(
                                 elem.cast::<u8>().as_ptr(),
-                                ptr.byte_add(len as usize * elem_layout.size()).cast::<u8>(),
+                                ptr.byte_add(idx * elem_layout.size()).cast::<u8>(),
                                 elem_layout.size(),
                             );
                         }
@@ -375,14 +454,17 @@ impl JitValue {
                         )))?
                     }
                 }
+                #[cfg(not(feature = "with-runtime"))]
+                Self::Felt252Dict { .. } => Err(Error::MissingFeature(
+                    "enable the `with-runtime` feature to use felt252 dicts".to_string(),
+                ))?,
+                #[cfg(feature = "with-runtime")]
                 Self::Felt252Dict { value: map, .. } => {
                     if let CoreTypeConcrete::Felt252Dict(info) = Self::resolve_type(ty, registry) {
                         let elem_ty = registry.get_type(&info.ty).unwrap();
                         let elem_layout = elem_ty.layout(registry).unwrap().pad_to_align();
 
-                        let mut value_map = HashMap::<[u8; 32], NonNull<std::ffi::c_void>>::new();
-
-                        // next key must be called before next_value
+                        let mut value_map = cairo_native_runtime::FeltDict::new();
 
                         for (key, value) in map.iter() {
                             let key = key.to_bytes_le();
@@ -510,8 +592,34 @@ impl JitValue {
 
                     ptr
                 }
-                Self::Secp256K1Point { .. } => todo!(),
-                Self::Secp256R1Point { .. } => todo!(),
+                Self::Secp256K1Point { x, y } | Self::Secp256R1Point { x, y } => {
+                    let is_expected_curve = matches!(
+                        (self, Self::resolve_type(ty, registry)),
+                        (
+                            Self::Secp256K1Point { .. },
+                            CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::Secp256Point(
+                                Secp256PointTypeConcrete::K1(_)
+                            ))
+                        ) | (
+                            Self::Secp256R1Point { .. },
+                            CoreTypeConcrete::StarkNet(StarkNetTypeConcrete::Secp256Point(
+                                Secp256PointTypeConcrete::R1(_)
+                            ))
+                        )
+                    );
+                    if !is_expected_curve {
+                        Err(Error::UnexpectedValue(format!(
+                            "expected value of type {:?} but got a secp256 point",
+                            type_id.debug_name
+                        )))?
+                    }
+
+                    // Points are stored as two `u256`, each of them as its low and high parts.
+                    let ptr = arena.alloc_layout(Layout::new::<[[u128; 2]; 2]>()).cast();
+                    *ptr.cast::<[[u128; 2]; 2]>().as_mut() = [[x.0, x.1], [y.0, y.1]];
+
+                    ptr
+                }
                 Self::Null => {
                     unimplemented!("null is meant as return value for nullable for now")
                 }
@@ -520,14 +628,41 @@ impl JitValue {
     }
 
     /// From the given pointer acquired from the JIT outputs, convert it to a [`Self`]
+    ///
+    /// The value is consumed: the memory it owns (ex. the contents of an array) is released. When
+    /// there's a `limit`, only the first elements of each outermost array are returned and the rest
+    /// are just released. The arrays nested in them are decoded in full.
     pub(crate) fn from_jit(
         ptr: NonNull<()>,
         type_id: &ConcreteTypeId,
         registry: &ProgramRegistry<CoreType, CoreLibfunc>,
-    ) -> Self {
+        limit: Option<&ArrayLenLimit>,
+    ) -> Result<Self, Error> {
+        Self::read_jit(ptr, type_id, registry, true, limit)
+    }
+
+    /// From the given pointer to a live value, convert it to a [`Self`] without taking its
+    /// ownership.
+    ///
+    /// The memory owned by the value is left untouched, therefore the program can keep using it.
+    pub(crate) fn peek_jit(
+        ptr: NonNull<()>,
+        type_id: &ConcreteTypeId,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    ) -> Result<Self, Error> {
+        Self::read_jit(ptr, type_id, registry, false, None)
+    }
+
+    fn read_jit(
+        ptr: NonNull<()>,
+        type_id: &ConcreteTypeId,
+        registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+        owned: bool,
+        limit: Option<&ArrayLenLimit>,
+    ) -> Result<Self, Error> {
         let ty = registry.get_type(type_id).unwrap();
 
-        unsafe {
+        Ok(unsafe {
             match ty {
                 CoreTypeConcrete::Array(info) => {
                     let elem_ty = registry.get_type(&info.ty).unwrap();
@@ -556,26 +691,45 @@ impl JitValue {
 
                     assert!(end_offset_value >= start_offset_value);
                     let num_elems = (end_offset_value - start_offset_value) as usize;
-                    let mut array_value = Vec::with_capacity(num_elems);
+                    let num_decoded = limit.map_or(num_elems, |limit| limit.apply(num_elems));
+                    let mut array_value = Vec::with_capacity(num_decoded);
 
                     for i in 0..num_elems {
                         // safe to create a NonNull because if the array has elements, the init_data_ptr can't be null.
                         let cur_elem_ptr =
                             NonNull::new(data_ptr.byte_add(elem_stride * i)).unwrap();
 
-                        array_value.push(Self::from_jit(cur_elem_ptr, &info.ty, registry));
+                        // The limit only applies to the outermost arrays.
+                        if i < num_decoded {
+                            array_value.push(Self::read_jit(
+                                cur_elem_ptr,
+                                &info.ty,
+                                registry,
+                                owned,
+                                None,
+                            )?);
+                        } else if !owned || matches!(elem_ty, CoreTypeConcrete::Felt252(_)) {
+                            // Snapshots don't own their elements and felts don't own any memory,
+                            // so there's nothing left to release.
+                            break;
+                        } else {
+                            // The element may own memory, which has to be released anyway.
+                            Self::read_jit(cur_elem_ptr, &info.ty, registry, owned, None)?;
+                        }
                     }
 
-                    if !init_data_ptr.is_null() {
-                        libc::free(init_data_ptr.cast());
+                    if owned {
+                        crate::types::array::release_data(init_data_ptr);
                     }
 
                     Self::Array(array_value)
                 }
                 CoreTypeConcrete::Box(info) => {
                     let inner = *ptr.cast::<NonNull<()>>().as_ptr();
-                    let value = Self::from_jit(inner, &info.ty, registry);
-                    libc::free(inner.as_ptr().cast());
+                    let value = Self::read_jit(inner, &info.ty, registry, owned, limit)?;
+                    if owned {
+                        crate::executor::free_allocation(inner.as_ptr().cast());
+                    }
                     value
                 }
                 CoreTypeConcrete::EcPoint(_) => {
@@ -609,18 +763,24 @@ impl JitValue {
                 CoreTypeConcrete::Sint32(_) => Self::Sint32(*ptr.cast::<i32>().as_ref()),
                 CoreTypeConcrete::Sint64(_) => Self::Sint64(*ptr.cast::<i64>().as_ref()),
                 CoreTypeConcrete::Sint128(_) => Self::Sint128(*ptr.cast::<i128>().as_ref()),
-                CoreTypeConcrete::NonZero(info) => Self::from_jit(ptr, &info.ty, registry),
+                CoreTypeConcrete::NonZero(info) => {
+                    Self::read_jit(ptr, &info.ty, registry, owned, limit)?
+                }
                 CoreTypeConcrete::Nullable(info) => {
                     let inner_ptr = *ptr.cast::<*mut ()>().as_ptr();
                     if inner_ptr.is_null() {
                         Self::Null
                     } else {
-                        let value = Self::from_jit(
+                        let value = Self::read_jit(
                             NonNull::new_unchecked(inner_ptr).cast(),
                             &info.ty,
                             registry,
-                        );
-                        libc::free(inner_ptr.cast());
+                            owned,
+                            limit,
+                        )?;
+                        if owned {
+                            crate::executor::free_allocation(inner_ptr.cast());
+                        }
                         value
                     }
                 }
@@ -659,8 +819,13 @@ impl JitValue {
                             .byte_add(tag_layout.extend(payload_layout).unwrap().1),
                     )
                     .unwrap();
-                    let payload =
-                        JitValue::from_jit(payload_ptr, &info.variants[tag_value], registry);
+                    let payload = Self::read_jit(
+                        payload_ptr,
+                        &info.variants[tag_value],
+                        registry,
+                        owned,
+                        limit,
+                    )?;
 
                     JitValue::Enum {
                         tag: tag_value,
@@ -682,11 +847,13 @@ impl JitValue {
                         };
                         layout = Some(new_layout);
 
-                        members.push(Self::from_jit(
+                        members.push(Self::read_jit(
                             NonNull::new(ptr.as_ptr().byte_add(offset)).unwrap(),
                             member_ty,
                             registry,
-                        ));
+                            owned,
+                            limit,
+                        )?);
                     }
 
                     JitValue::Struct {
@@ -694,20 +861,39 @@ impl JitValue {
                         debug_name: type_id.debug_name.as_ref().map(|x| x.to_string()),
                     }
                 }
+                #[cfg(not(feature = "with-runtime"))]
+                CoreTypeConcrete::Felt252Dict(_) | CoreTypeConcrete::SquashedFelt252Dict(_) => {
+                    Err(Error::MissingFeature(
+                        "enable the `with-runtime` feature to use felt252 dicts".to_string(),
+                    ))?
+                }
+                #[cfg(feature = "with-runtime")]
                 CoreTypeConcrete::Felt252Dict(info)
                 | CoreTypeConcrete::SquashedFelt252Dict(info) => {
-                    let (map, _) = *Box::from_raw(
-                        ptr.cast::<NonNull<()>>()
-                            .as_ref()
-                            .cast::<(HashMap<[u8; 32], NonNull<std::ffi::c_void>>, u64)>()
-                            .as_ptr(),
-                    );
+                    let dict_ptr = ptr
+                        .cast::<NonNull<()>>()
+                        .as_ref()
+                        .cast::<cairo_native_runtime::FeltDict>();
+                    let map = dict_ptr.as_ref();
 
                     let mut output_map = HashMap::with_capacity(map.len());
 
                     for (key, val_ptr) in map.iter() {
                         let key = Felt::from_bytes_le(key);
-                        output_map.insert(key, Self::from_jit(val_ptr.cast(), &info.ty, registry));
+                        output_map.insert(
+                            key,
+                            Self::read_jit(val_ptr.cast(), &info.ty, registry, owned, limit)?,
+                        );
+
+                        // The values are allocated separately by the generated code, like
+                        // `cairo_native__dict_free` expects.
+                        if owned {
+                            crate::executor::free_allocation(val_ptr.as_ptr().cast());
+                        }
+                    }
+
+                    if owned {
+                        drop(Box::from_raw(dict_ptr.as_ptr()));
                     }
 
                     JitValue::Felt252Dict {
@@ -755,7 +941,10 @@ impl JitValue {
                     }
                 },
                 CoreTypeConcrete::Span(_) => todo!("implement span from_jit"),
-                CoreTypeConcrete::Snapshot(info) => Self::from_jit(ptr, &info.ty, registry),
+                // Snapshots may share the memory they point to, so it's left to the arena.
+                CoreTypeConcrete::Snapshot(info) => {
+                    Self::read_jit(ptr, &info.ty, registry, false, limit)?
+                }
                 CoreTypeConcrete::Bytes31(_) => {
                     let data = *ptr.cast::<[u8; 31]>().as_ref();
                     Self::Bytes31(data)
@@ -763,27 +952,98 @@ impl JitValue {
 
                 CoreTypeConcrete::Const(_) => todo!(),
                 CoreTypeConcrete::BoundedInt(info) => {
-                    let data = ptr.cast::<[u8; 32]>().as_ref();
-                    let data = Felt::from_bytes_le(data);
+                    let width = bounded_int::width(&info.range);
+                    let data = std::slice::from_raw_parts(
+                        ptr.cast::<u8>().as_ptr(),
+                        get_integer_layout(width).size(),
+                    );
+
+                    // The bits above the integer width are unspecified, therefore they have to be
+                    // masked out before sign-extending the value.
+                    let mut value =
+                        BigInt::from_bytes_le(Sign::Plus, data) & ((BigInt::from(1) << width) - 1);
+                    if info.range.lower.is_negative() && value.bit(u64::from(width) - 1) {
+                        value -= BigInt::from(1) << width;
+                    }
+
                     Self::BoundedInt {
-                        value: data,
+                        value: felt::from_bigint(&value),
                         range: info.range.clone(),
                     }
                 }
                 CoreTypeConcrete::Coupon(_) => todo!(),
             }
-        }
+        })
     }
 
     /// String to felt
     pub fn felt_str(value: &str) -> Self {
-        let value = value.parse::<BigInt>().unwrap();
-        let value = match value.sign() {
-            Sign::Minus => &*PRIME - value.neg().to_biguint().unwrap(),
-            _ => value.to_biguint().unwrap(),
+        Self::Felt252(felt::from_bigint(&value.parse::<BigInt>().unwrap()))
+    }
+
+    /// String to `core::byte_array::ByteArray`.
+    ///
+    /// The string is split into 31-byte words, with the remaining bytes stored in the pending word.
+    pub fn byte_array(value: &str) -> Self {
+        let words = value.as_bytes().chunks_exact(31);
+        let pending_word = words.remainder();
+
+        let data = words
+            .map(|word| {
+                // Words are stored big-endian within the felt, but `Bytes31` is little-endian.
+                let mut data = [0; 31];
+                data.copy_from_slice(word);
+                data.reverse();
+
+                Self::Bytes31(data)
+            })
+            .collect();
+
+        Self::Struct {
+            fields: vec![
+                Self::Array(data),
+                Self::Felt252(Felt::from_bytes_be_slice(pending_word)),
+                Self::Uint32(pending_word.len() as u32),
+            ],
+            debug_name: None,
+        }
+    }
+
+    /// `core::byte_array::ByteArray` to string.
+    ///
+    /// Returns `None` if the value doesn't have the layout of a `ByteArray` or if its contents are
+    /// not valid UTF-8.
+    pub fn to_byte_array_string(&self) -> Option<String> {
+        let Self::Struct { fields, .. } = self else {
+            return None;
+        };
+        let [Self::Array(words), Self::Felt252(pending_word), Self::Uint32(pending_word_len)] =
+            fields.as_slice()
+        else {
+            return None;
         };
 
-        Self::Felt252(Felt::from(&value))
+        let pending_word_len = *pending_word_len as usize;
+        if pending_word_len >= 31 {
+            return None;
+        }
+
+        let mut data = Vec::with_capacity(words.len() * 31 + pending_word_len);
+        for word in words {
+            let Self::Bytes31(word) = word else {
+                return None;
+            };
+            data.extend(word.iter().rev());
+        }
+
+        let pending_word = pending_word.to_bytes_be();
+        let (padding, pending_word) = pending_word.split_at(32 - pending_word_len);
+        if padding.iter().any(|x| *x != 0) {
+            return None;
+        }
+        data.extend_from_slice(pending_word);
+
+        String::from_utf8(data).ok()
     }
 }
 
@@ -796,6 +1056,7 @@ mod test {
     use cairo_lang_sierra::program::Program;
     use cairo_lang_sierra::program::TypeDeclaration;
     use cairo_lang_sierra::ProgramParser;
+    use test_case::test_case;
 
     #[test]
     fn test_jit_value_conversion_felt() {
@@ -874,6 +1135,24 @@ mod test {
         assert_eq!(jit_value, JitValue::Sint128(-10));
     }
 
+    #[test]
+    fn test_jit_value_conversion_bool() {
+        for bool_value in [false, true] {
+            let jit_value: JitValue = bool_value.into();
+            assert_eq!(
+                jit_value,
+                JitValue::Enum {
+                    tag: bool_value as usize,
+                    value: Box::new(JitValue::Struct {
+                        fields: vec![],
+                        debug_name: None
+                    }),
+                    debug_name: None
+                }
+            );
+        }
+    }
+
     #[test]
     fn test_jit_value_conversion_array_from_slice() {
         let array_slice: &[u8] = &[1, 2, 3];
@@ -954,6 +1233,174 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_to_jit_bounded_int() {
+        let program = ProgramParser::new()
+            .parse("type BoundedInt = BoundedInt<-5, 10>;")
+            .unwrap();
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let type_id = &program.type_declarations[0].id;
+        let range = Range::closed(-5, 10);
+        let arena = Bump::new();
+
+        // The type is 5 bits wide, so it is stored as a single byte.
+        let ptr = JitValue::BoundedInt {
+            value: Felt::from(-3),
+            range: range.clone(),
+        }
+        .to_jit(&arena, &registry, type_id)
+        .unwrap();
+        assert_eq!(unsafe { *ptr.cast::<i8>().as_ptr() }, -3);
+        assert_eq!(
+            JitValue::from_jit(ptr, type_id, &registry, None).unwrap(),
+            JitValue::BoundedInt {
+                value: Felt::from(-3),
+                range: range.clone(),
+            }
+        );
+
+        assert!(JitValue::BoundedInt {
+            value: Felt::from(11),
+            range,
+        }
+        .to_jit(&arena, &registry, type_id)
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_jit_bounded_int_unsigned() {
+        // The range has no negative values, so felts above half the prime are positive.
+        let program = ProgramParser::new()
+            .parse(&format!(
+                "type BoundedInt = BoundedInt<0, {}>;",
+                Felt::MAX.to_biguint()
+            ))
+            .unwrap();
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let type_id = &program.type_declarations[0].id;
+        let value = JitValue::BoundedInt {
+            value: Felt::MAX,
+            range: Range::closed(0, Felt::MAX.to_bigint()),
+        };
+        let arena = Bump::new();
+
+        let ptr = value.to_jit(&arena, &registry, type_id).unwrap();
+        assert_eq!(
+            JitValue::from_jit(ptr, type_id, &registry, None).unwrap(),
+            value
+        );
+    }
+
+    #[test_case(255, 200; "u8")]
+    #[test_case(u128::MAX, u128::MAX; "u128")]
+    fn test_to_jit_bounded_int_full_width(max: u128, value: u128) {
+        // The maximum fills every bit of the layout, with no room for a sign bit.
+        let program = ProgramParser::new()
+            .parse(&format!("type BoundedInt = BoundedInt<0, {max}>;"))
+            .unwrap();
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let type_id = &program.type_declarations[0].id;
+        let value = JitValue::BoundedInt {
+            value: Felt::from(value),
+            range: Range::closed(0, max),
+        };
+        let arena = Bump::new();
+
+        let ptr = value.to_jit(&arena, &registry, type_id).unwrap();
+        assert_eq!(
+            JitValue::from_jit(ptr, type_id, &registry, None).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_to_jit_secp256_point() {
+        let program = ProgramParser::new()
+            .parse(
+                "type Secp256k1Point = Secp256k1Point;
+                type Secp256r1Point = Secp256r1Point;",
+            )
+            .unwrap();
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let k1_type_id = &program.type_declarations[0].id;
+        let r1_type_id = &program.type_declarations[1].id;
+        let arena = Bump::new();
+
+        let point = JitValue::Secp256K1Point {
+            x: (1, 2),
+            y: (3, u128::MAX),
+        };
+        let ptr = point.to_jit(&arena, &registry, k1_type_id).unwrap();
+        assert_eq!(
+            JitValue::from_jit(ptr, k1_type_id, &registry, None).unwrap(),
+            point
+        );
+
+        let point = JitValue::Secp256R1Point {
+            x: (u128::MAX, 5),
+            y: (6, 7),
+        };
+        let ptr = point.to_jit(&arena, &registry, r1_type_id).unwrap();
+        assert_eq!(
+            JitValue::from_jit(ptr, r1_type_id, &registry, None).unwrap(),
+            point
+        );
+
+        assert!(matches!(
+            point.to_jit(&arena, &registry, k1_type_id),
+            Err(Error::UnexpectedValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_jit_array_len_limit() {
+        let program = ProgramParser::new()
+            .parse(
+                "type felt252 = felt252;
+                type Array_felt252 = Array<felt252>;
+                type Array_Array_felt252 = Array<Array_felt252>;",
+            )
+            .unwrap();
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let felts_type_id = &program.type_declarations[1].id;
+        let arrays_type_id = &program.type_declarations[2].id;
+        let arena = Bump::new();
+
+        let felts = |n: u64| JitValue::Array((0..n).map(|x| JitValue::Felt252(x.into())).collect());
+
+        let limit = ArrayLenLimit::new(2);
+        let ptr = felts(5).to_jit(&arena, &registry, felts_type_id).unwrap();
+        assert_eq!(
+            JitValue::from_jit(ptr, felts_type_id, &registry, Some(&limit)).unwrap(),
+            felts(2)
+        );
+        assert_eq!(limit.exceeded(), Some(5));
+
+        // The arrays past the limit are released, and the ones within it are decoded in full.
+        let limit = ArrayLenLimit::new(2);
+        let ptr = JitValue::Array(vec![felts(1), felts(3), felts(4)])
+            .to_jit(&arena, &registry, arrays_type_id)
+            .unwrap();
+        assert_eq!(
+            JitValue::from_jit(ptr, arrays_type_id, &registry, Some(&limit)).unwrap(),
+            JitValue::Array(vec![felts(1), felts(3)])
+        );
+        assert_eq!(limit.exceeded(), Some(3));
+
+        let limit = ArrayLenLimit::new(2);
+        let ptr = felts(2).to_jit(&arena, &registry, felts_type_id).unwrap();
+        assert_eq!(
+            JitValue::from_jit(ptr, felts_type_id, &registry, Some(&limit)).unwrap(),
+            felts(2)
+        );
+        assert_eq!(limit.exceeded(), None);
+    }
+
     #[test]
     fn test_to_jit_felt252() {
         let program = ProgramParser::new()
@@ -1087,6 +1534,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_to_jit_array() {
+        let program = ProgramParser::new()
+            .parse("type u32 = u32;\ntype Array_u32 = Array<u32>;")
+            .unwrap();
+
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+
+        let data = unsafe {
+            let ptr = JitValue::Array(vec![1u32.into(), 2u32.into(), 3u32.into()])
+                .to_jit(&Bump::new(), &registry, &program.type_declarations[1].id)
+                .unwrap();
+            let data = *ptr.cast::<*const u32>().as_ptr();
+            let values = std::slice::from_raw_parts(data, 3).to_vec();
+            crate::types::array::release_data(data as *mut _);
+            values
+        };
+
+        assert_eq!(data, [1, 2, 3]);
+    }
+
     #[test]
     fn test_to_jit_sint8() {
         let program = ProgramParser::new().parse("type i8 = i8;").unwrap();
@@ -1374,6 +1842,62 @@ mod test {
             _ => panic!("Unexpected error type: {:?}", result),
         }
     }
+
+    #[test]
+    fn test_byte_array() {
+        assert_eq!(
+            JitValue::byte_array("hello"),
+            JitValue::Struct {
+                fields: vec![
+                    JitValue::Array(vec![]),
+                    JitValue::Felt252(Felt::from_bytes_be_slice(b"hello")),
+                    JitValue::Uint32(5),
+                ],
+                debug_name: None,
+            }
+        );
+
+        let mut word = *b"This is a long string with more";
+        word.reverse();
+        assert_eq!(
+            JitValue::byte_array("This is a long string with more than 31 characters."),
+            JitValue::Struct {
+                fields: vec![
+                    JitValue::Array(vec![JitValue::Bytes31(word)]),
+                    JitValue::Felt252(Felt::from_bytes_be_slice(b" than 31 characters.")),
+                    JitValue::Uint32(20),
+                ],
+                debug_name: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_byte_array_roundtrip() {
+        for value in ["", "hello", "ñandú 🦀", &"x".repeat(31), &"y".repeat(100)] {
+            assert_eq!(
+                JitValue::byte_array(value)
+                    .to_byte_array_string()
+                    .as_deref(),
+                Some(value)
+            );
+        }
+
+        // The pending word has more bytes than its length.
+        assert_eq!(
+            JitValue::Struct {
+                fields: vec![
+                    JitValue::Array(vec![]),
+                    JitValue::Felt252(Felt::from_bytes_be_slice(b"hello")),
+                    JitValue::Uint32(4),
+                ],
+                debug_name: None,
+            }
+            .to_byte_array_string(),
+            None
+        );
+        assert_eq!(JitValue::Felt252(Felt::ONE).to_byte_array_string(), None);
+    }
 }
 
 #[cfg(feature = "with-serde")]
@@ -0,0 +1,21 @@
+fn main() -> (
+    u8, u8, u8,
+    u16, u16, u16,
+    u32, u32, u32,
+    u64, u64, u64,
+) {
+    (
+        0xA5_u8 & 0x0F_u8,
+        0xA5_u8 | 0x0F_u8,
+        0xA5_u8 ^ 0xFF_u8,
+        0x1234_u16 & 0xFF00_u16,
+        0x1234_u16 | 0x0F0F_u16,
+        0x1234_u16 ^ 0xFFFF_u16,
+        0x12345678_u32 & 0x0000FFFF_u32,
+        0x12345678_u32 | 0xF0F0F0F0_u32,
+        0x12345678_u32 ^ 0xFFFF0000_u32,
+        0x0123456789ABCDEF_u64 & 0x00FF00FF00FF00FF_u64,
+        0x0123456789ABCDEF_u64 | 0xF000000000000000_u64,
+        0x0123456789ABCDEF_u64 ^ 0xFFFFFFFFFFFFFFFF_u64,
+    )
+}
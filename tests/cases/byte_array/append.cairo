@@ -0,0 +1,17 @@
+use core::byte_array::ByteArrayTrait;
+
+fn main() -> (ByteArray, u32) {
+    let mut data: ByteArray = Default::default();
+    let mut i: u8 = 0;
+    loop {
+        if i == 40 {
+            break;
+        }
+        data.append_byte(97_u8 + i);
+        i += 1;
+    };
+    data.append_word('hello', 5);
+
+    let len = data.len();
+    (data, len)
+}
@@ -0,0 +1,6 @@
+use core::byte_array::ByteArrayTrait;
+
+fn main() -> (Option<u8>, Option<u8>, Option<u8>) {
+    let data: ByteArray = "The quick brown fox jumps over the lazy dog";
+    (data.at(0), data.at(40), data.at(100))
+}
@@ -0,0 +1,5 @@
+fn main() -> ByteArray {
+    let a: ByteArray = "This is a long string with more";
+    let b: ByteArray = " than 31 characters.";
+    a + b
+}
@@ -0,0 +1,3 @@
+fn main() {
+    panic!("A panic message with more than 31 characters.");
+}
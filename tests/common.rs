@@ -6,7 +6,11 @@
 use cairo_lang_compiler::{
     compile_prepared_db, db::RootDatabase, project::setup_project, CompilerConfig,
 };
-use cairo_lang_filesystem::db::init_dev_corelib;
+use cairo_lang_filesystem::{
+    db::{init_dev_corelib, FilesGroupEx},
+    flag::Flag,
+    ids::FlagId,
+};
 use cairo_lang_runner::{
     Arg, RunResultStarknet, RunResultValue, RunnerError, SierraCasmRunner, StarknetState,
 };
@@ -30,11 +34,9 @@ use cairo_native::{
     context::NativeContext,
     execution_result::{ContractExecutionResult, ExecutionResult},
     executor::JitNativeExecutor,
+    felt::{HALF_PRIME, PRIME},
     starknet::{DummySyscallHandler, StarknetSyscallHandler},
-    types::{
-        felt252::{HALF_PRIME, PRIME},
-        TypeBuilder,
-    },
+    types::TypeBuilder,
     utils::find_entry_point_by_idx,
     values::JitValue,
     OptLevel,
@@ -51,10 +53,10 @@ use lambdaworks_math::{
     },
     unsigned_integer::element::UnsignedInteger,
 };
-use num_bigint::{BigInt, Sign};
+use num_bigint::BigInt;
 use proptest::{strategy::Strategy, test_runner::TestCaseError};
 use starknet_types_core::felt::Felt;
-use std::{collections::HashMap, env::var, fs, ops::Neg, path::Path};
+use std::{collections::HashMap, env::var, fs, path::Path, sync::Arc};
 
 #[allow(unused_macros)]
 macro_rules! load_cairo {
@@ -63,37 +65,30 @@ macro_rules! load_cairo {
     };
 }
 
+#[allow(unused_macros)]
+macro_rules! load_cairo_with_redeposit_gas {
+    ( $( $program:tt )+ ) => {
+        $crate::common::load_cairo_str_with_redeposit_gas(stringify!($($program)+))
+    };
+}
+
 use cairo_felt::Felt252;
 #[allow(unused_imports)]
 pub(crate) use load_cairo;
+#[allow(unused_imports)]
+pub(crate) use load_cairo_with_redeposit_gas;
 use num_traits::ToPrimitive;
 
 pub const DEFAULT_GAS: u64 = u64::MAX;
 
 // Parse numeric string into felt, wrapping negatives around the prime modulo.
 pub fn felt(value: &str) -> [u32; 8] {
-    let value = value.parse::<BigInt>().unwrap();
-    let value = match value.sign() {
-        Sign::Minus => &*PRIME - value.neg().to_biguint().unwrap(),
-        _ => value.to_biguint().unwrap(),
-    };
-
-    let mut u32_digits = value.to_u32_digits();
-    u32_digits.resize(8, 0);
-    u32_digits.try_into().unwrap()
+    cairo_native::felt::to_u32_digits(&value.parse::<BigInt>().unwrap())
 }
 
 /// Parse any time that can be a bigint to a felt that can be used in the cairo-native input.
 pub fn feltn(value: impl Into<BigInt>) -> [u32; 8] {
-    let value: BigInt = value.into();
-    let value = match value.sign() {
-        Sign::Minus => &*PRIME - value.neg().to_biguint().unwrap(),
-        _ => value.to_biguint().unwrap(),
-    };
-
-    let mut u32_digits = value.to_u32_digits();
-    u32_digits.resize(8, 0);
-    u32_digits.try_into().unwrap()
+    cairo_native::felt::to_u32_digits(&value.into())
 }
 
 /// Converts a casm variant to sierra.
@@ -110,6 +105,19 @@ pub fn get_run_result(r: &RunResultValue) -> Vec<String> {
 }
 
 pub fn load_cairo_str(program_str: &str) -> (String, Program, SierraCasmRunner) {
+    compile_cairo_str(program_str, false)
+}
+
+/// Like [`load_cairo_str`], but the Sierra generator inserts `redeposit_gas` calls where branches
+/// with different costs merge.
+pub fn load_cairo_str_with_redeposit_gas(program_str: &str) -> (String, Program, SierraCasmRunner) {
+    compile_cairo_str(program_str, true)
+}
+
+fn compile_cairo_str(
+    program_str: &str,
+    add_redeposit_gas: bool,
+) -> (String, Program, SierraCasmRunner) {
     let mut program_file = tempfile::Builder::new()
         .prefix("test_")
         .suffix(".cairo")
@@ -123,6 +131,10 @@ pub fn load_cairo_str(program_str: &str) -> (String, Program, SierraCasmRunner)
         Path::new(&var("CARGO_MANIFEST_DIR").unwrap()).join("corelib/src"),
     );
     let main_crate_ids = setup_project(&mut db, program_file.path()).unwrap();
+    if add_redeposit_gas {
+        let flag_id = FlagId::new(db.upcast(), "add_redeposit_gas");
+        db.set_flag(flag_id, Some(Arc::new(Flag::AddRedepositGas(true))));
+    }
     let program = compile_prepared_db(
         &mut db,
         main_crate_ids.clone(),
@@ -0,0 +1,45 @@
+use crate::common::{load_cairo, run_native_program, DEFAULT_GAS};
+use cairo_lang_runner::SierraCasmRunner;
+use cairo_lang_sierra::program::Program;
+use cairo_native::{starknet::DummySyscallHandler, values::JitValue};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref BYTE_ARRAY_APPEND: (String, Program, SierraCasmRunner) = load_cairo! {
+        fn run_test(lhs: ByteArray, rhs: ByteArray) -> ByteArray {
+            let mut result = lhs;
+            result.append(@rhs);
+            result
+        }
+    };
+}
+
+#[test]
+fn byte_array_argument_roundtrip() {
+    for (lhs, rhs) in [
+        ("", ""),
+        ("hello", " world"),
+        ("This is a long string with more", " than 31 characters."),
+        ("ñandú ", "🦀"),
+    ] {
+        let result = run_native_program(
+            &BYTE_ARRAY_APPEND,
+            "run_test",
+            &[JitValue::byte_array(lhs), JitValue::byte_array(rhs)],
+            Some(DEFAULT_GAS as u128),
+            Option::<DummySyscallHandler>::None,
+        );
+
+        let JitValue::Enum { tag: 0, value, .. } = result.return_value else {
+            panic!("unexpected return value: {:?}", result.return_value);
+        };
+        let JitValue::Struct { fields, .. } = *value else {
+            panic!("unexpected return value: {value:?}");
+        };
+
+        assert_eq!(
+            fields[0].to_byte_array_string(),
+            Some(format!("{lhs}{rhs}"))
+        );
+    }
+}
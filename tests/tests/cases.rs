@@ -99,6 +99,7 @@ use test_case::test_case;
 #[test_case("tests/cases/bool/xor.cairo")]
 // bitwise
 #[test_case("tests/cases/bitwise/and.cairo")]
+#[test_case("tests/cases/bitwise/narrow.cairo")]
 #[test_case("tests/cases/bitwise/or.cairo")]
 #[test_case("tests/cases/bitwise/xor.cairo")]
 // array
@@ -109,6 +110,11 @@ use test_case::test_case;
 #[test_case("tests/cases/array/slice.cairo")]
 // nullable
 #[test_case("tests/cases/nullable/test_nullable.cairo")]
+// byte array
+#[test_case("tests/cases/byte_array/append.cairo")]
+#[test_case("tests/cases/byte_array/at.cairo")]
+#[test_case("tests/cases/byte_array/concat.cairo")]
+#[test_case("tests/cases/byte_array/panic.cairo")]
 // Programs copied from the cairo-vm
 // https://github.com/lambdaclass/cairo-vm/tree/main/cairo_programs/cairo-1-programs
 #[test_case("tests/cases/cairo_vm/programs/array_append.cairo")]
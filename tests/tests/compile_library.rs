@@ -1,9 +1,14 @@
-use crate::common::load_cairo;
+use crate::common::{load_cairo, load_cairo_contract_path};
 use cairo_lang_sierra::{
     extensions::core::{CoreLibfunc, CoreType},
     program_registry::ProgramRegistry,
+    ProgramParser,
+};
+use cairo_native::{
+    context::{CompilationLimits, CompilerConfig, NativeContext, MAX_TYPE_DEPTH},
+    error::Error as NativeError,
+    metadata::{runtime_bindings::RuntimeBindingsMeta, MetadataStorage},
 };
-use cairo_native::metadata::{runtime_bindings::RuntimeBindingsMeta, MetadataStorage};
 use melior::{
     dialect::DialectRegistry,
     ir::{Location, Module},
@@ -71,3 +76,144 @@ pub fn compile_library() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+pub fn compile_limits() {
+    let program = load_cairo! {
+        fn run_test(lhs: felt252, rhs: felt252) -> felt252 {
+            lhs + rhs
+        }
+    };
+
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_limits(CompilationLimits {
+            max_functions: Some(1),
+            ..Default::default()
+        }));
+    assert!(context.compile(&program.1, None).is_ok());
+
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_limits(CompilationLimits {
+            max_mlir_operations: Some(1),
+            ..Default::default()
+        }));
+    assert!(matches!(
+        context.compile(&program.1, None),
+        Err(NativeError::CompilationLimitExceeded(_))
+    ));
+
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_limits(CompilationLimits {
+            max_statements: Some(0),
+            ..Default::default()
+        }));
+    assert!(matches!(
+        context.compile(&program.1, None),
+        Err(NativeError::CompilationLimitExceeded(_))
+    ));
+}
+
+#[test]
+pub fn compile_type_limits() {
+    // A struct nested within itself `depth` times.
+    let nested = |depth| {
+        let mut source = String::from("type T0 = felt252;\n");
+        for i in 1..=depth {
+            source.push_str(&format!("type T{i} = Struct<ut@T{i}, T{}>;\n", i - 1));
+        }
+        ProgramParser::new().parse(&source).unwrap()
+    };
+
+    // The depth limit can't be raised above the maximum.
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_limits(CompilationLimits {
+            max_type_depth: usize::MAX,
+            ..Default::default()
+        }));
+    assert!(matches!(
+        context.compile(&nested(MAX_TYPE_DEPTH + 1), None),
+        Err(NativeError::TypeTooComplex(_))
+    ));
+
+    // This one is deeper than the default limit.
+    let program = nested(200);
+
+    let context = NativeContext::new();
+    assert!(matches!(
+        context.compile(&program, None),
+        Err(NativeError::TypeTooComplex(_))
+    ));
+
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_limits(CompilationLimits {
+            max_type_depth: MAX_TYPE_DEPTH,
+            ..Default::default()
+        }));
+    assert!(context.compile(&program, None).is_ok());
+
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_limits(CompilationLimits {
+            max_type_depth: MAX_TYPE_DEPTH,
+            max_type_size: Some(16),
+            ..Default::default()
+        }));
+    assert!(matches!(
+        context.compile(&program, None),
+        Err(NativeError::TypeTooComplex(_))
+    ));
+
+    // A struct which contains itself is rejected even without limits.
+    let program = ProgramParser::new()
+        .parse(
+            "type T0 = felt252;
+             type T1 = Struct<ut@T1, T0, T1> [storable: true, drop: true, dup: true, zero_sized: false];",
+        )
+        .unwrap();
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_limits(CompilationLimits {
+            max_type_depth: usize::MAX,
+            max_type_size: None,
+            ..Default::default()
+        }));
+    assert!(matches!(
+        context.compile(&program, None),
+        Err(NativeError::TypeTooComplex(_))
+    ));
+}
+
+#[test]
+pub fn compile_deterministic() {
+    let program = load_cairo_contract_path("programs/erc20.cairo")
+        .extract_sierra_program()
+        .expect("contract bytes should be a valid sierra program");
+
+    // Every iteration uses a fresh context so that nothing (ex. interned types or the order in
+    // which helper functions are declared) is shared between the compilations.
+    let compile = |parallel_compilation| {
+        let context = NativeContext::new()
+            .with_config(CompilerConfig::new().with_parallel_compilation(parallel_compilation));
+        let module = context.compile(&program, None).unwrap();
+
+        (
+            module.module().as_operation().to_string(),
+            cairo_native::module_to_object(module.module(), Default::default()).unwrap(),
+        )
+    };
+
+    // The output doesn't depend on how the functions are scheduled when compiling in parallel,
+    // and it's the same as when compiling sequentially.
+    let (expected_mlir, expected_object) = compile(false);
+    for parallel_compilation in [false, true] {
+        for _ in 0..4 {
+            let (mlir, object) = compile(parallel_compilation);
+            assert_eq!(
+                mlir, expected_mlir,
+                "parallel_compilation = {parallel_compilation}"
+            );
+            assert!(
+                object == expected_object,
+                "object files differ (parallel_compilation = {parallel_compilation})"
+            );
+        }
+    }
+}
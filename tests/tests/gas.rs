@@ -0,0 +1,202 @@
+//! Differential tests around gas accounting.
+//!
+//! The remaining gas must match the cairo-vm to the unit, including when the program runs out of
+//! gas in the middle of a loop, when branches of different costs are merged (redeposit) and when
+//! dictionaries refund gas on squash.
+
+use crate::common::{
+    compare_outputs, load_cairo, load_cairo_with_redeposit_gas, run_native_program, run_vm_program,
+};
+use cairo_felt::Felt252 as DeprecatedFelt;
+use cairo_lang_runner::{Arg, SierraCasmRunner};
+use cairo_lang_sierra::program::Program;
+use cairo_native::{starknet::DummySyscallHandler, values::JitValue};
+use lazy_static::lazy_static;
+use proptest::prelude::*;
+use starknet_types_core::felt::Felt;
+
+lazy_static! {
+    static ref LOOP_COUNTER: (String, Program, SierraCasmRunner) = load_cairo! {
+        fn run_test(n: felt252) -> felt252 {
+            let mut i = 0;
+            loop {
+                if i == n {
+                    break i;
+                }
+                i += 1;
+            }
+        }
+    };
+    static ref LOOP_REDEPOSIT: (String, Program, SierraCasmRunner) = load_cairo_with_redeposit_gas! {
+        fn run_test(n: felt252) -> felt252 {
+            let mut acc = 0;
+            let mut i = 0;
+            loop {
+                if i == n {
+                    break acc;
+                }
+                // Both branches have a different cost, so the cheaper one must redeposit the
+                // difference before they are merged.
+                if i * 3 == acc {
+                    acc = acc * 7 + i * i * 13 + (acc + i) * (acc - i);
+                } else {
+                    acc += 1;
+                }
+                i += 1;
+            }
+        }
+    };
+    static ref DICT_REFUND: (String, Program, SierraCasmRunner) = load_cairo! {
+        use traits::Default;
+        use dict::Felt252DictTrait;
+
+        fn run_test(n: felt252) -> felt252 {
+            let mut dict: Felt252Dict<felt252> = Default::default();
+            let mut i = 0;
+            loop {
+                if i == n {
+                    break;
+                }
+                // Repeated keys make the squash refund part of the gas.
+                dict.insert(0, i);
+                i += 1;
+            };
+            dict.get(0)
+        }
+    };
+}
+
+#[track_caller]
+fn compare_gas(
+    program: &(String, Program, SierraCasmRunner),
+    n: u64,
+    gas: u64,
+) -> Result<(), TestCaseError> {
+    let result_vm = run_vm_program(
+        program,
+        "run_test",
+        &[Arg::Value(DeprecatedFelt::from(n))],
+        Some(gas as usize),
+    )
+    .unwrap();
+    let result_native = run_native_program(
+        program,
+        "run_test",
+        &[JitValue::Felt252(Felt::from(n))],
+        Some(gas as u128),
+        Option::<DummySyscallHandler>::None,
+    );
+
+    compare_outputs(
+        &program.1,
+        &program.2.find_function("run_test").unwrap().id,
+        &result_vm,
+        &result_native,
+    )
+}
+
+/// Find the smallest amount of gas with which the program finishes successfully, using the vm.
+fn min_successful_gas(program: &(String, Program, SierraCasmRunner), n: u64) -> u64 {
+    let succeeds = |gas: u64| {
+        run_vm_program(
+            program,
+            "run_test",
+            &[Arg::Value(DeprecatedFelt::from(n))],
+            Some(gas as usize),
+        )
+        .map(|result| matches!(result.value, cairo_lang_runner::RunResultValue::Success(_)))
+        .unwrap_or(false)
+    };
+
+    let (mut lo, mut hi) = (0, 1 << 24);
+    assert!(succeeds(hi), "program does not finish with the maximum gas");
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if succeeds(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    lo
+}
+
+#[test]
+fn loop_exact_out_of_gas() {
+    for n in [0, 1, 5, 20] {
+        let gas = min_successful_gas(&LOOP_COUNTER, n);
+        for gas in gas.saturating_sub(2)..=gas + 2 {
+            compare_gas(&LOOP_COUNTER, n, gas).unwrap();
+        }
+    }
+}
+
+#[test]
+fn redeposit_exact_out_of_gas() {
+    for n in [0, 1, 5, 20] {
+        let gas = min_successful_gas(&LOOP_REDEPOSIT, n);
+        for gas in gas.saturating_sub(2)..=gas + 2 {
+            compare_gas(&LOOP_REDEPOSIT, n, gas).unwrap();
+        }
+    }
+}
+
+#[test]
+fn redeposit_gas_refunds_the_cheaper_branch() {
+    assert!(LOOP_REDEPOSIT
+        .1
+        .libfunc_declarations
+        .iter()
+        .any(|decl| decl.long_id.generic_id.0 == "redeposit_gas"));
+
+    let gas = 100_000;
+    let result_vm = run_vm_program(
+        &LOOP_REDEPOSIT,
+        "run_test",
+        &[Arg::Value(DeprecatedFelt::from(20))],
+        Some(gas),
+    )
+    .unwrap();
+    let result_native = run_native_program(
+        &LOOP_REDEPOSIT,
+        "run_test",
+        &[JitValue::Felt252(Felt::from(20))],
+        Some(gas as u128),
+        Option::<DummySyscallHandler>::None,
+    );
+
+    let remaining_gas = result_native.remaining_gas.unwrap();
+    assert!(remaining_gas < gas as u128);
+    assert_eq!(
+        result_vm.gas_counter,
+        Some(DeprecatedFelt::from(remaining_gas))
+    );
+}
+
+#[test]
+fn dict_refund_exact_out_of_gas() {
+    for n in [0, 1, 5, 20] {
+        let gas = min_successful_gas(&DICT_REFUND, n);
+        for gas in gas.saturating_sub(2)..=gas + 2 {
+            compare_gas(&DICT_REFUND, n, gas).unwrap();
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn loop_gas_proptest(n in 0u64..64, gas in 0u64..100_000) {
+        compare_gas(&LOOP_COUNTER, n, gas)?;
+    }
+
+    #[test]
+    fn redeposit_gas_proptest(n in 0u64..64, gas in 0u64..100_000) {
+        compare_gas(&LOOP_REDEPOSIT, n, gas)?;
+    }
+
+    #[test]
+    fn dict_refund_gas_proptest(n in 0u64..64, gas in 0u64..200_000) {
+        compare_gas(&DICT_REFUND, n, gas)?;
+    }
+}
@@ -0,0 +1,111 @@
+//! Soak tests checking that compiling, running and dropping programs doesn't leak memory.
+//!
+//! They take a while, so they're ignored by default. Run them with:
+//!
+//! ```sh
+//! cargo test --test tests memory -- --ignored
+//! ```
+
+#![cfg(target_os = "linux")]
+
+use crate::common::load_cairo;
+use cairo_lang_runner::SierraCasmRunner;
+use cairo_lang_sierra::program::Program;
+use cairo_native::{
+    cache::JitProgramCache, context::NativeContext, executor::JitNativeExecutor, values::JitValue,
+    OptLevel,
+};
+use lazy_static::lazy_static;
+use starknet_types_core::felt::Felt;
+
+const ITERATIONS: usize = 10_000;
+const WARMUP_ITERATIONS: usize = 1_000;
+
+/// Maximum RSS growth (in bytes) allowed after the warmup.
+const MAX_RSS_GROWTH: usize = 32 << 20;
+
+lazy_static! {
+    static ref PROGRAM: (String, Program, SierraCasmRunner) = load_cairo! {
+        use array::ArrayTrait;
+
+        fn run_test(value: felt252) -> Array<felt252> {
+            let mut data = ArrayTrait::new();
+            data.append(value);
+            data.append(value + 1);
+            data
+        }
+    };
+}
+
+/// Current resident set size, in bytes.
+fn current_rss() -> usize {
+    let statm = std::fs::read_to_string("/proc/self/statm").unwrap();
+    let pages = statm
+        .split_whitespace()
+        .nth(1)
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+
+    pages * unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize
+}
+
+/// Run `f` for [`ITERATIONS`] iterations, asserting that the RSS stabilizes after the warmup.
+fn assert_rss_stabilizes(mut f: impl FnMut()) {
+    (0..WARMUP_ITERATIONS).for_each(|_| f());
+    let initial_rss = current_rss();
+
+    (WARMUP_ITERATIONS..ITERATIONS).for_each(|_| f());
+    let final_rss = current_rss();
+
+    assert!(
+        final_rss <= initial_rss + MAX_RSS_GROWTH,
+        "RSS grew from {initial_rss} to {final_rss} bytes"
+    );
+}
+
+fn run(executor: &JitNativeExecutor) {
+    let entry_point = format!("{0}::{0}::run_test", PROGRAM.0);
+    let function_id = &PROGRAM
+        .1
+        .funcs
+        .iter()
+        .find(|x| x.id.debug_name.as_deref() == Some(&entry_point))
+        .expect("entry point not found")
+        .id;
+
+    let result = executor
+        .invoke_dynamic(function_id, &[Felt::from(7).into()], None)
+        .unwrap();
+    assert_eq!(
+        result.return_value,
+        JitValue::Array(vec![Felt::from(7).into(), Felt::from(8).into()])
+    );
+}
+
+#[test]
+#[ignore]
+fn jit_compile_execute_drop() {
+    let context = NativeContext::new();
+
+    assert_rss_stabilizes(|| {
+        let module = context.compile(&PROGRAM.1, None).unwrap();
+        let executor = JitNativeExecutor::from_native_module(module, OptLevel::None);
+        run(&executor);
+    });
+}
+
+#[test]
+#[ignore]
+fn jit_cache_insert_remove() {
+    let context = NativeContext::new();
+    let mut cache = JitProgramCache::new(&context);
+
+    assert_rss_stabilizes(|| {
+        let executor = cache.compile_and_insert("program", &PROGRAM.1, OptLevel::None);
+        run(&executor);
+
+        drop(executor);
+        cache.remove(&"program").unwrap();
+    });
+}
@@ -1,14 +1,19 @@
 pub mod alexandria;
 pub mod arrays;
 pub mod boolean;
+pub mod byte_array;
 pub mod cases;
 pub mod compile_library;
 pub mod dict;
 pub mod ec;
 pub mod felt252;
+pub mod gas;
 pub mod libfuncs;
+pub mod memory;
 pub mod programs;
+pub mod range_check;
 pub mod result;
 pub mod starknet;
+pub mod testing;
 pub mod trampoline;
 pub mod uint;
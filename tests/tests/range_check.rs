@@ -0,0 +1,375 @@
+//! Differential tests for the range check usage tracking.
+//!
+//! Every libfunc with a known usage in `RangeCheckUsageMeta` is run through each of its branches,
+//! both on the VM and on Native with range check tracking enabled, and both runs must use the same
+//! number of range checks. A libfunc whose usage is added to or changed in the table should get its
+//! cases here too.
+
+use crate::common::{load_cairo, DEFAULT_GAS};
+use cairo_felt::Felt252 as DeprecatedFelt;
+use cairo_lang_runner::{Arg, SierraCasmRunner};
+use cairo_lang_sierra::program::Program;
+use cairo_native::{
+    context::{CompilerConfig, NativeContext},
+    executor::JitNativeExecutor,
+    testing::run_vm,
+    values::JitValue,
+};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use starknet_types_core::felt::Felt;
+use test_case::test_case;
+
+lazy_static! {
+    static ref LIBFUNCS: (String, Program, SierraCasmRunner) = load_cairo! {
+        use core::array::{ArrayTrait, SpanTrait};
+        use core::bytes_31::bytes31_try_from_felt252;
+        use core::ec::ec_point_from_x_nz;
+        use core::gas::{get_builtin_costs, withdraw_gas_all};
+        use core::integer::{downcast, u256_as_non_zero, u512, u512_safe_div_rem_by_u256};
+        use core::math::u256_inv_mod;
+        use core::option::OptionTrait;
+        use core::traits::{Into, TryInto};
+        use starknet::class_hash::{ClassHash, class_hash_try_from_felt252};
+        use starknet::contract_address::{ContractAddress, contract_address_try_from_felt252};
+        use starknet::storage_access::{
+            StorageAddress, StorageBaseAddress, storage_address_try_from_felt252,
+            storage_base_address_from_felt252,
+        };
+
+        extern fn i8_diff(lhs: i8, rhs: i8) -> Result<u8, u8> implicits(RangeCheck) nopanic;
+        extern fn i128_diff(lhs: i128, rhs: i128) -> Result<u128, u128> implicits(RangeCheck) nopanic;
+
+        fn u8_add(a: u8, b: u8) -> u8 {
+            a + b
+        }
+
+        fn u16_add(a: u16, b: u16) -> u16 {
+            a + b
+        }
+
+        fn u32_sub(a: u32, b: u32) -> u32 {
+            a - b
+        }
+
+        fn u64_add(a: u64, b: u64) -> u64 {
+            a + b
+        }
+
+        fn u128_add(a: u128, b: u128) -> u128 {
+            a + b
+        }
+
+        fn u8_from_felt252(a: felt252) -> Option<u8> {
+            a.try_into()
+        }
+
+        fn u64_from_felt252(a: felt252) -> Option<u64> {
+            a.try_into()
+        }
+
+        fn i8_from_felt252(a: felt252) -> Option<i8> {
+            a.try_into()
+        }
+
+        fn i64_from_felt252(a: felt252) -> Option<i64> {
+            a.try_into()
+        }
+
+        fn u128_from_felt252(a: felt252) -> Option<u128> {
+            a.try_into()
+        }
+
+        fn i128_from_felt252(a: felt252) -> Option<i128> {
+            a.try_into()
+        }
+
+        fn u8_div(a: u8, b: u8) -> u8 {
+            a / b
+        }
+
+        fn u64_div(a: u64, b: u64) -> u64 {
+            a / b
+        }
+
+        fn u128_div(a: u128, b: u128) -> u128 {
+            a / b
+        }
+
+        fn u8_root(a: u8) -> u8 {
+            core::integer::u8_sqrt(a)
+        }
+
+        fn u64_root(a: u64) -> u32 {
+            core::integer::u64_sqrt(a)
+        }
+
+        fn u128_root(a: u128) -> u64 {
+            core::integer::u128_sqrt(a)
+        }
+
+        fn u128_wide_mul_high(a: u128, b: u128) -> u128 {
+            let (high, _) = core::integer::u128_wide_mul(a, b);
+            high
+        }
+
+        fn u256_div(a: u128, b: u128) -> u256 {
+            u256 { low: a, high: a } / u256 { low: b, high: 0 }
+        }
+
+        fn u256_root(a: u128) -> u128 {
+            core::integer::u256_sqrt(u256 { low: a, high: a })
+        }
+
+        fn u256_inverse(a: u128, n: u128) -> Option<NonZero<u256>> {
+            u256_inv_mod(a.into(), u256_as_non_zero(n.into()))
+        }
+
+        fn u512_div(a: u128, b: u128) -> u512 {
+            let (quotient, _) = u512_safe_div_rem_by_u256(
+                u512 { limb0: a, limb1: a, limb2: a, limb3: a }, u256_as_non_zero(b.into())
+            );
+            quotient
+        }
+
+        fn i8_add(a: i8, b: i8) -> i8 {
+            a + b
+        }
+
+        fn i64_sub(a: i64, b: i64) -> i64 {
+            a - b
+        }
+
+        fn i128_add(a: i128, b: i128) -> i128 {
+            a + b
+        }
+
+        fn i8_difference(a: i8, b: i8) -> Result<u8, u8> {
+            i8_diff(a, b)
+        }
+
+        fn i128_difference(a: i128, b: i128) -> Result<u128, u128> {
+            i128_diff(a, b)
+        }
+
+        fn u16_to_u8(a: u16) -> Option<u8> {
+            downcast(a)
+        }
+
+        fn i16_to_i8(a: i16) -> Option<i8> {
+            downcast(a)
+        }
+
+        fn i16_to_u8(a: i16) -> Option<u8> {
+            downcast(a)
+        }
+
+        fn array_get(index: u32) -> Option<felt252> {
+            match array![1, 2, 3].span().get(index) {
+                Option::Some(x) => Option::Some(*x.unbox()),
+                Option::None => Option::None,
+            }
+        }
+
+        fn array_slice(start: u32, len: u32) -> u32 {
+            array![1, 2, 3].span().slice(start, len).len()
+        }
+
+        fn point_from_x(x: felt252) -> bool {
+            ec_point_from_x_nz(x).is_some()
+        }
+
+        fn withdraw_gas(n: felt252) -> felt252 {
+            let mut i = 0;
+            loop {
+                if i == n {
+                    break i;
+                }
+                i += 1;
+            }
+        }
+
+        fn builtin_withdraw_gas(n: felt252) -> felt252 {
+            let mut i = 0;
+            loop {
+                if i == n {
+                    break i;
+                }
+                match withdraw_gas_all(get_builtin_costs()) {
+                    Option::Some(()) => {},
+                    Option::None => {
+                        break i;
+                    },
+                }
+                i += 1;
+            }
+        }
+
+        fn bytes31_from_felt252(a: felt252) -> Option<bytes31> {
+            bytes31_try_from_felt252(a)
+        }
+
+        fn class_hash_from_felt252(a: felt252) -> Option<ClassHash> {
+            class_hash_try_from_felt252(a)
+        }
+
+        fn contract_address_from_felt252(a: felt252) -> Option<ContractAddress> {
+            contract_address_try_from_felt252(a)
+        }
+
+        fn storage_address_from_felt252(a: felt252) -> Option<StorageAddress> {
+            storage_address_try_from_felt252(a)
+        }
+
+        fn storage_base_address(a: felt252) -> StorageBaseAddress {
+            storage_base_address_from_felt252(a)
+        }
+    };
+}
+
+/// 2**248, the first value that doesn't fit in a `bytes31`.
+fn bytes31_limit() -> Felt {
+    Felt::from_hex("0x100000000000000000000000000000000000000000000000000000000000000").unwrap()
+}
+
+/// 2**251, the first value that isn't a valid contract address.
+fn address_limit() -> Felt {
+    Felt::from_hex("0x800000000000000000000000000000000000000000000000000000000000000").unwrap()
+}
+
+fn vm_arg(value: &JitValue) -> Arg {
+    let value = match value {
+        JitValue::Felt252(x) => *x,
+        JitValue::Uint8(x) => Felt::from(*x),
+        JitValue::Uint16(x) => Felt::from(*x),
+        JitValue::Uint32(x) => Felt::from(*x),
+        JitValue::Uint64(x) => Felt::from(*x),
+        JitValue::Uint128(x) => Felt::from(*x),
+        JitValue::Sint8(x) => Felt::from(*x),
+        JitValue::Sint16(x) => Felt::from(*x),
+        JitValue::Sint64(x) => Felt::from(*x),
+        JitValue::Sint128(x) => Felt::from(*x),
+        _ => unimplemented!("unsupported test argument: {value:?}"),
+    };
+
+    Arg::Value(DeprecatedFelt::from_bytes_be(&value.to_bytes_be()))
+}
+
+#[track_caller]
+fn compare_range_checks(entry_point: &str, args: &[JitValue], gas: u64) {
+    let (name, program, runner) = &*LIBFUNCS;
+
+    let debug_name = format!("{name}::{name}::{entry_point}");
+    let entry_point_id = &program
+        .funcs
+        .iter()
+        .find(|x| x.id.debug_name.as_deref() == Some(&debug_name))
+        .expect("Test program entry point not found.")
+        .id;
+
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_range_check_tracking(true));
+    let module = context
+        .compile(program, None)
+        .expect("Could not compile test program to MLIR.");
+    let native_result = JitNativeExecutor::from_native_module(module, Default::default())
+        .invoke_dynamic(entry_point_id, args, Some(gas as u128))
+        .unwrap();
+
+    let vm_result = run_vm(
+        runner,
+        runner.find_function(entry_point).unwrap(),
+        &args.iter().map(vm_arg).collect_vec(),
+        Some(gas as usize),
+    )
+    .unwrap();
+
+    assert_eq!(
+        native_result.builtin_stats.range_check, vm_result.builtins.range_check,
+        "range checks used by `{entry_point}` with {args:?}"
+    );
+}
+
+#[test_case("u8_add", &[JitValue::Uint8(1), JitValue::Uint8(2)] ; "u8_overflowing_add")]
+#[test_case("u8_add", &[JitValue::Uint8(u8::MAX), JitValue::Uint8(1)] ; "u8_overflowing_add overflow")]
+#[test_case("u16_add", &[JitValue::Uint16(1), JitValue::Uint16(2)] ; "u16_overflowing_add")]
+#[test_case("u16_add", &[JitValue::Uint16(u16::MAX), JitValue::Uint16(1)] ; "u16_overflowing_add overflow")]
+#[test_case("u32_sub", &[JitValue::Uint32(2), JitValue::Uint32(1)] ; "u32_overflowing_sub")]
+#[test_case("u32_sub", &[JitValue::Uint32(1), JitValue::Uint32(2)] ; "u32_overflowing_sub overflow")]
+#[test_case("u64_add", &[JitValue::Uint64(1), JitValue::Uint64(2)] ; "u64_overflowing_add")]
+#[test_case("u64_add", &[JitValue::Uint64(u64::MAX), JitValue::Uint64(1)] ; "u64_overflowing_add overflow")]
+#[test_case("u128_add", &[JitValue::Uint128(1), JitValue::Uint128(2)] ; "u128_overflowing_add")]
+#[test_case("u128_add", &[JitValue::Uint128(u128::MAX), JitValue::Uint128(1)] ; "u128_overflowing_add overflow")]
+#[test_case("u8_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "u8_try_from_felt252")]
+#[test_case("u8_from_felt252", &[JitValue::Felt252(Felt::from(256))] ; "u8_try_from_felt252 above")]
+#[test_case("u8_from_felt252", &[JitValue::Felt252(Felt::from(-1))] ; "u8_try_from_felt252 below")]
+#[test_case("u64_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "u64_try_from_felt252")]
+#[test_case("u64_from_felt252", &[JitValue::Felt252(Felt::from(u128::MAX))] ; "u64_try_from_felt252 above")]
+#[test_case("i8_from_felt252", &[JitValue::Felt252(Felt::from(-3))] ; "i8_try_from_felt252")]
+#[test_case("i8_from_felt252", &[JitValue::Felt252(Felt::from(128))] ; "i8_try_from_felt252 above")]
+#[test_case("i8_from_felt252", &[JitValue::Felt252(Felt::from(-129))] ; "i8_try_from_felt252 below")]
+#[test_case("i64_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "i64_try_from_felt252")]
+#[test_case("i64_from_felt252", &[JitValue::Felt252(Felt::from(u64::MAX))] ; "i64_try_from_felt252 above")]
+#[test_case("u128_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "u128s_from_felt252")]
+#[test_case("u128_from_felt252", &[JitValue::Felt252(Felt::from(u128::MAX) + Felt::ONE)] ; "u128s_from_felt252 wide")]
+#[test_case("i128_from_felt252", &[JitValue::Felt252(Felt::from(-3))] ; "i128_try_from_felt252")]
+#[test_case("i128_from_felt252", &[JitValue::Felt252(Felt::from(u128::MAX))] ; "i128_try_from_felt252 above")]
+#[test_case("u8_div", &[JitValue::Uint8(200), JitValue::Uint8(7)] ; "u8_safe_divmod")]
+#[test_case("u64_div", &[JitValue::Uint64(u64::MAX), JitValue::Uint64(7)] ; "u64_safe_divmod")]
+#[test_case("u128_div", &[JitValue::Uint128(u128::MAX), JitValue::Uint128(7)] ; "u128_safe_divmod")]
+#[test_case("u8_root", &[JitValue::Uint8(200)] ; "u8_sqrt")]
+#[test_case("u64_root", &[JitValue::Uint64(u64::MAX)] ; "u64_sqrt")]
+#[test_case("u128_root", &[JitValue::Uint128(u128::MAX)] ; "u128_sqrt")]
+#[test_case("u128_wide_mul_high", &[JitValue::Uint128(u128::MAX), JitValue::Uint128(3)] ; "u128_mul_guarantee_verify")]
+#[test_case("u256_div", &[JitValue::Uint128(u128::MAX), JitValue::Uint128(7)] ; "u256_safe_divmod")]
+#[test_case("u256_root", &[JitValue::Uint128(u128::MAX)] ; "u256_sqrt")]
+#[test_case("u256_inverse", &[JitValue::Uint128(3), JitValue::Uint128(7)] ; "u256_guarantee_inv_mod_n")]
+#[test_case("u256_inverse", &[JitValue::Uint128(2), JitValue::Uint128(4)] ; "u256_guarantee_inv_mod_n not invertible")]
+#[test_case("u512_div", &[JitValue::Uint128(u128::MAX), JitValue::Uint128(7)] ; "u512_safe_divmod_by_u256")]
+#[test_case("i8_add", &[JitValue::Sint8(1), JitValue::Sint8(2)] ; "i8_overflowing_add_impl")]
+#[test_case("i8_add", &[JitValue::Sint8(i8::MAX), JitValue::Sint8(1)] ; "i8_overflowing_add_impl overflow")]
+#[test_case("i8_add", &[JitValue::Sint8(i8::MIN), JitValue::Sint8(-1)] ; "i8_overflowing_add_impl underflow")]
+#[test_case("i64_sub", &[JitValue::Sint64(1), JitValue::Sint64(2)] ; "i64_overflowing_sub_impl")]
+#[test_case("i64_sub", &[JitValue::Sint64(i64::MAX), JitValue::Sint64(-1)] ; "i64_overflowing_sub_impl overflow")]
+#[test_case("i64_sub", &[JitValue::Sint64(i64::MIN), JitValue::Sint64(1)] ; "i64_overflowing_sub_impl underflow")]
+#[test_case("i128_add", &[JitValue::Sint128(1), JitValue::Sint128(2)] ; "i128_overflowing_add_impl")]
+#[test_case("i128_add", &[JitValue::Sint128(i128::MAX), JitValue::Sint128(1)] ; "i128_overflowing_add_impl overflow")]
+#[test_case("i128_add", &[JitValue::Sint128(i128::MIN), JitValue::Sint128(-1)] ; "i128_overflowing_add_impl underflow")]
+#[test_case("i8_difference", &[JitValue::Sint8(2), JitValue::Sint8(-1)] ; "i8_diff")]
+#[test_case("i8_difference", &[JitValue::Sint8(-1), JitValue::Sint8(2)] ; "i8_diff negative")]
+#[test_case("i128_difference", &[JitValue::Sint128(2), JitValue::Sint128(-1)] ; "i128_diff")]
+#[test_case("i128_difference", &[JitValue::Sint128(-1), JitValue::Sint128(2)] ; "i128_diff negative")]
+#[test_case("u16_to_u8", &[JitValue::Uint16(3)] ; "downcast above")]
+#[test_case("u16_to_u8", &[JitValue::Uint16(256)] ; "downcast above overflow")]
+#[test_case("i16_to_i8", &[JitValue::Sint16(-3)] ; "downcast both")]
+#[test_case("i16_to_i8", &[JitValue::Sint16(128)] ; "downcast both overflow")]
+#[test_case("i16_to_i8", &[JitValue::Sint16(-129)] ; "downcast both underflow")]
+#[test_case("i16_to_u8", &[JitValue::Sint16(3)] ; "downcast signedness")]
+#[test_case("i16_to_u8", &[JitValue::Sint16(-1)] ; "downcast signedness underflow")]
+#[test_case("array_get", &[JitValue::Uint32(2)] ; "array_get")]
+#[test_case("array_get", &[JitValue::Uint32(3)] ; "array_get out of bounds")]
+#[test_case("array_slice", &[JitValue::Uint32(1), JitValue::Uint32(2)] ; "array_slice")]
+#[test_case("array_slice", &[JitValue::Uint32(2), JitValue::Uint32(2)] ; "array_slice out of bounds")]
+#[test_case("point_from_x", &[JitValue::Felt252(Felt::from(1))] ; "ec_point_from_x_nz")]
+#[test_case("point_from_x", &[JitValue::Felt252(Felt::from(0))] ; "ec_point_from_x_nz not on curve")]
+#[test_case("bytes31_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "bytes31_try_from_felt252")]
+#[test_case("bytes31_from_felt252", &[JitValue::Felt252(bytes31_limit())] ; "bytes31_try_from_felt252 above")]
+#[test_case("class_hash_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "class_hash_try_from_felt252")]
+#[test_case("class_hash_from_felt252", &[JitValue::Felt252(address_limit())] ; "class_hash_try_from_felt252 above")]
+#[test_case("contract_address_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "contract_address_try_from_felt252")]
+#[test_case("contract_address_from_felt252", &[JitValue::Felt252(address_limit())] ; "contract_address_try_from_felt252 above")]
+#[test_case("storage_address_from_felt252", &[JitValue::Felt252(Felt::from(3))] ; "storage_address_try_from_felt252")]
+#[test_case("storage_address_from_felt252", &[JitValue::Felt252(address_limit())] ; "storage_address_try_from_felt252 above")]
+#[test_case("storage_base_address", &[JitValue::Felt252(Felt::from(3))] ; "storage_base_address_from_felt252")]
+#[test_case("storage_base_address", &[JitValue::Felt252(Felt::MAX)] ; "storage_base_address_from_felt252 reduced")]
+fn range_check_usage(entry_point: &str, args: &[JitValue]) {
+    compare_range_checks(entry_point, args, DEFAULT_GAS);
+}
+
+#[test_case("withdraw_gas", 10, DEFAULT_GAS ; "withdraw_gas")]
+#[test_case("withdraw_gas", 1_000_000, 100_000 ; "withdraw_gas out of gas")]
+#[test_case("builtin_withdraw_gas", 10, DEFAULT_GAS ; "withdraw_gas_all")]
+#[test_case("builtin_withdraw_gas", 1_000_000, 100_000 ; "withdraw_gas_all out of gas")]
+fn range_check_usage_with_gas(entry_point: &str, n: u64, gas: u64) {
+    compare_range_checks(entry_point, &[JitValue::Felt252(Felt::from(n))], gas);
+}
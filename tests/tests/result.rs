@@ -1,9 +1,44 @@
+use crate::common::{load_cairo, run_native_program, DEFAULT_GAS};
+use cairo_lang_runner::SierraCasmRunner;
+use cairo_lang_sierra::program::Program;
 use cairo_native::{
+    error::Error,
     execution_result::{BuiltinStats, ContractExecutionResult, ExecutionResult},
+    starknet::DummySyscallHandler,
     values::JitValue,
 };
+use lazy_static::lazy_static;
+use starknet_types_core::felt::Felt;
 use test_case::test_case;
 
+lazy_static! {
+    static ref PANICS: (String, Program, SierraCasmRunner) = load_cairo! {
+        use array::ArrayTrait;
+        use core::panics::panic;
+
+        fn check(x: felt252) -> felt252 {
+            if x == 0 {
+                // 'zero'
+                panic_with_felt252(0x7a65726f);
+            }
+            x
+        }
+
+        fn run_test(x: felt252) -> felt252 {
+            // The addition must not be executed when `check` panics.
+            check(x) + 1
+        }
+
+        fn run_test_data() -> felt252 {
+            let mut data = ArrayTrait::new();
+            // 'error'
+            data.append(0x6572726f72);
+            data.append(42);
+            panic(data)
+        }
+    };
+}
+
 fn from_execution_result(
     res: ExecutionResult,
 ) -> Result<ContractExecutionResult, cairo_native::error::Error> {
@@ -90,6 +125,48 @@ fn test_cases(return_value: JitValue) {
         return_value,
         remaining_gas: None,
         builtin_stats: BuiltinStats::default(),
+        syscall_profile: None,
     })
     .unwrap();
 }
+
+fn run_panics(entry_point: &str, args: &[JitValue]) -> Result<JitValue, Error> {
+    run_native_program(
+        &PANICS,
+        entry_point,
+        args,
+        Some(DEFAULT_GAS as u128),
+        Option::<DummySyscallHandler>::None,
+    )
+    .into_result()
+}
+
+#[test]
+fn panic_result_ok() {
+    assert_eq!(
+        run_panics("run_test", &[JitValue::Felt252(2.into())]).unwrap(),
+        JitValue::Felt252(3.into())
+    );
+}
+
+#[test]
+fn panic_result_propagated() {
+    match run_panics("run_test", &[JitValue::Felt252(0.into())]) {
+        Err(Error::Panic { message, data }) => {
+            assert_eq!(message, "'zero'");
+            assert_eq!(data, [Felt::from_bytes_be_slice(b"zero")]);
+        }
+        x => panic!("expected a panic, got {x:?}"),
+    }
+}
+
+#[test]
+fn panic_result_data() {
+    let error = run_panics("run_test_data", &[]).unwrap_err();
+    assert!(matches!(
+        &error,
+        Error::Panic { data, .. } if data == &[Felt::from_bytes_be_slice(b"error"), Felt::from(42)]
+    ));
+    // The single-byte felt is a number, not the `'*'` character.
+    assert_eq!(error.to_string(), "program panicked with ('error', 42)");
+}
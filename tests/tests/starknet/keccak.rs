@@ -1,8 +1,11 @@
 use crate::common::run_native_starknet_contract;
 use cairo_lang_compiler::CompilerConfig;
 use cairo_lang_starknet::compile::compile_path;
-use cairo_native::starknet_stub::StubSyscallHandler;
+use cairo_native::{
+    context::NativeContext, executor::JitNativeExecutor, starknet_stub::StubSyscallHandler,
+};
 use lazy_static::lazy_static;
+use starknet_types_core::felt::Felt;
 use std::path::Path;
 
 lazy_static! {
@@ -42,3 +45,33 @@ fn keccak_test() {
     );
     assert_eq!(result.return_values, vec![1.into()]);
 }
+
+#[test]
+fn keccak_test_compile_contract() {
+    let contract = &KECCAK_CONTRACT;
+    let selector = Felt::from_bytes_be_slice(
+        &contract.entry_points_by_type.external[0]
+            .selector
+            .to_bytes_be(),
+    );
+
+    let native_context = NativeContext::new();
+    let (module, entry_points) = native_context.compile_contract(contract).unwrap();
+    assert_eq!(entry_points.external.len(), 1);
+    assert!(entry_points.l1_handler.is_empty());
+    assert!(entry_points.constructor.is_empty());
+
+    let function_id = entry_points.find_external(&selector).unwrap();
+    let executor = JitNativeExecutor::from_native_module(module, Default::default());
+    let result = executor
+        .invoke_contract_dynamic(
+            function_id,
+            &[],
+            u128::MAX.into(),
+            &mut StubSyscallHandler::default(),
+        )
+        .unwrap();
+
+    assert!(!result.failure_flag);
+    assert_eq!(result.return_values, vec![1.into()]);
+}
@@ -0,0 +1,35 @@
+use core::starknet::{
+    SyscallResultTrait,
+    secp256r1::{
+        Secp256r1Point, secp256r1_new_syscall, secp256r1_add_syscall, secp256r1_mul_syscall,
+        secp256r1_get_point_from_x_syscall, secp256r1_get_xy_syscall,
+    },
+};
+
+fn new_point(x: u256, y: u256) -> Secp256r1Point {
+    secp256r1_new_syscall(x, y).unwrap_syscall().unwrap()
+}
+
+fn run_new(x: u256, y: u256) -> Option<(u256, u256)> {
+    match secp256r1_new_syscall(x, y).unwrap_syscall() {
+        Option::Some(p) => Option::Some(secp256r1_get_xy_syscall(p).unwrap_syscall()),
+        Option::None => Option::None,
+    }
+}
+
+fn run_add(x0: u256, y0: u256, x1: u256, y1: u256) -> (u256, u256) {
+    let p = secp256r1_add_syscall(new_point(x0, y0), new_point(x1, y1)).unwrap_syscall();
+    secp256r1_get_xy_syscall(p).unwrap_syscall()
+}
+
+fn run_mul(x: u256, y: u256, scalar: u256) -> (u256, u256) {
+    let p = secp256r1_mul_syscall(new_point(x, y), scalar).unwrap_syscall();
+    secp256r1_get_xy_syscall(p).unwrap_syscall()
+}
+
+fn run_get_point_from_x(x: u256, y_parity: felt252) -> Option<(u256, u256)> {
+    match secp256r1_get_point_from_x_syscall(x, y_parity == 1).unwrap_syscall() {
+        Option::Some(p) => Option::Some(secp256r1_get_xy_syscall(p).unwrap_syscall()),
+        Option::None => Option::None,
+    }
+}
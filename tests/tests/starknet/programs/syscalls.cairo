@@ -21,10 +21,26 @@ fn get_execution_info_v2() -> SyscallResult<Box<core::starknet::info::v2::Execut
     get_execution_info_v2_syscall()
 }
 
+fn get_execution_info_v2_tx_arrays() -> (u32, u32, felt252, u64, u128) {
+    let tx_info = get_execution_info_v2_syscall().unwrap().unbox().tx_info.unbox();
+    let bounds = *tx_info.resource_bounds.at(0);
+    (
+        tx_info.signature.len(),
+        tx_info.resource_bounds.len(),
+        bounds.resource,
+        bounds.max_amount,
+        bounds.max_price_per_unit,
+    )
+}
+
 fn deploy() -> SyscallResult<(ContractAddress, Span<felt252>)> {
     deploy_syscall(class_hash_const::<0>(), 0, array![].span(), false)
 }
 
+fn deploy_undeclared() -> SyscallResult<(ContractAddress, Span<felt252>)> {
+    deploy_syscall(class_hash_const::<1>(), 0, array![].span(), false)
+}
+
 fn replace_class() -> SyscallResult<()> {
     replace_class_syscall(class_hash_const::<0>())
 }
@@ -37,6 +53,14 @@ fn call_contract() -> SyscallResult<Span<felt252>> {
     call_contract_syscall(contract_address_const::<0>(), 0, array![].span())
 }
 
+fn library_call_echo(a: felt252, b: felt252) -> SyscallResult<Span<felt252>> {
+    library_call_syscall(class_hash_const::<0>(), 0, array![a, b].span())
+}
+
+fn call_contract_echo(a: felt252, b: felt252) -> SyscallResult<Span<felt252>> {
+    call_contract_syscall(contract_address_const::<0>(), 0, array![a, b].span())
+}
+
 fn storage_read() -> felt252 {
     storage_read_syscall(0, storage_address_try_from_felt252(0).unwrap()).unwrap()
 }
@@ -45,10 +69,22 @@ fn storage_write() {
     storage_write_syscall(0, storage_address_try_from_felt252(0).unwrap(), 0).unwrap()
 }
 
+fn storage_write_read(value: felt252) -> SyscallResult<(felt252, felt252)> {
+    let address = storage_address_try_from_felt252(1).unwrap();
+    storage_write_syscall(0, address, value)?;
+    let written = storage_read_syscall(0, address)?;
+    let unwritten = storage_read_syscall(0, storage_address_try_from_felt252(2).unwrap())?;
+    Result::Ok((written, unwritten))
+}
+
 fn emit_event() -> SyscallResult<()> {
     emit_event_syscall(array![].span(), array![].span())
 }
 
+fn emit_big_event() -> SyscallResult<()> {
+    emit_event_syscall(array![1].span(), array![1, 2, 3].span())
+}
+
 fn send_message_to_l1() -> SyscallResult<()> {
     send_message_to_l1_syscall(3, array![2].span())
 }
@@ -1,11 +1,17 @@
-use crate::common::{load_cairo_path, run_native_program};
-use cairo_lang_runner::SierraCasmRunner;
+use crate::common::{
+    compare_outputs, load_cairo_path, run_native_program, run_vm_program, DEFAULT_GAS,
+};
+use cairo_felt::Felt252 as DeprecatedFelt;
+use cairo_lang_runner::{Arg, SierraCasmRunner};
 use cairo_lang_sierra::program::Program;
 use cairo_native::{
     starknet::{Secp256k1Point, Secp256r1Point, StarknetSyscallHandler, SyscallResult, U256},
+    starknet_stub::StubSyscallHandler,
     values::JitValue,
 };
 use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 use pretty_assertions_sorted::assert_eq;
 use starknet_types_core::felt::Felt;
 use std::collections::VecDeque;
@@ -252,6 +258,8 @@ impl StarknetSyscallHandler for &mut SyscallHandler {
 lazy_static! {
     static ref SECP256_PROGRAM: (String, Program, SierraCasmRunner) =
         load_cairo_path("tests/tests/starknet/programs/secp256.cairo");
+    static ref SECP256R1_OPS_PROGRAM: (String, Program, SierraCasmRunner) =
+        load_cairo_path("tests/tests/starknet/programs/secp256r1_ops.cairo");
 }
 
 #[test]
@@ -2085,3 +2093,125 @@ fn secp256r1_get_xy() {
     );
     assert!(syscall_handler.secp256r1_get_xy.1.is_empty());
 }
+
+// The following tests compare the secp256r1 syscalls of the stub syscall handler against the
+// syscall hints of the Cairo VM.
+
+const P256_GX: &str = "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
+const P256_GY: &str = "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5";
+const P256_NEG_GY: &str = "b01cbd1c01e58065711814b583f061e9d431cca994cea1313449bf97c840ae0a";
+const P256_N: &str = "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551";
+
+fn u256(value: &str) -> JitValue {
+    let value = BigUint::parse_bytes(value.as_bytes(), 16).unwrap();
+    let low = (&value & BigUint::from(u128::MAX)).to_u128().unwrap();
+    let high = (value >> 128u32).to_u128().unwrap();
+
+    JitValue::Struct {
+        fields: vec![JitValue::Uint128(low), JitValue::Uint128(high)],
+        debug_name: None,
+    }
+}
+
+fn vm_args(args: &[JitValue]) -> Vec<Arg> {
+    args.iter()
+        .flat_map(|arg| match arg {
+            JitValue::Struct { fields, .. } => vm_args(fields),
+            JitValue::Uint128(value) => vec![Arg::Value(DeprecatedFelt::from(*value))],
+            JitValue::Felt252(value) => vec![Arg::Value(DeprecatedFelt::from_bytes_be(
+                &value.to_bytes_be(),
+            ))],
+            _ => unimplemented!(),
+        })
+        .collect()
+}
+
+#[track_caller]
+fn compare_secp256r1_ops(entry_point: &str, args: &[JitValue]) {
+    let program = &SECP256R1_OPS_PROGRAM;
+
+    let mut result_vm = run_vm_program(
+        program,
+        entry_point,
+        &vm_args(args),
+        Some(DEFAULT_GAS as usize),
+    )
+    .unwrap();
+    let result_native = run_native_program(
+        program,
+        entry_point,
+        args,
+        Some(DEFAULT_GAS as u128),
+        Some(&mut StubSyscallHandler::default()),
+    );
+
+    // The stub syscall handler doesn't charge the cost of the syscalls, so only the returned values
+    // are compared.
+    result_vm.gas_counter = result_native.remaining_gas.map(DeprecatedFelt::from);
+
+    compare_outputs(
+        &program.1,
+        &program.2.find_function(entry_point).unwrap().id,
+        &result_vm,
+        &result_native,
+    )
+    .unwrap();
+}
+
+#[test]
+fn secp256r1_new_vm() {
+    compare_secp256r1_ops("run_new", &[u256(P256_GX), u256(P256_GY)]);
+    compare_secp256r1_ops("run_new", &[u256("0"), u256("0")]);
+    compare_secp256r1_ops("run_new", &[u256("0"), u256("1")]);
+}
+
+#[test]
+fn secp256r1_add_vm() {
+    compare_secp256r1_ops(
+        "run_add",
+        &[u256(P256_GX), u256(P256_GY), u256(P256_GX), u256(P256_GY)],
+    );
+    compare_secp256r1_ops(
+        "run_add",
+        &[
+            u256(P256_GX),
+            u256(P256_GY),
+            u256(P256_GX),
+            u256(P256_NEG_GY),
+        ],
+    );
+    compare_secp256r1_ops(
+        "run_add",
+        &[u256("0"), u256("0"), u256(P256_GX), u256(P256_GY)],
+    );
+}
+
+#[test]
+fn secp256r1_mul_vm() {
+    for scalar in [
+        "0",
+        "3",
+        P256_N,
+        "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632552",
+        "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+    ] {
+        compare_secp256r1_ops("run_mul", &[u256(P256_GX), u256(P256_GY), u256(scalar)]);
+    }
+}
+
+#[test]
+fn secp256r1_get_point_from_x_vm() {
+    for y_parity in [0u8, 1] {
+        compare_secp256r1_ops(
+            "run_get_point_from_x",
+            &[u256(P256_GX), JitValue::Felt252(y_parity.into())],
+        );
+        compare_secp256r1_ops(
+            "run_get_point_from_x",
+            &[
+                u256("a00000000000000000000000000000000"),
+                JitValue::Felt252(y_parity.into()),
+            ],
+        );
+    }
+}
@@ -8,9 +8,11 @@ use cairo_lang_runner::SierraCasmRunner;
 use cairo_lang_sierra::program::Program;
 use cairo_native::{
     starknet::{
-        BlockInfo, ExecutionInfo, ExecutionInfoV2, Secp256k1Point, Secp256r1Point,
-        StarknetSyscallHandler, SyscallResult, TxInfo, TxV2Info, U256,
+        BlockInfo, ExecutionInfo, ExecutionInfoV2, PayloadLimitPolicy, PayloadLimits,
+        ResourceBounds, Secp256k1Point, Secp256r1Point, StarknetSyscallHandler, SyscallResult,
+        TxInfo, TxV2Info, U256,
     },
+    starknet_stub::StubSyscallHandler,
     values::JitValue,
 };
 use itertools::Itertools;
@@ -41,24 +43,31 @@ struct TestingState {
     block_number: u64,
     block_timestamp: u64,
     signature: Vec<Felt>,
+    resource_bounds: Vec<ResourceBounds>,
     logs: HashMap<Felt, ContractLogs>,
 }
 
 struct SyscallHandler {
     /// Arc<Mutex> Is needed to test that the valures are set correct after the execution
     testing_state: Arc<Mutex<TestingState>>,
+    payload_limits: PayloadLimits,
+    profile_syscalls: bool,
 }
 
 impl SyscallHandler {
     fn new() -> Self {
         Self {
             testing_state: Arc::new(Mutex::new(TestingState::default())),
+            payload_limits: PayloadLimits::default(),
+            profile_syscalls: false,
         }
     }
 
     fn with(state: Arc<Mutex<TestingState>>) -> Self {
         Self {
             testing_state: state,
+            payload_limits: PayloadLimits::default(),
+            profile_syscalls: false,
         }
     }
 }
@@ -128,6 +137,7 @@ impl StarknetSyscallHandler for SyscallHandler {
         &mut self,
         _remaining_gas: &mut u128,
     ) -> SyscallResult<ExecutionInfoV2> {
+        let state = self.testing_state.lock().unwrap();
         Ok(ExecutionInfoV2 {
             block_info: BlockInfo {
                 block_number: 10290342497028289173,
@@ -147,7 +157,7 @@ impl StarknetSyscallHandler for SyscallHandler {
                 )
                 .unwrap(),
                 max_fee: 268753657614351187400966367706860329387,
-                signature: Vec::new(),
+                signature: state.signature.clone(),
                 transaction_hash: Felt::from_dec_str(
                     "1123336726531770778820945049824733201592457249587063926479184903627272350002",
                 )
@@ -160,7 +170,7 @@ impl StarknetSyscallHandler for SyscallHandler {
                     "3012936192361023209451741736298028332652992971202997279327088951248532774884",
                 )
                 .unwrap(),
-                resource_bounds: Vec::new(),
+                resource_bounds: state.resource_bounds.clone(),
                 tip: 215444579144685671333997376989135077200,
                 paymaster_data: Vec::new(),
                 nonce_data_availability_mode: 140600095,
@@ -184,12 +194,17 @@ impl StarknetSyscallHandler for SyscallHandler {
 
     fn deploy(
         &mut self,
-        _class_hash: Felt,
+        class_hash: Felt,
         _contract_address_salt: Felt,
         _calldata: &[Felt],
         _deploy_from_zero: bool,
         _remaining_gas: &mut u128,
     ) -> SyscallResult<(Felt, Vec<Felt>)> {
+        // Only the class hash zero is declared.
+        if class_hash != Felt::ZERO {
+            return Err(vec![Felt::from_bytes_be_slice(b"CLASS_HASH_NOT_FOUND")]);
+        }
+
         Ok((
             Felt::from_dec_str(
                 "1833707083418045616336697070784512826809940908236872124572250196391719980392",
@@ -207,9 +222,14 @@ impl StarknetSyscallHandler for SyscallHandler {
         &mut self,
         _class_hash: Felt,
         _function_selector: Felt,
-        _calldata: &[Felt],
+        calldata: &[Felt],
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Vec<Felt>> {
+        // Echo the calldata (reversed) to check its marshalling.
+        if !calldata.is_empty() {
+            return Ok(calldata.iter().rev().copied().collect());
+        }
+
         Ok(vec![
             Felt::from_dec_str(
                 "3358892263739032253767642605669710712087178958719188919195252597609334880396",
@@ -230,9 +250,14 @@ impl StarknetSyscallHandler for SyscallHandler {
         &mut self,
         _address: Felt,
         _entry_point_selector: Felt,
-        _calldata: &[Felt],
+        calldata: &[Felt],
         _remaining_gas: &mut u128,
     ) -> SyscallResult<Vec<Felt>> {
+        // Echo the calldata (reversed) to check its marshalling.
+        if !calldata.is_empty() {
+            return Ok(calldata.iter().rev().copied().collect());
+        }
+
         Ok(vec![
             Felt::from_dec_str(
                 "3358892263739032253767642605669710712087178958719188919195252597609334880396",
@@ -400,6 +425,14 @@ impl StarknetSyscallHandler for SyscallHandler {
         unimplemented!()
     }
 
+    fn payload_limits(&self) -> PayloadLimits {
+        self.payload_limits
+    }
+
+    fn profile_syscalls(&self) -> bool {
+        self.profile_syscalls
+    }
+
     fn cheatcode(&mut self, selector: Felt, input: &[Felt]) -> Vec<Felt> {
         let selector_bytes = selector.to_bytes_be();
 
@@ -697,6 +730,55 @@ fn get_execution_info_v2() {
     );
 }
 
+#[test]
+fn get_execution_info_v2_tx_arrays() {
+    let state = Arc::new(Mutex::new(TestingState {
+        signature: vec![Felt::ONE, Felt::TWO],
+        resource_bounds: vec![
+            ResourceBounds {
+                resource: Felt::from_bytes_be_slice(b"L1_GAS"),
+                max_amount: 10,
+                max_price_per_unit: 20,
+            },
+            ResourceBounds {
+                resource: Felt::from_bytes_be_slice(b"L2_GAS"),
+                max_amount: 30,
+                max_price_per_unit: 40,
+            },
+        ],
+        ..Default::default()
+    }));
+
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "get_execution_info_v2_tx_arrays",
+        &[],
+        Some(u128::MAX),
+        Some(SyscallHandler::with(state)),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Struct {
+                fields: vec![JitValue::Struct {
+                    fields: vec![
+                        JitValue::Uint32(2),
+                        JitValue::Uint32(2),
+                        JitValue::Felt252(Felt::from_bytes_be_slice(b"L1_GAS")),
+                        JitValue::Uint64(10),
+                        JitValue::Uint128(20),
+                    ],
+                    debug_name: None,
+                }],
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
 #[test]
 fn deploy() {
     let result = run_native_program(
@@ -729,6 +811,28 @@ fn deploy() {
     )
 }
 
+#[test]
+fn deploy_undeclared() {
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "deploy_undeclared",
+        &[],
+        Some(u128::MAX),
+        Some(SyscallHandler::new()),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 1,
+            value: Box::new(JitValue::Array(vec![JitValue::Felt252(
+                Felt::from_bytes_be_slice(b"CLASS_HASH_NOT_FOUND")
+            )])),
+            debug_name: None,
+        },
+    );
+}
+
 #[test]
 fn replace_class() {
     let result = run_native_program(
@@ -824,6 +928,64 @@ fn call_contract() {
     );
 }
 
+#[test]
+fn library_call_echo() {
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "library_call_echo",
+        &[
+            JitValue::Felt252(Felt::from(1)),
+            JitValue::Felt252(Felt::from(2)),
+        ],
+        Some(u128::MAX),
+        Some(SyscallHandler::new()),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Struct {
+                fields: vec![JitValue::Array(vec![
+                    JitValue::Felt252(Felt::from(2)),
+                    JitValue::Felt252(Felt::from(1)),
+                ])],
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
+#[test]
+fn call_contract_echo() {
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "call_contract_echo",
+        &[
+            JitValue::Felt252(Felt::from(1)),
+            JitValue::Felt252(Felt::from(2)),
+        ],
+        Some(u128::MAX),
+        Some(SyscallHandler::new()),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Struct {
+                fields: vec![JitValue::Array(vec![
+                    JitValue::Felt252(Felt::from(2)),
+                    JitValue::Felt252(Felt::from(1)),
+                ])],
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
 #[test]
 fn storage_read() {
     let result = run_native_program(
@@ -852,6 +1014,38 @@ fn storage_read() {
     );
 }
 
+#[test]
+fn storage_read_profile() {
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "storage_read",
+        &[],
+        Some(u128::MAX),
+        Some(SyscallHandler::new()),
+    );
+    assert_eq!(result.syscall_profile, None);
+
+    let mut syscall_handler = SyscallHandler::new();
+    syscall_handler.profile_syscalls = true;
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "storage_read",
+        &[],
+        Some(u128::MAX),
+        Some(syscall_handler),
+    );
+
+    let profile = result.syscall_profile.unwrap();
+    assert_eq!(
+        profile.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        ["storage_read"]
+    );
+
+    let latencies = profile.get("storage_read").unwrap();
+    assert_eq!(latencies.handler.count(), 1);
+    assert_eq!(latencies.marshalling.count(), 1);
+}
+
 #[test]
 fn storage_write() {
     let result = run_native_program(
@@ -878,6 +1072,39 @@ fn storage_write() {
     );
 }
 
+#[test]
+fn storage_write_read() {
+    let result = run_native_program(
+        &SYSCALLS_PROGRAM,
+        "storage_write_read",
+        &[JitValue::Felt252(Felt::from(42))],
+        Some(u128::MAX),
+        Some(&mut StubSyscallHandler::default()),
+    );
+
+    assert_eq_sorted!(
+        result.return_value,
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Struct {
+                fields: vec![JitValue::Enum {
+                    tag: 0,
+                    value: Box::new(JitValue::Struct {
+                        fields: vec![
+                            JitValue::Felt252(Felt::from(42)),
+                            JitValue::Felt252(Felt::ZERO),
+                        ],
+                        debug_name: None,
+                    }),
+                    debug_name: None,
+                }],
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
 #[test]
 fn emit_event() {
     let result = run_native_program(
@@ -901,6 +1128,49 @@ fn emit_event() {
     );
 }
 
+#[test]
+fn emit_event_limits() {
+    let run = |policy| {
+        let mut syscall_handler = SyscallHandler::new();
+        syscall_handler.payload_limits = PayloadLimits {
+            max_event_len: Some(2),
+            policy,
+            ..Default::default()
+        };
+
+        run_native_program(
+            &SYSCALLS_PROGRAM,
+            "emit_big_event",
+            &[],
+            Some(u128::MAX),
+            Some(syscall_handler),
+        )
+        .return_value
+    };
+
+    assert_eq_sorted!(
+        run(PayloadLimitPolicy::Fail),
+        JitValue::Enum {
+            tag: 1,
+            value: Box::new(JitValue::Array(vec![JitValue::Felt252(
+                Felt::from_bytes_be_slice(b"Event too large")
+            )])),
+            debug_name: None,
+        },
+    );
+    assert_eq_sorted!(
+        run(PayloadLimitPolicy::Truncate),
+        JitValue::Enum {
+            tag: 0,
+            value: Box::new(JitValue::Struct {
+                fields: Vec::new(),
+                debug_name: None,
+            }),
+            debug_name: None,
+        },
+    );
+}
+
 #[test]
 fn send_message_to_l1() {
     let result = run_native_program(
@@ -0,0 +1,66 @@
+//! Tests for the VM comparison helpers in `cairo_native::testing`.
+
+use crate::common::{load_cairo, DEFAULT_GAS};
+use cairo_felt::Felt252 as DeprecatedFelt;
+use cairo_lang_runner::{Arg, SierraCasmRunner};
+use cairo_lang_sierra::program::Program;
+use cairo_native::{
+    context::{CompilerConfig, NativeContext},
+    executor::JitNativeExecutor,
+    testing::{run_vm, RunComparison},
+    values::JitValue,
+};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref BUILTINS: (String, Program, SierraCasmRunner) = load_cairo! {
+        use core::pedersen::pedersen;
+
+        fn run_test(a: u128, b: u128) -> (felt252, u128) {
+            (pedersen(a.into(), b.into()), (a & b) + 1)
+        }
+    };
+}
+
+#[test]
+fn compare_with_vm() {
+    let (name, program, runner) = &*BUILTINS;
+
+    let entry_point = format!("{name}::{name}::run_test");
+    let entry_point_id = &program
+        .funcs
+        .iter()
+        .find(|x| x.id.debug_name.as_deref() == Some(&entry_point))
+        .expect("Test program entry point not found.")
+        .id;
+
+    let context =
+        NativeContext::new().with_config(CompilerConfig::new().with_range_check_tracking(true));
+    let module = context
+        .compile(program, None)
+        .expect("Could not compile test program to MLIR.");
+    let native_result = JitNativeExecutor::from_native_module(module, Default::default())
+        .invoke_dynamic(
+            entry_point_id,
+            &[JitValue::Uint128(12), JitValue::Uint128(10)],
+            Some(DEFAULT_GAS as u128),
+        )
+        .unwrap();
+
+    let vm_result = run_vm(
+        runner,
+        runner.find_function("run_test").unwrap(),
+        &[
+            Arg::Value(DeprecatedFelt::from(12)),
+            Arg::Value(DeprecatedFelt::from(10)),
+        ],
+        Some(DEFAULT_GAS as usize),
+    )
+    .unwrap();
+    assert_eq!(vm_result.builtins.pedersen, 1);
+    assert_eq!(vm_result.builtins.bitwise, 1);
+
+    RunComparison::new(&vm_result, &native_result)
+        .unwrap()
+        .assert_match();
+}
@@ -36,6 +36,7 @@ fn invoke0() {
                 debug_name: None,
             },
             builtin_stats: BuiltinStats::default(),
+            syscall_profile: None,
         },
     );
 }
@@ -60,6 +61,7 @@ fn invoke1_felt252() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -90,6 +92,7 @@ fn invoke1_u8() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -120,6 +123,7 @@ fn invoke1_u16() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -150,6 +154,7 @@ fn invoke1_u32() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -180,6 +185,7 @@ fn invoke1_u64() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -210,6 +216,7 @@ fn invoke1_u128() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -243,6 +250,7 @@ fn invoke1_tuple1_felt252() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -276,6 +284,7 @@ fn invoke1_tuple1_u64() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -315,6 +324,7 @@ fn invoke1_tuple5_u8_u16_u32_u64_u128() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -345,6 +355,7 @@ fn invoke1_array_felt252() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -386,6 +397,7 @@ fn invoke1_enum1_unit() {
             remaining_gas: None,
             return_value: x,
             builtin_stats: BuiltinStats::default(),
+            syscall_profile: None,
         },
     );
 }
@@ -418,6 +430,7 @@ fn invoke1_enum1_u64() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -456,6 +469,7 @@ fn invoke1_enum1_felt252() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };
@@ -507,6 +521,7 @@ fn invoke1_enum2_u8_u16() {
                 remaining_gas: None,
                 return_value: x,
                 builtin_stats: BuiltinStats::default(),
+                syscall_profile: None,
             },
         );
     };